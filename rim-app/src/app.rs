@@ -1,15 +1,28 @@
-use std::{cell::RefCell, fs, ops::ControlFlow, path::{Path, PathBuf}, process::Command};
+use std::{cell::RefCell, fs, ops::ControlFlow, path::{Path, PathBuf}, process::Command, time::{Duration, Instant}};
 
 use anyhow::{Context, Result};
-use rim_application::{action::{AppAction, FileAction, PluginRuntimeAction, SystemAction}, config as application_config, state::RimState};
+use rim_application::{action::{AppAction, EditorAction, FileAction, KeyEvent, LayoutAction, PluginRuntimeAction, SystemAction}, config as application_config, state::RimState};
 use rim_infra_file_watcher::FileWatcherState;
 use rim_infra_input::InputPumpService;
+#[cfg(feature = "lsp")]
+use rim_infra_lsp::LanguageClientState;
 use rim_infra_storage::StorageIoState;
-use rim_infra_ui::{Renderer, TerminalSession};
+use rim_infra_ui::{Renderer, TerminalSession, render_to_string};
 use rim_plugin_host::PluginHostState;
 use rim_ports::{FilePicker, FilePickerError, StorageIo};
 use tracing::trace;
 
+// Builds without the `lsp` feature keep the slot so the ports stay the same
+// shape; the port then falls back to its no-op defaults.
+#[cfg(feature = "lsp")]
+pub(crate) type LanguageClientHost = LanguageClientState;
+#[cfg(not(feature = "lsp"))]
+pub(crate) type LanguageClientHost = ();
+
+// Queued actions are applied back to back until this budget is spent, then the
+// loop renders once before draining the rest.
+const FRAME_BUDGET: Duration = Duration::from_millis(16);
+
 #[derive(derive_more::AsRef, derive_more::AsMut)]
 pub struct App {
 	// Runtime state is mutable because action dispatch mutates application and domain state.
@@ -22,6 +35,8 @@ pub struct App {
 	file_watcher:       FileWatcherState,
 	#[as_ref]
 	plugin_host:        PluginHostState,
+	#[cfg_attr(feature = "lsp", as_ref)]
+	language_client:    LanguageClientHost,
 	terminal_session:   RefCell<Option<TerminalSession>>,
 	input_pump_service: RefCell<InputPumpService>,
 	event_tx:           flume::Sender<AppAction>,
@@ -33,6 +48,8 @@ pub(crate) struct AppPorts<'a> {
 	pub(crate) storage_io:       &'a StorageIoState,
 	pub(crate) file_watcher:     &'a FileWatcherState,
 	pub(crate) plugin_host:      &'a PluginHostState,
+	#[cfg_attr(not(feature = "lsp"), allow(dead_code))]
+	pub(crate) language_client:  &'a LanguageClientHost,
 	pub(crate) terminal_session: &'a RefCell<Option<TerminalSession>>,
 	pub(crate) input_pump:       &'a RefCell<InputPumpService>,
 }
@@ -42,10 +59,11 @@ impl<'a> AppPorts<'a> {
 		storage_io: &'a StorageIoState,
 		file_watcher: &'a FileWatcherState,
 		plugin_host: &'a PluginHostState,
+		language_client: &'a LanguageClientHost,
 		terminal_session: &'a RefCell<Option<TerminalSession>>,
 		input_pump: &'a RefCell<InputPumpService>,
 	) -> Self {
-		Self { storage_io, file_watcher, plugin_host, terminal_session, input_pump }
+		Self { storage_io, file_watcher, plugin_host, language_client, terminal_session, input_pump }
 	}
 }
 
@@ -62,6 +80,10 @@ impl App {
 			storage_io: StorageIoState::new(event_tx.clone()),
 			file_watcher: FileWatcherState::new(event_tx.clone()),
 			plugin_host: PluginHostState::new(event_tx.clone()),
+			#[cfg(feature = "lsp")]
+			language_client: LanguageClientState::new(event_tx.clone()),
+			#[cfg(not(feature = "lsp"))]
+			language_client: (),
 			terminal_session: RefCell::new(None),
 			input_pump_service: RefCell::new(InputPumpService::new(event_tx.clone())),
 			event_tx,
@@ -75,6 +97,8 @@ impl App {
 		self.storage_io.start();
 		self.file_watcher.start();
 		self.plugin_host.start();
+		#[cfg(feature = "lsp")]
+		self.language_client.start();
 		if let Err(err) = self.event_tx.send(AppAction::Plugin(PluginRuntimeAction::DiscoverRequested)) {
 			tracing::error!("plugin discovery start failed: {}", err);
 		}
@@ -110,14 +134,41 @@ impl App {
 		});
 	}
 
-	pub fn open_startup_files(&mut self, file_paths: Vec<PathBuf>) {
+	// Startup commands are requested after the files, so they can wait for the
+	// active one to load.
+	pub fn open_startup_files(
+		&mut self,
+		file_paths: Vec<PathBuf>,
+		session_path: Option<PathBuf>,
+		startup_commands: Vec<String>,
+	) {
+		self.open_startup_buffers(file_paths, session_path);
+		if !startup_commands.is_empty() {
+			let _ = self
+				.process_action(AppAction::File(FileAction::StartupCommandsRequested { commands: startup_commands }));
+		}
+	}
+
+	fn open_startup_buffers(&mut self, file_paths: Vec<PathBuf>, session_path: Option<PathBuf>) {
 		// Startup file opening is expressed as regular actions to reuse the same
 		// application flow.
+		let session_path = session_path.or_else(|| {
+			file_paths
+				.is_empty()
+				.then(|| self.state.persisted_session_path())
+				.flatten()
+				.filter(|path| path.exists())
+		});
+		if let Some(path) = session_path {
+			let _ = self.process_action(AppAction::File(FileAction::SessionSourceRequested { path }));
+			return;
+		}
 		if file_paths.is_empty() {
 			let ports = AppPorts::new(
 				&self.storage_io,
 				&self.file_watcher,
 				&self.plugin_host,
+				&self.language_client,
 				&self.terminal_session,
 				&self.input_pump_service,
 			);
@@ -132,10 +183,15 @@ impl App {
 		}
 	}
 
-	pub fn run(mut self, file_paths: Vec<PathBuf>) -> Result<()> {
+	pub fn run(
+		mut self,
+		file_paths: Vec<PathBuf>,
+		session_path: Option<PathBuf>,
+		startup_commands: Vec<String>,
+	) -> Result<()> {
 		// Start external workers first, then seed startup actions into the application.
 		self.start_services();
-		self.open_startup_files(file_paths);
+		self.open_startup_files(file_paths, session_path, startup_commands);
 
 		// Terminal session and input pump are pure runtime concerns.
 		let title = self.state.workbench.title.clone();
@@ -146,31 +202,34 @@ impl App {
 			terminal_session
 				.as_mut()
 				.expect("terminal session should exist while app is running")
-				.sync_cursor_style(self.state.mode)
+				.sync_cursor_style(self.state.mode, self.state.workbench.cursor_blink)
 				.context("sync cursor style failed")?;
 		}
+		self.sync_input_key_timeout();
 		self.input_pump_service.borrow_mut().start();
 		let mut renderer = Renderer::new();
 
 		loop {
-			// Render from the current state snapshot.
+			// Render from the current state snapshot, unless nothing visible changed.
 			{
 				let mut terminal_session = self.terminal_session.borrow_mut();
-				terminal_session
-					.as_mut()
-					.expect("terminal session should exist while app is running")
-					.draw(|frame| renderer.render(frame, &mut self.state))
-					.context("terminal draw failed")?;
+				let terminal_session =
+					terminal_session.as_mut().expect("terminal session should exist while app is running");
+				if terminal_session.take_screen_reset() {
+					renderer.invalidate();
+				}
+				if renderer.needs_redraw(&self.state) {
+					terminal_session
+						.draw(|frame| renderer.render(frame, &mut self.state))
+						.context("terminal draw failed")?;
+					trace!("redraw");
+				}
 			}
-			trace!("redraw");
 
-			// Pull one action from the event bus and dispatch it through the
-			// application handler.
+			// Block for one action, then apply everything already queued behind it so
+			// fast typing and pastes render once per burst.
 			let action = self.event_rx.recv().context("event bus disconnected while waiting for next action")?;
-			if Self::action_affects_layout(&action) {
-				renderer.mark_layout_dirty();
-			}
-			if self.process_action(action).is_break() {
+			if self.process_action_burst(action, &mut renderer, FRAME_BUDGET).is_break() {
 				break;
 			}
 			// Cursor shape is synchronized after each state transition.
@@ -178,9 +237,12 @@ impl App {
 			terminal_session
 				.as_mut()
 				.expect("terminal session should exist while app is running")
-				.sync_cursor_style(self.state.mode)
+				.sync_cursor_style(self.state.mode, self.state.workbench.cursor_blink)
 				.context("sync cursor style failed")?;
 		}
+		// Language servers are asked to exit before the editor does.
+		#[cfg(feature = "lsp")]
+		self.language_client.shutdown();
 		Ok(())
 	}
 
@@ -194,24 +256,84 @@ impl App {
 			&self.storage_io,
 			&self.file_watcher,
 			&self.plugin_host,
+			&self.language_client,
 			&self.terminal_session,
 			&self.input_pump_service,
 		);
 		state.apply_action(&ports, action)
 	}
 
+	pub fn process_action_burst(
+		&mut self,
+		first: AppAction,
+		renderer: &mut Renderer,
+		frame_budget: Duration,
+	) -> ControlFlow<()> {
+		let started_at = Instant::now();
+		let mut next = Some(first);
+		while let Some(action) = next.take() {
+			if Self::action_affects_layout(&action) {
+				renderer.mark_layout_dirty();
+			}
+			if matches!(action, AppAction::Layout(LayoutAction::ViewportResized { .. })) {
+				renderer.invalidate();
+			}
+			self.process_action(action)?;
+			if started_at.elapsed() >= frame_budget {
+				break;
+			}
+			next = self.event_rx.try_recv().ok();
+		}
+		ControlFlow::Continue(())
+	}
+
+	// Headless entrypoints drive the same dispatch path as the event loop, without
+	// a terminal session or background workers.
+	pub fn dispatch_key(&mut self, key: KeyEvent) -> ControlFlow<()> {
+		self.process_action(AppAction::Editor(EditorAction::KeyPressed(key)))
+	}
+
+	pub fn dispatch_keys(&mut self, keys: impl IntoIterator<Item = KeyEvent>) -> ControlFlow<()> {
+		for key in keys {
+			self.dispatch_key(key)?;
+		}
+		ControlFlow::Continue(())
+	}
+
+	pub fn process_pending_actions(&mut self) -> ControlFlow<()> {
+		while let Ok(action) = self.event_rx.try_recv() {
+			self.process_action(action)?;
+		}
+		ControlFlow::Continue(())
+	}
+
+	pub fn render_to_string(&mut self, width: u16, height: u16) -> String {
+		render_to_string(&mut self.state, width, height)
+	}
+
+	pub fn state(&self) -> &RimState { &self.state }
+
+	pub fn state_mut(&mut self) -> &mut RimState { &mut self.state }
+
 	pub fn action_affects_layout(action: &AppAction) -> bool {
 		matches!(
 			action,
 			AppAction::Editor(_)
 				| AppAction::Layout(_)
 				| AppAction::File(FileAction::WorkspaceSessionLoaded { .. })
+				| AppAction::File(FileAction::SessionFileLoaded { .. })
 				| AppAction::Plugin(_)
 		)
 	}
 
+	fn sync_input_key_timeout(&self) {
+		let timeout = Duration::from_millis(self.state.workbench.key_timeout_ms);
+		self.input_pump_service.borrow().set_key_timeout(timeout);
+	}
+
 	fn reload_all_configs(&mut self) -> ControlFlow<()> {
 		let config_errors = application_config::apply_all_configs(&mut self.state);
+		self.sync_input_key_timeout();
 		self.state.refresh_key_hints_overlay_after_config_reload();
 		self.state.refresh_command_palette();
 		if config_errors.is_empty() {
@@ -219,6 +341,7 @@ impl App {
 		} else {
 			application_config::apply_config_errors_to_status(&mut self.state, config_errors);
 		}
+		self.state.mark_render_dirty();
 		ControlFlow::Continue(())
 	}
 }
@@ -325,7 +448,66 @@ fn materialize_picker_command(
 
 #[cfg(test)]
 mod tests {
-	use rim_application::{command::{BindingMatch, BuiltinCommand, CommandConfigFile, CommandKeymapSection, CommandTarget, CursorCommand, KeyBindingOn, KeymapBindingConfig, ModeKeymapSections}, config as application_config, state::{KeymapScope, NormalSequenceKey, RimState}};
+	use std::{ops::ControlFlow, time::Duration};
+
+	use rim_application::{action::{AppAction, EditorAction, KeyCode, KeyEvent, KeyModifiers}, command::{BindingMatch, BuiltinCommand, CommandConfigFile, CommandKeymapSection, CommandTarget, CursorCommand, KeyBindingOn, KeymapBindingConfig, ModeKeymapSections}, config as application_config, state::{EditorMode, KeymapScope, NormalSequenceKey, RimState}};
+	use rim_infra_ui::Renderer;
+
+	use super::App;
+
+	fn typed(text: &str) -> impl Iterator<Item = KeyEvent> + '_ {
+		text.chars().map(|ch| KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE))
+	}
+
+	fn key(code: KeyCode) -> KeyEvent { KeyEvent::new(code, KeyModifiers::NONE) }
+
+	#[test]
+	fn dispatch_api_should_drive_editing_session_headlessly() {
+		let mut app = App::new(std::env::temp_dir()).expect("app should build");
+		app.state_mut().create_untitled_buffer();
+
+		assert!(app.dispatch_keys(typed("ihello")).is_continue());
+		assert_eq!(app.state().mode, EditorMode::Insert);
+		assert!(app.dispatch_key(key(KeyCode::Enter)).is_continue());
+		assert!(app.dispatch_keys(typed("world")).is_continue());
+		assert!(app.dispatch_key(key(KeyCode::Esc)).is_continue());
+		assert_eq!(app.state().mode, EditorMode::Normal);
+		assert_eq!(app.state().active_buffer_text_string().as_deref(), Some("hello\nworld"));
+
+		assert!(app.dispatch_keys(typed("ggdd")).is_continue());
+		assert_eq!(app.state().active_buffer_text_string().as_deref(), Some("world"));
+		assert!(app.dispatch_key(key(KeyCode::Char('u'))).is_continue());
+		assert_eq!(app.state().active_buffer_text_string().as_deref(), Some("hello\nworld"));
+		assert!(app.process_pending_actions().is_continue());
+
+		assert!(app.dispatch_keys(typed(":qa!")).is_continue());
+		assert_eq!(app.dispatch_key(key(KeyCode::Enter)), ControlFlow::Break(()));
+	}
+
+	#[test]
+	fn queued_key_burst_should_match_keys_applied_one_by_one() {
+		let keys =
+			typed("ihello world").chain([key(KeyCode::Esc)]).chain(typed("0dwgg\"ayyjd")).collect::<Vec<_>>();
+		let mut one_by_one = App::new(std::env::temp_dir()).expect("app should build");
+		one_by_one.state_mut().create_untitled_buffer();
+		assert!(one_by_one.dispatch_keys(keys.iter().copied()).is_continue());
+
+		let mut burst = App::new(std::env::temp_dir()).expect("app should build");
+		burst.state_mut().create_untitled_buffer();
+		for key in keys.iter().skip(1) {
+			burst.event_tx.send(AppAction::Editor(EditorAction::KeyPressed(*key))).expect("queue key");
+		}
+		let first = AppAction::Editor(EditorAction::KeyPressed(keys[0]));
+		let mut renderer = Renderer::new();
+		assert!(burst.process_action_burst(first, &mut renderer, Duration::from_secs(60)).is_continue());
+
+		assert!(burst.event_rx.is_empty());
+		assert_eq!(burst.state().active_buffer_text_string(), one_by_one.state().active_buffer_text_string());
+		assert_eq!(burst.state().mode, one_by_one.state().mode);
+		assert_eq!(burst.state().active_cursor(), one_by_one.state().active_cursor());
+		assert_eq!(burst.state().workbench.normal_sequence, one_by_one.state().workbench.normal_sequence);
+		assert!(!burst.state().workbench.normal_sequence.is_empty());
+	}
 
 	#[test]
 	fn reset_config_state_to_defaults_should_restore_removed_user_keymap_override() {
@@ -397,17 +579,27 @@ mod tests {
 		});
 		assert!(errors.is_empty());
 		assert_eq!(
-			state.workbench.command_registry.key_hints(KeymapScope::ModeNormal, &[NormalSequenceKey::Char('g')])[0]
-				.summary,
-			"Jump to beginning"
+			state
+				.workbench
+				.command_registry
+				.key_hints(KeymapScope::ModeNormal, &[NormalSequenceKey::Char('g')])
+				.into_iter()
+				.find(|hint| hint.key == "g")
+				.map(|hint| hint.summary),
+			Some("Jump to beginning".to_string())
 		);
 
 		application_config::reset_config_state_to_defaults(&mut state);
 
 		assert_eq!(
-			state.workbench.command_registry.key_hints(KeymapScope::ModeNormal, &[NormalSequenceKey::Char('g')])[0]
-				.summary,
-			"Move to file start"
+			state
+				.workbench
+				.command_registry
+				.key_hints(KeymapScope::ModeNormal, &[NormalSequenceKey::Char('g')])
+				.into_iter()
+				.find(|hint| hint.key == "g")
+				.map(|hint| hint.summary),
+			Some("Move to file start".to_string())
 		);
 	}
 }
@@ -3,9 +3,11 @@ use std::path::PathBuf;
 use rim_application::ports::SwapEditOp;
 use rim_domain::model::{BufferId, PersistedBufferHistory, WorkspaceSessionSnapshot};
 use rim_infra_file_watcher::FileWatcherImpl;
+#[cfg(feature = "lsp")]
+use rim_infra_lsp::LanguageClientImpl;
 use rim_infra_storage::StorageIoImpl;
 use rim_plugin_host::PluginRuntimeImpl;
-use rim_ports::{FileWatcher, FileWatcherError, PluginCommandRequest, PluginRuntime, PluginRuntimeError, StorageIo, StorageIoError};
+use rim_ports::{FileSaveOptions, FileWatcher, FileWatcherError, LanguageClient, PluginCommandRequest, PluginRuntime, PluginRuntimeError, StorageIo, StorageIoError};
 
 use crate::app::AppPorts;
 
@@ -23,6 +25,18 @@ impl StorageIo for AppPorts<'_> {
 		StorageIoImpl::inj_ref(self.storage_io).enqueue_save_workspace_session(snapshot)
 	}
 
+	fn enqueue_save_session_file(
+		&self,
+		path: PathBuf,
+		snapshot: WorkspaceSessionSnapshot,
+	) -> Result<(), StorageIoError> {
+		StorageIoImpl::inj_ref(self.storage_io).enqueue_save_session_file(path, snapshot)
+	}
+
+	fn enqueue_load_session_file(&self, path: PathBuf) -> Result<(), StorageIoError> {
+		StorageIoImpl::inj_ref(self.storage_io).enqueue_load_session_file(path)
+	}
+
 	fn enqueue_load(&self, buffer_id: BufferId, path: PathBuf) -> Result<(), StorageIoError> {
 		StorageIoImpl::inj_ref(self.storage_io).enqueue_load(buffer_id, path)
 	}
@@ -35,8 +49,26 @@ impl StorageIo for AppPorts<'_> {
 		StorageIoImpl::inj_ref(self.storage_io).enqueue_load_workspace_file_preview(path)
 	}
 
-	fn enqueue_save(&self, buffer_id: BufferId, path: PathBuf, text: String) -> Result<(), StorageIoError> {
-		StorageIoImpl::inj_ref(self.storage_io).enqueue_save(buffer_id, path, text)
+	fn enqueue_expand_glob(&self, workspace_root: PathBuf, pattern: String) -> Result<(), StorageIoError> {
+		StorageIoImpl::inj_ref(self.storage_io).enqueue_expand_glob(workspace_root, pattern)
+	}
+
+	fn enqueue_load_tags(&self, tags_path: PathBuf, tag: String) -> Result<(), StorageIoError> {
+		StorageIoImpl::inj_ref(self.storage_io).enqueue_load_tags(tags_path, tag)
+	}
+
+	fn enqueue_check_mtimes(&self, files: Vec<(BufferId, PathBuf)>) -> Result<(), StorageIoError> {
+		StorageIoImpl::inj_ref(self.storage_io).enqueue_check_mtimes(files)
+	}
+
+	fn enqueue_save(
+		&self,
+		buffer_id: BufferId,
+		path: PathBuf,
+		text: String,
+		options: FileSaveOptions,
+	) -> Result<(), StorageIoError> {
+		StorageIoImpl::inj_ref(self.storage_io).enqueue_save(buffer_id, path, text, options)
 	}
 
 	fn enqueue_external_load(&self, buffer_id: BufferId, path: PathBuf) -> Result<(), StorageIoError> {
@@ -133,6 +165,52 @@ impl FileWatcher for AppPorts<'_> {
 	}
 }
 
+#[cfg(feature = "lsp")]
+impl LanguageClient for AppPorts<'_> {
+	type BufferId = BufferId;
+
+	fn enqueue_lsp_open(
+		&self,
+		buffer_id: BufferId,
+		document: rim_ports::LanguageDocument,
+	) -> Result<(), rim_ports::LanguageClientError> {
+		LanguageClientImpl::inj_ref(self.language_client).enqueue_lsp_open(buffer_id, document)
+	}
+
+	fn enqueue_lsp_change(
+		&self,
+		buffer_id: BufferId,
+		text: String,
+	) -> Result<(), rim_ports::LanguageClientError> {
+		LanguageClientImpl::inj_ref(self.language_client).enqueue_lsp_change(buffer_id, text)
+	}
+
+	fn enqueue_lsp_close(&self, buffer_id: BufferId) -> Result<(), rim_ports::LanguageClientError> {
+		LanguageClientImpl::inj_ref(self.language_client).enqueue_lsp_close(buffer_id)
+	}
+
+	fn enqueue_lsp_definition(
+		&self,
+		buffer_id: BufferId,
+		position: rim_ports::LanguagePosition,
+	) -> Result<(), rim_ports::LanguageClientError> {
+		LanguageClientImpl::inj_ref(self.language_client).enqueue_lsp_definition(buffer_id, position)
+	}
+
+	fn enqueue_lsp_hover(
+		&self,
+		buffer_id: BufferId,
+		position: rim_ports::LanguagePosition,
+	) -> Result<(), rim_ports::LanguageClientError> {
+		LanguageClientImpl::inj_ref(self.language_client).enqueue_lsp_hover(buffer_id, position)
+	}
+}
+
+#[cfg(not(feature = "lsp"))]
+impl LanguageClient for AppPorts<'_> {
+	type BufferId = BufferId;
+}
+
 impl PluginRuntime for AppPorts<'_> {
 	fn enqueue_discover_plugins(&self, workspace_root: String) -> Result<(), PluginRuntimeError> {
 		PluginRuntimeImpl::inj_ref(self.plugin_host).enqueue_discover_plugins(workspace_root)
@@ -17,6 +17,31 @@ pub(crate) struct Cli {
 	/// Files to open on startup.
 	#[arg(value_name = "FILE")]
 	pub(crate) files: Vec<PathBuf>,
+
+	/// Restore tabs and windows from a session file written by :mksession.
+	#[arg(short = 'S', value_name = "SESSION", conflicts_with = "files")]
+	pub(crate) session: Option<PathBuf>,
+
+	/// Run an Ex command once the file has loaded; may be given several times.
+	#[arg(short = 'c', value_name = "COMMAND")]
+	pub(crate) commands: Vec<String>,
+}
+
+impl Cli {
+	// `+{command}` and `+/{pattern}` arrive as files. As in Vim they run before
+	// the `-c` commands, each as typed after `:` or `/`.
+	pub(crate) fn take_startup_commands(&mut self) -> Vec<String> {
+		let mut commands = Vec::new();
+		self.files.retain(|file| match file.to_str().and_then(|file| file.strip_prefix('+')) {
+			Some(command) => {
+				commands.push(command.to_string());
+				false
+			}
+			None => true,
+		});
+		commands.append(&mut self.commands);
+		commands
+	}
 }
 
 #[derive(Debug, Subcommand)]
@@ -291,6 +316,14 @@ mod tests {
 		assert_eq!(cli.files, vec![PathBuf::from("src/main.rs"), PathBuf::from("Cargo.toml")]);
 	}
 
+	#[test]
+	fn cli_should_collect_plus_and_dash_c_startup_commands_in_order() {
+		let mut cli = Cli::parse_from(["rim", "-c", "set list", "+/needle", "file.txt", "-c", "set nowrap"]);
+		assert_eq!(cli.take_startup_commands(), vec!["/needle", "set list", "set nowrap"]);
+		assert_eq!(cli.files, vec![PathBuf::from("file.txt")]);
+		assert!(cli.take_startup_commands().is_empty());
+	}
+
 	#[test]
 	fn cli_should_allow_path_named_plugin_when_disambiguated_by_position() {
 		let cli = Cli::parse_from(["rim", "plugin.txt"]);
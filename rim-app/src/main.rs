@@ -15,7 +15,7 @@ fn main() {
 fn run() -> Result<(), Box<dyn Error>> {
 	// Bootstrap cross-cutting infrastructure before constructing the app container.
 	logging::init_logging()?;
-	let cli = cli::parse();
+	let mut cli = cli::parse();
 	if let Some(command) = cli.command {
 		return Ok(cli::run(command)?);
 	}
@@ -23,9 +23,10 @@ fn run() -> Result<(), Box<dyn Error>> {
 	let workspace_root = detect_workspace_root(launch_dir.as_path());
 	std::env::set_current_dir(workspace_root.as_path())?;
 	let app = App::new(workspace_root)?;
+	let startup_commands = cli.take_startup_commands();
 	// CLI positional args are treated as startup files to be opened by the runtime.
 	let file_paths = cli.files;
 	// Hand over control to the app-owned runtime loop.
-	app.run(file_paths)?;
+	app.run(file_paths, cli.session, startup_commands)?;
 	Ok(())
 }
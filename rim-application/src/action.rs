@@ -1,8 +1,8 @@
-use std::{ops::{BitOr, BitOrAssign}, path::PathBuf};
+use std::{ops::{BitOr, BitOrAssign}, path::PathBuf, time::SystemTime};
 
 use rim_ports::{PluginCommandResponse, PluginDiscoveryResult, PluginInvocationError, PluginRuntimeFailure};
 
-use crate::state::{BufferId, PersistedBufferHistory, WorkspaceSessionSnapshot};
+use crate::state::{BufferDiagnostic, BufferId, DefinitionLocation, Motion, PersistedBufferHistory, WorkspaceSessionSnapshot};
 
 /// Facility-agnostic key code used by the application.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -57,12 +57,15 @@ impl KeyEvent {
 #[derive(Debug)]
 pub enum AppAction {
 	Editor(EditorAction),
+	// Text from a bracketed paste, delivered whole instead of as keys.
+	Paste(String),
 	Layout(LayoutAction),
 	Window(WindowAction),
 	Buffer(BufferAction),
 	Tab(TabAction),
 	File(FileAction),
 	Plugin(PluginRuntimeAction),
+	Lsp(LspAction),
 	System(SystemAction),
 }
 
@@ -74,7 +77,13 @@ pub enum EditorAction {
 	AppendInsert,
 	OpenLineBelowInsert,
 	OpenLineAboveInsert,
+	SubstituteCharInsert,
+	ChangeLineInsert,
+	RepeatLastInsert,
 	EnterCommandMode,
+	EnterCommandPalette,
+	EnterSearchMode,
+	BeginDeleteToSearch,
 	EnterVisualMode,
 	EnterVisualLineMode,
 	EnterVisualBlockMode,
@@ -83,12 +92,19 @@ pub enum EditorAction {
 	MoveLeftInVisual,
 	MoveLineStart,
 	MoveLineEnd,
+	MoveByMotion(Motion),
 	MoveDown,
 	MoveUp,
 	MoveRight,
 	MoveRightInVisual,
 	MoveFileStart,
 	MoveFileEnd,
+	MoveNextChange,
+	MovePrevChange,
+	MoveOlderChangePosition,
+	MoveNewerChangePosition,
+	MoveNextMisspelling,
+	MovePrevMisspelling,
 	ScrollViewDown,
 	ScrollViewUp,
 	ScrollViewHalfPageDown,
@@ -99,21 +115,74 @@ pub enum EditorAction {
 	ScrollKeyHintsHalfPageUp,
 	ScrollKeyHintsHalfPageDown,
 	Undo,
+	UndoAll,
 	Redo,
 	JoinLineBelow,
 	CutCharToSlot,
+	DeleteMotionToSlot(Motion),
 	PasteSlotAfterCursor,
+	PasteSlotWithIndentBelow,
+	PasteSlotWithIndentAbove,
 	DeleteCurrentLineToSlot,
+	MoveLinesDown,
+	MoveLinesUp,
+	DuplicateLines,
+	MarkSpellWordGood,
+	SuggestSpelling,
 	DeleteVisualSelectionToSlot,
 	YankVisualSelectionToSlot,
 	ReplaceVisualSelectionWithSlot,
 	ChangeVisualSelectionToInsertMode,
 	BeginVisualBlockInsertBefore,
 	BeginVisualBlockInsertAfter,
+	FoldVisualSelection,
+	SearchVisualSelection,
+	OpenFold,
+	CloseFold,
+	ToggleFold,
+	OpenAllFolds,
+	CloseAllFolds,
 	CloseActiveBuffer,
 	NewEmptyBuffer,
 }
 
+impl EditorAction {
+	/// Whether the action reads or changes the active buffer text.
+	pub const fn edits_buffer(self) -> bool {
+		matches!(
+			self,
+			Self::EnterInsert
+				| Self::AppendInsert
+				| Self::OpenLineBelowInsert
+				| Self::OpenLineAboveInsert
+				| Self::SubstituteCharInsert
+				| Self::ChangeLineInsert
+				| Self::RepeatLastInsert
+				| Self::SuggestSpelling
+				| Self::BeginDeleteToSearch
+				| Self::Undo
+				| Self::UndoAll
+				| Self::Redo
+				| Self::JoinLineBelow
+				| Self::CutCharToSlot
+				| Self::DeleteMotionToSlot(_)
+				| Self::PasteSlotAfterCursor
+				| Self::PasteSlotWithIndentBelow
+				| Self::PasteSlotWithIndentAbove
+				| Self::DeleteCurrentLineToSlot
+				| Self::MoveLinesDown
+				| Self::MoveLinesUp
+				| Self::DuplicateLines
+				| Self::DeleteVisualSelectionToSlot
+				| Self::YankVisualSelectionToSlot
+				| Self::ReplaceVisualSelectionWithSlot
+				| Self::ChangeVisualSelectionToInsertMode
+				| Self::BeginVisualBlockInsertBefore
+				| Self::BeginVisualBlockInsertAfter
+		)
+	}
+}
+
 /// Layout-affecting actions emitted by input/runtime.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LayoutAction {
@@ -130,6 +199,7 @@ pub enum WindowAction {
 	FocusUp,
 	FocusRight,
 	CloseActive,
+	ZoomToggle,
 }
 
 /// Buffer navigation actions.
@@ -137,6 +207,8 @@ pub enum WindowAction {
 pub enum BufferAction {
 	SwitchPrev,
 	SwitchNext,
+	SwitchAlternate,
+	ReopenClosed,
 }
 
 /// Tab management actions.
@@ -157,6 +229,7 @@ pub enum FileAction {
 	ExternalChangeDetected {
 		buffer_id: BufferId,
 		path:      PathBuf,
+		on_disk:   bool,
 	},
 	SwapConflictDetected {
 		buffer_id: BufferId,
@@ -176,6 +249,17 @@ pub enum FileAction {
 	WorkspaceSessionLoaded {
 		result: anyhow::Result<Option<WorkspaceSessionSnapshot>>,
 	},
+	SessionSourceRequested {
+		path: PathBuf,
+	},
+	SessionFileLoaded {
+		path:   PathBuf,
+		result: anyhow::Result<Option<WorkspaceSessionSnapshot>>,
+	},
+	SessionFileSaved {
+		path:   PathBuf,
+		result: anyhow::Result<()>,
+	},
 	WorkspaceFilesListed {
 		workspace_root: PathBuf,
 		result:         anyhow::Result<Vec<PathBuf>>,
@@ -187,14 +271,31 @@ pub enum FileAction {
 		path:   PathBuf,
 		result: anyhow::Result<String>,
 	},
+	GlobExpanded {
+		pattern: String,
+		result:  anyhow::Result<Vec<PathBuf>>,
+	},
+	TagsLoaded {
+		tags_path: PathBuf,
+		tag:       String,
+		result:    anyhow::Result<Option<String>>,
+	},
 	LoadCompleted {
 		buffer_id: BufferId,
 		source:    FileLoadSource,
 		result:    anyhow::Result<String>,
+		// Read before the text, so a write racing the load shows as a newer mtime.
+		mtime:     Option<SystemTime>,
+	},
+	MtimesChecked {
+		mtimes: Vec<(BufferId, PathBuf, Option<SystemTime>)>,
+	},
+	StartupCommandsRequested {
+		commands: Vec<String>,
 	},
 	SaveCompleted {
 		buffer_id: BufferId,
-		result:    anyhow::Result<()>,
+		result:    anyhow::Result<SaveVerification>,
 	},
 }
 
@@ -206,6 +307,16 @@ pub enum PluginRuntimeAction {
 	CommandCompleted { command_id: String, result: Result<PluginCommandResponse, PluginInvocationError> },
 }
 
+/// Language server callbacks, already mapped to buffer coordinates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LspAction {
+	DiagnosticsPublished { buffer_id: BufferId, diagnostics: Vec<BufferDiagnostic> },
+	DefinitionFound { locations: Vec<DefinitionLocation> },
+	HoverReceived { contents: String },
+	RequestFailed { request: &'static str, reason: String },
+	ServerFailed { command: String, reason: String },
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SwapConflictInfo {
 	pub pid:      u32,
@@ -218,6 +329,20 @@ pub enum SwapConflictCheckResult {
 	NoSwapActionNeeded,
 }
 
+/// What the file on disk looked like right after a save write finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveVerification {
+	Matched {
+		mtime: Option<SystemTime>,
+	},
+	SizeMismatch {
+		written: u64,
+		on_disk: u64,
+	},
+	/// The size matched but the file was modified after the write finished.
+	MtimeMismatch,
+}
+
 /// Marks whether a load comes from explicit open or external reload.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileLoadSource {
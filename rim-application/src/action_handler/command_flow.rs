@@ -2,11 +2,19 @@ use std::{ops::ControlFlow, path::PathBuf};
 
 use tracing::error;
 
-use super::{ActionHandlerError, ActionPorts, RuntimePorts, StoragePorts, plugin_flow};
-use crate::{action::{AppAction, FileAction, KeyCode, KeyEvent, WindowAction}, command::{BindingMatch, BuiltinCommand, CommandCommand, CommandPaletteCommand, CommandTarget, InsertCommand, ModeCommand, OverlayCommand, PickerCommand, ResolvedCommand}, state::{KeymapScope, NotificationLevel, RimState}};
+use super::{ActionHandlerError, ActionPorts, RuntimePorts, StoragePorts, file_flow, lsp_flow, plugin_flow};
+use crate::{action::{AppAction, FileAction, KeyCode, KeyEvent, WindowAction}, command::{BindingMatch, BuiltinCommand, CommandCommand, CommandPaletteCommand, CommandTarget, CursorCommand, InsertCommand, ModeCommand, OverlayCommand, PickerCommand, ResolvedCommand}, state::{DEFAULT_SESSION_FILE_NAME, KeymapScope, NotificationLevel, RimState, SearchOperator, parse_substitute_input}};
 
 pub(super) fn handle_command_mode_key<P>(ports: &P, state: &mut RimState, key: KeyEvent) -> ControlFlow<()>
 where P: ActionPorts {
+	if std::mem::take(&mut state.workbench.pending_register_insert) {
+		if let KeyCode::Char(name) = key.code {
+			state.insert_register_into_command_line(name);
+			ensure_command_palette_workspace_files(ports, state);
+			enqueue_command_palette_preview(ports, state, true);
+		}
+		return ControlFlow::Continue(());
+	}
 	if let Some(flow) = dispatch_scope_key(ports, state, key, KeymapScope::OverlayCommandPalette) {
 		return flow;
 	}
@@ -78,10 +86,56 @@ where
 	}
 }
 
+// Runs each line as if typed after `:`, or after `/` when it starts with one.
+pub(super) fn run_command_lines<P>(ports: &P, state: &mut RimState, lines: Vec<String>) -> ControlFlow<()>
+where P: ActionPorts {
+	for line in lines {
+		super::mode_flow::track_active_buffer_edit(ports, state, false, |state| {
+			state.enter_command_mode();
+			state.workbench.command_line = line;
+			execute_current_command_input(ports, state)?;
+			if state.is_command_mode() {
+				state.exit_command_mode();
+			}
+			ControlFlow::Continue(())
+		})?;
+	}
+	ControlFlow::Continue(())
+}
+
 fn execute_current_command_input<P>(ports: &P, state: &mut RimState) -> ControlFlow<()>
 where P: ActionPorts {
+	// A palette command that needs arguments is completed onto the line so they
+	// can be typed; Enter then runs the line as usual.
+	if std::mem::take(&mut state.workbench.command_palette_run_selection)
+		&& let Some(needs_arguments) = state.selected_command_palette_needs_arguments()
+	{
+		state.complete_command_palette_selection();
+		if needs_arguments {
+			state.push_command_char(' ');
+			return ControlFlow::Continue(());
+		}
+	}
 	let raw_command = state.workbench.command_line.clone();
-	let command = raw_command.trim().to_string();
+	if let Some(pattern) = raw_command.strip_prefix('/') {
+		let pattern = pattern.to_string();
+		let operator = state.workbench.search_operator.take();
+		state.exit_command_mode();
+		match operator {
+			Some(SearchOperator::Delete) => state.delete_to_search_match(pattern.as_str()),
+			None => state.search_forward(pattern.as_str()),
+		}
+		return ControlFlow::Continue(());
+	}
+	let (range, command) = state.split_command_range(raw_command.trim());
+	let command = command.to_string();
+	if let Some(input) = parse_substitute_input(command.as_str()) {
+		state.exit_command_mode();
+		state.substitute(range, &input);
+		state.workbench.command_selection = None;
+		state.workbench.command_range = None;
+		return ControlFlow::Continue(());
+	}
 	if command.is_empty() {
 		state.exit_command_mode();
 		return ControlFlow::Continue(());
@@ -93,7 +147,13 @@ where P: ActionPorts {
 		return ControlFlow::Continue(());
 	};
 	state.exit_command_mode();
-	execute_resolved_command(ports, state, resolved)
+	if range.is_some() {
+		state.workbench.command_range = range;
+	}
+	let flow = execute_resolved_command(ports, state, resolved);
+	state.workbench.command_selection = None;
+	state.workbench.command_range = None;
+	flow
 }
 
 pub(super) fn execute_command_target<P>(
@@ -111,6 +171,14 @@ where
 		CommandTarget::Plugin { plugin_id, command_id } => {
 			plugin_flow::enqueue_plugin_command(ports, state, plugin_id, command_id, &params)
 		}
+		CommandTarget::Host { command_id } => {
+			let Some(handler) = state.host_command_handler(command_id.as_str()) else {
+				state.push_notification(NotificationLevel::Error, format!("unknown command: {}", command_id));
+				return ControlFlow::Continue(());
+			};
+			handler(state, &params);
+			ControlFlow::Continue(())
+		}
 	}
 }
 
@@ -146,6 +214,7 @@ where
 		BuiltinCommand::Command(CommandCommand::QuitForce) => quit_current_scope(ports, state, true),
 		BuiltinCommand::Command(CommandCommand::QuitAll) => quit_application(ports, state, false),
 		BuiltinCommand::Command(CommandCommand::QuitAllForce) => quit_application(ports, state, true),
+		BuiltinCommand::Command(CommandCommand::Close) => close_current_window(ports, state),
 		BuiltinCommand::Command(CommandCommand::Save { .. }) => {
 			enqueue_save_active_buffer(ports, state, false, false, path_argument());
 			ControlFlow::Continue(())
@@ -158,6 +227,10 @@ where
 			enqueue_save_all_buffers(ports, state, false, false);
 			ControlFlow::Continue(())
 		}
+		BuiltinCommand::Command(CommandCommand::SaveAllForce) => {
+			enqueue_save_all_buffers(ports, state, false, true);
+			ControlFlow::Continue(())
+		}
 		BuiltinCommand::Command(CommandCommand::SaveAndQuit { .. }) => {
 			enqueue_save_active_buffer(ports, state, true, false, path_argument());
 			ControlFlow::Continue(())
@@ -176,7 +249,7 @@ where
 		}
 		BuiltinCommand::Command(CommandCommand::Reload { .. }) => {
 			if let Some(path) = path_argument() {
-				RimState::dispatch_internal(ports, state, AppAction::File(FileAction::OpenRequested { path }))
+				open_path_or_glob(ports, state, path)
 			} else {
 				enqueue_reload_active_buffer(ports, state, false);
 				ControlFlow::Continue(())
@@ -184,12 +257,67 @@ where
 		}
 		BuiltinCommand::Command(CommandCommand::ReloadForce { .. }) => {
 			if let Some(path) = path_argument() {
-				RimState::dispatch_internal(ports, state, AppAction::File(FileAction::OpenRequested { path }))
+				open_path_or_glob(ports, state, path)
 			} else {
 				enqueue_reload_active_buffer(ports, state, true);
 				ControlFlow::Continue(())
 			}
 		}
+		BuiltinCommand::Command(CommandCommand::Checktime) => {
+			file_flow::check_buffers_on_disk(ports, state);
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::DiffSplit { .. }) => {
+			let Some(path) = path_argument() else {
+				state.workbench.status_bar.message = "diffsplit failed: no file name".to_string();
+				return ControlFlow::Continue(());
+			};
+			state.diff_split_active_window();
+			RimState::dispatch_internal(ports, state, AppAction::File(FileAction::OpenRequested { path }))
+		}
+		BuiltinCommand::Command(CommandCommand::DiffThis) => {
+			state.diff_this();
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::DiffOff) => {
+			state.diff_off();
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::Drop { .. }) => {
+			let Some(path) = path_argument() else {
+				state.workbench.status_bar.message = "drop failed: no file name".to_string();
+				return ControlFlow::Continue(());
+			};
+			let normalized_path = file_flow::normalize_file_path(state.working_directory(), path.as_path());
+			if let Some(buffer_id) = state.find_buffer_by_path(normalized_path.as_path())
+				&& state.focus_window_showing_buffer(buffer_id)
+			{
+				state.workbench.status_bar.message = format!("focused {}", path.display());
+				return ControlFlow::Continue(());
+			}
+			open_path_or_glob(ports, state, path)
+		}
+		BuiltinCommand::Cursor(CursorCommand::TagJump) => {
+			let Some(tag) = state.tag_under_cursor() else {
+				state.workbench.status_bar.message = "no identifier under cursor".to_string();
+				return ControlFlow::Continue(());
+			};
+			if let Err(source) = ports.enqueue_load_tags(state.working_directory().join("tags"), tag) {
+				let err = ActionHandlerError::TagsLoad { source };
+				error!("io worker unavailable while enqueueing tags load: {}", err);
+				state.workbench.status_bar.message = "tag jump failed: io worker unavailable".to_string();
+			}
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Cursor(CursorCommand::Definition) => {
+			let count = state.workbench.pending_count.take();
+			lsp_flow::request_definition(ports, state, count);
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Cursor(CursorCommand::TagPop) => {
+			state.pop_tag_stack();
+			ControlFlow::Continue(())
+		}
 		BuiltinCommand::Picker(PickerCommand::Files) => {
 			open_workspace_file_picker(ports, state);
 			ControlFlow::Continue(())
@@ -217,10 +345,111 @@ where
 			enqueue_command_palette_preview(ports, state, true);
 			ControlFlow::Continue(())
 		}
+		BuiltinCommand::Command(CommandCommand::Registers) => {
+			state.show_registers();
+			ControlFlow::Continue(())
+		}
 		BuiltinCommand::Command(CommandCommand::Notifications) => {
 			state.open_notification_center();
 			ControlFlow::Continue(())
 		}
+		BuiltinCommand::Command(CommandCommand::Set { .. }) => {
+			let option = params.get_text("option").or_else(|| argv.first().map(String::as_str)).unwrap_or_default();
+			match state.apply_set_option(option) {
+				Ok(message) => state.workbench.status_bar.message = message,
+				Err(err) => state.push_notification(NotificationLevel::Error, err),
+			}
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::SetLocal { .. }) => {
+			let option = params.get_text("option").or_else(|| argv.first().map(String::as_str)).unwrap_or_default();
+			match state.apply_set_local_option(option) {
+				Ok(message) => state.workbench.status_bar.message = message,
+				Err(err) => state.push_notification(NotificationLevel::Error, err),
+			}
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::Date { .. }) => {
+			let format = params.get_text("format").or_else(|| argv.first().map(String::as_str)).map(str::to_string);
+			state.insert_date(format.as_deref());
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::Put { .. }) => {
+			let register =
+				params.get_text("register").or_else(|| argv.first().map(String::as_str)).map(str::to_string);
+			state.put_register(register.as_deref());
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::UndoList) => {
+			state.show_undo_list();
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::UndoTo { .. }) => {
+			match params.get_text("seq").or_else(|| argv.first().map(String::as_str)) {
+				None => state.undo_active_buffer_edit(),
+				Some(seq) => match seq.trim().parse::<usize>() {
+					Ok(seq) => state.undo_to_change(seq),
+					Err(_) => {
+						state.push_notification(NotificationLevel::Error, format!("invalid change number: {}", seq))
+					}
+				},
+			}
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::Retab) => {
+			state.retab(false);
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::RetabForce) => {
+			state.retab(true);
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::Sort { .. }) => {
+			let flags = params.get_text("flags").or_else(|| argv.first().map(String::as_str)).unwrap_or_default();
+			state.sort_lines(flags);
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::Uniq) => {
+			state.dedup_adjacent_lines();
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::WordCount) => {
+			state.report_word_count();
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::Pwd) => {
+			state.workbench.status_bar.message = state.working_directory().display().to_string();
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::Cd { .. }) => {
+			let path = path_argument();
+			match state.change_working_directory(path.as_deref()) {
+				Ok(directory) => state.workbench.status_bar.message = directory.display().to_string(),
+				Err(err) => state.push_notification(NotificationLevel::Error, err),
+			}
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(
+			command @ (CommandCommand::MakeSession { .. } | CommandCommand::MakeSessionForce { .. }),
+		) => {
+			let path = path_argument().unwrap_or_else(|| PathBuf::from(DEFAULT_SESSION_FILE_NAME));
+			let path = file_flow::normalize_file_path(state.working_directory(), path.as_path());
+			if path.exists() && !matches!(command, CommandCommand::MakeSessionForce { .. }) {
+				state.workbench.status_bar.message =
+					format!("mksession failed: {} exists (add ! to overwrite)", path.display());
+				return ControlFlow::Continue(());
+			}
+			if let Err(source) = ports.enqueue_save_session_file(path, state.layout_session_snapshot()) {
+				let err = ActionHandlerError::SessionFileSave { source };
+				error!("io worker unavailable while enqueueing session file save: {}", err);
+				state.workbench.status_bar.message = "mksession failed: io worker unavailable".to_string();
+			}
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Command(CommandCommand::Source { .. }) => {
+			let path = path_argument().unwrap_or_else(|| PathBuf::from(DEFAULT_SESSION_FILE_NAME));
+			RimState::dispatch_internal(ports, state, AppAction::File(FileAction::SessionSourceRequested { path }))
+		}
 		BuiltinCommand::CommandPalette(CommandPaletteCommand::PageUp) => {
 			let moved = state.page_command_palette_selection(-1);
 			enqueue_command_palette_preview(ports, state, moved);
@@ -249,6 +478,10 @@ where
 			enqueue_command_palette_preview(ports, state, moved);
 			ControlFlow::Continue(())
 		}
+		BuiltinCommand::View(crate::command::ViewCommand::Hover) => {
+			lsp_flow::request_hover(ports, state);
+			ControlFlow::Continue(())
+		}
 		BuiltinCommand::View(crate::command::ViewCommand::ToggleWordWrap) => {
 			state.toggle_word_wrap();
 			ControlFlow::Continue(())
@@ -300,7 +533,7 @@ where
 			ControlFlow::Continue(())
 		}
 		BuiltinCommand::Insert(InsertCommand::Newline) => {
-			state.insert_newline_at_cursor();
+			state.insert_newline_with_smart_indent();
 			ControlFlow::Continue(())
 		}
 		BuiltinCommand::Insert(InsertCommand::Backspace) => {
@@ -324,7 +557,27 @@ where
 			ControlFlow::Continue(())
 		}
 		BuiltinCommand::Insert(InsertCommand::Tab) => {
-			state.insert_char_at_cursor('\t');
+			state.insert_tab_at_cursor();
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Insert(InsertCommand::CompleteNext) => {
+			state.complete_word_at_cursor(true);
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Insert(InsertCommand::CompletePrev) => {
+			state.complete_word_at_cursor(false);
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Insert(InsertCommand::LineStart) => {
+			state.move_cursor_line_start();
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Insert(InsertCommand::LineEnd) => {
+			state.move_cursor_to_insert_line_end_slot();
+			ControlFlow::Continue(())
+		}
+		BuiltinCommand::Insert(InsertCommand::Register) => {
+			state.workbench.pending_register_insert = true;
 			ControlFlow::Continue(())
 		}
 		_ => {
@@ -335,6 +588,24 @@ where
 	}
 }
 
+// Wildcard paths are expanded on the storage worker; the matches come back as
+// `FileAction::GlobExpanded`.
+fn open_path_or_glob<P>(ports: &P, state: &mut RimState, path: PathBuf) -> ControlFlow<()>
+where P: ActionPorts {
+	let pattern = path.to_string_lossy().into_owned();
+	if !pattern.contains(['*', '?', '[']) {
+		return RimState::dispatch_internal(ports, state, AppAction::File(FileAction::OpenRequested { path }));
+	}
+	if let Err(source) = ports.enqueue_expand_glob(state.working_directory().to_path_buf(), pattern.clone()) {
+		let err = ActionHandlerError::ExpandGlob { source };
+		error!("io worker unavailable while enqueueing glob expansion: {}", err);
+		state.workbench.status_bar.message = "open failed: io worker unavailable".to_string();
+	} else {
+		state.workbench.status_bar.message = format!("expanding {}", pattern);
+	}
+	ControlFlow::Continue(())
+}
+
 fn ensure_command_palette_workspace_files<P>(ports: &P, state: &mut RimState)
 where P: StoragePorts {
 	if !state.command_palette_needs_workspace_files() {
@@ -430,6 +701,19 @@ where P: ActionPorts {
 	RimState::dispatch_internal(ports, state, AppAction::System(crate::action::SystemAction::Quit))
 }
 
+// Like `:q` minus the quit: the last window of the last tab stays open.
+fn close_current_window<P>(ports: &P, state: &mut RimState) -> ControlFlow<()>
+where P: ActionPorts {
+	if state.active_tab_window_ids().len() > 1 {
+		return RimState::dispatch_internal(ports, state, AppAction::Window(WindowAction::CloseActive));
+	}
+	if state.tabs.len() > 1 {
+		return RimState::dispatch_internal(ports, state, AppAction::Tab(crate::action::TabAction::CloseCurrent));
+	}
+	state.workbench.status_bar.message = "close blocked: cannot close last window".to_string();
+	ControlFlow::Continue(())
+}
+
 fn enqueue_save_active_buffer<P>(
 	ports: &P,
 	state: &mut RimState,
@@ -439,6 +723,8 @@ fn enqueue_save_active_buffer<P>(
 ) where
 	P: RuntimePorts,
 {
+	let path_override =
+		path_override.map(|path| file_flow::normalize_file_path(state.working_directory(), path.as_path()));
 	if !force_overwrite
 		&& path_override.is_none()
 		&& matches!(state.active_buffer_is_externally_modified(), Some(true))
@@ -448,6 +734,11 @@ fn enqueue_save_active_buffer<P>(
 		state.workbench.quit_after_save = false;
 		return;
 	}
+	if !force_overwrite && path_override.is_none() && matches!(state.active_buffer_is_read_only(), Some(true)) {
+		state.workbench.status_bar.message = "save blocked: buffer is read-only (use :w! to write)".to_string();
+		state.workbench.quit_after_save = false;
+		return;
+	}
 
 	let bind_override_path =
 		matches!((path_override.as_ref(), state.active_buffer_has_path()), (Some(_), Some(false)));
@@ -461,7 +752,7 @@ fn enqueue_save_active_buffer<P>(
 	};
 
 	state.workbench.in_flight_internal_saves.insert(buffer_id);
-	if let Err(source) = ports.enqueue_save(buffer_id, path, text) {
+	if let Err(source) = ports.enqueue_save(buffer_id, path, text, state.file_save_options()) {
 		let err = ActionHandlerError::Save { source };
 		error!("io worker unavailable while enqueueing file save: {}", err);
 		state.workbench.status_bar.message = "save failed: io worker unavailable".to_string();
@@ -522,12 +813,23 @@ fn enqueue_save_all_buffers<P>(
 		state.workbench.quit_after_save = false;
 		return;
 	}
+	let force_command = if quit_after_save { ":wqa!" } else { ":wa!" };
 	if !force_overwrite
 		&& snapshots.iter().any(|(buffer_id, ..)| {
 			state.buffers.get(*buffer_id).map(|buffer| buffer.externally_modified).unwrap_or(false)
 		}) {
 		state.workbench.status_bar.message =
-			"save all blocked: file changed externally (use :wqa! to overwrite)".to_string();
+			format!("save all blocked: file changed externally (use {} to overwrite)", force_command);
+		state.workbench.quit_after_save = false;
+		return;
+	}
+	if !force_overwrite
+		&& snapshots
+			.iter()
+			.any(|(buffer_id, ..)| state.buffers.get(*buffer_id).is_some_and(|buffer| buffer.read_only))
+	{
+		state.workbench.status_bar.message =
+			format!("save all blocked: a buffer is read-only (use {} to write)", force_command);
 		state.workbench.quit_after_save = false;
 		return;
 	}
@@ -544,7 +846,7 @@ fn enqueue_save_all_buffers<P>(
 	let mut enqueued = 0usize;
 	for (buffer_id, path, text) in snapshots {
 		state.workbench.in_flight_internal_saves.insert(buffer_id);
-		if let Err(source) = ports.enqueue_save(buffer_id, path, text) {
+		if let Err(source) = ports.enqueue_save(buffer_id, path, text, state.file_save_options()) {
 			let err = ActionHandlerError::SaveAll { source };
 			error!("io worker unavailable while enqueueing file save: {}", err);
 			state.workbench.status_bar.message = "save failed: io worker unavailable".to_string();
@@ -1,32 +1,55 @@
 use tracing::error;
 
 use super::{ActionHandlerError, RimState, RuntimePorts, enqueue_history_save_for_buffer};
-use crate::action::EditorAction;
+use crate::{action::EditorAction, state::SearchOperator};
 
 pub(super) fn apply_editor_action<P>(ports: &P, state: &mut RimState, action: EditorAction)
 where P: RuntimePorts {
+	if action.edits_buffer() && state.require_active_buffer().is_none() {
+		return;
+	}
+	// `.` only replays an insert while it is still the last change; undo and redo
+	// are not changes of their own.
+	if action.edits_buffer()
+		&& !matches!(
+			action,
+			EditorAction::RepeatLastInsert
+				| EditorAction::Undo
+				| EditorAction::UndoAll
+				| EditorAction::Redo
+				| EditorAction::YankVisualSelectionToSlot
+		) {
+		state.workbench.last_insert = None;
+	}
 	match action {
 		EditorAction::KeyPressed(_) => {}
-		EditorAction::EnterInsert => {
-			state.begin_insert_history_group();
-			state.enter_insert_mode();
-		}
-		EditorAction::AppendInsert => {
+		EditorAction::EnterInsert
+		| EditorAction::AppendInsert
+		| EditorAction::OpenLineBelowInsert
+		| EditorAction::OpenLineAboveInsert
+		| EditorAction::SubstituteCharInsert
+		| EditorAction::ChangeLineInsert => {
 			state.begin_insert_history_group();
-			state.move_cursor_right_for_insert();
+			prepare_insert_entry(state, action);
 			state.enter_insert_mode();
-		}
-		EditorAction::OpenLineBelowInsert => {
-			state.begin_insert_history_group();
-			state.open_line_below_at_cursor();
-			state.enter_insert_mode();
-		}
-		EditorAction::OpenLineAboveInsert => {
-			state.begin_insert_history_group();
-			state.open_line_above_at_cursor();
+			state.begin_insert_repeat(action);
+		}
+		EditorAction::RepeatLastInsert => {
+			let Some(last_insert) = state.workbench.last_insert.clone() else {
+				state.workbench.status_bar.message = "no previous insert to repeat".to_string();
+				return;
+			};
+			prepare_insert_entry(state, last_insert.entry);
 			state.enter_insert_mode();
+			for key in last_insert.keys {
+				let _ = super::mode_flow::handle_insert_mode_key(state, key);
+			}
+			state.exit_insert_mode();
 		}
 		EditorAction::EnterCommandMode => state.enter_command_mode(),
+		EditorAction::EnterCommandPalette => state.enter_command_palette(),
+		EditorAction::EnterSearchMode => state.begin_search(),
+		EditorAction::BeginDeleteToSearch => state.begin_search_operator(SearchOperator::Delete),
 		EditorAction::EnterVisualMode => state.enter_visual_mode(),
 		EditorAction::EnterVisualLineMode => state.enter_visual_line_mode(),
 		EditorAction::EnterVisualBlockMode => state.enter_visual_block_mode(),
@@ -41,6 +64,7 @@ where P: RuntimePorts {
 		}
 		EditorAction::MoveLineStart => state.move_cursor_line_start(),
 		EditorAction::MoveLineEnd => state.move_cursor_line_end(),
+		EditorAction::MoveByMotion(motion) => state.move_cursor_by_motion(motion),
 		EditorAction::MoveDown => state.move_cursor_down(),
 		EditorAction::MoveUp => state.move_cursor_up(),
 		EditorAction::MoveRight => state.move_cursor_right(),
@@ -53,6 +77,30 @@ where P: RuntimePorts {
 		}
 		EditorAction::MoveFileStart => state.move_cursor_file_start(),
 		EditorAction::MoveFileEnd => state.move_cursor_file_end(),
+		EditorAction::MoveNextChange => state.move_cursor_to_change_hunk(true),
+		EditorAction::MovePrevChange => state.move_cursor_to_change_hunk(false),
+		EditorAction::MoveOlderChangePosition => {
+			let count = state.workbench.pending_count.take().unwrap_or(1);
+			state.move_cursor_in_change_list(true, count);
+		}
+		EditorAction::MoveNewerChangePosition => {
+			let count = state.workbench.pending_count.take().unwrap_or(1);
+			state.move_cursor_in_change_list(false, count);
+		}
+		EditorAction::MoveNextMisspelling => state.move_cursor_to_misspelling(true),
+		EditorAction::MovePrevMisspelling => state.move_cursor_to_misspelling(false),
+		EditorAction::MarkSpellWordGood => state.mark_spell_word_good(),
+		EditorAction::FoldVisualSelection => state.fold_visual_selection(),
+		EditorAction::SearchVisualSelection => state.search_visual_selection(),
+		EditorAction::OpenFold => state.open_fold_under_cursor(),
+		EditorAction::CloseFold => state.close_fold_under_cursor(),
+		EditorAction::ToggleFold => state.toggle_fold_under_cursor(),
+		EditorAction::OpenAllFolds => state.set_all_folds_closed(false),
+		EditorAction::CloseAllFolds => state.set_all_folds_closed(true),
+		EditorAction::SuggestSpelling => {
+			let count = state.workbench.pending_count.take();
+			state.suggest_spelling(count);
+		}
 		EditorAction::ScrollViewDown => state.scroll_view_down_one_line(),
 		EditorAction::ScrollViewUp => state.scroll_view_up_one_line(),
 		EditorAction::ScrollViewHalfPageDown => state.scroll_view_down_half_page(),
@@ -77,13 +125,29 @@ where P: RuntimePorts {
 			let _ = state.scroll_key_hints_half_page_down();
 		}
 		EditorAction::Undo => state.undo_active_buffer_edit(),
+		EditorAction::UndoAll => state.undo_all_active_buffer_edits(),
 		EditorAction::Redo => state.redo_active_buffer_edit(),
 		EditorAction::JoinLineBelow => state.join_line_below_at_cursor(),
+		// As in Vim, there is nothing under a cursor in the virtual space to cut.
+		EditorAction::CutCharToSlot | EditorAction::DeleteMotionToSlot(_)
+			if state.active_virtual_display_col().is_some() => {}
 		EditorAction::CutCharToSlot => state.cut_current_char_to_slot(),
+		EditorAction::DeleteMotionToSlot(motion) => {
+			let count = state.workbench.pending_count.take().unwrap_or(1);
+			state.delete_motion_to_slot(motion, count);
+		}
 		EditorAction::PasteSlotAfterCursor => state.paste_slot_at_cursor(),
+		EditorAction::PasteSlotWithIndentBelow => state.paste_slot_with_indent(false),
+		EditorAction::PasteSlotWithIndentAbove => state.paste_slot_with_indent(true),
 		EditorAction::DeleteCurrentLineToSlot => state.delete_current_line_to_slot(),
+		EditorAction::MoveLinesDown => state.move_lines(true),
+		EditorAction::DuplicateLines => state.duplicate_lines(),
+		EditorAction::MoveLinesUp => state.move_lines(false),
 		EditorAction::DeleteVisualSelectionToSlot => {
-			let _ = state.delete_visual_selection_to_slot();
+			// The delete leaves room to append for `c`; plain `d` stays in normal mode.
+			if state.delete_visual_selection_to_slot() {
+				state.clamp_cursor_to_navigable_col();
+			}
 		}
 		EditorAction::YankVisualSelectionToSlot => state.yank_visual_selection_to_slot(),
 		EditorAction::ReplaceVisualSelectionWithSlot => state.replace_visual_selection_with_slot(),
@@ -120,3 +184,19 @@ where P: RuntimePorts {
 		}
 	}
 }
+
+fn prepare_insert_entry(state: &mut RimState, entry: EditorAction) {
+	match entry {
+		EditorAction::EnterInsert => {
+			state.fill_virtual_space_for_insert(false);
+		}
+		EditorAction::AppendInsert if !state.fill_virtual_space_for_insert(true) => {
+			state.move_cursor_right_for_insert()
+		}
+		EditorAction::OpenLineBelowInsert => state.open_line_below_at_cursor(),
+		EditorAction::OpenLineAboveInsert => state.open_line_above_at_cursor(),
+		EditorAction::SubstituteCharInsert => state.substitute_current_char_to_slot(),
+		EditorAction::ChangeLineInsert => state.clear_current_line_to_slot(),
+		_ => {}
+	}
+}
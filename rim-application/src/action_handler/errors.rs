@@ -1,4 +1,4 @@
-use rim_ports::{FileWatcherError, PluginRuntimeError, StorageIoError};
+use rim_ports::{FileWatcherError, LanguageClientError, PluginRuntimeError, StorageIoError};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -38,6 +38,32 @@ pub(super) enum ActionHandlerError {
 		#[source]
 		source: StorageIoError,
 	},
+	#[error("enqueue glob expansion failed")]
+	ExpandGlob {
+		#[source]
+		source: StorageIoError,
+	},
+	#[error("enqueue tags file load failed")]
+	TagsLoad {
+		#[source]
+		source: StorageIoError,
+	},
+	#[error("enqueue language server {operation} failed")]
+	Lsp {
+		operation: &'static str,
+		#[source]
+		source:    LanguageClientError,
+	},
+	#[error("enqueue session file save failed")]
+	SessionFileSave {
+		#[source]
+		source: StorageIoError,
+	},
+	#[error("enqueue session file load failed")]
+	SessionFileLoad {
+		#[source]
+		source: StorageIoError,
+	},
 	#[error("enqueue file save for :wa failed")]
 	SaveAll {
 		#[source]
@@ -1,9 +1,47 @@
 use std::{ops::ControlFlow, path::{Path, PathBuf}};
 
-use tracing::error;
+use rim_domain::tags::TagAddress;
+use tracing::{error, warn};
 
-use super::{ActionHandlerError, ActionPorts, RimState, RuntimePorts, StoragePorts};
-use crate::{action::{FileAction, KeyCode, KeyEvent, KeyModifiers, SwapConflictCheckResult, SwapConflictInfo}, state::{BufferId, PendingSwapDecision, PersistedBufferHistory}};
+use super::{ActionHandlerError, ActionPorts, RimState, RuntimePorts, StoragePorts, command_flow, lsp_flow::open_language_document};
+use crate::{action::{AppAction, FileAction, KeyCode, KeyEvent, KeyModifiers, SaveVerification, SwapConflictCheckResult, SwapConflictInfo}, hook::HookEvent, state::{BufferId, NotificationLevel, PendingSwapDecision, PersistedBufferHistory}};
+
+// A file that still has to load takes the jump once its text arrives.
+pub(super) fn open_path_at_tag_address<P>(
+	ports: &P,
+	state: &mut RimState,
+	path: PathBuf,
+	address: TagAddress,
+) -> ControlFlow<()>
+where
+	P: ActionPorts,
+{
+	let normalized_path = normalize_file_path(state.working_directory(), path.as_path());
+	let needs_load = state.find_buffer_by_path(normalized_path.as_path()).is_none();
+	if needs_load && !normalized_path.exists() {
+		state.workbench.status_bar.message = format!("tag target not found: {}", path.display());
+		return ControlFlow::Continue(());
+	}
+	let origin = state.tag_stack_origin();
+	RimState::dispatch_internal(ports, state, AppAction::File(FileAction::OpenRequested { path }))?;
+	let Some(buffer_id) = state
+		.find_buffer_by_path(normalized_path.as_path())
+		.filter(|buffer_id| state.active_buffer_id() == Some(*buffer_id))
+	else {
+		return ControlFlow::Continue(());
+	};
+	if needs_load {
+		// The origin is pushed when the load lands, so a failed load leaves the tag
+		// stack alone.
+		if !state.workbench.loading_buffers.contains(&buffer_id) {
+			return ControlFlow::Continue(());
+		}
+		state.defer_tag_jump(buffer_id, address, origin);
+	} else {
+		state.finish_tag_jump(&address, origin);
+	}
+	ControlFlow::Continue(())
+}
 
 pub(super) fn enqueue_swap_recover<P>(
 	ports: &P,
@@ -101,6 +139,46 @@ where P: RuntimePorts {
 	}
 }
 
+// Drops watches and swap sessions of every file buffer before a session file
+// replaces them.
+fn release_file_buffers<P>(ports: &P, state: &RimState)
+where P: RuntimePorts {
+	for (buffer_id, buffer) in &state.buffers {
+		if buffer.path.is_none() {
+			continue;
+		}
+		if let Err(source) = ports.enqueue_unwatch(buffer_id) {
+			let err = ActionHandlerError::CloseBufferUnwatch { source };
+			error!("watch worker unavailable while enqueueing file unwatch: {}", err);
+		}
+		if let Err(source) = ports.enqueue_close(buffer_id) {
+			let err = ActionHandlerError::PersistenceSwapClose { source };
+			error!("persistence worker unavailable while enqueueing swap close: {}", err);
+		}
+	}
+}
+
+fn enqueue_session_buffer_loads<P>(ports: &P, state: &RimState, buffer_ids: &[BufferId])
+where P: RuntimePorts {
+	for buffer_id in buffer_ids {
+		let Some(source_path) = state.buffers.get(*buffer_id).and_then(|buffer| buffer.path.clone()) else {
+			continue;
+		};
+		if let Err(source) = ports.enqueue_open(*buffer_id, source_path.clone()) {
+			let err = ActionHandlerError::PersistenceOpen { source };
+			error!("session source enqueue_open failed: {}", err);
+		}
+		if let Err(source) = ports.enqueue_watch(*buffer_id, source_path.clone()) {
+			let err = ActionHandlerError::OpenFileWatch { source };
+			error!("session source enqueue_watch failed: {}", err);
+		}
+		if let Err(source) = ports.enqueue_load(*buffer_id, source_path) {
+			let err = ActionHandlerError::OpenFileLoad { source };
+			error!("session source enqueue_load failed: {}", err);
+		}
+	}
+}
+
 pub(super) fn swap_conflict_prompt_message(conflict: &SwapConflictInfo) -> String {
 	format!(
 		"swap exists (pid {}, user {}): [r]ecover [d]elete [e]dit anyway [a]bort",
@@ -299,6 +377,57 @@ where P: ActionPorts {
 				state.workbench.status_bar.message = format!("session load failed: {}", err);
 			}
 		},
+		FileAction::SessionSourceRequested { path } => {
+			if state.has_dirty_buffers() {
+				state.workbench.status_bar.message = "source blocked: unsaved changes".to_string();
+				return ControlFlow::Continue(());
+			}
+			let path = normalize_file_path(state.working_directory(), path.as_path());
+			if let Err(source) = ports.enqueue_load_session_file(path.clone()) {
+				let err = ActionHandlerError::SessionFileLoad { source };
+				error!("io worker unavailable while enqueueing session file load: {}", err);
+				state.workbench.status_bar.message = "source failed: io worker unavailable".to_string();
+			} else {
+				state.workbench.status_bar.message = format!("sourcing {}", path.display());
+			}
+		}
+		FileAction::SessionFileLoaded { path, result } => {
+			match result {
+				Ok(Some(snapshot)) => {
+					if state.has_dirty_buffers() {
+						state.workbench.status_bar.message = "source blocked: unsaved changes".to_string();
+						return ControlFlow::Continue(());
+					}
+					release_file_buffers(ports, state);
+					match state.restore_layout_session(snapshot) {
+						Some(pending) => {
+							enqueue_session_buffer_loads(ports, state, pending.as_slice());
+							state.workbench.status_bar.message = format!("session sourced: {}", path.display());
+						}
+						None => {
+							state.workbench.status_bar.message = format!("session restore failed: {}", path.display());
+						}
+					}
+				}
+				Ok(None) => {
+					state.workbench.status_bar.message = format!("session file not found: {}", path.display());
+				}
+				Err(err) => {
+					error!("session file load failed: path={} error={}", path.display(), err);
+					state.workbench.status_bar.message = format!("session load failed: {}", err);
+				}
+			}
+			if state.buffers.is_empty() {
+				state.create_untitled_buffer();
+			}
+		}
+		FileAction::SessionFileSaved { path, result } => match result {
+			Ok(()) => state.workbench.status_bar.message = format!("session written: {}", path.display()),
+			Err(err) => {
+				error!("session file save failed: path={} error={}", path.display(), err);
+				state.workbench.status_bar.message = format!("mksession failed: {}", err);
+			}
+		},
 		FileAction::WorkspaceFilesListed { workspace_root, result } => match result {
 			Ok(paths) => {
 				let entries = paths
@@ -380,28 +509,113 @@ where P: ActionPorts {
 				state.set_command_palette_preview(path.as_path(), error_message);
 			}
 		},
-		FileAction::LoadCompleted { buffer_id, source, result } => match (source, result) {
+		FileAction::GlobExpanded { pattern, result } => match result {
+			Ok(paths) => {
+				let Some(first_path) = paths.first().cloned() else {
+					state.workbench.status_bar.message = format!("no files match {}", pattern);
+					return ControlFlow::Continue(());
+				};
+				let opened = paths.len();
+				for path in paths {
+					RimState::dispatch_internal(ports, state, AppAction::File(FileAction::OpenRequested { path }))?;
+				}
+				let first_path = normalize_file_path(state.working_directory(), first_path.as_path());
+				if let Some(buffer_id) = state.find_buffer_by_path(first_path.as_path()) {
+					state.bind_buffer_to_active_window(buffer_id);
+				}
+				state.workbench.status_bar.message =
+					format!("opened {} file{}", opened, if opened == 1 { "" } else { "s" });
+			}
+			Err(err) => {
+				error!("glob expansion failed: pattern={} error={}", pattern, err);
+				state.workbench.status_bar.message = format!("glob expansion failed: {}", err);
+			}
+		},
+		FileAction::TagsLoaded { tags_path, tag, result } => match result {
+			Ok(Some(text)) => {
+				let Some((path, address)) = state.begin_tag_jump(tags_path.as_path(), tag.as_str(), text.as_str())
+				else {
+					return ControlFlow::Continue(());
+				};
+				return open_path_at_tag_address(ports, state, path, address);
+			}
+			Ok(None) => {
+				state.workbench.status_bar.message = format!("tags file not found: {}", tags_path.display());
+			}
+			Err(err) => {
+				error!("tags load failed: path={} error={}", tags_path.display(), err);
+				state.workbench.status_bar.message = format!("tags load failed: {}", err);
+			}
+		},
+		FileAction::MtimesChecked { mtimes } => reload_buffers_changed_on_disk(ports, state, mtimes),
+		FileAction::StartupCommandsRequested { commands } => {
+			let commands = state.defer_startup_commands(commands);
+			return command_flow::run_command_lines(ports, state, commands);
+		}
+		FileAction::LoadCompleted { buffer_id, source, result, mtime } => match (source, result) {
 			(crate::action::FileLoadSource::Open, Ok(text)) => {
+				state.workbench.loading_buffers.remove(&buffer_id);
 				if let Some(buffer) = state.buffers.get_mut(buffer_id) {
 					buffer.text = text.into();
+					buffer.bump_changedtick();
 				} else {
 					error!("load completed for unknown buffer: buffer_id={:?}", buffer_id);
 				}
 				state.clear_buffer_history(buffer_id);
 				state.mark_buffer_clean(buffer_id);
 				state.set_buffer_externally_modified(buffer_id, false);
-				enqueue_history_load_for_buffer(ports, state, buffer_id, true);
+				state.set_buffer_read_only(buffer_id, false);
+				state.set_buffer_deleted_on_disk(buffer_id, false);
+				state.record_buffer_disk_mtime(buffer_id, mtime);
+				state.detect_buffer_indent(buffer_id);
+				// A pending tag jump, a sourced session or startup commands own the cursor,
+				// so skip restoring the persisted view.
+				let session_load = state.finish_session_buffer_load(buffer_id);
+				let restore_view = !session_load
+					&& !state.has_pending_tag_jump(buffer_id)
+					&& !state.has_pending_startup_commands(buffer_id);
+				enqueue_history_load_for_buffer(ports, state, buffer_id, restore_view);
 				state.workbench.status_bar.message = "file loaded".to_string();
+				state.apply_pending_tag_jump(buffer_id);
+				state.emit_hook(HookEvent::BufferOpened { buffer_id });
+				open_language_document(ports, state, buffer_id);
 				if let Some(source_path) = state.buffers.get(buffer_id).and_then(|buffer| buffer.path.clone())
 					&& let Err(source) = ports.enqueue_detect_conflict(buffer_id, source_path)
 				{
 					let err = ActionHandlerError::PersistenceSwapDetectConflict { source };
 					error!("persistence worker unavailable while enqueueing swap conflict check: {}", err);
 				}
+				let commands = state.take_startup_commands(buffer_id);
+				return command_flow::run_command_lines(ports, state, commands);
 			}
 			(crate::action::FileLoadSource::Open, Err(err)) => {
-				error!("file load failed: buffer_id={:?}, error={}", buffer_id, err);
-				state.workbench.status_bar.message = format!("load failed: {}", err);
+				state.workbench.loading_buffers.remove(&buffer_id);
+				state.finish_session_buffer_load(buffer_id);
+				state.cancel_pending_tag_jump(buffer_id);
+				let kind =
+					err.chain().find_map(|cause| cause.downcast_ref::<std::io::Error>()).map(std::io::Error::kind);
+				match kind {
+					// Gone since the open was requested: edit it as a new file.
+					Some(std::io::ErrorKind::NotFound) => {
+						state.set_buffer_read_only(buffer_id, false);
+						state.set_buffer_new_file(buffer_id, true);
+						state.mark_buffer_clean(buffer_id);
+						let name = state.buffers.get(buffer_id).map(|buffer| buffer.name.clone()).unwrap_or_default();
+						state.workbench.status_bar.message = format!("new {}", name);
+					}
+					Some(std::io::ErrorKind::PermissionDenied) => {
+						error!("file load failed: buffer_id={:?}, error={}", buffer_id, err);
+						state.set_buffer_read_only(buffer_id, true);
+						state.workbench.status_bar.message =
+							"load failed: permission denied; buffer is read-only".to_string();
+					}
+					_ => {
+						error!("file load failed: buffer_id={:?}, error={}", buffer_id, err);
+						state.workbench.status_bar.message = format!("load failed: {}", err);
+					}
+				}
+				let commands = state.take_startup_commands(buffer_id);
+				return command_flow::run_command_lines(ports, state, commands);
 			}
 			(crate::action::FileLoadSource::External, Ok(text)) => {
 				let is_active = state.active_buffer_id() == Some(buffer_id);
@@ -423,6 +637,8 @@ where P: ActionPorts {
 				state.clear_buffer_history(buffer_id);
 				state.mark_buffer_clean(buffer_id);
 				state.set_buffer_externally_modified(buffer_id, false);
+				state.set_buffer_deleted_on_disk(buffer_id, false);
+				state.record_buffer_disk_mtime(buffer_id, mtime);
 				enqueue_history_load_for_buffer(ports, state, buffer_id, false);
 				if is_active {
 					state.workbench.status_bar.message = format!("reloaded {}", name);
@@ -434,7 +650,7 @@ where P: ActionPorts {
 		},
 		FileAction::OpenRequested { path } => {
 			tracing::info!("open_file: {}", path.display());
-			let normalized_path = normalize_file_path(state.workspace_root(), path.as_path());
+			let normalized_path = normalize_file_path(state.working_directory(), path.as_path());
 			let replaceable_untitled = state.replaceable_active_untitled_buffer_id();
 			if let Some(buffer_id) = state.find_buffer_by_path(normalized_path.as_path()) {
 				if let Some(untitled_buffer_id) = replaceable_untitled
@@ -458,6 +674,7 @@ where P: ActionPorts {
 					buffer_id
 				};
 				state.bind_buffer_to_active_window(buffer_id);
+				state.set_buffer_new_file(buffer_id, true);
 				state.workbench.status_bar.message = format!("new {}", path.display());
 				return ControlFlow::Continue(());
 			}
@@ -482,13 +699,19 @@ where P: ActionPorts {
 				error!("io worker unavailable while enqueueing file load: {}", io_err);
 				state.workbench.status_bar.message = "load failed: io worker unavailable".to_string();
 			} else {
+				state.workbench.loading_buffers.insert(buffer_id);
 				state.workbench.status_bar.message = format!("loading {}", path.display());
 			}
 		}
-		FileAction::ExternalChangeDetected { buffer_id, path } => {
+		FileAction::ExternalChangeDetected { buffer_id, path, on_disk } => {
 			if state.workbench.in_flight_internal_saves.contains(&buffer_id) {
 				return ControlFlow::Continue(());
 			}
+			state.set_buffer_deleted_on_disk(buffer_id, !on_disk);
+			if !on_disk {
+				// Nothing to reload; keep the text until the file comes back or is saved.
+				return ControlFlow::Continue(());
+			}
 			if state.should_ignore_recent_external_change(buffer_id) {
 				state.set_buffer_externally_modified(buffer_id, false);
 				return ControlFlow::Continue(());
@@ -515,7 +738,32 @@ where P: ActionPorts {
 			}
 		}
 		FileAction::SaveCompleted { buffer_id, result } => match result {
-			Ok(()) => {
+			Ok(verification @ (SaveVerification::SizeMismatch { .. } | SaveVerification::MtimeMismatch)) => {
+				// Something else wrote the file during the save, so the disk no longer
+				// holds the buffer text: keep it dirty and let the next watch event through.
+				state.workbench.in_flight_internal_saves.remove(&buffer_id);
+				state.clear_recent_internal_save(buffer_id);
+				state.workbench.quit_after_save = false;
+				state.apply_pending_save_path_if_matches(buffer_id);
+				if let Some(path) = state.buffers.get(buffer_id).and_then(|buffer| buffer.path.clone())
+					&& let Err(source) = ports.enqueue_watch(buffer_id, path)
+				{
+					let err = ActionHandlerError::SaveWatch { source };
+					error!("watch worker unavailable while enqueueing file watch: {}", err);
+				}
+				state.set_buffer_externally_modified(buffer_id, true);
+				warn!("file changed during save: buffer_id={:?} verification={:?}", buffer_id, verification);
+				let message = match verification {
+					SaveVerification::SizeMismatch { written, on_disk } => format!(
+						"file changed during save: wrote {} bytes, found {}; buffer kept modified",
+						written, on_disk
+					),
+					_ => "file changed during save: modified after the write; buffer kept modified".to_string(),
+				};
+				state.push_notification(NotificationLevel::Warn, message.clone());
+				state.workbench.status_bar.message = message;
+			}
+			Ok(SaveVerification::Matched { mtime }) => {
 				state.workbench.in_flight_internal_saves.remove(&buffer_id);
 				state.mark_recent_internal_save(buffer_id);
 				state.apply_pending_save_path_if_matches(buffer_id);
@@ -531,8 +779,13 @@ where P: ActionPorts {
 				}
 				state.mark_buffer_clean(buffer_id);
 				state.set_buffer_externally_modified(buffer_id, false);
+				state.set_buffer_new_file(buffer_id, false);
+				state.set_buffer_deleted_on_disk(buffer_id, false);
+				state.set_buffer_read_only(buffer_id, false);
+				state.record_buffer_disk_mtime(buffer_id, mtime);
 				enqueue_history_save_for_buffer(ports, state, buffer_id);
 				state.workbench.status_bar.message = "file saved".to_string();
+				state.emit_hook(HookEvent::BufferSaved { buffer_id });
 				if state.workbench.quit_after_save && state.workbench.in_flight_internal_saves.is_empty() {
 					state.workbench.quit_after_save = false;
 					return RimState::dispatch_internal(
@@ -556,7 +809,65 @@ where P: ActionPorts {
 	ControlFlow::Continue(())
 }
 
-fn normalize_file_path(workspace_root: &Path, path: &Path) -> PathBuf {
-	let absolute = if path.is_absolute() { path.to_path_buf() } else { workspace_root.join(path) };
+// Catches changes the watcher missed, e.g. after a checkout. The io worker
+// reads the mtimes and answers with `MtimesChecked`.
+pub(super) fn check_buffers_on_disk<P>(ports: &P, state: &mut RimState)
+where P: RuntimePorts {
+	let files = state.buffers_with_disk_mtime();
+	if files.is_empty() {
+		state.workbench.status_bar.message = "checktime: no files changed".to_string();
+		return;
+	}
+	if let Err(source) = ports.enqueue_check_mtimes(files) {
+		let err = ActionHandlerError::ExternalReload { source };
+		error!("io worker unavailable while enqueueing checktime: {}", err);
+		state.workbench.status_bar.message = "checktime failed: io worker unavailable".to_string();
+	}
+}
+
+// Clean buffers whose file moved reload; modified ones are only flagged like a
+// watcher event would.
+fn reload_buffers_changed_on_disk<P>(
+	ports: &P,
+	state: &mut RimState,
+	mtimes: Vec<(BufferId, PathBuf, Option<std::time::SystemTime>)>,
+) where
+	P: RuntimePorts,
+{
+	let mut reloaded = 0usize;
+	let mut conflicted = 0usize;
+	for (buffer_id, path, mtime) in mtimes {
+		let Some(mtime) = mtime else {
+			continue;
+		};
+		if state.buffers.get(buffer_id).is_none_or(|buffer| buffer.path.as_ref() != Some(&path))
+			|| !state.update_buffer_disk_mtime(buffer_id, mtime)
+		{
+			continue;
+		}
+		if state.buffers.get(buffer_id).is_some_and(|buffer| buffer.dirty) {
+			state.set_buffer_externally_modified(buffer_id, true);
+			conflicted += 1;
+			continue;
+		}
+		if let Err(source) = ports.enqueue_external_load(buffer_id, path) {
+			let err = ActionHandlerError::ExternalReload { source };
+			error!("io worker unavailable while enqueueing checktime reload: {}", err);
+			state.workbench.status_bar.message = "checktime failed: io worker unavailable".to_string();
+			return;
+		}
+		reloaded += 1;
+	}
+	state.workbench.status_bar.message = match (reloaded, conflicted) {
+		(0, 0) => "checktime: no files changed".to_string(),
+		(reloaded, 0) => format!("checktime: reloaded {}", reloaded),
+		(reloaded, conflicted) => {
+			format!("checktime: reloaded {}, kept {} with unsaved changes", reloaded, conflicted)
+		}
+	};
+}
+
+pub(super) fn normalize_file_path(working_directory: &Path, path: &Path) -> PathBuf {
+	let absolute = if path.is_absolute() { path.to_path_buf() } else { working_directory.join(path) };
 	std::fs::canonicalize(&absolute).unwrap_or(absolute)
 }
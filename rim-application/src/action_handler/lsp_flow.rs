@@ -0,0 +1,168 @@
+use std::{ops::ControlFlow, time::{Duration, Instant}};
+
+use rim_domain::{model::CursorState, tags::TagAddress};
+use tracing::error;
+
+use super::{ActionHandlerError, ActionPorts, RimState, RuntimePorts, file_flow::open_path_at_tag_address};
+use crate::{action::LspAction, state::{BufferId, NotificationLevel}};
+
+pub(super) fn open_language_document<P>(ports: &P, state: &mut RimState, buffer_id: BufferId)
+where P: RuntimePorts {
+	if state.workbench.lsp_documents.contains_key(&buffer_id) {
+		return;
+	}
+	let Some(document) = state.language_document_for_buffer(buffer_id) else {
+		return;
+	};
+	let Some(changedtick) = state.buffer_changedtick(buffer_id) else {
+		return;
+	};
+	if let Err(source) = ports.enqueue_lsp_open(buffer_id, document) {
+		let err = ActionHandlerError::Lsp { operation: "open", source };
+		error!("language server open enqueue failed: {}", err);
+		return;
+	}
+	state.workbench.lsp_documents.insert(buffer_id, changedtick);
+}
+
+// Full-text changes go out once typing pauses this long, so a held key does
+// not copy the whole buffer to the server on every keystroke.
+const LSP_CHANGE_DELAY: Duration = Duration::from_millis(150);
+
+// Runs after every action, ticks included: edits go out as full-text changes
+// once they settle, and buffers that went away are closed on the server along
+// with their diagnostics.
+pub(super) fn sync_language_documents<P>(ports: &P, state: &mut RimState, now: Instant)
+where P: RuntimePorts {
+	if state.workbench.lsp_documents.is_empty() {
+		return;
+	}
+	let tracked = state.workbench.lsp_documents.iter().map(|(id, tick)| (*id, *tick)).collect::<Vec<_>>();
+	for (buffer_id, sent_tick) in tracked {
+		let Some(buffer) = state.buffers.get(buffer_id) else {
+			state.workbench.lsp_documents.remove(&buffer_id);
+			state.workbench.lsp_change_due.remove(&buffer_id);
+			state.workbench.diagnostics.remove(&buffer_id);
+			if let Err(source) = ports.enqueue_lsp_close(buffer_id) {
+				let err = ActionHandlerError::Lsp { operation: "close", source };
+				error!("language server close enqueue failed: {}", err);
+			}
+			continue;
+		};
+		let changedtick = buffer.changedtick;
+		if changedtick == sent_tick {
+			state.workbench.lsp_change_due.remove(&buffer_id);
+			continue;
+		}
+		let due = match state.workbench.lsp_change_due.get(&buffer_id) {
+			Some((seen_tick, due)) if *seen_tick == changedtick => *due,
+			_ => {
+				state.workbench.lsp_change_due.insert(buffer_id, (changedtick, now + LSP_CHANGE_DELAY));
+				continue;
+			}
+		};
+		if due <= now {
+			send_language_change(ports, state, buffer_id);
+		}
+	}
+}
+
+// Requests carry a cursor position, so the server must see the text it points
+// into first.
+fn flush_language_change<P>(ports: &P, state: &mut RimState, buffer_id: BufferId)
+where P: RuntimePorts {
+	let sent_tick = state.workbench.lsp_documents.get(&buffer_id).copied();
+	if sent_tick.is_some() && sent_tick != state.buffer_changedtick(buffer_id) {
+		send_language_change(ports, state, buffer_id);
+	}
+}
+
+fn send_language_change<P>(ports: &P, state: &mut RimState, buffer_id: BufferId)
+where P: RuntimePorts {
+	let Some(buffer) = state.buffers.get(buffer_id) else {
+		return;
+	};
+	let changedtick = buffer.changedtick;
+	if let Err(source) = ports.enqueue_lsp_change(buffer_id, buffer.text.to_string()) {
+		let err = ActionHandlerError::Lsp { operation: "change", source };
+		error!("language server change enqueue failed: {}", err);
+		return;
+	}
+	state.workbench.lsp_documents.insert(buffer_id, changedtick);
+	state.workbench.lsp_change_due.remove(&buffer_id);
+}
+
+// A count picks one entry when the server answers with several locations.
+pub(super) fn request_definition<P>(ports: &P, state: &mut RimState, count: Option<usize>)
+where P: RuntimePorts {
+	let Some((buffer_id, position)) = state.language_position_under_cursor() else {
+		return;
+	};
+	flush_language_change(ports, state, buffer_id);
+	state.workbench.pending_definition_pick = count;
+	if let Err(source) = ports.enqueue_lsp_definition(buffer_id, position) {
+		let err = ActionHandlerError::Lsp { operation: "definition", source };
+		error!("language server definition enqueue failed: {}", err);
+		state.workbench.pending_definition_pick = None;
+		state.workbench.status_bar.message = "definition failed: language server unavailable".to_string();
+	}
+}
+
+pub(super) fn request_hover<P>(ports: &P, state: &mut RimState)
+where P: RuntimePorts {
+	let Some((buffer_id, position)) = state.language_position_under_cursor() else {
+		return;
+	};
+	flush_language_change(ports, state, buffer_id);
+	if let Err(source) = ports.enqueue_lsp_hover(buffer_id, position) {
+		let err = ActionHandlerError::Lsp { operation: "hover", source };
+		error!("language server hover enqueue failed: {}", err);
+		state.workbench.status_bar.message = "hover failed: language server unavailable".to_string();
+	}
+}
+
+pub(super) fn handle_lsp_action<P>(ports: &P, state: &mut RimState, action: LspAction) -> ControlFlow<()>
+where P: ActionPorts {
+	match action {
+		LspAction::DiagnosticsPublished { buffer_id, diagnostics } => {
+			state.set_buffer_diagnostics(buffer_id, diagnostics);
+		}
+		LspAction::DefinitionFound { locations } => {
+			let pick = state.workbench.pending_definition_pick.take();
+			let location = match (locations.as_slice(), pick) {
+				([], _) => {
+					state.workbench.status_bar.message = "no definition found".to_string();
+					return ControlFlow::Continue(());
+				}
+				([location], None) => location,
+				(_, None) => {
+					state.show_definition_list(&locations);
+					return ControlFlow::Continue(());
+				}
+				(locations, Some(pick)) => {
+					let Some(location) = pick.checked_sub(1).and_then(|index| locations.get(index)) else {
+						state.workbench.status_bar.message = format!("only {} definitions", locations.len());
+						return ControlFlow::Continue(());
+					};
+					location
+				}
+			};
+			let address = TagAddress::Cursor(CursorState {
+				row: location.row.saturating_add(1) as u16,
+				col: location.col.saturating_add(1) as u16,
+			});
+			return open_path_at_tag_address(ports, state, location.path.clone(), address);
+		}
+		LspAction::HoverReceived { contents } => {
+			state.show_hover(contents.as_str());
+		}
+		LspAction::RequestFailed { request, reason } => {
+			state.workbench.status_bar.message = format!("{} failed: {}", request, reason);
+		}
+		LspAction::ServerFailed { command, reason } => {
+			error!("language server failed: command={} reason={}", command, reason);
+			state.push_notification(NotificationLevel::Error, format!("language server {}: {}", command, reason));
+		}
+	}
+	ControlFlow::Continue(())
+}
@@ -1,21 +1,24 @@
 use std::ops::ControlFlow;
 
-use rim_ports::{FilePicker, FileWatcher, PluginRuntime, StorageIo};
+use rim_domain::tags::TagAddress;
+use rim_ports::{FilePicker, FileWatcher, LanguageClient, PluginRuntime, StorageIo};
 
 mod command_flow;
 mod editor_flow;
 mod errors;
 mod file_flow;
+mod lsp_flow;
 mod mode_flow;
 mod plugin_flow;
 mod post_edit_flow;
 
 use errors::ActionHandlerError;
 use file_flow::{enqueue_history_save, enqueue_history_save_for_buffer, handle_file_action, handle_pending_swap_decision_key};
+use lsp_flow::{handle_lsp_action, sync_language_documents};
 use mode_flow::SequenceMatch;
 use plugin_flow::handle_plugin_runtime_action;
 
-use crate::{action::{AppAction, BufferAction, EditorAction, KeyEvent, LayoutAction, SystemAction, TabAction, WindowAction}, ports::SwapEditOp, state::{BufferId, BufferSwitchDirection, FocusDirection, NormalSequenceKey, NotificationLevel, PersistedBufferHistory, RimState, SplitAxis, WorkspaceSessionSnapshot}};
+use crate::{action::{AppAction, BufferAction, EditorAction, KeyEvent, LayoutAction, SystemAction, TabAction, WindowAction}, command::{BindingMatch, BuiltinCommand, CommandCommand, CommandTarget, ResolvedCommand}, hook::HookEvent, ports::SwapEditOp, state::{BufferId, BufferSwitchDirection, FocusDirection, KeymapScope, NormalSequenceKey, NotificationLevel, PersistedBufferHistory, RimState, SplitAxis, WorkspaceSessionSnapshot}};
 
 #[doc(hidden)]
 pub trait StoragePorts:
@@ -38,9 +41,13 @@ impl<T> StoragePorts for T where T: StorageIo<
 }
 
 #[doc(hidden)]
-pub trait RuntimePorts: StoragePorts + FileWatcher<BufferId = BufferId> {}
+pub trait RuntimePorts:
+	StoragePorts + FileWatcher<BufferId = BufferId> + LanguageClient<BufferId = BufferId>
+{
+}
 
-impl<T> RuntimePorts for T where T: StoragePorts + FileWatcher<BufferId = BufferId> {}
+impl<T> RuntimePorts for T where T: StoragePorts + FileWatcher<BufferId = BufferId> + LanguageClient<BufferId = BufferId>
+{}
 
 #[doc(hidden)]
 pub trait PluginPorts: PluginRuntime {}
@@ -55,7 +62,39 @@ impl<T> ActionPorts for T where T: RuntimePorts + FilePicker + PluginPorts {}
 impl RimState {
 	pub fn apply_action<P>(&mut self, ports: &P, action: AppAction) -> ControlFlow<()>
 	where P: ActionPorts {
-		Self::dispatch_internal(ports, self, action)
+		// Ticks mark the frame dirty themselves, only when something they own expired.
+		if !matches!(action, AppAction::System(SystemAction::Tick)) {
+			self.mark_render_dirty();
+		}
+		if self.workbench.hooks.is_empty() {
+			let flow = Self::dispatch_internal(ports, self, action);
+			let now = std::time::Instant::now();
+			sync_language_documents(ports, self, now);
+			self.track_indent_fold_changes(now);
+			self.sync_scroll_bound_windows();
+			self.sync_diff_window_scroll();
+			self.refresh_viewport_layout();
+			return flow;
+		}
+		let mode_before = self.mode;
+		let changedticks_before =
+			self.buffers.iter().map(|(buffer_id, buffer)| (buffer_id, buffer.changedtick)).collect::<Vec<_>>();
+		let flow = Self::dispatch_internal(ports, self, action);
+		for (buffer_id, changedtick) in changedticks_before {
+			if self.buffer_changedtick(buffer_id).is_some_and(|current| current != changedtick) {
+				self.emit_hook(HookEvent::BufferChanged { buffer_id });
+			}
+		}
+		if self.mode != mode_before {
+			self.emit_hook(HookEvent::ModeChanged { from: mode_before, to: self.mode });
+		}
+		let now = std::time::Instant::now();
+		sync_language_documents(ports, self, now);
+		self.track_indent_fold_changes(now);
+		self.sync_scroll_bound_windows();
+		self.sync_diff_window_scroll();
+		self.refresh_viewport_layout();
+		flow
 	}
 }
 
@@ -70,6 +109,31 @@ impl RimState {
 		}
 	}
 
+	// Ex commands such as `:earlier!` that walk the undo history must not be
+	// recorded as a new edit once they return.
+	fn predicted_command_line_editor_action_for_key(state: &RimState, key: KeyEvent) -> Option<EditorAction> {
+		let normal_key = Self::to_normal_key(state, key)?;
+		let registry = &state.workbench.command_registry;
+		let BindingMatch::Exact(ResolvedCommand {
+			target: CommandTarget::Builtin(BuiltinCommand::Command(CommandCommand::Submit)),
+			..
+		}) = registry.resolve_scope_sequence(KeymapScope::ModeCommand, &[normal_key])
+		else {
+			return None;
+		};
+		let resolved = registry.resolve_command_input(state.workbench.command_line.trim()).ok()?;
+		match resolved.target {
+			CommandTarget::Builtin(BuiltinCommand::Command(CommandCommand::UndoTo { .. })) => {
+				Some(EditorAction::Undo)
+			}
+			CommandTarget::Builtin(command) => match command.normal_mode_action()? {
+				AppAction::Editor(editor_action) => Some(editor_action),
+				_ => None,
+			},
+			_ => None,
+		}
+	}
+
 	fn dispatch_internal<P>(ports: &P, state: &mut RimState, action: AppAction) -> ControlFlow<()>
 	where P: ActionPorts {
 		let status_before = state.workbench.status_bar.message.clone();
@@ -80,24 +144,43 @@ impl RimState {
 			AppAction::Editor(editor_action) => {
 				editor_flow::apply_editor_action(ports, state, editor_action);
 			}
+			AppAction::Paste(text) => return mode_flow::handle_paste(ports, state, text),
 			AppAction::Layout(LayoutAction::SplitHorizontal) => {
 				state.split_active_window(SplitAxis::Horizontal);
 			}
 			AppAction::Layout(LayoutAction::SplitVertical) => {
 				state.split_active_window(SplitAxis::Vertical);
 			}
-			AppAction::Layout(LayoutAction::ViewportResized { .. }) => {}
+			AppAction::Layout(LayoutAction::ViewportResized { width, height }) => {
+				state.resize_viewport(width, height);
+			}
 			AppAction::Window(WindowAction::FocusLeft) => state.focus_window(FocusDirection::Left),
 			AppAction::Window(WindowAction::FocusDown) => state.focus_window(FocusDirection::Down),
 			AppAction::Window(WindowAction::FocusUp) => state.focus_window(FocusDirection::Up),
 			AppAction::Window(WindowAction::FocusRight) => state.focus_window(FocusDirection::Right),
 			AppAction::Window(WindowAction::CloseActive) => state.close_active_window(),
+			AppAction::Window(WindowAction::ZoomToggle) => state.toggle_active_window_zoom(),
 			AppAction::Buffer(BufferAction::SwitchPrev) => {
 				state.switch_active_window_buffer(BufferSwitchDirection::Prev);
 			}
 			AppAction::Buffer(BufferAction::SwitchNext) => {
 				state.switch_active_window_buffer(BufferSwitchDirection::Next);
 			}
+			AppAction::Buffer(BufferAction::SwitchAlternate) => {
+				let count = state.workbench.pending_count.take();
+				state.switch_to_alternate_buffer(count);
+			}
+			AppAction::Buffer(BufferAction::ReopenClosed) => {
+				let Some(entry) = state.take_last_closed_buffer() else {
+					return ControlFlow::Continue(());
+				};
+				return file_flow::open_path_at_tag_address(
+					ports,
+					state,
+					entry.path,
+					TagAddress::Cursor(entry.cursor),
+				);
+			}
 			AppAction::Tab(TabAction::New) => {
 				state.open_new_tab();
 			}
@@ -114,6 +197,7 @@ impl RimState {
 			AppAction::Plugin(plugin_action) => {
 				return handle_plugin_runtime_action(ports, state, plugin_action);
 			}
+			AppAction::Lsp(lsp_action) => return handle_lsp_action(ports, state, lsp_action),
 			AppAction::System(system_action) => match system_action {
 				SystemAction::Quit => {
 					for (buffer_id, path, history) in state.all_file_backed_persisted_history_snapshots() {
@@ -124,11 +208,27 @@ impl RimState {
 						let err = ActionHandlerError::SaveAll { source };
 						tracing::error!("workspace session save enqueue failed: {}", err);
 					}
+					if let Some(path) = state.persisted_session_path()
+						&& let Err(source) = ports.enqueue_save_session_file(path, state.layout_session_snapshot())
+					{
+						let err = ActionHandlerError::SessionFileSave { source };
+						tracing::error!("persisted session save enqueue failed: {}", err);
+					}
 					return ControlFlow::Break(());
 				}
 				SystemAction::ReloadConfig => {}
 				SystemAction::Tick => {
-					let _ = state.tick_notifications(std::time::Instant::now());
+					let now = std::time::Instant::now();
+					let pasting = state.has_pending_paste();
+					let preview = state.notification_preview();
+					let _ = state.tick_notifications(now);
+					let notifications_changed = state.notification_preview() != preview;
+					let folds_changed = state.recompute_due_indent_folds(now);
+					let highlight_cleared = state.clear_expired_yank_highlight(now);
+					mode_flow::continue_paste(ports, state);
+					if pasting || notifications_changed || folds_changed || highlight_cleared {
+						state.mark_render_dirty();
+					}
 				}
 			},
 		}
@@ -1,7 +1,7 @@
 use std::ops::ControlFlow;
 
 use super::{ActionPorts, command_flow, enqueue_history_save_for_buffer, handle_pending_swap_decision_key, post_edit_flow};
-use crate::{action::{AppAction, EditorAction, KeyCode, KeyEvent, KeyModifiers}, command::{BindingMatch, BuiltinCommand, CommandRegistry, CommandTarget, HelpCommand, InsertCommand, ModeCommand, NotificationCommand, OverlayCommand, ResolvedCommand}, state::{EditorMode, KeymapScope, NormalSequenceKey, RimState}};
+use crate::{action::{AppAction, EditorAction, KeyCode, KeyEvent, KeyModifiers}, command::{BindingMatch, BuiltinCommand, CommandRegistry, CommandTarget, HelpCommand, InsertCommand, ModeCommand, NotificationCommand, OverlayCommand, ResolvedCommand}, state::{EditorMode, KeymapScope, NormalSequenceKey, RimState, compute_rope_text_diff}};
 
 #[derive(Debug)]
 pub(super) enum SequenceMatch {
@@ -13,6 +13,7 @@ pub(super) enum SequenceMatch {
 
 pub(super) fn handle_key<P>(ports: &P, state: &mut RimState, key: KeyEvent) -> ControlFlow<()>
 where P: ActionPorts {
+	state.mark_status_message_seen();
 	if state.workbench.pending_swap_decision.is_some() {
 		return handle_pending_swap_decision_key(ports, state, key);
 	}
@@ -32,45 +33,73 @@ where P: ActionPorts {
 		state.workbench.visual_g_pending = false;
 	}
 
-	if key.modifiers.contains(KeyModifiers::ALT) {
+	// Only normal and visual keymaps bind Alt chords; elsewhere they are dropped.
+	if key.modifiers.contains(KeyModifiers::ALT) && (state.is_command_mode() || state.is_insert_mode()) {
 		state.workbench.normal_sequence.clear();
 		state.workbench.status_bar.key_sequence.clear();
 		state.close_key_hints();
 		return ControlFlow::Continue(());
 	}
 
-	let mode_before = state.mode;
-	let pre_text_snapshot = post_edit_flow::capture_active_buffer_text_snapshot(state);
+	// Keys typed while a long paste is still landing go after it.
+	if state.has_pending_paste() {
+		track_active_buffer_edit(ports, state, false, RimState::finish_pending_paste);
+	}
+
 	let predicted_editor_action =
 		if !state.is_command_mode() && !state.is_insert_mode() && !state.is_visual_mode() {
 			RimState::predicted_normal_mode_editor_action_for_key(state, key)
+		} else if state.is_command_mode() {
+			RimState::predicted_command_line_editor_action_for_key(state, key)
 		} else {
 			None
 		};
-	let skip_history = matches!(predicted_editor_action, Some(EditorAction::Undo | EditorAction::Redo));
-
-	let flow = if state.is_command_mode() {
-		state.workbench.normal_sequence.clear();
-		state.workbench.status_bar.key_sequence.clear();
-		command_flow::handle_command_mode_key(ports, state, key)
-	} else if state.is_visual_mode() {
-		handle_visual_mode_key(ports, state, key)
-	} else if state.is_insert_mode() {
-		state.workbench.normal_sequence.clear();
-		state.workbench.status_bar.key_sequence.clear();
-		handle_insert_mode_key(state, key)
-	} else {
-		handle_normal_mode_key(ports, state, key)
-	};
+	let skip_history =
+		matches!(predicted_editor_action, Some(EditorAction::Undo | EditorAction::UndoAll | EditorAction::Redo));
+
+	track_active_buffer_edit(ports, state, skip_history, |state| {
+		if state.is_command_mode() {
+			state.workbench.normal_sequence.clear();
+			state.workbench.status_bar.key_sequence.clear();
+			command_flow::handle_command_mode_key(ports, state, key)
+		} else if state.is_visual_mode() {
+			handle_visual_mode_key(ports, state, key)
+		} else if state.is_insert_mode() {
+			state.workbench.normal_sequence.clear();
+			state.workbench.status_bar.key_sequence.clear();
+			let flow = handle_insert_mode_key(state, key);
+			state.record_insert_repeat_key(key);
+			flow
+		} else {
+			handle_normal_mode_key(ports, state, key)
+		}
+	})
+}
 
-	if let Some(snapshot) = pre_text_snapshot.as_ref() {
-		state.record_history_from_text_diff(
-			snapshot.buffer_id,
-			&snapshot.text,
-			snapshot.cursor,
-			mode_before,
-			skip_history,
-		);
+// Records what `edit` does to the active buffer in its undo history, swap file
+// and persisted history.
+pub(super) fn track_active_buffer_edit<P, T>(
+	ports: &P,
+	state: &mut RimState,
+	skip_history: bool,
+	edit: impl FnOnce(&mut RimState) -> T,
+) -> T
+where
+	P: ActionPorts,
+{
+	let mode_before = state.mode;
+	let pre_text_snapshot = post_edit_flow::capture_active_buffer_text_snapshot(state);
+	let result = edit(state);
+
+	// One diff of the edit serves folds, the changelist and the undo history.
+	if let Some(snapshot) = pre_text_snapshot.as_ref()
+		&& let Some(diff) = state
+			.buffers
+			.get(snapshot.buffer_id)
+			.and_then(|buffer| compute_rope_text_diff(&snapshot.text, &buffer.text))
+	{
+		state.adjust_buffer_positions_for_edit(snapshot.buffer_id, &snapshot.text, &diff);
+		state.record_history_from_text_diff(snapshot.buffer_id, diff, snapshot.cursor, mode_before, skip_history);
 	}
 	if mode_before == EditorMode::Insert && state.mode != EditorMode::Insert {
 		state.commit_insert_history_group();
@@ -82,18 +111,58 @@ where P: ActionPorts {
 		enqueue_history_save_for_buffer(ports, state, snapshot.buffer_id);
 	}
 
-	flow
+	result
+}
+
+// A bracketed paste goes in as text rather than as keys, so it cannot run
+// mappings. Normal mode takes it like an insert at the cursor; the command line
+// and block insert take its first line as typed keys, and visual mode drops it.
+pub(super) fn handle_paste<P>(ports: &P, state: &mut RimState, text: String) -> ControlFlow<()>
+where P: ActionPorts {
+	if state.has_pending_paste() {
+		track_active_buffer_edit(ports, state, false, RimState::finish_pending_paste);
+	}
+	if state.is_command_mode() || state.is_block_insert_mode() {
+		for ch in text.chars().take_while(|ch| !matches!(ch, '\r' | '\n')) {
+			let _ = handle_key(ports, state, KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE));
+		}
+		return ControlFlow::Continue(());
+	}
+	if state.is_visual_mode() || state.workspace_file_picker_open() || state.notification_center_open() {
+		return ControlFlow::Continue(());
+	}
+	let return_to_normal = !state.is_insert_mode();
+	if return_to_normal {
+		state.workbench.normal_sequence.clear();
+		state.workbench.status_bar.key_sequence.clear();
+		state.begin_insert_history_group();
+		state.enter_insert_mode();
+	}
+	track_active_buffer_edit(ports, state, false, |state| state.begin_paste(text.as_str(), return_to_normal));
+	ControlFlow::Continue(())
+}
+
+// Runs on every tick.
+pub(super) fn continue_paste<P>(ports: &P, state: &mut RimState)
+where P: ActionPorts {
+	if state.has_pending_paste() {
+		track_active_buffer_edit(ports, state, false, RimState::insert_next_paste_chunk);
+	}
 }
 
 pub(super) fn handle_normal_mode_key<P>(ports: &P, state: &mut RimState, key: KeyEvent) -> ControlFlow<()>
 where P: ActionPorts {
 	let Some(normal_key) = to_normal_key(state, key) else {
+		state.workbench.pending_count = None;
 		state.workbench.normal_sequence.clear();
 		state.workbench.status_bar.key_sequence.clear();
 		state.close_key_hints();
 		return ControlFlow::Continue(());
 	};
 
+	if handle_register_prefix_key(state, normal_key) || handle_count_prefix_key(state, normal_key) {
+		return ControlFlow::Continue(());
+	}
 	state.workbench.normal_sequence.push(normal_key);
 
 	loop {
@@ -107,39 +176,122 @@ where P: ActionPorts {
 				if !should_keep_key_hints_open_for_action(&action) {
 					state.close_key_hints();
 				}
-				return RimState::dispatch_internal(ports, state, action);
+				let flow = RimState::dispatch_internal(ports, state, action);
+				state.selected_register = None;
+				state.workbench.pending_count = None;
+				return flow;
 			}
 			SequenceMatch::Command(target) => {
 				state.workbench.normal_sequence.clear();
 				state.workbench.status_bar.key_sequence.clear();
 				state.close_key_hints();
-				return command_flow::execute_resolved_command(ports, state, target);
+				let flow = command_flow::execute_resolved_command(ports, state, target);
+				state.selected_register = None;
+				state.workbench.pending_count = None;
+				return flow;
 			}
 			SequenceMatch::Pending => {
-				state.workbench.status_bar.key_sequence = render_normal_sequence(&state.workbench.normal_sequence);
+				state.workbench.status_bar.key_sequence = render_pending_prefix(state);
 				state.refresh_pending_key_hints();
 				return ControlFlow::Continue(());
 			}
 			SequenceMatch::NoMatch => {
+				if fall_back_from_unbound_leader(state) {
+					continue;
+				}
 				if state.workbench.normal_sequence.len() <= 1 {
 					state.workbench.normal_sequence.clear();
 					state.workbench.status_bar.key_sequence.clear();
 					state.close_key_hints();
+					state.selected_register = None;
+					state.workbench.pending_count = None;
 					return ControlFlow::Continue(());
 				}
+				// The key that broke the sequence starts a fresh one: the aborted count and
+				// register go, and it may itself pick a register or begin a count.
 				let last = *state.workbench.normal_sequence.last().expect("normal sequence has at least one key");
 				state.workbench.normal_sequence.clear();
+				state.selected_register = None;
+				state.workbench.pending_count = None;
+				if handle_register_prefix_key(state, last) || handle_count_prefix_key(state, last) {
+					state.close_key_hints();
+					return ControlFlow::Continue(());
+				}
 				state.workbench.normal_sequence.push(last);
-				state.workbench.status_bar.key_sequence = render_normal_sequence(&state.workbench.normal_sequence);
+				state.workbench.status_bar.key_sequence = render_pending_prefix(state);
 				state.refresh_pending_key_hints();
 			}
 		}
 	}
 }
 
+// `"x` picks the register for the next command and never reaches the keymap.
+fn handle_register_prefix_key(state: &mut RimState, key: NormalSequenceKey) -> bool {
+	if state.workbench.normal_sequence.as_slice() == [NormalSequenceKey::Char('"')] {
+		state.workbench.normal_sequence.clear();
+		state.close_key_hints();
+		let NormalSequenceKey::Char(name) = key else {
+			state.workbench.status_bar.key_sequence.clear();
+			return true;
+		};
+		if state.select_register(name) {
+			state.workbench.status_bar.key_sequence = render_pending_prefix(state);
+		} else {
+			state.workbench.status_bar.key_sequence.clear();
+			state.workbench.status_bar.message = format!("invalid register: {}", name);
+		}
+		return true;
+	}
+	if state.workbench.normal_sequence.is_empty() && key == NormalSequenceKey::Char('"') {
+		state.workbench.normal_sequence.push(key);
+		state.workbench.status_bar.key_sequence = render_pending_prefix(state);
+		return true;
+	}
+	false
+}
+
+// Digits typed before a command form its count; `0` only extends a count since
+// on its own it is the line-start motion.
+fn handle_count_prefix_key(state: &mut RimState, key: NormalSequenceKey) -> bool {
+	if !state.workbench.normal_sequence.is_empty() {
+		return false;
+	}
+	let NormalSequenceKey::Char(ch) = key else {
+		return false;
+	};
+	let Some(digit) = ch.to_digit(10) else {
+		return false;
+	};
+	if digit == 0 && state.workbench.pending_count.is_none() {
+		return false;
+	}
+	let count = state.workbench.pending_count.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize);
+	state.workbench.pending_count = Some(count);
+	state.workbench.status_bar.key_sequence = render_pending_prefix(state);
+	true
+}
+
+// Everything typed toward the next command so far, e.g. `"a2d`, so the status
+// line shows that the editor is still waiting.
+fn render_pending_prefix(state: &RimState) -> String {
+	let mut prefix = String::new();
+	if let Some(register) = state.selected_register {
+		prefix.push('"');
+		prefix.push(register);
+	}
+	if let Some(count) = state.workbench.pending_count {
+		prefix.push_str(count.to_string().as_str());
+	}
+	prefix.push_str(render_normal_sequence(&state.workbench.normal_sequence).as_str());
+	prefix
+}
+
 pub(super) fn to_normal_key(state: &RimState, key: KeyEvent) -> Option<NormalSequenceKey> {
 	if key.modifiers.contains(KeyModifiers::ALT) {
-		return None;
+		return match key.code {
+			KeyCode::Char(ch) if !key.modifiers.contains(KeyModifiers::CONTROL) => Some(NormalSequenceKey::Alt(ch)),
+			_ => None,
+		};
 	}
 
 	if key.modifiers.contains(KeyModifiers::CONTROL) {
@@ -244,12 +396,19 @@ pub(super) fn render_normal_sequence(keys: &[NormalSequenceKey]) -> String {
 			NormalSequenceKey::Down => "<Down>".to_string(),
 			NormalSequenceKey::Char(ch) => ch.to_string(),
 			NormalSequenceKey::Ctrl(ch) => format!("<C-{}>", ch),
+			NormalSequenceKey::Alt(ch) => format!("<A-{}>", ch),
 		})
 		.collect::<Vec<_>>()
 		.join("")
 }
 
 pub(super) fn handle_insert_mode_key(state: &mut RimState, key: KeyEvent) -> ControlFlow<()> {
+	if std::mem::take(&mut state.workbench.pending_register_insert) {
+		if let KeyCode::Char(name) = key.code {
+			state.insert_register_at_cursor(name);
+		}
+		return ControlFlow::Continue(());
+	}
 	if let Some(flow) = handle_insert_scope_key(state, key) {
 		return flow;
 	}
@@ -266,7 +425,7 @@ pub(super) fn handle_insert_mode_key(state: &mut RimState, key: KeyEvent) -> Con
 			state.exit_insert_mode();
 		}
 		KeyCode::Enter => {
-			state.insert_newline_at_cursor();
+			state.insert_newline_with_smart_indent();
 		}
 		KeyCode::Backspace => {
 			state.backspace_at_cursor();
@@ -275,9 +434,12 @@ pub(super) fn handle_insert_mode_key(state: &mut RimState, key: KeyEvent) -> Con
 		KeyCode::Down => state.move_cursor_down(),
 		KeyCode::Up => state.move_cursor_up(),
 		KeyCode::Right => state.move_cursor_right_for_insert(),
-		KeyCode::Tab => state.insert_char_at_cursor('\t'),
+		KeyCode::Tab => state.insert_tab_at_cursor(),
 		KeyCode::F1 => {}
 		KeyCode::Char(ch) => {
+			if ch == '}' {
+				state.dedent_for_closing_brace();
+			}
 			state.insert_char_at_cursor(ch);
 		}
 	}
@@ -293,7 +455,7 @@ fn handle_block_insert_mode_key(state: &mut RimState, key: KeyEvent) -> ControlF
 	match key.code {
 		KeyCode::Esc => state.exit_insert_mode(),
 		KeyCode::Backspace => state.backspace_at_block_cursor(),
-		KeyCode::Tab => state.insert_char_at_block_cursor('\t'),
+		KeyCode::Tab => state.insert_tab_at_block_cursor(),
 		KeyCode::F1 => {}
 		KeyCode::Char(ch) => state.insert_char_at_block_cursor(ch),
 		KeyCode::Enter | KeyCode::Left | KeyCode::Down | KeyCode::Up | KeyCode::Right => {
@@ -312,6 +474,9 @@ where P: ActionPorts {
 		state.close_key_hints();
 		return ControlFlow::Continue(());
 	};
+	if handle_register_prefix_key(state, visual_key) {
+		return ControlFlow::Continue(());
+	}
 	state.workbench.normal_sequence.push(visual_key);
 
 	loop {
@@ -325,20 +490,27 @@ where P: ActionPorts {
 				if !should_keep_key_hints_open_for_action(&action) {
 					state.close_key_hints();
 				}
-				return RimState::dispatch_internal(ports, state, action);
+				let flow = RimState::dispatch_internal(ports, state, action);
+				state.selected_register = None;
+				return flow;
 			}
 			SequenceMatch::Command(target) => {
 				state.workbench.normal_sequence.clear();
 				state.workbench.status_bar.key_sequence.clear();
 				state.close_key_hints();
-				return command_flow::execute_resolved_command(ports, state, target);
+				let flow = command_flow::execute_resolved_command(ports, state, target);
+				state.selected_register = None;
+				return flow;
 			}
 			SequenceMatch::Pending => {
-				state.workbench.status_bar.key_sequence = render_normal_sequence(&state.workbench.normal_sequence);
+				state.workbench.status_bar.key_sequence = render_pending_prefix(state);
 				state.refresh_pending_key_hints();
 				return ControlFlow::Continue(());
 			}
 			SequenceMatch::NoMatch => {
+				if fall_back_from_unbound_leader(state) {
+					continue;
+				}
 				if state.workbench.normal_sequence.len() <= 1 {
 					state.workbench.normal_sequence.clear();
 					state.workbench.status_bar.key_sequence.clear();
@@ -348,13 +520,23 @@ where P: ActionPorts {
 				let last = *state.workbench.normal_sequence.last().expect("visual sequence has at least one key");
 				state.workbench.normal_sequence.clear();
 				state.workbench.normal_sequence.push(last);
-				state.workbench.status_bar.key_sequence = render_normal_sequence(&state.workbench.normal_sequence);
+				state.workbench.status_bar.key_sequence = render_pending_prefix(state);
 				state.refresh_pending_key_hints();
 			}
 		}
 	}
 }
 
+// A leader key that starts no binding in this scope is retried as its literal
+// character, so reusing an already bound key as leader keeps that binding.
+fn fall_back_from_unbound_leader(state: &mut RimState) -> bool {
+	if state.workbench.normal_sequence.as_slice() != [NormalSequenceKey::Leader] {
+		return false;
+	}
+	state.workbench.normal_sequence[0] = NormalSequenceKey::Char(state.workbench.leader_key);
+	true
+}
+
 fn should_keep_key_hints_open_for_action(action: &AppAction) -> bool {
 	matches!(
 		action,
@@ -414,7 +596,7 @@ fn handle_insert_scope_key(state: &mut RimState, key: KeyEvent) -> Option<Contro
 					"block insert supports text, tab, backspace, esc only".to_string();
 				return Some(ControlFlow::Continue(()));
 			}
-			state.insert_newline_at_cursor();
+			state.insert_newline_with_smart_indent();
 			Some(ControlFlow::Continue(()))
 		}
 		BindingMatch::Exact(ResolvedCommand {
@@ -481,10 +663,61 @@ fn handle_insert_scope_key(state: &mut RimState, key: KeyEvent) -> Option<Contro
 			..
 		}) => {
 			if state.is_block_insert_mode() {
-				state.insert_char_at_block_cursor('\t');
+				state.insert_tab_at_block_cursor();
 			} else {
-				state.insert_char_at_cursor('\t');
+				state.insert_tab_at_cursor();
+			}
+			Some(ControlFlow::Continue(()))
+		}
+		BindingMatch::Exact(ResolvedCommand {
+			target:
+				CommandTarget::Builtin(crate::command::BuiltinCommand::Insert(
+					command @ (InsertCommand::CompleteNext | InsertCommand::CompletePrev),
+				)),
+			..
+		}) => {
+			if state.is_block_insert_mode() {
+				state.workbench.status_bar.message =
+					"block insert supports text, tab, backspace, esc only".to_string();
+				return Some(ControlFlow::Continue(()));
+			}
+			state.complete_word_at_cursor(command == InsertCommand::CompleteNext);
+			Some(ControlFlow::Continue(()))
+		}
+		BindingMatch::Exact(ResolvedCommand {
+			target: CommandTarget::Builtin(crate::command::BuiltinCommand::Insert(InsertCommand::LineStart)),
+			..
+		}) => {
+			if state.is_block_insert_mode() {
+				state.workbench.status_bar.message =
+					"block insert supports text, tab, backspace, esc only".to_string();
+				return Some(ControlFlow::Continue(()));
+			}
+			state.move_cursor_line_start();
+			Some(ControlFlow::Continue(()))
+		}
+		BindingMatch::Exact(ResolvedCommand {
+			target: CommandTarget::Builtin(crate::command::BuiltinCommand::Insert(InsertCommand::LineEnd)),
+			..
+		}) => {
+			if state.is_block_insert_mode() {
+				state.workbench.status_bar.message =
+					"block insert supports text, tab, backspace, esc only".to_string();
+				return Some(ControlFlow::Continue(()));
+			}
+			state.move_cursor_to_insert_line_end_slot();
+			Some(ControlFlow::Continue(()))
+		}
+		BindingMatch::Exact(ResolvedCommand {
+			target: CommandTarget::Builtin(crate::command::BuiltinCommand::Insert(InsertCommand::Register)),
+			..
+		}) => {
+			if state.is_block_insert_mode() {
+				state.workbench.status_bar.message =
+					"block insert supports text, tab, backspace, esc only".to_string();
+				return Some(ControlFlow::Continue(()));
 			}
+			state.workbench.pending_register_insert = true;
 			Some(ControlFlow::Continue(()))
 		}
 		BindingMatch::Exact(ResolvedCommand {
@@ -527,7 +760,7 @@ fn handle_notification_center_key(state: &mut RimState, key: KeyEvent) -> Contro
 				return ControlFlow::Continue(());
 			}
 			BindingMatch::Pending => {
-				state.workbench.status_bar.key_sequence = render_normal_sequence(&state.workbench.normal_sequence);
+				state.workbench.status_bar.key_sequence = render_pending_prefix(state);
 				state.refresh_pending_key_hints();
 				return ControlFlow::Continue(());
 			}
@@ -542,7 +775,7 @@ fn handle_notification_center_key(state: &mut RimState, key: KeyEvent) -> Contro
 					*state.workbench.normal_sequence.last().expect("notification center sequence has at least one key");
 				state.workbench.normal_sequence.clear();
 				state.workbench.normal_sequence.push(last);
-				state.workbench.status_bar.key_sequence = render_normal_sequence(&state.workbench.normal_sequence);
+				state.workbench.status_bar.key_sequence = render_pending_prefix(state);
 				state.refresh_pending_key_hints();
 			}
 		}
@@ -579,6 +812,6 @@ fn handle_notification_center_target(state: &mut RimState, target: CommandTarget
 		CommandTarget::Builtin(BuiltinCommand::Help(HelpCommand::KeymapHalfPageDown)) => {
 			let _ = state.scroll_key_hints_half_page_down();
 		}
-		CommandTarget::Builtin(_) | CommandTarget::Plugin { .. } => {}
+		CommandTarget::Builtin(_) | CommandTarget::Plugin { .. } | CommandTarget::Host { .. } => {}
 	}
 }
@@ -1,9 +1,9 @@
-use std::{ops::ControlFlow, path::PathBuf, time::{Duration, Instant}};
+use std::{cell::RefCell, ops::ControlFlow, path::PathBuf, rc::Rc, time::{Duration, Instant}};
 
-use rim_ports::{PluginAction as RequestedPluginAction, PluginCommandResponse, PluginEffect};
+use rim_ports::{LanguagePosition, PluginAction as RequestedPluginAction, PluginCommandResponse, PluginEffect};
 
 use super::support::{FilePickerPorts, RecordingPorts, SwapDecisionPorts, dispatch_test_action, normalize_test_path};
-use crate::{action::{AppAction, EditorAction, FileAction, KeyCode, KeyEvent, KeyModifiers, PluginRuntimeAction, SwapConflictCheckResult, SwapConflictInfo, SystemAction}, command::{CommandAliasConfig, CommandAliasSection, CommandConfigFile, PluginCommandRegistration}, state::{BufferEditSnapshot, BufferHistoryEntry, CursorState, PendingSwapDecision, PersistedBufferHistory, RimState, WorkspaceBufferHistorySnapshot, WorkspaceBufferSnapshot, WorkspaceSessionSnapshot, WorkspaceTabSnapshot, WorkspaceWindowBufferViewSnapshot, WorkspaceWindowSnapshot}};
+use crate::{action::{AppAction, EditorAction, FileAction, KeyCode, KeyEvent, KeyModifiers, LspAction, PluginRuntimeAction, SaveVerification, SwapConflictCheckResult, SwapConflictInfo, SystemAction}, command::{CommandAliasConfig, CommandAliasSection, CommandConfigFile, PluginCommandRegistration}, hook::{HookEvent, HookEventKind}, state::{BufferDiagnostic, BufferEditSnapshot, BufferHistoryEntry, BufferId, CursorState, DefinitionLocation, DiagnosticSeverity, EditorMode, LanguageServerConfig, OverlayState, PendingSwapDecision, PersistedBufferHistory, RimState, SplitAxis, WorkspaceBufferHistorySnapshot, WorkspaceBufferSnapshot, WorkspaceSessionSnapshot, WorkspaceTabSnapshot, WorkspaceWindowBufferViewSnapshot, WorkspaceWindowSnapshot}};
 
 fn register_pick_plugin_command(state: &mut RimState) {
 	state
@@ -32,6 +32,7 @@ fn file_load_completed_should_mark_buffer_clean() {
 			buffer_id,
 			source: crate::action::FileLoadSource::Open,
 			result: Ok("loaded".to_string()),
+			mtime: None,
 		}),
 	);
 
@@ -39,6 +40,78 @@ fn file_load_completed_should_mark_buffer_clean() {
 	assert!(!buffer.dirty);
 }
 
+#[test]
+fn file_load_failure_should_keep_missing_files_writable_and_unreadable_ones_read_only() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let load_error = |kind: std::io::ErrorKind| {
+		Err(anyhow::Error::new(std::io::Error::from(kind)).context("read file failed: notes.txt"))
+	};
+	let missing_id = state.create_buffer(Some(normalize_test_path("notes.txt")), "");
+	state.bind_buffer_to_active_window(missing_id);
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::LoadCompleted {
+			buffer_id: missing_id,
+			source:    crate::action::FileLoadSource::Open,
+			result:    load_error(std::io::ErrorKind::NotFound),
+			mtime:     None,
+		}),
+	);
+	let buffer = state.buffers.get(missing_id).expect("buffer exists");
+	assert_eq!(buffer.text.to_string(), "");
+	assert!(!buffer.read_only);
+	assert!(buffer.new_file);
+	assert_eq!(state.workbench.status_bar.message, "new notes.txt");
+
+	let locked_id = state.create_buffer(Some(normalize_test_path("locked.txt")), "");
+	state.bind_buffer_to_active_window(locked_id);
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::LoadCompleted {
+			buffer_id: locked_id,
+			source:    crate::action::FileLoadSource::Open,
+			result:    load_error(std::io::ErrorKind::PermissionDenied),
+			mtime:     None,
+		}),
+	);
+	assert!(state.buffers.get(locked_id).expect("buffer exists").read_only);
+	assert_eq!(state.workbench.status_bar.message, "load failed: permission denied; buffer is read-only");
+	assert_eq!(state.status_line_position(), "1:1 Top[RO]");
+	submit_command_with_ports(&mut state, &ports, "w");
+	assert_eq!(state.workbench.status_bar.message, "save blocked: buffer is read-only (use :w! to write)");
+	submit_command_with_ports(&mut state, &ports, "w!");
+	assert_eq!(state.workbench.status_bar.message, "saving...");
+}
+
+#[test]
+fn opening_a_missing_path_should_flag_the_buffer_new_until_it_is_saved() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	assert_eq!(state.apply_set_option("statusline=%f%n"), Ok("statusline=%f%n".to_string()));
+
+	submit_command_with_ports(&mut state, &ports, "e no-such-file.txt");
+	let buffer_id = state.active_buffer_id().expect("new buffer should be active");
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert!(buffer.new_file);
+	assert!(!buffer.dirty);
+	assert!(ports.file_loads.borrow().is_empty());
+	assert_eq!(state.workbench.status_bar.message, "new no-such-file.txt");
+	assert_eq!(state.status_line_position(), "no-such-file.txt[New]");
+	assert_eq!(state.apply_set_option("statusline="), Ok("statusline=".to_string()));
+	assert_eq!(state.status_line_position(), "1:1 Top[New]");
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::SaveCompleted {
+			buffer_id,
+			result: Ok(SaveVerification::Matched { mtime: None }),
+		}),
+	);
+	assert!(!state.buffers.get(buffer_id).expect("buffer exists").new_file);
+	assert_eq!(state.status_line_position(), "1:1 Top");
+}
+
 #[test]
 fn file_save_completed_should_mark_buffer_clean() {
 	let mut state = RimState::new();
@@ -48,13 +121,135 @@ fn file_save_completed_should_mark_buffer_clean() {
 
 	let _ = dispatch_test_action(
 		&mut state,
-		AppAction::File(crate::action::FileAction::SaveCompleted { buffer_id, result: Ok(()) }),
+		AppAction::File(crate::action::FileAction::SaveCompleted {
+			buffer_id,
+			result: Ok(SaveVerification::Matched { mtime: None }),
+		}),
 	);
 
 	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
 	assert!(!buffer.dirty);
 }
 
+#[test]
+fn file_save_completed_should_fire_buffer_saved_hook_with_buffer_id() {
+	let mut state = RimState::new();
+	let _ = state.create_buffer(None, "other");
+	let buffer_id = state.create_buffer(None, "");
+	state.bind_buffer_to_active_window(buffer_id);
+	let saved = Rc::new(RefCell::new(Vec::new()));
+	let saved_events = Rc::clone(&saved);
+	state.register_hook(HookEventKind::BufferSaved, move |event| saved_events.borrow_mut().push(*event));
+	let opened = Rc::new(RefCell::new(0usize));
+	let opened_events = Rc::clone(&opened);
+	state.register_hook(HookEventKind::BufferOpened, move |_| *opened_events.borrow_mut() += 1);
+
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::File(crate::action::FileAction::SaveCompleted {
+			buffer_id,
+			result: Ok(SaveVerification::Matched { mtime: None }),
+		}),
+	);
+
+	assert_eq!(saved.borrow().as_slice(), &[HookEvent::BufferSaved { buffer_id }]);
+	assert_eq!(*opened.borrow(), 0);
+}
+
+#[test]
+fn failed_save_should_not_fire_buffer_saved_hook() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "");
+	state.bind_buffer_to_active_window(buffer_id);
+	let saved = Rc::new(RefCell::new(0usize));
+	let saved_events = Rc::clone(&saved);
+	let hook_id = state.register_hook(HookEventKind::BufferSaved, move |_| *saved_events.borrow_mut() += 1);
+
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::File(crate::action::FileAction::SaveCompleted {
+			buffer_id,
+			result: Err(anyhow::anyhow!("disk full")),
+		}),
+	);
+	assert_eq!(*saved.borrow(), 0);
+
+	assert!(state.unregister_hook(hook_id));
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::File(crate::action::FileAction::SaveCompleted {
+			buffer_id,
+			result: Ok(SaveVerification::Matched { mtime: None }),
+		}),
+	);
+	assert_eq!(*saved.borrow(), 0);
+}
+
+#[test]
+fn save_with_mismatched_disk_size_should_keep_buffer_flagged_for_review() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(Some(PathBuf::from("/tmp/raced.txt")), "abc");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.set_buffer_dirty(buffer_id, true);
+	let saved = Rc::new(RefCell::new(0usize));
+	let saved_events = Rc::clone(&saved);
+	state.register_hook(HookEventKind::BufferSaved, move |_| *saved_events.borrow_mut() += 1);
+
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::File(FileAction::SaveCompleted {
+			buffer_id,
+			result: Ok(SaveVerification::SizeMismatch { written: 3, on_disk: 7 }),
+		}),
+	);
+
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert!(buffer.dirty);
+	assert!(buffer.externally_modified);
+	assert!(!state.should_ignore_recent_external_change(buffer_id));
+	assert_eq!(*saved.borrow(), 0);
+	assert_eq!(
+		state.workbench.status_bar.message,
+		"file changed during save: wrote 3 bytes, found 7; buffer kept modified"
+	);
+
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::File(FileAction::SaveCompleted { buffer_id, result: Ok(SaveVerification::MtimeMismatch) }),
+	);
+	assert!(state.buffers.get(buffer_id).expect("buffer exists").dirty);
+	assert_eq!(
+		state.workbench.status_bar.message,
+		"file changed during save: modified after the write; buffer kept modified"
+	);
+}
+
+#[test]
+fn editing_keys_should_fire_buffer_changed_and_mode_changed_hooks() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "");
+	state.bind_buffer_to_active_window(buffer_id);
+	let events = Rc::new(RefCell::new(Vec::new()));
+	for kind in [HookEventKind::BufferChanged, HookEventKind::ModeChanged] {
+		let events = Rc::clone(&events);
+		state.register_hook(kind, move |event| events.borrow_mut().push(*event));
+	}
+
+	for key in [
+		KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE),
+		KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE),
+		KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE),
+	] {
+		let _ = dispatch_test_action(&mut state, AppAction::Editor(EditorAction::KeyPressed(key)));
+	}
+
+	assert_eq!(events.borrow().as_slice(), &[
+		HookEvent::ModeChanged { from: EditorMode::Normal, to: EditorMode::Insert },
+		HookEvent::BufferChanged { buffer_id },
+		HookEvent::ModeChanged { from: EditorMode::Insert, to: EditorMode::Normal },
+	]);
+}
+
 #[test]
 fn system_quit_should_enqueue_workspace_session_save() {
 	let mut state = RimState::new();
@@ -95,6 +290,7 @@ fn workspace_session_loaded_should_restore_state_and_enqueue_runtime_bindings()
 						}],
 						before_cursor: CursorState { row: 1, col: 1 },
 						after_cursor:  CursorState { row: 1, col: 8 },
+						changed_at:    None,
 					}],
 					redo_stack: Vec::new(),
 				}),
@@ -173,11 +369,14 @@ fn external_change_detected_should_be_ignored_briefly_after_save() {
 
 	let _ = state.apply_action(
 		&ports,
-		AppAction::File(crate::action::FileAction::SaveCompleted { buffer_id, result: Ok(()) }),
+		AppAction::File(crate::action::FileAction::SaveCompleted {
+			buffer_id,
+			result: Ok(SaveVerification::Matched { mtime: None }),
+		}),
 	);
 	let _ = state.apply_action(
 		&ports,
-		AppAction::File(crate::action::FileAction::ExternalChangeDetected { buffer_id, path }),
+		AppAction::File(crate::action::FileAction::ExternalChangeDetected { buffer_id, path, on_disk: true }),
 	);
 
 	assert!(ports.external_loads.borrow().is_empty());
@@ -195,12 +394,41 @@ fn external_change_detected_should_reload_after_ignore_window_expires() {
 
 	let _ = state.apply_action(
 		&ports,
-		AppAction::File(crate::action::FileAction::ExternalChangeDetected { buffer_id, path: path.clone() }),
+		AppAction::File(crate::action::FileAction::ExternalChangeDetected {
+			buffer_id,
+			path: path.clone(),
+			on_disk: true,
+		}),
 	);
 
 	assert_eq!(ports.external_loads.borrow().as_slice(), &[(buffer_id, path)]);
 }
 
+#[test]
+fn external_deletion_should_mark_buffer_deleted_until_saved() {
+	let mut state = RimState::new();
+	let ports = RecordingPorts::default();
+	let path = PathBuf::from("a.txt");
+	let buffer_id = state.create_buffer(Some(path.clone()), "old");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(crate::action::FileAction::ExternalChangeDetected { buffer_id, path, on_disk: false }),
+	);
+	assert!(ports.external_loads.borrow().is_empty());
+	assert!(state.buffers.get(buffer_id).is_some_and(|buffer| buffer.deleted_on_disk));
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(crate::action::FileAction::SaveCompleted {
+			buffer_id,
+			result: Ok(SaveVerification::Matched { mtime: None }),
+		}),
+	);
+	assert!(state.buffers.get(buffer_id).is_some_and(|buffer| !buffer.deleted_on_disk));
+}
+
 #[test]
 fn internal_save_echo_should_not_leave_reloading_message() {
 	let mut state = RimState::new();
@@ -211,13 +439,16 @@ fn internal_save_echo_should_not_leave_reloading_message() {
 
 	let _ = state.apply_action(
 		&ports,
-		AppAction::File(crate::action::FileAction::SaveCompleted { buffer_id, result: Ok(()) }),
+		AppAction::File(crate::action::FileAction::SaveCompleted {
+			buffer_id,
+			result: Ok(SaveVerification::Matched { mtime: None }),
+		}),
 	);
 	assert_eq!(state.workbench.status_bar.message, "file saved");
 
 	let _ = state.apply_action(
 		&ports,
-		AppAction::File(crate::action::FileAction::ExternalChangeDetected { buffer_id, path }),
+		AppAction::File(crate::action::FileAction::ExternalChangeDetected { buffer_id, path, on_disk: true }),
 	);
 	assert_eq!(state.workbench.status_bar.message, "file saved");
 	assert!(ports.external_loads.borrow().is_empty());
@@ -234,7 +465,7 @@ fn external_change_detected_should_be_ignored_while_internal_save_in_flight() {
 
 	let _ = state.apply_action(
 		&ports,
-		AppAction::File(crate::action::FileAction::ExternalChangeDetected { buffer_id, path }),
+		AppAction::File(crate::action::FileAction::ExternalChangeDetected { buffer_id, path, on_disk: true }),
 	);
 
 	assert!(ports.external_loads.borrow().is_empty());
@@ -428,7 +659,10 @@ fn command_wq_should_break_after_save_completed_and_save_workspace_session() {
 
 	let flow = state.apply_action(
 		&ports,
-		AppAction::File(crate::action::FileAction::SaveCompleted { buffer_id, result: Ok(()) }),
+		AppAction::File(crate::action::FileAction::SaveCompleted {
+			buffer_id,
+			result: Ok(SaveVerification::Matched { mtime: None }),
+		}),
 	);
 
 	assert!(matches!(flow, ControlFlow::Break(())));
@@ -460,7 +694,10 @@ fn command_wqa_should_enqueue_save_all_and_quit_after_last_save() {
 
 	let flow = state.apply_action(
 		&ports,
-		AppAction::File(crate::action::FileAction::SaveCompleted { buffer_id: first, result: Ok(()) }),
+		AppAction::File(crate::action::FileAction::SaveCompleted {
+			buffer_id: first,
+			result:    Ok(SaveVerification::Matched { mtime: None }),
+		}),
 	);
 	assert!(matches!(flow, ControlFlow::Continue(())));
 	assert!(state.workbench.quit_after_save);
@@ -468,7 +705,10 @@ fn command_wqa_should_enqueue_save_all_and_quit_after_last_save() {
 
 	let flow = state.apply_action(
 		&ports,
-		AppAction::File(crate::action::FileAction::SaveCompleted { buffer_id: second, result: Ok(()) }),
+		AppAction::File(crate::action::FileAction::SaveCompleted {
+			buffer_id: second,
+			result:    Ok(SaveVerification::Matched { mtime: None }),
+		}),
 	);
 	assert!(matches!(flow, ControlFlow::Break(())));
 	assert_eq!(ports.session_saves.borrow().len(), 1);
@@ -524,6 +764,29 @@ fn command_wqa_should_be_blocked_when_any_buffer_was_changed_externally() {
 	assert!(!state.workbench.quit_after_save);
 }
 
+#[test]
+fn command_wa_should_point_a_blocked_write_at_wa_bang() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let first = state.create_buffer(Some(PathBuf::from("a.txt")), "a");
+	let second = state.create_buffer(Some(PathBuf::from("b.txt")), "b");
+	state.bind_buffer_to_active_window(first);
+	state.set_buffer_dirty(first, true);
+	state.set_buffer_dirty(second, true);
+	state.set_buffer_read_only(second, true);
+
+	submit_command_with_ports(&mut state, &ports, "wa");
+	assert_eq!(
+		state.workbench.status_bar.message,
+		"save all blocked: a buffer is read-only (use :wa! to write)"
+	);
+	assert!(state.workbench.in_flight_internal_saves.is_empty());
+
+	submit_command_with_ports(&mut state, &ports, "wa!");
+	assert!(!state.workbench.quit_after_save);
+	assert_eq!(state.workbench.in_flight_internal_saves.len(), 2);
+}
+
 #[test]
 fn command_wqa_bang_should_force_save_all_and_quit() {
 	let mut state = RimState::new();
@@ -550,11 +813,17 @@ fn command_wqa_bang_should_force_save_all_and_quit() {
 
 	let _ = state.apply_action(
 		&ports,
-		AppAction::File(crate::action::FileAction::SaveCompleted { buffer_id: first, result: Ok(()) }),
+		AppAction::File(crate::action::FileAction::SaveCompleted {
+			buffer_id: first,
+			result:    Ok(SaveVerification::Matched { mtime: None }),
+		}),
 	);
 	let flow = state.apply_action(
 		&ports,
-		AppAction::File(crate::action::FileAction::SaveCompleted { buffer_id: second, result: Ok(()) }),
+		AppAction::File(crate::action::FileAction::SaveCompleted {
+			buffer_id: second,
+			result:    Ok(SaveVerification::Matched { mtime: None }),
+		}),
 	);
 
 	assert!(matches!(flow, ControlFlow::Break(())));
@@ -659,6 +928,7 @@ fn external_changed_should_reload_when_buffer_is_clean() {
 			buffer_id,
 			source: crate::action::FileLoadSource::External,
 			result: Ok("new".to_string()),
+			mtime: None,
 		}),
 	);
 
@@ -668,6 +938,67 @@ fn external_changed_should_reload_when_buffer_is_clean() {
 	assert!(!buffer.dirty);
 }
 
+#[test]
+fn checktime_should_reload_only_clean_buffers_changed_on_disk() {
+	let mut state = RimState::new();
+	let ports = RecordingPorts::default();
+	let seen = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+	let [clean, dirty, untouched] = ["clean.txt", "dirty.txt", "untouched.txt"].map(|name| {
+		let buffer_id = state.create_buffer(Some(PathBuf::from(name)), "old");
+		state.record_buffer_disk_mtime(buffer_id, Some(seen));
+		buffer_id
+	});
+	let never_loaded = state.create_buffer(Some(PathBuf::from("new.txt")), "");
+	state.bind_buffer_to_active_window(clean);
+	state.set_buffer_dirty(dirty, true);
+
+	state.enter_command_mode();
+	for ch in "checktime".chars() {
+		state.push_command_char(ch);
+	}
+	let _ = state.apply_action(
+		&ports,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))),
+	);
+	let mut checked = ports.mtime_checks.borrow().concat();
+	checked.sort_by(|left, right| left.1.cmp(&right.1));
+	assert_eq!(checked, vec![
+		(clean, PathBuf::from("clean.txt")),
+		(dirty, PathBuf::from("dirty.txt")),
+		(untouched, PathBuf::from("untouched.txt")),
+	]);
+	assert!(checked.iter().all(|(buffer_id, _)| *buffer_id != never_loaded));
+
+	let later = seen + Duration::from_secs(10);
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::MtimesChecked {
+			mtimes: vec![
+				(clean, PathBuf::from("clean.txt"), Some(later)),
+				(dirty, PathBuf::from("dirty.txt"), Some(later)),
+				(untouched, PathBuf::from("untouched.txt"), Some(seen)),
+			],
+		}),
+	);
+
+	assert_eq!(ports.external_loads.borrow().clone(), vec![(clean, PathBuf::from("clean.txt"))]);
+	assert!(state.buffers.get(dirty).expect("buffer exists").externally_modified);
+	assert!(!state.buffers.get(untouched).expect("buffer exists").externally_modified);
+	assert_eq!(state.workbench.status_bar.message, "checktime: reloaded 1, kept 1 with unsaved changes");
+}
+
+#[test]
+fn closing_a_buffer_should_forget_its_disk_mtime() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(Some(PathBuf::from("a.txt")), "old");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.record_buffer_disk_mtime(buffer_id, Some(std::time::SystemTime::UNIX_EPOCH));
+
+	state.close_buffer(buffer_id);
+
+	assert!(!state.workbench.disk_mtimes.contains_key(&buffer_id));
+}
+
 #[test]
 fn external_changed_should_not_reload_when_buffer_is_dirty() {
 	let mut state = RimState::new();
@@ -681,6 +1012,7 @@ fn external_changed_should_not_reload_when_buffer_is_dirty() {
 			buffer_id,
 			source: crate::action::FileLoadSource::External,
 			result: Ok("new".to_string()),
+			mtime: None,
 		}),
 	);
 
@@ -890,6 +1222,43 @@ fn command_e_with_same_path_should_reuse_existing_buffer_across_tabs() {
 	assert_eq!(count, 1);
 }
 
+#[test]
+fn command_drop_should_focus_window_already_showing_file_in_another_tab() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let existing = state.create_buffer(Some(normalize_test_path("b.txt")), "old");
+	state.bind_buffer_to_active_window(existing);
+	state.split_active_window(crate::state::SplitAxis::Horizontal);
+	let showing_window = state.active_window_id();
+	let first_tab = state.active_tab;
+	state.open_new_tab();
+	let scratch = state.active_buffer_id().expect("new tab has a buffer");
+
+	submit_command_with_ports(&mut state, &ports, "drop b.txt");
+
+	assert_eq!(state.active_tab, first_tab);
+	assert_eq!(state.active_window_id(), showing_window);
+	assert_eq!(state.active_buffer_id(), Some(existing));
+	assert_eq!(state.workbench.status_bar.message, "focused b.txt");
+	assert!(state.buffers.contains_key(scratch));
+	assert!(ports.file_loads.borrow().is_empty());
+}
+
+#[test]
+fn command_drop_should_open_file_in_current_window_when_not_displayed() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let tab_id = state.active_tab;
+	let window_id = state.active_window_id();
+
+	submit_command_with_ports(&mut state, &ports, "drop Cargo.toml");
+
+	assert_eq!(state.active_tab, tab_id);
+	assert_eq!(state.active_window_id(), window_id);
+	assert_eq!(ports.file_loads.borrow().len(), 1);
+	assert_eq!(ports.file_loads.borrow()[0].1, normalize_test_path("Cargo.toml"));
+}
+
 #[test]
 fn open_requested_should_enqueue_file_load() {
 	let mut state = RimState::new();
@@ -942,6 +1311,7 @@ fn undo_history_loaded_should_restore_buffer_history_when_text_matches() {
 			}],
 			before_cursor: CursorState { row: 1, col: 2 },
 			after_cursor:  CursorState { row: 1, col: 3 },
+			changed_at:    None,
 		}],
 		redo_stack:   vec![BufferHistoryEntry {
 			edits:         vec![BufferEditSnapshot {
@@ -951,6 +1321,7 @@ fn undo_history_loaded_should_restore_buffer_history_when_text_matches() {
 			}],
 			before_cursor: CursorState { row: 1, col: 3 },
 			after_cursor:  CursorState { row: 1, col: 2 },
+			changed_at:    None,
 		}],
 	};
 
@@ -1021,6 +1392,7 @@ fn open_load_should_detect_swap_conflict_before_recover() {
 			buffer_id,
 			source: crate::action::FileLoadSource::Open,
 			result: Ok("base".to_string()),
+			mtime: None,
 		}),
 	);
 
@@ -1145,3 +1517,809 @@ fn swap_conflict_detected_without_conflict_should_initialize_swap_base() {
 	assert_eq!(init_text, "base-text");
 	assert!(!delete_existing);
 }
+
+fn press_ctrl_key(state: &mut RimState, ports: &FilePickerPorts, ch: char) {
+	let _ = state.apply_action(
+		ports,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::CONTROL))),
+	);
+}
+
+const WIDGET_TAGS: &str =
+	"!_TAG_FILE_FORMAT\t2\t/extended format/\nwidget\tCargo.toml\t/^fn widget() {}$/;\"\tf\n";
+
+#[test]
+fn tag_jump_should_open_tag_target_and_pop_back_with_ctrl_t() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let source_id = state.create_buffer(Some(normalize_test_path("src/lib.rs")), "call(widget);\n");
+	state.bind_buffer_to_active_window(source_id);
+	let target_id =
+		state.create_buffer(Some(normalize_test_path("Cargo.toml")), "[package]\n\nfn widget() {}\n");
+	for _ in 0..7 {
+		state.move_cursor_right();
+	}
+
+	press_ctrl_key(&mut state, &ports, ']');
+	let tags_path = state.workspace_root().join("tags");
+	assert_eq!(ports.tag_loads.borrow().as_slice(), &[(tags_path.clone(), "widget".to_string())]);
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::TagsLoaded {
+			tags_path,
+			tag: "widget".to_string(),
+			result: Ok(Some(WIDGET_TAGS.to_string())),
+		}),
+	);
+	assert_eq!(state.active_buffer_id(), Some(target_id));
+	assert_eq!(state.active_cursor(), CursorState { row: 3, col: 1 });
+
+	press_ctrl_key(&mut state, &ports, 't');
+	assert_eq!(state.active_buffer_id(), Some(source_id));
+	assert_eq!(state.active_cursor(), CursorState { row: 1, col: 8 });
+
+	press_ctrl_key(&mut state, &ports, 't');
+	assert_eq!(state.workbench.status_bar.message, "tag stack empty");
+}
+
+#[test]
+fn tag_jump_should_place_cursor_after_target_file_loads() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let source_id = state.create_buffer(Some(normalize_test_path("src/lib.rs")), "widget\n");
+	state.bind_buffer_to_active_window(source_id);
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::TagsLoaded {
+			tags_path: state.workspace_root().join("tags"),
+			tag:       "widget".to_string(),
+			result:    Ok(Some("widget\tCargo.toml\t3;\"\tf\n".to_string())),
+		}),
+	);
+	let target_id = state.active_buffer_id().expect("target buffer should be active");
+	assert_ne!(target_id, source_id);
+	assert_eq!(ports.file_loads.borrow().as_slice(), &[(target_id, normalize_test_path("Cargo.toml"))]);
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::LoadCompleted {
+			buffer_id: target_id,
+			source:    crate::action::FileLoadSource::Open,
+			result:    Ok("[package]\n\n    fn widget() {}\n".to_string()),
+			mtime:     None,
+		}),
+	);
+	assert_eq!(state.active_cursor(), CursorState { row: 3, col: 5 });
+	assert!(state.workbench.pending_tag_jump.is_none());
+}
+
+#[test]
+fn tag_jump_should_leave_tag_stack_alone_when_target_fails_to_open() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let source_id = state.create_buffer(Some(normalize_test_path("src/lib.rs")), "widget\n");
+	state.bind_buffer_to_active_window(source_id);
+	let tags_path = state.workspace_root().join("tags");
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::TagsLoaded {
+			tags_path: tags_path.clone(),
+			tag:       "widget".to_string(),
+			result:    Ok(Some("widget\tmissing-tag-target.rs\t3;\"\tf\n".to_string())),
+		}),
+	);
+	assert_eq!(state.active_buffer_id(), Some(source_id));
+	assert!(state.workbench.status_bar.message.starts_with("tag target not found: "));
+	assert!(state.workbench.tag_stack.is_empty());
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::TagsLoaded {
+			tags_path,
+			tag: "widget".to_string(),
+			result: Ok(Some("widget\tCargo.toml\t3;\"\tf\n".to_string())),
+		}),
+	);
+	let target_id = state.active_buffer_id().expect("target buffer should be active");
+	assert!(state.workbench.tag_stack.is_empty());
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::LoadCompleted {
+			buffer_id: target_id,
+			source:    crate::action::FileLoadSource::Open,
+			result:    Err(anyhow::anyhow!("disk on fire")),
+			mtime:     None,
+		}),
+	);
+	assert!(state.workbench.tag_stack.is_empty());
+	assert!(state.workbench.pending_tag_jump.is_none());
+}
+
+#[test]
+fn tag_jump_should_report_missing_tag_and_missing_tags_file() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let buffer_id = state.create_buffer(Some(normalize_test_path("src/lib.rs")), "gadget\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	let tags_path = state.workspace_root().join("tags");
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::TagsLoaded {
+			tags_path: tags_path.clone(),
+			tag:       "gadget".to_string(),
+			result:    Ok(Some(WIDGET_TAGS.to_string())),
+		}),
+	);
+	assert_eq!(state.workbench.status_bar.message, "tag not found: gadget");
+	assert!(state.workbench.tag_stack.is_empty());
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::TagsLoaded {
+			tags_path: tags_path.clone(),
+			tag:       "gadget".to_string(),
+			result:    Ok(None),
+		}),
+	);
+	assert_eq!(state.workbench.status_bar.message, format!("tags file not found: {}", tags_path.display()));
+}
+
+#[test]
+fn edit_glob_should_expand_on_worker_and_open_every_match() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	state.enter_command_mode();
+	for ch in "e src/*.rs".chars() {
+		state.push_command_char(ch);
+	}
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))),
+	);
+	assert_eq!(ports.glob_requests.borrow().as_slice(), &[(
+		state.workspace_root().to_path_buf(),
+		"src/*.rs".to_string()
+	)]);
+	assert!(ports.file_loads.borrow().is_empty());
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::GlobExpanded {
+			pattern: "src/*.rs".to_string(),
+			result:  Ok(vec![normalize_test_path("src/action.rs"), normalize_test_path("src/lib.rs")]),
+		}),
+	);
+
+	let loaded = ports.file_loads.borrow().iter().map(|(_, path)| path.clone()).collect::<Vec<_>>();
+	assert_eq!(loaded, vec![normalize_test_path("src/action.rs"), normalize_test_path("src/lib.rs")]);
+	let first_id =
+		state.find_buffer_by_path(normalize_test_path("src/action.rs").as_path()).expect("first match");
+	assert!(state.find_buffer_by_path(normalize_test_path("src/lib.rs").as_path()).is_some());
+	assert_eq!(state.active_buffer_id(), Some(first_id));
+	assert_eq!(state.workbench.status_bar.message, "opened 2 files");
+}
+
+#[test]
+fn edit_glob_without_matches_should_report_pattern() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::GlobExpanded { pattern: "src/*.zig".to_string(), result: Ok(Vec::new()) }),
+	);
+
+	assert!(ports.file_loads.borrow().is_empty());
+	assert_eq!(state.workbench.status_bar.message, "no files match src/*.zig");
+}
+
+fn submit_command_with_ports(state: &mut RimState, ports: &FilePickerPorts, input: &str) {
+	state.enter_command_mode();
+	for ch in input.chars() {
+		state.push_command_char(ch);
+	}
+	let _ = state.apply_action(
+		ports,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))),
+	);
+}
+
+#[test]
+fn cd_should_resolve_later_relative_edits_against_new_directory() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let workspace_root = state.workspace_root().to_path_buf();
+
+	submit_command_with_ports(&mut state, &ports, "cd src");
+	assert_eq!(state.working_directory(), normalize_test_path("src").as_path());
+	assert_eq!(state.workspace_root(), workspace_root.as_path());
+
+	submit_command_with_ports(&mut state, &ports, "pwd");
+	assert_eq!(state.workbench.status_bar.message, normalize_test_path("src").display().to_string());
+
+	submit_command_with_ports(&mut state, &ports, "e lib.rs");
+	assert_eq!(ports.file_loads.borrow().len(), 1);
+	assert_eq!(ports.file_loads.borrow()[0].1, normalize_test_path("src/lib.rs"));
+
+	submit_command_with_ports(&mut state, &ports, "cd");
+	assert_eq!(state.working_directory(), workspace_root.as_path());
+}
+
+#[test]
+fn cd_to_missing_directory_should_keep_working_directory() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let before = state.working_directory().to_path_buf();
+
+	submit_command_with_ports(&mut state, &ports, "cd no-such-dir");
+
+	assert_eq!(state.working_directory(), before.as_path());
+	let notification = state.workbench.notifications.last().expect("cd failure should notify");
+	assert!(notification.message.starts_with("cd failed:"));
+}
+
+fn set_active_window_cursor(state: &mut RimState, cursor: CursorState) {
+	let active_window_id = state.active_window_id();
+	state.windows.get_mut(active_window_id).expect("active window should exist").cursor = cursor;
+}
+
+#[test]
+fn mksession_should_refuse_to_overwrite_existing_file_without_bang() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let existing = normalize_test_path("Cargo.toml");
+
+	submit_command_with_ports(&mut state, &ports, "mksession Cargo.toml");
+	assert!(ports.session_saves.borrow().is_empty());
+	assert_eq!(
+		state.workbench.status_bar.message,
+		format!("mksession failed: {} exists (add ! to overwrite)", existing.display())
+	);
+
+	submit_command_with_ports(&mut state, &ports, "mksession! Cargo.toml");
+	assert_eq!(ports.session_saves.borrow().len(), 1);
+	assert_eq!(ports.session_saves.borrow()[0].0, existing);
+}
+
+#[test]
+fn mksession_and_source_should_rebuild_two_tab_layout_and_reopen_files() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let lib_path = normalize_test_path("src/lib.rs");
+	let cargo_path = normalize_test_path("Cargo.toml");
+	let lib_id = state.create_buffer(Some(lib_path.clone()), "fn a() {}\nfn b() {}\nfn c() {}\n");
+	state.bind_buffer_to_active_window(lib_id);
+	state.update_active_tab_layout(100, 20);
+	set_active_window_cursor(&mut state, CursorState { row: 2, col: 4 });
+	state.split_active_window(SplitAxis::Vertical);
+	set_active_window_cursor(&mut state, CursorState { row: 3, col: 1 });
+	state.open_new_tab();
+	let cargo_id = state.create_buffer(Some(cargo_path.clone()), "[package]\nname = \"rim\"\n");
+	state.bind_buffer_to_active_window(cargo_id);
+	set_active_window_cursor(&mut state, CursorState { row: 2, col: 3 });
+	let _scratch = state.create_buffer(None, "scratch");
+
+	submit_command_with_ports(&mut state, &ports, "mksession");
+	let (session_path, snapshot) = ports.session_saves.borrow()[0].clone();
+	assert_eq!(session_path, normalize_test_path("Session.json"));
+	assert_eq!(snapshot.buffers.len(), 2);
+	assert!(snapshot.buffers.iter().all(|buffer| buffer.path.is_some() && buffer.text.is_empty()));
+
+	let mut restored = RimState::new();
+	restored.create_untitled_buffer();
+	submit_command_with_ports(&mut restored, &ports, "source");
+	assert_eq!(ports.session_loads.borrow().as_slice(), std::slice::from_ref(&session_path));
+	let _ = restored.apply_action(
+		&ports,
+		AppAction::File(FileAction::SessionFileLoaded { path: session_path, result: Ok(Some(snapshot)) }),
+	);
+
+	assert_eq!(restored.tabs.len(), 2);
+	assert_eq!(restored.active_tab_window_ids().len(), 1);
+	let mut loaded_paths = ports.file_loads.borrow().iter().map(|(_, path)| path.clone()).collect::<Vec<_>>();
+	loaded_paths.sort();
+	let mut expected_paths = vec![lib_path.clone(), cargo_path.clone()];
+	expected_paths.sort();
+	assert_eq!(loaded_paths, expected_paths);
+	assert!(restored.buffers.values().all(|buffer| buffer.path.is_some()));
+
+	for (buffer_id, path) in ports.file_loads.borrow().clone() {
+		let text =
+			if path == lib_path { "fn a() {}\nfn b() {}\nfn c() {}\n" } else { "[package]\nname = \"rim\"\n" };
+		let _ = restored.apply_action(
+			&ports,
+			AppAction::File(FileAction::LoadCompleted {
+				buffer_id,
+				source: crate::action::FileLoadSource::Open,
+				result: Ok(text.to_string()),
+				mtime: None,
+			}),
+		);
+	}
+	let active_buffer = restored.active_buffer_id().expect("active buffer should exist");
+	assert_eq!(restored.buffers.get(active_buffer).and_then(|buffer| buffer.path.clone()), Some(cargo_path));
+	assert_eq!(restored.active_cursor(), CursorState { row: 2, col: 3 });
+
+	restored.switch_to_prev_tab();
+	let window_ids = restored.active_tab_window_ids();
+	assert_eq!(window_ids.len(), 2);
+	for window_id in &window_ids {
+		let buffer_id = restored.windows.get(*window_id).and_then(|window| window.buffer_id);
+		assert_eq!(
+			buffer_id.and_then(|id| restored.buffers.get(id)).and_then(|buffer| buffer.path.clone()),
+			Some(lib_path.clone())
+		);
+	}
+	assert_eq!(restored.active_cursor(), CursorState { row: 3, col: 1 });
+}
+
+#[test]
+fn persist_session_should_write_layout_on_quit_and_restore_it_on_next_start() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let lib_path = normalize_test_path("src/lib.rs");
+	let lib_id = state.create_buffer(Some(lib_path.clone()), "fn a() {}\nfn b() {}\n");
+	state.bind_buffer_to_active_window(lib_id);
+	set_active_window_cursor(&mut state, CursorState { row: 2, col: 4 });
+
+	let _ = state.apply_action(&ports, AppAction::System(SystemAction::Quit));
+	assert!(ports.session_saves.borrow().is_empty());
+
+	submit_command_with_ports(&mut state, &ports, "set persistsession");
+	let flow = state.apply_action(&ports, AppAction::System(SystemAction::Quit));
+	assert!(matches!(flow, ControlFlow::Break(())));
+	let (session_path, snapshot) = ports.session_saves.borrow()[0].clone();
+	assert_eq!(session_path, normalize_test_path("Session.json"));
+	assert_eq!(state.persisted_session_path(), Some(session_path.clone()));
+
+	let mut restored = RimState::new();
+	assert_eq!(restored.persisted_session_path(), None);
+	restored.workbench.persist_session = true;
+	assert_eq!(restored.persisted_session_path(), Some(session_path.clone()));
+	let _ = restored.apply_action(
+		&ports,
+		AppAction::File(FileAction::SessionFileLoaded { path: session_path, result: Ok(Some(snapshot)) }),
+	);
+	let (buffer_id, path) = ports.file_loads.borrow()[0].clone();
+	assert_eq!(path, lib_path);
+	let _ = restored.apply_action(
+		&ports,
+		AppAction::File(FileAction::LoadCompleted {
+			buffer_id,
+			source: crate::action::FileLoadSource::Open,
+			result: Ok("fn a() {}\nfn b() {}\n".to_string()),
+			mtime: None,
+		}),
+	);
+	assert_eq!(restored.active_buffer_id(), Some(buffer_id));
+	assert_eq!(restored.active_cursor(), CursorState { row: 2, col: 4 });
+}
+
+#[test]
+fn source_with_unsaved_changes_should_not_load_session_file() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let buffer_id = state.create_untitled_buffer();
+	state.set_buffer_dirty(buffer_id, true);
+
+	submit_command_with_ports(&mut state, &ports, "source layout.json");
+
+	assert!(ports.session_loads.borrow().is_empty());
+	assert_eq!(state.workbench.status_bar.message, "source blocked: unsaved changes");
+}
+
+#[test]
+fn language_server_should_track_open_edit_and_close_of_matching_buffers() {
+	let mut state = RimState::new();
+	let ports = RecordingPorts::default();
+	state.workbench.language_servers = vec![LanguageServerConfig {
+		filetypes: vec!["rust".to_string()],
+		command:   vec!["rust-analyzer".to_string()],
+	}];
+	let rust_buffer = state.create_buffer(Some(PathBuf::from("/tmp/rim-lsp/main.rs")), "");
+	let text_buffer = state.create_buffer(Some(PathBuf::from("/tmp/rim-lsp/notes.txt")), "");
+	state.bind_buffer_to_active_window(rust_buffer);
+	for buffer_id in [rust_buffer, text_buffer] {
+		let _ = state.apply_action(
+			&ports,
+			AppAction::File(FileAction::LoadCompleted {
+				buffer_id,
+				source: crate::action::FileLoadSource::Open,
+				result: Ok("fn main() {}\n".to_string()),
+				mtime: None,
+			}),
+		);
+	}
+
+	let opens = ports.lsp_opens.borrow().clone();
+	assert_eq!(opens.len(), 1);
+	assert_eq!(opens[0].0, rust_buffer);
+	assert_eq!(opens[0].1.language_id, "rust");
+	assert_eq!(opens[0].1.server_command, vec!["rust-analyzer".to_string()]);
+	assert_eq!(opens[0].1.text, "fn main() {}\n");
+
+	for ch in ['i', 'x'] {
+		let _ = state.apply_action(
+			&ports,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE))),
+		);
+	}
+	assert!(ports.lsp_changes.borrow().is_empty());
+	crate::action_handler::lsp_flow::sync_language_documents(
+		&ports,
+		&mut state,
+		Instant::now() + Duration::from_secs(1),
+	);
+	assert_eq!(ports.lsp_changes.borrow().clone(), vec![(rust_buffer, "xfn main() {}\n".to_string())]);
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::Lsp(LspAction::DiagnosticsPublished {
+			buffer_id:   rust_buffer,
+			diagnostics: vec![BufferDiagnostic {
+				start_row: 0,
+				start_col: 0,
+				end_row:   0,
+				end_col:   1,
+				severity:  DiagnosticSeverity::Warning,
+				message:   "unused".to_string(),
+			}],
+		}),
+	);
+	assert_eq!(state.buffer_diagnostic_signs(rust_buffer, 0..2), vec![Some(DiagnosticSeverity::Warning), None]);
+
+	state.close_buffer(rust_buffer);
+	let _ = state.apply_action(&ports, AppAction::System(SystemAction::Tick));
+	assert_eq!(ports.lsp_closes.borrow().clone(), vec![rust_buffer]);
+	assert!(state.workbench.diagnostics.is_empty());
+}
+
+#[test]
+fn definition_request_should_send_pending_edits_first() {
+	let mut state = RimState::new();
+	let ports = RecordingPorts::default();
+	let buffer_id =
+		open_tracked_rust_buffer(&mut state, &ports, PathBuf::from("/tmp/rim-lsp/main.rs"), "widget();\n");
+	press_keys(&mut state, &ports, "ix");
+	let _ = state.apply_action(
+		&ports,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))),
+	);
+	assert!(ports.lsp_changes.borrow().is_empty());
+
+	press_keys(&mut state, &ports, "gd");
+
+	assert_eq!(ports.lsp_changes.borrow().clone(), vec![(buffer_id, "xwidget();\n".to_string())]);
+	assert_eq!(ports.lsp_definitions.borrow().len(), 1);
+}
+
+fn open_tracked_rust_buffer(
+	state: &mut RimState,
+	ports: &RecordingPorts,
+	path: PathBuf,
+	text: &str,
+) -> BufferId {
+	state.workbench.language_servers = vec![LanguageServerConfig {
+		filetypes: vec!["rust".to_string()],
+		command:   vec!["rust-analyzer".to_string()],
+	}];
+	let buffer_id = state.create_buffer(Some(path), "");
+	state.bind_buffer_to_active_window(buffer_id);
+	let _ = state.apply_action(
+		ports,
+		AppAction::File(FileAction::LoadCompleted {
+			buffer_id,
+			source: crate::action::FileLoadSource::Open,
+			result: Ok(text.to_string()),
+			mtime: None,
+		}),
+	);
+	buffer_id
+}
+
+fn press_keys(state: &mut RimState, ports: &RecordingPorts, keys: &str) {
+	for ch in keys.chars() {
+		let _ = state.apply_action(
+			ports,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE))),
+		);
+	}
+}
+
+#[test]
+fn definition_and_hover_should_send_cursor_position_of_tracked_buffer() {
+	let mut state = RimState::new();
+	let ports = RecordingPorts::default();
+	let untracked = state.create_buffer(Some(PathBuf::from("/tmp/rim-lsp/notes.txt")), "widget\n");
+	state.bind_buffer_to_active_window(untracked);
+	press_keys(&mut state, &ports, "gd");
+	assert!(ports.lsp_definitions.borrow().is_empty());
+	assert_eq!(state.workbench.status_bar.message, "no language server for this buffer");
+
+	let buffer_id = open_tracked_rust_buffer(
+		&mut state,
+		&ports,
+		PathBuf::from("/tmp/rim-lsp/main.rs"),
+		"fn main() {\n    widget();\n}\n",
+	);
+	press_keys(&mut state, &ports, "jwgd");
+	press_keys(&mut state, &ports, "K");
+
+	let position = LanguagePosition { row: 1, col: 4 };
+	assert_eq!(ports.lsp_definitions.borrow().as_slice(), &[(buffer_id, position)]);
+	assert_eq!(ports.lsp_hovers.borrow().as_slice(), &[(buffer_id, position)]);
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::Lsp(LspAction::HoverReceived { contents: "fn widget()\n\nDraws a widget.".to_string() }),
+	);
+	let Some(OverlayState::FloatingWindow(window)) = state.workbench.overlay.as_ref() else {
+		panic!("hover should open a floating window");
+	};
+	assert_eq!(window.title, "Hover");
+	assert_eq!(window.lines.iter().map(|line| line.summary.as_str()).collect::<Vec<_>>(), vec![
+		"fn widget()",
+		"",
+		"Draws a widget."
+	]);
+}
+
+#[test]
+fn definition_response_should_open_target_file_at_location_and_pop_back_with_ctrl_t() {
+	let dir = std::env::temp_dir().join(format!("rim-lsp-definition-{}", std::process::id()));
+	std::fs::create_dir_all(&dir).expect("temp dir should be created");
+	let target_path = dir.join("widget.rs");
+	std::fs::write(&target_path, "\n\npub fn widget() {}\n").expect("target file should be written");
+	let mut state = RimState::new();
+	let ports = RecordingPorts::default();
+	let source_id =
+		open_tracked_rust_buffer(&mut state, &ports, dir.join("main.rs"), "fn main() { widget(); }\n");
+	press_keys(&mut state, &ports, "wwwwgd");
+	let origin = state.active_cursor();
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::Lsp(LspAction::DefinitionFound {
+			locations: vec![DefinitionLocation { path: target_path.clone(), row: 2, col: 7 }],
+		}),
+	);
+	let target_id = state.active_buffer_id().expect("target buffer should be active");
+	assert_ne!(target_id, source_id);
+	assert_eq!(ports.file_loads.borrow().last().cloned(), Some((target_id, target_path)));
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::LoadCompleted {
+			buffer_id: target_id,
+			source:    crate::action::FileLoadSource::Open,
+			result:    Ok("\n\npub fn widget() {}\n".to_string()),
+			mtime:     None,
+		}),
+	);
+	assert_eq!(state.active_cursor(), CursorState { row: 3, col: 8 });
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL))),
+	);
+	assert_eq!(state.active_buffer_id(), Some(source_id));
+	assert_eq!(state.active_cursor(), origin);
+	let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn multiple_definitions_should_be_listed_and_picked_by_count() {
+	let mut state = RimState::new();
+	let ports = RecordingPorts::default();
+	let source_id =
+		open_tracked_rust_buffer(&mut state, &ports, normalize_test_path("src/main.rs"), "widget\n");
+	let target_id = state.create_buffer(Some(normalize_test_path("src/lib.rs")), "one\ntwo\nthree\n");
+	let locations = vec![
+		DefinitionLocation { path: normalize_test_path("src/lib.rs"), row: 0, col: 0 },
+		DefinitionLocation { path: normalize_test_path("src/lib.rs"), row: 2, col: 1 },
+	];
+
+	press_keys(&mut state, &ports, "gd");
+	let _ =
+		state.apply_action(&ports, AppAction::Lsp(LspAction::DefinitionFound { locations: locations.clone() }));
+	let Some(OverlayState::FloatingWindow(window)) = state.workbench.overlay.as_ref() else {
+		panic!("several definitions should open a floating window");
+	};
+	assert_eq!(window.title, "Definitions (2)");
+	assert_eq!(window.lines.iter().map(|line| line.summary.as_str()).collect::<Vec<_>>(), vec![
+		"src/lib.rs:1:1",
+		"src/lib.rs:3:2"
+	]);
+	assert_eq!(state.active_buffer_id(), Some(source_id));
+
+	press_keys(&mut state, &ports, "5gd");
+	let _ =
+		state.apply_action(&ports, AppAction::Lsp(LspAction::DefinitionFound { locations: locations.clone() }));
+	assert_eq!(state.workbench.status_bar.message, "only 2 definitions");
+	assert_eq!(state.active_buffer_id(), Some(source_id));
+
+	press_keys(&mut state, &ports, "2gd");
+	let _ = state.apply_action(&ports, AppAction::Lsp(LspAction::DefinitionFound { locations }));
+	assert_eq!(state.active_buffer_id(), Some(target_id));
+	assert_eq!(state.active_cursor(), CursorState { row: 3, col: 2 });
+}
+
+#[test]
+fn reopen_should_restore_last_closed_file_at_its_cursor_and_skip_untitled_buffers() {
+	let mut state = RimState::new();
+	let ports = FilePickerPorts::default();
+	let other_id = state.create_buffer(Some(normalize_test_path("src/lib.rs")), "lib\n");
+	state.bind_buffer_to_active_window(other_id);
+	let manifest_path = normalize_test_path("Cargo.toml");
+	let manifest_id = state.create_buffer(Some(manifest_path.clone()), "[package]\nname = \"rim\"\n");
+	state.bind_buffer_to_active_window(manifest_id);
+	state.move_cursor_down();
+	state.move_cursor_right();
+	state.move_cursor_right();
+
+	let _ = state.apply_action(&ports, AppAction::Editor(EditorAction::CloseActiveBuffer));
+	let untitled_id = state.create_untitled_buffer();
+	state.bind_buffer_to_active_window(untitled_id);
+	let _ = state.apply_action(&ports, AppAction::Editor(EditorAction::CloseActiveBuffer));
+	assert!(state.find_buffer_by_path(manifest_path.as_path()).is_none());
+
+	submit_command_with_ports(&mut state, &ports, "reopen");
+	let reopened_id = state.active_buffer_id().expect("reopened buffer should be active");
+	assert_eq!(
+		state.buffers.get(reopened_id).and_then(|buffer| buffer.path.clone()),
+		Some(manifest_path.clone())
+	);
+	assert_eq!(ports.file_loads.borrow().last(), Some(&(reopened_id, manifest_path)));
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::LoadCompleted {
+			buffer_id: reopened_id,
+			source:    crate::action::FileLoadSource::Open,
+			result:    Ok("[package]\nname = \"rim\"\n".to_string()),
+			mtime:     None,
+		}),
+	);
+	assert_eq!(state.active_cursor(), CursorState { row: 2, col: 3 });
+
+	submit_command_with_ports(&mut state, &ports, "reopen");
+	assert_eq!(state.workbench.status_bar.message, "no closed buffer to reopen");
+}
+
+#[test]
+fn startup_commands_should_wait_for_the_active_file_to_load() {
+	let mut state = RimState::new();
+	state.create_untitled_buffer();
+	let ports = RecordingPorts::default();
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::OpenRequested { path: normalize_test_path("Cargo.toml") }),
+	);
+	let buffer_id = state.active_buffer_id().expect("active buffer should exist");
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::StartupCommandsRequested {
+			commands: vec!["/needle".to_string(), "set list".to_string()],
+		}),
+	);
+	assert!(!state.workbench.list_mode);
+	assert!(state.has_pending_startup_commands(buffer_id));
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::LoadCompleted {
+			buffer_id,
+			source: crate::action::FileLoadSource::Open,
+			result: Ok("hay\nhay needle".to_string()),
+			mtime: None,
+		}),
+	);
+	assert!(state.workbench.list_mode);
+	assert_eq!(state.active_cursor(), CursorState { row: 2, col: 5 });
+	assert!(!state.is_command_mode());
+	assert!(!state.has_pending_startup_commands(buffer_id));
+}
+
+#[test]
+fn startup_commands_should_run_at_once_without_a_pending_load() {
+	let mut state = RimState::new();
+	state.create_untitled_buffer();
+
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::File(FileAction::StartupCommandsRequested { commands: vec!["set list".to_string()] }),
+	);
+	assert!(state.workbench.list_mode);
+	assert!(!state.is_command_mode());
+}
+
+#[test]
+fn startup_command_edits_should_be_undoable() {
+	let mut state = RimState::new();
+	let ports = RecordingPorts::default();
+	let buffer_id = state.create_buffer(None, "hay");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::StartupCommandsRequested { commands: vec!["s/hay/straw/".to_string()] }),
+	);
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").text.to_string(), "straw");
+
+	press_keys(&mut state, &ports, "u");
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").text.to_string(), "hay");
+}
+
+#[test]
+fn closing_a_buffer_mid_load_should_forget_the_load() {
+	let mut state = RimState::new();
+	state.create_untitled_buffer();
+	let ports = RecordingPorts::default();
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::OpenRequested { path: normalize_test_path("Cargo.toml") }),
+	);
+	let buffer_id = state.active_buffer_id().expect("active buffer should exist");
+	assert!(state.workbench.loading_buffers.contains(&buffer_id));
+
+	state.close_buffer(buffer_id);
+	assert!(!state.workbench.loading_buffers.contains(&buffer_id));
+}
+
+#[test]
+fn opened_files_should_take_the_indentation_they_use() {
+	let mut state = RimState::new();
+	state.create_untitled_buffer();
+	let ports = RecordingPorts::default();
+	let open = |state: &mut RimState, path: &str, text: &str| {
+		let _ = state
+			.apply_action(&ports, AppAction::File(FileAction::OpenRequested { path: normalize_test_path(path) }));
+		let buffer_id = state.active_buffer_id().expect("active buffer should exist");
+		let _ = state.apply_action(
+			&ports,
+			AppAction::File(FileAction::LoadCompleted {
+				buffer_id,
+				source: crate::action::FileLoadSource::Open,
+				result: Ok(text.to_string()),
+				mtime: None,
+			}),
+		);
+	};
+
+	open(&mut state, "notes.txt", "list:\n  - one\n    - one.a\n  - two\n");
+	assert!(state.active_indent_settings().expand_tab);
+	assert_eq!(state.active_indent_settings().shift_width, 2);
+
+	open(&mut state, "tabbed.txt", "[table]\n\tkey = 1\n\tother = 2\n");
+	assert!(!state.active_indent_settings().expand_tab);
+
+	// `[indent.rust]` in editor.toml wins over what the file happens to use.
+	open(&mut state, "main.rs", "fn a() {\n  b();\n  c();\n}\n");
+	assert_eq!(state.active_indent_settings().shift_width, 4);
+
+	// Reloading with `:e!` keeps what the buffer was already given.
+	open(&mut state, "spaces.txt", "a:\n  b:\n    c: 1\n");
+	assert_eq!(state.apply_set_option("shiftwidth=3"), Ok("shiftwidth=3".to_string()));
+	let buffer_id = state.active_buffer_id().expect("active buffer should exist");
+	let _ = state.apply_action(
+		&ports,
+		AppAction::File(FileAction::LoadCompleted {
+			buffer_id,
+			source: crate::action::FileLoadSource::Open,
+			result: Ok("a:\n  b:\n    c: 1\n".to_string()),
+			mtime: None,
+		}),
+	);
+	assert_eq!(state.active_indent_settings().shift_width, 3);
+
+	assert_eq!(state.apply_set_option("nodetectindent"), Ok("nodetectindent".to_string()));
+	open(&mut state, "plain.txt", "a:\n  b:\n    c: 1\n");
+	assert!(!state.active_indent_settings().expand_tab);
+	assert_eq!(state.active_indent_settings().shift_width, 3);
+}
@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use rim_domain::display_geometry::display_width_of_char_prefix_with_virtual as geom_display_width_of_char_prefix_with_virtual;
 
 use super::{super::mode_flow::SequenceMatch, support::{FilePickerPorts, RecordingPorts, dispatch_test_action, map_normal_key, resolve_keys}};
-use crate::{action::{AppAction, BufferAction, EditorAction, KeyCode, KeyEvent, KeyModifiers, LayoutAction, TabAction}, command::{BuiltinCommand, CommandAliasConfig, CommandAliasSection, CommandArgKind, CommandConfigFile, CommandKeymapSection, CommandTarget, KeyBindingOn, KeymapBindingConfig, PluginCommandRegistration, ViewCommand}, state::{FloatingWindowPlacement, NormalSequenceKey, RimState, WorkspaceFileEntry}};
+use crate::{action::{AppAction, BufferAction, EditorAction, KeyCode, KeyEvent, KeyModifiers, LayoutAction, SystemAction, TabAction}, command::{BuiltinCommand, CommandAliasConfig, CommandAliasSection, CommandArgKind, CommandConfigFile, CommandKeymapSection, CommandTarget, HostCommandRegistration, KeyBindingOn, KeymapBindingConfig, PluginCommandRegistration, ViewCommand}, state::{CursorState, FloatingWindowPlacement, FocusDirection, NormalSequenceKey, OverlayState, RimState, SplitAxis, WorkspaceFileEntry}};
 
 fn register_pick_plugin_command(state: &mut RimState) {
 	state
@@ -507,6 +507,47 @@ fn visual_block_append_should_pad_short_lines_when_block_is_beyond_text() {
 	assert_eq!(buffer.text.to_string(), "abcdeXf\nx    X\nzzz  X");
 }
 
+#[test]
+fn viewport_resize_should_relayout_splits_immediately() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abc");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(80, 20);
+	let _ = dispatch_test_action(&mut state, AppAction::Layout(LayoutAction::SplitHorizontal));
+
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Layout(LayoutAction::ViewportResized { width: 120, height: 42 }),
+	);
+
+	let tab = state.tabs.get(&state.active_tab).expect("active tab exists");
+	assert_eq!(tab.windows.len(), 2);
+	for window_id in &tab.windows {
+		let window = state.windows.get(*window_id).expect("window exists");
+		assert_eq!(window.width, 60);
+		assert_eq!(window.height, 40);
+	}
+}
+
+#[test]
+fn close_command_should_close_split_but_keep_last_window() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abc");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(80, 20);
+	let _ = dispatch_test_action(&mut state, AppAction::Layout(LayoutAction::SplitVertical));
+	assert_eq!(state.active_tab_window_ids().len(), 2);
+
+	submit_command_line(&mut state, "close");
+	assert_eq!(state.active_tab_window_ids().len(), 1);
+	assert_eq!(state.workbench.status_bar.message, "window closed");
+
+	submit_command_line(&mut state, "clo");
+	assert_eq!(state.active_tab_window_ids().len(), 1);
+	assert_eq!(state.tabs.len(), 1);
+	assert_eq!(state.workbench.status_bar.message, "close blocked: cannot close last window");
+}
+
 #[test]
 fn visual_block_right_move_should_not_be_clamped_by_layout_tick() {
 	let mut state = RimState::new();
@@ -747,6 +788,31 @@ fn visual_block_delete_should_be_undoable_with_single_u() {
 	assert_eq!(buffer.text.to_string(), "abcd\nefgh\nijkl");
 }
 
+#[test]
+fn block_yank_paste_should_insert_rectangle_at_target_column() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abcd\nefgh\nij\nk\n\tuv\n\twx");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+
+	press_normal_keys(&mut state, "l");
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char('v'), KeyModifiers::CONTROL))),
+	);
+	press_normal_keys(&mut state, "ljy");
+	assert_eq!(state.line_slot.as_deref(), Some("bc\nfg"));
+
+	// The second row is shorter than the target column and gets padded.
+	press_normal_keys(&mut state, "jj0lp");
+	assert_eq!(text(&state), "abcd\nefgh\nijbc\nk fg\n\tuv\n\twx");
+
+	// Tabs are measured by display width, and text right of the block stays
+	// aligned.
+	press_normal_keys(&mut state, "jj0lp");
+	assert_eq!(text(&state), "abcd\nefgh\nijbc\nk fg\n\tubcv\n\twfgx");
+}
+
 #[test]
 fn insert_typing_should_be_grouped_into_single_undo_step() {
 	let mut state = RimState::new();
@@ -925,6 +991,145 @@ fn append_insert_at_line_end_should_work_with_word_wrap_enabled() {
 	assert_eq!(buffer.text.to_string(), "abcdefghijklmnopqrstuvwxyzx");
 }
 
+#[test]
+fn insert_ctrl_n_should_complete_unique_buffer_word_in_single_undo_step() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "alphabet");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	for key in [
+		KeyEvent::new(KeyCode::Char('o'), KeyModifiers::NONE),
+		KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE),
+		KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE),
+		KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL),
+	] {
+		let _ = dispatch_test_action(&mut state, AppAction::Editor(EditorAction::KeyPressed(key)));
+	}
+
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert_eq!(buffer.text.to_string(), "alphabet\nalphabet");
+	assert_eq!(state.active_cursor().col, 9);
+
+	for key in
+		[KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE), KeyEvent::new(KeyCode::Char('u'), KeyModifiers::NONE)]
+	{
+		let _ = dispatch_test_action(&mut state, AppAction::Editor(EditorAction::KeyPressed(key)));
+	}
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert_eq!(buffer.text.to_string(), "alphabet");
+}
+
+#[test]
+fn insert_completion_should_cycle_through_candidates_and_back_to_prefix() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "foo fob");
+	let _ = state.create_buffer(None, "fox");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	for key in [
+		KeyEvent::new(KeyCode::Char('o'), KeyModifiers::NONE),
+		KeyEvent::new(KeyCode::Char('f'), KeyModifiers::NONE),
+		KeyEvent::new(KeyCode::Char('o'), KeyModifiers::NONE),
+	] {
+		let _ = dispatch_test_action(&mut state, AppAction::Editor(EditorAction::KeyPressed(key)));
+	}
+
+	let mut lines = Vec::new();
+	for code in ['n', 'n', 'n', 'n', 'p'] {
+		let _ = dispatch_test_action(
+			&mut state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char(code), KeyModifiers::CONTROL))),
+		);
+		let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+		lines.push(buffer.text.line(1).to_string());
+	}
+
+	assert_eq!(lines, vec!["foo", "fob", "fox", "fo", "fox"]);
+	assert_eq!(state.active_cursor().col, 4);
+}
+
+fn apply_emacs_insert_line_bindings(state: &mut RimState) {
+	let errors = state.apply_command_config(&CommandConfigFile {
+		mode: crate::command::ModeKeymapSections {
+			insert: CommandKeymapSection {
+				keymap: vec![
+					KeymapBindingConfig {
+						on:   KeyBindingOn::single("<C-a>"),
+						run:  "core.insert.line_start".into(),
+						args: Vec::new(),
+						desc: None,
+					},
+					KeymapBindingConfig {
+						on:   KeyBindingOn::single("<C-e>"),
+						run:  "core.insert.line_end".into(),
+						args: Vec::new(),
+						desc: None,
+					},
+				],
+			},
+			..crate::command::ModeKeymapSections::default()
+		},
+		..CommandConfigFile::default()
+	});
+	assert!(errors.is_empty());
+}
+
+#[test]
+fn insert_ctrl_a_and_ctrl_e_should_move_within_line_when_configured() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "hello world");
+	state.bind_buffer_to_active_window(buffer_id);
+	apply_emacs_insert_line_bindings(&mut state);
+	for _ in 0..5 {
+		let _ = dispatch_test_action(
+			&mut state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE))),
+		);
+	}
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE))),
+	);
+	assert_eq!(state.active_cursor().col, 6);
+
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL))),
+	);
+	assert!(state.is_insert_mode());
+	assert_eq!(state.active_cursor().col, 12);
+
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL))),
+	);
+	assert!(state.is_insert_mode());
+	assert_eq!(state.active_cursor().col, 1);
+
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert_eq!(buffer.text.to_string(), "hello world");
+	assert!(!buffer.dirty);
+}
+
+#[test]
+fn insert_ctrl_a_and_ctrl_e_should_be_ignored_without_opt_in() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "hello");
+	state.bind_buffer_to_active_window(buffer_id);
+	for key in [
+		KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE),
+		KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE),
+		KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL),
+		KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL),
+	] {
+		let _ = dispatch_test_action(&mut state, AppAction::Editor(EditorAction::KeyPressed(key)));
+	}
+
+	assert_eq!(state.active_cursor().col, 2);
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert_eq!(buffer.text.to_string(), "hello");
+}
+
 #[test]
 fn f1_should_open_current_mode_key_hint_overview() {
 	let mut state = RimState::new();
@@ -950,9 +1155,11 @@ fn pending_multi_key_sequence_should_refresh_key_hint_popup() {
 	);
 	let floating = state.floating_window().expect("pending g should open hints");
 	assert!(floating.title.ends_with("g"));
-	assert_eq!(floating.lines.len(), 1);
-	assert_eq!(floating.lines[0].key, "g");
-	assert_eq!(floating.lines[0].summary, "Move to file start");
+	assert_eq!(floating.lines.len(), 4);
+	assert!(floating.lines.iter().any(|line| line.key == "g" && line.summary == "Move to file start"));
+	assert!(
+		floating.lines.iter().any(|line| line.key == "d" && line.summary == "Jump to definition under cursor")
+	);
 
 	let _ = dispatch_test_action(
 		&mut state,
@@ -1558,6 +1765,73 @@ fn command_mode_tab_completion_should_use_plugin_command_id() {
 	assert_eq!(state.workbench.command_line, "plugin.demo.echo");
 }
 
+fn set_greeting_message(state: &mut RimState, _params: &crate::command::ResolvedParams) {
+	state.workbench.status_bar.message = "hello from host".to_string();
+}
+
+fn host_hello_registration() -> HostCommandRegistration {
+	HostCommandRegistration {
+		id:           "host.demo.hello".to_string(),
+		default_name: "Hello".to_string(),
+		category:     "Demo".to_string(),
+		description:  "Say hello from the host".to_string(),
+		params:       Vec::new(),
+	}
+}
+
+fn submit_command_line(state: &mut RimState, input: &str) {
+	state.enter_command_mode();
+	for ch in input.chars() {
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE))),
+		);
+	}
+	let _ = dispatch_test_action(
+		state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))),
+	);
+}
+
+#[test]
+fn command_mode_should_invoke_registered_host_command_by_name_and_id() {
+	let mut state = RimState::new();
+	state
+		.register_host_command(host_hello_registration(), set_greeting_message)
+		.expect("host command should register");
+
+	submit_command_line(&mut state, "Hello");
+	assert_eq!(state.workbench.status_bar.message, "hello from host");
+
+	state.workbench.status_bar.message.clear();
+	submit_command_line(&mut state, "host.demo.hello");
+	assert_eq!(state.workbench.status_bar.message, "hello from host");
+}
+
+#[test]
+fn host_command_registration_should_reject_duplicate_ids() {
+	let mut state = RimState::new();
+	state
+		.register_host_command(host_hello_registration(), set_greeting_message)
+		.expect("host command should register");
+
+	let err = state
+		.register_host_command(host_hello_registration(), set_greeting_message)
+		.expect_err("duplicate id should fail");
+	assert!(err.contains("duplicate command id"));
+}
+
+#[test]
+fn command_mode_should_report_unknown_command() {
+	let mut state = RimState::new();
+
+	submit_command_line(&mut state, "nosuchcommand");
+
+	assert!(
+		state.workbench.notifications.iter().any(|entry| entry.message == "unknown command: nosuchcommand")
+	);
+}
+
 #[test]
 fn command_mode_should_switch_palette_to_workspace_file_picker_for_path_params() {
 	let workspace_root = PathBuf::from("/workspace");
@@ -2026,3 +2300,1638 @@ fn workspace_file_picker_should_restore_selected_file_after_transient_removal()
 		PathBuf::from("/workspace/src/lib.rs").as_path()
 	);
 }
+
+fn press_normal_keys(state: &mut RimState, keys: &str) {
+	for ch in keys.chars() {
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE))),
+		);
+	}
+}
+
+#[test]
+fn change_hunk_jumps_should_visit_changed_regions_in_order_and_wrap() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "a\nb\nc\nd\ne\nf");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.move_cursor_down();
+	state.insert_char_at_cursor('x');
+	state.move_cursor_down();
+	state.move_cursor_down();
+	state.move_cursor_down();
+	state.insert_char_at_cursor('y');
+	state.move_cursor_file_start();
+
+	press_normal_keys(&mut state, "]c");
+	assert_eq!(state.active_cursor().row, 2);
+	press_normal_keys(&mut state, "]c");
+	assert_eq!(state.active_cursor().row, 5);
+	press_normal_keys(&mut state, "]c");
+	assert_eq!(state.active_cursor().row, 2);
+	assert_eq!(state.workbench.status_bar.message, "change jump wrapped to top");
+
+	press_normal_keys(&mut state, "[c");
+	assert_eq!(state.active_cursor().row, 5);
+	assert_eq!(state.workbench.status_bar.message, "change jump wrapped to bottom");
+	press_normal_keys(&mut state, "[c");
+	assert_eq!(state.active_cursor().row, 2);
+}
+
+#[test]
+fn changelist_jumps_should_visit_edits_newest_first_and_stop_at_the_ends() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "one\ntwo\nthree\nfour\nfive\nsix");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "g;");
+	assert_eq!(state.workbench.status_bar.message, "changelist is empty");
+
+	press_normal_keys(&mut state, "jllx");
+	press_normal_keys(&mut state, "jjjxx");
+	press_normal_keys(&mut state, "G0");
+	press_normal_keys(&mut state, "g;");
+	assert_eq!(state.active_cursor(), CursorState { row: 5, col: 2 });
+	press_normal_keys(&mut state, "g;");
+	assert_eq!(state.active_cursor(), CursorState { row: 2, col: 2 });
+	press_normal_keys(&mut state, "g;");
+	assert_eq!(state.active_cursor(), CursorState { row: 2, col: 2 });
+	assert_eq!(state.workbench.status_bar.message, "at start of changelist");
+
+	press_normal_keys(&mut state, "g,");
+	assert_eq!(state.active_cursor(), CursorState { row: 5, col: 2 });
+	press_normal_keys(&mut state, "g,");
+	assert_eq!(state.workbench.status_bar.message, "at end of changelist");
+
+	press_normal_keys(&mut state, "dd2g;");
+	assert_eq!(state.active_cursor(), CursorState { row: 2, col: 2 });
+}
+
+#[test]
+fn changelist_positions_should_follow_lines_added_or_removed_above_them() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "one\ntwo\nthree\nfour");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "jjjx");
+	press_normal_keys(&mut state, "ggOzero");
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))),
+	);
+	press_normal_keys(&mut state, "2g;");
+	assert_eq!(state.active_cursor().row, 5);
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("zero\none\ntwo\nthree\nour"));
+
+	press_normal_keys(&mut state, "ggjdd");
+	press_normal_keys(&mut state, "G0");
+	press_normal_keys(&mut state, "3g;");
+	assert_eq!(state.active_cursor().row, 4);
+}
+
+#[test]
+fn reopening_a_buffer_should_drop_changelist_positions_into_the_old_text() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "one\ntwo\nthree");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "jjx");
+	assert!(!state.buffers.get(buffer_id).expect("buffer exists").change_list.is_empty());
+	state.prepare_buffer_for_open(buffer_id, std::env::temp_dir().join("rim-reopened.txt"));
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert!(buffer.change_list.is_empty());
+	assert_eq!(buffer.change_list_index, 0);
+}
+
+#[test]
+fn change_hunk_jump_should_report_clean_buffer() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "a\nb");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "]c");
+
+	assert_eq!(state.active_cursor().row, 1);
+	assert_eq!(state.workbench.status_bar.message, "no changes since last save");
+}
+
+#[test]
+fn spell_jumps_should_visit_flagged_words_and_honor_good_words() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "the quikc fox\nall good here\nsome wrods");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.apply_set_option("spell").expect("spell should be a known option");
+
+	press_normal_keys(&mut state, "]s");
+	assert_eq!((state.active_cursor().row, state.active_cursor().col), (1, 5));
+	press_normal_keys(&mut state, "]s");
+	assert_eq!((state.active_cursor().row, state.active_cursor().col), (3, 6));
+	press_normal_keys(&mut state, "[s");
+	assert_eq!((state.active_cursor().row, state.active_cursor().col), (1, 5));
+
+	press_normal_keys(&mut state, "zg]s");
+	assert_eq!((state.active_cursor().row, state.active_cursor().col), (3, 6));
+	press_normal_keys(&mut state, "]s");
+	assert_eq!((state.active_cursor().row, state.active_cursor().col), (3, 6));
+	assert_eq!(state.workbench.status_bar.message, "spell jump wrapped to top");
+
+	press_normal_keys(&mut state, "1z=");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("the quikc fox\nall good here\nsome words"));
+}
+
+#[test]
+fn set_leader_should_move_leader_bindings_to_new_key() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abc");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(120, 40);
+
+	submit_command_line(&mut state, "set leader=,");
+	assert_eq!(state.workbench.leader_key, ',');
+	assert_eq!(state.workbench.status_bar.message, "leader=,");
+
+	press_normal_keys(&mut state, " wv");
+	assert_eq!(state.active_tab_window_ids().len(), 1);
+
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))),
+	);
+	press_normal_keys(&mut state, ",wv");
+	assert_eq!(state.active_tab_window_ids().len(), 2);
+}
+
+#[test]
+fn set_leader_should_clear_pending_sequence() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abc");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, " w");
+	assert!(!state.workbench.normal_sequence.is_empty());
+
+	assert_eq!(state.apply_set_option("leader=,"), Ok("leader=,".to_string()));
+	assert!(state.workbench.normal_sequence.is_empty());
+	assert!(state.workbench.status_bar.key_sequence.is_empty());
+}
+
+#[test]
+fn leader_without_bindings_in_scope_should_fall_back_to_literal_key() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abc");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.set_leader_key('x');
+	state.enter_visual_mode();
+
+	press_normal_keys(&mut state, "x");
+
+	assert!(!state.is_visual_mode());
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("bc"));
+}
+
+#[test]
+fn set_should_report_unknown_option_and_invalid_leader() {
+	let mut state = RimState::new();
+
+	submit_command_line(&mut state, "set nosuchoption");
+	assert!(state.workbench.notifications.iter().any(|entry| entry.message == "unknown option: nosuchoption"));
+
+	assert_eq!(state.apply_set_option("leader=ab"), Err("invalid leader key: ab".to_string()));
+	assert_eq!(state.apply_set_option("leader=<Space>"), Ok("leader=<Space>".to_string()));
+	assert_eq!(state.workbench.leader_key, ' ');
+}
+
+#[test]
+fn set_listchars_should_parse_vim_style_entries() {
+	let mut state = RimState::new();
+
+	submit_command_line(&mut state, "set listchars=tab:>-,trail:.");
+	assert_eq!(state.workbench.status_bar.message, "listchars=tab:>-,trail:.");
+	assert_eq!(state.workbench.list_chars.tab_head, '>');
+	assert_eq!(state.workbench.list_chars.tab_fill, '-');
+	assert_eq!(state.workbench.list_chars.trail, '.');
+
+	assert_eq!(state.apply_set_option("listchars=tab:>"), Err("invalid listchars: tab:>".to_string()));
+	assert_eq!(state.workbench.list_chars.trail, '.');
+}
+
+#[test]
+fn successive_line_deletes_should_rotate_through_numbered_registers() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "one\ntwo\nthree\nfour\n");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "dddddd");
+	let text = |name| state.register_content(name).map(|content| content.text);
+	assert_eq!(text('1'), Some("three".to_string()));
+	assert_eq!(text('2'), Some("two".to_string()));
+	assert_eq!(text('3'), Some("one".to_string()));
+	assert_eq!(text('4'), None);
+
+	press_normal_keys(&mut state, "\"3p\"2p");
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert_eq!(buffer.text.to_string(), "four\none\ntwo\n");
+	assert!(state.selected_register.is_none());
+}
+
+#[test]
+fn small_delete_should_fill_minus_register_without_shifting_numbered_registers() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abc\ndef\n");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "ddx");
+	assert_eq!(state.register_content('-').map(|content| content.text), Some("d".to_string()));
+	assert_eq!(state.register_content('1').map(|content| content.text), Some("abc".to_string()));
+	assert_eq!(state.register_content('2'), None);
+
+	press_normal_keys(&mut state, "\"!");
+	assert_eq!(state.workbench.status_bar.message, "invalid register: !");
+}
+
+#[test]
+fn yank_register_should_keep_last_yank_after_intervening_delete() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abc\nxyz\n");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "vly");
+	press_normal_keys(&mut state, "jdd");
+	assert_eq!(state.line_slot, Some("xyz".to_string()));
+
+	press_normal_keys(&mut state, "\"0p");
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert_eq!(buffer.text.to_string(), "aabbc\n");
+}
+
+#[test]
+fn registers_command_should_list_named_and_numbered_registers() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "alpha\nbeta\n");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "V\"ay");
+	press_normal_keys(&mut state, "jdd");
+	state.enter_command_mode();
+	for ch in "reg".chars() {
+		state.push_command_char(ch);
+	}
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))),
+	);
+
+	let Some(OverlayState::FloatingWindow(window)) = state.workbench.overlay.as_ref() else {
+		panic!("registers overlay should be open");
+	};
+	let entries =
+		window.lines.iter().map(|line| (line.key.as_str(), line.summary.as_str())).collect::<Vec<_>>();
+	assert!(entries.contains(&("\"a", "l  alpha")));
+	assert!(entries.contains(&("\"1", "l  beta")));
+	assert!(entries.contains(&("\"\"", "l  beta")));
+	assert!(!entries.iter().any(|(key, _)| *key == "\"0"));
+}
+
+#[test]
+fn ctrl_r_should_insert_registers_and_the_file_path_in_insert_and_command_mode() {
+	let mut state = RimState::new();
+	state.workbench.working_directory = PathBuf::from("/work");
+	let buffer_id = state.create_buffer(Some(PathBuf::from("/work/src/main.rs")), "alpha\nbeta\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	assert_eq!(state.register_text('%').as_deref(), Some("src/main.rs"));
+	let ctrl_r =
+		|| AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL)));
+
+	press_normal_keys(&mut state, "V\"ayi");
+	let _ = dispatch_test_action(&mut state, ctrl_r());
+	press_normal_keys(&mut state, "%");
+	let _ = dispatch_test_action(&mut state, ctrl_r());
+	press_normal_keys(&mut state, "a");
+	assert_eq!(state.buffer_text_string(buffer_id).as_deref(), Some("src/main.rsalpha\nalpha\nbeta\n"));
+
+	let _ = dispatch_test_action(&mut state, ctrl_r());
+	press_normal_keys(&mut state, "q");
+	assert_eq!(state.workbench.status_bar.message, "insert failed: register q is empty");
+
+	state.exit_insert_mode();
+	state.enter_command_mode();
+	for ch in "e ".chars() {
+		state.push_command_char(ch);
+	}
+	let _ = dispatch_test_action(&mut state, ctrl_r());
+	press_normal_keys(&mut state, "%");
+	assert_eq!(state.workbench.command_line, "e src/main.rs");
+}
+
+#[test]
+fn ctrl_caret_should_toggle_alternate_buffer_and_jump_to_counted_buffer() {
+	let mut state = RimState::new();
+	let first = state.create_buffer(Some(PathBuf::from("a.rs")), "a");
+	let second = state.create_buffer(Some(PathBuf::from("b.rs")), "b");
+	let third = state.create_buffer(Some(PathBuf::from("c.rs")), "c");
+	state.bind_buffer_to_active_window(first);
+	let ctrl_caret =
+		|| AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char('^'), KeyModifiers::CONTROL)));
+
+	let _ = dispatch_test_action(&mut state, ctrl_caret());
+	assert_eq!(state.active_buffer_id(), Some(first));
+	assert_eq!(state.workbench.status_bar.message, "no alternate buffer");
+
+	press_normal_keys(&mut state, "L");
+	assert_eq!(state.active_buffer_id(), Some(second));
+	let _ = dispatch_test_action(&mut state, ctrl_caret());
+	assert_eq!(state.active_buffer_id(), Some(first));
+	let _ = dispatch_test_action(&mut state, ctrl_caret());
+	assert_eq!(state.active_buffer_id(), Some(second));
+
+	press_normal_keys(&mut state, "3");
+	let _ = dispatch_test_action(&mut state, ctrl_caret());
+	assert_eq!(state.active_buffer_id(), Some(third));
+	assert!(state.workbench.pending_count.is_none());
+	let _ = dispatch_test_action(&mut state, ctrl_caret());
+	assert_eq!(state.active_buffer_id(), Some(second));
+}
+
+#[test]
+fn search_from_visual_mode_should_extend_selection_to_match() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "alpha beta\ngamma target\n");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "vl/target");
+	assert!(state.is_command_mode());
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))),
+	);
+
+	assert_eq!(state.mode, crate::state::EditorMode::VisualChar);
+	assert_eq!(state.visual_anchor, Some(crate::state::CursorState { row: 1, col: 1 }));
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 2, col: 7 });
+	press_normal_keys(&mut state, "y");
+	assert_eq!(
+		state.register_content('"').map(|content| content.text),
+		Some("alpha beta\ngamma t".to_string())
+	);
+}
+
+#[test]
+fn de_should_include_word_end_while_dw_stops_before_next_word() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "one two three\n");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "wde");
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert_eq!(buffer.text.to_string(), "one  three\n");
+	assert_eq!(state.register_content('-').map(|content| content.text), Some("two".to_string()));
+
+	press_normal_keys(&mut state, "u0wdw");
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert_eq!(buffer.text.to_string(), "one three\n");
+	assert_eq!(state.register_content('-').map(|content| content.text), Some("two ".to_string()));
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 1, col: 5 });
+}
+
+#[test]
+fn cursor_should_step_over_flag_and_zwj_emoji_by_display_width() {
+	let mut state = RimState::new();
+	let buffer_id =
+		state.create_buffer(None, "\u{1F1EF}\u{1F1F5} \u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467} x");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(80, 20);
+
+	assert_eq!(state.editor.active_line_display_width(), 7);
+	press_normal_keys(&mut state, "l");
+	assert_eq!(state.active_cursor().col, 3);
+	assert_eq!(state.active_cursor_display_col(), 2);
+	press_normal_keys(&mut state, "ll");
+	assert_eq!(state.active_cursor().col, 9);
+	assert_eq!(state.active_cursor_display_col(), 5);
+	press_normal_keys(&mut state, "h");
+	assert_eq!(state.active_cursor().col, 4);
+	assert_eq!(state.active_cursor_display_col(), 3);
+	press_normal_keys(&mut state, "$");
+	assert_eq!(state.active_cursor().col, 10);
+	assert_eq!(state.active_cursor_display_col(), 6);
+}
+
+#[test]
+fn visual_char_vertical_moves_should_restore_column_after_short_line() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abcdef\nab\nabcdef\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(80, 20);
+
+	press_normal_keys(&mut state, "vlllllj");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 2, col: 2 });
+	press_normal_keys(&mut state, "k");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 1, col: 6 });
+
+	// Parked on the newline slot, the selection keeps following line ends.
+	press_normal_keys(&mut state, "lj");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 2, col: 3 });
+	press_normal_keys(&mut state, "j");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 3, col: 7 });
+	press_normal_keys(&mut state, "kk");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 1, col: 7 });
+}
+
+#[test]
+fn delete_char_should_drop_stale_preferred_column_before_vertical_move() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abcdef\nab\nabcdef\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(80, 20);
+
+	press_normal_keys(&mut state, "lllllj");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 2, col: 2 });
+	press_normal_keys(&mut state, "xj");
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").text.to_string(), "abcdef\na\nabcdef\n");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 3, col: 1 });
+
+	press_normal_keys(&mut state, "$kuj");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 3, col: 2 });
+}
+
+#[test]
+fn word_count_command_should_count_buffer_or_visual_selection() {
+	let mut state = RimState::new();
+	let buffer_id =
+		state.create_buffer(None, "# Title\n\nFirst paragraph has five words.\n\nSecond one, with four.\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	let submit_wc = |state: &mut RimState| {
+		for ch in "wc".chars() {
+			state.push_command_char(ch);
+		}
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))),
+		);
+	};
+
+	press_normal_keys(&mut state, ":");
+	submit_wc(&mut state);
+	assert_eq!(state.workbench.status_bar.message, "buffer: 10 words, 65 chars, ~1 min read");
+
+	press_normal_keys(&mut state, "jjVjj:");
+	submit_wc(&mut state);
+	assert_eq!(state.workbench.status_bar.message, "selection: 9 words, 55 chars, ~1 min read");
+	assert_eq!(state.mode, crate::state::EditorMode::Normal);
+
+	press_normal_keys(&mut state, ":");
+	submit_wc(&mut state);
+	assert!(state.workbench.status_bar.message.starts_with("buffer: 10 words"));
+}
+
+#[test]
+fn date_command_should_insert_formatted_clock_time_as_one_undo_step() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "ab\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.workbench.clock = || time::macros::datetime!(2024-03-05 14:07:09 UTC);
+	let submit = |state: &mut RimState, input: &str| {
+		press_normal_keys(state, ":");
+		for ch in input.chars() {
+			state.push_command_char(ch);
+		}
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))),
+		);
+	};
+
+	press_normal_keys(&mut state, "l");
+	submit(&mut state, "date");
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+	assert_eq!(text(&state), "a2024-03-05b\n");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 1, col: 11 });
+
+	submit(&mut state, "date \"%a %H:%M:%S %%\"");
+	assert_eq!(text(&state), "a2024-03-0Tue 14:07:09 %5b\n");
+
+	press_normal_keys(&mut state, "u");
+	assert_eq!(text(&state), "a2024-03-05b\n");
+}
+
+#[test]
+fn put_command_should_put_line_wise_register_below_cursor_or_given_line() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "one\n  two\nthree\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+
+	press_normal_keys(&mut state, "jVy");
+	submit_command_line(&mut state, "put");
+	assert_eq!(text(&state), "one\n  two\n  two\nthree\n");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 3, col: 3 });
+
+	submit_command_line(&mut state, "0put");
+	assert_eq!(text(&state), "  two\none\n  two\n  two\nthree\n");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 1, col: 3 });
+
+	press_normal_keys(&mut state, "u");
+	assert_eq!(text(&state), "one\n  two\n  two\nthree\n");
+
+	submit_command_line(&mut state, "4put");
+	assert_eq!(text(&state), "one\n  two\n  two\nthree\n  two\n");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 5, col: 3 });
+}
+
+#[test]
+fn put_command_should_place_char_wise_register_on_its_own_line() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "alpha beta\ngamma\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+
+	press_normal_keys(&mut state, "wvey0");
+	submit_command_line(&mut state, "put 0");
+	assert_eq!(text(&state), "alpha beta\nbeta\ngamma\n");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 2, col: 1 });
+
+	submit_command_line(&mut state, "put z");
+	assert_eq!(state.workbench.status_bar.message, "put failed: register z is empty");
+	submit_command_line(&mut state, "put !");
+	assert_eq!(state.workbench.status_bar.message, "invalid register: !");
+	assert_eq!(text(&state), "alpha beta\nbeta\ngamma\n");
+}
+
+#[test]
+fn retab_command_should_convert_indentation_between_tabs_and_spaces() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "fn main() {\n\tlet a = \"x\ty\";\n\t\tb();\n  \tc();\n}\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+
+	submit_command_line(&mut state, "set expandtab");
+	submit_command_line(&mut state, "retab");
+	assert_eq!(text(&state), "fn main() {\n    let a = \"x\ty\";\n        b();\n    c();\n}\n");
+	assert_eq!(state.workbench.status_bar.message, "retab: 3 lines changed");
+
+	submit_command_line(&mut state, "set noexpandtab");
+	submit_command_line(&mut state, "retab");
+	assert_eq!(state.workbench.status_bar.message, "retab: 0 lines changed");
+	submit_command_line(&mut state, "retab!");
+	assert_eq!(text(&state), "fn main() {\n\tlet a = \"x\ty\";\n\t\tb();\n\tc();\n}\n");
+
+	press_normal_keys(&mut state, "u");
+	assert_eq!(text(&state), "fn main() {\n    let a = \"x\ty\";\n        b();\n    c();\n}\n");
+
+	submit_command_line(&mut state, "3,$retab!");
+	assert_eq!(text(&state), "fn main() {\n    let a = \"x\ty\";\n\t\tb();\n\tc();\n}\n");
+
+	submit_command_line(&mut state, "set expandtab");
+	submit_command_line(&mut state, "set tabstop=2");
+	submit_command_line(&mut state, "retab");
+	assert_eq!(text(&state), "fn main() {\n    let a = \"x\ty\";\n    b();\n  c();\n}\n");
+}
+
+#[test]
+fn sort_u_should_sort_and_drop_duplicates_as_one_undo_step() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "pear\napple\npear\nfig\napple\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+
+	submit_command_line(&mut state, "sort u");
+	assert_eq!(text(&state), "apple\nfig\npear\n");
+	assert_eq!(state.workbench.status_bar.message, "sort: 2 duplicate lines removed");
+
+	press_normal_keys(&mut state, "u");
+	assert_eq!(text(&state), "pear\napple\npear\nfig\napple\n");
+
+	submit_command_line(&mut state, "2,4sort");
+	assert_eq!(text(&state), "pear\napple\nfig\npear\napple\n");
+
+	submit_command_line(&mut state, "1,4sort u");
+	assert_eq!(text(&state), "apple\nfig\npear\napple\n");
+	assert_eq!(state.workbench.status_bar.message, "sort: 1 duplicate line removed");
+}
+
+#[test]
+fn substitute_should_preview_while_typed_and_only_change_the_buffer_on_submit() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "foo foo\nbar\nfoo\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+	let type_keys = |state: &mut RimState, input: &str| {
+		for ch in input.chars() {
+			let _ = dispatch_test_action(
+				state,
+				AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE))),
+			);
+		}
+	};
+	let preview = |state: &RimState| {
+		state
+			.substitute_preview_for_buffer(buffer_id)
+			.map(|preview| (preview.text.to_string(), preview.ranges.clone()))
+	};
+
+	state.enter_command_mode();
+	type_keys(&mut state, "%s/foo");
+	assert_eq!(preview(&state), Some(("foo foo\nbar\nfoo\n".to_string(), vec![(0, 0..3), (2, 0..3)])));
+	type_keys(&mut state, "/quux/g");
+	assert_eq!(
+		preview(&state),
+		Some(("quux quux\nbar\nquux\n".to_string(), vec![(0, 0..4), (0, 5..9), (2, 0..4)]))
+	);
+	assert_eq!(text(&state), "foo foo\nbar\nfoo\n");
+
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))),
+	);
+	assert_eq!(preview(&state), None);
+	assert_eq!(text(&state), "foo foo\nbar\nfoo\n");
+
+	submit_command_line(&mut state, "s/foo/x");
+	assert_eq!(text(&state), "x foo\nbar\nfoo\n");
+	assert_eq!(state.workbench.status_bar.message, "substitute: 1 replaced");
+	assert_eq!(preview(&state), None);
+
+	submit_command_line(&mut state, "2,$s//y/g");
+	assert_eq!(text(&state), "x foo\nbar\ny\n");
+
+	press_normal_keys(&mut state, "u");
+	assert_eq!(text(&state), "x foo\nbar\nfoo\n");
+
+	submit_command_line(&mut state, "%s/nope/y");
+	assert_eq!(state.workbench.status_bar.message, "pattern not found: nope");
+}
+
+#[test]
+fn s_and_big_s_should_replace_a_char_or_the_whole_line_as_one_undo_step() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abc\ndef\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+	let escape = |state: &mut RimState| {
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))),
+		);
+	};
+
+	press_normal_keys(&mut state, "lsXY");
+	escape(&mut state);
+	assert_eq!(text(&state), "aXYc\ndef\n");
+	assert_eq!(state.line_slot, Some("b".to_string()));
+	press_normal_keys(&mut state, "u");
+	assert_eq!(text(&state), "abc\ndef\n");
+
+	press_normal_keys(&mut state, "$sZ");
+	escape(&mut state);
+	assert_eq!(text(&state), "abZ\ndef\n");
+	press_normal_keys(&mut state, "u");
+
+	press_normal_keys(&mut state, "jSnew");
+	escape(&mut state);
+	assert_eq!(text(&state), "abc\nnew\n");
+	assert_eq!(state.line_slot, Some("def".to_string()));
+	press_normal_keys(&mut state, "u");
+	assert_eq!(text(&state), "abc\ndef\n");
+
+	press_normal_keys(&mut state, "ccxy");
+	escape(&mut state);
+	assert_eq!(text(&state), "abc\nxy\n");
+}
+
+#[test]
+fn uniq_should_only_collapse_adjacent_duplicates_in_visual_lines() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "a\na\nb\nb\na\nc\nc\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+
+	press_normal_keys(&mut state, "Vjjj");
+	submit_command_line(&mut state, "uniq");
+	assert_eq!(text(&state), "a\nb\na\nc\nc\n");
+	assert_eq!(state.workbench.status_bar.message, "uniq: 2 duplicate lines removed");
+
+	submit_command_line(&mut state, "uniq");
+	assert_eq!(text(&state), "a\nb\na\nc\n");
+	assert_eq!(state.workbench.status_bar.message, "uniq: 1 duplicate line removed");
+}
+
+#[test]
+fn bracket_paste_should_write_the_indent_with_the_buffer_expandtab() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(
+		Some(std::path::PathBuf::from("main.rs")),
+		"a();
+	b();
+",
+	);
+	state.bind_buffer_to_active_window(buffer_id);
+	state.workbench.filetype_indent_settings.insert("rust".to_string(), crate::state::IndentSettings {
+		tab_stop:    4,
+		shift_width: 4,
+		expand_tab:  true,
+	});
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+
+	press_normal_keys(&mut state, "Vyj]p");
+	assert_eq!(
+		text(&state),
+		"a();
+	b();
+    a();
+"
+	);
+}
+
+#[test]
+fn bracket_paste_should_reindent_line_wise_block_to_cursor_line() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "  if a {\n    b();\n  }\nfn f() {\n\t\t\tdeep();\n}\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+
+	press_normal_keys(&mut state, "Vjyjjjj]p");
+	assert_eq!(
+		text(&state),
+		"  if a {\n    b();\n  }\nfn f() {\n\t\t\tdeep();\n\t\t\tif a {\n\t\t\t  b();\n}\n"
+	);
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 7, col: 1 });
+
+	press_normal_keys(&mut state, "uggjjj[p");
+	assert_eq!(text(&state), "  if a {\n    b();\n  }\nif a {\n  b();\nfn f() {\n\t\t\tdeep();\n}\n");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 5, col: 1 });
+}
+
+#[test]
+fn normal_mode_cursor_should_rest_on_last_char_while_append_goes_past_it() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abc\n\nxyz\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+
+	press_normal_keys(&mut state, "$l");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 1, col: 3 });
+
+	press_normal_keys(&mut state, "aZ");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 1, col: 5 });
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))),
+	);
+	assert_eq!(text(&state), "abcZ\n\nxyz\n");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 1, col: 4 });
+
+	press_normal_keys(&mut state, "vhd");
+	assert_eq!(text(&state), "ab\n\nxyz\n");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 1, col: 2 });
+
+	press_normal_keys(&mut state, "vyjp");
+	assert_eq!(text(&state), "ab\nb\nxyz\n");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 2, col: 1 });
+}
+
+#[test]
+fn delete_to_search_should_stop_before_match_and_span_lines() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "one two foo three\nalpha\nbeta\ngamma foo\n");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+	let press_enter = |state: &mut RimState| {
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))),
+		);
+	};
+
+	press_normal_keys(&mut state, "wd/foo");
+	press_enter(&mut state);
+	assert_eq!(text(&state), "one foo three\nalpha\nbeta\ngamma foo\n");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 1, col: 5 });
+	assert_eq!(state.register_content('"').map(|content| content.text), Some("two ".to_string()));
+
+	press_normal_keys(&mut state, "j0lld/foo");
+	press_enter(&mut state);
+	assert_eq!(text(&state), "one foo three\nalfoo\n");
+	assert_eq!(state.register_content('"').map(|content| content.text), Some("pha\nbeta\ngamma ".to_string()));
+
+	press_normal_keys(&mut state, "u");
+	assert_eq!(text(&state), "one foo three\nalpha\nbeta\ngamma foo\n");
+
+	press_normal_keys(&mut state, "d/");
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))),
+	);
+	press_normal_keys(&mut state, "/gamma");
+	press_enter(&mut state);
+	assert_eq!(text(&state), "one foo three\nalpha\nbeta\ngamma foo\n");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 4, col: 1 });
+}
+
+#[test]
+fn delete_to_wrapped_search_should_take_the_match_and_keep_the_cursor_char() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abc foo\nxyz bar");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "j$d/foo");
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))),
+	);
+
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("abc r"));
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 1, col: 5 });
+	assert_eq!(state.register_content('"').map(|content| content.text), Some("foo\nxyz ba".to_string()));
+}
+
+#[test]
+fn alt_j_and_alt_k_should_move_current_line_or_selected_lines() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "one\ntwo\nthree\nfour");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+	let press_alt = |state: &mut RimState, ch: char| {
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::ALT))),
+		);
+	};
+
+	press_normal_keys(&mut state, "l");
+	press_alt(&mut state, 'j');
+	assert_eq!(text(&state), "two\none\nthree\nfour");
+	assert_eq!(state.active_cursor(), crate::state::CursorState { row: 2, col: 2 });
+
+	press_normal_keys(&mut state, "u");
+	assert_eq!(text(&state), "one\ntwo\nthree\nfour");
+
+	press_normal_keys(&mut state, "jjVj");
+	press_alt(&mut state, 'k');
+	assert_eq!(text(&state), "one\nthree\nfour\ntwo");
+	assert_eq!(state.mode, crate::state::EditorMode::VisualLine);
+	assert_eq!(state.visual_anchor.map(|anchor| anchor.row), Some(2));
+	assert_eq!(state.active_cursor().row, 3);
+
+	press_alt(&mut state, 'k');
+	press_alt(&mut state, 'k');
+	assert_eq!(text(&state), "three\nfour\none\ntwo");
+	assert_eq!(state.visual_anchor.map(|anchor| anchor.row), Some(1));
+	assert_eq!(state.active_cursor().row, 2);
+}
+
+#[test]
+fn editing_actions_without_active_buffer_should_report_standard_message() {
+	let mut state = RimState::new();
+	assert!(state.active_buffer_id().is_none());
+
+	for keys in ["x", "dd", "dw", "p", "]p", "J", "u", "i", "a", "o", "O", "d/", "vd", "Vy", "vp"] {
+		state.workbench.status_bar.message.clear();
+		press_normal_keys(&mut state, keys);
+		assert_eq!(state.workbench.status_bar.message, "no active buffer", "keys: {}", keys);
+		state.exit_visual_mode();
+		assert_eq!(state.mode, crate::state::EditorMode::Normal, "keys: {}", keys);
+	}
+
+	for command in ["put", "retab", "date", "wc"] {
+		state.workbench.status_bar.message.clear();
+		submit_command_line(&mut state, command);
+		assert_eq!(state.workbench.status_bar.message, "no active buffer", "command: {}", command);
+	}
+	assert!(state.active_buffer_id().is_none());
+}
+
+#[test]
+fn status_line_should_show_pending_count_and_operator_until_command_completes() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "one two three four");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "2");
+	assert_eq!(state.workbench.status_bar.key_sequence, "2");
+	press_normal_keys(&mut state, "d");
+	assert_eq!(state.workbench.status_bar.key_sequence, "2d");
+	press_normal_keys(&mut state, "w");
+	assert_eq!(state.workbench.status_bar.key_sequence, "");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("three four"));
+	assert_eq!(state.register_content('"').map(|content| content.text), Some("one two ".to_string()));
+	press_normal_keys(&mut state, "u");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("one two three four"));
+
+	press_normal_keys(&mut state, "\"a3d");
+	assert_eq!(state.workbench.status_bar.key_sequence, "\"a3d");
+	press_normal_keys(&mut state, "d");
+	assert_eq!(state.workbench.status_bar.key_sequence, "");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some(""));
+}
+
+#[test]
+fn dot_should_replay_keys_of_last_insert_session() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "ab");
+	state.bind_buffer_to_active_window(buffer_id);
+	let press = |state: &mut RimState, code: KeyCode| {
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(code, KeyModifiers::NONE))),
+		);
+	};
+
+	press_normal_keys(&mut state, "ifoo");
+	press(&mut state, KeyCode::Esc);
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("fooab"));
+	press_normal_keys(&mut state, ".");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("foofooab"));
+	assert!(!state.is_insert_mode());
+	press_normal_keys(&mut state, "u");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("fooab"));
+
+	press_normal_keys(&mut state, "oxyz");
+	press(&mut state, KeyCode::Backspace);
+	press(&mut state, KeyCode::Esc);
+	press_normal_keys(&mut state, ".");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("fooab\nxy\nxy"));
+	assert_eq!(state.active_cursor().row, 3);
+}
+
+#[test]
+fn dot_should_replay_cursor_moves_inside_last_insert_session() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "12");
+	state.bind_buffer_to_active_window(buffer_id);
+	let press = |state: &mut RimState, code: KeyCode| {
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(code, KeyModifiers::NONE))),
+		);
+	};
+
+	press_normal_keys(&mut state, "i");
+	press(&mut state, KeyCode::Right);
+	press(&mut state, KeyCode::Right);
+	press_normal_keys(&mut state, "z");
+	press(&mut state, KeyCode::Esc);
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("12z"));
+	press_normal_keys(&mut state, "0.");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("12zz"));
+}
+
+#[test]
+fn dot_should_replay_multi_line_insert_session() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "x");
+	state.bind_buffer_to_active_window(buffer_id);
+	let press = |state: &mut RimState, code: KeyCode| {
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(code, KeyModifiers::NONE))),
+		);
+	};
+
+	press_normal_keys(&mut state, "afoo");
+	press(&mut state, KeyCode::Enter);
+	press_normal_keys(&mut state, "bar");
+	press(&mut state, KeyCode::Esc);
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("xfoo\nbar"));
+	press_normal_keys(&mut state, ".");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("xfoo\nbarfoo\nbar"));
+	assert!(!state.is_insert_mode());
+	press_normal_keys(&mut state, "u");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("xfoo\nbar"));
+}
+
+#[test]
+fn dot_should_not_replay_an_insert_after_a_later_change() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "");
+	state.bind_buffer_to_active_window(buffer_id);
+	let press = |state: &mut RimState, code: KeyCode| {
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(code, KeyModifiers::NONE))),
+		);
+	};
+
+	press_normal_keys(&mut state, "ifoo");
+	press(&mut state, KeyCode::Esc);
+	press_normal_keys(&mut state, "x");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("fo"));
+	press_normal_keys(&mut state, ".");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("fo"));
+	assert_eq!(state.workbench.status_bar.message, "no previous insert to repeat");
+
+	press_normal_keys(&mut state, "obar");
+	press(&mut state, KeyCode::Esc);
+	press_normal_keys(&mut state, "dd");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("fo"));
+	press_normal_keys(&mut state, ".");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("fo"));
+}
+
+#[test]
+fn dot_should_still_replay_an_insert_after_undo() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "ab");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "ifoo");
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))),
+	);
+	press_normal_keys(&mut state, "u.");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("fooab"));
+}
+
+#[test]
+fn undo_all_should_report_a_single_reverted_change_in_singular() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abc");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "x");
+	submit_command_line(&mut state, "earlier!");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("abc"));
+	assert_eq!(state.workbench.status_bar.message, "undo: reverted 1 change");
+}
+
+#[test]
+fn undo_all_should_restore_opened_text_and_keep_edits_redoable() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abc\ndef");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "xxJ");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("c def"));
+	submit_command_line(&mut state, "earlier!");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("abc\ndef"));
+	assert_eq!(state.workbench.status_bar.message, "undo: reverted 3 changes");
+	assert!(!state.buffers.get(buffer_id).expect("buffer exists").dirty);
+
+	for expected in ["bc\ndef", "c\ndef", "c def"] {
+		let _ = dispatch_test_action(
+			&mut state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL))),
+		);
+		assert_eq!(state.active_buffer_text_string().as_deref(), Some(expected));
+	}
+	submit_command_line(&mut state, "earlier!");
+	submit_command_line(&mut state, "earlier!");
+	assert_eq!(state.workbench.status_bar.message, "already at oldest change");
+}
+
+#[test]
+fn undo_and_redo_at_the_history_ends_should_say_so_and_leave_the_text_alone() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "");
+	state.bind_buffer_to_active_window(buffer_id);
+	let redo = |state: &mut RimState| {
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL))),
+		);
+	};
+
+	press_normal_keys(&mut state, "u");
+	assert_eq!(state.workbench.status_bar.message, "already at oldest change");
+	submit_command_line(&mut state, "earlier!");
+	assert_eq!(state.workbench.status_bar.message, "already at oldest change");
+	redo(&mut state);
+	assert_eq!(state.workbench.status_bar.message, "already at newest change");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some(""));
+
+	press_normal_keys(&mut state, "ia");
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))),
+	);
+	redo(&mut state);
+	assert_eq!(state.workbench.status_bar.message, "already at newest change");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("a"));
+	press_normal_keys(&mut state, "uu");
+	assert_eq!(state.workbench.status_bar.message, "already at oldest change");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some(""));
+}
+
+#[test]
+fn undobreaks_should_split_insert_undo_at_each_enter() {
+	let type_lines = |state: &mut RimState| {
+		for code in [
+			KeyCode::Char('i'),
+			KeyCode::Char('a'),
+			KeyCode::Enter,
+			KeyCode::Char('b'),
+			KeyCode::Enter,
+			KeyCode::Char('c'),
+			KeyCode::Esc,
+		] {
+			let _ = dispatch_test_action(
+				state,
+				AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(code, KeyModifiers::NONE))),
+			);
+		}
+		assert_eq!(state.active_buffer_text_string().as_deref(), Some("a\nb\nc"));
+	};
+
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "");
+	state.bind_buffer_to_active_window(buffer_id);
+	type_lines(&mut state);
+	press_normal_keys(&mut state, "u");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some(""));
+
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "");
+	state.bind_buffer_to_active_window(buffer_id);
+	submit_command_line(&mut state, "set undobreaks");
+	type_lines(&mut state);
+	for expected in ["a\nb", "a", ""] {
+		press_normal_keys(&mut state, "u");
+		assert_eq!(state.active_buffer_text_string().as_deref(), Some(expected));
+	}
+	press_normal_keys(&mut state, ".");
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("a\nb\nc"));
+}
+
+#[test]
+fn zf_should_fold_visual_lines_and_vertical_motion_should_skip_closed_fold() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "one\ntwo\nthree\nfour\nfive");
+	state.bind_buffer_to_active_window(buffer_id);
+	let folds = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").folds.clone();
+
+	press_normal_keys(&mut state, "jVjzf");
+	assert_eq!(folds(&state), vec![crate::state::Fold { start_row: 2, end_row: 3, closed: true }]);
+	assert_eq!(state.mode, crate::state::EditorMode::Normal);
+	assert_eq!(state.active_cursor().row, 2);
+	assert_eq!(state.workbench.status_bar.message, "2 lines folded");
+
+	press_normal_keys(&mut state, "j");
+	assert_eq!(state.active_cursor().row, 4);
+	press_normal_keys(&mut state, "k");
+	assert_eq!(state.active_cursor().row, 2);
+	press_normal_keys(&mut state, "kj");
+	assert_eq!(state.active_cursor().row, 2);
+
+	press_normal_keys(&mut state, "zoj");
+	assert_eq!(state.active_cursor().row, 3);
+	press_normal_keys(&mut state, "zcj");
+	assert!(folds(&state)[0].closed);
+	assert_eq!(state.active_cursor().row, 4);
+
+	press_normal_keys(&mut state, "kzojVjzf");
+	assert!(
+		state.workbench.notifications.iter().any(|entry| entry.message == "fold failed: overlaps another fold")
+	);
+	assert_eq!(folds(&state).len(), 1);
+}
+
+#[test]
+fn folds_should_follow_their_lines_when_rows_above_change() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "one\ntwo\nthree\nfour\nfive");
+	state.bind_buffer_to_active_window(buffer_id);
+	let folds = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").folds.clone();
+
+	press_normal_keys(&mut state, "jjVjzf");
+	press_normal_keys(&mut state, "ggdd");
+	assert_eq!(folds(&state), vec![crate::state::Fold { start_row: 2, end_row: 3, closed: true }]);
+
+	press_normal_keys(&mut state, "u");
+	assert_eq!(folds(&state), vec![crate::state::Fold { start_row: 3, end_row: 4, closed: true }]);
+
+	press_normal_keys(&mut state, "jjzoVjd");
+	assert!(folds(&state).is_empty());
+}
+
+#[test]
+fn foldmethod_indent_should_fold_indented_blocks_and_refold_after_edits_settle() {
+	let mut state = RimState::new();
+	let buffer_id = state
+		.create_buffer(None, "fn a() {\n    x();\n    if y {\n        z();\n        w();\n    }\n}\nfn b() {}");
+	state.bind_buffer_to_active_window(buffer_id);
+	let fold_ranges = |state: &RimState| {
+		let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+		buffer.folds.iter().map(|fold| (fold.start_row, fold.end_row, fold.closed)).collect::<Vec<_>>()
+	};
+	let press_esc = |state: &mut RimState| {
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))),
+		);
+	};
+
+	assert_eq!(state.apply_set_option("foldmethod=indent"), Ok("foldmethod=indent".to_string()));
+	assert_eq!(fold_ranges(&state), vec![(2, 6, false), (4, 5, false)]);
+
+	press_normal_keys(&mut state, "zM");
+	assert_eq!(fold_ranges(&state), vec![(2, 6, true), (4, 5, true)]);
+	press_normal_keys(&mut state, "j");
+	assert_eq!(state.active_cursor().row, 2);
+	press_normal_keys(&mut state, "j");
+	assert_eq!(state.active_cursor().row, 7);
+	press_normal_keys(&mut state, "kzajj");
+	assert_eq!(fold_ranges(&state), vec![(2, 6, false), (4, 5, true)]);
+	assert_eq!(state.active_cursor().row, 4);
+	press_normal_keys(&mut state, "zR");
+	assert_eq!(fold_ranges(&state), vec![(2, 6, false), (4, 5, false)]);
+
+	// Indenting the last two lines joins them to the block once edits settle.
+	press_normal_keys(&mut state, "G0i    ");
+	press_esc(&mut state);
+	press_normal_keys(&mut state, "k0i    ");
+	press_esc(&mut state);
+	state.recompute_due_indent_folds(std::time::Instant::now());
+	assert_eq!(fold_ranges(&state), vec![(2, 6, false), (4, 5, false)]);
+	state.recompute_due_indent_folds(std::time::Instant::now() + std::time::Duration::from_secs(1));
+	assert_eq!(fold_ranges(&state), vec![(2, 8, false), (4, 5, false)]);
+
+	assert_eq!(state.apply_set_option("fdm"), Ok("foldmethod=indent".to_string()));
+	assert_eq!(state.apply_set_option("fdm=syntax"), Err("invalid foldmethod: syntax".to_string()));
+}
+
+#[test]
+fn diffsplit_should_open_file_in_a_diff_mode_split_and_scroll_both_windows() {
+	let mut state = RimState::new();
+	let text = (1..=40).map(|n| format!("line {n}")).collect::<Vec<_>>().join("\n");
+	let buffer_id = state.create_buffer(None, text.as_str());
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(80, 10);
+	let original_window = state.active_window_id();
+
+	submit_command_line(&mut state, "diffsplit other.txt");
+	let diff_window = state.active_window_id();
+	assert_ne!(diff_window, original_window);
+	assert!(state.windows.get(original_window).expect("window exists").diff);
+	assert!(state.windows.get(diff_window).expect("window exists").diff);
+	assert_eq!(state.window_diff_partner(original_window), Some(diff_window));
+	let other_buffer =
+		state.windows.get(diff_window).and_then(|window| window.buffer_id).expect("buffer bound");
+	assert_ne!(other_buffer, buffer_id);
+
+	state.replace_buffer_text_preserving_cursor(other_buffer, text.clone());
+	press_normal_keys(&mut state, "G");
+	let scroll_y = state.windows.get(diff_window).expect("window exists").scroll_y;
+	assert!(scroll_y > 0);
+	assert_eq!(state.windows.get(original_window).expect("window exists").scroll_y, scroll_y);
+
+	submit_command_line(&mut state, "diffoff");
+	press_normal_keys(&mut state, "gg");
+	assert!(!state.windows.get(original_window).expect("window exists").diff);
+	assert_eq!(state.windows.get(original_window).expect("window exists").scroll_y, scroll_y);
+}
+
+#[test]
+fn scrollbind_should_scroll_bound_windows_by_the_same_delta_clamped_per_buffer() {
+	let mut state = RimState::new();
+	let long_text = (1..=40).map(|n| format!("line {n}")).collect::<Vec<_>>().join("\n");
+	let long_buffer = state.create_buffer(None, long_text.as_str());
+	state.bind_buffer_to_active_window(long_buffer);
+	state.update_active_tab_layout(80, 20);
+	submit_command_line(&mut state, "set scrollbind");
+	state.split_active_window(SplitAxis::Vertical);
+	let short_window = state.active_window_id();
+	let short_buffer = state.create_buffer(None, "a\nb\nc\nd\ne\nf");
+	state.bind_buffer_to_active_window(short_buffer);
+	state.focus_window(FocusDirection::Up);
+	let long_window = state.active_window_id();
+	let scroll_y =
+		|state: &RimState, window_id| state.windows.get(window_id).expect("window should exist").scroll_y;
+	let press_ctrl = |state: &mut RimState, ch| {
+		let _ = dispatch_test_action(
+			state,
+			AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Char(ch), KeyModifiers::CONTROL))),
+		);
+	};
+
+	press_ctrl(&mut state, 'e');
+	press_ctrl(&mut state, 'e');
+	assert_eq!((scroll_y(&state, long_window), scroll_y(&state, short_window)), (2, 2));
+
+	for _ in 0..6 {
+		press_ctrl(&mut state, 'e');
+	}
+	assert_eq!((scroll_y(&state, long_window), scroll_y(&state, short_window)), (8, 5));
+
+	press_ctrl(&mut state, 'y');
+	assert_eq!((scroll_y(&state, long_window), scroll_y(&state, short_window)), (7, 4));
+
+	submit_command_line(&mut state, "set noscb");
+	press_ctrl(&mut state, 'y');
+	assert_eq!((scroll_y(&state, long_window), scroll_y(&state, short_window)), (6, 4));
+}
+
+#[test]
+fn invalid_key_before_a_prefix_should_leave_that_prefix_pending() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "a\nb\nc\nd");
+	state.bind_buffer_to_active_window(buffer_id);
+	press_normal_keys(&mut state, "Vjzfzo");
+
+	press_normal_keys(&mut state, "gz");
+	assert_eq!(state.workbench.normal_sequence, vec![NormalSequenceKey::Char('z')]);
+	assert_eq!(state.workbench.status_bar.key_sequence, "z");
+	assert!(state.floating_window().expect("pending z should open hints").title.ends_with("z"));
+	press_normal_keys(&mut state, "c");
+	assert!(state.buffers.get(buffer_id).expect("buffer exists").folds[0].closed);
+
+	press_normal_keys(&mut state, "zoG");
+	press_normal_keys(&mut state, "g\"add");
+	assert_eq!(state.mode, crate::state::EditorMode::Normal);
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").text.to_string(), "a\nb\nc");
+	press_normal_keys(&mut state, "gg\"ap");
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").text.to_string(), "a\nd\nb\nc");
+}
+
+#[test]
+fn setlocal_wrap_should_leave_the_sibling_window_on_the_global_value() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abcdefghijklmnopqrstuvwxyz");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.split_active_window(SplitAxis::Vertical);
+	let sibling_id =
+		state.active_tab_window_ids().into_iter().find(|id| *id != state.active_window_id()).unwrap();
+
+	submit_command_line(&mut state, "setlocal wrap");
+	assert!(state.word_wrap_enabled());
+	assert!(!state.window_word_wrap(sibling_id));
+
+	submit_command_line(&mut state, "setl nowrap");
+	submit_command_line(&mut state, "set wrap");
+	assert!(state.word_wrap_enabled());
+	assert!(state.window_word_wrap(sibling_id));
+
+	submit_command_line(&mut state, "setlocal list");
+	assert!(state.workbench.notifications.iter().any(|entry| entry.message == "unknown local option: list"));
+}
+
+#[test]
+fn undolist_should_list_the_changes_and_undo_n_should_jump_to_one() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abcd\nefgh");
+	state.bind_buffer_to_active_window(buffer_id);
+	press_normal_keys(&mut state, "xxjx");
+	assert!(
+		state
+			.buffers
+			.get(buffer_id)
+			.expect("buffer exists")
+			.undo_stack
+			.iter()
+			.all(|entry| entry.changed_at.is_some())
+	);
+	state.workbench.clock = || time::macros::datetime!(2024-03-05 14:07:09 UTC);
+	let now = std::time::SystemTime::from((state.workbench.clock)());
+	for (entry, age) in
+		state.buffers.get_mut(buffer_id).expect("buffer exists").undo_stack.iter_mut().zip([600, 42, 1])
+	{
+		entry.changed_at = Some(now - std::time::Duration::from_secs(age));
+	}
+
+	submit_command_line(&mut state, "undolist");
+	let Some(OverlayState::FloatingWindow(window)) = state.workbench.overlay.as_ref() else {
+		panic!("undo list overlay should be open");
+	};
+	let entries =
+		window.lines.iter().map(|line| (line.key.as_str(), line.summary.as_str())).collect::<Vec<_>>();
+	assert_eq!(entries[0], ("0", "original"));
+	// Older changes show a local timestamp, so only its date prefix is fixed.
+	assert!(entries[1].1.starts_with("line 1, 1 edit  2024-03-0"), "{:?}", entries[1]);
+	assert_eq!(entries[2..], [
+		("2", "line 1, 1 edit  42 seconds ago"),
+		("3", "line 2, 1 edit  1 second ago  <- current")
+	]);
+	state.workbench.overlay = None;
+
+	submit_command_line(&mut state, "undo 1");
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+	assert_eq!(text(&state), "bcd\nefgh");
+	assert_eq!(state.workbench.status_bar.message, "undo: at change 1 of 3");
+
+	// Jumping walks the history instead of adding to it.
+	submit_command_line(&mut state, "undo 3");
+	assert_eq!(text(&state), "cd\nfgh");
+	submit_command_line(&mut state, "u 0");
+	assert_eq!(text(&state), "abcd\nefgh");
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert_eq!((buffer.undo_stack.len(), buffer.redo_stack.len()), (0, 3));
+
+	submit_command_line(&mut state, "undo 4");
+	assert_eq!(state.workbench.status_bar.message, "undo failed: no change 4");
+}
+
+#[test]
+fn duplicate_lines_should_copy_below_as_one_undo_step_and_keep_the_register() {
+	let mut state = RimState::new();
+	state.workbench.leader_key = ' ';
+	let buffer_id = state.create_buffer(None, "one\ntwo\nthree");
+	state.bind_buffer_to_active_window(buffer_id);
+	press_normal_keys(&mut state, "vly");
+	let register = state.editor.register_content('"');
+	assert!(register.is_some());
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+
+	press_normal_keys(&mut state, " d");
+	assert_eq!(text(&state), "one\none\ntwo\nthree");
+	assert_eq!(state.active_cursor().row, 2);
+	assert_eq!(state.editor.register_content('"'), register);
+
+	press_normal_keys(&mut state, "u");
+	assert_eq!(text(&state), "one\ntwo\nthree");
+
+	press_normal_keys(&mut state, "jVj");
+	submit_command_line(&mut state, "dup");
+	assert_eq!(text(&state), "one\ntwo\nthree\ntwo\nthree");
+	assert!(!state.is_visual_mode());
+	assert_eq!(state.active_cursor().row, 4);
+
+	submit_command_line(&mut state, "1dup");
+	assert_eq!(text(&state), "one\none\ntwo\nthree\ntwo\nthree");
+	assert_eq!(state.editor.register_content('"'), register);
+}
+
+#[test]
+fn large_paste_should_land_over_ticks_and_undo_as_one_step() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "end");
+	state.bind_buffer_to_active_window(buffer_id);
+	let line = "x".repeat(99);
+	let pasted = format!("{}\r", line).repeat(2_000);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+
+	let _ = dispatch_test_action(&mut state, AppAction::Paste(pasted));
+	assert!(state.has_pending_paste());
+	assert!(state.is_insert_mode());
+	assert!(state.workbench.status_bar.message.starts_with("pasting "));
+	assert!(text(&state).len() < 200_003);
+
+	for _ in 0..10 {
+		let _ = dispatch_test_action(&mut state, AppAction::System(SystemAction::Tick));
+	}
+	assert!(!state.has_pending_paste());
+	assert!(!state.is_insert_mode());
+	assert_eq!(text(&state), format!("{}end", format!("{}\n", line).repeat(2_000)));
+	assert_eq!(state.workbench.status_bar.message, "pasted 200000 chars");
+
+	press_normal_keys(&mut state, "u");
+	assert_eq!(text(&state), "end");
+}
+
+#[test]
+fn actions_should_bump_render_generation_but_idle_ticks_should_not() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "\talpha");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	let before = state.render_generation();
+	submit_command_line(&mut state, "setlocal shiftwidth=2");
+	assert_ne!(state.render_generation(), before);
+
+	let before = state.render_generation();
+	let _ = dispatch_test_action(&mut state, AppAction::System(SystemAction::Tick));
+	assert_eq!(state.render_generation(), before);
+
+	let _ = dispatch_test_action(&mut state, AppAction::Paste("x".repeat(200_000)));
+	let before = state.render_generation();
+	let _ = dispatch_test_action(&mut state, AppAction::System(SystemAction::Tick));
+	assert_ne!(state.render_generation(), before);
+}
+
+#[test]
+fn paste_over_pastemax_should_be_truncated_with_a_warning() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "");
+	state.bind_buffer_to_active_window(buffer_id);
+	submit_command_line(&mut state, "set pastemax=4");
+
+	let _ = dispatch_test_action(&mut state, AppAction::Paste("abcdefgh".to_string()));
+
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").text.to_string(), "abcd");
+	assert!(!state.has_pending_paste());
+	assert_eq!(
+		state.workbench.notifications.last().map(|notification| notification.message.as_str()),
+		Some("paste truncated to 4 of 8 chars")
+	);
+}
+
+#[test]
+fn visual_star_should_search_for_the_selected_text_literally() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "a.b(c) axb(c)\nnext a.b(c) end\n");
+	state.bind_buffer_to_active_window(buffer_id);
+
+	press_normal_keys(&mut state, "vlllll*");
+
+	assert_eq!(state.mode, crate::state::EditorMode::Normal);
+	assert_eq!(state.active_cursor(), CursorState { row: 2, col: 6 });
+	assert_eq!(state.workbench.last_search.as_deref(), Some("a.b(c)"));
+	assert_eq!(state.workbench.status_bar.message, "/a.b(c)");
+
+	press_normal_keys(&mut state, "vk*");
+	assert_eq!(
+		state.workbench.notifications.last().map(|notification| notification.message.as_str()),
+		Some("search failed: selection spans lines")
+	);
+	assert!(state.is_visual_mode());
+}
+
+#[test]
+fn leader_colon_palette_should_run_the_selected_command_on_enter() {
+	let mut state = RimState::new();
+	state.workbench.leader_key = ' ';
+	let buffer_id = state.create_buffer(None, "text");
+	state.bind_buffer_to_active_window(buffer_id);
+	let enter = AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)));
+
+	press_normal_keys(&mut state, " :split vertical");
+	assert!(state.is_command_mode());
+	let _ = dispatch_test_action(&mut state, enter);
+	assert!(!state.is_command_mode());
+	assert_eq!(state.active_tab_window_ids().len(), 2);
+
+	press_normal_keys(&mut state, " :drop");
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))),
+	);
+	assert!(state.is_command_mode());
+	assert_eq!(state.workbench.command_line, "core.drop ");
+	assert!(!state.workbench.command_palette_run_selection);
+}
+
+#[test]
+fn smartindent_should_indent_after_an_open_brace_and_dedent_a_closing_one() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "fn main() {");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+	let enter =
+		|| AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)));
+	let esc = || AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)));
+	submit_command_line(&mut state, "set smartindent");
+	submit_command_line(&mut state, "set expandtab");
+	submit_command_line(&mut state, "set shiftwidth=4");
+
+	press_normal_keys(&mut state, "$a");
+	let _ = dispatch_test_action(&mut state, enter());
+	press_normal_keys(&mut state, "if x {");
+	let _ = dispatch_test_action(&mut state, enter());
+	assert_eq!(text(&state), "fn main() {\n    if x {\n        ");
+	assert_eq!(state.active_cursor(), CursorState { row: 3, col: 9 });
+	press_normal_keys(&mut state, "y();");
+	let _ = dispatch_test_action(&mut state, enter());
+	press_normal_keys(&mut state, "}");
+	assert_eq!(text(&state), "fn main() {\n    if x {\n        y();\n    }");
+	assert_eq!(state.active_cursor(), CursorState { row: 4, col: 6 });
+	let _ = dispatch_test_action(&mut state, esc());
+
+	press_normal_keys(&mut state, "u");
+	assert_eq!(text(&state), "fn main() {");
+
+	submit_command_line(&mut state, "set noexpandtab");
+	press_normal_keys(&mut state, "$a");
+	let _ = dispatch_test_action(&mut state, enter());
+	press_normal_keys(&mut state, "}");
+	assert_eq!(text(&state), "fn main() {\n}");
+}
+
+#[test]
+fn virtualedit_all_append_should_pad_to_the_virtual_column_and_undo_as_one_step() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abcdef\nx");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+	submit_command_line(&mut state, "set virtualedit=all");
+
+	press_normal_keys(&mut state, "$jll");
+	assert_eq!(text(&state), "abcdef\nx");
+	assert_eq!(state.active_cursor_display_col(), 7);
+	press_normal_keys(&mut state, "aY");
+	let _ = dispatch_test_action(
+		&mut state,
+		AppAction::Editor(EditorAction::KeyPressed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))),
+	);
+	assert_eq!(text(&state), "abcdef\nx       Y");
+
+	press_normal_keys(&mut state, "u");
+	assert_eq!(text(&state), "abcdef\nx");
+}
+
+#[test]
+fn virtualedit_all_cut_and_delete_should_do_nothing_in_the_virtual_space() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abcdef\nxy");
+	state.bind_buffer_to_active_window(buffer_id);
+	let text = |state: &RimState| state.buffers.get(buffer_id).expect("buffer exists").text.to_string();
+	submit_command_line(&mut state, "set virtualedit=all");
+
+	press_normal_keys(&mut state, "$j");
+	assert_eq!(state.active_cursor_display_col(), 5);
+	press_normal_keys(&mut state, "x");
+	assert_eq!(text(&state), "abcdef\nxy");
+	press_normal_keys(&mut state, "dw");
+	assert_eq!(text(&state), "abcdef\nxy");
+	assert_eq!(state.active_cursor_display_col(), 5);
+
+	press_normal_keys(&mut state, "0x");
+	assert_eq!(text(&state), "abcdef\ny");
+}
@@ -1,6 +1,6 @@
 use std::{cell::RefCell, ops::ControlFlow, path::{Path, PathBuf}};
 
-use rim_ports::{FilePicker, FilePickerError, FileWatcher, FileWatcherError, PluginCommandRequest, PluginRuntime, PluginRuntimeError, StorageIo, StorageIoError};
+use rim_ports::{FilePicker, FilePickerError, FileSaveOptions, FileWatcher, FileWatcherError, LanguageClient, LanguageClientError, LanguageDocument, LanguagePosition, PluginCommandRequest, PluginRuntime, PluginRuntimeError, StorageIo, StorageIoError};
 
 use super::super::mode_flow::{SequenceMatch, resolve_normal_sequence_with_registry};
 use crate::{action::{AppAction, KeyEvent}, command::CommandRegistry, ports::SwapEditOp, state::{BufferId, NormalSequenceKey, PersistedBufferHistory, RimState, WorkspaceSessionSnapshot}};
@@ -15,6 +15,10 @@ impl FileWatcher for TestPorts {
 	fn enqueue_unwatch(&self, _buffer_id: BufferId) -> Result<(), FileWatcherError> { Ok(()) }
 }
 
+impl LanguageClient for TestPorts {
+	type BufferId = BufferId;
+}
+
 impl FilePicker for TestPorts {
 	fn pick_open_path(
 		&self,
@@ -45,7 +49,13 @@ impl StorageIo for TestPorts {
 
 	fn enqueue_load_workspace_file_preview(&self, _path: PathBuf) -> Result<(), StorageIoError> { Ok(()) }
 
-	fn enqueue_save(&self, _buffer_id: BufferId, _path: PathBuf, _text: String) -> Result<(), StorageIoError> {
+	fn enqueue_save(
+		&self,
+		_buffer_id: BufferId,
+		_path: PathBuf,
+		_text: String,
+		_options: FileSaveOptions,
+	) -> Result<(), StorageIoError> {
 		Ok(())
 	}
 
@@ -126,6 +136,7 @@ pub(super) fn dispatch_test_action(state: &mut RimState, action: AppAction) -> C
 pub(super) struct RecordingPorts {
 	pub(super) file_loads:            RefCell<Vec<(BufferId, PathBuf)>>,
 	pub(super) external_loads:        RefCell<Vec<(BufferId, PathBuf)>>,
+	pub(super) mtime_checks:          RefCell<Vec<Vec<(BufferId, PathBuf)>>>,
 	pub(super) swap_conflict_detects: RefCell<Vec<(BufferId, PathBuf)>>,
 	pub(super) swap_edits:            RefCell<Vec<(BufferId, PathBuf, SwapEditOp)>>,
 	pub(super) swap_recovers:         RefCell<Vec<(BufferId, PathBuf, String)>>,
@@ -140,6 +151,11 @@ pub(super) struct RecordingPorts {
 	pub(super) plugin_discovers:      RefCell<Vec<String>>,
 	pub(super) plugin_invocations:    RefCell<Vec<PluginCommandRequest>>,
 	pub(super) picked_path:           RefCell<Option<PathBuf>>,
+	pub(super) lsp_opens:             RefCell<Vec<(BufferId, LanguageDocument)>>,
+	pub(super) lsp_changes:           RefCell<Vec<(BufferId, String)>>,
+	pub(super) lsp_closes:            RefCell<Vec<BufferId>>,
+	pub(super) lsp_definitions:       RefCell<Vec<(BufferId, LanguagePosition)>>,
+	pub(super) lsp_hovers:            RefCell<Vec<(BufferId, LanguagePosition)>>,
 }
 
 impl FileWatcher for RecordingPorts {
@@ -156,6 +172,47 @@ impl FileWatcher for RecordingPorts {
 	}
 }
 
+impl LanguageClient for RecordingPorts {
+	type BufferId = BufferId;
+
+	fn enqueue_lsp_open(
+		&self,
+		buffer_id: BufferId,
+		document: LanguageDocument,
+	) -> Result<(), LanguageClientError> {
+		self.lsp_opens.borrow_mut().push((buffer_id, document));
+		Ok(())
+	}
+
+	fn enqueue_lsp_change(&self, buffer_id: BufferId, text: String) -> Result<(), LanguageClientError> {
+		self.lsp_changes.borrow_mut().push((buffer_id, text));
+		Ok(())
+	}
+
+	fn enqueue_lsp_close(&self, buffer_id: BufferId) -> Result<(), LanguageClientError> {
+		self.lsp_closes.borrow_mut().push(buffer_id);
+		Ok(())
+	}
+
+	fn enqueue_lsp_definition(
+		&self,
+		buffer_id: BufferId,
+		position: LanguagePosition,
+	) -> Result<(), LanguageClientError> {
+		self.lsp_definitions.borrow_mut().push((buffer_id, position));
+		Ok(())
+	}
+
+	fn enqueue_lsp_hover(
+		&self,
+		buffer_id: BufferId,
+		position: LanguagePosition,
+	) -> Result<(), LanguageClientError> {
+		self.lsp_hovers.borrow_mut().push((buffer_id, position));
+		Ok(())
+	}
+}
+
 impl FilePicker for RecordingPorts {
 	fn pick_open_path(
 		&self,
@@ -198,7 +255,18 @@ impl StorageIo for RecordingPorts {
 
 	fn enqueue_load_workspace_file_preview(&self, _path: PathBuf) -> Result<(), StorageIoError> { Ok(()) }
 
-	fn enqueue_save(&self, _buffer_id: BufferId, _path: PathBuf, _text: String) -> Result<(), StorageIoError> {
+	fn enqueue_save(
+		&self,
+		_buffer_id: BufferId,
+		_path: PathBuf,
+		_text: String,
+		_options: FileSaveOptions,
+	) -> Result<(), StorageIoError> {
+		Ok(())
+	}
+
+	fn enqueue_check_mtimes(&self, files: Vec<(BufferId, PathBuf)>) -> Result<(), StorageIoError> {
+		self.mtime_checks.borrow_mut().push(files);
 		Ok(())
 	}
 
@@ -299,6 +367,10 @@ impl FileWatcher for SwapDecisionPorts {
 	}
 }
 
+impl LanguageClient for SwapDecisionPorts {
+	type BufferId = BufferId;
+}
+
 impl FilePicker for SwapDecisionPorts {
 	fn pick_open_path(
 		&self,
@@ -326,6 +398,10 @@ pub(super) struct FilePickerPorts {
 	pub(super) open_requests:     RefCell<Vec<(BufferId, PathBuf)>>,
 	pub(super) preview_requests:  RefCell<Vec<PathBuf>>,
 	pub(super) workspace_queries: RefCell<Vec<PathBuf>>,
+	pub(super) glob_requests:     RefCell<Vec<(PathBuf, String)>>,
+	pub(super) tag_loads:         RefCell<Vec<(PathBuf, String)>>,
+	pub(super) session_saves:     RefCell<Vec<(PathBuf, WorkspaceSessionSnapshot)>>,
+	pub(super) session_loads:     RefCell<Vec<PathBuf>>,
 }
 
 impl FileWatcher for FilePickerPorts {
@@ -336,6 +412,10 @@ impl FileWatcher for FilePickerPorts {
 	fn enqueue_unwatch(&self, _buffer_id: BufferId) -> Result<(), FileWatcherError> { Ok(()) }
 }
 
+impl LanguageClient for FilePickerPorts {
+	type BufferId = BufferId;
+}
+
 impl FilePicker for FilePickerPorts {
 	fn pick_open_path(
 		&self,
@@ -375,7 +455,37 @@ impl StorageIo for FilePickerPorts {
 		Ok(())
 	}
 
-	fn enqueue_save(&self, _buffer_id: BufferId, _path: PathBuf, _text: String) -> Result<(), StorageIoError> {
+	fn enqueue_expand_glob(&self, workspace_root: PathBuf, pattern: String) -> Result<(), StorageIoError> {
+		self.glob_requests.borrow_mut().push((workspace_root, pattern));
+		Ok(())
+	}
+
+	fn enqueue_load_tags(&self, tags_path: PathBuf, tag: String) -> Result<(), StorageIoError> {
+		self.tag_loads.borrow_mut().push((tags_path, tag));
+		Ok(())
+	}
+
+	fn enqueue_save_session_file(
+		&self,
+		path: PathBuf,
+		snapshot: WorkspaceSessionSnapshot,
+	) -> Result<(), StorageIoError> {
+		self.session_saves.borrow_mut().push((path, snapshot));
+		Ok(())
+	}
+
+	fn enqueue_load_session_file(&self, path: PathBuf) -> Result<(), StorageIoError> {
+		self.session_loads.borrow_mut().push(path);
+		Ok(())
+	}
+
+	fn enqueue_save(
+		&self,
+		_buffer_id: BufferId,
+		_path: PathBuf,
+		_text: String,
+		_options: FileSaveOptions,
+	) -> Result<(), StorageIoError> {
 		Ok(())
 	}
 
@@ -458,7 +568,13 @@ impl StorageIo for SwapDecisionPorts {
 
 	fn enqueue_load(&self, _buffer_id: BufferId, _path: PathBuf) -> Result<(), StorageIoError> { Ok(()) }
 
-	fn enqueue_save(&self, _buffer_id: BufferId, _path: PathBuf, _text: String) -> Result<(), StorageIoError> {
+	fn enqueue_save(
+		&self,
+		_buffer_id: BufferId,
+		_path: PathBuf,
+		_text: String,
+		_options: FileSaveOptions,
+	) -> Result<(), StorageIoError> {
 		Ok(())
 	}
 
@@ -4,7 +4,7 @@ use frizbee::{Config as FrizbeeConfig, match_list_indices};
 use rim_command_macros::{BuiltinCommandGroup, BuiltinCommandRoot};
 use serde::{Deserialize, Serialize};
 
-use crate::{action::{AppAction, BufferAction, EditorAction, LayoutAction, TabAction, WindowAction}, defaults, state::{FloatingWindowLine, KeymapScope, NormalSequenceKey}};
+use crate::{action::{AppAction, BufferAction, EditorAction, LayoutAction, TabAction, WindowAction}, defaults, state::{FloatingWindowLine, KeymapScope, Motion, NormalSequenceKey}};
 
 pub trait BuiltinCommandGroupMeta: Copy {
 	fn command_segment(self) -> &'static str;
@@ -92,6 +92,8 @@ pub enum WindowCommand {
 	FocusUp,
 	/// Focus right window
 	FocusRight,
+	/// Maximize the active window or restore the layout
+	ZoomToggle,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BuiltinCommandGroup)]
@@ -118,6 +120,10 @@ pub enum BufferCommand {
 	Prev,
 	/// Next buffer
 	Next,
+	/// Alternate buffer, or buffer N with a count
+	Alternate,
+	/// Reopen the last closed file
+	Reopen,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BuiltinCommandGroup)]
@@ -132,6 +138,10 @@ pub enum ModeCommand {
 	OpenAbove,
 	/// Enter command mode
 	Command,
+	/// Open the command palette to run the selected command
+	Palette,
+	/// Search forward
+	Search,
 	/// Enter visual mode
 	Visual,
 	/// Enter visual line mode
@@ -146,14 +156,42 @@ pub enum ModeCommand {
 pub enum EditCommand {
 	/// Undo
 	Undo,
+	/// Undo every change back to the opened text
+	UndoAll,
 	/// Redo
 	Redo,
 	/// Join line below
 	JoinLineBelow,
 	/// Cut current char
 	CutChar,
+	/// Substitute char under cursor and insert
+	SubstituteChar,
+	/// Change whole line
+	ChangeLine,
 	/// Paste slot after cursor
 	Paste,
+	/// Paste lines below, matching current indent
+	PasteIndentBelow,
+	/// Paste lines above, matching current indent
+	PasteIndentAbove,
+	/// Delete to next word start
+	DeleteWord,
+	/// Delete through word end
+	DeleteWordEnd,
+	/// Delete to next search match
+	DeleteToSearch,
+	/// Move line or selected lines down
+	MoveLinesDown,
+	/// Move line or selected lines up
+	MoveLinesUp,
+	/// Duplicate line or selected lines below
+	DuplicateLines,
+	/// Repeat last insert
+	RepeatLastInsert,
+	/// Add word under cursor to good words
+	SpellGood,
+	/// Suggest spellings for word under cursor
+	SpellSuggest,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BuiltinCommandGroup)]
@@ -164,6 +202,10 @@ pub enum CursorCommand {
 	LineStart,
 	/// Move to line end
 	LineEnd,
+	/// Move to next word start
+	WordForward,
+	/// Move to word end
+	WordEnd,
 	/// Move down
 	Down,
 	/// Move up
@@ -174,6 +216,24 @@ pub enum CursorCommand {
 	FileStart,
 	/// Move to file end
 	FileEnd,
+	/// Move to next changed hunk
+	NextChange,
+	/// Move to previous changed hunk
+	PrevChange,
+	/// Jump to older change position
+	OlderChangePosition,
+	/// Jump to newer change position
+	NewerChangePosition,
+	/// Move to next misspelled word
+	NextMisspelling,
+	/// Move to previous misspelled word
+	PrevMisspelling,
+	/// Jump to tag under cursor
+	TagJump,
+	/// Pop back from tag jump
+	TagPop,
+	/// Jump to definition under cursor
+	Definition,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BuiltinCommandGroup)]
@@ -188,6 +248,18 @@ pub enum ViewCommand {
 	ScrollHalfPageUp,
 	/// Toggle word wrap
 	ToggleWordWrap,
+	/// Show hover information under cursor
+	Hover,
+	/// Open fold under cursor
+	FoldOpen,
+	/// Close fold under cursor
+	FoldClose,
+	/// Toggle fold under cursor
+	FoldToggle,
+	/// Open all folds
+	FoldOpenAll,
+	/// Close all folds
+	FoldCloseAll,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BuiltinCommandGroup)]
@@ -272,6 +344,16 @@ pub enum InsertCommand {
 	Right,
 	/// Insert tab
 	Tab,
+	/// Complete word before cursor with next buffer word
+	CompleteNext,
+	/// Complete word before cursor with previous buffer word
+	CompletePrev,
+	/// Move to line start in insert mode
+	LineStart,
+	/// Move to line end in insert mode
+	LineEnd,
+	/// Insert the contents of the next named register
+	Register,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BuiltinCommandGroup)]
@@ -294,6 +376,10 @@ pub enum VisualCommand {
 	Left,
 	/// Move right in visual mode
 	Right,
+	/// Fold selected lines
+	Fold,
+	/// Search for the selected text
+	SearchSelection,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BuiltinCommandGroup)]
@@ -306,12 +392,16 @@ pub enum CommandCommand {
 	QuitAll,
 	/// Force quit application
 	QuitAllForce,
+	/// Close the window, but never the last one
+	Close,
 	/// Save current buffer
 	Save { path: Option<File> },
 	/// Force save current buffer
 	SaveForce { path: Option<File> },
 	/// Save all file-backed buffers
 	SaveAll,
+	/// Force save all file-backed buffers
+	SaveAllForce,
 	/// Save current buffer and quit
 	SaveAndQuit { path: Option<File> },
 	/// Force save current buffer and quit
@@ -324,12 +414,56 @@ pub enum CommandCommand {
 	Reload { path: Option<File> },
 	/// Force reload current buffer
 	ReloadForce { path: Option<File> },
+	/// Focus the window showing a file, or open it
+	Drop { path: File },
+	/// Reload clean buffers whose file changed on disk
+	Checktime,
+	/// Compare the window with another file in a split
+	DiffSplit { path: File },
+	/// Compare the window with other diff windows
+	DiffThis,
+	/// Leave diff mode in every window of the tab
+	DiffOff,
 	/// Execute current command input
 	Submit,
 	/// Delete previous command character
 	Backspace,
 	/// Open notification center
 	Notifications,
+	/// Set editor option
+	Set { option: Text },
+	/// Set window-local option
+	SetLocal { option: Text },
+	/// Show working directory
+	Pwd,
+	/// Change working directory
+	Cd { path: Option<File> },
+	/// Write tab and window layout to a session file
+	MakeSession { path: Option<File> },
+	/// Like mksession, overwriting an existing session file
+	MakeSessionForce { path: Option<File> },
+	/// Restore tabs and windows from a session file
+	Source { path: Option<File> },
+	/// Show register contents
+	Registers,
+	/// Count words and reading time in the buffer or selection
+	WordCount,
+	/// Insert the current date at the cursor
+	Date { format: Option<Text> },
+	/// Put a register on new lines below the cursor or line N
+	Put { register: Option<Text> },
+	/// Rewrite line indentation to follow expandtab and tabstop
+	Retab,
+	/// Like retab, also turning all-space indentation into tabs
+	RetabForce,
+	/// Sort lines; the u flag drops duplicates
+	Sort { flags: Option<Text> },
+	/// Remove adjacent duplicate lines
+	Uniq,
+	/// List the changes in the undo history
+	UndoList,
+	/// Undo, or jump to change N of the undo list
+	UndoTo { seq: Option<Text> },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BuiltinCommandRoot)]
@@ -356,13 +490,14 @@ pub enum BuiltinCommand {
 pub enum CommandId {
 	Builtin(BuiltinCommand),
 	Plugin(String),
+	Host(String),
 }
 
 impl CommandId {
 	pub fn display_text(&self) -> String {
 		match self {
 			Self::Builtin(command) => command.id(),
-			Self::Plugin(id) => id.clone(),
+			Self::Plugin(id) | Self::Host(id) => id.clone(),
 		}
 	}
 }
@@ -371,6 +506,7 @@ impl CommandId {
 pub enum CommandCategory {
 	Builtin(BuiltinCommandCategory),
 	Plugin,
+	Host,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -387,6 +523,8 @@ impl CommandCategoryInfo {
 	pub fn plugin(label: impl Into<String>) -> Self {
 		Self { kind: CommandCategory::Plugin, label: label.into() }
 	}
+
+	pub fn host(label: impl Into<String>) -> Self { Self { kind: CommandCategory::Host, label: label.into() } }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -473,28 +611,80 @@ impl BuiltinCommand {
 			Self::Mode(ModeCommand::OpenBelow) => Some(AppAction::Editor(EditorAction::OpenLineBelowInsert)),
 			Self::Mode(ModeCommand::OpenAbove) => Some(AppAction::Editor(EditorAction::OpenLineAboveInsert)),
 			Self::Mode(ModeCommand::Command) => Some(AppAction::Editor(EditorAction::EnterCommandMode)),
+			Self::Mode(ModeCommand::Palette) => Some(AppAction::Editor(EditorAction::EnterCommandPalette)),
+			Self::Mode(ModeCommand::Search) => Some(AppAction::Editor(EditorAction::EnterSearchMode)),
 			Self::Mode(ModeCommand::Visual) => Some(AppAction::Editor(EditorAction::EnterVisualMode)),
 			Self::Mode(ModeCommand::VisualLine) => Some(AppAction::Editor(EditorAction::EnterVisualLineMode)),
 			Self::Mode(ModeCommand::VisualBlock) => Some(AppAction::Editor(EditorAction::EnterVisualBlockMode)),
 			Self::Edit(EditCommand::Undo) => Some(AppAction::Editor(EditorAction::Undo)),
+			Self::Edit(EditCommand::UndoAll) => Some(AppAction::Editor(EditorAction::UndoAll)),
 			Self::Edit(EditCommand::Redo) => Some(AppAction::Editor(EditorAction::Redo)),
 			Self::Cursor(CursorCommand::Left) => Some(AppAction::Editor(EditorAction::MoveLeft)),
 			Self::Cursor(CursorCommand::LineStart) => Some(AppAction::Editor(EditorAction::MoveLineStart)),
 			Self::Cursor(CursorCommand::LineEnd) => Some(AppAction::Editor(EditorAction::MoveLineEnd)),
+			Self::Cursor(CursorCommand::WordForward) => {
+				Some(AppAction::Editor(EditorAction::MoveByMotion(Motion::WordForward)))
+			}
+			Self::Cursor(CursorCommand::WordEnd) => {
+				Some(AppAction::Editor(EditorAction::MoveByMotion(Motion::WordEnd)))
+			}
 			Self::Cursor(CursorCommand::Down) => Some(AppAction::Editor(EditorAction::MoveDown)),
 			Self::Cursor(CursorCommand::Up) => Some(AppAction::Editor(EditorAction::MoveUp)),
 			Self::Cursor(CursorCommand::Right) => Some(AppAction::Editor(EditorAction::MoveRight)),
 			Self::Cursor(CursorCommand::FileStart) => Some(AppAction::Editor(EditorAction::MoveFileStart)),
 			Self::Cursor(CursorCommand::FileEnd) => Some(AppAction::Editor(EditorAction::MoveFileEnd)),
+			Self::Cursor(CursorCommand::NextChange) => Some(AppAction::Editor(EditorAction::MoveNextChange)),
+			Self::Cursor(CursorCommand::PrevChange) => Some(AppAction::Editor(EditorAction::MovePrevChange)),
+			Self::Cursor(CursorCommand::OlderChangePosition) => {
+				Some(AppAction::Editor(EditorAction::MoveOlderChangePosition))
+			}
+			Self::Cursor(CursorCommand::NewerChangePosition) => {
+				Some(AppAction::Editor(EditorAction::MoveNewerChangePosition))
+			}
+			Self::Cursor(CursorCommand::NextMisspelling) => {
+				Some(AppAction::Editor(EditorAction::MoveNextMisspelling))
+			}
+			Self::Cursor(CursorCommand::PrevMisspelling) => {
+				Some(AppAction::Editor(EditorAction::MovePrevMisspelling))
+			}
 			Self::Edit(EditCommand::JoinLineBelow) => Some(AppAction::Editor(EditorAction::JoinLineBelow)),
 			Self::Edit(EditCommand::CutChar) => Some(AppAction::Editor(EditorAction::CutCharToSlot)),
+			Self::Edit(EditCommand::SubstituteChar) => Some(AppAction::Editor(EditorAction::SubstituteCharInsert)),
+			Self::Edit(EditCommand::ChangeLine) => Some(AppAction::Editor(EditorAction::ChangeLineInsert)),
 			Self::Edit(EditCommand::Paste) => Some(AppAction::Editor(EditorAction::PasteSlotAfterCursor)),
+			Self::Edit(EditCommand::PasteIndentBelow) => {
+				Some(AppAction::Editor(EditorAction::PasteSlotWithIndentBelow))
+			}
+			Self::Edit(EditCommand::PasteIndentAbove) => {
+				Some(AppAction::Editor(EditorAction::PasteSlotWithIndentAbove))
+			}
+			Self::Edit(EditCommand::DeleteWord) => {
+				Some(AppAction::Editor(EditorAction::DeleteMotionToSlot(Motion::WordForward)))
+			}
+			Self::Edit(EditCommand::DeleteToSearch) => Some(AppAction::Editor(EditorAction::BeginDeleteToSearch)),
+			Self::Edit(EditCommand::MoveLinesDown) => Some(AppAction::Editor(EditorAction::MoveLinesDown)),
+			Self::Edit(EditCommand::DuplicateLines) => Some(AppAction::Editor(EditorAction::DuplicateLines)),
+			Self::Edit(EditCommand::MoveLinesUp) => Some(AppAction::Editor(EditorAction::MoveLinesUp)),
+			Self::Edit(EditCommand::RepeatLastInsert) => Some(AppAction::Editor(EditorAction::RepeatLastInsert)),
+			Self::Edit(EditCommand::SpellGood) => Some(AppAction::Editor(EditorAction::MarkSpellWordGood)),
+			Self::Edit(EditCommand::SpellSuggest) => Some(AppAction::Editor(EditorAction::SuggestSpelling)),
+			Self::Edit(EditCommand::DeleteWordEnd) => {
+				Some(AppAction::Editor(EditorAction::DeleteMotionToSlot(Motion::WordEnd)))
+			}
 			Self::Buffer(BufferCommand::Prev) => Some(AppAction::Buffer(BufferAction::SwitchPrev)),
 			Self::Buffer(BufferCommand::Next) => Some(AppAction::Buffer(BufferAction::SwitchNext)),
+			Self::Buffer(BufferCommand::Alternate) => Some(AppAction::Buffer(BufferAction::SwitchAlternate)),
+			Self::Buffer(BufferCommand::Reopen) => Some(AppAction::Buffer(BufferAction::ReopenClosed)),
 			Self::Window(WindowCommand::FocusLeft) => Some(AppAction::Window(WindowAction::FocusLeft)),
 			Self::Window(WindowCommand::FocusDown) => Some(AppAction::Window(WindowAction::FocusDown)),
 			Self::Window(WindowCommand::FocusUp) => Some(AppAction::Window(WindowAction::FocusUp)),
 			Self::Window(WindowCommand::FocusRight) => Some(AppAction::Window(WindowAction::FocusRight)),
+			Self::Window(WindowCommand::ZoomToggle) => Some(AppAction::Window(WindowAction::ZoomToggle)),
+			Self::View(ViewCommand::FoldOpen) => Some(AppAction::Editor(EditorAction::OpenFold)),
+			Self::View(ViewCommand::FoldClose) => Some(AppAction::Editor(EditorAction::CloseFold)),
+			Self::View(ViewCommand::FoldToggle) => Some(AppAction::Editor(EditorAction::ToggleFold)),
+			Self::View(ViewCommand::FoldOpenAll) => Some(AppAction::Editor(EditorAction::OpenAllFolds)),
+			Self::View(ViewCommand::FoldCloseAll) => Some(AppAction::Editor(EditorAction::CloseAllFolds)),
 			Self::View(ViewCommand::ScrollDown) => Some(AppAction::Editor(EditorAction::ScrollViewDown)),
 			Self::View(ViewCommand::ScrollUp) => Some(AppAction::Editor(EditorAction::ScrollViewUp)),
 			Self::View(ViewCommand::ScrollHalfPageDown) => {
@@ -525,8 +715,16 @@ impl BuiltinCommand {
 			Self::Cursor(CursorCommand::Up) => Some(AppAction::Editor(EditorAction::MoveUp)),
 			Self::Cursor(CursorCommand::LineStart) => Some(AppAction::Editor(EditorAction::MoveLineStart)),
 			Self::Cursor(CursorCommand::LineEnd) => Some(AppAction::Editor(EditorAction::MoveLineEnd)),
+			Self::Cursor(CursorCommand::WordForward) => {
+				Some(AppAction::Editor(EditorAction::MoveByMotion(Motion::WordForward)))
+			}
+			Self::Cursor(CursorCommand::WordEnd) => {
+				Some(AppAction::Editor(EditorAction::MoveByMotion(Motion::WordEnd)))
+			}
 			Self::Cursor(CursorCommand::FileStart) => Some(AppAction::Editor(EditorAction::MoveFileStart)),
 			Self::Cursor(CursorCommand::FileEnd) => Some(AppAction::Editor(EditorAction::MoveFileEnd)),
+			Self::Cursor(CursorCommand::NextChange) => Some(AppAction::Editor(EditorAction::MoveNextChange)),
+			Self::Cursor(CursorCommand::PrevChange) => Some(AppAction::Editor(EditorAction::MovePrevChange)),
 			Self::View(ViewCommand::ScrollDown) => Some(AppAction::Editor(EditorAction::ScrollViewDown)),
 			Self::View(ViewCommand::ScrollUp) => Some(AppAction::Editor(EditorAction::ScrollViewUp)),
 			Self::View(ViewCommand::ScrollHalfPageDown) => {
@@ -563,6 +761,10 @@ impl BuiltinCommand {
 			}
 			Self::Visual(VisualCommand::Left) => Some(AppAction::Editor(EditorAction::MoveLeftInVisual)),
 			Self::Visual(VisualCommand::Right) => Some(AppAction::Editor(EditorAction::MoveRightInVisual)),
+			Self::Visual(VisualCommand::Fold) => Some(AppAction::Editor(EditorAction::FoldVisualSelection)),
+			Self::Visual(VisualCommand::SearchSelection) => {
+				Some(AppAction::Editor(EditorAction::SearchVisualSelection))
+			}
 			_ => None,
 		}
 	}
@@ -572,6 +774,7 @@ impl BuiltinCommand {
 pub enum CommandTarget {
 	Builtin(BuiltinCommand),
 	Plugin { plugin_id: String, command_id: String },
+	Host { command_id: String },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -713,6 +916,18 @@ impl CommandSpec {
 			display_name: None,
 		}
 	}
+
+	fn host(registration: HostCommandRegistration) -> Self {
+		Self {
+			id:           CommandId::Host(registration.id.clone()),
+			category:     CommandCategoryInfo::host(registration.category),
+			description:  registration.description,
+			params:       registration.params,
+			target:       CommandTarget::Host { command_id: registration.id },
+			display_name: Some(normalize_pascal_case_name(registration.default_name.trim()))
+				.filter(|name| !name.is_empty()),
+		}
+	}
 }
 
 fn builtin_params_to_runtime(params: &[BuiltinCommandParamSpec]) -> Vec<CommandParamSpec> {
@@ -754,9 +969,12 @@ pub struct CommandPaletteMatch {
 	pub command_id:                CommandId,
 	pub command_id_label:          String,
 	pub description:               String,
+	// Normal mode keys bound to the command, comma separated; empty when unbound.
+	pub key_binding:               String,
 	pub name_match_indices:        Vec<usize>,
 	pub command_id_match_indices:  Vec<usize>,
 	pub description_match_indices: Vec<usize>,
+	pub key_binding_match_indices: Vec<usize>,
 	pub is_error:                  bool,
 }
 
@@ -797,6 +1015,7 @@ struct CommandPaletteCandidate {
 	command_id:       CommandId,
 	command_id_label: String,
 	description:      String,
+	key_binding:      String,
 	is_error:         bool,
 }
 
@@ -1015,6 +1234,18 @@ impl CommandRegistry {
 		Ok(())
 	}
 
+	pub fn register_host_command(&mut self, registration: HostCommandRegistration) -> Result<(), String> {
+		let command_id = CommandId::Host(registration.id.clone());
+		if BuiltinCommand::from_id(registration.id.as_str()).is_some()
+			|| registration.id.starts_with("plugin.")
+			|| self.commands.contains_key(&command_id)
+		{
+			return Err(format!("duplicate command id: {}", registration.id));
+		}
+		self.commands.insert(command_id, CommandSpec::host(registration));
+		Ok(())
+	}
+
 	fn defer_keymap_binding(
 		&mut self,
 		scope: KeymapScope,
@@ -1274,9 +1505,11 @@ impl CommandRegistry {
 						command_id:                candidate.command_id,
 						command_id_label:          candidate.command_id_label,
 						description:               candidate.description,
+						key_binding:               candidate.key_binding,
 						name_match_indices:        Vec::new(),
 						command_id_match_indices:  Vec::new(),
 						description_match_indices: Vec::new(),
+						key_binding_match_indices: Vec::new(),
 						is_error:                  candidate.is_error,
 					}));
 				}
@@ -1286,16 +1519,23 @@ impl CommandRegistry {
 					candidate.alternate_name.as_deref().and_then(|text| frizbee_match(query, text));
 				let command_match = frizbee_match(query, candidate.command_id_label.as_str());
 				let description_match = frizbee_match(query, candidate.description.as_str());
+				let key_binding_match = frizbee_match(query, candidate.key_binding.as_str());
 				let mut score = name_match.as_ref().map(|(score, _)| *score).unwrap_or_default();
 				score = score.max(alternate_name_match.as_ref().map(|(score, _)| *score).unwrap_or_default());
 				score = score.max(command_match.as_ref().map(|(score, _)| *score).unwrap_or_default());
 				score = score.max(description_match.as_ref().map(|(score, _)| *score).unwrap_or_default());
+				// Keys only decide whether a command shows up, so short queries that
+				// happen to be bound keys still rank by name and description.
+				if score == 0 {
+					score = key_binding_match.as_ref().map(|(score, _)| *score).unwrap_or_default();
+				}
 				if score == 0 {
 					return None;
 				}
 				let name_match_indices = name_match.map(|(_, indices)| indices).unwrap_or_default();
 				let command_id_match_indices = command_match.map(|(_, indices)| indices).unwrap_or_default();
 				let description_match_indices = description_match.map(|(_, indices)| indices).unwrap_or_default();
+				let key_binding_match_indices = key_binding_match.map(|(_, indices)| indices).unwrap_or_default();
 
 				Some((score, CommandPaletteMatch {
 					name: candidate.name,
@@ -1303,9 +1543,11 @@ impl CommandRegistry {
 					command_id: candidate.command_id,
 					command_id_label: candidate.command_id_label,
 					description: candidate.description,
+					key_binding: candidate.key_binding,
 					name_match_indices,
 					command_id_match_indices,
 					description_match_indices,
+					key_binding_match_indices,
 					is_error: candidate.is_error,
 				}))
 			})
@@ -1373,6 +1615,11 @@ impl CommandRegistry {
 					}
 					(None, None, None) => "invalid command".to_string(),
 				},
+				key_binding:      alias
+					.resolved_command_id
+					.as_ref()
+					.map(|command_id| self.normal_key_binding_label(command_id))
+					.unwrap_or_default(),
 				is_error:         alias.error.is_some(),
 			});
 		}
@@ -1383,13 +1630,14 @@ impl CommandRegistry {
 			.filter(|spec| !aliased_command_ids.contains(&spec.id))
 			.cloned()
 			.collect::<Vec<_>>();
-		unaliased_specs.sort_by(|left, right| left.id.display_text().cmp(&right.id.display_text()));
+		unaliased_specs.sort_by_key(|spec| spec.id.display_text());
 		for spec in unaliased_specs {
 			candidates.push(CommandPaletteCandidate {
 				name:             spec.display_name.clone().unwrap_or_default(),
 				alternate_name:   None,
 				completion:       spec.id.display_text(),
 				command_id_label: format_command_palette_command_label(&spec.id, spec.params.as_slice()),
+				key_binding:      self.normal_key_binding_label(&spec.id),
 				command_id:       spec.id,
 				description:      spec.description,
 				is_error:         false,
@@ -1410,7 +1658,16 @@ impl CommandRegistry {
 					params:     ResolvedParams::default(),
 				})
 			}
-			RunDirective::PluginInvocation { .. } | RunDirective::Unresolved(_) => None,
+			RunDirective::Unresolved(raw) => {
+				let spec = self.commands.get(&CommandId::Host(raw.clone()))?;
+				Some(ResolvedCommand {
+					command_id: spec.id.clone(),
+					target:     spec.target.clone(),
+					argv:       Vec::new(),
+					params:     ResolvedParams::default(),
+				})
+			}
+			RunDirective::PluginInvocation { .. } => None,
 		}
 	}
 
@@ -1435,6 +1692,16 @@ impl CommandRegistry {
 		})
 	}
 
+	fn normal_key_binding_label(&self, command_id: &CommandId) -> String {
+		self
+			.normal_bindings
+			.iter()
+			.filter(|binding| binding.command_id == *command_id)
+			.map(|binding| render_normal_sequence(binding.keys.as_slice()))
+			.collect::<Vec<_>>()
+			.join(", ")
+	}
+
 	fn bindings(&self, scope: KeymapScope) -> &[ScopedKeyBinding] {
 		match scope {
 			KeymapScope::ModeNormal => self.normal_bindings.as_slice(),
@@ -1598,7 +1865,11 @@ impl CommandRegistry {
 				argv_prefix: Vec::new(),
 			});
 		}
-		if let Some(spec) = self.commands.get(&CommandId::Plugin(token.to_string())) {
+		if let Some(spec) = self
+			.commands
+			.get(&CommandId::Plugin(token.to_string()))
+			.or_else(|| self.commands.get(&CommandId::Host(token.to_string())))
+		{
 			return Some(CommandTokenResolution {
 				command_id:  spec.id.clone(),
 				target:      spec.target.clone(),
@@ -1926,6 +2197,15 @@ impl KeyBindingView for ScopedKeyBinding {
 	fn desc(&self) -> Option<&str> { self.desc.as_deref() }
 }
 
+#[derive(Debug, Clone)]
+pub struct HostCommandRegistration {
+	pub id:           String,
+	pub default_name: String,
+	pub category:     String,
+	pub description:  String,
+	pub params:       Vec<CommandParamSpec>,
+}
+
 #[derive(Debug, Clone)]
 pub struct PluginCommandRegistration {
 	pub id:           String,
@@ -2022,6 +2302,7 @@ where
 				CommandId::Plugin(command_id) => {
 					RunDirective::PluginInvocation { plugin_name: command_id.trim_start_matches("plugin.").to_string() }
 				}
+				CommandId::Host(command_id) => RunDirective::Unresolved(command_id.clone()),
 			},
 			args: binding.args().to_vec(),
 			desc: binding.desc().map(ToString::to_string).or_else(|| Some(spec.description.clone())),
@@ -2152,6 +2433,14 @@ fn parse_normal_sequence(input: &str) -> Result<Vec<NormalSequenceKey>, String>
 				result.push(NormalSequenceKey::Ctrl(ctrl_char));
 				continue;
 			}
+			if let Some(rest) = token.strip_prefix("A-").or_else(|| token.strip_prefix("M-")) {
+				let mut token_chars = rest.chars();
+				let (Some(alt_char), None) = (token_chars.next(), token_chars.next()) else {
+					return Err(format!("unsupported alt token: <{}>", token));
+				};
+				result.push(NormalSequenceKey::Alt(alt_char));
+				continue;
+			}
 			return Err(format!("unsupported token: <{}>", token));
 		}
 		result.push(NormalSequenceKey::Char(ch));
@@ -2178,6 +2467,7 @@ fn render_normal_sequence(keys: &[NormalSequenceKey]) -> String {
 			NormalSequenceKey::Down => "<Down>".to_string(),
 			NormalSequenceKey::Char(ch) => ch.to_string(),
 			NormalSequenceKey::Ctrl(ch) => format!("<C-{}>", ch),
+			NormalSequenceKey::Alt(ch) => format!("<A-{}>", ch),
 		})
 		.collect::<Vec<_>>()
 		.join("")
@@ -2220,6 +2510,15 @@ mod tests {
 		assert_eq!(keys, vec![NormalSequenceKey::Leader, NormalSequenceKey::Tab, NormalSequenceKey::Ctrl('h'),]);
 	}
 
+	#[test]
+	fn parse_normal_sequence_should_support_alt_tokens() {
+		let keys = parse_normal_sequence("<A-j><M-K>").expect("sequence should parse");
+
+		assert_eq!(keys, vec![NormalSequenceKey::Alt('j'), NormalSequenceKey::Alt('K')]);
+		assert_eq!(render_normal_sequence(keys.as_slice()), "<A-j><A-K>");
+		assert!(parse_normal_sequence("<A-jk>").is_err());
+	}
+
 	#[test]
 	fn config_should_reject_conflicting_normal_binding() {
 		let mut registry = CommandRegistry::with_defaults();
@@ -2539,10 +2838,16 @@ mod tests {
 
 		let hints = registry.key_hints(KeymapScope::ModeNormal, &[NormalSequenceKey::Char('g')]);
 
-		assert_eq!(hints.len(), 1);
-		assert_eq!(hints[0].key, "g");
-		assert_eq!(hints[0].summary, "Move to file start");
-		assert!(!hints[0].is_prefix);
+		assert_eq!(hints.len(), 4);
+		assert!(
+			hints.iter().any(|hint| hint.key == "g" && hint.summary == "Move to file start" && !hint.is_prefix)
+		);
+		assert!(hints.iter().any(|hint| hint.key == ";" && hint.summary == "Jump to older change position"));
+		assert!(
+			hints
+				.iter()
+				.any(|hint| hint.key == "d" && hint.summary == "Jump to definition under cursor" && !hint.is_prefix)
+		);
 	}
 
 	#[test]
@@ -2567,8 +2872,8 @@ mod tests {
 		let hints = registry.key_hints(KeymapScope::ModeNormal, &[NormalSequenceKey::Char('g')]);
 
 		assert!(errors.is_empty());
-		assert_eq!(hints.len(), 1);
-		assert_eq!(hints[0].summary, "Jump to beginning");
+		assert_eq!(hints.len(), 4);
+		assert!(hints.iter().any(|hint| hint.key == "g" && hint.summary == "Jump to beginning"));
 	}
 
 	#[test]
@@ -2642,6 +2947,26 @@ mod tests {
 		}));
 	}
 
+	#[test]
+	fn command_palette_should_list_and_match_normal_key_bindings() {
+		let registry = CommandRegistry::with_defaults();
+		let delete_line = CommandId::Builtin(BuiltinCommand::Buffer(BufferCommand::DeleteLine));
+
+		let everything = registry.command_palette_matches("", usize::MAX);
+		assert!(everything.iter().any(|item| item.command_id == delete_line && item.key_binding == "dd"));
+		assert!(everything.iter().any(|item| {
+			item.command_id == CommandId::Builtin(BuiltinCommand::Window(WindowCommand::SplitVertical))
+				&& item.key_binding == "<leader>wv"
+		}));
+
+		let item = registry
+			.command_palette_matches("dd", 16)
+			.into_iter()
+			.find(|item| item.command_id == delete_line)
+			.expect("delete line should match its keys");
+		assert_eq!(item.key_binding_match_indices, vec![0, 1]);
+	}
+
 	#[test]
 	fn command_palette_should_show_param_summary_in_command_column() {
 		let registry = CommandRegistry::with_defaults();
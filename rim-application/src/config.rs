@@ -4,7 +4,7 @@ use anyhow::{Context, Result};
 use rim_paths::user_config_root;
 use serde::{Deserialize, Serialize};
 
-use crate::{command::{CommandAliasConfig, CommandAliasSection, CommandConfigError, CommandConfigFile, CommandKeymapSection, CommandRegistry, KeymapBindingConfig, ModeKeymapSections, OverlayKeymapSections}, defaults, state::{NotificationLevel, RimState}};
+use crate::{command::{CommandAliasConfig, CommandAliasSection, CommandConfigError, CommandConfigFile, CommandKeymapSection, CommandRegistry, KeymapBindingConfig, ModeKeymapSections, OverlayKeymapSections}, defaults, state::{IndentSettings, LanguageServerConfig, NotificationLevel, RimState}};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct ConfigLoadError {
@@ -105,8 +105,18 @@ pub fn apply_all_configs(state: &mut RimState) -> Vec<String> {
 		Ok(Some(config)) => {
 			state.workbench.leader_key = config.editor.leader_key;
 			state.workbench.cursor_scroll_threshold = config.editor.cursor_scroll_threshold;
+			state.workbench.cursor_side_scroll_threshold = config.editor.cursor_side_scroll_threshold;
 			state.workbench.key_hints_width = config.editor.key_hints_width;
 			state.workbench.key_hints_max_height = config.editor.key_hints_max_height;
+			state.workbench.key_timeout_ms = config.editor.key_timeout_ms;
+			state.workbench.persist_session = config.editor.persist_session;
+			state.workbench.atomic_save = config.editor.atomic_save;
+			state.workbench.backup = config.editor.backup;
+			state.workbench.date_format = config.editor.date_format;
+			state.workbench.status_line_format = config.editor.status_line;
+			state.workbench.detect_indent = config.editor.detect_indent;
+			state.workbench.language_servers = config.lsp.servers;
+			state.workbench.filetype_indent_settings.extend(config.indent);
 		}
 		Ok(None) => {}
 		Err(err) => {
@@ -155,8 +165,18 @@ pub fn reset_config_state_to_defaults(state: &mut RimState) {
 	let default_editor = defaults::default_editor_config();
 	state.workbench.leader_key = default_editor.editor.leader_key;
 	state.workbench.cursor_scroll_threshold = default_editor.editor.cursor_scroll_threshold;
+	state.workbench.cursor_side_scroll_threshold = default_editor.editor.cursor_side_scroll_threshold;
 	state.workbench.key_hints_width = default_editor.editor.key_hints_width;
 	state.workbench.key_hints_max_height = default_editor.editor.key_hints_max_height;
+	state.workbench.key_timeout_ms = default_editor.editor.key_timeout_ms;
+	state.workbench.persist_session = default_editor.editor.persist_session;
+	state.workbench.atomic_save = default_editor.editor.atomic_save;
+	state.workbench.backup = default_editor.editor.backup;
+	state.workbench.date_format = default_editor.editor.date_format.clone();
+	state.workbench.status_line_format = default_editor.editor.status_line.clone();
+	state.workbench.detect_indent = default_editor.editor.detect_indent;
+	state.workbench.language_servers = default_editor.lsp.servers.clone();
+	state.workbench.filetype_indent_settings = default_editor.indent.clone();
 	state.workbench.command_registry = CommandRegistry::with_defaults();
 }
 
@@ -400,28 +420,65 @@ fn collect_command_alias_section(
 pub(crate) struct EditorConfigFile {
 	#[serde(default)]
 	pub editor: EditorConfigSection,
+	#[serde(default)]
+	pub lsp:    LspConfigSection,
+	// Keyed by filetype; an entry replaces the preset one for that filetype.
+	#[serde(default)]
+	pub indent: HashMap<String, IndentSettings>,
+}
+
+// Language servers are opt-in; without this section no server is started.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LspConfigSection {
+	#[serde(default)]
+	pub servers: Vec<LanguageServerConfig>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct EditorConfigSection {
 	#[serde(default = "default_leader_key")]
-	pub leader_key:              char,
+	pub leader_key:                   char,
 	#[serde(default = "default_cursor_scroll_threshold")]
-	pub cursor_scroll_threshold: u16,
+	pub cursor_scroll_threshold:      u16,
+	#[serde(default = "default_cursor_side_scroll_threshold")]
+	pub cursor_side_scroll_threshold: u16,
 	#[serde(default = "default_key_hints_width")]
-	pub key_hints_width:         u16,
+	pub key_hints_width:              u16,
 	#[serde(default = "default_key_hints_max_height")]
-	pub key_hints_max_height:    u16,
+	pub key_hints_max_height:         u16,
+	#[serde(default = "default_key_timeout_ms")]
+	pub key_timeout_ms:               u64,
+	#[serde(default = "default_persist_session")]
+	pub persist_session:              bool,
+	#[serde(default = "default_atomic_save")]
+	pub atomic_save:                  bool,
+	#[serde(default = "default_backup")]
+	pub backup:                       bool,
+	#[serde(default = "default_date_format")]
+	pub date_format:                  String,
+	#[serde(default = "default_status_line")]
+	pub status_line:                  String,
+	#[serde(default = "default_detect_indent")]
+	pub detect_indent:                bool,
 }
 
 impl Default for EditorConfigSection {
 	fn default() -> Self {
 		Self {
-			leader_key:              default_leader_key(),
-			cursor_scroll_threshold: default_cursor_scroll_threshold(),
-			key_hints_width:         default_key_hints_width(),
-			key_hints_max_height:    default_key_hints_max_height(),
+			leader_key:                   default_leader_key(),
+			cursor_scroll_threshold:      default_cursor_scroll_threshold(),
+			cursor_side_scroll_threshold: default_cursor_side_scroll_threshold(),
+			key_hints_width:              default_key_hints_width(),
+			key_hints_max_height:         default_key_hints_max_height(),
+			key_timeout_ms:               default_key_timeout_ms(),
+			persist_session:              default_persist_session(),
+			atomic_save:                  default_atomic_save(),
+			backup:                       default_backup(),
+			date_format:                  default_date_format(),
+			status_line:                  default_status_line(),
+			detect_indent:                default_detect_indent(),
 		}
 	}
 }
@@ -432,10 +489,28 @@ fn default_cursor_scroll_threshold() -> u16 {
 	defaults::default_editor_config().editor.cursor_scroll_threshold
 }
 
+fn default_cursor_side_scroll_threshold() -> u16 {
+	defaults::default_editor_config().editor.cursor_side_scroll_threshold
+}
+
 fn default_key_hints_width() -> u16 { defaults::default_editor_config().editor.key_hints_width }
 
 fn default_key_hints_max_height() -> u16 { defaults::default_editor_config().editor.key_hints_max_height }
 
+fn default_key_timeout_ms() -> u64 { defaults::default_editor_config().editor.key_timeout_ms }
+
+fn default_persist_session() -> bool { defaults::default_editor_config().editor.persist_session }
+
+fn default_atomic_save() -> bool { defaults::default_editor_config().editor.atomic_save }
+
+fn default_backup() -> bool { defaults::default_editor_config().editor.backup }
+
+fn default_date_format() -> String { defaults::default_editor_config().editor.date_format.clone() }
+
+fn default_status_line() -> String { defaults::default_editor_config().editor.status_line.clone() }
+
+fn default_detect_indent() -> bool { defaults::default_editor_config().editor.detect_indent }
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -508,6 +583,7 @@ leader_key = ","
 cursor_scroll_threshold = 3
 key_hints_width = 64
 key_hints_max_height = 28
+key_timeout_ms = 20
 "#,
 		)
 		.expect("editor config should be written");
@@ -519,6 +595,63 @@ key_hints_max_height = 28
 		assert_eq!(loaded.editor.cursor_scroll_threshold, 3);
 		assert_eq!(loaded.editor.key_hints_width, 64);
 		assert_eq!(loaded.editor.key_hints_max_height, 28);
+		assert_eq!(loaded.editor.key_timeout_ms, 20);
+		let _ = fs::remove_dir_all(config_dir);
+	}
+
+	#[test]
+	fn editor_config_should_parse_language_servers() {
+		let config_dir = unique_temp_config_dir("editor-lsp");
+		let editor_path = config_dir.join("editor.toml");
+		fs::create_dir_all(config_dir.as_path()).expect("config directory should be created");
+		fs::write(
+			editor_path.as_path(),
+			r#"
+[[lsp.servers]]
+filetypes = ["rust"]
+command = ["rust-analyzer"]
+"#,
+		)
+		.expect("editor config should be written");
+
+		let loaded = load_editor_config_from_path(editor_path.as_path())
+			.expect("editor config should load")
+			.expect("config");
+		assert_eq!(loaded.editor, EditorConfigSection::default());
+		assert_eq!(loaded.lsp.servers, vec![LanguageServerConfig {
+			filetypes: vec!["rust".to_string()],
+			command:   vec!["rust-analyzer".to_string()],
+		}]);
+		let _ = fs::remove_dir_all(config_dir);
+	}
+
+	#[test]
+	fn editor_config_should_parse_filetype_indent_overrides() {
+		let config_dir = unique_temp_config_dir("editor-indent");
+		let editor_path = config_dir.join("editor.toml");
+		fs::create_dir_all(config_dir.as_path()).expect("config directory should be created");
+		fs::write(
+			editor_path.as_path(),
+			r#"
+[indent.go]
+tabstop = 8
+shiftwidth = 8
+expandtab = true
+"#,
+		)
+		.expect("editor config should be written");
+
+		let loaded = load_editor_config_from_path(editor_path.as_path())
+			.expect("editor config should load")
+			.expect("config");
+		assert_eq!(
+			loaded.indent.get("go"),
+			Some(&IndentSettings { tab_stop: 8, shift_width: 8, expand_tab: true })
+		);
+		assert_eq!(
+			defaults::default_editor_config().indent.get("go"),
+			Some(&IndentSettings { tab_stop: 4, shift_width: 4, expand_tab: false })
+		);
 		let _ = fs::remove_dir_all(config_dir);
 	}
 
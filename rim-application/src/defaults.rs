@@ -1,8 +1,8 @@
-use std::sync::OnceLock;
+use std::{collections::HashMap, sync::OnceLock};
 
 use serde::Deserialize;
 
-use crate::{command::CommandConfigFile, config::{EditorConfigFile, EditorConfigSection}};
+use crate::{command::CommandConfigFile, config::{EditorConfigFile, EditorConfigSection}, state::IndentSettings};
 
 const DEFAULT_KEYMAPS_TOML: &str = include_str!("../presets/keymaps.toml");
 const DEFAULT_COMMANDS_TOML: &str = include_str!("../presets/commands.toml");
@@ -12,15 +12,24 @@ const DEFAULT_EDITOR_TOML: &str = include_str!("../presets/editor.toml");
 #[serde(deny_unknown_fields)]
 struct EditorPresetFile {
 	editor: EditorPresetSection,
+	indent: HashMap<String, IndentSettings>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
 struct EditorPresetSection {
-	leader_key:              char,
-	cursor_scroll_threshold: u16,
-	key_hints_width:         u16,
-	key_hints_max_height:    u16,
+	leader_key:                   char,
+	cursor_scroll_threshold:      u16,
+	cursor_side_scroll_threshold: u16,
+	key_hints_width:              u16,
+	key_hints_max_height:         u16,
+	key_timeout_ms:               u64,
+	persist_session:              bool,
+	atomic_save:                  bool,
+	backup:                       bool,
+	date_format:                  String,
+	status_line:                  String,
+	detect_indent:                bool,
 }
 
 pub(crate) fn default_command_config() -> &'static CommandConfigFile {
@@ -41,11 +50,21 @@ pub(crate) fn default_editor_config() -> &'static EditorConfigFile {
 			.expect("embedded default editor preset should be valid");
 		EditorConfigFile {
 			editor: EditorConfigSection {
-				leader_key:              preset.editor.leader_key,
-				cursor_scroll_threshold: preset.editor.cursor_scroll_threshold,
-				key_hints_width:         preset.editor.key_hints_width,
-				key_hints_max_height:    preset.editor.key_hints_max_height,
+				leader_key:                   preset.editor.leader_key,
+				cursor_scroll_threshold:      preset.editor.cursor_scroll_threshold,
+				cursor_side_scroll_threshold: preset.editor.cursor_side_scroll_threshold,
+				key_hints_width:              preset.editor.key_hints_width,
+				key_hints_max_height:         preset.editor.key_hints_max_height,
+				key_timeout_ms:               preset.editor.key_timeout_ms,
+				persist_session:              preset.editor.persist_session,
+				atomic_save:                  preset.editor.atomic_save,
+				backup:                       preset.editor.backup,
+				date_format:                  preset.editor.date_format,
+				status_line:                  preset.editor.status_line,
+				detect_indent:                preset.editor.detect_indent,
 			},
+			lsp:    Default::default(),
+			indent: preset.indent,
 		}
 	})
 }
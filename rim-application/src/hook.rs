@@ -0,0 +1,78 @@
+use std::fmt;
+
+use crate::state::{BufferId, EditorMode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookEventKind {
+	BufferOpened,
+	BufferSaved,
+	BufferChanged,
+	ModeChanged,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+	BufferOpened { buffer_id: BufferId },
+	BufferSaved { buffer_id: BufferId },
+	BufferChanged { buffer_id: BufferId },
+	ModeChanged { from: EditorMode, to: EditorMode },
+}
+
+impl HookEvent {
+	pub fn kind(&self) -> HookEventKind {
+		match self {
+			Self::BufferOpened { .. } => HookEventKind::BufferOpened,
+			Self::BufferSaved { .. } => HookEventKind::BufferSaved,
+			Self::BufferChanged { .. } => HookEventKind::BufferChanged,
+			Self::ModeChanged { .. } => HookEventKind::ModeChanged,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HookId(u64);
+
+pub type HookCallback = Box<dyn FnMut(&HookEvent)>;
+
+struct RegisteredHook {
+	id:       HookId,
+	kind:     HookEventKind,
+	callback: HookCallback,
+}
+
+#[derive(Default)]
+pub struct HookRegistry {
+	hooks:   Vec<RegisteredHook>,
+	next_id: u64,
+}
+
+impl HookRegistry {
+	pub fn register<F>(&mut self, kind: HookEventKind, callback: F) -> HookId
+	where F: FnMut(&HookEvent) + 'static {
+		self.next_id = self.next_id.saturating_add(1);
+		let id = HookId(self.next_id);
+		self.hooks.push(RegisteredHook { id, kind, callback: Box::new(callback) });
+		id
+	}
+
+	pub fn unregister(&mut self, id: HookId) -> bool {
+		let before = self.hooks.len();
+		self.hooks.retain(|hook| hook.id != id);
+		self.hooks.len() != before
+	}
+
+	pub fn is_empty(&self) -> bool { self.hooks.is_empty() }
+
+	pub fn emit(&mut self, event: &HookEvent) {
+		let kind = event.kind();
+		for hook in self.hooks.iter_mut().filter(|hook| hook.kind == kind) {
+			(hook.callback)(event);
+		}
+	}
+}
+
+impl fmt::Debug for HookRegistry {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("HookRegistry").field("hooks", &self.hooks.len()).finish()
+	}
+}
@@ -3,5 +3,6 @@ pub mod action_handler;
 pub mod command;
 pub mod config;
 mod defaults;
+pub mod hook;
 pub mod ports;
 pub mod state;
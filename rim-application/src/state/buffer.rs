@@ -1,9 +1,15 @@
-use std::{path::PathBuf, time::Instant};
+use std::{ops::Range, path::PathBuf, time::{Instant, SystemTime}};
 
 use rim_domain::editor::EditorOperationError;
 use ropey::Rope;
 
-use super::{BufferId, BufferSwitchDirection, PersistedBufferHistory, RimState, buffer_name_from_path};
+use super::{BufferId, BufferSwitchDirection, ClosedBufferEntry, LineChangeSign, PersistedBufferHistory, RimState, buffer_name_from_path};
+
+pub(crate) const NO_ACTIVE_BUFFER_MESSAGE: &str = "no active buffer";
+// Shared by every way of walking the undo history, in Vim's words.
+const OLDEST_CHANGE_MESSAGE: &str = "already at oldest change";
+const NEWEST_CHANGE_MESSAGE: &str = "already at newest change";
+const CLOSED_BUFFER_CAPACITY: usize = 20;
 
 impl RimState {
 	pub(crate) fn remove_buffer_from_tab_orders(&mut self, buffer_id: BufferId) {
@@ -18,9 +24,30 @@ impl RimState {
 		self.editor.create_buffer(path, text)
 	}
 
+	// Drops the buffer's own indent values so the new filetype's defaults apply,
+	// as a filetype plugin would set them in Vim. An empty name goes back to the
+	// filetype of the path.
+	pub(super) fn set_active_buffer_filetype(&mut self, filetype: &str) -> Option<String> {
+		let buffer_id = self.active_buffer_id()?;
+		let buffer = self.buffers.get_mut(buffer_id)?;
+		buffer.filetype = Some(filetype.to_string()).filter(|filetype| !filetype.is_empty());
+		let filetype = buffer.filetype().unwrap_or_default().to_string();
+		self.workbench.buffer_indent_settings.remove(&buffer_id);
+		Some(filetype)
+	}
+
+	// Shared short-circuit for actions that need a buffer, so a window without one
+	// always reports the same message instead of each action wording its own.
+	pub(crate) fn require_active_buffer(&mut self) -> Option<BufferId> {
+		let buffer_id = self.active_buffer_id().filter(|buffer_id| self.buffers.contains_key(*buffer_id));
+		if buffer_id.is_none() {
+			self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
+		}
+		buffer_id
+	}
+
 	pub fn close_active_buffer(&mut self) {
-		let Some(active_buffer_id) = self.active_buffer_id() else {
-			self.workbench.status_bar.message = "buffer close failed: no active buffer".to_string();
+		let Some(active_buffer_id) = self.require_active_buffer() else {
 			return;
 		};
 		let active_tab_id = self.active_tab;
@@ -33,6 +60,7 @@ impl RimState {
 			return;
 		}
 
+		self.remember_closed_buffer(target_buffer_id);
 		let mut fallback = match self.buffer_order.iter().position(|id| *id == target_buffer_id) {
 			Some(idx) if self.buffer_order.len() > 1 => {
 				if idx > 0 {
@@ -45,8 +73,7 @@ impl RimState {
 		};
 		self.buffer_order.retain(|id| *id != target_buffer_id);
 		self.remove_buffer_from_tab_orders(target_buffer_id);
-		self.workbench.in_flight_internal_saves.remove(&target_buffer_id);
-		self.workbench.ignore_external_change_until.remove(&target_buffer_id);
+		self.forget_closed_buffer(target_buffer_id);
 
 		let _ = self.buffers.remove(target_buffer_id);
 		if fallback.is_none() {
@@ -99,6 +126,7 @@ impl RimState {
 			return false;
 		}
 
+		self.remember_closed_buffer(target_buffer_id);
 		let window_ids = self.tabs.get(&tab_id).map(|tab| tab.windows.clone()).unwrap_or_default();
 		let tab_buffer_order_before =
 			self.tabs.get(&tab_id).map(|tab| tab.buffer_order.clone()).unwrap_or_default();
@@ -140,6 +168,47 @@ impl RimState {
 		removed_globally
 	}
 
+	pub fn take_last_closed_buffer(&mut self) -> Option<ClosedBufferEntry> {
+		let entry = self.workbench.closed_buffers.pop();
+		if entry.is_none() {
+			self.workbench.status_bar.message = "no closed buffer to reopen".to_string();
+		}
+		entry
+	}
+
+	// Untitled buffers have nothing to reopen. The cursor is the one the active
+	// window had in the buffer, if it ever showed it.
+	fn remember_closed_buffer(&mut self, buffer_id: BufferId) {
+		let Some(path) = self.buffers.get(buffer_id).and_then(|buffer| buffer.path.clone()) else {
+			return;
+		};
+		let active_window_id = self.active_window_id();
+		let cursor = match self.windows.get(active_window_id) {
+			Some(window) if window.buffer_id == Some(buffer_id) => window.cursor,
+			_ => self
+				.window_buffer_views
+				.get(&(active_window_id, buffer_id))
+				.map(|view| view.cursor)
+				.unwrap_or_default(),
+		};
+		let closed_buffers = &mut self.workbench.closed_buffers;
+		closed_buffers.retain(|entry| entry.path != path);
+		closed_buffers.push(ClosedBufferEntry { path, cursor });
+		if closed_buffers.len() > CLOSED_BUFFER_CAPACITY {
+			closed_buffers.remove(0);
+		}
+	}
+
+	// Per-buffer bookkeeping that must not outlive the buffer, even when a load
+	// or save for it is still in flight.
+	fn forget_closed_buffer(&mut self, buffer_id: BufferId) {
+		self.workbench.in_flight_internal_saves.remove(&buffer_id);
+		self.workbench.ignore_external_change_until.remove(&buffer_id);
+		self.workbench.loading_buffers.remove(&buffer_id);
+		self.workbench.disk_mtimes.remove(&buffer_id);
+		self.workbench.buffer_indent_settings.remove(&buffer_id);
+	}
+
 	fn try_remove_buffer_globally(&mut self, target_buffer_id: BufferId) -> bool {
 		let still_visible_in_tab = self.tabs.values().any(|tab| tab.buffer_order.contains(&target_buffer_id));
 		let still_bound_to_window =
@@ -149,8 +218,7 @@ impl RimState {
 		}
 
 		self.buffer_order.retain(|id| *id != target_buffer_id);
-		self.workbench.in_flight_internal_saves.remove(&target_buffer_id);
-		self.workbench.ignore_external_change_until.remove(&target_buffer_id);
+		self.forget_closed_buffer(target_buffer_id);
 		self.window_buffer_views.retain(|(_, buffer_id), _| *buffer_id != target_buffer_id);
 		let _ = self.buffers.remove(target_buffer_id);
 		true
@@ -178,6 +246,25 @@ impl RimState {
 		}
 	}
 
+	// Like Vim's Ctrl-^, a count jumps to that buffer number instead.
+	pub fn switch_to_alternate_buffer(&mut self, count: Option<usize>) {
+		let target = match count {
+			Some(number) => self.editor.switch_to_buffer_number(number),
+			None => self.editor.switch_to_alternate_buffer(),
+		};
+		let Some(target) = target else {
+			self.workbench.status_bar.message = match count {
+				Some(number) => format!("buffer {} does not exist", number),
+				None => "no alternate buffer".to_string(),
+			};
+			return;
+		};
+		self.align_active_window_scroll_to_cursor();
+		if let Some(buffer) = self.buffers.get(target) {
+			self.workbench.status_bar.message = format!("buffer {}", buffer.name);
+		}
+	}
+
 	pub fn active_buffer_save_snapshot(
 		&self,
 		path_override: Option<PathBuf>,
@@ -230,10 +317,30 @@ impl RimState {
 
 	pub fn mark_buffer_clean(&mut self, buffer_id: BufferId) { self.editor.mark_buffer_clean(buffer_id); }
 
+	pub fn buffer_line_change_signs(
+		&self,
+		buffer_id: BufferId,
+		rows: Range<usize>,
+	) -> Vec<Option<LineChangeSign>> {
+		self.editor.buffer_line_change_signs(buffer_id, rows)
+	}
+
 	pub fn set_buffer_externally_modified(&mut self, buffer_id: BufferId, externally_modified: bool) {
 		self.editor.set_buffer_externally_modified(buffer_id, externally_modified);
 	}
 
+	pub fn set_buffer_read_only(&mut self, buffer_id: BufferId, read_only: bool) {
+		self.editor.set_buffer_read_only(buffer_id, read_only);
+	}
+
+	pub fn set_buffer_new_file(&mut self, buffer_id: BufferId, new_file: bool) {
+		self.editor.set_buffer_new_file(buffer_id, new_file);
+	}
+
+	pub fn set_buffer_deleted_on_disk(&mut self, buffer_id: BufferId, deleted_on_disk: bool) {
+		self.editor.set_buffer_deleted_on_disk(buffer_id, deleted_on_disk);
+	}
+
 	pub fn clear_buffer_history(&mut self, buffer_id: BufferId) { self.editor.clear_buffer_history(buffer_id); }
 
 	pub fn mark_recent_internal_save(&mut self, buffer_id: BufferId) {
@@ -258,14 +365,43 @@ impl RimState {
 		false
 	}
 
+	pub fn record_buffer_disk_mtime(&mut self, buffer_id: BufferId, mtime: Option<SystemTime>) {
+		match mtime {
+			Some(mtime) => self.workbench.disk_mtimes.insert(buffer_id, mtime),
+			None => self.workbench.disk_mtimes.remove(&buffer_id),
+		};
+	}
+
+	// File buffers with an mtime to compare against; buffers never loaded from
+	// or written to disk have nothing to check.
+	pub fn buffers_with_disk_mtime(&self) -> Vec<(BufferId, PathBuf)> {
+		self
+			.buffers
+			.iter()
+			.filter(|(buffer_id, _)| self.workbench.disk_mtimes.contains_key(buffer_id))
+			.filter_map(|(buffer_id, buffer)| Some((buffer_id, buffer.path.clone()?)))
+			.collect()
+	}
+
+	// Records `mtime` and reports whether it moved since the last load or save.
+	pub fn update_buffer_disk_mtime(&mut self, buffer_id: BufferId, mtime: SystemTime) -> bool {
+		self.workbench.disk_mtimes.insert(buffer_id, mtime).is_some_and(|seen| seen != mtime)
+	}
+
 	pub fn active_buffer_is_externally_modified(&self) -> Option<bool> {
 		self.editor.active_buffer_is_externally_modified()
 	}
 
+	pub fn active_buffer_is_read_only(&self) -> Option<bool> { self.editor.active_buffer_is_read_only() }
+
 	pub fn mark_active_buffer_dirty(&mut self) { self.editor.mark_active_buffer_dirty(); }
 
 	pub fn refresh_buffer_dirty(&mut self, buffer_id: BufferId) { self.editor.refresh_buffer_dirty(buffer_id); }
 
+	pub fn buffer_changedtick(&self, buffer_id: BufferId) -> Option<u64> {
+		self.editor.buffer_changedtick(buffer_id)
+	}
+
 	pub fn apply_edit_entry(
 		&mut self,
 		buffer_id: BufferId,
@@ -278,18 +414,12 @@ impl RimState {
 	pub fn record_history_from_text_diff(
 		&mut self,
 		buffer_id: BufferId,
-		before_text: &Rope,
+		diff: super::RopeTextDiff,
 		before_cursor: super::CursorState,
 		mode_before: super::EditorMode,
 		skip_history: bool,
 	) {
-		self.editor.record_history_from_text_diff(
-			buffer_id,
-			before_text,
-			before_cursor,
-			mode_before,
-			skip_history,
-		);
+		self.editor.record_history_from_text_diff(buffer_id, diff, before_cursor, mode_before, skip_history);
 	}
 
 	pub fn push_buffer_history_entry(&mut self, buffer_id: BufferId, entry: super::BufferHistoryEntry) {
@@ -324,19 +454,47 @@ impl RimState {
 	}
 
 	pub fn undo_active_buffer_edit(&mut self) {
+		if self.require_active_buffer().is_none() {
+			return;
+		}
 		match self.editor.undo_active_buffer_edit() {
 			Ok(()) => {
 				self.align_active_window_scroll_to_cursor();
 				self.workbench.status_bar.message = "undo".to_string();
 			}
 			Err(EditorOperationError::NoActiveBuffer) => {
-				self.workbench.status_bar.message = "undo failed: no active buffer".to_string();
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
+			}
+			Err(EditorOperationError::ActiveBufferMissing) => {
+				self.workbench.status_bar.message = "undo failed: active buffer missing".to_string();
+			}
+			Err(EditorOperationError::NothingToUndo) => {
+				self.workbench.status_bar.message = OLDEST_CHANGE_MESSAGE.to_string();
+			}
+			Err(other) => {
+				self.workbench.status_bar.message = format!("undo failed: {:?}", other);
+			}
+		}
+	}
+
+	pub fn undo_all_active_buffer_edits(&mut self) {
+		if self.require_active_buffer().is_none() {
+			return;
+		}
+		match self.editor.undo_all_active_buffer_edits() {
+			Ok(undone) => {
+				self.align_active_window_scroll_to_cursor();
+				self.workbench.status_bar.message =
+					format!("undo: reverted {} change{}", undone, if undone == 1 { "" } else { "s" });
+			}
+			Err(EditorOperationError::NoActiveBuffer) => {
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
 			}
 			Err(EditorOperationError::ActiveBufferMissing) => {
 				self.workbench.status_bar.message = "undo failed: active buffer missing".to_string();
 			}
 			Err(EditorOperationError::NothingToUndo) => {
-				self.workbench.status_bar.message = "undo: nothing to undo".to_string();
+				self.workbench.status_bar.message = OLDEST_CHANGE_MESSAGE.to_string();
 			}
 			Err(other) => {
 				self.workbench.status_bar.message = format!("undo failed: {:?}", other);
@@ -345,19 +503,22 @@ impl RimState {
 	}
 
 	pub fn redo_active_buffer_edit(&mut self) {
+		if self.require_active_buffer().is_none() {
+			return;
+		}
 		match self.editor.redo_active_buffer_edit() {
 			Ok(()) => {
 				self.align_active_window_scroll_to_cursor();
 				self.workbench.status_bar.message = "redo".to_string();
 			}
 			Err(EditorOperationError::NoActiveBuffer) => {
-				self.workbench.status_bar.message = "redo failed: no active buffer".to_string();
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
 			}
 			Err(EditorOperationError::ActiveBufferMissing) => {
 				self.workbench.status_bar.message = "redo failed: active buffer missing".to_string();
 			}
 			Err(EditorOperationError::NothingToRedo) => {
-				self.workbench.status_bar.message = "redo: nothing to redo".to_string();
+				self.workbench.status_bar.message = NEWEST_CHANGE_MESSAGE.to_string();
 			}
 			Err(other) => {
 				self.workbench.status_bar.message = format!("redo failed: {:?}", other);
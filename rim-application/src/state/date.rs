@@ -0,0 +1,55 @@
+use time::OffsetDateTime;
+
+use super::RimState;
+
+impl RimState {
+	// Inserts before the cursor like `i`, leaving the cursor on the last inserted
+	// char so `u` removes the whole timestamp in one step.
+	pub fn insert_date(&mut self, format: Option<&str>) {
+		if self.require_active_buffer().is_none() {
+			return;
+		}
+		let format = format.unwrap_or(self.workbench.date_format.as_str());
+		let text = format_strftime((self.workbench.clock)(), format);
+		if text.is_empty() {
+			self.workbench.status_bar.message = "date failed: empty format".to_string();
+			return;
+		}
+		for ch in text.chars() {
+			self.editor.insert_char_at_cursor(ch);
+		}
+		self.editor.move_cursor_left();
+		self.align_active_window_scroll_to_cursor();
+		self.workbench.status_bar.message = text;
+	}
+}
+
+// A strftime subset; unknown specifiers are kept verbatim.
+fn format_strftime(time: OffsetDateTime, format: &str) -> String {
+	let mut out = String::new();
+	let mut chars = format.chars();
+	while let Some(ch) = chars.next() {
+		if ch != '%' {
+			out.push(ch);
+			continue;
+		}
+		match chars.next() {
+			Some('Y') => out.push_str(format!("{:04}", time.year()).as_str()),
+			Some('y') => out.push_str(format!("{:02}", time.year().rem_euclid(100)).as_str()),
+			Some('m') => out.push_str(format!("{:02}", u8::from(time.month())).as_str()),
+			Some('d') => out.push_str(format!("{:02}", time.day()).as_str()),
+			Some('H') => out.push_str(format!("{:02}", time.hour()).as_str()),
+			Some('M') => out.push_str(format!("{:02}", time.minute()).as_str()),
+			Some('S') => out.push_str(format!("{:02}", time.second()).as_str()),
+			Some('a') => out.push_str(&time.weekday().to_string()[..3]),
+			Some('b') => out.push_str(&time.month().to_string()[..3]),
+			Some('%') => out.push('%'),
+			Some(other) => {
+				out.push('%');
+				out.push(other);
+			}
+			None => out.push('%'),
+		}
+	}
+	out
+}
@@ -0,0 +1,95 @@
+use std::ops::Range;
+
+use rim_domain::text::compute_line_change_signs;
+
+use super::{LineChangeSign, RimState, SplitAxis, WindowId};
+
+impl RimState {
+	pub fn diff_this(&mut self) {
+		let active_window_id = self.active_window_id();
+		if let Some(window) = self.windows.get_mut(active_window_id) {
+			window.diff = true;
+		}
+	}
+
+	pub fn diff_off(&mut self) {
+		for window_id in self.active_tab_window_ids() {
+			if let Some(window) = self.windows.get_mut(window_id) {
+				window.diff = false;
+			}
+		}
+		self.workbench.status_bar.message = "diff off".to_string();
+	}
+
+	// `:diffsplit` puts the current window in diff mode, then splits it; the
+	// caller opens the other file in the new, active window.
+	pub fn diff_split_active_window(&mut self) {
+		self.diff_this();
+		self.split_active_window(SplitAxis::Vertical);
+		self.diff_this();
+	}
+
+	// A diff window compares against the first other diff window of its tab.
+	pub fn window_diff_partner(&self, window_id: WindowId) -> Option<WindowId> {
+		if !self.windows.get(window_id)?.diff {
+			return None;
+		}
+		let tab = self.tabs.values().find(|tab| tab.windows.contains(&window_id))?;
+		tab
+			.windows
+			.iter()
+			.copied()
+			.find(|other| *other != window_id && self.windows.get(*other).is_some_and(|window| window.diff))
+	}
+
+	// Rows of a diff window that are new, changed or followed by lines that only
+	// its partner has, in the same terms as the change signs.
+	pub fn window_diff_signs(
+		&self,
+		window_id: WindowId,
+		rows: Range<usize>,
+	) -> Option<Vec<Option<LineChangeSign>>> {
+		let partner_id = self.window_diff_partner(window_id)?;
+		let text = &self.buffers.get(self.windows.get(window_id)?.buffer_id?)?.text;
+		let partner_text = &self.buffers.get(self.windows.get(partner_id)?.buffer_id?)?.text;
+		Some(compute_line_change_signs(partner_text, text, rows))
+	}
+
+	// Diff mode replaces the unsaved-change signs in the gutter.
+	pub fn window_line_change_signs(
+		&self,
+		window_id: WindowId,
+		rows: Range<usize>,
+	) -> Vec<Option<LineChangeSign>> {
+		if let Some(signs) = self.window_diff_signs(window_id, rows.clone()) {
+			return signs;
+		}
+		match self.windows.get(window_id).and_then(|window| window.buffer_id) {
+			Some(buffer_id) => self.buffer_line_change_signs(buffer_id, rows),
+			None => vec![None; rows.len()],
+		}
+	}
+
+	// Runs after every action: the other diff windows of the tab follow the
+	// active one's top line.
+	pub fn sync_diff_window_scroll(&mut self) {
+		let active_window_id = self.active_window_id();
+		let Some(scroll_y) =
+			self.windows.get(active_window_id).filter(|window| window.diff).map(|window| window.scroll_y)
+		else {
+			return;
+		};
+		for window_id in self.active_tab_window_ids() {
+			if window_id == active_window_id {
+				continue;
+			}
+			if !self.windows.get(window_id).is_some_and(|window| window.diff) {
+				continue;
+			}
+			let max_scroll = self.window_max_scroll_y(window_id);
+			if let Some(window) = self.editor.windows.get_mut(window_id) {
+				window.scroll_y = scroll_y.min(max_scroll);
+			}
+		}
+	}
+}
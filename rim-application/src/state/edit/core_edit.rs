@@ -1,5 +1,5 @@
 use super::RimState;
-use crate::state::EditorOperationError;
+use crate::state::{CommandRange, EditorOperationError, EditorState, Motion, buffer::NO_ACTIVE_BUFFER_MESSAGE};
 
 impl RimState {
 	pub fn insert_char_at_cursor(&mut self, ch: char) {
@@ -9,11 +9,18 @@ impl RimState {
 	}
 
 	pub fn insert_newline_at_cursor(&mut self) {
+		self.break_insert_history_group();
 		if self.editor.insert_newline_at_cursor() {
 			self.align_active_window_scroll_to_cursor();
 		}
 	}
 
+	pub fn insert_text_at_cursor(&mut self, text: &str) {
+		if self.editor.insert_text_at_cursor(text) {
+			self.align_active_window_scroll_to_cursor();
+		}
+	}
+
 	pub fn open_line_below_at_cursor(&mut self) {
 		if self.editor.open_line_below_at_cursor() {
 			self.align_active_window_scroll_to_cursor();
@@ -27,7 +34,7 @@ impl RimState {
 	}
 
 	pub fn join_line_below_at_cursor(&mut self) {
-		if self.editor.join_line_below_at_cursor() {
+		if self.editor.join_line_below_at_cursor(self.workbench.join_spaces) {
 			self.align_active_window_scroll_to_cursor();
 		}
 	}
@@ -38,6 +45,30 @@ impl RimState {
 		}
 	}
 
+	pub fn complete_word_at_cursor(&mut self, forward: bool) {
+		match self.editor.complete_word_at_cursor(forward) {
+			Ok(()) => {
+				self.align_active_window_scroll_to_cursor();
+				self.workbench.status_bar.message = match self.insert_completion.as_ref() {
+					Some(completion) => match completion.selected {
+						Some(index) => format!("match {} of {}", index + 1, completion.candidates.len()),
+						None => "back at original".to_string(),
+					},
+					None => String::new(),
+				};
+			}
+			Err(EditorOperationError::NoCompletionMatch) => {
+				self.workbench.status_bar.message = "no completion match".to_string();
+			}
+			Err(EditorOperationError::NoActiveBuffer) => {
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
+			}
+			Err(other) => {
+				self.workbench.status_bar.message = format!("completion failed: {:?}", other);
+			}
+		}
+	}
+
 	pub fn cut_current_char_to_slot(&mut self) {
 		match self.editor.cut_current_char_to_slot() {
 			Ok(()) => {
@@ -45,7 +76,7 @@ impl RimState {
 				self.workbench.status_bar.message = "char cut".to_string();
 			}
 			Err(EditorOperationError::NoActiveBuffer) => {
-				self.workbench.status_bar.message = "cut failed: no active buffer".to_string();
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
 			}
 			Err(EditorOperationError::OutOfRange) => {
 				self.workbench.status_bar.message = "cut failed: out of range".to_string();
@@ -59,8 +90,52 @@ impl RimState {
 		}
 	}
 
+	// On an empty line `s` just starts inserting.
+	pub fn substitute_current_char_to_slot(&mut self) {
+		match self.editor.substitute_current_char_to_slot() {
+			Ok(()) | Err(EditorOperationError::NoChar) => self.align_active_window_scroll_to_cursor(),
+			Err(other) => self.workbench.status_bar.message = format!("substitute failed: {:?}", other),
+		}
+	}
+
+	pub fn clear_current_line_to_slot(&mut self) {
+		match self.editor.clear_current_line_to_slot() {
+			Ok(()) => self.align_active_window_scroll_to_cursor(),
+			Err(other) => self.workbench.status_bar.message = format!("change failed: {:?}", other),
+		}
+	}
+
+	pub fn delete_motion_to_slot(&mut self, motion: Motion, count: usize) {
+		match self.editor.delete_motion_to_slot(motion, count) {
+			Ok(()) => {
+				self.align_active_window_scroll_to_cursor();
+				self.workbench.status_bar.message = "deleted".to_string();
+			}
+			Err(EditorOperationError::NoActiveBuffer) => {
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
+			}
+			Err(EditorOperationError::NoChar) => {
+				self.workbench.status_bar.message = "delete failed: no char".to_string();
+			}
+			Err(other) => {
+				self.workbench.status_bar.message = format!("delete failed: {:?}", other);
+			}
+		}
+	}
+
 	pub fn paste_slot_at_cursor(&mut self) {
-		match self.editor.paste_slot_at_cursor() {
+		let result = self.editor.paste_slot_at_cursor();
+		self.report_paste_result(result);
+	}
+
+	pub fn paste_slot_with_indent(&mut self, above: bool) {
+		let expand_tab = self.active_indent_settings().expand_tab;
+		let result = self.editor.paste_slot_with_indent(above, expand_tab);
+		self.report_paste_result(result);
+	}
+
+	fn report_paste_result(&mut self, result: Result<(), EditorOperationError>) {
+		match result {
 			Ok(()) => {
 				self.align_active_window_scroll_to_cursor();
 				self.workbench.status_bar.message = "pasted".to_string();
@@ -69,7 +144,7 @@ impl RimState {
 				self.workbench.status_bar.message = "paste failed: slot is empty".to_string();
 			}
 			Err(EditorOperationError::NoActiveBuffer) => {
-				self.workbench.status_bar.message = "paste failed: no active buffer".to_string();
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
 			}
 			Err(EditorOperationError::OutOfRange) => {
 				self.workbench.status_bar.message = "paste failed: out of range".to_string();
@@ -80,6 +155,152 @@ impl RimState {
 		}
 	}
 
+	pub fn put_register(&mut self, register: Option<&str>) {
+		if self.require_active_buffer().is_none() {
+			return;
+		}
+		let name = match register.map(|register| register.trim_start_matches('"')) {
+			None | Some("") => '"',
+			Some(register) => {
+				let mut chars = register.chars();
+				match (chars.next(), chars.next()) {
+					(Some(name), None) if EditorState::is_readable_register(name) => name,
+					_ => {
+						self.workbench.status_bar.message = format!("invalid register: {}", register);
+						return;
+					}
+				}
+			}
+		};
+		let after_row = self.workbench.command_range.map_or(self.active_cursor().row as usize, |range| range.end);
+		match self.editor.put_register_below_row(name, after_row) {
+			Ok(()) => {
+				self.align_active_window_scroll_to_cursor();
+				self.workbench.status_bar.message = "put".to_string();
+			}
+			Err(EditorOperationError::SlotEmpty) => {
+				self.workbench.status_bar.message = format!("put failed: register {} is empty", name);
+			}
+			Err(EditorOperationError::NoActiveBuffer) => {
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
+			}
+			Err(other) => {
+				self.workbench.status_bar.message = format!("put failed: {:?}", other);
+			}
+		}
+	}
+
+	// `expandtab` picks the direction and `tabstop` the width; `:retab!` also
+	// turns indents made only of spaces into tabs.
+	pub fn retab(&mut self, force: bool) {
+		if self.require_active_buffer().is_none() {
+			return;
+		}
+		let settings = self.active_indent_settings();
+		let range = self.workbench.command_range.unwrap_or(CommandRange { start: 1, end: usize::MAX });
+		match self.editor.retab_rows(
+			range.start.saturating_sub(1),
+			range.end.saturating_sub(1),
+			settings.tab_stop,
+			!settings.expand_tab,
+			force,
+		) {
+			Ok(changed) => {
+				self.workbench.status_bar.message = format!("retab: {} lines changed", changed);
+			}
+			Err(EditorOperationError::NoActiveBuffer) => {
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
+			}
+			Err(other) => {
+				self.workbench.status_bar.message = format!("retab failed: {:?}", other);
+			}
+		}
+	}
+
+	// `:sort` takes Vim's `u` flag; without a range the whole buffer is sorted.
+	pub fn sort_lines(&mut self, flags: &str) {
+		if self.require_active_buffer().is_none() {
+			return;
+		}
+		let mut unique = false;
+		for flag in flags.chars().filter(|ch| !ch.is_whitespace()) {
+			if flag != 'u' {
+				self.workbench.status_bar.message = format!("sort failed: unknown flag {}", flag);
+				return;
+			}
+			unique = true;
+		}
+		let range = self.workbench.command_range.unwrap_or(CommandRange { start: 1, end: usize::MAX });
+		match self.editor.sort_rows(range.start.saturating_sub(1), range.end.saturating_sub(1), unique) {
+			Ok(0) => self.workbench.status_bar.message = "sort: done".to_string(),
+			Ok(removed) => {
+				self.workbench.status_bar.message =
+					format!("sort: {} duplicate line{} removed", removed, if removed == 1 { "" } else { "s" })
+			}
+			Err(other) => self.workbench.status_bar.message = format!("sort failed: {:?}", other),
+		}
+		self.align_active_window_scroll_to_cursor();
+	}
+
+	pub fn dedup_adjacent_lines(&mut self) {
+		if self.require_active_buffer().is_none() {
+			return;
+		}
+		let range = self.workbench.command_range.unwrap_or(CommandRange { start: 1, end: usize::MAX });
+		match self.editor.dedup_rows(range.start.saturating_sub(1), range.end.saturating_sub(1)) {
+			Ok(removed) => {
+				self.workbench.status_bar.message =
+					format!("uniq: {} duplicate line{} removed", removed, if removed == 1 { "" } else { "s" })
+			}
+			Err(other) => self.workbench.status_bar.message = format!("uniq failed: {:?}", other),
+		}
+		self.align_active_window_scroll_to_cursor();
+	}
+
+	pub fn move_lines(&mut self, down: bool) {
+		let cursor_row = self.active_cursor().row as usize;
+		let anchor_row =
+			self.visual_anchor.filter(|_| self.is_visual_mode()).map_or(cursor_row, |anchor| anchor.row as usize);
+		let (start_row, end_row) = (cursor_row.min(anchor_row), cursor_row.max(anchor_row));
+		match self.editor.move_rows(start_row.saturating_sub(1), end_row.saturating_sub(1), down) {
+			Ok(()) => self.align_active_window_scroll_to_cursor(),
+			Err(EditorOperationError::OutOfRange) => {
+				self.workbench.status_bar.message =
+					if down { "move failed: already at bottom" } else { "move failed: already at top" }.to_string();
+			}
+			Err(other) => {
+				self.workbench.status_bar.message = format!("move failed: {:?}", other);
+			}
+		}
+	}
+
+	// `:[range]dup` and `<leader>d`: the range, else the visual lines, else the
+	// cursor line.
+	pub fn duplicate_lines(&mut self) {
+		if self.require_active_buffer().is_none() {
+			return;
+		}
+		let cursor_row = self.active_cursor().row as usize;
+		let anchor_row =
+			self.visual_anchor.filter(|_| self.is_visual_mode()).map_or(cursor_row, |anchor| anchor.row as usize);
+		let range = self
+			.workbench
+			.command_range
+			.unwrap_or(CommandRange { start: cursor_row.min(anchor_row), end: cursor_row.max(anchor_row) });
+		if self.is_visual_mode() {
+			self.exit_visual_mode();
+		}
+		match self.editor.duplicate_rows(range.start.saturating_sub(1), range.end.saturating_sub(1)) {
+			Ok(()) => {
+				let count = range.end.saturating_sub(range.start).saturating_add(1);
+				self.workbench.status_bar.message =
+					if count == 1 { "line duplicated".to_string() } else { format!("{} lines duplicated", count) };
+				self.align_active_window_scroll_to_cursor();
+			}
+			Err(other) => self.workbench.status_bar.message = format!("duplicate failed: {:?}", other),
+		}
+	}
+
 	pub fn delete_current_line_to_slot(&mut self) {
 		match self.editor.delete_current_line_to_slot() {
 			Ok(()) => {
@@ -87,7 +308,7 @@ impl RimState {
 				self.workbench.status_bar.message = "line deleted".to_string();
 			}
 			Err(EditorOperationError::NoActiveBuffer) => {
-				self.workbench.status_bar.message = "line delete failed: no active buffer".to_string();
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
 			}
 			Err(EditorOperationError::OutOfRange) => {
 				self.workbench.status_bar.message = "line delete failed: out of range".to_string();
@@ -1,8 +1,8 @@
-use rim_domain::display_geometry::{char_display_width as geom_char_display_width, display_col_of_cursor_slot as geom_display_col_of_cursor_slot, wrapped_row_index_for_cursor as geom_wrapped_row_index_for_cursor, wrapped_total_rows_for_rope as geom_wrapped_total_rows_for_rope};
+use rim_domain::display_geometry::{display_col_of_cursor_slot as geom_display_col_of_cursor_slot, line_display_width as geom_line_display_width, wrapped_row_index_for_cursor as geom_wrapped_row_index_for_cursor, wrapped_total_rows_for_rope as geom_wrapped_total_rows_for_rope};
 use ropey::Rope;
 
 use super::RimState;
-use crate::state::{CursorState, WindowId, rope_line_count, rope_line_without_newline};
+use crate::state::{CursorState, Motion, WindowId, rope_line_count, rope_line_without_newline};
 
 impl RimState {
 	pub fn move_cursor_up(&mut self) {
@@ -49,6 +49,11 @@ impl RimState {
 		self.adjust_scroll_after_horizontal_move(HorizontalMoveDirection::Right);
 	}
 
+	pub fn move_cursor_by_motion(&mut self, motion: Motion) {
+		self.editor.move_cursor_by_motion(motion);
+		self.align_active_window_scroll_to_cursor();
+	}
+
 	pub fn move_cursor_file_start(&mut self) {
 		self.editor.move_cursor_file_start();
 		self.adjust_scroll_after_vertical_move(VerticalMoveDirection::Up);
@@ -59,6 +64,37 @@ impl RimState {
 		self.adjust_scroll_after_vertical_move(VerticalMoveDirection::Down);
 	}
 
+	pub fn move_cursor_to_change_hunk(&mut self, forward: bool) {
+		let Some(wrapped) = self.editor.move_cursor_to_change_hunk(forward) else {
+			self.workbench.status_bar.message = "no changes since last save".to_string();
+			return;
+		};
+		if wrapped {
+			self.workbench.status_bar.message =
+				if forward { "change jump wrapped to top" } else { "change jump wrapped to bottom" }.to_string();
+		}
+		self.align_active_window_scroll_to_cursor();
+	}
+
+	pub fn move_cursor_in_change_list(&mut self, older: bool, count: usize) {
+		match self.editor.move_cursor_in_change_list(older, count) {
+			None => self.workbench.status_bar.message = "changelist is empty".to_string(),
+			Some(false) => {
+				self.workbench.status_bar.message =
+					if older { "at start of changelist" } else { "at end of changelist" }.to_string();
+			}
+			Some(true) => self.align_active_window_scroll_to_cursor(),
+		}
+	}
+
+	pub fn fill_virtual_space_for_insert(&mut self, append: bool) -> bool {
+		let filled = self.editor.fill_virtual_space_for_insert(append);
+		if filled {
+			self.align_active_window_scroll_to_cursor();
+		}
+		filled
+	}
+
 	pub fn move_cursor_right_for_insert(&mut self) {
 		self.editor.move_cursor_right_for_insert();
 		self.adjust_scroll_after_horizontal_move(HorizontalMoveDirection::Right);
@@ -167,7 +203,7 @@ impl RimState {
 			return;
 		};
 		let visible_rows = self.active_window_visible_rows();
-		let threshold = self.workbench.cursor_scroll_threshold.min(visible_rows.saturating_sub(1));
+		let threshold = self.vertical_scroll_threshold(visible_rows);
 		let top_row = window.scroll_y.saturating_add(1);
 		let bottom_row = top_row.saturating_add(visible_rows.saturating_sub(1));
 		let top_safe_row = top_row.saturating_add(threshold);
@@ -189,6 +225,12 @@ impl RimState {
 		}
 	}
 
+	// Capped at half the window, so a huge value such as `scrolloff=999` keeps
+	// the cursor line centered until the view reaches either end of the file.
+	fn vertical_scroll_threshold(&self, visible_rows: u16) -> u16 {
+		self.workbench.cursor_scroll_threshold.min(visible_rows.saturating_sub(1) / 2)
+	}
+
 	fn active_window_visible_text_cols(&self) -> u16 {
 		let window_id = self.active_window_id();
 		self
@@ -212,34 +254,59 @@ impl RimState {
 			return;
 		}
 		let active_window_id = self.active_window_id();
+		let Some(window_scroll_y) = self.windows.get(active_window_id).map(|window| window.scroll_y) else {
+			return;
+		};
 		let cursor_row = self.active_cursor().row;
-		let cursor_line = cursor_row.saturating_sub(1);
+		let cursor_line = self.fold_display_line(cursor_row.saturating_sub(1));
+		let scroll_y = self.fold_display_line(window_scroll_y);
 		let visible_rows = self.active_window_visible_rows();
-		let max_row = self.max_row();
+		let max_row = self.fold_display_line(self.max_row().saturating_sub(1)).saturating_add(1);
 		let max_scroll = max_row.saturating_sub(visible_rows);
-		let threshold = self.workbench.cursor_scroll_threshold;
+		let threshold = self.vertical_scroll_threshold(visible_rows);
 		let visible_tail = visible_rows.saturating_sub(1);
-
-		if let Some(window) = self.windows.get_mut(active_window_id) {
-			match direction {
-				VerticalMoveDirection::Up => {
-					let top_trigger = window.scroll_y.saturating_add(threshold);
-					if cursor_line < top_trigger {
-						window.scroll_y = cursor_line.saturating_sub(threshold).min(max_scroll);
-					}
+		// scrolljump: once the view has to move, move it at least this far.
+		let jump = self.workbench.scroll_jump.min(visible_rows.max(1));
+
+		let next_scroll_y = match direction {
+			VerticalMoveDirection::Up => {
+				let top_trigger = scroll_y.saturating_add(threshold);
+				if cursor_line >= top_trigger {
+					return;
 				}
-				VerticalMoveDirection::Down => {
-					let bottom = window.scroll_y.saturating_add(visible_tail);
-					let bottom_trigger = bottom.saturating_sub(threshold);
-					if cursor_line > bottom_trigger {
-						let needed_top = cursor_line.saturating_add(threshold).saturating_sub(visible_tail);
-						window.scroll_y = needed_top.min(max_scroll);
-					}
+				let needed_top = cursor_line.saturating_sub(threshold);
+				needed_top.min(scroll_y.saturating_sub(jump)).min(max_scroll)
+			}
+			VerticalMoveDirection::Down => {
+				let bottom = scroll_y.saturating_add(visible_tail);
+				let bottom_trigger = bottom.saturating_sub(threshold);
+				if cursor_line <= bottom_trigger {
+					return;
 				}
+				let needed_top = cursor_line.saturating_add(threshold).saturating_sub(visible_tail);
+				needed_top.max(scroll_y.saturating_add(jump)).min(max_scroll)
 			}
+		};
+		let next_scroll_y = self.fold_row_index(next_scroll_y);
+		if let Some(window) = self.windows.get_mut(active_window_id) {
+			window.scroll_y = next_scroll_y;
 		}
 	}
 
+	// Without wrap, vertical scrolling counts display lines: a closed fold in the
+	// active buffer is one line however many rows it holds.
+	fn fold_display_line(&self, row_index: u16) -> u16 {
+		self.active_buffer_id().map_or(row_index, |buffer_id| {
+			self.editor.buffer_fold_display_line(buffer_id, row_index as usize) as u16
+		})
+	}
+
+	fn fold_row_index(&self, display_line: u16) -> u16 {
+		self.active_buffer_id().map_or(display_line, |buffer_id| {
+			self.editor.buffer_fold_row_index(buffer_id, display_line as usize) as u16
+		})
+	}
+
 	fn adjust_scroll_after_horizontal_move(&mut self, direction: HorizontalMoveDirection) {
 		if self.word_wrap_enabled() {
 			let _ = direction;
@@ -253,7 +320,7 @@ impl RimState {
 		let active_window_id = self.active_window_id();
 		let visible_cols = self.active_window_visible_text_cols();
 		let visible_tail = visible_cols.saturating_sub(1);
-		let threshold = self.workbench.cursor_scroll_threshold.min(visible_tail);
+		let threshold = self.workbench.cursor_side_scroll_threshold.min(visible_tail);
 		let cursor_display_col = self.active_cursor_display_col();
 		let line_display_width = self.active_line_display_width();
 		let max_scroll = line_display_width.saturating_sub(visible_tail);
@@ -288,18 +355,19 @@ impl RimState {
 		let Some(window) = self.windows.get(active_window_id).cloned() else {
 			return;
 		};
-		let cursor_line = self.active_cursor().row.saturating_sub(1);
+		let cursor_line = self.fold_display_line(self.active_cursor().row.saturating_sub(1));
+		let scroll_y = self.fold_display_line(window.scroll_y);
 		let visible_rows = self.active_window_visible_rows();
-		let max_row = self.max_row();
+		let max_row = self.fold_display_line(self.max_row().saturating_sub(1)).saturating_add(1);
 		let max_scroll = max_row.saturating_sub(visible_rows);
-		let threshold = self.workbench.cursor_scroll_threshold.min(visible_rows.saturating_sub(1));
+		let threshold = self.vertical_scroll_threshold(visible_rows);
 		let visible_tail = visible_rows.saturating_sub(1);
-		let top_trigger = window.scroll_y.saturating_add(threshold);
-		let bottom = window.scroll_y.saturating_add(visible_tail);
+		let top_trigger = scroll_y.saturating_add(threshold);
+		let bottom = scroll_y.saturating_add(visible_tail);
 		let bottom_trigger = bottom.saturating_sub(threshold);
 		let visible_cols = self.active_window_visible_text_cols();
 		let col_tail = visible_cols.saturating_sub(1);
-		let col_threshold = self.workbench.cursor_scroll_threshold.min(col_tail);
+		let col_threshold = self.workbench.cursor_side_scroll_threshold.min(col_tail);
 		let cursor_display_col = self.active_cursor_display_col();
 		let line_display_width = self.active_line_display_width();
 		let max_scroll_x = line_display_width.saturating_sub(col_tail);
@@ -307,13 +375,13 @@ impl RimState {
 		let right = window.scroll_x.saturating_add(col_tail);
 		let right_trigger = right.saturating_sub(col_threshold);
 
-		let mut next_scroll = window.scroll_y;
+		let mut next_scroll = scroll_y;
 		if cursor_line < top_trigger {
 			next_scroll = cursor_line.saturating_sub(threshold);
 		} else if cursor_line > bottom_trigger {
 			next_scroll = cursor_line.saturating_add(threshold).saturating_sub(visible_tail);
 		}
-		next_scroll = next_scroll.min(max_scroll);
+		let next_scroll = self.fold_row_index(next_scroll.min(max_scroll));
 		let mut next_scroll_x = window.scroll_x;
 		if cursor_display_col < left_trigger {
 			next_scroll_x = cursor_display_col.saturating_sub(col_threshold);
@@ -389,23 +457,33 @@ impl RimState {
 			return 1;
 		};
 		let width = self.active_window_visible_text_cols().max(1) as usize;
-		geom_wrapped_total_rows_for_rope(text, width)
+		let hidden = self
+			.active_buffer_id()
+			.map_or(0, |buffer_id| self.editor.buffer_fold_hidden_wrapped_rows(buffer_id, usize::MAX, width));
+		(geom_wrapped_total_rows_for_rope(text, width) as usize).saturating_sub(hidden).max(1) as u16
 	}
 
 	fn active_cursor_wrapped_row_index(&self) -> u16 {
-		let cursor = self.active_cursor();
+		let mut cursor = self.active_cursor();
 		let width = self.active_window_visible_text_cols().max(1) as usize;
 		let Some(text) = self.active_buffer_rope() else {
 			return 0;
 		};
-		geom_wrapped_row_index_for_cursor(text, cursor, width)
+		// A cursor inside a closed fold shows on the fold's summary row.
+		if let Some(fold) = self.editor.active_buffer_closed_fold_at(cursor.row) {
+			cursor = CursorState { row: fold.start_row, col: 1 };
+		}
+		let hidden = self.active_buffer_id().map_or(0, |buffer_id| {
+			self.editor.buffer_fold_hidden_wrapped_rows(buffer_id, cursor.row.saturating_sub(1) as usize, width)
+		});
+		(geom_wrapped_row_index_for_cursor(text, cursor, width) as usize).saturating_sub(hidden) as u16
 	}
 
 	fn adjust_scroll_after_move_wrapped(&mut self, direction: VerticalMoveDirection) {
 		let active_window_id = self.active_window_id();
 		let visible_rows = self.active_window_visible_rows();
 		let max_scroll = self.max_scroll_y_for_active_window(visible_rows);
-		let threshold = self.workbench.cursor_scroll_threshold.min(visible_rows.saturating_sub(1));
+		let threshold = self.vertical_scroll_threshold(visible_rows);
 		let visible_tail = visible_rows.saturating_sub(1);
 		let cursor_wrapped_row = self.active_cursor_wrapped_row_index();
 
@@ -438,7 +516,7 @@ impl RimState {
 		let cursor_wrapped_row = self.active_cursor_wrapped_row_index();
 		let visible_rows = self.active_window_visible_rows();
 		let max_scroll = self.max_scroll_y_for_active_window(visible_rows);
-		let threshold = self.workbench.cursor_scroll_threshold.min(visible_rows.saturating_sub(1));
+		let threshold = self.vertical_scroll_threshold(visible_rows);
 		let visible_tail = visible_rows.saturating_sub(1);
 		let top_trigger = window.scroll_y.saturating_add(threshold);
 		let bottom = window.scroll_y.saturating_add(visible_tail);
@@ -502,7 +580,7 @@ fn cursor_display_col_for_window(text: &Rope, cursor: CursorState) -> u16 {
 fn line_display_width_for_window(text: &Rope, cursor: CursorState) -> u16 {
 	let row_index = cursor.row.saturating_sub(1) as usize;
 	rope_line_without_newline(text, row_index)
-		.map(|line| line.chars().map(|ch| geom_char_display_width(ch) as u16).sum())
+		.map(|line| geom_line_display_width(line.as_str()) as u16)
 		.unwrap_or(0)
 }
 
@@ -1,5 +1,7 @@
+use std::time::Instant;
+
 use super::RimState;
-use crate::state::EditorOperationError;
+use crate::state::{EditorOperationError, buffer::NO_ACTIVE_BUFFER_MESSAGE};
 
 impl RimState {
 	pub fn begin_visual_block_insert(&mut self, append: bool) {
@@ -13,7 +15,7 @@ impl RimState {
 				self.exit_visual_mode();
 			}
 			Err(EditorOperationError::NoActiveBuffer) => {
-				self.workbench.status_bar.message = "block insert failed: no active buffer".to_string();
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
 				self.exit_visual_mode();
 			}
 			Err(other) => {
@@ -57,7 +59,7 @@ impl RimState {
 				false
 			}
 			Err(EditorOperationError::NoActiveBuffer) => {
-				self.workbench.status_bar.message = "visual delete failed: no active buffer".to_string();
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
 				self.exit_visual_mode();
 				false
 			}
@@ -107,9 +109,12 @@ impl RimState {
 	}
 
 	pub fn yank_visual_selection_to_slot(&mut self) {
+		let highlight = self.visual_yank_highlight(Instant::now());
 		match self.editor.yank_visual_selection_to_slot() {
 			Ok(()) => {
+				self.workbench.yank_highlight = highlight;
 				self.exit_visual_mode();
+				self.align_active_window_scroll_to_cursor();
 				self.workbench.status_bar.message = "selection yanked".to_string();
 			}
 			Err(EditorOperationError::NoAnchor) => {
@@ -117,7 +122,7 @@ impl RimState {
 				self.exit_visual_mode();
 			}
 			Err(EditorOperationError::NoActiveBuffer) => {
-				self.workbench.status_bar.message = "visual yank failed: no active buffer".to_string();
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
 				self.exit_visual_mode();
 			}
 			Err(EditorOperationError::OutOfRange) => {
@@ -138,6 +143,7 @@ impl RimState {
 	pub fn replace_visual_selection_with_slot(&mut self) {
 		match self.editor.replace_visual_selection_with_slot() {
 			Ok(()) => {
+				self.editor.clamp_cursor_to_navigable_col();
 				self.align_active_window_scroll_to_cursor();
 				self.exit_visual_mode();
 				self.workbench.status_bar.message = "selection replaced".to_string();
@@ -151,7 +157,7 @@ impl RimState {
 				self.exit_visual_mode();
 			}
 			Err(EditorOperationError::NoActiveBuffer) => {
-				self.workbench.status_bar.message = "visual paste failed: no active buffer".to_string();
+				self.workbench.status_bar.message = NO_ACTIVE_BUFFER_MESSAGE.to_string();
 				self.exit_visual_mode();
 			}
 			Err(EditorOperationError::OutOfRange) => {
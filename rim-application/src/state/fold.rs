@@ -0,0 +1,136 @@
+use std::time::{Duration, Instant};
+
+use rim_domain::editor::EditorOperationError;
+
+use super::{BufferId, RimState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldMethod {
+	Manual,
+	Indent,
+}
+
+// Changedtick whose indent folds are current, and when a later edit is due to
+// be refolded.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct IndentFoldSync {
+	changedtick: u64,
+	due:         Option<Instant>,
+}
+
+impl RimState {
+	// Edits keep their folds roughly in place until typing pauses this long.
+	const INDENT_FOLD_RECOMPUTE_DELAY: Duration = Duration::from_millis(300);
+
+	// Any visual mode folds whole lines, like `zf` in Vim.
+	pub fn fold_visual_selection(&mut self) {
+		let Some((start, end)) = self.normalized_visual_bounds() else {
+			self.workbench.status_bar.message = "fold failed: no selection".to_string();
+			self.exit_visual_mode();
+			return;
+		};
+		let result = self.editor.create_fold(start.row, end.row);
+		self.exit_visual_mode();
+		match result {
+			Ok(()) => {
+				self.workbench.status_bar.message =
+					format!("{} lines folded", end.row.saturating_sub(start.row).saturating_add(1));
+				self.align_active_window_scroll_to_cursor();
+			}
+			Err(EditorOperationError::OutOfRange) => {
+				self.workbench.status_bar.message = "fold failed: overlaps another fold".to_string();
+			}
+			Err(err) => {
+				self.workbench.status_bar.message = format!("fold failed: {:?}", err);
+			}
+		}
+	}
+
+	pub fn open_fold_under_cursor(&mut self) {
+		if !self.editor.open_fold_at_cursor() {
+			self.workbench.status_bar.message = "no fold found".to_string();
+		}
+	}
+
+	pub fn close_fold_under_cursor(&mut self) {
+		if self.editor.close_fold_at_cursor() {
+			self.align_active_window_scroll_to_cursor();
+		} else {
+			self.workbench.status_bar.message = "no fold found".to_string();
+		}
+	}
+
+	pub fn toggle_fold_under_cursor(&mut self) {
+		if self.editor.toggle_fold_at_cursor() {
+			self.align_active_window_scroll_to_cursor();
+		} else {
+			self.workbench.status_bar.message = "no fold found".to_string();
+		}
+	}
+
+	pub fn set_all_folds_closed(&mut self, closed: bool) {
+		if self.editor.set_all_folds_closed(closed) {
+			self.align_active_window_scroll_to_cursor();
+		} else {
+			self.workbench.status_bar.message = "no fold found".to_string();
+		}
+	}
+
+	pub(super) fn set_fold_method(&mut self, fold_method: FoldMethod) {
+		self.workbench.fold_method = fold_method;
+		self.workbench.indent_fold_sync.clear();
+		if fold_method == FoldMethod::Indent {
+			let buffer_ids = self.buffers.keys().collect::<Vec<_>>();
+			for buffer_id in buffer_ids {
+				self.recompute_indent_folds(buffer_id);
+			}
+			self.align_active_window_scroll_to_cursor();
+		}
+	}
+
+	// Runs after every action. New buffers are folded at once; edited ones once
+	// the delay passes without further changes.
+	pub fn track_indent_fold_changes(&mut self, now: Instant) {
+		if self.workbench.fold_method != FoldMethod::Indent {
+			return;
+		}
+		self.workbench.indent_fold_sync.retain(|buffer_id, _| self.editor.buffers.contains_key(*buffer_id));
+		let buffer_ids = self.buffers.keys().collect::<Vec<_>>();
+		for buffer_id in buffer_ids {
+			let Some(changedtick) = self.buffer_changedtick(buffer_id) else {
+				continue;
+			};
+			match self.workbench.indent_fold_sync.get_mut(&buffer_id) {
+				None => self.recompute_indent_folds(buffer_id),
+				Some(sync) if sync.changedtick != changedtick => {
+					sync.changedtick = changedtick;
+					sync.due = Some(now + Self::INDENT_FOLD_RECOMPUTE_DELAY);
+				}
+				Some(_) => {}
+			}
+		}
+	}
+
+	pub fn recompute_due_indent_folds(&mut self, now: Instant) -> bool {
+		let due = self
+			.workbench
+			.indent_fold_sync
+			.iter()
+			.filter(|(_, sync)| sync.due.is_some_and(|due| due <= now))
+			.map(|(buffer_id, _)| *buffer_id)
+			.collect::<Vec<_>>();
+		for buffer_id in &due {
+			self.recompute_indent_folds(*buffer_id);
+		}
+		!due.is_empty()
+	}
+
+	fn recompute_indent_folds(&mut self, buffer_id: BufferId) {
+		let Some(changedtick) = self.buffer_changedtick(buffer_id) else {
+			return;
+		};
+		let shift_width = self.buffer_indent_settings(buffer_id).shift_width;
+		self.editor.recompute_buffer_indent_folds(buffer_id, shift_width);
+		self.workbench.indent_fold_sync.insert(buffer_id, IndentFoldSync { changedtick, due: None });
+	}
+}
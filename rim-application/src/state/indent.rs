@@ -0,0 +1,191 @@
+use rim_domain::{display_geometry::{TAB_DISPLAY_WIDTH, display_col_of_cursor_slot}, indent::{DetectedIndent, detect_indent, indent_display_width, leading_indent}};
+use serde::{Deserialize, Serialize};
+
+use super::{BufferId, RimState, rope_line_without_newline};
+
+// How many of the first lines of an opened file `detectindent` looks at.
+const DETECT_INDENT_SAMPLE_LINES: usize = 200;
+
+// `tabstop` only sets where an expanded tab stops; tab characters keep their
+// display width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndentSettings {
+	#[serde(rename = "tabstop")]
+	pub tab_stop:    usize,
+	#[serde(rename = "shiftwidth")]
+	pub shift_width: usize,
+	#[serde(rename = "expandtab")]
+	pub expand_tab:  bool,
+}
+
+impl RimState {
+	// A buffer's own `:set` values win over its filetype's defaults, and those
+	// over the global ones. Looking the filetype up each time keeps a buffer
+	// saved under a new extension, or given one by `:set filetype`, in step.
+	pub fn buffer_indent_settings(&self, buffer_id: BufferId) -> IndentSettings {
+		if let Some(settings) = self.workbench.buffer_indent_settings.get(&buffer_id) {
+			return *settings;
+		}
+		self
+			.buffers
+			.get(buffer_id)
+			.and_then(|buffer| buffer.filetype())
+			.and_then(|filetype| self.workbench.filetype_indent_settings.get(filetype))
+			.copied()
+			.unwrap_or_else(|| self.global_indent_settings())
+	}
+
+	pub fn active_indent_settings(&self) -> IndentSettings {
+		match self.active_buffer_id() {
+			Some(buffer_id) => self.buffer_indent_settings(buffer_id),
+			None => self.global_indent_settings(),
+		}
+	}
+
+	// Like Vim's `:set`, changes both the global value and the active buffer's.
+	pub(super) fn update_indent_settings(&mut self, update: impl Fn(&mut IndentSettings)) {
+		let mut global = self.global_indent_settings();
+		update(&mut global);
+		self.workbench.tab_stop = global.tab_stop;
+		self.workbench.shift_width = global.shift_width;
+		self.workbench.expand_tab = global.expand_tab;
+		if let Some(buffer_id) = self.active_buffer_id() {
+			let mut settings = self.buffer_indent_settings(buffer_id);
+			update(&mut settings);
+			self.workbench.buffer_indent_settings.insert(buffer_id, settings);
+		}
+	}
+
+	// Runs once a file has loaded. The detected values are kept as the buffer's
+	// own, but only where they differ from what it would get anyway, so a file
+	// that follows its filetype's defaults still tracks them. A filetype with
+	// its own `[indent.*]` table, or a buffer that already has its own values
+	// (from `:set` or from an earlier load before `:e!`), is left alone.
+	pub(crate) fn detect_buffer_indent(&mut self, buffer_id: BufferId) {
+		if !self.workbench.detect_indent || self.workbench.buffer_indent_settings.contains_key(&buffer_id) {
+			return;
+		}
+		let Some(buffer) = self.buffers.get(buffer_id) else {
+			return;
+		};
+		if buffer
+			.filetype()
+			.is_some_and(|filetype| self.workbench.filetype_indent_settings.contains_key(filetype))
+		{
+			return;
+		}
+		let Some(detected) = detect_indent(buffer.text.lines().map(String::from), DETECT_INDENT_SAMPLE_LINES)
+		else {
+			return;
+		};
+		let current = self.buffer_indent_settings(buffer_id);
+		let mut settings = current;
+		match detected {
+			DetectedIndent::Tabs => settings.expand_tab = false,
+			DetectedIndent::Spaces(width) => {
+				settings.expand_tab = true;
+				settings.shift_width = width;
+			}
+		}
+		if settings != current {
+			self.workbench.buffer_indent_settings.insert(buffer_id, settings);
+		}
+	}
+
+	// With `expandtab`, spaces up to the next tab stop of the cursor's column.
+	pub fn insert_tab_at_cursor(&mut self) {
+		match self.expanded_tab_width() {
+			Some(width) => (0..width).for_each(|_| self.insert_char_at_cursor(' ')),
+			None => self.insert_char_at_cursor('\t'),
+		}
+	}
+
+	pub fn insert_tab_at_block_cursor(&mut self) {
+		match self.expanded_tab_width() {
+			Some(width) => (0..width).for_each(|_| self.insert_char_at_block_cursor(' ')),
+			None => self.insert_char_at_block_cursor('\t'),
+		}
+	}
+
+	// Enter from insert mode. With `smartindent` the new line keeps the indent of
+	// the one it splits, plus a shift width after a `{`. Replaying an insert
+	// goes through the plain newline, since its text holds the indent already.
+	pub fn insert_newline_with_smart_indent(&mut self) {
+		let before = self.workbench.smart_indent.then(|| self.active_line_before_cursor());
+		self.insert_newline_at_cursor();
+		let Some(before) = before else {
+			return;
+		};
+		let settings = self.active_indent_settings();
+		let mut width = indent_display_width(leading_indent(before.as_str()), TAB_DISPLAY_WIDTH);
+		if before.trim_end().ends_with('{') {
+			width += settings.shift_width;
+		}
+		let indent = indent_for_width(width, settings);
+		if !indent.is_empty() {
+			self.insert_text_at_cursor(indent.as_str());
+		}
+	}
+
+	// With `smartindent`, a `}` typed with only indent before it on the line
+	// takes a shift width off that indent.
+	pub fn dedent_for_closing_brace(&mut self) {
+		if !self.workbench.smart_indent {
+			return;
+		}
+		let before = self.active_line_before_cursor();
+		if before.is_empty() || !before.chars().all(|ch| matches!(ch, ' ' | '\t')) {
+			return;
+		}
+		let settings = self.active_indent_settings();
+		let width = indent_display_width(before.as_str(), TAB_DISPLAY_WIDTH).saturating_sub(settings.shift_width);
+		let row_idx = self.active_cursor().row.saturating_sub(1) as usize;
+		if self.editor.replace_row_indent(row_idx, indent_for_width(width, settings).as_str()) {
+			self.align_active_window_scroll_to_cursor();
+		}
+	}
+
+	fn active_line_before_cursor(&self) -> String {
+		let cursor = self.active_cursor();
+		self
+			.active_buffer_id()
+			.and_then(|buffer_id| self.buffers.get(buffer_id))
+			.and_then(|buffer| rope_line_without_newline(&buffer.text, cursor.row.saturating_sub(1) as usize))
+			.map(|line| line.chars().take(cursor.col.saturating_sub(1) as usize).collect())
+			.unwrap_or_default()
+	}
+
+	fn global_indent_settings(&self) -> IndentSettings {
+		IndentSettings {
+			tab_stop:    self.workbench.tab_stop,
+			shift_width: self.workbench.shift_width,
+			expand_tab:  self.workbench.expand_tab,
+		}
+	}
+
+	fn expanded_tab_width(&self) -> Option<usize> {
+		let settings = self.active_indent_settings();
+		if !settings.expand_tab {
+			return None;
+		}
+		let cursor = self.active_cursor();
+		let line = self
+			.active_buffer_id()
+			.and_then(|buffer_id| self.buffers.get(buffer_id))
+			.and_then(|buffer| rope_line_without_newline(&buffer.text, cursor.row.saturating_sub(1) as usize))
+			.unwrap_or_default();
+		let display_col = usize::from(display_col_of_cursor_slot(line.as_str(), cursor.col));
+		let tab_stop = settings.tab_stop.max(1);
+		Some(tab_stop - display_col % tab_stop)
+	}
+}
+
+// Without `expandtab`, full tabs first and spaces for the rest.
+fn indent_for_width(width: usize, settings: IndentSettings) -> String {
+	if settings.expand_tab {
+		" ".repeat(width)
+	} else {
+		format!("{}{}", "\t".repeat(width / TAB_DISPLAY_WIDTH), " ".repeat(width % TAB_DISPLAY_WIDTH))
+	}
+}
@@ -0,0 +1,161 @@
+use std::{ops::Range, path::PathBuf};
+
+use rim_ports::{LanguageDocument, LanguagePosition};
+use serde::{Deserialize, Serialize};
+
+use super::{BufferId, FloatingWindowLine, FloatingWindowPlacement, FloatingWindowState, OverlayState, RimState};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LanguageServerConfig {
+	pub filetypes: Vec<String>,
+	pub command:   Vec<String>,
+}
+
+// Ordered worst first, so the smallest severity on a row wins the gutter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DiagnosticSeverity {
+	Error,
+	Warning,
+	Information,
+	Hint,
+}
+
+// Zero-based char coordinates; the end is exclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferDiagnostic {
+	pub start_row: usize,
+	pub start_col: usize,
+	pub end_row:   usize,
+	pub end_col:   usize,
+	pub severity:  DiagnosticSeverity,
+	pub message:   String,
+}
+
+// Zero-based row and char column, already mapped onto the target file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefinitionLocation {
+	pub path: PathBuf,
+	pub row:  usize,
+	pub col:  usize,
+}
+
+impl RimState {
+	// Only file buffers whose filetype has a configured server are tracked.
+	pub fn language_document_for_buffer(&self, buffer_id: BufferId) -> Option<LanguageDocument> {
+		let buffer = self.buffers.get(buffer_id)?;
+		let path = buffer.path.as_ref()?;
+		let language_id = buffer.filetype()?;
+		let server = self
+			.workbench
+			.language_servers
+			.iter()
+			.find(|server| !server.command.is_empty() && server.filetypes.iter().any(|ft| ft == language_id))?;
+		Some(LanguageDocument {
+			path:           path.clone(),
+			language_id:    language_id.to_string(),
+			server_command: server.command.clone(),
+			root:           self.workspace_root().to_path_buf(),
+			text:           buffer.text.to_string(),
+		})
+	}
+
+	pub fn set_buffer_diagnostics(&mut self, buffer_id: BufferId, diagnostics: Vec<BufferDiagnostic>) {
+		if !self.buffers.contains_key(buffer_id) {
+			return;
+		}
+		if diagnostics.is_empty() {
+			self.workbench.diagnostics.remove(&buffer_id);
+		} else {
+			self.workbench.diagnostics.insert(buffer_id, diagnostics);
+		}
+	}
+
+	pub fn buffer_diagnostics(&self, buffer_id: BufferId) -> &[BufferDiagnostic] {
+		self.workbench.diagnostics.get(&buffer_id).map(Vec::as_slice).unwrap_or_default()
+	}
+
+	// The worst severity touching each row, for the gutter.
+	pub fn buffer_diagnostic_signs(
+		&self,
+		buffer_id: BufferId,
+		rows: Range<usize>,
+	) -> Vec<Option<DiagnosticSeverity>> {
+		let mut signs = vec![None; rows.len()];
+		for diagnostic in self.buffer_diagnostics(buffer_id) {
+			let covered = diagnostic.start_row.max(rows.start)..diagnostic.end_row.saturating_add(1).min(rows.end);
+			for row in covered {
+				let sign = &mut signs[row - rows.start];
+				if sign.is_none_or(|current| diagnostic.severity < current) {
+					*sign = Some(diagnostic.severity);
+				}
+			}
+		}
+		signs
+	}
+
+	// Requests need a buffer the server already knows about.
+	pub fn language_position_under_cursor(&mut self) -> Option<(BufferId, LanguagePosition)> {
+		let buffer_id = self.active_buffer_id()?;
+		if !self.workbench.lsp_documents.contains_key(&buffer_id) {
+			self.workbench.status_bar.message = "no language server for this buffer".to_string();
+			return None;
+		}
+		let cursor = self.active_cursor();
+		Some((buffer_id, LanguagePosition {
+			row: cursor.row.saturating_sub(1) as usize,
+			col: cursor.col.saturating_sub(1) as usize,
+		}))
+	}
+
+	// Like `z=`, `<n>gd` picks one entry of the list.
+	pub fn show_definition_list(&mut self, locations: &[DefinitionLocation]) {
+		let lines = locations
+			.iter()
+			.enumerate()
+			.map(|(index, location)| {
+				let path = location.path.strip_prefix(self.workspace_root()).unwrap_or(location.path.as_path());
+				FloatingWindowLine {
+					key:       index.saturating_add(1).to_string(),
+					summary:   format!("{}:{}:{}", path.display(), location.row + 1, location.col + 1),
+					is_prefix: false,
+				}
+			})
+			.collect::<Vec<_>>();
+		self.show_lsp_window(format!("Definitions ({})", locations.len()), "<n>gd jump  Esc close", lines);
+	}
+
+	pub fn show_hover(&mut self, contents: &str) {
+		if contents.trim().is_empty() {
+			self.workbench.status_bar.message = "no hover information".to_string();
+			return;
+		}
+		let lines = contents
+			.trim_end()
+			.lines()
+			.map(|line| FloatingWindowLine {
+				key:       String::new(),
+				summary:   line.to_string(),
+				is_prefix: false,
+			})
+			.collect::<Vec<_>>();
+		self.show_lsp_window("Hover".to_string(), "Esc close", lines);
+	}
+
+	fn show_lsp_window(&mut self, title: String, footer: &str, lines: Vec<FloatingWindowLine>) {
+		let height = lines.len().saturating_add(4).min(self.workbench.key_hints_max_height as usize) as u16;
+		self.workbench.overlay = Some(OverlayState::FloatingWindow(FloatingWindowState {
+			title,
+			subtitle: None,
+			footer: Some(footer.to_string()),
+			placement: FloatingWindowPlacement::BottomRight {
+				width:         self.workbench.key_hints_width,
+				height:        height.max(4),
+				margin_right:  1,
+				margin_bottom: 1,
+			},
+			lines,
+			scroll: 0,
+		}));
+	}
+}
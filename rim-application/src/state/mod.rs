@@ -1,28 +1,68 @@
 use std::{collections::{HashMap, HashSet, VecDeque}, fmt, ops::{Deref, DerefMut}, path::{Path, PathBuf}, time::{Duration, Instant, SystemTime}};
 
 use frizbee::{Config as FrizbeeConfig, match_list_indices};
-use rim_domain::preview::preview_max_scroll_with_mode;
-pub use rim_domain::{editor::{EditorOperationError, EditorState}, model::{BufferEditSnapshot, BufferHistoryEntry, BufferId, BufferState, BufferSwitchDirection, CursorState, EditorMode, FocusDirection, PendingBlockInsert, PendingInsertUndoGroup, PersistedBufferHistory, RopeTextDiff, SplitAxis, TabId, TabState, WindowBufferViewState, WindowId, WindowState, WorkspaceBufferHistorySnapshot, WorkspaceBufferSnapshot, WorkspaceSessionSnapshot, WorkspaceTabSnapshot, WorkspaceWindowBufferViewSnapshot, WorkspaceWindowSnapshot}};
+use rim_domain::{display_geometry::TAB_DISPLAY_WIDTH, preview::preview_max_scroll_with_mode, tags::TagAddress};
+pub use rim_domain::{editor::{EditorOperationError, EditorState}, model::{BufferEditSnapshot, BufferHistoryEntry, BufferId, BufferState, BufferSwitchDirection, CursorState, EditorMode, FocusDirection, Fold, LineChangeSign, PendingBlockInsert, PendingInsertUndoGroup, PersistedBufferHistory, RopeTextDiff, SplitAxis, TabId, TabState, VirtualEdit, WindowBufferViewState, WindowId, WindowState, WorkspaceBufferHistorySnapshot, WorkspaceBufferSnapshot, WorkspaceSessionSnapshot, WorkspaceTabSnapshot, WorkspaceWindowBufferViewSnapshot, WorkspaceWindowSnapshot}, motion::Motion};
 use rim_ports::PluginRegistration;
 use time::{OffsetDateTime, format_description::FormatItem, macros::format_description};
 
-use crate::{command::{BuiltinCommand, CommandArgKind, CommandCommand, CommandConfigError, CommandConfigFile, CommandPaletteFileMatch, CommandPaletteItem, CommandPaletteMatch, CommandRegistry, Picker, PickerRegistry, PluginCommandRegistration, Suggestion}, defaults};
+use crate::{action::{EditorAction, KeyEvent}, command::{BuiltinCommand, CommandArgKind, CommandCommand, CommandConfigError, CommandConfigFile, CommandPaletteFileMatch, CommandPaletteItem, CommandPaletteMatch, CommandRegistry, HostCommandRegistration, Picker, PickerRegistry, PluginCommandRegistration, ResolvedParams, Suggestion}, defaults, hook::{HookEvent, HookEventKind, HookId, HookRegistry}};
 
 mod buffer;
+mod date;
+mod diff;
 mod edit;
+mod fold;
+mod indent;
+mod lsp;
 mod mode;
+mod options;
+mod paste;
 mod plugin;
+mod registers;
+mod scroll_bind;
+mod search;
 mod session;
+mod spell;
+mod startup;
+mod status_line;
+mod substitute;
 mod tab;
+mod tags;
+mod undo_list;
 mod window;
+mod word_count;
+mod yank_highlight;
 
+pub use fold::FoldMethod;
+pub use indent::IndentSettings;
+pub use lsp::{BufferDiagnostic, DefinitionLocation, DiagnosticSeverity, LanguageServerConfig};
+pub use paste::PendingPaste;
 pub(crate) use rim_domain::text::{buffer_name_from_path, compute_rope_text_diff, rope_line_count, rope_line_without_newline};
+pub(crate) use session::DEFAULT_SESSION_FILE_NAME;
+pub(crate) use substitute::parse_substitute_input;
+pub use substitute::{SubstituteInput, SubstitutePreview};
+pub use yank_highlight::YankHighlight;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListChars {
+	pub tab_head: char,
+	pub tab_fill: char,
+	pub trail:    char,
+}
+
+impl Default for ListChars {
+	fn default() -> Self { Self { tab_head: '»', tab_fill: ' ', trail: '·' } }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StatusBarState {
 	pub mode:         StatusBarMode,
 	pub message:      String,
 	pub key_sequence: String,
+	// The message as it stood at the last key press; any other one is still
+	// unread.
+	pub seen_message: String,
 }
 
 impl Default for StatusBarState {
@@ -31,6 +71,7 @@ impl Default for StatusBarState {
 			mode:         StatusBarMode::Normal,
 			message:      "new file".to_string(),
 			key_sequence: String::new(),
+			seen_message: "new file".to_string(),
 		}
 	}
 }
@@ -75,6 +116,7 @@ pub enum NormalSequenceKey {
 	Down,
 	Char(char),
 	Ctrl(char),
+	Alt(char),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -272,10 +314,87 @@ pub struct PendingSwapDecision {
 	pub owner_username: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClosedBufferEntry {
+	pub path:   PathBuf,
+	pub cursor: CursorState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TagStackEntry {
+	pub buffer_id: BufferId,
+	pub cursor:    CursorState,
+}
+
+// Inclusive 1-based lines an ex command applies to; `start` is only 0 for
+// `:0put`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandRange {
+	pub start: usize,
+	pub end:   usize,
+}
+
+// What a zoomed tab looked like before `<leader>wz`: its window order and the
+// geometry of every window, so the toggle back is exact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoomedLayout {
+	pub windows:  Vec<WindowId>,
+	pub geometry: Vec<(WindowId, WindowState)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingTagJump {
+	pub buffer_id: BufferId,
+	pub address:   TagAddress,
+	pub origin:    Option<TagStackEntry>,
+}
+
+// `rim -c` and `+cmd` command lines, waiting for the text of the buffer that
+// was active once the startup files opened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingStartupCommands {
+	pub buffer_id: BufferId,
+	pub commands:  Vec<String>,
+}
+
+// Keys are recorded from right after the insert was entered, so whatever the
+// entry itself did (like the new line of `o`) is replayed by the entry alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingInsertRepeat {
+	pub entry:     EditorAction,
+	pub buffer_id: BufferId,
+	pub keys:      Vec<KeyEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InsertRepeat {
+	pub entry: EditorAction,
+	pub keys:  Vec<KeyEvent>,
+}
+
+// Visual selection to restore once a `/` search started from visual mode ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOrigin {
+	pub mode:                     EditorMode,
+	pub anchor:                   Option<CursorState>,
+	pub block_anchor_display_col: Option<u16>,
+}
+
+// Operator waiting on a `/` search to supply its motion, as in `d/foo`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchOperator {
+	Delete,
+}
+
+pub type HostCommandHandler = fn(&mut RimState, &ResolvedParams);
+
 #[derive(Debug)]
 pub struct WorkbenchState {
 	pub title:                                 String,
 	pub workspace_root:                        PathBuf,
+	pub working_directory:                     PathBuf,
+	// Shown as `~` in paths outside the working directory.
+	pub home_directory:                        Option<PathBuf>,
 	pub plugins:                               Vec<PluginRegistration>,
 	pub leader_key:                            char,
 	pub command_line:                          String,
@@ -283,18 +402,102 @@ pub struct WorkbenchState {
 	pub force_quit_trim_file_dirty_in_session: bool,
 	pub pending_save_path:                     Option<(BufferId, PathBuf)>,
 	pub cursor_scroll_threshold:               u16,
+	pub cursor_side_scroll_threshold:          u16,
+	pub scroll_jump:                           u16,
 	pub key_hints_width:                       u16,
 	pub key_hints_max_height:                  u16,
+	pub key_timeout_ms:                        u64,
+	pub persist_session:                       bool,
+	pub atomic_save:                           bool,
+	pub backup:                                bool,
+	pub date_format:                           String,
+	pub status_line_format:                    String,
+	// Vim's `laststatus`: 0 never shows the status bar, 1 only with several
+	// windows in the tab, 2 always. A hidden one still comes up for the command
+	// line and unread messages.
+	pub last_status:                           u8,
+	// The terminal size of the last resize, and whether the layout then left the
+	// status bar a row.
+	pub viewport_size:                         Option<(u16, u16)>,
+	pub viewport_status_row:                   bool,
+	pub clock:                                 fn() -> OffsetDateTime,
 	pub word_wrap:                             bool,
+	pub list_mode:                             bool,
+	pub indent_guides:                         bool,
+	pub shift_width:                           usize,
+	pub tab_stop:                              usize,
+	pub expand_tab:                            bool,
+	pub filetype_indent_settings:              HashMap<String, IndentSettings>,
+	// `:set detectindent`; files opened with it take the indentation they use.
+	pub detect_indent:                         bool,
+	buffer_indent_settings:                    HashMap<BufferId, IndentSettings>,
+	pub join_spaces:                           bool,
+	pub undo_breaks:                           bool,
+	pub spell:                                 bool,
+	// `:set smartindent`; Enter keeps the line's indent and indents after `{`.
+	pub smart_indent:                          bool,
+	// `:set cursorblink`; the cursor is drawn steady unless set.
+	pub cursor_blink:                          bool,
+	pub fold_method:                           FoldMethod,
+	indent_fold_sync:                          HashMap<BufferId, fold::IndentFoldSync>,
+	// Active window and top line the last scrollbind sync saw.
+	scroll_bind_anchor:                        Option<(WindowId, u16)>,
+	// zg additions; they last for the session only.
+	pub spell_good_words:                      HashSet<String>,
+	pub list_chars:                            ListChars,
 	pub picker_preview_word_wrap:              bool,
 	pub normal_sequence:                       Vec<NormalSequenceKey>,
 	pub visual_g_pending:                      bool,
+	pub pending_count:                         Option<usize>,
+	// Set by `<C-r>` in insert or command mode: the next key names the register
+	// to insert.
+	pub pending_register_insert:               bool,
+	pub yank_highlight:                        Option<YankHighlight>,
+	// Bumped whenever an action may have changed what is on screen; the
+	// renderer skips frames while it stays put.
+	render_generation:                         u64,
 	pub pending_swap_decision:                 Option<PendingSwapDecision>,
+	pub tag_stack:                             Vec<TagStackEntry>,
+	// Files closed most recently last, for `:reopen`.
+	pub closed_buffers:                        Vec<ClosedBufferEntry>,
+	pub pending_tag_jump:                      Option<PendingTagJump>,
+	pub pending_startup_commands:              Option<PendingStartupCommands>,
+	pub pending_paste:                         Option<PendingPaste>,
+	// `:set pastemax`; longer pastes are cut to this many chars, 0 for no limit.
+	pub paste_max_chars:                       usize,
+	pub pending_insert_repeat:                 Option<PendingInsertRepeat>,
+	pub last_insert:                           Option<InsertRepeat>,
+	pub search_origin:                         Option<SearchOrigin>,
+	pub search_operator:                       Option<SearchOperator>,
+	pub last_search:                           Option<String>,
+	pub substitute_preview:                    Option<SubstitutePreview>,
+	pub command_selection:                     Option<String>,
+	pub command_range:                         Option<CommandRange>,
+	pub zoomed_layouts:                        HashMap<TabId, ZoomedLayout>,
+	pub pending_session_loads:                 HashSet<BufferId>,
+	// Buffers opened from disk whose text has not arrived yet.
+	pub loading_buffers:                       HashSet<BufferId>,
 	pub in_flight_internal_saves:              HashSet<BufferId>,
 	pub ignore_external_change_until:          HashMap<BufferId, Instant>,
+	// File mtimes seen at the last load or save, for `:checktime`.
+	pub disk_mtimes:                           HashMap<BufferId, SystemTime>,
+	pub language_servers:                      Vec<LanguageServerConfig>,
+	// Changedtick last sent to the language server for each tracked buffer.
+	pub lsp_documents:                         HashMap<BufferId, u64>,
+	// Changedtick seen on a tracked buffer that has not been sent yet, and when
+	// it is due to go out; later edits push the deadline back.
+	pub lsp_change_due:                        HashMap<BufferId, (u64, Instant)>,
+	pub diagnostics:                           HashMap<BufferId, Vec<BufferDiagnostic>>,
+	// The count typed before `gd`, applied once the definitions arrive.
+	pub pending_definition_pick:               Option<usize>,
 	pub command_registry:                      CommandRegistry,
+	pub hooks:                                 HookRegistry,
+	pub host_command_handlers:                 HashMap<String, HostCommandHandler>,
 	pub overlay:                               Option<OverlayState>,
 	pub command_palette:                       Option<CommandPaletteState>,
+	// Set by `<leader>:`, where the command line only filters the palette and
+	// Enter runs the selected command.
+	pub command_palette_run_selection:         bool,
 	pub workspace_file_picker:                 Option<WorkspaceFilePickerState>,
 	pub notification_center:                   Option<NotificationCenterState>,
 	pub notifications:                         Vec<NotificationEntry>,
@@ -312,6 +515,8 @@ impl WorkbenchState {
 		Self {
 			title:                                 "Rim".to_string(),
 			workspace_root:                        std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+			working_directory:                     std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+			home_directory:                        rim_paths::user_home_dir(),
 			plugins:                               Vec::new(),
 			leader_key:                            default_editor.editor.leader_key,
 			command_line:                          String::new(),
@@ -319,18 +524,78 @@ impl WorkbenchState {
 			force_quit_trim_file_dirty_in_session: false,
 			pending_save_path:                     None,
 			cursor_scroll_threshold:               default_editor.editor.cursor_scroll_threshold,
+			cursor_side_scroll_threshold:          default_editor.editor.cursor_side_scroll_threshold,
+			scroll_jump:                           1,
 			key_hints_width:                       default_editor.editor.key_hints_width,
 			key_hints_max_height:                  default_editor.editor.key_hints_max_height,
+			key_timeout_ms:                        default_editor.editor.key_timeout_ms,
+			persist_session:                       default_editor.editor.persist_session,
+			atomic_save:                           default_editor.editor.atomic_save,
+			backup:                                default_editor.editor.backup,
+			date_format:                           default_editor.editor.date_format.clone(),
+			status_line_format:                    default_editor.editor.status_line.clone(),
+			last_status:                           2,
+			viewport_size:                         None,
+			viewport_status_row:                   true,
+			clock:                                 local_now,
 			word_wrap:                             false,
+			list_mode:                             false,
+			indent_guides:                         false,
+			shift_width:                           TAB_DISPLAY_WIDTH,
+			tab_stop:                              TAB_DISPLAY_WIDTH,
+			expand_tab:                            false,
+			filetype_indent_settings:              default_editor.indent.clone(),
+			detect_indent:                         default_editor.editor.detect_indent,
+			buffer_indent_settings:                HashMap::new(),
+			join_spaces:                           false,
+			undo_breaks:                           false,
+			spell:                                 false,
+			smart_indent:                          false,
+			cursor_blink:                          false,
+			fold_method:                           FoldMethod::Manual,
+			indent_fold_sync:                      HashMap::new(),
+			scroll_bind_anchor:                    None,
+			spell_good_words:                      HashSet::new(),
+			list_chars:                            ListChars::default(),
 			picker_preview_word_wrap:              true,
 			normal_sequence:                       Vec::new(),
 			visual_g_pending:                      false,
+			pending_count:                         None,
+			pending_register_insert:               false,
+			yank_highlight:                        None,
+			render_generation:                     0,
 			pending_swap_decision:                 None,
+			tag_stack:                             Vec::new(),
+			closed_buffers:                        Vec::new(),
+			pending_tag_jump:                      None,
+			pending_startup_commands:              None,
+			pending_paste:                         None,
+			paste_max_chars:                       1_000_000,
+			pending_insert_repeat:                 None,
+			last_insert:                           None,
+			search_origin:                         None,
+			search_operator:                       None,
+			last_search:                           None,
+			substitute_preview:                    None,
+			command_selection:                     None,
+			command_range:                         None,
+			zoomed_layouts:                        HashMap::new(),
+			pending_session_loads:                 HashSet::new(),
+			loading_buffers:                       HashSet::new(),
 			in_flight_internal_saves:              HashSet::new(),
 			ignore_external_change_until:          HashMap::new(),
+			disk_mtimes:                           HashMap::new(),
+			language_servers:                      Vec::new(),
+			lsp_documents:                         HashMap::new(),
+			lsp_change_due:                        HashMap::new(),
+			diagnostics:                           HashMap::new(),
+			pending_definition_pick:               None,
 			command_registry:                      CommandRegistry::with_defaults(),
+			hooks:                                 HookRegistry::default(),
+			host_command_handlers:                 HashMap::new(),
 			overlay:                               None,
 			command_palette:                       None,
+			command_palette_run_selection:         false,
 			workspace_file_picker:                 None,
 			notification_center:                   None,
 			notifications:                         Vec::new(),
@@ -388,6 +653,8 @@ pub fn compute_command_palette_preview_width(content_width: u16) -> u16 {
 	palette_width.saturating_sub(2)
 }
 
+fn local_now() -> OffsetDateTime { OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc()) }
+
 fn format_local_timestamp(time: SystemTime) -> String {
 	static FORMAT: &[FormatItem<'static>] =
 		format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
@@ -427,6 +694,15 @@ impl RimState {
 
 	pub fn new() -> Self { Self { editor: EditorState::new(), workbench: WorkbenchState::new() } }
 
+	pub fn register_hook<F>(&mut self, kind: HookEventKind, callback: F) -> HookId
+	where F: FnMut(&HookEvent) + 'static {
+		self.workbench.hooks.register(kind, callback)
+	}
+
+	pub fn unregister_hook(&mut self, id: HookId) -> bool { self.workbench.hooks.unregister(id) }
+
+	pub(crate) fn emit_hook(&mut self, event: HookEvent) { self.workbench.hooks.emit(&event); }
+
 	pub fn apply_command_config(&mut self, config: &CommandConfigFile) -> Vec<CommandConfigError> {
 		let errors = self.workbench.command_registry.apply_config(config);
 		self.rebuild_plugin_command_registry_entries();
@@ -452,6 +728,12 @@ impl RimState {
 		self.tick_notifications(now);
 	}
 
+	pub fn render_generation(&self) -> u64 { self.workbench.render_generation }
+
+	pub fn mark_render_dirty(&mut self) {
+		self.workbench.render_generation = self.workbench.render_generation.wrapping_add(1);
+	}
+
 	pub fn tick_notifications(&mut self, now: Instant) -> bool {
 		let before = self.workbench.notification_preview_active.len();
 		self.workbench.notification_preview_active.retain(|item| item.expires_at > now);
@@ -613,6 +895,21 @@ impl RimState {
 		self.workbench.command_registry.register_plugin_command(registration)
 	}
 
+	pub fn register_host_command(
+		&mut self,
+		registration: HostCommandRegistration,
+		handler: HostCommandHandler,
+	) -> Result<(), String> {
+		let command_id = registration.id.clone();
+		self.workbench.command_registry.register_host_command(registration)?;
+		self.workbench.host_command_handlers.insert(command_id, handler);
+		Ok(())
+	}
+
+	pub fn host_command_handler(&self, command_id: &str) -> Option<HostCommandHandler> {
+		self.workbench.host_command_handlers.get(command_id).copied()
+	}
+
 	pub fn command_palette(&self) -> Option<&CommandPaletteState> { self.workbench.command_palette.as_ref() }
 
 	pub fn workspace_file_picker(&self) -> Option<&WorkspaceFilePickerState> {
@@ -622,10 +919,31 @@ impl RimState {
 	pub fn workspace_root(&self) -> &Path { self.workbench.workspace_root.as_path() }
 
 	pub fn set_workspace_root(&mut self, workspace_root: PathBuf) {
+		self.workbench.working_directory = workspace_root.clone();
 		self.workbench.workspace_root = workspace_root;
 	}
 
+	// Relative paths typed on the command line resolve against this directory; it
+	// starts at the workspace root and only moves with `:cd`.
+	pub fn working_directory(&self) -> &Path { self.workbench.working_directory.as_path() }
+
+	pub fn change_working_directory(&mut self, path: Option<&Path>) -> Result<&Path, String> {
+		let target = match path {
+			Some(path) if path.is_absolute() => path.to_path_buf(),
+			Some(path) => self.working_directory().join(path),
+			None => self.workspace_root().to_path_buf(),
+		};
+		let target =
+			std::fs::canonicalize(&target).map_err(|err| format!("cd failed: {}: {}", target.display(), err))?;
+		if !target.is_dir() {
+			return Err(format!("cd failed: not a directory: {}", target.display()));
+		}
+		self.workbench.working_directory = target;
+		Ok(self.working_directory())
+	}
+
 	pub fn refresh_command_palette(&mut self) {
+		self.refresh_substitute_preview();
 		if !self.is_command_mode() {
 			self.workbench.command_palette = None;
 			return;
@@ -643,39 +961,43 @@ impl RimState {
 			.and_then(|palette| palette.items.get(palette.selected))
 			.and_then(CommandPaletteItem::as_file)
 			.map(|item| (item.absolute_path.clone(), item.relative_path.clone()));
-		let (items, loading, active_param_kind) = if let Some(context) = self.command_palette_picker_context() {
-			match context.param.kind {
-				CommandArgKind::File if self.workbench.workspace_file_cache_loading => {
-					(Vec::new(), true, Some(CommandArgKind::File))
+		let (items, loading, active_param_kind) =
+			if self.workbench.command_line.starts_with('/') || self.workbench.substitute_preview.is_some() {
+				// Search and substitute patterns have no command candidates.
+				(Vec::new(), false, None)
+			} else if let Some(context) = self.command_palette_picker_context() {
+				match context.param.kind {
+					CommandArgKind::File if self.workbench.workspace_file_cache_loading => {
+						(Vec::new(), true, Some(CommandArgKind::File))
+					}
+					CommandArgKind::File => (
+						self.command_palette_picker_matches(CommandArgKind::File, context.input.as_str(), 512),
+						false,
+						Some(CommandArgKind::File),
+					),
+					CommandArgKind::Text => {
+						let command_query = self.workbench.command_line.split_whitespace().next().unwrap_or_default();
+						let items = self
+							.workbench
+							.command_registry
+							.command_palette_matches(command_query, 512)
+							.into_iter()
+							.map(CommandPaletteItem::Command)
+							.collect();
+						(items, false, Some(CommandArgKind::Text))
+					}
 				}
-				CommandArgKind::File => (
-					self.command_palette_picker_matches(CommandArgKind::File, context.input.as_str(), 512),
-					false,
-					Some(CommandArgKind::File),
-				),
-				CommandArgKind::Text => {
-					let command_query = self.workbench.command_line.split_whitespace().next().unwrap_or_default();
-					let items = self
-						.workbench
-						.command_registry
-						.command_palette_matches(command_query, 512)
-						.into_iter()
-						.map(CommandPaletteItem::Command)
-						.collect();
-					(items, false, Some(CommandArgKind::Text))
-				}
-			}
-		} else {
-			let command_query = self.workbench.command_line.split_whitespace().next().unwrap_or_default();
-			let items = self
-				.workbench
-				.command_registry
-				.command_palette_matches(command_query, 512)
-				.into_iter()
-				.map(CommandPaletteItem::Command)
-				.collect();
-			(items, false, None)
-		};
+			} else {
+				let command_query = self.workbench.command_line.split_whitespace().next().unwrap_or_default();
+				let items = self
+					.workbench
+					.command_registry
+					.command_palette_matches(command_query, 512)
+					.into_iter()
+					.map(CommandPaletteItem::Command)
+					.collect();
+				(items, false, None)
+			};
 		let selected = if let Some((selected_path, selected_relative_path)) = previous_selected_file.as_ref() {
 			items
 				.iter()
@@ -1061,21 +1383,28 @@ impl RimState {
 		}
 	}
 
-	pub fn word_wrap_enabled(&self) -> bool { self.workbench.word_wrap }
+	pub fn word_wrap_enabled(&self) -> bool { self.window_word_wrap(self.active_window_id()) }
+
+	pub fn window_word_wrap(&self, window_id: WindowId) -> bool {
+		self.windows.get(window_id).and_then(|window| window.wrap).unwrap_or(self.workbench.word_wrap)
+	}
 
+	// Only the active window; the others keep wrapping as they did.
 	pub fn toggle_word_wrap(&mut self) {
-		self.workbench.word_wrap = !self.workbench.word_wrap;
-		for window_id in self.active_tab_window_ids() {
-			if let Some(window) = self.windows.get_mut(window_id) {
-				window.scroll_x = 0;
-			}
+		let word_wrap = !self.word_wrap_enabled();
+		self.set_active_window_word_wrap(Some(word_wrap));
+		self.workbench.status_bar.message =
+			if word_wrap { "word wrap enabled".to_string() } else { "word wrap disabled".to_string() };
+	}
+
+	// `None` puts the window back on the global value.
+	pub(super) fn set_active_window_word_wrap(&mut self, word_wrap: Option<bool>) {
+		let active_window_id = self.active_window_id();
+		if let Some(window) = self.windows.get_mut(active_window_id) {
+			window.wrap = word_wrap;
+			window.scroll_x = 0;
 		}
 		self.align_active_window_scroll_to_cursor();
-		self.workbench.status_bar.message = if self.workbench.word_wrap {
-			"word wrap enabled".to_string()
-		} else {
-			"word wrap disabled".to_string()
-		};
 	}
 
 	pub fn picker_preview_word_wrap_enabled(&self) -> bool { self.workbench.picker_preview_word_wrap }
@@ -1204,6 +1533,16 @@ impl RimState {
 		}
 	}
 
+	// `None` unless a command is selected.
+	pub fn selected_command_palette_needs_arguments(&self) -> Option<bool> {
+		let palette = self.workbench.command_palette.as_ref()?;
+		let CommandPaletteItem::Command(item) = palette.items.get(palette.selected)? else {
+			return None;
+		};
+		let spec = self.workbench.command_registry.command_spec(&item.command_id)?;
+		Some(spec.params.iter().any(|param| !param.optional))
+	}
+
 	pub fn active_keymap_scope(&self) -> KeymapScope {
 		if let Some(OverlayState::KeyHints(overlay)) = self.workbench.overlay.as_ref() {
 			overlay.scope
@@ -1325,6 +1664,7 @@ impl RimState {
 				NormalSequenceKey::Down => "<Down>".to_string(),
 				NormalSequenceKey::Char(ch) => ch.to_string(),
 				NormalSequenceKey::Ctrl(ch) => format!("<C-{}>", ch),
+				NormalSequenceKey::Alt(ch) => format!("<A-{}>", ch),
 			})
 			.collect::<Vec<_>>()
 			.join("")
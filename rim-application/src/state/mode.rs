@@ -1,22 +1,9 @@
-use super::{BufferHistoryEntry, CursorState, EditorMode, PendingBlockInsert, PendingInsertUndoGroup, PendingSwapDecision, RimState, StatusBarMode, rope_line_count};
+use super::{BufferHistoryEntry, CommandRange, CursorState, EditorMode, InsertRepeat, PendingBlockInsert, PendingInsertRepeat, PendingInsertUndoGroup, PendingSwapDecision, RimState, StatusBarMode, rope_line_count};
+use crate::action::{EditorAction, KeyEvent};
 
 impl RimState {
 	pub fn status_line(&self) -> String {
-		let cursor = self.active_cursor();
-		let total_rows = self
-			.active_buffer_id()
-			.and_then(|buffer_id| self.buffers.get(buffer_id))
-			.map(|buffer| rope_line_count(&buffer.text) as u16)
-			.unwrap_or(1);
-		let progress = if cursor.row <= 1 {
-			"Top".to_string()
-		} else if cursor.row >= total_rows {
-			"Bot".to_string()
-		} else {
-			let percent = (u32::from(cursor.row) * 100 / u32::from(total_rows)) as u16;
-			format!("{}%", percent)
-		};
-		let cursor_pos = format!("{}:{} {}", cursor.row, cursor.col, progress);
+		let cursor_pos = self.status_line_position();
 
 		if self.mode == EditorMode::Command {
 			return format!(":{} | {}", self.workbench.command_line, cursor_pos);
@@ -77,6 +64,7 @@ impl RimState {
 		self.visual_block_anchor_display_col = None;
 		self.visual_block_cursor_display_col = None;
 		self.pending_block_insert = None;
+		self.insert_completion = None;
 		self.workbench.status_bar.mode = StatusBarMode::Normal;
 		self.close_key_hints();
 		self.close_workspace_file_picker();
@@ -85,9 +73,17 @@ impl RimState {
 	}
 
 	pub fn enter_command_mode(&mut self) {
+		// Commands typed from visual mode act on the selection they were started from.
+		self.workbench.command_selection = self
+			.is_visual_mode()
+			.then(|| self.editor.visual_selection_content().ok())
+			.flatten()
+			.map(|content| content.text);
+		self.workbench.command_range = self.is_visual_mode().then(|| self.visual_line_range()).flatten();
 		self.mode = EditorMode::Command;
 		self.visual_anchor = None;
 		self.workbench.command_line.clear();
+		self.workbench.command_palette_run_selection = false;
 		self.workbench.status_bar.mode = StatusBarMode::Command;
 		self.close_key_hints();
 		self.close_workspace_file_picker();
@@ -95,15 +91,66 @@ impl RimState {
 		self.refresh_command_palette();
 	}
 
+	pub fn enter_command_palette(&mut self) {
+		self.enter_command_mode();
+		self.workbench.command_palette_run_selection = true;
+	}
+
+	fn visual_line_range(&self) -> Option<CommandRange> {
+		let anchor = self.visual_anchor?.row as usize;
+		let cursor = self.active_cursor().row as usize;
+		Some(CommandRange { start: anchor.min(cursor), end: anchor.max(cursor) })
+	}
+
+	// Splits a leading `%`, `N`, `N,M`, `.` or `$` range off the command. A bare
+	// range with no command after it is left alone.
+	pub(crate) fn split_command_range<'a>(&self, command: &'a str) -> (Option<CommandRange>, &'a str) {
+		let line_count = self
+			.active_buffer_id()
+			.and_then(|buffer_id| self.buffers.get(buffer_id))
+			.map_or(1, |buffer| rope_line_count(&buffer.text));
+		let current_row = self.active_cursor().row as usize;
+		let parse_address = |input: &'a str| -> Option<(usize, &'a str)> {
+			if let Some(rest) = input.strip_prefix('.') {
+				return Some((current_row, rest));
+			}
+			if let Some(rest) = input.strip_prefix('$') {
+				return Some((line_count, rest));
+			}
+			let digits = input.chars().take_while(char::is_ascii_digit).count();
+			Some((input[..digits].parse().ok()?, &input[digits..]))
+		};
+
+		let parsed = if let Some(rest) = command.strip_prefix('%') {
+			Some((CommandRange { start: 1, end: line_count }, rest))
+		} else {
+			parse_address(command).and_then(|(start, rest)| match rest.strip_prefix(',') {
+				Some(rest) => parse_address(rest).map(|(end, rest)| (CommandRange { start, end }, rest)),
+				None => Some((CommandRange { start, end: start }, rest)),
+			})
+		};
+		match parsed {
+			Some((range, rest)) if !rest.trim().is_empty() => {
+				let range = CommandRange { start: range.start.min(range.end), end: range.start.max(range.end) };
+				(Some(range), rest.trim_start())
+			}
+			_ => (None, command),
+		}
+	}
+
 	pub fn exit_command_mode(&mut self) {
 		self.mode = EditorMode::Normal;
 		self.visual_anchor = None;
 		self.workbench.command_line.clear();
+		self.workbench.command_palette_run_selection = false;
 		self.workbench.status_bar.mode = StatusBarMode::Normal;
 		self.close_key_hints();
 		self.close_command_palette();
+		self.workbench.substitute_preview = None;
 		self.close_workspace_file_picker();
 		self.close_notification_center();
+		self.restore_search_origin();
+		self.workbench.search_operator = None;
 	}
 
 	pub fn enter_visual_mode(&mut self) {
@@ -193,10 +240,51 @@ impl RimState {
 
 	pub fn cancel_insert_history_group(&mut self) { self.pending_insert_group = None; }
 
+	pub fn begin_insert_repeat(&mut self, entry: EditorAction) {
+		self.workbench.pending_insert_repeat =
+			self.active_buffer_id().map(|buffer_id| PendingInsertRepeat { entry, buffer_id, keys: Vec::new() });
+	}
+
+	// Called after each insert-mode key is handled; the key that leaves insert
+	// mode has already closed the session and is not recorded.
+	pub fn record_insert_repeat_key(&mut self, key: KeyEvent) {
+		if let Some(pending) = self.workbench.pending_insert_repeat.as_mut() {
+			pending.keys.push(key);
+		}
+	}
+
+	fn finish_insert_repeat(&mut self) {
+		let Some(pending) = self.workbench.pending_insert_repeat.take() else {
+			return;
+		};
+		if !self.buffers.contains_key(pending.buffer_id) {
+			return;
+		}
+		self.workbench.last_insert = Some(InsertRepeat { entry: pending.entry, keys: pending.keys });
+	}
+
 	pub fn commit_insert_history_group(&mut self) {
+		self.finish_insert_repeat();
 		let Some(group) = self.pending_insert_group.take() else {
 			return;
 		};
+		self.push_insert_history_group(group);
+	}
+
+	// With `undobreaks`, each Enter closes the undo step so far and opens a new
+	// one, leaving the `.` repeat of the session untouched.
+	pub fn break_insert_history_group(&mut self) {
+		if !self.workbench.undo_breaks {
+			return;
+		}
+		let Some(group) = self.pending_insert_group.take() else {
+			return;
+		};
+		self.push_insert_history_group(group);
+		self.begin_insert_history_group();
+	}
+
+	fn push_insert_history_group(&mut self, group: PendingInsertUndoGroup) {
 		if group.edits.is_empty() {
 			return;
 		}
@@ -205,6 +293,7 @@ impl RimState {
 			edits: group.edits,
 			before_cursor: group.before_cursor,
 			after_cursor,
+			changed_at: None,
 		});
 	}
 }
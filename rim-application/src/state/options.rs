@@ -0,0 +1,366 @@
+use rim_ports::FileSaveOptions;
+
+use super::{FoldMethod, ListChars, RimState, VirtualEdit};
+
+impl RimState {
+	pub fn apply_set_option(&mut self, spec: &str) -> Result<String, String> {
+		let spec = spec.trim();
+		let (name, value) = match spec.split_once('=') {
+			Some((name, value)) => (name.trim(), Some(value)),
+			None => (spec, None),
+		};
+		match name {
+			"" => Err("set requires an option".to_string()),
+			"leader" => {
+				let Some(value) = value else {
+					return Ok(format!("leader={}", render_leader_key(self.workbench.leader_key)));
+				};
+				let leader = parse_leader_key(value).ok_or_else(|| format!("invalid leader key: {}", value))?;
+				self.set_leader_key(leader);
+				Ok(format!("leader={}", render_leader_key(leader)))
+			}
+			"list" => {
+				self.workbench.list_mode = true;
+				Ok("list".to_string())
+			}
+			"nolist" => {
+				self.workbench.list_mode = false;
+				Ok("nolist".to_string())
+			}
+			"indentguides" => {
+				self.workbench.indent_guides = true;
+				Ok("indentguides".to_string())
+			}
+			"noindentguides" => {
+				self.workbench.indent_guides = false;
+				Ok("noindentguides".to_string())
+			}
+			"shiftwidth" | "sw" => {
+				let Some(value) = value else {
+					return Ok(format!("shiftwidth={}", self.active_indent_settings().shift_width));
+				};
+				let width = value
+					.trim()
+					.parse::<usize>()
+					.ok()
+					.filter(|width| *width > 0)
+					.ok_or_else(|| format!("invalid shiftwidth: {}", value))?;
+				self.update_indent_settings(|settings| settings.shift_width = width);
+				Ok(format!("shiftwidth={}", width))
+			}
+			"tabstop" | "ts" => {
+				let Some(value) = value else {
+					return Ok(format!("tabstop={}", self.active_indent_settings().tab_stop));
+				};
+				let width = value
+					.trim()
+					.parse::<usize>()
+					.ok()
+					.filter(|width| *width > 0)
+					.ok_or_else(|| format!("invalid tabstop: {}", value))?;
+				self.update_indent_settings(|settings| settings.tab_stop = width);
+				Ok(format!("tabstop={}", width))
+			}
+			"expandtab" | "et" => {
+				self.update_indent_settings(|settings| settings.expand_tab = true);
+				Ok("expandtab".to_string())
+			}
+			"noexpandtab" | "noet" => {
+				self.update_indent_settings(|settings| settings.expand_tab = false);
+				Ok("noexpandtab".to_string())
+			}
+			"joinspaces" | "js" => {
+				self.workbench.join_spaces = true;
+				Ok("joinspaces".to_string())
+			}
+			"nojoinspaces" | "nojs" => {
+				self.workbench.join_spaces = false;
+				Ok("nojoinspaces".to_string())
+			}
+			"undobreaks" => {
+				self.workbench.undo_breaks = true;
+				Ok("undobreaks".to_string())
+			}
+			"noundobreaks" => {
+				self.workbench.undo_breaks = false;
+				Ok("noundobreaks".to_string())
+			}
+			"spell" => {
+				self.workbench.spell = true;
+				Ok("spell".to_string())
+			}
+			"nospell" => {
+				self.workbench.spell = false;
+				Ok("nospell".to_string())
+			}
+			"smartindent" | "si" => {
+				self.workbench.smart_indent = true;
+				Ok("smartindent".to_string())
+			}
+			"nosmartindent" | "nosi" => {
+				self.workbench.smart_indent = false;
+				Ok("nosmartindent".to_string())
+			}
+			"wrap" => {
+				self.workbench.word_wrap = true;
+				self.set_active_window_word_wrap(None);
+				Ok("wrap".to_string())
+			}
+			"nowrap" => {
+				self.workbench.word_wrap = false;
+				self.set_active_window_word_wrap(None);
+				Ok("nowrap".to_string())
+			}
+			"cursorblink" => {
+				self.workbench.cursor_blink = true;
+				Ok("cursorblink".to_string())
+			}
+			"nocursorblink" => {
+				self.workbench.cursor_blink = false;
+				Ok("nocursorblink".to_string())
+			}
+			"scrollbind" | "scb" => {
+				self.set_active_window_scroll_bind(true);
+				Ok("scrollbind".to_string())
+			}
+			"noscrollbind" | "noscb" => {
+				self.set_active_window_scroll_bind(false);
+				Ok("noscrollbind".to_string())
+			}
+			"foldmethod" | "fdm" => {
+				let Some(value) = value else {
+					return Ok(format!("foldmethod={}", render_fold_method(self.workbench.fold_method)));
+				};
+				let fold_method = match value.trim() {
+					"manual" => FoldMethod::Manual,
+					"indent" => FoldMethod::Indent,
+					_ => return Err(format!("invalid foldmethod: {}", value)),
+				};
+				self.set_fold_method(fold_method);
+				Ok(format!("foldmethod={}", render_fold_method(fold_method)))
+			}
+			"statusline" | "stl" => {
+				let Some(value) = value else {
+					return Ok(format!("statusline={}", self.workbench.status_line_format));
+				};
+				self.workbench.status_line_format = value.to_string();
+				Ok(format!("statusline={}", value))
+			}
+			"listchars" | "lcs" => {
+				let Some(value) = value else {
+					return Ok(format!("listchars={}", render_list_chars(self.workbench.list_chars)));
+				};
+				let list_chars = parse_list_chars(value).ok_or_else(|| format!("invalid listchars: {}", value))?;
+				self.workbench.list_chars = list_chars;
+				Ok(format!("listchars={}", render_list_chars(list_chars)))
+			}
+			"persistsession" => {
+				self.workbench.persist_session = true;
+				Ok("persistsession".to_string())
+			}
+			"nopersistsession" => {
+				self.workbench.persist_session = false;
+				Ok("nopersistsession".to_string())
+			}
+			"detectindent" => {
+				self.workbench.detect_indent = true;
+				Ok("detectindent".to_string())
+			}
+			"nodetectindent" => {
+				self.workbench.detect_indent = false;
+				Ok("nodetectindent".to_string())
+			}
+			"backup" | "bk" => {
+				self.workbench.backup = true;
+				Ok("backup".to_string())
+			}
+			"nobackup" | "nobk" => {
+				self.workbench.backup = false;
+				Ok("nobackup".to_string())
+			}
+			"atomicsave" => {
+				self.workbench.atomic_save = true;
+				Ok("atomicsave".to_string())
+			}
+			"noatomicsave" => {
+				self.workbench.atomic_save = false;
+				Ok("noatomicsave".to_string())
+			}
+			"scrolloff" | "so" => {
+				let Some(value) = value else {
+					return Ok(format!("scrolloff={}", self.workbench.cursor_scroll_threshold));
+				};
+				let lines = value.trim().parse::<u16>().map_err(|_| format!("invalid scrolloff: {}", value))?;
+				self.workbench.cursor_scroll_threshold = lines;
+				self.align_active_window_scroll_to_cursor();
+				Ok(format!("scrolloff={}", lines))
+			}
+			"sidescrolloff" | "siso" => {
+				let Some(value) = value else {
+					return Ok(format!("sidescrolloff={}", self.workbench.cursor_side_scroll_threshold));
+				};
+				let columns = value.trim().parse::<u16>().map_err(|_| format!("invalid sidescrolloff: {}", value))?;
+				self.workbench.cursor_side_scroll_threshold = columns;
+				self.align_active_window_scroll_to_cursor();
+				Ok(format!("sidescrolloff={}", columns))
+			}
+			"scrolljump" | "sj" => {
+				let Some(value) = value else {
+					return Ok(format!("scrolljump={}", self.workbench.scroll_jump));
+				};
+				let lines = value
+					.trim()
+					.parse::<u16>()
+					.ok()
+					.filter(|lines| *lines > 0)
+					.ok_or_else(|| format!("invalid scrolljump: {}", value))?;
+				self.workbench.scroll_jump = lines;
+				Ok(format!("scrolljump={}", lines))
+			}
+			"laststatus" | "ls" => {
+				let Some(value) = value else {
+					return Ok(format!("laststatus={}", self.workbench.last_status));
+				};
+				let last_status = value
+					.trim()
+					.parse::<u8>()
+					.ok()
+					.filter(|last_status| *last_status <= 2)
+					.ok_or_else(|| format!("invalid laststatus: {}", value))?;
+				self.workbench.last_status = last_status;
+				Ok(format!("laststatus={}", last_status))
+			}
+			"undolevels" | "ul" => {
+				let Some(value) = value else {
+					return Ok(format!("undolevels={}", self.undo_levels));
+				};
+				let levels = value.trim().parse::<usize>().map_err(|_| format!("invalid undolevels: {}", value))?;
+				self.editor.set_undo_levels(levels);
+				Ok(format!("undolevels={}", levels))
+			}
+			"filetype" | "ft" => {
+				let Some(value) = value else {
+					let filetype = self
+						.active_buffer_id()
+						.and_then(|buffer_id| self.buffers.get(buffer_id))
+						.and_then(|buffer| buffer.filetype())
+						.unwrap_or_default();
+					return Ok(format!("filetype={}", filetype));
+				};
+				let filetype = value.trim();
+				if !filetype.chars().all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '-' | '.')) {
+					return Err(format!("invalid filetype: {}", value));
+				}
+				let filetype = self.set_active_buffer_filetype(filetype).ok_or("no active buffer")?;
+				Ok(format!("filetype={}", filetype))
+			}
+			"virtualedit" | "ve" => {
+				let Some(value) = value else {
+					return Ok(format!("virtualedit={}", render_virtual_edit(self.virtual_edit)));
+				};
+				let virtual_edit = match value.trim() {
+					"" | "none" => VirtualEdit::None,
+					"block" => VirtualEdit::Block,
+					"all" => VirtualEdit::All,
+					_ => return Err(format!("invalid virtualedit: {}", value)),
+				};
+				self.virtual_edit = virtual_edit;
+				if virtual_edit != VirtualEdit::All {
+					self.virtual_cursor = None;
+				}
+				Ok(format!("virtualedit={}", render_virtual_edit(virtual_edit)))
+			}
+			"pastemax" => {
+				let Some(value) = value else {
+					return Ok(format!("pastemax={}", self.workbench.paste_max_chars));
+				};
+				let max = value.trim().parse::<usize>().map_err(|_| format!("invalid pastemax: {}", value))?;
+				self.workbench.paste_max_chars = max;
+				Ok(format!("pastemax={}", max))
+			}
+			_ => Err(format!("unknown option: {}", name)),
+		}
+	}
+
+	// `:setlocal` only changes the active window's copy of a window option.
+	pub fn apply_set_local_option(&mut self, spec: &str) -> Result<String, String> {
+		match spec.trim() {
+			"" => Err("setlocal requires an option".to_string()),
+			"wrap" => {
+				self.set_active_window_word_wrap(Some(true));
+				Ok("wrap".to_string())
+			}
+			"nowrap" => {
+				self.set_active_window_word_wrap(Some(false));
+				Ok("nowrap".to_string())
+			}
+			name => Err(format!("unknown local option: {}", name)),
+		}
+	}
+
+	pub fn file_save_options(&self) -> FileSaveOptions {
+		FileSaveOptions { atomic: self.workbench.atomic_save, backup: self.workbench.backup }
+	}
+
+	pub fn set_leader_key(&mut self, leader: char) {
+		if self.workbench.leader_key == leader {
+			return;
+		}
+		self.workbench.leader_key = leader;
+		self.workbench.normal_sequence.clear();
+		self.workbench.status_bar.key_sequence.clear();
+		self.close_key_hints();
+	}
+}
+
+fn parse_leader_key(value: &str) -> Option<char> {
+	if value.eq_ignore_ascii_case("<space>") || value.eq_ignore_ascii_case("space") {
+		return Some(' ');
+	}
+	let mut chars = value.chars();
+	match (chars.next(), chars.next()) {
+		(Some(ch), None) if !ch.is_control() => Some(ch),
+		_ => None,
+	}
+}
+
+// Accepts Vim's `tab:XY,trail:C` form; omitted entries keep their defaults.
+fn parse_list_chars(value: &str) -> Option<ListChars> {
+	let mut list_chars = ListChars::default();
+	for entry in value.split(',') {
+		let (name, glyphs) = entry.split_once(':')?;
+		let glyphs = glyphs.chars().collect::<Vec<_>>();
+		match (name, glyphs.as_slice()) {
+			("tab", [head, fill]) => {
+				list_chars.tab_head = *head;
+				list_chars.tab_fill = *fill;
+			}
+			("trail", [trail]) => list_chars.trail = *trail,
+			_ => return None,
+		}
+	}
+	Some(list_chars)
+}
+
+fn render_list_chars(list_chars: ListChars) -> String {
+	format!("tab:{}{},trail:{}", list_chars.tab_head, list_chars.tab_fill, list_chars.trail)
+}
+
+fn render_fold_method(fold_method: FoldMethod) -> &'static str {
+	match fold_method {
+		FoldMethod::Manual => "manual",
+		FoldMethod::Indent => "indent",
+	}
+}
+
+fn render_virtual_edit(virtual_edit: VirtualEdit) -> &'static str {
+	match virtual_edit {
+		VirtualEdit::None => "none",
+		VirtualEdit::Block => "block",
+		VirtualEdit::All => "all",
+	}
+}
+
+fn render_leader_key(leader: char) -> String {
+	if leader == ' ' { "<Space>".to_string() } else { leader.to_string() }
+}
@@ -0,0 +1,72 @@
+use super::{BufferId, NotificationLevel, RimState};
+
+// A paste up to this many chars goes in at once. A longer one goes in this
+// many chars per tick, so the screen keeps drawing while it lands.
+pub(crate) const PASTE_CHUNK_CHARS: usize = 65_536;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingPaste {
+	pub buffer_id:        BufferId,
+	pub text:             String,
+	// Byte offset into `text` of what is still to insert.
+	pub inserted:         usize,
+	// Set when the paste arrived in normal mode and entered insert mode for it.
+	pub return_to_normal: bool,
+}
+
+impl RimState {
+	// Terminals send pasted line breaks as `\r`. The paste lands as one insert,
+	// so it undoes as one step however many chunks it takes.
+	pub fn begin_paste(&mut self, text: &str, return_to_normal: bool) {
+		let Some(buffer_id) = self.active_buffer_id() else {
+			return;
+		};
+		let mut text = text.replace("\r\n", "\n").replace('\r', "\n");
+		let max = self.workbench.paste_max_chars;
+		if max > 0
+			&& let Some((cut, _)) = text.char_indices().nth(max)
+		{
+			let total = text.chars().count();
+			text.truncate(cut);
+			self
+				.push_notification(NotificationLevel::Warn, format!("paste truncated to {} of {} chars", max, total));
+		}
+		self.workbench.pending_paste = Some(PendingPaste { buffer_id, text, inserted: 0, return_to_normal });
+		self.insert_next_paste_chunk();
+	}
+
+	pub fn has_pending_paste(&self) -> bool { self.workbench.pending_paste.is_some() }
+
+	pub fn insert_next_paste_chunk(&mut self) {
+		let Some(mut pending) = self.workbench.pending_paste.take() else {
+			return;
+		};
+		if self.active_buffer_id() != Some(pending.buffer_id) || !self.is_insert_mode() {
+			self.workbench.status_bar.message = "paste stopped: buffer changed".to_string();
+			return;
+		}
+		let rest = &pending.text[pending.inserted..];
+		let end = rest.char_indices().nth(PASTE_CHUNK_CHARS).map_or(rest.len(), |(end, _)| end);
+		let chunked = pending.inserted > 0 || end < rest.len();
+		self.insert_text_at_cursor(&rest[..end]);
+		pending.inserted += end;
+		if pending.inserted < pending.text.len() {
+			let percent = pending.inserted * 100 / pending.text.len();
+			self.workbench.status_bar.message = format!("pasting {}%", percent);
+			self.workbench.pending_paste = Some(pending);
+			return;
+		}
+		if chunked {
+			self.workbench.status_bar.message = format!("pasted {} chars", pending.text.chars().count());
+		}
+		if pending.return_to_normal {
+			self.exit_insert_mode();
+		}
+	}
+
+	pub fn finish_pending_paste(&mut self) {
+		while self.has_pending_paste() {
+			self.insert_next_paste_chunk();
+		}
+	}
+}
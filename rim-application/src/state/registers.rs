@@ -0,0 +1,93 @@
+use super::{FloatingWindowLine, FloatingWindowPlacement, FloatingWindowState, OverlayState, RimState};
+
+const REGISTER_PREVIEW_MAX_CHARS: usize = 48;
+
+impl RimState {
+	// `%` reads the active buffer's path, relative to the working directory when
+	// it lies inside it. A line-wise register keeps the newline it ends with.
+	pub fn register_text(&self, name: char) -> Option<String> {
+		if name == '%' {
+			let path =
+				self.active_buffer_id().and_then(|buffer_id| self.buffers.get(buffer_id))?.path.as_deref()?;
+			let path = path.strip_prefix(self.working_directory()).unwrap_or(path);
+			return Some(path.to_string_lossy().into_owned());
+		}
+		let content = self.register_content(name)?;
+		Some(if content.line_wise { format!("{}\n", content.text) } else { content.text })
+	}
+
+	pub fn insert_register_at_cursor(&mut self, name: char) {
+		let Some(text) = self.register_text(name) else {
+			self.workbench.status_bar.message = format!("insert failed: register {} is empty", name);
+			return;
+		};
+		for ch in text.chars() {
+			if ch == '\n' {
+				self.editor.insert_newline_at_cursor();
+			} else {
+				self.editor.insert_char_at_cursor(ch);
+			}
+		}
+		self.align_active_window_scroll_to_cursor();
+	}
+
+	// The command line is a single line, so newlines are dropped.
+	pub fn insert_register_into_command_line(&mut self, name: char) {
+		let Some(text) = self.register_text(name) else {
+			self.workbench.status_bar.message = format!("insert failed: register {} is empty", name);
+			return;
+		};
+		self.workbench.command_line.extend(text.chars().filter(|ch| *ch != '\n'));
+		self.refresh_command_palette();
+	}
+
+	pub fn show_registers(&mut self) {
+		let lines = self
+			.register_listing()
+			.into_iter()
+			.map(|(name, content)| {
+				let kind = if content.block_wise {
+					'b'
+				} else if content.line_wise {
+					'l'
+				} else {
+					'c'
+				};
+				FloatingWindowLine {
+					key:       format!("\"{}", name),
+					summary:   format!("{}  {}", kind, register_preview(content.text.as_str())),
+					is_prefix: false,
+				}
+			})
+			.collect::<Vec<_>>();
+		if lines.is_empty() {
+			self.workbench.status_bar.message = "registers are empty".to_string();
+			return;
+		}
+		let height = lines.len().saturating_add(4).min(self.workbench.key_hints_max_height as usize) as u16;
+		self.workbench.overlay = Some(OverlayState::FloatingWindow(FloatingWindowState {
+			title: "Registers".to_string(),
+			subtitle: None,
+			footer: Some("Esc close".to_string()),
+			placement: FloatingWindowPlacement::BottomRight {
+				width:         self.workbench.key_hints_width,
+				height:        height.max(4),
+				margin_right:  1,
+				margin_bottom: 1,
+			},
+			lines,
+			scroll: 0,
+		}));
+	}
+}
+
+// Control characters are shown Vim-style so every register fits on one row.
+fn register_preview(text: &str) -> String {
+	let escaped = text.replace('\n', "^J").replace('\t', "^I");
+	if escaped.chars().count() <= REGISTER_PREVIEW_MAX_CHARS {
+		return escaped;
+	}
+	let mut preview = escaped.chars().take(REGISTER_PREVIEW_MAX_CHARS.saturating_sub(3)).collect::<String>();
+	preview.push_str("...");
+	preview
+}
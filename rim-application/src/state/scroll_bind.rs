@@ -0,0 +1,54 @@
+use super::{RimState, WindowId, rope_line_count};
+
+impl RimState {
+	pub(super) fn set_active_window_scroll_bind(&mut self, scrollbind: bool) {
+		let active_window_id = self.active_window_id();
+		if let Some(window) = self.windows.get_mut(active_window_id) {
+			window.scrollbind = scrollbind;
+		}
+		self.workbench.scroll_bind_anchor = None;
+	}
+
+	// Runs after every action: when a bound active window scrolled since the
+	// last sync, the other bound windows of the tab move by the same delta.
+	pub fn sync_scroll_bound_windows(&mut self) {
+		let active_window_id = self.active_window_id();
+		let Some(scroll_y) =
+			self.windows.get(active_window_id).filter(|window| window.scrollbind).map(|window| window.scroll_y)
+		else {
+			self.workbench.scroll_bind_anchor = None;
+			return;
+		};
+		let anchor = self.workbench.scroll_bind_anchor.replace((active_window_id, scroll_y));
+		let Some((_, previous_scroll_y)) = anchor.filter(|(window_id, _)| *window_id == active_window_id) else {
+			return;
+		};
+		let delta = i32::from(scroll_y) - i32::from(previous_scroll_y);
+		if delta == 0 {
+			return;
+		}
+		for window_id in self.active_tab_window_ids() {
+			if window_id == active_window_id {
+				continue;
+			}
+			let Some(window) = self.windows.get(window_id).filter(|window| window.scrollbind) else {
+				continue;
+			};
+			let max_scroll = i32::from(self.window_max_scroll_y(window_id));
+			let next_scroll = (i32::from(window.scroll_y) + delta).clamp(0, max_scroll.max(0));
+			if let Some(window) = self.editor.windows.get_mut(window_id) {
+				window.scroll_y = next_scroll as u16;
+			}
+		}
+	}
+
+	// Bound and diff windows may scroll until their last line is the top one.
+	pub(super) fn window_max_scroll_y(&self, window_id: WindowId) -> u16 {
+		self
+			.windows
+			.get(window_id)
+			.and_then(|window| window.buffer_id)
+			.and_then(|buffer_id| self.buffers.get(buffer_id))
+			.map_or(0, |buffer| rope_line_count(&buffer.text).saturating_sub(1) as u16)
+	}
+}
@@ -0,0 +1,126 @@
+use rim_domain::search::{SearchMatch, find_next_match};
+
+use super::{EditorMode, EditorOperationError, RimState, SearchOperator, SearchOrigin, StatusBarMode, rope_line_without_newline};
+
+impl RimState {
+	// `/` reuses the command line; the leading slash tells submit to search.
+	pub fn begin_search(&mut self) {
+		let origin = self.is_visual_mode().then_some(SearchOrigin {
+			mode:                     self.mode,
+			anchor:                   self.visual_anchor,
+			block_anchor_display_col: self.visual_block_anchor_display_col,
+		});
+		self.enter_command_mode();
+		self.workbench.search_origin = origin;
+		self.push_command_char('/');
+	}
+
+	pub fn begin_search_operator(&mut self, operator: SearchOperator) {
+		self.begin_search();
+		self.workbench.search_operator = Some(operator);
+	}
+
+	pub fn search_forward(&mut self, pattern: &str) {
+		let Some((pattern, found)) = self.find_search_match(pattern) else {
+			return;
+		};
+		self.editor.move_cursor_to(found.cursor);
+		if self.is_visual_block_mode() {
+			// Clear the stale block column first so the display col is read from the
+			// cursor.
+			self.visual_block_cursor_display_col = None;
+			self.visual_block_cursor_display_col = Some(self.active_cursor_display_col());
+		}
+		self.align_active_window_scroll_to_cursor();
+		self.workbench.status_bar.message = if found.wrapped {
+			"search hit BOTTOM, continuing at TOP".to_string()
+		} else {
+			format!("/{}", pattern)
+		};
+	}
+
+	// Visual `*`. Search patterns are literal, so the selected text is searched
+	// for as it stands; they match within one line, so the selection must too.
+	pub fn search_visual_selection(&mut self) {
+		let Some((start, end)) = self.normalized_visual_bounds() else {
+			return;
+		};
+		if start.row != end.row {
+			self.workbench.status_bar.message = "search failed: selection spans lines".to_string();
+			return;
+		}
+		let pattern = self
+			.active_buffer_id()
+			.and_then(|buffer_id| self.buffers.get(buffer_id))
+			.and_then(|buffer| rope_line_without_newline(&buffer.text, start.row.saturating_sub(1) as usize))
+			.map(|line| {
+				let len = usize::from(end.col.saturating_sub(start.col)).saturating_add(1);
+				line.chars().skip(start.col.saturating_sub(1) as usize).take(len).collect::<String>()
+			})
+			.unwrap_or_default();
+		self.exit_visual_mode();
+		if pattern.is_empty() {
+			self.workbench.status_bar.message = "search failed: empty selection".to_string();
+			return;
+		}
+		self.editor.move_cursor_to(start);
+		self.search_forward(pattern.as_str());
+	}
+
+	pub fn delete_to_search_match(&mut self, pattern: &str) {
+		let Some((pattern, found)) = self.find_search_match(pattern) else {
+			return;
+		};
+		match self.editor.delete_to_cursor_to_slot(found.cursor) {
+			Ok(()) => {
+				self.align_active_window_scroll_to_cursor();
+				self.workbench.status_bar.message = format!("deleted to /{}", pattern);
+			}
+			Err(EditorOperationError::NoChar) => {
+				self.workbench.status_bar.message = "delete failed: no char".to_string();
+			}
+			Err(other) => {
+				self.workbench.status_bar.message = format!("delete failed: {:?}", other);
+			}
+		}
+	}
+
+	// An empty pattern repeats the last search, as in Vim.
+	fn find_search_match(&mut self, pattern: &str) -> Option<(String, SearchMatch)> {
+		let pattern = if pattern.is_empty() {
+			let Some(last) = self.workbench.last_search.clone() else {
+				self.workbench.status_bar.message = "no previous search pattern".to_string();
+				return None;
+			};
+			last
+		} else {
+			pattern.to_string()
+		};
+		self.workbench.last_search = Some(pattern.clone());
+		let Some(found) = self
+			.active_buffer_id()
+			.and_then(|buffer_id| self.buffers.get(buffer_id))
+			.and_then(|buffer| find_next_match(&buffer.text, self.active_cursor(), pattern.as_str()))
+		else {
+			self.workbench.status_bar.message = format!("pattern not found: {}", pattern);
+			return None;
+		};
+		Some((pattern, found))
+	}
+
+	// Leaving the search prompt from visual mode keeps the selection anchor, so a
+	// search extends the selection instead of dropping it.
+	pub(super) fn restore_search_origin(&mut self) {
+		let Some(origin) = self.workbench.search_origin.take() else {
+			return;
+		};
+		self.mode = origin.mode;
+		self.visual_anchor = origin.anchor;
+		self.visual_block_anchor_display_col = origin.block_anchor_display_col;
+		self.workbench.status_bar.mode = match origin.mode {
+			EditorMode::VisualLine => StatusBarMode::VisualLine,
+			EditorMode::VisualBlock => StatusBarMode::VisualBlock,
+			_ => StatusBarMode::Visual,
+		};
+	}
+}
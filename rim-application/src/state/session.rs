@@ -1,11 +1,67 @@
-use super::{RimState, StatusBarState, WorkspaceSessionSnapshot};
+use std::path::PathBuf;
+
+use super::{BufferId, RimState, StatusBarState, WorkspaceSessionSnapshot};
+
+pub(crate) const DEFAULT_SESSION_FILE_NAME: &str = "Session.json";
 
 impl RimState {
-	pub fn workspace_session_snapshot(&self) -> WorkspaceSessionSnapshot {
-		self.editor.workspace_session_snapshot(self.workbench.force_quit_trim_file_dirty_in_session)
+	// With `persistsession` on, quitting writes the layout here and a launch
+	// without file arguments sources it back.
+	pub fn persisted_session_path(&self) -> Option<PathBuf> {
+		self.workbench.persist_session.then(|| self.working_directory().join(DEFAULT_SESSION_FILE_NAME))
+	}
+
+	pub fn workspace_session_snapshot(&mut self) -> WorkspaceSessionSnapshot {
+		let trim_dirty_file_buffers = self.workbench.force_quit_trim_file_dirty_in_session;
+		self.with_zoomed_tabs_restored(|state| state.editor.workspace_session_snapshot(trim_dirty_file_buffers))
+	}
+
+	pub fn layout_session_snapshot(&mut self) -> WorkspaceSessionSnapshot {
+		self.with_zoomed_tabs_restored(|state| state.editor.layout_session_snapshot())
 	}
 
 	pub fn restore_workspace_session(&mut self, snapshot: WorkspaceSessionSnapshot) -> bool {
+		self.reset_workbench_for_session_restore();
+		if !self.editor.restore_workspace_session(snapshot) {
+			return false;
+		}
+		self.workbench.status_bar.message = "session restored".to_string();
+		true
+	}
+
+	// Restores the layout of a session file and returns the file buffers that
+	// still need to be loaded.
+	pub fn restore_layout_session(&mut self, snapshot: WorkspaceSessionSnapshot) -> Option<Vec<BufferId>> {
+		self.reset_workbench_for_session_restore();
+		if !self.editor.restore_layout_session(snapshot) {
+			return None;
+		}
+		let pending = self
+			.buffers
+			.iter()
+			.filter_map(|(buffer_id, buffer)| buffer.path.is_some().then_some(buffer_id))
+			.collect::<Vec<_>>();
+		self.workbench.pending_session_loads = pending.iter().copied().collect();
+		if self.buffers.is_empty() {
+			self.create_untitled_buffer();
+		}
+		Some(pending)
+	}
+
+	// Session cursors win over the persisted undo view and are clamped once the
+	// file text is known.
+	pub fn finish_session_buffer_load(&mut self, buffer_id: BufferId) -> bool {
+		if !self.workbench.pending_session_loads.remove(&buffer_id) {
+			return false;
+		}
+		self.editor.clamp_window_cursors_for_buffer(buffer_id);
+		if self.active_buffer_id() == Some(buffer_id) {
+			self.clamp_cursor_to_navigable_col();
+		}
+		true
+	}
+
+	fn reset_workbench_for_session_restore(&mut self) {
 		self.workbench.command_line.clear();
 		self.workbench.quit_after_save = false;
 		self.workbench.force_quit_trim_file_dirty_in_session = false;
@@ -15,11 +71,10 @@ impl RimState {
 		self.workbench.pending_swap_decision = None;
 		self.workbench.in_flight_internal_saves.clear();
 		self.workbench.ignore_external_change_until.clear();
+		self.workbench.tag_stack.clear();
+		self.workbench.pending_tag_jump = None;
+		self.workbench.pending_session_loads.clear();
+		self.workbench.zoomed_layouts.clear();
 		self.workbench.status_bar = StatusBarState::default();
-		if !self.editor.restore_workspace_session(snapshot) {
-			return false;
-		}
-		self.workbench.status_bar.message = "session restored".to_string();
-		true
 	}
 }
@@ -0,0 +1,152 @@
+use std::ops::Range;
+
+use rim_domain::spell::{misspelled_word_ranges, spell_suggestions};
+
+use super::{CursorState, FloatingWindowLine, FloatingWindowPlacement, FloatingWindowState, OverlayState, RimState, rope_line_count, rope_line_without_newline};
+
+const SPELL_SUGGESTION_LIMIT: usize = 9;
+
+impl RimState {
+	pub fn misspelled_char_ranges(&self, line: &str) -> Vec<Range<usize>> {
+		if !self.workbench.spell {
+			return Vec::new();
+		}
+		misspelled_word_ranges(line, &self.workbench.spell_good_words)
+	}
+
+	// Like `]c`, the search wraps around the buffer and says so.
+	pub fn move_cursor_to_misspelling(&mut self, forward: bool) {
+		if !self.workbench.spell {
+			self.workbench.status_bar.message = "spell checking is off; :set spell".to_string();
+			return;
+		}
+		let Some(buffer) = self.active_buffer_id().and_then(|buffer_id| self.buffers.get(buffer_id)) else {
+			return;
+		};
+		let cursor = self.active_cursor();
+		let current_row = cursor.row.saturating_sub(1) as usize;
+		let current_col = cursor.col.saturating_sub(1) as usize;
+		let line_count = rope_line_count(&buffer.text);
+		let rows = if forward {
+			(current_row..line_count).chain(0..=current_row).collect::<Vec<_>>()
+		} else {
+			(0..=current_row).rev().chain((current_row..line_count).rev()).collect::<Vec<_>>()
+		};
+		let unwrapped_steps =
+			if forward { line_count.saturating_sub(current_row) } else { current_row.saturating_add(1) };
+		let target = rows.into_iter().enumerate().find_map(|(step, row_idx)| {
+			let line = rope_line_without_newline(&buffer.text, row_idx)?;
+			let ranges = self.misspelled_char_ranges(line.as_str());
+			let start = if step == 0 && forward {
+				ranges.iter().find(|range| range.start > current_col)
+			} else if step == 0 {
+				ranges.iter().rev().find(|range| range.start < current_col)
+			} else if forward {
+				ranges.first()
+			} else {
+				ranges.last()
+			}?
+			.start;
+			Some((row_idx, start, step >= unwrapped_steps))
+		});
+		let Some((row_idx, start, wrapped)) = target else {
+			self.workbench.status_bar.message = "no misspellings found".to_string();
+			return;
+		};
+		self.editor.move_cursor_to(CursorState {
+			row: row_idx.saturating_add(1) as u16,
+			col: start.saturating_add(1) as u16,
+		});
+		if wrapped {
+			self.workbench.status_bar.message =
+				if forward { "spell jump wrapped to top" } else { "spell jump wrapped to bottom" }.to_string();
+		}
+		self.align_active_window_scroll_to_cursor();
+	}
+
+	pub fn mark_spell_word_good(&mut self) {
+		let Some((_, _, word)) = self.spell_word_under_cursor() else {
+			self.workbench.status_bar.message = "no word under cursor".to_string();
+			return;
+		};
+		self.workbench.status_bar.message = format!("good word: {}", word);
+		self.workbench.spell_good_words.insert(word.to_lowercase());
+	}
+
+	// Without a count this lists the suggestions; `3z=` takes the third one.
+	pub fn suggest_spelling(&mut self, count: Option<usize>) {
+		let Some((row_idx, chars, word)) = self.spell_word_under_cursor() else {
+			self.workbench.status_bar.message = "no word under cursor".to_string();
+			return;
+		};
+		let suggestions =
+			spell_suggestions(word.as_str(), &self.workbench.spell_good_words, SPELL_SUGGESTION_LIMIT);
+		if suggestions.is_empty() {
+			self.workbench.status_bar.message = format!("no suggestions for {}", word);
+			return;
+		}
+		let Some(count) = count else {
+			let lines = suggestions
+				.iter()
+				.enumerate()
+				.map(|(index, suggestion)| FloatingWindowLine {
+					key:       index.saturating_add(1).to_string(),
+					summary:   suggestion.clone(),
+					is_prefix: false,
+				})
+				.collect::<Vec<_>>();
+			let height = lines.len().saturating_add(4).min(self.workbench.key_hints_max_height as usize) as u16;
+			self.workbench.overlay = Some(OverlayState::FloatingWindow(FloatingWindowState {
+				title: format!("Spelling: {}", word),
+				subtitle: None,
+				footer: Some("<n>z= replace  Esc close".to_string()),
+				placement: FloatingWindowPlacement::BottomRight {
+					width:         self.workbench.key_hints_width,
+					height:        height.max(4),
+					margin_right:  1,
+					margin_bottom: 1,
+				},
+				lines,
+				scroll: 0,
+			}));
+			return;
+		};
+		let Some(replacement) = suggestions.get(count.saturating_sub(1)) else {
+			self.workbench.status_bar.message = format!("only {} suggestions for {}", suggestions.len(), word);
+			return;
+		};
+		match self.editor.replace_row_chars(row_idx, chars, replacement.as_str()) {
+			Ok(()) => {
+				self.workbench.status_bar.message = format!("{} -> {}", word, replacement);
+				self.align_active_window_scroll_to_cursor();
+			}
+			Err(other) => {
+				self.workbench.status_bar.message = format!("spell replace failed: {:?}", other);
+			}
+		}
+	}
+
+	fn spell_word_under_cursor(&self) -> Option<(usize, Range<usize>, String)> {
+		let buffer = self.buffers.get(self.active_buffer_id()?)?;
+		let cursor = self.active_cursor();
+		let row_idx = cursor.row.saturating_sub(1) as usize;
+		let chars = rope_line_without_newline(&buffer.text, row_idx)?.chars().collect::<Vec<_>>();
+		let cursor_idx = cursor.col.saturating_sub(1) as usize;
+		if !chars.get(cursor_idx).is_some_and(|ch| ch.is_alphabetic()) {
+			return None;
+		}
+		let is_word_at = |index: usize| {
+			chars[index].is_alphabetic()
+				|| (chars[index] == '\''
+					&& index > 0
+					&& chars[index - 1].is_alphabetic()
+					&& chars.get(index + 1).is_some_and(|ch| ch.is_alphabetic()))
+		};
+		let start = (0..cursor_idx).rev().take_while(|index| is_word_at(*index)).last().unwrap_or(cursor_idx);
+		let end = (cursor_idx..chars.len())
+			.take_while(|index| is_word_at(*index))
+			.last()
+			.map_or(cursor_idx, |index| index + 1);
+		Some((row_idx, start..end, chars[start..end].iter().collect()))
+	}
+}
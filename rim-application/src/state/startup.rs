@@ -0,0 +1,26 @@
+use super::{BufferId, PendingStartupCommands, RimState};
+
+impl RimState {
+	// Returns the commands to run now when the active buffer already has its
+	// text, else keeps them until it loads.
+	pub fn defer_startup_commands(&mut self, commands: Vec<String>) -> Vec<String> {
+		match self.active_buffer_id().filter(|buffer_id| self.workbench.loading_buffers.contains(buffer_id)) {
+			Some(buffer_id) => {
+				self.workbench.pending_startup_commands = Some(PendingStartupCommands { buffer_id, commands });
+				Vec::new()
+			}
+			None => commands,
+		}
+	}
+
+	pub fn has_pending_startup_commands(&self, buffer_id: BufferId) -> bool {
+		self.workbench.pending_startup_commands.as_ref().is_some_and(|pending| pending.buffer_id == buffer_id)
+	}
+
+	pub fn take_startup_commands(&mut self, buffer_id: BufferId) -> Vec<String> {
+		if !self.has_pending_startup_commands(buffer_id) {
+			return Vec::new();
+		}
+		self.workbench.pending_startup_commands.take().map(|pending| pending.commands).unwrap_or_default()
+	}
+}
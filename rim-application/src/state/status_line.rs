@@ -0,0 +1,131 @@
+use std::path::{MAIN_SEPARATOR, Path};
+
+use rim_domain::display_geometry::display_col_of_cursor_slot;
+
+use super::{RimState, rope_line_count, rope_line_without_newline};
+
+// What the status line shows after the message when `status_line` is unset.
+const DEFAULT_STATUS_LINE_FORMAT: &str = "%l:%c %P%r%n";
+
+impl RimState {
+	// The status bar is the only place the command line and messages are drawn.
+	pub fn status_bar_visible(&self) -> bool {
+		if self.is_command_mode() || self.status_message_unread() {
+			return true;
+		}
+		match self.workbench.last_status {
+			0 => false,
+			1 => self.active_tab_window_ids().len() > 1,
+			_ => true,
+		}
+	}
+
+	pub fn status_message_unread(&self) -> bool {
+		let status_bar = &self.workbench.status_bar;
+		!status_bar.message.is_empty() && status_bar.message != status_bar.seen_message
+	}
+
+	pub fn mark_status_message_seen(&mut self) {
+		let status_bar = &mut self.workbench.status_bar;
+		if status_bar.seen_message != status_bar.message {
+			status_bar.seen_message.clone_from(&status_bar.message);
+		}
+	}
+
+	// Paths under the working directory show relative to it and other paths
+	// under the home directory with a leading `~`; the rest stay absolute.
+	pub fn display_path(&self, path: &Path) -> String {
+		if let Ok(relative) = path.strip_prefix(self.working_directory()) {
+			return relative.to_string_lossy().into_owned();
+		}
+		match self.workbench.home_directory.as_deref().and_then(|home| path.strip_prefix(home).ok()) {
+			Some(relative) if relative.as_os_str().is_empty() => "~".to_string(),
+			Some(relative) => format!("~{}{}", MAIN_SEPARATOR, relative.to_string_lossy()),
+			None => path.to_string_lossy().into_owned(),
+		}
+	}
+
+	// Expands the `status_line` format, a small subset of Vim's `statusline`:
+	// `%f` file, `%m` modified flag, `%r` read-only flag, `%n` new-file flag,
+	// `%l` line, `%c` column, `%p` percent through the file, `%P` Top/Bot or
+	// that percent, `%y` filetype and `%%`. Unknown items are kept verbatim.
+	pub fn status_line_position(&self) -> String {
+		let format = match self.workbench.status_line_format.as_str() {
+			"" => DEFAULT_STATUS_LINE_FORMAT,
+			format => format,
+		};
+		let cursor = self.active_cursor();
+		let buffer = self.active_buffer_id().and_then(|buffer_id| self.buffers.get(buffer_id));
+		// The cursor can sit on the empty row after a trailing newline while inserting.
+		let total_rows =
+			buffer.map(|buffer| rope_line_count(&buffer.text)).unwrap_or(1).max(usize::from(cursor.row)).max(1);
+		let percent = usize::from(cursor.row) * 100 / total_rows;
+
+		let mut out = String::new();
+		let mut chars = format.chars();
+		while let Some(ch) = chars.next() {
+			if ch != '%' {
+				out.push(ch);
+				continue;
+			}
+			match chars.next() {
+				Some('f') => match buffer.and_then(|buffer| buffer.path.as_deref()) {
+					Some(path) => out.push_str(self.display_path(path).as_str()),
+					None => out.push_str(buffer.map_or("untitled", |buffer| buffer.name.as_str())),
+				},
+				Some('m') => {
+					if buffer.is_some_and(|buffer| buffer.dirty) {
+						out.push_str("[+]");
+					}
+				}
+				Some('r') => {
+					if buffer.is_some_and(|buffer| buffer.read_only) {
+						out.push_str("[RO]");
+					}
+				}
+				Some('n') => {
+					if buffer.is_some_and(|buffer| buffer.new_file) {
+						out.push_str("[New]");
+					}
+				}
+				Some('l') => out.push_str(cursor.row.to_string().as_str()),
+				Some('c') => {
+					let line = buffer
+						.and_then(|buffer| rope_line_without_newline(&buffer.text, cursor.row.saturating_sub(1) as usize))
+						.unwrap_or_default();
+					let display_col = self
+						.active_virtual_display_col()
+						.unwrap_or_else(|| display_col_of_cursor_slot(line.as_str(), cursor.col));
+					let virtual_col = display_col.saturating_add(1);
+					if virtual_col == cursor.col {
+						out.push_str(cursor.col.to_string().as_str());
+					} else {
+						out.push_str(format!("{}-{}", cursor.col, virtual_col).as_str());
+					}
+				}
+				Some('p') => out.push_str(format!("{}%", percent).as_str()),
+				Some('P') => {
+					if cursor.row <= 1 {
+						out.push_str("Top");
+					} else if usize::from(cursor.row) >= total_rows {
+						out.push_str("Bot");
+					} else {
+						out.push_str(format!("{}%", percent).as_str());
+					}
+				}
+				Some('y') => {
+					if let Some(filetype) = buffer.and_then(|buffer| buffer.filetype()) {
+						out.push_str(format!("[{}]", filetype).as_str());
+					}
+				}
+				Some('%') => out.push('%'),
+				Some(other) => {
+					out.push('%');
+					out.push(other);
+				}
+				None => out.push('%'),
+			}
+		}
+		out
+	}
+}
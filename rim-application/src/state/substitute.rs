@@ -0,0 +1,123 @@
+use std::ops::Range;
+
+use rim_domain::{edit::rope_line_char_range_without_newline, search::{find_line_matches, substitute_line}};
+use ropey::Rope;
+
+use super::{BufferId, CommandRange, RimState, rope_line_count, rope_line_without_newline};
+
+// `:[range]s/pattern/replacement/[flags]` with a literal pattern. While it is
+// still being typed the replacement may be missing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubstituteInput {
+	pub pattern:     String,
+	pub replacement: Option<String>,
+	pub flags:       String,
+}
+
+// The buffer as the `:s` being typed would leave it, kept until the command
+// line closes; nothing is written to the buffer itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubstitutePreview {
+	pub buffer_id: BufferId,
+	pub text:      Rope,
+	// 0-based rows with the char ranges that match or, once a replacement is
+	// typed, that it takes in `text`.
+	pub ranges:    Vec<(usize, Range<usize>)>,
+}
+
+pub(crate) fn parse_substitute_input(command: &str) -> Option<SubstituteInput> {
+	let rest = command.strip_prefix("substitute").or_else(|| command.strip_prefix('s'))?;
+	let mut parts = rest.strip_prefix('/')?.splitn(3, '/');
+	Some(SubstituteInput {
+		pattern:     parts.next().unwrap_or_default().to_string(),
+		replacement: parts.next().map(str::to_string),
+		flags:       parts.next().unwrap_or_default().to_string(),
+	})
+}
+
+impl RimState {
+	// An empty pattern reuses the last search, as in Vim.
+	pub fn substitute(&mut self, range: Option<CommandRange>, input: &SubstituteInput) {
+		if self.require_active_buffer().is_none() {
+			return;
+		}
+		if let Some(flag) = input.flags.chars().find(|flag| *flag != 'g') {
+			self.workbench.status_bar.message = format!("substitute failed: unknown flag {}", flag);
+			return;
+		}
+		let Some(pattern) = self.substitute_pattern(input) else {
+			self.workbench.status_bar.message = "no previous search pattern".to_string();
+			return;
+		};
+		self.workbench.last_search = Some(pattern.clone());
+		let range = self.substitute_range(range);
+		match self.editor.substitute_rows(
+			range.start.saturating_sub(1),
+			range.end.saturating_sub(1),
+			pattern.as_str(),
+			input.replacement.as_deref().unwrap_or_default(),
+			input.flags.contains('g'),
+		) {
+			Ok(0) => self.workbench.status_bar.message = format!("pattern not found: {}", pattern),
+			Ok(replaced) => self.workbench.status_bar.message = format!("substitute: {} replaced", replaced),
+			Err(other) => self.workbench.status_bar.message = format!("substitute failed: {:?}", other),
+		}
+		self.align_active_window_scroll_to_cursor();
+	}
+
+	pub fn substitute_preview_for_buffer(&self, buffer_id: BufferId) -> Option<&SubstitutePreview> {
+		self.workbench.substitute_preview.as_ref().filter(|preview| preview.buffer_id == buffer_id)
+	}
+
+	// Runs on every change to the command line.
+	pub(super) fn refresh_substitute_preview(&mut self) {
+		self.workbench.substitute_preview =
+			if self.is_command_mode() { self.command_line_substitute_preview() } else { None };
+	}
+
+	fn command_line_substitute_preview(&self) -> Option<SubstitutePreview> {
+		let (range, command) = self.split_command_range(self.workbench.command_line.trim());
+		let input = parse_substitute_input(command)?;
+		let pattern = self.substitute_pattern(&input)?;
+		let buffer_id = self.active_buffer_id()?;
+		let source = &self.buffers.get(buffer_id)?.text;
+		let range = self.substitute_range(range);
+		let global = input.flags.contains('g');
+		let mut text = source.clone();
+		let mut ranges = Vec::new();
+		for row_idx in range.start.saturating_sub(1)..range.end.min(rope_line_count(source)) {
+			let Some(line) = rope_line_without_newline(source, row_idx) else {
+				continue;
+			};
+			let Some(replacement) = input.replacement.as_deref() else {
+				let matches = find_line_matches(line.as_str(), pattern.as_str(), global);
+				ranges.extend(matches.into_iter().map(|found| (row_idx, found)));
+				continue;
+			};
+			let (substituted, replaced) = substitute_line(line.as_str(), pattern.as_str(), replacement, global);
+			if replaced.is_empty() {
+				continue;
+			}
+			// Replacements never add or drop lines, so rows line up with the source.
+			let line_range = rope_line_char_range_without_newline(&text, row_idx)?;
+			text.remove(line_range.clone());
+			text.insert(line_range.start, substituted.as_str());
+			ranges.extend(replaced.into_iter().map(|found| (row_idx, found)));
+		}
+		Some(SubstitutePreview { buffer_id, text, ranges })
+	}
+
+	fn substitute_pattern(&self, input: &SubstituteInput) -> Option<String> {
+		match input.pattern.as_str() {
+			"" => self.workbench.last_search.clone(),
+			pattern => Some(pattern.to_string()),
+		}
+	}
+
+	// Without a typed range `:s` works on the visual lines it was started from,
+	// else on the cursor line.
+	fn substitute_range(&self, range: Option<CommandRange>) -> CommandRange {
+		let cursor_row = self.active_cursor().row as usize;
+		range.or(self.workbench.command_range).unwrap_or(CommandRange { start: cursor_row, end: cursor_row })
+	}
+}
@@ -12,6 +12,14 @@ impl RimState {
 
 	pub fn switch_tab(&mut self, tab_id: TabId) { self.editor.switch_tab(tab_id); }
 
+	pub fn focus_window_showing_buffer(&mut self, buffer_id: super::BufferId) -> bool {
+		let focused = self.editor.focus_window_showing_buffer(buffer_id);
+		if focused {
+			self.align_active_window_scroll_to_cursor();
+		}
+		focused
+	}
+
 	pub fn active_tab_window_ids(&self) -> Vec<WindowId> { self.editor.active_tab_window_ids() }
 
 	pub fn active_tab_buffer_ids(&self) -> Vec<super::BufferId> { self.editor.active_tab_buffer_ids() }
@@ -23,6 +31,7 @@ impl RimState {
 			return;
 		}
 		let current_tab = self.active_tab;
+		self.restore_zoomed_tab(current_tab);
 		self.editor.remove_tab(current_tab);
 		self.workbench.status_bar.message = "tab closed".to_string();
 	}
@@ -0,0 +1,106 @@
+use std::path::{Path, PathBuf};
+
+use rim_domain::{tags::{TagAddress, find_tag, parse_tags, resolve_tag_cursor}, text::is_completion_word_char};
+
+use super::{BufferId, PendingTagJump, RimState, TagStackEntry, rope_line_without_newline};
+
+impl RimState {
+	pub fn tag_under_cursor(&self) -> Option<String> {
+		let buffer = self.buffers.get(self.active_buffer_id()?)?;
+		let cursor = self.active_cursor();
+		let line = rope_line_without_newline(&buffer.text, cursor.row.saturating_sub(1) as usize)?;
+		let chars = line.chars().collect::<Vec<_>>();
+		// Like Vim, fall forward to the first keyword on the line after the cursor.
+		let cursor_index = cursor.col.saturating_sub(1) as usize;
+		let start_search = (cursor_index..chars.len()).find(|index| is_completion_word_char(chars[*index]))?;
+		let start = (0..=start_search)
+			.rev()
+			.take_while(|index| is_completion_word_char(chars[*index]))
+			.last()
+			.unwrap_or(start_search);
+		let end = (start_search..chars.len())
+			.take_while(|index| is_completion_word_char(chars[*index]))
+			.last()
+			.map_or(start_search, |index| index.saturating_add(1));
+		Some(chars[start..end].iter().collect())
+	}
+
+	// Returns the file holding the tag; paths in a tags file are relative to the
+	// directory of the tags file itself.
+	pub fn begin_tag_jump(
+		&mut self,
+		tags_path: &Path,
+		tag: &str,
+		tags_text: &str,
+	) -> Option<(PathBuf, TagAddress)> {
+		let entries = parse_tags(tags_text);
+		let Some(entry) = find_tag(entries.as_slice(), tag) else {
+			self.workbench.status_bar.message = format!("tag not found: {}", tag);
+			return None;
+		};
+		let tags_dir = tags_path.parent().unwrap_or_else(|| Path::new(""));
+		Some((tags_dir.join(entry.file.as_str()), entry.address.clone()))
+	}
+
+	// `<C-t>` comes back here, whether the jump came from a tag or a language
+	// server. It is only pushed once the target has opened.
+	pub fn tag_stack_origin(&self) -> Option<TagStackEntry> {
+		self.active_buffer_id().map(|buffer_id| TagStackEntry { buffer_id, cursor: self.active_cursor() })
+	}
+
+	pub fn defer_tag_jump(&mut self, buffer_id: BufferId, address: TagAddress, origin: Option<TagStackEntry>) {
+		self.workbench.pending_tag_jump = Some(PendingTagJump { buffer_id, address, origin });
+	}
+
+	pub fn cancel_pending_tag_jump(&mut self, buffer_id: BufferId) {
+		if self.has_pending_tag_jump(buffer_id) {
+			self.workbench.pending_tag_jump = None;
+		}
+	}
+
+	pub fn finish_tag_jump(&mut self, address: &TagAddress, origin: Option<TagStackEntry>) {
+		self.workbench.tag_stack.extend(origin);
+		self.jump_to_tag_address(address);
+	}
+
+	pub fn has_pending_tag_jump(&self, buffer_id: BufferId) -> bool {
+		self.workbench.pending_tag_jump.as_ref().is_some_and(|pending| pending.buffer_id == buffer_id)
+	}
+
+	pub fn apply_pending_tag_jump(&mut self, buffer_id: BufferId) {
+		if !self.has_pending_tag_jump(buffer_id) || self.active_buffer_id() != Some(buffer_id) {
+			return;
+		}
+		if let Some(pending) = self.workbench.pending_tag_jump.take() {
+			self.finish_tag_jump(&pending.address, pending.origin);
+		}
+	}
+
+	pub fn jump_to_tag_address(&mut self, address: &TagAddress) {
+		let Some(target) = self
+			.active_buffer_id()
+			.and_then(|buffer_id| self.buffers.get(buffer_id))
+			.and_then(|buffer| resolve_tag_cursor(&buffer.text, address))
+		else {
+			self.workbench.status_bar.message = "tag pattern not found".to_string();
+			return;
+		};
+		self.editor.move_cursor_to(target);
+		self.align_active_window_scroll_to_cursor();
+	}
+
+	pub fn pop_tag_stack(&mut self) {
+		let Some(entry) = self.workbench.tag_stack.pop() else {
+			self.workbench.status_bar.message = "tag stack empty".to_string();
+			return;
+		};
+		if !self.buffers.contains_key(entry.buffer_id) {
+			self.workbench.status_bar.message = "tag stack buffer was closed".to_string();
+			return;
+		}
+		self.workbench.pending_tag_jump = None;
+		self.bind_buffer_to_active_window(entry.buffer_id);
+		self.editor.move_cursor_to(entry.cursor);
+		self.align_active_window_scroll_to_cursor();
+	}
+}
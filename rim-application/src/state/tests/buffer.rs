@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use super::common::{set_active_buffer_text, test_state};
-use crate::state::{BufferEditSnapshot, BufferHistoryEntry, BufferSwitchDirection, CursorState, RimState, SplitAxis};
+use crate::state::{BufferEditSnapshot, BufferHistoryEntry, BufferSwitchDirection, CursorState, LineChangeSign, RimState, SplitAxis};
 
 #[test]
 fn same_buffer_in_different_windows_should_keep_separate_cursor_positions() {
@@ -395,6 +395,7 @@ fn undo_back_to_clean_text_should_clear_dirty() {
 		}],
 		before_cursor: CursorState { row: 1, col: 1 },
 		after_cursor:  CursorState { row: 1, col: 2 },
+		changed_at:    None,
 	});
 
 	assert!(state.buffers.get(buffer_id).expect("buffer exists").dirty);
@@ -419,3 +420,226 @@ fn replace_buffer_text_preserving_cursor_should_keep_bottom_when_file_grows() {
 	assert_eq!(state.active_cursor().row, 4);
 	assert_eq!(state.active_cursor().col, 1);
 }
+
+#[test]
+fn replace_buffer_text_preserving_cursor_should_clamp_cursor_and_scroll_when_file_shrinks() {
+	let mut state = test_state();
+	let buffer_id = state.active_buffer_id().expect("active buffer exists");
+	let other_window_id = state.active_window_id();
+	set_active_buffer_text(
+		&mut state,
+		&(1..=200).map(|idx| format!("line-{idx}")).collect::<Vec<_>>().join("\n"),
+	);
+	state.split_active_window(SplitAxis::Horizontal);
+	let active_window_id = state.active_window_id();
+	state.update_active_tab_layout(80, 20);
+	for window_id in [other_window_id, active_window_id] {
+		let window = state.windows.get_mut(window_id).expect("window should exist");
+		window.cursor = CursorState { row: 150, col: 8 };
+		window.scroll_y = 148;
+	}
+
+	state
+		.replace_buffer_text_preserving_cursor(buffer_id, (1..=20).map(|_| "x").collect::<Vec<_>>().join("\n"));
+
+	assert_eq!(state.active_cursor(), CursorState { row: 20, col: 1 });
+	let window = state.windows.get(active_window_id).expect("active window should exist");
+	assert!(window.scroll_y <= 19 && 19 < window.scroll_y + window.height, "scroll_y: {}", window.scroll_y);
+	let other_window = state.windows.get(other_window_id).expect("other window should exist");
+	assert_eq!(other_window.cursor, CursorState { row: 20, col: 1 });
+	assert!(other_window.scroll_y <= 19, "scroll_y: {}", other_window.scroll_y);
+}
+
+#[test]
+fn changedtick_should_increment_on_every_text_mutation() {
+	let mut state = test_state();
+	let buffer_id = state.active_buffer_id().expect("active buffer exists");
+	set_active_buffer_text(&mut state, "ab\ncd");
+	let mut last_tick = state.buffer_changedtick(buffer_id).expect("buffer exists");
+	let mut assert_tick_advanced = |state: &RimState, operation: &str| {
+		let tick = state.buffer_changedtick(buffer_id).expect("buffer exists");
+		assert!(tick > last_tick, "{} should advance changedtick", operation);
+		last_tick = tick;
+	};
+
+	state.insert_char_at_cursor('x');
+	assert_tick_advanced(&state, "insert char");
+	state.insert_newline_at_cursor();
+	assert_tick_advanced(&state, "insert newline");
+	state.backspace_at_cursor();
+	assert_tick_advanced(&state, "backspace");
+	state.cut_current_char_to_slot();
+	assert_tick_advanced(&state, "cut char");
+	state.paste_slot_at_cursor();
+	assert_tick_advanced(&state, "paste");
+	state.join_line_below_at_cursor();
+	assert_tick_advanced(&state, "join line");
+	state.delete_current_line_to_slot();
+	assert_tick_advanced(&state, "delete line");
+	state.push_buffer_history_entry(buffer_id, BufferHistoryEntry {
+		edits:         vec![BufferEditSnapshot {
+			start_byte:    0,
+			deleted_text:  String::new(),
+			inserted_text: "q".to_string(),
+		}],
+		before_cursor: CursorState { row: 1, col: 1 },
+		after_cursor:  CursorState { row: 1, col: 2 },
+		changed_at:    None,
+	});
+	state.undo_active_buffer_edit();
+	assert_tick_advanced(&state, "undo");
+	state.redo_active_buffer_edit();
+	assert_tick_advanced(&state, "redo");
+	state.replace_buffer_text_preserving_cursor(buffer_id, "reloaded".to_string());
+	assert_tick_advanced(&state, "reload");
+}
+
+#[test]
+fn changedtick_should_not_change_on_cursor_move() {
+	let mut state = test_state();
+	let buffer_id = state.active_buffer_id().expect("active buffer exists");
+	set_active_buffer_text(&mut state, "ab\ncd");
+	let before = state.buffer_changedtick(buffer_id);
+
+	state.move_cursor_right();
+	state.move_cursor_down();
+	state.move_cursor_line_end();
+	state.move_cursor_file_start();
+
+	assert_eq!(state.buffer_changedtick(buffer_id), before);
+}
+
+#[test]
+fn editing_a_line_should_mark_it_changed_until_buffer_is_saved() {
+	let mut state = test_state();
+	let buffer_id = state.active_buffer_id().expect("active buffer exists");
+	set_active_buffer_text(&mut state, "alpha\nbeta\ngamma");
+	assert_eq!(state.buffer_line_change_signs(buffer_id, 0..3), vec![None, None, None]);
+
+	state.move_cursor_down();
+	state.insert_char_at_cursor('x');
+	assert_eq!(state.buffer_line_change_signs(buffer_id, 0..3), vec![
+		None,
+		Some(LineChangeSign::Changed),
+		None
+	]);
+
+	state.mark_buffer_clean(buffer_id);
+	assert_eq!(state.buffer_line_change_signs(buffer_id, 0..3), vec![None, None, None]);
+}
+
+#[test]
+fn line_change_signs_should_mark_added_and_deleted_lines() {
+	let mut state = test_state();
+	let buffer_id = state.active_buffer_id().expect("active buffer exists");
+	set_active_buffer_text(&mut state, "alpha\nbeta\ngamma\ndelta");
+
+	state.move_cursor_down();
+	state.move_cursor_down();
+	state.delete_current_line_to_slot();
+	state.move_cursor_file_start();
+	state.move_cursor_down();
+	state.insert_char_at_cursor('n');
+	state.insert_newline_at_cursor();
+
+	assert_eq!(state.buffer_text_string(buffer_id).as_deref(), Some("alpha\nn\nbeta\ndelta"));
+	assert_eq!(state.buffer_line_change_signs(buffer_id, 0..4), vec![
+		None,
+		Some(LineChangeSign::Added),
+		Some(LineChangeSign::Deleted),
+		None
+	]);
+	assert_eq!(state.buffer_line_change_signs(buffer_id, 3..4), vec![None]);
+}
+
+#[test]
+fn filetype_indent_defaults_should_apply_until_the_buffer_sets_its_own() {
+	let mut state = test_state();
+	let go_buffer = state.create_buffer(Some(PathBuf::from("main.go")), "package main");
+	state.bind_buffer_to_active_window(go_buffer);
+	assert_eq!(state.apply_set_option("expandtab"), Ok("expandtab".to_string()));
+	assert!(state.active_indent_settings().expand_tab);
+	let go_buffer = state.create_buffer(Some(PathBuf::from("other.go")), "package other");
+	state.bind_buffer_to_active_window(go_buffer);
+	assert!(!state.active_indent_settings().expand_tab);
+	state.insert_tab_at_cursor();
+	assert_eq!(state.buffer_text_string(go_buffer).as_deref(), Some("\tpackage other"));
+
+	let yaml_buffer = state.create_buffer(Some(PathBuf::from("ci.yaml")), "on: push");
+	state.bind_buffer_to_active_window(yaml_buffer);
+	assert_eq!(state.apply_set_option("shiftwidth"), Ok("shiftwidth=2".to_string()));
+	assert!(state.active_indent_settings().expand_tab);
+	state.insert_tab_at_cursor();
+	assert_eq!(state.buffer_text_string(yaml_buffer).as_deref(), Some("  on: push"));
+
+	let notes_buffer = state.create_buffer(Some(PathBuf::from("notes")), "");
+	state.bind_buffer_to_active_window(notes_buffer);
+	assert!(state.active_indent_settings().expand_tab);
+}
+
+#[test]
+fn closing_a_buffer_should_forget_its_own_indent_settings() {
+	let mut state = test_state();
+	let buffer_id = state.create_buffer(Some(PathBuf::from("a.txt")), "");
+	state.bind_buffer_to_active_window(buffer_id);
+	assert_eq!(state.apply_set_option("shiftwidth=3"), Ok("shiftwidth=3".to_string()));
+	assert!(state.workbench.buffer_indent_settings.contains_key(&buffer_id));
+
+	state.close_buffer(buffer_id);
+
+	assert!(!state.workbench.buffer_indent_settings.contains_key(&buffer_id));
+}
+
+#[test]
+fn set_filetype_should_override_the_path_and_bring_its_indent_defaults() {
+	let mut state = test_state();
+	let buffer_id = state.create_buffer(Some(PathBuf::from("build-script")), "all:");
+	state.bind_buffer_to_active_window(buffer_id);
+	assert_eq!(state.apply_set_option("filetype"), Ok("filetype=".to_string()));
+	assert_eq!(state.apply_set_option("shiftwidth=8"), Ok("shiftwidth=8".to_string()));
+
+	assert_eq!(state.apply_set_option("ft=yaml"), Ok("filetype=yaml".to_string()));
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert_eq!(buffer.filetype.as_deref(), Some("yaml"));
+	assert_eq!(buffer.filetype(), Some("yaml"));
+	assert_eq!(state.active_indent_settings().shift_width, 2);
+	assert!(state.active_indent_settings().expand_tab);
+
+	assert_eq!(state.apply_set_option("filetype=go"), Ok("filetype=go".to_string()));
+	assert!(!state.active_indent_settings().expand_tab);
+	assert_eq!(state.apply_set_option("filetype"), Ok("filetype=go".to_string()));
+
+	assert_eq!(state.apply_set_option("filetype="), Ok("filetype=".to_string()));
+	assert_eq!(state.buffers.get(buffer_id).and_then(|buffer| buffer.filetype()), None);
+	assert_eq!(state.apply_set_option("ft=c pp"), Err("invalid filetype: c pp".to_string()));
+}
+
+#[test]
+fn switching_buffers_should_restore_horizontal_scroll_for_the_target_cursor() {
+	let mut state = test_state();
+	let b1 = state.active_buffer_id().expect("active buffer exists");
+	let b2 = state.create_buffer(Some(PathBuf::from("b2.rs")), "x\ny");
+	set_active_buffer_text(&mut state, "abcdefghijklmnopqrstuvwxyz");
+	state.update_active_tab_layout(12, 8);
+	let window_id = state.active_window_id();
+	state.move_cursor_line_end();
+	let scrolled = state.windows.get(window_id).expect("window exists").scroll_x;
+	assert!(scrolled > 0);
+
+	state.switch_active_window_buffer(BufferSwitchDirection::Next);
+	assert_eq!(state.active_buffer_id(), Some(b2));
+	assert_eq!(state.windows.get(window_id).expect("window exists").scroll_x, 0);
+
+	state.switch_active_window_buffer(BufferSwitchDirection::Prev);
+	assert_eq!(state.active_buffer_id(), Some(b1));
+	assert_eq!(state.active_cursor().col, 26);
+	assert_eq!(state.windows.get(window_id).expect("window exists").scroll_x, scrolled);
+
+	// A reload in the background that shortens the line clamps the cursor left
+	// of the saved scroll, which then follows it back into view.
+	state.switch_active_window_buffer(BufferSwitchDirection::Next);
+	state.replace_buffer_text_preserving_cursor(b1, "abcdefghij".to_string());
+	state.switch_active_window_buffer(BufferSwitchDirection::Prev);
+	assert_eq!(state.active_cursor().col, 10);
+	assert_eq!(state.windows.get(window_id).expect("window exists").scroll_x, 1);
+}
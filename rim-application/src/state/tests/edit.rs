@@ -1,5 +1,7 @@
+use std::time::Duration;
+
 use super::common::{set_active_buffer_text, test_state};
-use crate::state::{BufferEditSnapshot, BufferHistoryEntry, CursorState, PendingBlockInsert};
+use crate::state::{BufferEditSnapshot, BufferHistoryEntry, CursorState, PendingBlockInsert, RimState};
 
 #[test]
 fn cursor_move_right_should_stop_at_line_end() {
@@ -698,6 +700,106 @@ fn visual_block_yank_should_store_rectangular_selection() {
 	assert!(!state.is_visual_mode());
 }
 
+#[test]
+fn visual_block_yank_should_record_one_slice_per_row_and_paste_back_as_a_rectangle() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "abcd\na\nijkl");
+	state.move_cursor_right();
+	state.enter_visual_block_mode();
+	state.move_cursor_down();
+	state.move_cursor_down();
+	state.move_cursor_right();
+	state.yank_visual_selection_to_slot();
+
+	let content = state.editor.register_content('0').expect("block yank should fill register 0");
+	assert!(content.block_wise);
+	assert_eq!(content.block_rows, ["bc", "", "jk"]);
+
+	set_active_buffer_text(&mut state, "xx\nxx\nxx");
+	state.move_cursor_line_start();
+	state.paste_slot_at_cursor();
+	let buffer_id = state.active_buffer_id().expect("buffer id exists");
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").text.to_string(), "xbcx\nx  x\nxjkx");
+}
+
+#[test]
+fn appending_a_block_to_a_named_block_register_should_stack_its_rows() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "ab\ncd");
+	state.editor.select_register('a');
+	state.enter_visual_block_mode();
+	state.yank_visual_selection_to_slot();
+	state.move_cursor_down();
+	state.editor.select_register('A');
+	state.enter_visual_block_mode();
+	state.yank_visual_selection_to_slot();
+
+	let content = state.editor.register_content('a').expect("block yank should fill register a");
+	assert!(content.block_wise);
+	assert_eq!(content.block_rows, ["a", "c"]);
+
+	set_active_buffer_text(&mut state, "xx\nxx");
+	state.move_cursor_up();
+	state.move_cursor_line_start();
+	state.editor.select_register('a');
+	state.paste_slot_at_cursor();
+	let buffer_id = state.active_buffer_id().expect("buffer id exists");
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").text.to_string(), "xax\nxcx");
+}
+
+#[test]
+fn visual_yank_of_backward_selection_should_leave_cursor_at_selection_start() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "abcd\nefgh\nijkl");
+	state.move_cursor_down();
+	state.move_cursor_down();
+	state.move_cursor_right();
+	state.move_cursor_right();
+	state.enter_visual_mode();
+	state.move_cursor_up();
+	state.move_cursor_left();
+	state.yank_visual_selection_to_slot();
+
+	assert_eq!(state.line_slot, Some("fgh\nijk".to_string()));
+	assert_eq!(state.active_cursor(), CursorState { row: 2, col: 2 });
+
+	state.enter_visual_line_mode();
+	state.move_cursor_up();
+	state.yank_visual_selection_to_slot();
+
+	assert_eq!(state.line_slot, Some("abcd\nefgh".to_string()));
+	assert!(state.line_slot_line_wise);
+	assert_eq!(state.active_cursor(), CursorState { row: 1, col: 1 });
+}
+
+#[test]
+fn visual_yank_should_flash_the_yanked_range_until_the_highlight_expires() {
+	let mut state = test_state();
+	let buffer_id = state.active_buffer_id().expect("active buffer exists");
+	set_active_buffer_text(&mut state, "abcd\nefgh");
+	state.move_cursor_right();
+	state.enter_visual_mode();
+	state.move_cursor_down();
+	state.yank_visual_selection_to_slot();
+
+	let highlight = state.yank_highlight_for_buffer(buffer_id).expect("yank should set a highlight");
+	assert_eq!(
+		(highlight.start, highlight.end),
+		(CursorState { row: 1, col: 2 }, CursorState { row: 2, col: 2 })
+	);
+	assert!(!highlight.line_wise);
+	state.clear_expired_yank_highlight(highlight.expires_at - Duration::from_millis(1));
+	assert!(state.workbench.yank_highlight.is_some());
+	state.clear_expired_yank_highlight(highlight.expires_at);
+	assert!(state.workbench.yank_highlight.is_none());
+
+	state.enter_visual_line_mode();
+	state.yank_visual_selection_to_slot();
+	assert!(state.yank_highlight_for_buffer(buffer_id).is_some_and(|highlight| highlight.line_wise));
+	state.insert_char_at_cursor('x');
+	assert!(state.yank_highlight_for_buffer(buffer_id).is_none());
+}
+
 #[test]
 fn visual_block_delete_should_remove_rectangular_selection() {
 	let mut state = test_state();
@@ -724,6 +826,7 @@ fn visual_block_paste_should_replace_rectangular_selection() {
 	state.line_slot = Some("XY\nZ\n12".to_string());
 	state.line_slot_line_wise = false;
 	state.line_slot_block_wise = true;
+	state.line_slot_block_rows = vec!["XY".to_string(), "Z".to_string(), "12".to_string()];
 	state.move_cursor_right();
 	state.enter_visual_block_mode();
 	state.move_cursor_down();
@@ -794,6 +897,24 @@ fn join_line_below_at_cursor_should_merge_current_and_next_line() {
 	assert_eq!(state.active_cursor().col, 2);
 }
 
+#[test]
+fn join_line_below_at_cursor_should_add_two_spaces_after_period_only_with_joinspaces() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "End.\n\t  Next\nsum(a,\n  )");
+
+	state.join_line_below_at_cursor();
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("End. Next\nsum(a,\n  )"));
+
+	state.apply_set_option("joinspaces").expect("joinspaces should be a known option");
+	set_active_buffer_text(&mut state, "End.\n\t  Next\nsum(a,\n  )");
+	state.join_line_below_at_cursor();
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("End.  Next\nsum(a,\n  )"));
+
+	state.move_cursor_down();
+	state.join_line_below_at_cursor();
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("End.  Next\nsum(a,)"));
+}
+
 #[test]
 fn join_line_below_at_cursor_on_last_line_should_do_nothing() {
 	let mut state = test_state();
@@ -829,6 +950,47 @@ fn cursor_move_right_and_left_should_adjust_horizontal_scroll() {
 	assert_eq!(scrolled_left, 0);
 }
 
+#[test]
+fn sidescrolloff_should_keep_columns_visible_right_of_cursor() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "abcdefghijklmnopqrstuvwxyz");
+	state.update_active_tab_layout(12, 8);
+	assert_eq!(state.apply_set_option("sidescrolloff=3"), Ok("sidescrolloff=3".to_string()));
+	let window_id = state.active_window_id();
+	let visible_cols = 10u16;
+
+	for _ in 0..15 {
+		state.move_cursor_right();
+		let cursor_col = state.active_cursor().col - 1;
+		let scroll_x = state.windows.get(window_id).expect("window exists").scroll_x;
+		assert!(cursor_col + 3 < scroll_x + visible_cols, "cursor col {} scroll_x {}", cursor_col, scroll_x);
+	}
+	assert_eq!(state.windows.get(window_id).expect("window exists").scroll_x, 9);
+
+	state.move_cursor_line_start();
+	assert_eq!(state.windows.get(window_id).expect("window exists").scroll_x, 0);
+	state.move_cursor_line_end();
+	assert_eq!(state.windows.get(window_id).expect("window exists").scroll_x, 17);
+	assert_eq!(state.workbench.cursor_scroll_threshold, 0);
+}
+
+#[test]
+fn sidescrolloff_should_not_affect_vertical_scrolling() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12");
+	state.update_active_tab_layout(20, 6);
+	state.workbench.cursor_side_scroll_threshold = 4;
+	let window_id = state.active_window_id();
+
+	for _ in 0..5 {
+		state.move_cursor_down();
+	}
+
+	assert_eq!(state.windows.get(window_id).expect("window exists").scroll_y, 0);
+	state.move_cursor_down();
+	assert_eq!(state.windows.get(window_id).expect("window exists").scroll_y, 1);
+}
+
 #[test]
 fn insert_char_should_adjust_horizontal_scroll() {
 	let mut state = test_state();
@@ -873,6 +1035,51 @@ fn cut_current_char_to_slot_should_remove_char_and_store_it() {
 	assert_eq!(state.line_slot, Some("b".to_string()));
 }
 
+#[test]
+fn cursor_motions_should_step_over_combining_accent_clusters() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "cafe\u{301} x\ncafe\u{301}\nabcdefgh");
+
+	state.move_cursor_line_start();
+	for _ in 0..3 {
+		state.move_cursor_right();
+	}
+	assert_eq!(state.active_cursor().col, 4);
+	state.move_cursor_right();
+	assert_eq!(state.active_cursor().col, 6);
+	state.move_cursor_left();
+	assert_eq!(state.active_cursor().col, 4);
+	assert_eq!(state.active_cursor_display_col(), 3);
+
+	state.move_cursor_down();
+	state.move_cursor_line_end();
+	assert_eq!(state.active_cursor().col, 4);
+
+	state.move_cursor_down();
+	state.move_cursor_line_end();
+	state.move_cursor_up();
+	assert_eq!(state.active_cursor().col, 4);
+}
+
+#[test]
+fn cut_and_backspace_should_remove_whole_combining_accent_clusters() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "cafe\u{301}\nne\u{301}e");
+
+	state.move_cursor_line_end();
+	state.cut_current_char_to_slot();
+	let buffer_id = state.active_buffer_id().expect("buffer id exists");
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").text.to_string(), "caf\nne\u{301}e");
+	assert_eq!(state.line_slot, Some("e\u{301}".to_string()));
+	assert_eq!(state.active_cursor().col, 3);
+
+	state.move_cursor_down();
+	state.move_cursor_line_end();
+	state.backspace_at_cursor();
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").text.to_string(), "caf\nne");
+	assert_eq!(state.active_cursor().col, 2);
+}
+
 #[test]
 fn paste_slot_at_cursor_should_insert_slot_text_after_cursor() {
 	let mut state = test_state();
@@ -912,6 +1119,7 @@ fn paste_slot_at_cursor_should_insert_block_wise_slot_on_successive_rows() {
 	state.line_slot = Some("bc\nfg\njk".to_string());
 	state.line_slot_line_wise = false;
 	state.line_slot_block_wise = true;
+	state.line_slot_block_rows = vec!["bc".to_string(), "fg".to_string(), "jk".to_string()];
 	state.paste_slot_at_cursor();
 
 	let buffer_id = state.active_buffer_id().expect("buffer id exists");
@@ -952,6 +1160,30 @@ fn delete_current_line_to_slot_should_keep_one_empty_line_when_last_line_deleted
 	assert_eq!(state.line_slot, Some("only".to_string()));
 }
 
+#[test]
+fn deleting_the_last_line_should_keep_whether_the_text_ends_with_a_newline() {
+	for (text, expected) in [("a\nb\nc", "a\nb"), ("a\nb\nc\n", "a\nb\n")] {
+		for visual in [false, true] {
+			let mut state = test_state();
+			set_active_buffer_text(&mut state, text);
+			state.move_cursor_down();
+			state.move_cursor_down();
+			if visual {
+				state.enter_visual_line_mode();
+				state.delete_visual_selection_to_slot();
+			} else {
+				state.delete_current_line_to_slot();
+			}
+
+			let buffer_id = state.active_buffer_id().expect("buffer id exists");
+			let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+			assert_eq!(buffer.text.to_string(), expected, "text {:?}, visual {}", text, visual);
+			assert_eq!(state.active_cursor(), CursorState { row: 2, col: 1 });
+			assert_eq!(state.line_slot, Some("c".to_string()));
+		}
+	}
+}
+
 #[test]
 fn delete_current_line_to_slot_should_clamp_cursor_when_text_has_trailing_newline() {
 	let mut state = test_state();
@@ -997,6 +1229,7 @@ fn undo_active_buffer_edit_should_restore_previous_text_and_cursor() {
 		}],
 		before_cursor: CursorState { row: 1, col: 2 },
 		after_cursor:  CursorState { row: 1, col: 3 },
+		changed_at:    None,
 	});
 
 	state.undo_active_buffer_edit();
@@ -1007,6 +1240,81 @@ fn undo_active_buffer_edit_should_restore_previous_text_and_cursor() {
 	assert_eq!(state.active_cursor(), CursorState { row: 1, col: 2 });
 }
 
+fn push_inserted_char_history(state: &mut crate::state::RimState, ch: char) {
+	let buffer_id = state.active_buffer_id().expect("buffer id exists");
+	let before_cursor = state.active_cursor();
+	let start_byte = state.buffers.get(buffer_id).expect("buffer exists").text.len_bytes();
+	state.move_cursor_line_end();
+	state.move_cursor_right_for_insert();
+	state.insert_char_at_cursor(ch);
+	state.push_buffer_history_entry(buffer_id, BufferHistoryEntry {
+		edits: vec![BufferEditSnapshot {
+			start_byte,
+			deleted_text: String::new(),
+			inserted_text: ch.to_string(),
+		}],
+		before_cursor,
+		after_cursor: state.active_cursor(),
+		changed_at: None,
+	});
+}
+
+#[test]
+fn undo_history_should_drop_oldest_entry_past_configured_undolevels() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "");
+	assert_eq!(state.apply_set_option("undolevels=2"), Ok("undolevels=2".to_string()));
+	for ch in ['a', 'b', 'c'] {
+		push_inserted_char_history(&mut state, ch);
+	}
+	let buffer_id = state.active_buffer_id().expect("buffer id exists");
+	let inserted = |state: &crate::state::RimState| {
+		state
+			.buffers
+			.get(buffer_id)
+			.expect("buffer exists")
+			.undo_stack
+			.iter()
+			.map(|entry| entry.edits[0].inserted_text.clone())
+			.collect::<Vec<_>>()
+	};
+	assert_eq!(inserted(&state), ["b", "c"]);
+
+	state.undo_active_buffer_edit();
+	state.undo_active_buffer_edit();
+	state.undo_active_buffer_edit();
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").text.to_string(), "a");
+	assert_eq!(state.workbench.status_bar.message, "already at oldest change");
+
+	state.redo_active_buffer_edit();
+	push_inserted_char_history(&mut state, 'd');
+	assert_eq!(inserted(&state), ["b", "d"]);
+	assert!(state.buffers.get(buffer_id).expect("buffer exists").redo_stack.is_empty());
+}
+
+#[test]
+fn lowering_undolevels_should_trim_existing_history_immediately() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "");
+	for ch in ['a', 'b', 'c', 'd'] {
+		push_inserted_char_history(&mut state, ch);
+	}
+	state.undo_active_buffer_edit();
+	state.undo_active_buffer_edit();
+	let buffer_id = state.active_buffer_id().expect("buffer id exists");
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").undo_stack.len(), 2);
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").redo_stack.len(), 2);
+
+	assert_eq!(state.apply_set_option("ul=1"), Ok("undolevels=1".to_string()));
+	assert_eq!(state.apply_set_option("undolevels"), Ok("undolevels=1".to_string()));
+	let buffer = state.buffers.get(buffer_id).expect("buffer exists");
+	assert_eq!(buffer.undo_stack.len(), 1);
+	assert_eq!(buffer.undo_stack[0].edits[0].inserted_text, "b");
+	assert_eq!(buffer.redo_stack.len(), 1);
+	assert_eq!(buffer.redo_stack[0].edits[0].inserted_text, "c");
+	assert!(state.apply_set_option("undolevels=lots").is_err());
+}
+
 #[test]
 fn redo_active_buffer_edit_should_reapply_last_undone_change() {
 	let mut state = test_state();
@@ -1022,6 +1330,7 @@ fn redo_active_buffer_edit_should_reapply_last_undone_change() {
 		}],
 		before_cursor: CursorState { row: 1, col: 2 },
 		after_cursor:  CursorState { row: 1, col: 3 },
+		changed_at:    None,
 	});
 	state.undo_active_buffer_edit();
 
@@ -1032,3 +1341,142 @@ fn redo_active_buffer_edit_should_reapply_last_undone_change() {
 	assert_eq!(buffer.text.to_string(), "axb");
 	assert_eq!(state.active_cursor(), CursorState { row: 1, col: 3 });
 }
+
+#[test]
+fn scrolljump_should_scroll_by_configured_lines_past_bottom_edge() {
+	let mut state = test_state();
+	set_active_buffer_text(
+		&mut state,
+		(1..=30).map(|row| row.to_string()).collect::<Vec<_>>().join("\n").as_str(),
+	);
+	state.update_active_tab_layout(20, 6);
+	assert_eq!(state.apply_set_option("scrolljump=4"), Ok("scrolljump=4".to_string()));
+	let window_id = state.active_window_id();
+	let scroll_y = |state: &RimState| state.windows.get(window_id).expect("window exists").scroll_y;
+
+	for _ in 0..5 {
+		state.move_cursor_down();
+	}
+	assert_eq!(scroll_y(&state), 0);
+	state.move_cursor_down();
+	assert_eq!(scroll_y(&state), 4);
+	for _ in 0..4 {
+		state.move_cursor_down();
+	}
+	assert_eq!(scroll_y(&state), 8);
+
+	// Near the end the jump stops at the last full screen.
+	state.move_cursor_file_end();
+	assert_eq!(scroll_y(&state), 24);
+	for _ in 0..6 {
+		state.move_cursor_up();
+	}
+	assert_eq!(scroll_y(&state), 20);
+	assert!(state.apply_set_option("scrolljump=0").is_err());
+}
+
+#[test]
+fn huge_scrolloff_should_keep_cursor_line_centered_until_file_edges() {
+	let mut state = test_state();
+	set_active_buffer_text(
+		&mut state,
+		(1..=40).map(|row| row.to_string()).collect::<Vec<_>>().join("\n").as_str(),
+	);
+	state.update_active_tab_layout(20, 7);
+	assert_eq!(state.apply_set_option("scrolloff=999"), Ok("scrolloff=999".to_string()));
+	let window_id = state.active_window_id();
+	let scroll_y = |state: &RimState| state.windows.get(window_id).expect("window exists").scroll_y;
+
+	for _ in 0..3 {
+		state.move_cursor_down();
+	}
+	assert_eq!(scroll_y(&state), 0);
+	for row in 5..=37u16 {
+		state.move_cursor_down();
+		assert_eq!(state.active_cursor().row, row);
+		assert_eq!(scroll_y(&state), row - 4, "cursor row {} should sit mid-window", row);
+	}
+	state.move_cursor_down();
+	assert_eq!(scroll_y(&state), 33);
+
+	for _ in 0..10 {
+		state.move_cursor_up();
+	}
+	assert_eq!(scroll_y(&state), state.active_cursor().row - 4);
+}
+
+#[test]
+fn virtualedit_all_should_move_past_line_ends_without_inserting_spaces() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "abc\nx");
+	assert_eq!(state.apply_set_option("virtualedit=all"), Ok("virtualedit=all".to_string()));
+	state.move_cursor_line_end();
+	for _ in 0..3 {
+		state.move_cursor_right();
+	}
+	assert_eq!(state.active_cursor(), CursorState { row: 1, col: 3 });
+	assert_eq!(state.active_cursor_display_col(), 5);
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("abc\nx"));
+
+	state.move_cursor_down();
+	assert_eq!(state.active_cursor(), CursorState { row: 2, col: 1 });
+	assert_eq!(state.active_cursor_display_col(), 5);
+	state.move_cursor_left();
+	assert_eq!(state.active_cursor_display_col(), 4);
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("abc\nx"));
+
+	assert!(state.fill_virtual_space_for_insert(false));
+	assert_eq!(state.active_buffer_text_string().as_deref(), Some("abc\nx   "));
+	assert_eq!(state.active_cursor(), CursorState { row: 2, col: 5 });
+}
+
+#[test]
+fn virtualedit_all_should_step_back_onto_the_last_char() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "ab");
+	state.apply_set_option("ve=all").expect("virtualedit should be set");
+	state.move_cursor_right();
+	state.move_cursor_right();
+	state.move_cursor_right();
+	assert_eq!(state.active_cursor_display_col(), 3);
+
+	state.move_cursor_left();
+	state.move_cursor_left();
+	assert_eq!(state.active_cursor(), CursorState { row: 1, col: 2 });
+	assert_eq!(state.active_cursor_display_col(), 1);
+	assert_eq!(state.active_virtual_display_col(), None);
+	state.move_cursor_left();
+	assert_eq!(state.active_cursor(), CursorState { row: 1, col: 1 });
+}
+
+#[test]
+fn virtualedit_should_default_to_block_and_reject_unknown_values() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "abc");
+	assert_eq!(state.apply_set_option("virtualedit"), Ok("virtualedit=block".to_string()));
+	assert_eq!(state.apply_set_option("virtualedit=onemore"), Err("invalid virtualedit: onemore".to_string()));
+
+	state.move_cursor_line_end();
+	state.move_cursor_right();
+	assert_eq!(state.active_cursor_display_col(), 2);
+	assert!(!state.fill_virtual_space_for_insert(false));
+}
+
+#[test]
+fn virtualedit_none_should_keep_a_visual_block_cursor_on_short_lines() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "abcdef\nx\nzzzzzz");
+	assert_eq!(state.apply_set_option("virtualedit="), Ok("virtualedit=none".to_string()));
+	for _ in 0..4 {
+		state.move_cursor_right();
+	}
+	state.enter_visual_block_mode();
+	state.move_cursor_down();
+	assert_eq!(state.active_cursor(), CursorState { row: 2, col: 1 });
+	assert_eq!(state.active_cursor_display_col(), 0);
+	state.move_cursor_right_for_visual_char();
+	assert_eq!(state.active_cursor(), CursorState { row: 2, col: 1 });
+
+	state.move_cursor_down();
+	assert_eq!(state.active_cursor(), CursorState { row: 3, col: 5 });
+}
@@ -70,6 +70,44 @@ fn normal_mode_status_line_should_show_percentage_between_top_and_bot() {
 	assert!(state.status_line().contains("3:1 75%"));
 }
 
+#[test]
+fn status_line_should_report_virtual_column_on_lines_with_leading_tabs() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "\t\tab\nxy");
+	assert!(state.status_line().contains("1:1 Top"));
+
+	state.move_cursor_right();
+	assert!(state.status_line().contains("1:2-5 Top"));
+
+	state.move_cursor_right();
+	state.move_cursor_right();
+	assert!(state.status_line().contains("1:4-10 Top"));
+
+	state.move_cursor_down();
+	assert!(state.status_line().contains("2:2 Bot"));
+}
+
+#[test]
+fn status_line_should_count_trailing_empty_row_when_cursor_is_on_it() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "a\nb\nc\n");
+
+	state.move_cursor_down();
+	assert!(state.status_line().contains("2:1 66%"));
+
+	state.move_cursor_file_end();
+	assert!(state.status_line().contains("3:1 Bot"));
+
+	state.enter_insert_mode();
+	state.move_cursor_to_insert_line_end_slot();
+	state.insert_newline_at_cursor();
+	assert!(state.status_line().contains("4:1 Bot"));
+
+	state.move_cursor_file_start();
+	state.move_cursor_down();
+	assert!(state.status_line().contains("2:1 50%"));
+}
+
 #[test]
 fn take_command_line_should_return_trimmed_text_and_leave_command_mode() {
 	let mut state = test_state();
@@ -82,3 +120,39 @@ fn take_command_line_should_return_trimmed_text_and_leave_command_mode() {
 	assert!(!state.is_command_mode());
 	assert_eq!(state.workbench.status_bar.mode, super::super::StatusBarMode::Normal);
 }
+
+#[test]
+fn status_line_should_abbreviate_the_home_directory_outside_the_working_directory() {
+	let mut state = test_state();
+	let home = std::env::temp_dir().join("rim-display-home");
+	state.workbench.home_directory = Some(home.clone());
+	state.workbench.working_directory = home.join("project");
+	state.workbench.status_line_format = "%f".to_string();
+	let show = |state: &mut super::super::RimState, path: std::path::PathBuf| {
+		let buffer_id = state.create_buffer(Some(path), "");
+		state.bind_buffer_to_active_window(buffer_id);
+		state.status_line().rsplit(" | ").next().unwrap_or_default().to_string()
+	};
+
+	let sep = std::path::MAIN_SEPARATOR;
+	assert_eq!(show(&mut state, home.join("notes").join("todo.md")), format!("~{sep}notes{sep}todo.md"));
+	assert_eq!(show(&mut state, home.join("project").join("main.rs")), "main.rs");
+	let elsewhere = std::env::temp_dir().join("rim-display-elsewhere.rs");
+	assert_eq!(show(&mut state, elsewhere.clone()), elsewhere.to_string_lossy());
+}
+
+#[test]
+fn status_line_format_should_expand_tokens_for_the_active_buffer() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, "a\nb\nc\nd");
+	state.move_cursor_down();
+	state.insert_char_at_cursor('x');
+	state.workbench.status_line_format = "%f%m %y %l,%c %p %P 100%% %q".to_string();
+
+	assert!(state.status_line().ends_with(" | test.rs[+] [rust] 2,2 50% 50% 100% %q"));
+
+	assert_eq!(state.apply_set_option("statusline=%l"), Ok("statusline=%l".to_string()));
+	assert!(state.status_line().ends_with(" | 2"));
+	assert_eq!(state.apply_set_option("statusline="), Ok("statusline=".to_string()));
+	assert!(state.status_line().ends_with(" | 2:2 50%"));
+}
@@ -50,6 +50,7 @@ fn workspace_session_snapshot_should_roundtrip_tabs_windows_and_views() {
 		}],
 		before_cursor: CursorState { row: 1, col: 1 },
 		after_cursor:  CursorState { row: 1, col: 8 },
+		changed_at:    None,
 	});
 
 	let snapshot = state.workspace_session_snapshot();
@@ -1,5 +1,5 @@
-use super::common::test_state;
-use crate::state::{FocusDirection, SplitAxis, compute_workspace_file_picker_body_layout};
+use super::common::{set_active_buffer_text, test_state};
+use crate::state::{FocusDirection, LineChangeSign, SplitAxis, compute_workspace_file_picker_body_layout};
 
 #[test]
 fn horizontal_split_should_half_width_for_two_windows() {
@@ -117,6 +117,46 @@ fn close_active_window_should_remove_when_multiple_windows() {
 	assert_eq!(after_count, 1);
 }
 
+#[test]
+fn zoom_toggle_should_fill_tab_and_restore_exact_previous_geometry() {
+	let mut state = test_state();
+	set_active_buffer_text(&mut state, &(1..=60).map(|line| line.to_string()).collect::<Vec<_>>().join("\n"));
+	state.update_active_tab_layout(100, 20);
+	state.split_active_window(SplitAxis::Vertical);
+	state.split_active_window(SplitAxis::Horizontal);
+	for _ in 0..30 {
+		state.move_cursor_down();
+	}
+	let zoomed_id = state.active_window_id();
+	let before_ids = state.active_tab_window_ids();
+	let before = before_ids.iter().filter_map(|id| state.windows.get(*id).copied()).collect::<Vec<_>>();
+
+	state.toggle_active_window_zoom();
+	assert_eq!(state.active_tab_window_ids(), vec![zoomed_id]);
+	let zoomed = state.windows.get(zoomed_id).expect("zoomed window exists");
+	assert_eq!((zoomed.x, zoomed.y, zoomed.width, zoomed.height), (0, 0, 100, 20));
+	assert_eq!(zoomed.cursor, before.iter().find(|w| w.cursor.row == 31).expect("active window").cursor);
+	assert_eq!(state.layout_session_snapshot().tabs[0].windows.len(), 3);
+	assert_eq!(state.active_tab_window_ids(), vec![zoomed_id]);
+
+	state.toggle_active_window_zoom();
+	assert_eq!(state.active_tab_window_ids(), before_ids);
+	let after = before_ids.iter().filter_map(|id| state.windows.get(*id).copied()).collect::<Vec<_>>();
+	assert_eq!(after, before);
+	assert_eq!(state.workbench.status_bar.message, "window restored");
+}
+
+#[test]
+fn zoom_toggle_should_refuse_a_single_window() {
+	let mut state = test_state();
+	state.update_active_tab_layout(100, 20);
+
+	state.toggle_active_window_zoom();
+
+	assert!(!state.is_active_window_zoomed());
+	assert_eq!(state.workbench.status_bar.message, "zoom: only one window");
+}
+
 #[test]
 fn close_active_window_should_absorb_neighbor_after_v_then_h() {
 	let mut state = test_state();
@@ -285,3 +325,55 @@ fn sorted_window_rects(state: &crate::state::RimState) -> Vec<(u16, u16, u16, u1
 	rects.sort_unstable();
 	rects
 }
+
+#[test]
+fn diff_windows_should_sign_rows_against_each_other_until_diff_off() {
+	let mut state = test_state();
+	let left = state.create_buffer(None, "a\nb\nc\nd");
+	state.bind_buffer_to_active_window(left);
+	state.diff_this();
+	let left_window = state.active_window_id();
+	state.split_active_window(SplitAxis::Vertical);
+	let right = state.create_buffer(None, "a\nB\nc\nd\ne");
+	state.bind_buffer_to_active_window(right);
+	let right_window = state.active_window_id();
+	assert!(state.windows.get(right_window).expect("right window should exist").diff);
+
+	assert_eq!(
+		state.window_diff_signs(left_window, 0..4),
+		Some(vec![None, Some(LineChangeSign::Changed), None, Some(LineChangeSign::Deleted)])
+	);
+	assert_eq!(
+		state.window_diff_signs(right_window, 0..5),
+		Some(vec![None, Some(LineChangeSign::Changed), None, None, Some(LineChangeSign::Added)])
+	);
+	assert_eq!(state.window_line_change_signs(right_window, 1..2), vec![Some(LineChangeSign::Changed)]);
+
+	state.diff_off();
+	assert_eq!(state.window_diff_signs(left_window, 0..4), None);
+	assert_eq!(state.window_line_change_signs(right_window, 1..2), vec![None]);
+}
+
+#[test]
+fn viewport_layout_should_give_a_hidden_status_bar_row_to_the_windows() {
+	let mut state = test_state();
+	let window_height = |state: &crate::state::RimState| {
+		state.windows.get(state.active_window_id()).expect("window exists").height
+	};
+	state.resize_viewport(40, 10);
+	assert_eq!(window_height(&state), 8);
+
+	assert_eq!(state.apply_set_option("laststatus=1"), Ok("laststatus=1".to_string()));
+	state.mark_status_message_seen();
+	state.refresh_viewport_layout();
+	assert_eq!(window_height(&state), 9);
+
+	state.split_active_window(SplitAxis::Vertical);
+	state.refresh_viewport_layout();
+	let heights = state
+		.active_tab_window_ids()
+		.into_iter()
+		.map(|window_id| state.windows.get(window_id).expect("window exists").height)
+		.sum::<u16>();
+	assert_eq!(heights, 8);
+}
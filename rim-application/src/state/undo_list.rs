@@ -0,0 +1,99 @@
+use std::time::SystemTime;
+
+use super::{FloatingWindowLine, FloatingWindowPlacement, FloatingWindowState, OverlayState, RimState, format_local_timestamp};
+
+impl RimState {
+	// Change N is the text after the N-th change the history still keeps and 0
+	// the text before them, so the numbers shift once `undolevels` drops the
+	// oldest. The history is linear: an edit after undoing drops the undone
+	// changes, so there are no branches to list.
+	pub fn undo_list_lines(&self) -> Option<Vec<FloatingWindowLine>> {
+		let buffer = self.buffers.get(self.active_buffer_id()?)?;
+		let now = SystemTime::from((self.workbench.clock)());
+		let current = buffer.undo_stack.len();
+		let changes = buffer.undo_stack.iter().chain(buffer.redo_stack.iter().rev());
+		let mut lines = vec![FloatingWindowLine {
+			key:       "0".to_string(),
+			summary:   undo_list_summary("original", current == 0),
+			is_prefix: false,
+		}];
+		lines.extend(changes.enumerate().map(|(index, entry)| {
+			let seq = index + 1;
+			let edits = if entry.edits.len() == 1 { "edit" } else { "edits" };
+			let mut text = format!("line {}, {} {}", entry.after_cursor.row, entry.edits.len(), edits);
+			if let Some(changed_at) = entry.changed_at {
+				text = format!("{}  {}", text, undo_list_time(changed_at, now));
+			}
+			FloatingWindowLine {
+				key:       seq.to_string(),
+				summary:   undo_list_summary(text.as_str(), seq == current),
+				is_prefix: false,
+			}
+		}));
+		Some(lines)
+	}
+
+	pub fn show_undo_list(&mut self) {
+		if self.require_active_buffer().is_none() {
+			return;
+		}
+		let lines = self.undo_list_lines().unwrap_or_default();
+		let height = lines.len().saturating_add(4).min(self.workbench.key_hints_max_height as usize) as u16;
+		self.workbench.overlay = Some(OverlayState::FloatingWindow(FloatingWindowState {
+			title: "Undo list".to_string(),
+			subtitle: None,
+			footer: Some(":undo N jumps  Esc close".to_string()),
+			placement: FloatingWindowPlacement::BottomRight {
+				width:         self.workbench.key_hints_width,
+				height:        height.max(4),
+				margin_right:  1,
+				margin_bottom: 1,
+			},
+			lines,
+			scroll: 0,
+		}));
+	}
+
+	// Undoes or redoes until the buffer holds change `seq` of the undo list.
+	pub fn undo_to_change(&mut self, seq: usize) {
+		let Some(buffer_id) = self.require_active_buffer() else {
+			return;
+		};
+		let Some(buffer) = self.buffers.get(buffer_id) else {
+			return;
+		};
+		let current = buffer.undo_stack.len();
+		let total = current + buffer.redo_stack.len();
+		if seq > total {
+			self.workbench.status_bar.message = format!("undo failed: no change {}", seq);
+			return;
+		}
+		for _ in seq..current {
+			if self.editor.undo_active_buffer_edit().is_err() {
+				break;
+			}
+		}
+		for _ in current..seq {
+			if self.editor.redo_active_buffer_edit().is_err() {
+				break;
+			}
+		}
+		self.align_active_window_scroll_to_cursor();
+		self.workbench.status_bar.message = format!("undo: at change {} of {}", seq, total);
+	}
+}
+
+// Like Vim, recent changes read as an age and older ones as a local time.
+fn undo_list_time(changed_at: SystemTime, now: SystemTime) -> String {
+	match now.duration_since(changed_at) {
+		Ok(age) if age.as_secs() < 100 => {
+			let seconds = age.as_secs();
+			format!("{} {} ago", seconds, if seconds == 1 { "second" } else { "seconds" })
+		}
+		_ => format_local_timestamp(changed_at),
+	}
+}
+
+fn undo_list_summary(text: &str, current: bool) -> String {
+	if current { format!("{}  <- current", text) } else { text.to_string() }
+}
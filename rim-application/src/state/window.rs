@@ -1,13 +1,14 @@
-use rim_domain::display_geometry::{char_display_width as geom_char_display_width, display_width_of_char_prefix_with_virtual as geom_display_width_of_char_prefix_with_virtual, wrapped_row_index_for_cursor as geom_wrapped_row_index_for_cursor, wrapped_total_rows_for_rope as geom_wrapped_total_rows_for_rope};
+use rim_domain::display_geometry::{display_width_of_char_prefix_with_virtual as geom_display_width_of_char_prefix_with_virtual, line_display_width as geom_line_display_width, wrapped_row_index_for_cursor as geom_wrapped_row_index_for_cursor, wrapped_total_rows_for_rope as geom_wrapped_total_rows_for_rope};
 use ropey::Rope;
 use tracing::{error, trace};
 
-use super::{CursorState, FocusDirection, RimState, SplitAxis, WindowId, WindowState};
+use super::{CursorState, FocusDirection, RimState, SplitAxis, TabId, WindowId, WindowState, ZoomedLayout};
 
 impl RimState {
 	pub fn focus_window(&mut self, direction: FocusDirection) { self.editor.focus_window(direction); }
 
 	pub fn close_active_window(&mut self) {
+		self.restore_zoomed_tab(self.active_tab);
 		if !self.editor.close_active_window() {
 			return;
 		}
@@ -15,6 +16,7 @@ impl RimState {
 	}
 
 	pub fn split_active_window(&mut self, axis: SplitAxis) {
+		self.restore_zoomed_tab(self.active_tab);
 		let active_window_id = self.active_window_id();
 		let Some(new_window_id) = self.editor.split_active_window(axis) else {
 			error!(
@@ -31,6 +33,80 @@ impl RimState {
 		};
 	}
 
+	// The siblings leave the tab while zoomed so rendering and focus only see the
+	// zoomed window; they come back with their saved geometry.
+	pub fn toggle_active_window_zoom(&mut self) {
+		let tab_id = self.active_tab;
+		if self.workbench.zoomed_layouts.contains_key(&tab_id) {
+			let zoomed_area = self.windows.get(self.active_window_id()).map(|window| (window.width, window.height));
+			self.restore_zoomed_tab(tab_id);
+			let restored_area = self.active_tab_area();
+			match zoomed_area {
+				Some((width, height)) if (width, height) != restored_area => {
+					self.update_active_tab_layout(width, height);
+				}
+				_ => self.reconcile_active_tab_views(),
+			}
+			self.workbench.status_bar.message = "window restored".to_string();
+			return;
+		}
+		if self.active_tab_window_ids().len() <= 1 {
+			self.workbench.status_bar.message = "zoom: only one window".to_string();
+			return;
+		}
+		self.zoom_tab(tab_id);
+		self.reconcile_active_tab_views();
+		self.workbench.status_bar.message = "window zoomed".to_string();
+	}
+
+	pub fn is_active_window_zoomed(&self) -> bool {
+		self.workbench.zoomed_layouts.contains_key(&self.active_tab)
+	}
+
+	// Sessions always record the full layout, so zoomed tabs are restored for the
+	// snapshot and zoomed again afterwards.
+	pub(crate) fn with_zoomed_tabs_restored<T>(&mut self, snapshot: impl FnOnce(&Self) -> T) -> T {
+		let zoomed = self
+			.workbench
+			.zoomed_layouts
+			.keys()
+			.filter_map(|tab_id| {
+				let active_window = self.tabs.get(tab_id)?.active_window;
+				Some((*tab_id, active_window, self.windows.get(active_window).copied()?))
+			})
+			.collect::<Vec<_>>();
+		for (tab_id, ..) in &zoomed {
+			self.restore_zoomed_tab(*tab_id);
+		}
+		let result = snapshot(self);
+		for (tab_id, window_id, zoomed_window) in zoomed {
+			self.zoom_tab(tab_id);
+			if let Some(window) = self.windows.get_mut(window_id) {
+				copy_window_geometry(window, &zoomed_window);
+			}
+		}
+		result
+	}
+
+	// The renderer gives the top bar one row and a visible status bar another;
+	// relayout right away so splits re-proportion before the next key arrives.
+	pub fn resize_viewport(&mut self, width: u16, height: u16) {
+		let status_row = self.status_bar_visible();
+		self.workbench.viewport_size = Some((width, height));
+		self.workbench.viewport_status_row = status_row;
+		self.update_active_tab_layout(width, height.saturating_sub(1 + u16::from(status_row)).max(1));
+	}
+
+	// Runs after every action: `laststatus`, the window count, the command line
+	// and messages all decide whether the status bar takes a row.
+	pub(crate) fn refresh_viewport_layout(&mut self) {
+		if let Some((width, height)) = self.workbench.viewport_size
+			&& self.status_bar_visible() != self.workbench.viewport_status_row
+		{
+			self.resize_viewport(width, height);
+		}
+	}
+
 	pub fn update_active_tab_layout(&mut self, width: u16, height: u16) {
 		trace!("update_active_tab_layout");
 		let window_ids = self.active_tab_window_ids();
@@ -49,6 +125,69 @@ impl RimState {
 }
 
 impl RimState {
+	fn zoom_tab(&mut self, tab_id: TabId) {
+		let Some(tab) = self.tabs.get(&tab_id) else {
+			return;
+		};
+		let (zoomed_window, window_ids) = (tab.active_window, tab.windows.clone());
+		let geometry = window_ids
+			.iter()
+			.filter_map(|window_id| self.windows.get(*window_id).map(|window| (*window_id, *window)))
+			.collect::<Vec<_>>();
+		let max_of =
+			|edge: fn(&WindowState) -> u32| geometry.iter().map(|(_, window)| edge(window)).max().unwrap_or(0);
+		let right = max_of(|window| u32::from(window.x.saturating_add(window.width)));
+		let bottom = max_of(|window| u32::from(window.y.saturating_add(window.height)));
+		let layout_right = max_of(|window| window.layout_x.saturating_add(window.layout_w));
+		let layout_bottom = max_of(|window| window.layout_y.saturating_add(window.layout_h));
+		if let Some(window) = self.windows.get_mut(zoomed_window) {
+			window.x = 0;
+			window.y = 0;
+			window.width = right.max(1) as u16;
+			window.height = bottom.max(1) as u16;
+			window.layout_x = 0;
+			window.layout_y = 0;
+			window.layout_w = if layout_right == 0 { right.max(1) } else { layout_right };
+			window.layout_h = if layout_bottom == 0 { bottom.max(1) } else { layout_bottom };
+		}
+		if let Some(tab) = self.tabs.get_mut(&tab_id) {
+			tab.windows = vec![zoomed_window];
+		}
+		self.workbench.zoomed_layouts.insert(tab_id, ZoomedLayout { windows: window_ids, geometry });
+	}
+
+	pub(super) fn restore_zoomed_tab(&mut self, tab_id: TabId) {
+		let Some(layout) = self.workbench.zoomed_layouts.remove(&tab_id) else {
+			return;
+		};
+		for (window_id, saved) in &layout.geometry {
+			if let Some(window) = self.windows.get_mut(*window_id) {
+				copy_window_geometry(window, saved);
+			}
+		}
+		let windows =
+			layout.windows.into_iter().filter(|window_id| self.windows.contains_key(*window_id)).collect();
+		if let Some(tab) = self.tabs.get_mut(&tab_id) {
+			tab.windows = windows;
+		}
+	}
+
+	fn active_tab_area(&self) -> (u16, u16) {
+		self.active_tab_window_ids().into_iter().filter_map(|window_id| self.windows.get(window_id)).fold(
+			(0, 0),
+			|(right, bottom), window| {
+				(right.max(window.x.saturating_add(window.width)), bottom.max(window.y.saturating_add(window.height)))
+			},
+		)
+	}
+
+	fn reconcile_active_tab_views(&mut self) {
+		for window_id in self.active_tab_window_ids() {
+			self.reconcile_window_view_to_layout(window_id, None);
+			self.sync_window_view_binding(window_id);
+		}
+	}
+
 	fn clamp_cursor_for_layout_mode(&self, text: &Rope, cursor: CursorState) -> CursorState {
 		self.editor.clamp_cursor_for_layout_mode(text, cursor)
 	}
@@ -76,7 +215,7 @@ impl RimState {
 		} else {
 			cursor_display_col_for_window(&buffer.text, cursor)
 		};
-		if self.word_wrap_enabled() {
+		if self.window_word_wrap(window_id) {
 			let wrap_width = visible_cols.max(1) as usize;
 			let cursor_wrapped_row = geom_wrapped_row_index_for_cursor(&buffer.text, cursor, wrap_width);
 			let max_scroll_y =
@@ -169,7 +308,18 @@ fn cursor_display_col_for_window(text: &Rope, cursor: crate::state::CursorState)
 fn line_display_width_for_window(text: &Rope, cursor: crate::state::CursorState) -> u16 {
 	let row_index = cursor.row.saturating_sub(1) as usize;
 	let base_width = crate::state::rope_line_without_newline(text, row_index)
-		.map(|line| line.chars().map(|ch| geom_char_display_width(ch) as u16).sum())
+		.map(|line| geom_line_display_width(line.as_str()) as u16)
 		.unwrap_or(0);
 	base_width.max(cursor_display_col_for_window(text, cursor))
 }
+
+fn copy_window_geometry(window: &mut WindowState, saved: &WindowState) {
+	window.x = saved.x;
+	window.y = saved.y;
+	window.width = saved.width;
+	window.height = saved.height;
+	window.layout_x = saved.layout_x;
+	window.layout_y = saved.layout_y;
+	window.layout_w = saved.layout_w;
+	window.layout_h = saved.layout_h;
+}
@@ -0,0 +1,24 @@
+use rim_domain::word_count::text_stats;
+
+use super::RimState;
+
+impl RimState {
+	pub fn report_word_count(&mut self) {
+		let (scope, stats) = if let Some(selection) = self.workbench.command_selection.as_deref() {
+			("selection", text_stats(selection))
+		} else {
+			let Some(buffer) = self.require_active_buffer().and_then(|buffer_id| self.buffers.get(buffer_id))
+			else {
+				return;
+			};
+			("buffer", text_stats(buffer.text.to_string().as_str()))
+		};
+		self.workbench.status_bar.message = format!(
+			"{}: {} words, {} chars, ~{} min read",
+			scope,
+			stats.words,
+			stats.chars,
+			stats.reading_minutes()
+		);
+	}
+}
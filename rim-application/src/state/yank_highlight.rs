@@ -0,0 +1,57 @@
+use std::time::{Duration, Instant};
+
+use super::{BufferId, CursorState, RimState};
+
+// What a yank just copied, drawn highlighted until `expires_at`. An edit to the
+// buffer moves the text under it, so the highlight only holds at the
+// changedtick it was taken at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YankHighlight {
+	pub buffer_id:          BufferId,
+	pub changedtick:        u64,
+	pub start:              CursorState,
+	pub end:                CursorState,
+	pub line_wise:          bool,
+	// Display columns of the edges of a block yank.
+	pub block_display_cols: Option<(u16, u16)>,
+	pub expires_at:         Instant,
+}
+
+impl RimState {
+	const YANK_HIGHLIGHT_DURATION: Duration = Duration::from_millis(150);
+
+	// Taken before the yank, while the visual selection still exists.
+	pub(super) fn visual_yank_highlight(&self, now: Instant) -> Option<YankHighlight> {
+		let buffer_id = self.active_buffer_id()?;
+		let (start, end) = self.normalized_visual_bounds()?;
+		let block_display_cols = if self.is_visual_block_mode() {
+			Some((self.visual_block_anchor_display_col?, self.visual_block_cursor_display_col?))
+		} else {
+			None
+		};
+		Some(YankHighlight {
+			buffer_id,
+			changedtick: self.buffer_changedtick(buffer_id)?,
+			start,
+			end,
+			line_wise: self.is_visual_line_mode(),
+			block_display_cols,
+			expires_at: now + Self::YANK_HIGHLIGHT_DURATION,
+		})
+	}
+
+	pub fn yank_highlight_for_buffer(&self, buffer_id: BufferId) -> Option<YankHighlight> {
+		self.workbench.yank_highlight.filter(|highlight| {
+			highlight.buffer_id == buffer_id && self.buffer_changedtick(buffer_id) == Some(highlight.changedtick)
+		})
+	}
+
+	// Runs on every tick.
+	pub fn clear_expired_yank_highlight(&mut self, now: Instant) -> bool {
+		if self.workbench.yank_highlight.is_some_and(|highlight| highlight.expires_at <= now) {
+			self.workbench.yank_highlight = None;
+			return true;
+		}
+		false
+	}
+}
@@ -1,5 +1,6 @@
 use ropey::Rope;
-use unicode_width::UnicodeWidthChar;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::{model::CursorState, text::{rope_ends_with_newline, rope_line_count, rope_line_without_newline}};
 
@@ -9,8 +10,67 @@ pub fn char_display_width(ch: char) -> usize {
 	if ch == '\t' { TAB_DISPLAY_WIDTH } else { UnicodeWidthChar::width(ch).unwrap_or(0) }
 }
 
+// Multi-codepoint clusters (flags, ZWJ emoji) are measured as a whole instead
+// of summing their scalar widths.
+pub fn grapheme_display_width(cluster: &str) -> usize {
+	let mut chars = cluster.chars();
+	match (chars.next(), chars.next()) {
+		(Some(ch), None) => char_display_width(ch),
+		_ => UnicodeWidthStr::width(cluster),
+	}
+}
+
+// One width per char; a cluster's width sits on its first char and the rest
+// of the cluster is zero-width, so char-indexed cursors stay in sync.
+pub fn char_display_widths(line: &str) -> Vec<usize> {
+	let mut widths = Vec::with_capacity(line.len());
+	for cluster in line.graphemes(true) {
+		widths.push(grapheme_display_width(cluster));
+		widths.extend(std::iter::repeat_n(0, cluster.chars().count().saturating_sub(1)));
+	}
+	widths
+}
+
+// 1-based cursor cols at which each grapheme cluster of `line` starts.
+pub fn grapheme_start_cols(line: &str) -> Vec<u16> {
+	let mut col = 1u16;
+	line
+		.graphemes(true)
+		.map(|cluster| {
+			let start = col;
+			col = col.saturating_add(cluster.chars().count() as u16);
+			start
+		})
+		.collect()
+}
+
+// 0-based char range of the cluster holding `char_index`, so an edit never
+// splits a base char from its combining marks.
+pub fn grapheme_char_range_at(line: &str, char_index: usize) -> std::ops::Range<usize> {
+	let mut start = 0usize;
+	for cluster in line.graphemes(true) {
+		let end = start.saturating_add(cluster.chars().count());
+		if char_index < end {
+			return start..end;
+		}
+		start = end;
+	}
+	char_index..char_index.saturating_add(1)
+}
+
+pub fn line_display_width(line: &str) -> usize { line.graphemes(true).map(grapheme_display_width).sum() }
+
 pub fn display_width_of_char_prefix(line: &str, char_count: usize) -> usize {
-	line.chars().take(char_count).map(char_display_width).sum()
+	let mut chars = 0usize;
+	let mut width = 0usize;
+	for cluster in line.graphemes(true) {
+		if chars >= char_count {
+			break;
+		}
+		width = width.saturating_add(grapheme_display_width(cluster));
+		chars = chars.saturating_add(cluster.chars().count());
+	}
+	width
 }
 
 pub fn display_width_of_char_prefix_with_virtual(line: &str, char_count: usize) -> usize {
@@ -27,13 +87,13 @@ pub fn display_col_of_cursor_slot(line: &str, col: u16) -> u16 {
 pub fn cursor_col_for_display_slot(line: &str, target_display_col: u16) -> u16 {
 	let mut consumed = 0u16;
 	let mut col = 1u16;
-	for ch in line.chars() {
-		let width = char_display_width(ch).max(1) as u16;
+	for cluster in line.graphemes(true) {
+		let width = grapheme_display_width(cluster).max(1) as u16;
 		if consumed.saturating_add(width) > target_display_col {
 			return col;
 		}
 		consumed = consumed.saturating_add(width);
-		col = col.saturating_add(1);
+		col = col.saturating_add(cluster.chars().count() as u16);
 	}
 	if target_display_col <= consumed {
 		col
@@ -47,11 +107,17 @@ pub fn previous_char_display_width_at_cursor(line: &str, col: u16) -> u16 {
 		return 1;
 	}
 	let prev_idx = col.saturating_sub(2) as usize;
-	line.chars().nth(prev_idx).map(|ch| char_display_width(ch).max(1) as u16).unwrap_or(1)
+	let widths = char_display_widths(line);
+	widths
+		.iter()
+		.take(prev_idx.saturating_add(1))
+		.rev()
+		.find(|width| **width > 0)
+		.map_or(1, |width| *width as u16)
 }
 
 pub fn wrapped_line_rows(line: &str, has_newline: bool, width: usize) -> u16 {
-	let mut display_width = line.chars().map(char_display_width).sum::<usize>();
+	let mut display_width = line_display_width(line);
 	if has_newline {
 		display_width = display_width.saturating_add(1);
 	}
@@ -111,8 +177,9 @@ pub fn navigable_col_for_display_target(text: &Rope, row: u16, target_display_co
 	};
 	let mut consumed = 0u16;
 	let mut col = 1u16;
-	for ch in line.chars() {
-		let width = char_display_width(ch).max(1) as u16;
+	let mut last_start = 1u16;
+	for cluster in line.graphemes(true) {
+		let width = grapheme_display_width(cluster).max(1) as u16;
 		if consumed >= target_display_col {
 			break;
 		}
@@ -120,9 +187,12 @@ pub fn navigable_col_for_display_target(text: &Rope, row: u16, target_display_co
 			break;
 		}
 		consumed = consumed.saturating_add(width);
-		col = col.saturating_add(1);
+		last_start = col;
+		col = col.saturating_add(cluster.chars().count() as u16);
 	}
-	col.min(line.chars().count() as u16).max(1)
+	// Past the line end, land on the last cluster's first char rather than on a
+	// trailing combining mark.
+	if col > line.chars().count() as u16 { last_start } else { col }
 }
 
 pub fn wrap_line_with_display_span(line: &str, max_cols: usize) -> Vec<(usize, usize, String)> {
@@ -134,16 +204,36 @@ pub fn wrap_line_with_display_span(line: &str, max_cols: usize) -> Vec<(usize, u
 	let mut current = String::new();
 	let mut row_start = 0usize;
 	let mut row_width = 0usize;
-	for ch in line.chars() {
-		let width = char_display_width(ch).max(1);
+	for cluster in line.graphemes(true) {
+		let width = grapheme_display_width(cluster).max(1);
 		if row_width > 0 && row_width.saturating_add(width) > max_cols {
 			rows.push((row_start, row_start.saturating_add(row_width), std::mem::take(&mut current)));
 			row_start = row_start.saturating_add(row_width);
 			row_width = 0;
 		}
-		current.push(ch);
+		current.push_str(cluster);
 		row_width = row_width.saturating_add(width);
 	}
 	rows.push((row_start, row_start.saturating_add(row_width), current));
 	rows
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{char_display_widths, cursor_col_for_display_slot, display_width_of_char_prefix, line_display_width};
+
+	#[test]
+	fn flag_and_zwj_family_emoji_should_count_as_one_wide_cluster() {
+		let flag = "\u{1F1EF}\u{1F1F5}";
+		let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+		let line = format!("a{flag}{family}b");
+
+		assert_eq!(char_display_widths(flag), vec![2, 0]);
+		assert_eq!(char_display_widths(family), vec![2, 0, 0, 0, 0]);
+		assert_eq!(line_display_width(line.as_str()), 6);
+		assert_eq!(display_width_of_char_prefix(line.as_str(), 3), 3);
+		assert_eq!(display_width_of_char_prefix(line.as_str(), 8), 5);
+		assert_eq!(cursor_col_for_display_slot(line.as_str(), 3), 4);
+		assert_eq!(cursor_col_for_display_slot(line.as_str(), 5), 9);
+	}
+}
@@ -2,7 +2,7 @@ use std::ops::Range;
 
 use ropey::Rope;
 
-use crate::{display_geometry::{char_display_width, cursor_col_for_display_slot, display_col_of_cursor_slot, previous_char_display_width_at_cursor}, text::rope_line_without_newline};
+use crate::{display_geometry::{char_display_width, cursor_col_for_display_slot, display_col_of_cursor_slot, previous_char_display_width_at_cursor}, text::{rope_ends_with_newline, rope_line_count, rope_line_without_newline}};
 
 pub fn split_lines_owned(text: &str) -> Vec<String> {
 	let mut lines = text.split('\n').map(ToString::to_string).collect::<Vec<_>>();
@@ -72,6 +72,26 @@ pub fn rope_linewise_char_range(text: &Rope, start_row: usize, end_row: usize) -
 	Some(start..end)
 }
 
+// Deleting down to the last line takes the newline before the rows instead of
+// after them, so the text keeps ending with a newline only if it did before.
+// Past the last line is the empty row after a trailing newline; deleting it
+// drops that newline.
+pub fn rope_linewise_delete_range(text: &Rope, start_row: usize, end_row: usize) -> Option<Range<usize>> {
+	let start = rope_line_start_char(text, start_row)?;
+	let line_count = rope_line_count(text);
+	if end_row.saturating_add(1) < line_count {
+		return Some(start..rope_line_start_char(text, end_row.saturating_add(1))?);
+	}
+	if start_row == 0 {
+		return Some(0..text.len_chars());
+	}
+	if rope_ends_with_newline(text) && start_row < line_count {
+		Some(start..text.len_chars())
+	} else {
+		Some(start.saturating_sub(1)..text.len_chars())
+	}
+}
+
 pub fn rope_join_rows_without_newline(text: &Rope, start_row: usize, end_row: usize) -> Option<String> {
 	if start_row > end_row || end_row >= rope_editable_line_count(text) {
 		return None;
@@ -86,6 +106,20 @@ pub fn rope_join_rows_without_newline(text: &Rope, start_row: usize, end_row: us
 	Some(joined)
 }
 
+// What `J` puts between the joined lines, following Vim: nothing when either
+// side already has whitespace or the next line opens with `)`, two spaces after
+// a sentence end with `joinspaces`, and one space otherwise.
+pub fn join_separator(current: &str, next_trimmed: &str, join_spaces: bool) -> &'static str {
+	if current.is_empty()
+		|| next_trimmed.is_empty()
+		|| current.ends_with(char::is_whitespace)
+		|| next_trimmed.starts_with(')')
+	{
+		return "";
+	}
+	if join_spaces && current.ends_with(['.', '!', '?']) { "  " } else { " " }
+}
+
 pub fn rope_linewise_insertion_text(slot_text: &str, has_following_rows: bool) -> String {
 	let mut replacement = slot_text.to_string();
 	if has_following_rows {
@@ -1,17 +1,20 @@
 mod buffer;
+mod completion;
 mod core;
 mod edit;
+mod fold;
 mod movement;
+mod register;
 mod session;
 mod tab;
 mod visual;
 mod window;
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 use slotmap::SlotMap;
 
-use crate::model::{BufferId, BufferState, CursorState, EditorMode, PendingBlockInsert, PendingInsertUndoGroup, TabId, TabState, WindowBufferViewState, WindowId, WindowState};
+use crate::model::{BufferId, BufferState, CursorState, EditorMode, InsertCompletionState, PendingBlockInsert, PendingInsertUndoGroup, RegisterContent, TabId, TabState, VirtualCursor, VirtualEdit, WindowBufferViewState, WindowId, WindowState};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EditorOperationError {
@@ -24,6 +27,7 @@ pub enum EditorOperationError {
 	SlotEmpty,
 	NothingToUndo,
 	NothingToRedo,
+	NoCompletionMatch,
 }
 
 #[derive(Debug)]
@@ -34,16 +38,27 @@ pub struct EditorState {
 	pub visual_block_anchor_display_col: Option<u16>,
 	pub visual_block_cursor_display_col: Option<u16>,
 	pub preferred_col:                   Option<u16>,
+	pub virtual_edit:                    VirtualEdit,
+	pub virtual_cursor:                  Option<VirtualCursor>,
 	pub line_slot:                       Option<String>,
 	pub line_slot_line_wise:             bool,
 	pub line_slot_block_wise:            bool,
+	pub line_slot_block_rows:            Vec<String>,
+	pub yank_register:                   Option<RegisterContent>,
+	pub numbered_registers:              VecDeque<RegisterContent>,
+	pub named_registers:                 BTreeMap<char, RegisterContent>,
+	pub small_delete_register:           Option<RegisterContent>,
+	pub selected_register:               Option<char>,
 	pub pending_insert_group:            Option<PendingInsertUndoGroup>,
 	pub pending_block_insert:            Option<PendingBlockInsert>,
+	pub insert_completion:               Option<InsertCompletionState>,
 	pub window_buffer_views:             HashMap<(WindowId, BufferId), WindowBufferViewState>,
+	pub alternate_buffers:               HashMap<WindowId, BufferId>,
 	pub buffers:                         SlotMap<BufferId, BufferState>,
 	pub buffer_order:                    Vec<BufferId>,
 	pub windows:                         SlotMap<WindowId, WindowState>,
 	pub tabs:                            BTreeMap<TabId, TabState>,
+	pub undo_levels:                     usize,
 }
 
 impl EditorState {
@@ -68,16 +83,27 @@ impl EditorState {
 			visual_block_anchor_display_col: None,
 			visual_block_cursor_display_col: None,
 			preferred_col:                   None,
+			virtual_edit:                    VirtualEdit::Block,
+			virtual_cursor:                  None,
 			line_slot:                       None,
 			line_slot_line_wise:             false,
 			line_slot_block_wise:            false,
+			line_slot_block_rows:            Vec::new(),
+			yank_register:                   None,
+			numbered_registers:              VecDeque::new(),
+			named_registers:                 BTreeMap::new(),
+			small_delete_register:           None,
+			selected_register:               None,
 			pending_insert_group:            None,
 			pending_block_insert:            None,
+			insert_completion:               None,
 			window_buffer_views:             HashMap::new(),
+			alternate_buffers:               HashMap::new(),
 			buffers:                         SlotMap::with_key(),
 			buffer_order:                    Vec::new(),
 			windows:                         SlotMap::with_key(),
 			tabs:                            BTreeMap::new(),
+			undo_levels:                     Self::DEFAULT_UNDO_LEVELS,
 		}
 	}
 
@@ -87,11 +113,19 @@ impl EditorState {
 		self.visual_block_anchor_display_col = None;
 		self.visual_block_cursor_display_col = None;
 		self.preferred_col = None;
+		self.virtual_cursor = None;
 		self.line_slot = None;
 		self.line_slot_line_wise = false;
 		self.line_slot_block_wise = false;
+		self.line_slot_block_rows.clear();
+		self.yank_register = None;
+		self.numbered_registers.clear();
+		self.named_registers.clear();
+		self.small_delete_register = None;
+		self.selected_register = None;
 		self.pending_insert_group = None;
 		self.pending_block_insert = None;
+		self.insert_completion = None;
 	}
 }
 
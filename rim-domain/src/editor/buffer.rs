@@ -1,12 +1,22 @@
-use std::path::{Path, PathBuf};
+use std::{collections::VecDeque, ops::Range, path::{Path, PathBuf}, time::SystemTime};
 
 use ropey::Rope;
 use slotmap::Key;
 
-use crate::{editor::EditorState, model::{BufferEditSnapshot, BufferHistoryEntry, BufferId, BufferState, BufferSwitchDirection, CursorState, EditorMode, PersistedBufferHistory, TabId}, text::{buffer_name_from_path, clamp_cursor_for_rope, compute_rope_text_diff, merge_adjacent_insert_history_edits, rope_line_count}};
+use crate::{editor::EditorState, model::{BufferEditSnapshot, BufferHistoryEntry, BufferId, BufferState, BufferSwitchDirection, CursorState, EditorMode, LineChangeSign, PersistedBufferHistory, RopeTextDiff, TabId}, text::{buffer_name_from_path, clamp_cursor_for_rope, compute_line_change_signs, compute_rope_text_diff, line_change_hunk_starts, merge_adjacent_insert_history_edits, rope_line_count}};
 
 impl EditorState {
-	pub const MAX_HISTORY_ENTRIES: usize = 256;
+	pub const DEFAULT_UNDO_LEVELS: usize = 256;
+
+	// A lower limit drops the oldest undo and redo entries of every buffer right
+	// away instead of waiting for the next edit.
+	pub fn set_undo_levels(&mut self, undo_levels: usize) {
+		self.undo_levels = undo_levels;
+		for (_, buffer) in &mut self.buffers {
+			trim_history_front(&mut buffer.undo_stack, undo_levels);
+			trim_history_front(&mut buffer.redo_stack, undo_levels);
+		}
+	}
 
 	pub fn tab_id_for_window(&self, window_id: crate::model::WindowId) -> Option<TabId> {
 		self.tabs.iter().find_map(|(tab_id, tab)| tab.windows.contains(&window_id).then_some(*tab_id))
@@ -76,8 +86,16 @@ impl EditorState {
 			clean_text: rope,
 			dirty: false,
 			externally_modified: false,
-			undo_stack: Vec::new(),
-			redo_stack: Vec::new(),
+			read_only: false,
+			new_file: false,
+			deleted_on_disk: false,
+			undo_stack: VecDeque::new(),
+			redo_stack: VecDeque::new(),
+			changedtick: 0,
+			folds: Vec::new(),
+			change_list: Vec::new(),
+			change_list_index: 0,
+			filetype: None,
 		});
 		self.buffer_order.push(id);
 		self.register_buffer_in_tab_order(self.active_tab, id, None);
@@ -135,12 +153,43 @@ impl EditorState {
 		Some(target)
 	}
 
+	// The alternate buffer is whichever buffer the active window showed before the
+	// current one; it is gone once that buffer is closed.
+	pub fn switch_to_alternate_buffer(&mut self) -> Option<BufferId> {
+		let active_window_id = self.active_window_id();
+		let target = self
+			.alternate_buffers
+			.get(&active_window_id)
+			.copied()
+			.filter(|buffer_id| self.buffers.contains_key(*buffer_id))?;
+		self.bind_buffer_to_window(active_window_id, target, true);
+		self.clamp_window_cursors_for_buffer(target);
+		Some(target)
+	}
+
+	// Numbers follow the active tab's buffer order, starting at 1.
+	pub fn switch_to_buffer_number(&mut self, number: usize) -> Option<BufferId> {
+		let target = *self.active_tab_buffer_ids().get(number.checked_sub(1)?)?;
+		let active_window_id = self.active_window_id();
+		self.bind_buffer_to_window(active_window_id, target, true);
+		self.clamp_window_cursors_for_buffer(target);
+		Some(target)
+	}
+
 	pub fn replaceable_active_untitled_buffer_id(&self) -> Option<BufferId> {
 		let active_buffer_id = self.active_buffer_id()?;
 		let active_tab = self.tabs.get(&self.active_tab)?;
 		if active_tab.buffer_order.as_slice() != [active_buffer_id] {
 			return None;
 		}
+		// Another window still showing the buffer keeps it, as after `:diffsplit`.
+		let active_window_id = active_tab.active_window;
+		if active_tab.windows.iter().any(|window_id| {
+			*window_id != active_window_id
+				&& self.windows.get(*window_id).is_some_and(|window| window.buffer_id == Some(active_buffer_id))
+		}) {
+			return None;
+		}
 		let buffer = self.buffers.get(active_buffer_id)?;
 		(buffer.path.is_none() && !buffer.dirty && !buffer.externally_modified).then_some(active_buffer_id)
 	}
@@ -157,21 +206,30 @@ impl EditorState {
 		buffer.externally_modified = false;
 		buffer.undo_stack.clear();
 		buffer.redo_stack.clear();
+		buffer.folds.clear();
+		buffer.change_list.clear();
+		buffer.change_list_index = 0;
+		buffer.bump_changedtick();
 		self.pending_insert_group = self.pending_insert_group.take().filter(|group| group.buffer_id != buffer_id);
 	}
 
 	pub fn replace_buffer_text_preserving_cursor(&mut self, buffer_id: BufferId, text: String) -> bool {
 		let is_active = self.active_buffer_id() == Some(buffer_id);
-		let (previous_max_row, new_max_row, next_text) = {
+		let (previous_text, previous_max_row, new_max_row, next_text) = {
 			let Some(buffer) = self.buffers.get_mut(buffer_id) else {
 				return false;
 			};
+			let previous_text = buffer.text.clone();
 			let previous_max_row = rope_line_count(&buffer.text) as u16;
 			buffer.text = Rope::from_str(text.as_str());
+			buffer.bump_changedtick();
 			let next_text = buffer.text.clone();
 			let new_max_row = rope_line_count(&next_text) as u16;
-			(previous_max_row, new_max_row, next_text)
+			(previous_text, previous_max_row, new_max_row, next_text)
 		};
+		if let Some(diff) = compute_rope_text_diff(&previous_text, &next_text) {
+			self.adjust_buffer_positions_for_edit(buffer_id, &previous_text, &diff);
+		}
 		for ((_, saved_buffer_id), view) in &mut self.window_buffer_views {
 			if *saved_buffer_id != buffer_id {
 				continue;
@@ -180,17 +238,19 @@ impl EditorState {
 				view.cursor.row = new_max_row;
 			}
 			view.cursor = clamp_cursor_for_rope(&next_text, view.cursor);
+			view.scroll_y = view.scroll_y.min(view.cursor.row.saturating_sub(1));
 		}
+		// Only the active window gets a full scroll realign afterwards; the others
+		// must at least not be scrolled past their clamped cursor.
 		for (_, window) in &mut self.windows {
 			if window.buffer_id != Some(buffer_id) {
 				continue;
 			}
 			if window.cursor.row >= previous_max_row {
 				window.cursor.row = new_max_row;
-			} else {
-				window.cursor = clamp_cursor_for_rope(&next_text, window.cursor);
 			}
 			window.cursor = clamp_cursor_for_rope(&next_text, window.cursor);
+			window.scroll_y = window.scroll_y.min(window.cursor.row.saturating_sub(1));
 		}
 		is_active
 	}
@@ -256,6 +316,24 @@ impl EditorState {
 		}
 	}
 
+	pub fn set_buffer_read_only(&mut self, buffer_id: BufferId, read_only: bool) {
+		if let Some(buffer) = self.buffers.get_mut(buffer_id) {
+			buffer.read_only = read_only;
+		}
+	}
+
+	pub fn set_buffer_new_file(&mut self, buffer_id: BufferId, new_file: bool) {
+		if let Some(buffer) = self.buffers.get_mut(buffer_id) {
+			buffer.new_file = new_file;
+		}
+	}
+
+	pub fn set_buffer_deleted_on_disk(&mut self, buffer_id: BufferId, deleted_on_disk: bool) {
+		if let Some(buffer) = self.buffers.get_mut(buffer_id) {
+			buffer.deleted_on_disk = deleted_on_disk;
+		}
+	}
+
 	pub fn clear_buffer_history(&mut self, buffer_id: BufferId) {
 		let Some(buffer) = self.buffers.get_mut(buffer_id) else {
 			return;
@@ -273,12 +351,46 @@ impl EditorState {
 		Some(buffer.externally_modified)
 	}
 
+	pub fn active_buffer_is_read_only(&self) -> Option<bool> {
+		let buffer_id = self.active_buffer_id()?;
+		let buffer = self.buffers.get(buffer_id)?;
+		Some(buffer.read_only)
+	}
+
 	pub fn mark_active_buffer_dirty(&mut self) {
+		// Any edit may move the cursor column, so a vertical move afterward must
+		// start from where the cursor is now rather than a stale target.
+		self.preferred_col = None;
 		if let Some(buffer_id) = self.active_buffer_id() {
+			if let Some(buffer) = self.buffers.get_mut(buffer_id) {
+				buffer.bump_changedtick();
+			}
 			self.refresh_buffer_dirty(buffer_id);
 		}
 	}
 
+	pub fn buffer_changedtick(&self, buffer_id: BufferId) -> Option<u64> {
+		self.buffers.get(buffer_id).map(|buffer| buffer.changedtick)
+	}
+
+	pub fn buffer_line_change_signs(
+		&self,
+		buffer_id: BufferId,
+		rows: Range<usize>,
+	) -> Vec<Option<LineChangeSign>> {
+		match self.buffers.get(buffer_id) {
+			Some(buffer) if buffer.dirty => compute_line_change_signs(&buffer.clean_text, &buffer.text, rows),
+			_ => vec![None; rows.len()],
+		}
+	}
+
+	pub fn buffer_change_hunk_starts(&self, buffer_id: BufferId) -> Vec<usize> {
+		match self.buffers.get(buffer_id) {
+			Some(buffer) if buffer.dirty => line_change_hunk_starts(&buffer.clean_text, &buffer.text),
+			_ => Vec::new(),
+		}
+	}
+
 	pub fn refresh_buffer_dirty(&mut self, buffer_id: BufferId) {
 		if let Some(buffer) = self.buffers.get_mut(buffer_id) {
 			buffer.dirty = buffer.text != buffer.clean_text;
@@ -335,20 +447,14 @@ impl EditorState {
 	pub fn record_history_from_text_diff(
 		&mut self,
 		buffer_id: BufferId,
-		before_text: &Rope,
+		diff: RopeTextDiff,
 		before_cursor: CursorState,
 		mode_before: EditorMode,
 		skip_history: bool,
 	) {
-		if skip_history {
+		if skip_history || !self.buffers.contains_key(buffer_id) {
 			return;
 		}
-		let Some(after_buffer) = self.buffers.get(buffer_id) else {
-			return;
-		};
-		let Some(diff) = compute_rope_text_diff(before_text, &after_buffer.text) else {
-			return;
-		};
 		let edit = BufferEditSnapshot {
 			start_byte:    diff.start_byte,
 			deleted_text:  diff.deleted_text,
@@ -360,22 +466,23 @@ impl EditorState {
 			edits: vec![edit],
 			before_cursor,
 			after_cursor: after_cursor.unwrap_or(before_cursor),
+			changed_at: None,
 		};
 		self.apply_edit_entry(buffer_id, entry, mode_before);
 	}
 
-	pub fn push_buffer_history_entry(&mut self, buffer_id: BufferId, entry: BufferHistoryEntry) {
+	pub fn push_buffer_history_entry(&mut self, buffer_id: BufferId, mut entry: BufferHistoryEntry) {
 		let Some(buffer) = self.buffers.get_mut(buffer_id) else {
 			return;
 		};
 		if entry.edits.is_empty() {
 			return;
 		}
+		entry.changed_at.get_or_insert_with(SystemTime::now);
 
-		buffer.undo_stack.push(entry);
-		if buffer.undo_stack.len() > Self::MAX_HISTORY_ENTRIES {
-			buffer.undo_stack.remove(0);
-		}
+		record_change_position(buffer, entry.after_cursor);
+		buffer.undo_stack.push_back(entry);
+		trim_history_front(&mut buffer.undo_stack, self.undo_levels);
 		buffer.redo_stack.clear();
 	}
 
@@ -386,22 +493,20 @@ impl EditorState {
 			&& group.buffer_id == buffer_id
 			&& !group.edits.is_empty()
 		{
-			undo_stack.push(BufferHistoryEntry {
+			undo_stack.push_back(BufferHistoryEntry {
 				edits:         group.edits.clone(),
 				before_cursor: group.before_cursor,
 				after_cursor:  self.cursor_for_buffer(buffer_id).unwrap_or(group.before_cursor),
+				changed_at:    None,
 			});
-			let overflow = undo_stack.len().saturating_sub(Self::MAX_HISTORY_ENTRIES);
-			if overflow > 0 {
-				undo_stack.drain(0..overflow);
-			}
+			trim_history_front(&mut undo_stack, self.undo_levels);
 		}
 
 		Some(PersistedBufferHistory {
 			current_text: buffer.text.to_string(),
-			cursor: self.cursor_for_buffer(buffer_id).unwrap_or_default(),
-			undo_stack,
-			redo_stack: buffer.redo_stack.clone(),
+			cursor:       self.cursor_for_buffer(buffer_id).unwrap_or_default(),
+			undo_stack:   undo_stack.into(),
+			redo_stack:   buffer.redo_stack.iter().cloned().collect(),
 		})
 	}
 
@@ -435,8 +540,10 @@ impl EditorState {
 			return false;
 		}
 
-		buffer.undo_stack = persisted_history.undo_stack;
-		buffer.redo_stack = persisted_history.redo_stack;
+		buffer.undo_stack = persisted_history.undo_stack.into();
+		buffer.redo_stack = persisted_history.redo_stack.into();
+		trim_history_front(&mut buffer.undo_stack, self.undo_levels);
+		trim_history_front(&mut buffer.redo_stack, self.undo_levels);
 		if restore_view {
 			let persisted_cursor = clamp_cursor_for_rope(&buffer.text, persisted_history.cursor);
 			for ((_, saved_buffer_id), view) in &mut self.window_buffer_views {
@@ -517,3 +624,25 @@ impl EditorState {
 		}
 	}
 }
+
+pub(super) fn trim_history_front(stack: &mut VecDeque<BufferHistoryEntry>, limit: usize) {
+	while stack.len() > limit {
+		stack.pop_front();
+	}
+}
+
+const CHANGE_LIST_CAPACITY: usize = 100;
+
+// A change on the same line as the newest entry replaces it, so typing along a
+// line leaves one stop.
+fn record_change_position(buffer: &mut BufferState, cursor: CursorState) {
+	let change_list = &mut buffer.change_list;
+	if change_list.last().is_some_and(|last| last.row == cursor.row) {
+		change_list.pop();
+	}
+	change_list.push(cursor);
+	if change_list.len() > CHANGE_LIST_CAPACITY {
+		change_list.remove(0);
+	}
+	buffer.change_list_index = change_list.len();
+}
@@ -0,0 +1,101 @@
+use crate::{edit::rope_cursor_char, editor::{EditorOperationError, EditorState}, model::{BufferId, CursorState, InsertCompletionState}, text::{collect_completion_candidates, is_completion_word_char, rope_line_without_newline}};
+
+impl EditorState {
+	pub fn complete_word_at_cursor(&mut self, forward: bool) -> Result<(), EditorOperationError> {
+		let buffer_id = self.active_buffer_id().ok_or(EditorOperationError::NoActiveBuffer)?;
+		let window_id = self.active_window_id();
+		let cursor = self.windows.get(window_id).ok_or(EditorOperationError::NoActiveBuffer)?.cursor;
+
+		let mut completion = match self.insert_completion.take() {
+			Some(completion) if self.insert_completion_matches_cursor(&completion, buffer_id, cursor) => completion,
+			_ => self.start_insert_completion(buffer_id, cursor)?,
+		};
+		let candidate_count = completion.candidates.len();
+		completion.selected = match (completion.selected, forward) {
+			(None, true) => Some(0),
+			(None, false) => Some(candidate_count.saturating_sub(1)),
+			(Some(index), true) if index + 1 < candidate_count => Some(index + 1),
+			(Some(index), false) if index > 0 => Some(index - 1),
+			(Some(_), _) => None,
+		};
+
+		let buffer = self.buffers.get_mut(buffer_id).ok_or(EditorOperationError::ActiveBufferMissing)?;
+		let row_idx = cursor.row.saturating_sub(1) as usize;
+		let start = rope_cursor_char(&buffer.text, row_idx, completion.start_col.saturating_sub(1) as usize)
+			.ok_or(EditorOperationError::OutOfRange)?;
+		let end = rope_cursor_char(&buffer.text, row_idx, cursor.col.saturating_sub(1) as usize)
+			.ok_or(EditorOperationError::OutOfRange)?;
+		let replacement = completion.current_text().to_string();
+		buffer.text.remove(start..end);
+		buffer.text.insert(start, replacement.as_str());
+		if let Some(window) = self.windows.get_mut(window_id) {
+			window.cursor.col = completion.start_col.saturating_add(replacement.chars().count() as u16);
+		}
+		self.insert_completion = Some(completion);
+		self.mark_active_buffer_dirty();
+		Ok(())
+	}
+
+	fn insert_completion_matches_cursor(
+		&self,
+		completion: &InsertCompletionState,
+		buffer_id: BufferId,
+		cursor: CursorState,
+	) -> bool {
+		if completion.buffer_id != buffer_id || completion.row != cursor.row {
+			return false;
+		}
+		let current = completion.current_text();
+		if completion.start_col.saturating_add(current.chars().count() as u16) != cursor.col {
+			return false;
+		}
+		let Some(line) = self
+			.buffers
+			.get(buffer_id)
+			.and_then(|buffer| rope_line_without_newline(&buffer.text, cursor.row.saturating_sub(1) as usize))
+		else {
+			return false;
+		};
+		line
+			.chars()
+			.skip(completion.start_col.saturating_sub(1) as usize)
+			.take(current.chars().count())
+			.eq(current.chars())
+	}
+
+	fn start_insert_completion(
+		&self,
+		buffer_id: BufferId,
+		cursor: CursorState,
+	) -> Result<InsertCompletionState, EditorOperationError> {
+		let buffer = self.buffers.get(buffer_id).ok_or(EditorOperationError::ActiveBufferMissing)?;
+		let line = rope_line_without_newline(&buffer.text, cursor.row.saturating_sub(1) as usize)
+			.ok_or(EditorOperationError::OutOfRange)?;
+		let before_cursor = line.chars().take(cursor.col.saturating_sub(1) as usize).collect::<Vec<_>>();
+		let prefix_len = before_cursor.iter().rev().take_while(|ch| is_completion_word_char(**ch)).count();
+		let prefix = before_cursor[before_cursor.len() - prefix_len..].iter().collect::<String>();
+		if prefix.is_empty() {
+			return Err(EditorOperationError::NoCompletionMatch);
+		}
+
+		let mut candidates = Vec::new();
+		collect_completion_candidates(&buffer.text, prefix.as_str(), &mut candidates);
+		for other_id in self.buffer_order.iter().copied().filter(|id| *id != buffer_id) {
+			if let Some(other) = self.buffers.get(other_id) {
+				collect_completion_candidates(&other.text, prefix.as_str(), &mut candidates);
+			}
+		}
+		if candidates.is_empty() {
+			return Err(EditorOperationError::NoCompletionMatch);
+		}
+
+		Ok(InsertCompletionState {
+			buffer_id,
+			row: cursor.row,
+			start_col: cursor.col.saturating_sub(prefix_len as u16),
+			prefix,
+			candidates,
+			selected: None,
+		})
+	}
+}
@@ -59,6 +59,9 @@ impl EditorState {
 			scroll_x: restored_view.scroll_x,
 			scroll_y: restored_view.scroll_y,
 		});
+		if let Some(previous_buffer_id) = previous_buffer_id.filter(|id| *id != buffer_id) {
+			self.alternate_buffers.insert(window_id, previous_buffer_id);
+		}
 		if let Some(tab_id) = self.tab_id_for_window(window_id) {
 			self.register_buffer_in_tab_order(tab_id, buffer_id, previous_buffer_id);
 		}
@@ -79,5 +82,6 @@ impl EditorState {
 
 	pub fn remove_window_view_bindings(&mut self, window_id: WindowId) {
 		self.window_buffer_views.retain(|(candidate_window_id, _), _| *candidate_window_id != window_id);
+		self.alternate_buffers.remove(&window_id);
 	}
 }
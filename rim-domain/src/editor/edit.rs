@@ -1,4 +1,6 @@
-use crate::{edit::{ensure_rope_editable_rows, pad_rope_line_to_char_len, rope_cursor_char, rope_editable_line_count, rope_editable_line_len_chars, rope_line_char_end_without_newline, rope_line_char_range_without_newline, rope_line_start_char, split_lines_owned}, editor::{EditorOperationError, EditorState}, model::{BufferState, WindowState}, text::{apply_text_delta_redo, apply_text_delta_undo, rope_ends_with_newline, rope_line_count, rope_line_len_chars}};
+use std::ops::Range;
+
+use crate::{display_geometry::{TAB_DISPLAY_WIDTH, display_width_of_char_prefix_with_virtual, grapheme_char_range_at, grapheme_start_cols, line_display_width}, edit::{block_col_for_display_target, ensure_rope_editable_rows, expand_tab_padding_at_display_target, join_separator, pad_rope_line_to_char_len, rope_cursor_char, rope_editable_line_count, rope_editable_line_len_chars, rope_line_char_end_without_newline, rope_line_char_range_without_newline, rope_line_start_char, rope_linewise_delete_range, split_lines_owned}, editor::{EditorOperationError, EditorState, buffer::trim_history_front}, indent::{leading_indent, reindent_lines, retab_indent}, model::{BufferState, CursorState, RegisterContent, WindowState}, motion::{Motion, char_index_to_cursor, cursor_to_char_index, motion_operator_range}, search::substitute_line, text::{apply_text_delta_redo, apply_text_delta_undo, rope_ends_with_newline, rope_line_count, rope_line_len_chars, rope_line_without_newline}};
 
 fn active_buffer_and_window_mut(state: &mut EditorState) -> Option<(&mut BufferState, &mut WindowState)> {
 	let buffer_id = state.active_buffer_id()?;
@@ -42,6 +44,22 @@ impl EditorState {
 		true
 	}
 
+	// Leaves the cursor after the inserted text, as typing it would.
+	pub fn insert_text_at_cursor(&mut self, text: &str) -> bool {
+		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
+			return false;
+		};
+		let row_idx = window.cursor.row.saturating_sub(1) as usize;
+		let col_idx = window.cursor.col.saturating_sub(1) as usize;
+		let Some(insert_at) = rope_cursor_char(&buffer.text, row_idx, col_idx) else {
+			return false;
+		};
+		buffer.text.insert(insert_at, text);
+		window.cursor = char_index_to_cursor(&buffer.text, insert_at + text.chars().count());
+		self.mark_active_buffer_dirty();
+		true
+	}
+
 	pub fn open_line_below_at_cursor(&mut self) -> bool {
 		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
 			return false;
@@ -71,7 +89,7 @@ impl EditorState {
 		true
 	}
 
-	pub fn join_line_below_at_cursor(&mut self) -> bool {
+	pub fn join_line_below_at_cursor(&mut self, join_spaces: bool) -> bool {
 		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
 			return false;
 		};
@@ -92,14 +110,12 @@ impl EditorState {
 		let next = buffer.text.slice(next_range.clone()).to_string();
 		let next_trimmed = next.trim_start();
 		let mut merged = current;
-		if !merged.is_empty() && !next_trimmed.is_empty() && !merged.ends_with(' ') {
-			merged.push(' ');
-		}
+		merged.push_str(join_separator(merged.as_str(), next_trimmed, join_spaces));
 		merged.push_str(next_trimmed);
 
 		buffer.text.remove(current_range.start..next_range.end);
 		buffer.text.insert(current_range.start, merged.as_str());
-		let max_col = merged.chars().count() as u16 + 1;
+		let max_col = merged.chars().count() as u16;
 		window.cursor.col = window.cursor.col.min(max_col).max(1);
 		self.mark_active_buffer_dirty();
 		self.preferred_col = None;
@@ -120,9 +136,12 @@ impl EditorState {
 			let Some(delete_end) = rope_cursor_char(&buffer.text, row_idx, col_idx) else {
 				return false;
 			};
-			let delete_start = delete_end.saturating_sub(1);
+			let cluster_len = rope_line_without_newline(&buffer.text, row_idx)
+				.map(|line| grapheme_char_range_at(line.as_str(), col_idx.saturating_sub(1)))
+				.map_or(1, |cluster| col_idx.saturating_sub(cluster.start).max(1));
+			let delete_start = delete_end.saturating_sub(cluster_len);
 			buffer.text.remove(delete_start..delete_end);
-			window.cursor.col = window.cursor.col.saturating_sub(1);
+			window.cursor.col = window.cursor.col.saturating_sub(cluster_len as u16);
 		} else if row_idx > 0 {
 			let Some(current_start) = rope_line_start_char(&buffer.text, row_idx) else {
 				return false;
@@ -142,6 +161,42 @@ impl EditorState {
 	}
 
 	pub fn cut_current_char_to_slot(&mut self) -> Result<(), EditorOperationError> {
+		self.cut_char_under_cursor_to_slot(true)
+	}
+
+	// `s` leaves the cursor where the char was, even past the line's end, for
+	// the insert that follows.
+	pub fn substitute_current_char_to_slot(&mut self) -> Result<(), EditorOperationError> {
+		self.cut_char_under_cursor_to_slot(false)
+	}
+
+	// `cc`/`S`: the line goes to the slot line-wise and stays, emptied, for the
+	// insert that follows.
+	pub fn clear_current_line_to_slot(&mut self) -> Result<(), EditorOperationError> {
+		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
+			return Err(EditorOperationError::NoActiveBuffer);
+		};
+		let row_idx = window.cursor.row.saturating_sub(1) as usize;
+		let Some(line_range) = rope_line_char_range_without_newline(&buffer.text, row_idx) else {
+			return Err(EditorOperationError::OutOfRange);
+		};
+		let cleared = buffer.text.slice(line_range.clone()).to_string();
+		window.cursor.col = 1;
+		if !line_range.is_empty() {
+			buffer.text.remove(line_range);
+			self.mark_active_buffer_dirty();
+		}
+		self.preferred_col = None;
+		self.store_delete(RegisterContent {
+			text:       cleared,
+			line_wise:  true,
+			block_wise: false,
+			block_rows: Vec::new(),
+		});
+		Ok(())
+	}
+
+	fn cut_char_under_cursor_to_slot(&mut self, keep_cursor_on_char: bool) -> Result<(), EditorOperationError> {
 		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
 			return Err(EditorOperationError::NoActiveBuffer);
 		};
@@ -155,23 +210,117 @@ impl EditorState {
 			return Err(EditorOperationError::NoChar);
 		}
 
-		let start = line_range.start.saturating_add(col_idx);
-		let end = start.saturating_add(1);
+		let line = buffer.text.slice(line_range.clone()).to_string();
+		let cluster = grapheme_char_range_at(line.as_str(), col_idx);
+		let start = line_range.start.saturating_add(cluster.start);
+		let end = line_range.start.saturating_add(cluster.end);
 		let cut = buffer.text.slice(start..end).to_string();
 		buffer.text.remove(start..end);
+		window.cursor.col = cluster.start.saturating_add(1) as u16;
+		if keep_cursor_on_char {
+			// Deleting the last cluster leaves the cursor on the one before it.
+			let last_start = rope_line_without_newline(&buffer.text, row_idx)
+				.and_then(|line| grapheme_start_cols(line.as_str()).last().copied())
+				.unwrap_or(1);
+			window.cursor.col = window.cursor.col.min(last_start);
+		}
 		self.mark_active_buffer_dirty();
-		self.line_slot = Some(cut);
-		self.line_slot_line_wise = false;
-		self.line_slot_block_wise = false;
+		self.store_delete(RegisterContent {
+			text:       cut,
+			line_wise:  false,
+			block_wise: false,
+			block_rows: Vec::new(),
+		});
+		Ok(())
+	}
+
+	pub fn delete_motion_to_slot(&mut self, motion: Motion, count: usize) -> Result<(), EditorOperationError> {
+		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
+			return Err(EditorOperationError::NoActiveBuffer);
+		};
+		let from = cursor_to_char_index(&buffer.text, window.cursor);
+		let range = motion_operator_range(&buffer.text, from, motion, count);
+		self.delete_char_range_to_slot(range)
+	}
+
+	// Search motions are exclusive, so the later end survives: the match when it
+	// lies after the cursor, the cursor's char when the search wrapped before it.
+	pub fn delete_to_cursor_to_slot(&mut self, target: CursorState) -> Result<(), EditorOperationError> {
+		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
+			return Err(EditorOperationError::NoActiveBuffer);
+		};
+		let from = cursor_to_char_index(&buffer.text, window.cursor);
+		let to = cursor_to_char_index(&buffer.text, target);
+		self.delete_char_range_to_slot(from.min(to)..from.max(to))
+	}
+
+	fn delete_char_range_to_slot(&mut self, range: Range<usize>) -> Result<(), EditorOperationError> {
+		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
+			return Err(EditorOperationError::NoActiveBuffer);
+		};
+		if range.is_empty() {
+			return Err(EditorOperationError::NoChar);
+		}
+		let deleted = buffer.text.slice(range.clone()).to_string();
+		buffer.text.remove(range.clone());
+		window.cursor = char_index_to_cursor(&buffer.text, range.start);
+		self.mark_active_buffer_dirty();
+		self.clamp_cursor_to_navigable_col();
+		self.store_delete(RegisterContent {
+			text:       deleted,
+			line_wise:  false,
+			block_wise: false,
+			block_rows: Vec::new(),
+		});
 		Ok(())
 	}
 
 	pub fn paste_slot_at_cursor(&mut self) -> Result<(), EditorOperationError> {
-		let Some(slot_text) = self.line_slot.clone() else {
+		let Some(content) = self.take_paste_register() else {
+			return Err(EditorOperationError::SlotEmpty);
+		};
+		self.paste_register_content(content)
+	}
+
+	// `]p`/`[p`: line-wise text is re-indented to match the cursor line and goes
+	// below or above it; anything else pastes like `p`.
+	// The pasted lines take the cursor line's indent width, written out with tabs
+	// or spaces as the buffer's `expandtab` asks.
+	pub fn paste_slot_with_indent(
+		&mut self,
+		above: bool,
+		expand_tab: bool,
+	) -> Result<(), EditorOperationError> {
+		let Some(content) = self.take_paste_register() else {
 			return Err(EditorOperationError::SlotEmpty);
 		};
-		let line_wise_slot = self.line_slot_line_wise;
-		let block_wise_slot = self.line_slot_block_wise;
+		if !content.line_wise {
+			return self.paste_register_content(content);
+		}
+		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
+			return Err(EditorOperationError::NoActiveBuffer);
+		};
+		let row_idx = window.cursor.row.saturating_sub(1) as usize;
+		let Some(line_start) = rope_line_start_char(&buffer.text, row_idx) else {
+			return Err(EditorOperationError::OutOfRange);
+		};
+		let line = rope_line_without_newline(&buffer.text, row_idx).unwrap_or_default();
+		let target_indent = retab_indent(line.as_str(), TAB_DISPLAY_WIDTH, !expand_tab)
+			.map_or_else(|| leading_indent(line.as_str()).to_string(), |(indent, _)| indent);
+		let text = reindent_lines(content.text.as_str(), target_indent.as_str(), TAB_DISPLAY_WIDTH);
+		if !above {
+			return self.paste_register_content(RegisterContent { text, ..content });
+		}
+
+		buffer.text.insert(line_start, format!("{}\n", text).as_str());
+		window.cursor.row = row_idx.saturating_add(split_lines_owned(&text).len()) as u16;
+		window.cursor.col = 1;
+		self.mark_active_buffer_dirty();
+		Ok(())
+	}
+
+	fn paste_register_content(&mut self, content: RegisterContent) -> Result<(), EditorOperationError> {
+		let (slot_text, line_wise_slot, block_wise_slot) = (&content.text, content.line_wise, content.block_wise);
 		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
 			return Err(EditorOperationError::NoActiveBuffer);
 		};
@@ -182,7 +331,7 @@ impl EditorState {
 		}
 		if line_wise_slot {
 			let insert_row = row_idx.saturating_add(1).min(rope_editable_line_count(&buffer.text));
-			let inserted_count = split_lines_owned(&slot_text).len() as u16;
+			let inserted_count = split_lines_owned(slot_text).len() as u16;
 			if insert_row < rope_editable_line_count(&buffer.text) {
 				let insert_at = rope_line_start_char(&buffer.text, insert_row)
 					.expect("target line start must exist while linewise pasting");
@@ -204,17 +353,33 @@ impl EditorState {
 		}
 
 		if block_wise_slot {
-			let insert_char_idx = window.cursor.col as usize;
-			let slot_lines = split_lines_owned(&slot_text);
+			// Every row takes the block at the display column right after the cursor,
+			// so tabs and wide chars keep it rectangular. Rows with text to the right
+			// get each piece padded to the block width.
+			let cursor_line = rope_line_without_newline(&buffer.text, row_idx).unwrap_or_default();
+			let target_display_col =
+				display_width_of_char_prefix_with_virtual(cursor_line.as_str(), window.cursor.col as usize) as u16;
+			let slot_lines = &content.block_rows;
+			let block_width = slot_lines.iter().map(|line| line_display_width(line)).max().unwrap_or(0);
 			let target_last_row = row_idx.saturating_add(slot_lines.len().saturating_sub(1));
 			ensure_rope_editable_rows(&mut buffer.text, target_last_row);
 
 			for (offset, slot_line) in slot_lines.iter().enumerate() {
 				let target_row = row_idx.saturating_add(offset);
+				let row = target_row.saturating_add(1) as u16;
+				expand_tab_padding_at_display_target(&mut buffer.text, row, target_display_col);
+				let insert_char_idx =
+					block_col_for_display_target(&buffer.text, row, target_display_col).saturating_sub(1) as usize;
 				pad_rope_line_to_char_len(&mut buffer.text, target_row, insert_char_idx);
+				let has_tail = rope_editable_line_len_chars(&buffer.text, target_row)
+					.is_some_and(|line_len| line_len > insert_char_idx);
+				let mut piece = slot_line.to_string();
+				if has_tail {
+					piece.push_str(" ".repeat(block_width.saturating_sub(line_display_width(slot_line))).as_str());
+				}
 				let insert_at = rope_cursor_char(&buffer.text, target_row, insert_char_idx)
 					.expect("target cursor must exist while blockwise pasting");
-				buffer.text.insert(insert_at, slot_line.as_str());
+				buffer.text.insert(insert_at, piece.as_str());
 			}
 
 			window.cursor.row = row_idx.saturating_add(1) as u16;
@@ -233,11 +398,280 @@ impl EditorState {
 		let insert_at = rope_cursor_char(&buffer.text, row_idx, insert_char_idx)
 			.expect("active cursor must exist while pasting");
 		buffer.text.insert(insert_at, slot_text.as_str());
-		window.cursor.col = window.cursor.col.saturating_add(slot_text.chars().count() as u16);
+		window.cursor.col = insert_char_idx.saturating_add(slot_text.chars().count()).max(1) as u16;
 		self.mark_active_buffer_dirty();
 		Ok(())
 	}
 
+	// `:put` always goes line-wise, even for char-wise registers; `after_row` 0
+	// puts above the first line. The cursor lands on the last new line.
+	pub fn put_register_below_row(&mut self, name: char, after_row: usize) -> Result<(), EditorOperationError> {
+		let Some(RegisterContent { text: slot_text, .. }) = self.register_content(name) else {
+			return Err(EditorOperationError::SlotEmpty);
+		};
+		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
+			return Err(EditorOperationError::NoActiveBuffer);
+		};
+		let line_count = rope_editable_line_count(&buffer.text);
+		let insert_row = after_row.min(line_count);
+		let inserted = split_lines_owned(&slot_text);
+		if insert_row < line_count {
+			let insert_at = rope_line_start_char(&buffer.text, insert_row)
+				.expect("target line start must exist while putting lines");
+			buffer.text.insert(insert_at, format!("{}\n", slot_text).as_str());
+		} else {
+			buffer.text.insert(buffer.text.len_chars(), format!("\n{}", slot_text).as_str());
+		}
+
+		let last_line = inserted.last().map(String::as_str).unwrap_or_default();
+		window.cursor.row = insert_row.saturating_add(inserted.len().max(1)) as u16;
+		window.cursor.col =
+			last_line.chars().take_while(|ch| ch.is_whitespace()).count().saturating_add(1) as u16;
+		self.mark_active_buffer_dirty();
+		Ok(())
+	}
+
+	// Rows are 0-based and inclusive; returns how many lines changed. As in Vim,
+	// turning indents into tabs leaves all-space ones alone unless `force`.
+	pub fn retab_rows(
+		&mut self,
+		start_row_idx: usize,
+		end_row_idx: usize,
+		tab_width: usize,
+		to_tabs: bool,
+		force: bool,
+	) -> Result<usize, EditorOperationError> {
+		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
+			return Err(EditorOperationError::NoActiveBuffer);
+		};
+		let end_row_idx = end_row_idx.min(rope_line_count(&buffer.text).saturating_sub(1));
+		let cursor_row_idx = window.cursor.row.saturating_sub(1) as usize;
+		let mut changed = 0;
+		for row_idx in start_row_idx..=end_row_idx {
+			let Some(line) = rope_line_without_newline(&buffer.text, row_idx) else {
+				continue;
+			};
+			if to_tabs && !force && !leading_indent(line.as_str()).contains('\t') {
+				continue;
+			}
+			let Some((indent, old_len)) = retab_indent(line.as_str(), tab_width, to_tabs) else {
+				continue;
+			};
+			let line_start =
+				rope_line_start_char(&buffer.text, row_idx).expect("retab row must exist while rewriting indent");
+			buffer.text.remove(line_start..line_start.saturating_add(old_len));
+			buffer.text.insert(line_start, indent.as_str());
+			if row_idx == cursor_row_idx {
+				let new_len = indent.chars().count();
+				let col = (window.cursor.col as usize).saturating_add(new_len).saturating_sub(old_len).max(1);
+				let line_len = rope_line_len_chars(&buffer.text, row_idx);
+				window.cursor.col = col.min(line_len.max(1)) as u16;
+			}
+			changed += 1;
+		}
+		if changed > 0 {
+			self.mark_active_buffer_dirty();
+		}
+		Ok(changed)
+	}
+
+	// Swaps the leading indent of row `row_idx` for `indent`, keeping the cursor
+	// on the same text.
+	pub fn replace_row_indent(&mut self, row_idx: usize, indent: &str) -> bool {
+		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
+			return false;
+		};
+		let (Some(line), Some(line_start)) =
+			(rope_line_without_newline(&buffer.text, row_idx), rope_line_start_char(&buffer.text, row_idx))
+		else {
+			return false;
+		};
+		let old_len = leading_indent(line.as_str()).chars().count();
+		buffer.text.remove(line_start..line_start.saturating_add(old_len));
+		buffer.text.insert(line_start, indent);
+		if window.cursor.row.saturating_sub(1) as usize == row_idx {
+			let col = (window.cursor.col as usize).saturating_add(indent.chars().count()).saturating_sub(old_len);
+			window.cursor.col = col.max(1) as u16;
+		}
+		self.mark_active_buffer_dirty();
+		true
+	}
+
+	// `chars` is a 0-based char range within row `row_idx`; the cursor lands on
+	// the first replacement char.
+	pub fn replace_row_chars(
+		&mut self,
+		row_idx: usize,
+		chars: Range<usize>,
+		replacement: &str,
+	) -> Result<(), EditorOperationError> {
+		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
+			return Err(EditorOperationError::NoActiveBuffer);
+		};
+		if chars.start > chars.end || chars.end > rope_line_len_chars(&buffer.text, row_idx) {
+			return Err(EditorOperationError::OutOfRange);
+		}
+		let line_start = rope_line_start_char(&buffer.text, row_idx).ok_or(EditorOperationError::OutOfRange)?;
+		buffer.text.remove(line_start.saturating_add(chars.start)..line_start.saturating_add(chars.end));
+		buffer.text.insert(line_start.saturating_add(chars.start), replacement);
+		window.cursor =
+			CursorState { row: row_idx.saturating_add(1) as u16, col: chars.start.saturating_add(1) as u16 };
+		self.preferred_col = None;
+		self.mark_active_buffer_dirty();
+		Ok(())
+	}
+
+	// Swaps rows `start_row_idx..=end_row_idx` with the line just below or above
+	// them; the cursor and any visual anchor follow the moved lines.
+	pub fn move_rows(
+		&mut self,
+		start_row_idx: usize,
+		end_row_idx: usize,
+		down: bool,
+	) -> Result<(), EditorOperationError> {
+		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
+			return Err(EditorOperationError::NoActiveBuffer);
+		};
+		let line_count = rope_line_count(&buffer.text);
+		if (down && end_row_idx.saturating_add(1) >= line_count) || (!down && start_row_idx == 0) {
+			return Err(EditorOperationError::OutOfRange);
+		}
+		let (first_row, last_row) =
+			if down { (start_row_idx, end_row_idx + 1) } else { (start_row_idx - 1, end_row_idx) };
+		let mut lines = (first_row..=last_row)
+			.map(|row_idx| rope_line_without_newline(&buffer.text, row_idx).unwrap_or_default())
+			.collect::<Vec<_>>();
+		if down {
+			lines.rotate_right(1);
+		} else {
+			lines.rotate_left(1);
+		}
+		let (Some(first_range), Some(last_range)) = (
+			rope_line_char_range_without_newline(&buffer.text, first_row),
+			rope_line_char_range_without_newline(&buffer.text, last_row),
+		) else {
+			return Err(EditorOperationError::OutOfRange);
+		};
+		buffer.text.remove(first_range.start..last_range.end);
+		buffer.text.insert(first_range.start, lines.join("\n").as_str());
+
+		let shift = |row: u16| if down { row.saturating_add(1) } else { row.saturating_sub(1) };
+		window.cursor.row = shift(window.cursor.row);
+		self.visual_anchor = self.visual_anchor.map(|anchor| CursorState { row: shift(anchor.row), ..anchor });
+		self.mark_active_buffer_dirty();
+		Ok(())
+	}
+
+	// Rows are 0-based and inclusive. The copies go below them without touching
+	// any register, and the cursor lands on the first copy.
+	pub fn duplicate_rows(
+		&mut self,
+		start_row_idx: usize,
+		end_row_idx: usize,
+	) -> Result<(), EditorOperationError> {
+		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
+			return Err(EditorOperationError::NoActiveBuffer);
+		};
+		let end_row_idx = end_row_idx.min(rope_line_count(&buffer.text).saturating_sub(1));
+		if start_row_idx > end_row_idx {
+			return Err(EditorOperationError::OutOfRange);
+		}
+		let copies = (start_row_idx..=end_row_idx)
+			.map(|row_idx| rope_line_without_newline(&buffer.text, row_idx).unwrap_or_default())
+			.collect::<Vec<_>>();
+		let Some(insert_at) = rope_line_char_end_without_newline(&buffer.text, end_row_idx) else {
+			return Err(EditorOperationError::OutOfRange);
+		};
+		buffer.text.insert(insert_at, format!("\n{}", copies.join("\n")).as_str());
+		let row = end_row_idx.saturating_add(2) as u16;
+		let line = rope_line_without_newline(&buffer.text, row.saturating_sub(1) as usize).unwrap_or_default();
+		window.cursor = CursorState { row, col: window.cursor.col.min(line.chars().count().max(1) as u16) };
+		self.preferred_col = None;
+		self.mark_active_buffer_dirty();
+		Ok(())
+	}
+
+	// Rows are 0-based and inclusive; `unique` then keeps one of each line.
+	// Returns how many lines were dropped.
+	pub fn sort_rows(
+		&mut self,
+		start_row_idx: usize,
+		end_row_idx: usize,
+		unique: bool,
+	) -> Result<usize, EditorOperationError> {
+		self.rewrite_rows(start_row_idx, end_row_idx, |lines| {
+			lines.sort();
+			if unique {
+				lines.dedup();
+			}
+		})
+	}
+
+	// Like `uniq`, only runs of identical neighbouring lines collapse.
+	pub fn dedup_rows(
+		&mut self,
+		start_row_idx: usize,
+		end_row_idx: usize,
+	) -> Result<usize, EditorOperationError> {
+		self.rewrite_rows(start_row_idx, end_row_idx, |lines| lines.dedup())
+	}
+
+	// Literal `:s`; returns how many matches were replaced.
+	pub fn substitute_rows(
+		&mut self,
+		start_row_idx: usize,
+		end_row_idx: usize,
+		pattern: &str,
+		replacement: &str,
+		global: bool,
+	) -> Result<usize, EditorOperationError> {
+		let mut replaced = 0;
+		self.rewrite_rows(start_row_idx, end_row_idx, |lines| {
+			for line in lines.iter_mut() {
+				let (substituted, ranges) = substitute_line(line.as_str(), pattern, replacement, global);
+				replaced += ranges.len();
+				*line = substituted;
+			}
+		})?;
+		Ok(replaced)
+	}
+
+	fn rewrite_rows(
+		&mut self,
+		start_row_idx: usize,
+		end_row_idx: usize,
+		rewrite: impl FnOnce(&mut Vec<String>),
+	) -> Result<usize, EditorOperationError> {
+		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
+			return Err(EditorOperationError::NoActiveBuffer);
+		};
+		let end_row_idx = end_row_idx.min(rope_line_count(&buffer.text).saturating_sub(1));
+		if start_row_idx > end_row_idx {
+			return Err(EditorOperationError::OutOfRange);
+		}
+		let old_lines = (start_row_idx..=end_row_idx)
+			.map(|row_idx| rope_line_without_newline(&buffer.text, row_idx).unwrap_or_default())
+			.collect::<Vec<_>>();
+		let mut lines = old_lines.clone();
+		rewrite(&mut lines);
+		let removed = old_lines.len().saturating_sub(lines.len());
+		if lines == old_lines {
+			return Ok(0);
+		}
+		let (Some(first_range), Some(last_range)) = (
+			rope_line_char_range_without_newline(&buffer.text, start_row_idx),
+			rope_line_char_range_without_newline(&buffer.text, end_row_idx),
+		) else {
+			return Err(EditorOperationError::OutOfRange);
+		};
+		buffer.text.remove(first_range.start..last_range.end);
+		buffer.text.insert(first_range.start, lines.join("\n").as_str());
+		window.cursor = CursorState { row: start_row_idx.saturating_add(1) as u16, col: 1 };
+		self.preferred_col = None;
+		self.mark_active_buffer_dirty();
+		Ok(removed)
+	}
+
 	pub fn delete_current_line_to_slot(&mut self) -> Result<(), EditorOperationError> {
 		let Some((buffer, window)) = active_buffer_and_window_mut(self) else {
 			return Err(EditorOperationError::NoActiveBuffer);
@@ -252,21 +686,8 @@ impl EditorState {
 			return Err(EditorOperationError::OutOfRange);
 		};
 		let deleted = buffer.text.slice(line_range.clone()).to_string();
-		let line_count = rope_editable_line_count(&buffer.text);
-		let is_trailing_blank_line =
-			rope_ends_with_newline(&buffer.text) && row_idx + 1 == line_count && line_range.is_empty();
-		let delete_range = if line_count == 1 {
-			0..buffer.text.len_chars()
-		} else if row_idx + 1 < line_count {
-			let next_start = rope_line_start_char(&buffer.text, row_idx.saturating_add(1))
-				.expect("next line start must exist while deleting middle line");
-			line_range.start..next_start
-		} else if is_trailing_blank_line {
-			line_range.start.saturating_sub(1)..buffer.text.len_chars()
-		} else if rope_ends_with_newline(&buffer.text) {
-			line_range.start..buffer.text.len_chars()
-		} else {
-			line_range.start.saturating_sub(1)..buffer.text.len_chars()
+		let Some(delete_range) = rope_linewise_delete_range(&buffer.text, row_idx, row_idx) else {
+			return Err(EditorOperationError::OutOfRange);
 		};
 		buffer.text.remove(delete_range);
 		let visible_rows = rope_line_count(&buffer.text);
@@ -274,9 +695,12 @@ impl EditorState {
 		window.cursor.row = new_row;
 		window.cursor.col = 1;
 		self.mark_active_buffer_dirty();
-		self.line_slot = Some(deleted);
-		self.line_slot_line_wise = true;
-		self.line_slot_block_wise = false;
+		self.store_delete(RegisterContent {
+			text:       deleted,
+			line_wise:  true,
+			block_wise: false,
+			block_rows: Vec::new(),
+		});
 		Ok(())
 	}
 
@@ -289,17 +713,16 @@ impl EditorState {
 			let Some(buffer) = self.buffers.get_mut(buffer_id) else {
 				return Err(EditorOperationError::ActiveBufferMissing);
 			};
-			let Some(previous_entry) = buffer.undo_stack.pop() else {
+			let Some(previous_entry) = buffer.undo_stack.pop_back() else {
 				return Err(EditorOperationError::NothingToUndo);
 			};
 			let before_cursor = previous_entry.before_cursor;
 			for edit in previous_entry.edits.iter().rev() {
 				apply_text_delta_undo(&mut buffer.text, edit);
 			}
-			buffer.redo_stack.push(previous_entry);
-			if buffer.redo_stack.len() > Self::MAX_HISTORY_ENTRIES {
-				buffer.redo_stack.remove(0);
-			}
+			buffer.bump_changedtick();
+			buffer.redo_stack.push_back(previous_entry);
+			trim_history_front(&mut buffer.redo_stack, self.undo_levels);
 			buffer.dirty = buffer.text != buffer.clean_text;
 			before_cursor
 		};
@@ -307,9 +730,23 @@ impl EditorState {
 			window.cursor = before_cursor;
 		}
 		self.sync_window_view_binding(active_window_id);
+		self.preferred_col = None;
 		Ok(())
 	}
 
+	// Each entry moves to the redo stack in turn, so redo steps forward again one
+	// change at a time.
+	pub fn undo_all_active_buffer_edits(&mut self) -> Result<usize, EditorOperationError> {
+		let mut undone = 0usize;
+		loop {
+			match self.undo_active_buffer_edit() {
+				Ok(()) => undone = undone.saturating_add(1),
+				Err(EditorOperationError::NothingToUndo) if undone > 0 => return Ok(undone),
+				Err(err) => return Err(err),
+			}
+		}
+	}
+
 	pub fn redo_active_buffer_edit(&mut self) -> Result<(), EditorOperationError> {
 		let Some(buffer_id) = self.active_buffer_id() else {
 			return Err(EditorOperationError::NoActiveBuffer);
@@ -319,17 +756,16 @@ impl EditorState {
 			let Some(buffer) = self.buffers.get_mut(buffer_id) else {
 				return Err(EditorOperationError::ActiveBufferMissing);
 			};
-			let Some(next_entry) = buffer.redo_stack.pop() else {
+			let Some(next_entry) = buffer.redo_stack.pop_back() else {
 				return Err(EditorOperationError::NothingToRedo);
 			};
 			let after_cursor = next_entry.after_cursor;
 			for edit in &next_entry.edits {
 				apply_text_delta_redo(&mut buffer.text, edit);
 			}
-			buffer.undo_stack.push(next_entry);
-			if buffer.undo_stack.len() > Self::MAX_HISTORY_ENTRIES {
-				buffer.undo_stack.remove(0);
-			}
+			buffer.bump_changedtick();
+			buffer.undo_stack.push_back(next_entry);
+			trim_history_front(&mut buffer.undo_stack, self.undo_levels);
 			buffer.dirty = buffer.text != buffer.clean_text;
 			after_cursor
 		};
@@ -337,6 +773,7 @@ impl EditorState {
 			window.cursor = after_cursor;
 		}
 		self.sync_window_view_binding(active_window_id);
+		self.preferred_col = None;
 		Ok(())
 	}
 }
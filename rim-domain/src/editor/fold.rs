@@ -0,0 +1,265 @@
+use std::cmp::Reverse;
+
+use ropey::Rope;
+
+use crate::{display_geometry::{TAB_DISPLAY_WIDTH, wrapped_line_rows}, editor::{EditorOperationError, EditorState}, indent::indent_fold_ranges, model::{BufferId, CursorState, Fold, RopeTextDiff}, text::{rope_ends_with_newline, rope_line_count, rope_line_without_newline}};
+
+impl EditorState {
+	// `zf` leaves the new fold closed with the cursor on its first row.
+	pub fn create_fold(&mut self, start_row: u16, end_row: u16) -> Result<(), EditorOperationError> {
+		let buffer_id = self.active_buffer_id().ok_or(EditorOperationError::NoActiveBuffer)?;
+		let buffer = self.buffers.get_mut(buffer_id).ok_or(EditorOperationError::ActiveBufferMissing)?;
+		let (start_row, end_row) = (start_row.min(end_row).max(1), start_row.max(end_row));
+		if end_row > rope_line_count(&buffer.text) as u16 {
+			return Err(EditorOperationError::OutOfRange);
+		}
+		let partially_overlaps = |fold: &Fold| {
+			let disjoint = fold.end_row < start_row || fold.start_row > end_row;
+			let nested = (fold.start_row <= start_row && end_row <= fold.end_row)
+				|| (start_row <= fold.start_row && fold.end_row <= end_row);
+			!disjoint && !nested
+		};
+		if buffer.folds.iter().any(partially_overlaps) {
+			return Err(EditorOperationError::OutOfRange);
+		}
+		match buffer.folds.iter_mut().find(|fold| fold.start_row == start_row && fold.end_row == end_row) {
+			Some(fold) => fold.closed = true,
+			None => {
+				buffer.folds.push(Fold { start_row, end_row, closed: true });
+				buffer.folds.sort_by_key(|fold| (fold.start_row, Reverse(fold.end_row)));
+			}
+		}
+		self.move_cursor_to(CursorState { row: start_row, col: 1 });
+		Ok(())
+	}
+
+	// Opens the outermost closed fold under the cursor, one level like `zo`.
+	pub fn open_fold_at_cursor(&mut self) -> bool {
+		let row = self.active_cursor().row;
+		let Some(fold) = self.active_buffer_folds_mut().and_then(|folds| {
+			folds.iter_mut().find(|fold| fold.closed && fold.start_row <= row && row <= fold.end_row)
+		}) else {
+			return false;
+		};
+		fold.closed = false;
+		true
+	}
+
+	// Closes the innermost open fold under the cursor. The cursor moves to the
+	// first row of whatever closed fold now holds it.
+	pub fn close_fold_at_cursor(&mut self) -> bool {
+		let row = self.active_cursor().row;
+		let Some(fold) = self.active_buffer_folds_mut().and_then(|folds| {
+			folds.iter_mut().rev().find(|fold| !fold.closed && fold.start_row <= row && row <= fold.end_row)
+		}) else {
+			return self.active_buffer_closed_fold_at(row).is_some();
+		};
+		fold.closed = true;
+		if let Some(fold) = self.active_buffer_closed_fold_at(row) {
+			self.move_cursor_to(CursorState { row: fold.start_row, col: 1 });
+		}
+		true
+	}
+
+	// `za`: opens a closed fold under the cursor, otherwise closes one.
+	pub fn toggle_fold_at_cursor(&mut self) -> bool {
+		if self.active_buffer_closed_fold_at(self.active_cursor().row).is_some() {
+			return self.open_fold_at_cursor();
+		}
+		self.close_fold_at_cursor()
+	}
+
+	// `zR` and `zM`. Closing everything brings the cursor to the start of the
+	// fold that now hides it.
+	pub fn set_all_folds_closed(&mut self, closed: bool) -> bool {
+		let Some(folds) = self.active_buffer_folds_mut().filter(|folds| !folds.is_empty()) else {
+			return false;
+		};
+		folds.iter_mut().for_each(|fold| fold.closed = closed);
+		if let Some(fold) = self.active_buffer_closed_fold_at(self.active_cursor().row) {
+			self.move_cursor_to(CursorState { row: fold.start_row, col: 1 });
+		}
+		true
+	}
+
+	// `foldmethod=indent` replaces the folds of a buffer with those its indent
+	// gives. A fold stays closed when the one at the same start and depth was.
+	pub fn recompute_buffer_indent_folds(&mut self, buffer_id: BufferId, shift_width: usize) {
+		let Some(buffer) = self.buffers.get_mut(buffer_id) else {
+			return;
+		};
+		let lines = (0..rope_line_count(&buffer.text))
+			.map(|row_idx| rope_line_without_newline(&buffer.text, row_idx).unwrap_or_default())
+			.collect::<Vec<_>>();
+		let depth_at_start = |folds: &[Fold], idx: usize| {
+			folds[..idx].iter().filter(|fold| fold.start_row == folds[idx].start_row).count()
+		};
+		let previous = std::mem::take(&mut buffer.folds);
+		let mut folds = indent_fold_ranges(&lines, shift_width, TAB_DISPLAY_WIDTH)
+			.into_iter()
+			.map(|(start_row, end_row)| Fold {
+				start_row: start_row as u16,
+				end_row:   end_row as u16,
+				closed:    false,
+			})
+			.collect::<Vec<_>>();
+		for idx in 0..folds.len() {
+			let depth = depth_at_start(&folds, idx);
+			folds[idx].closed = (0..previous.len()).any(|old_idx| {
+				previous[old_idx].closed
+					&& previous[old_idx].start_row == folds[idx].start_row
+					&& depth_at_start(&previous, old_idx) == depth
+			});
+		}
+		buffer.folds = folds;
+	}
+
+	// Only the outermost closed fold matters: everything inside it is hidden.
+	pub fn buffer_closed_fold_at(&self, buffer_id: BufferId, row: u16) -> Option<Fold> {
+		let buffer = self.buffers.get(buffer_id)?;
+		buffer.folds.iter().find(|fold| fold.closed && fold.start_row <= row && row <= fold.end_row).copied()
+	}
+
+	pub fn active_buffer_closed_fold_at(&self, row: u16) -> Option<Fold> {
+		self.buffer_closed_fold_at(self.active_buffer_id()?, row)
+	}
+
+	// Zero-based row index shown on each display line, counting a closed fold as
+	// one line, so views and scrolling can work in display lines.
+	pub fn buffer_fold_display_line(&self, buffer_id: BufferId, row_index: usize) -> usize {
+		let hidden = self
+			.buffer_closed_fold_spans(buffer_id)
+			.into_iter()
+			.filter(|(start, _)| *start < row_index)
+			.map(|(start, end)| end.min(row_index).saturating_sub(start))
+			.sum::<usize>();
+		row_index.saturating_sub(hidden)
+	}
+
+	pub fn buffer_fold_row_index(&self, buffer_id: BufferId, display_line: usize) -> usize {
+		let mut row_index = display_line;
+		for (start, end) in self.buffer_closed_fold_spans(buffer_id) {
+			if start >= row_index {
+				break;
+			}
+			row_index = row_index.saturating_add(end.saturating_sub(start));
+		}
+		row_index
+	}
+
+	// Wrapped rows that closed folds ending above `row_index` take out of view,
+	// less the one summary row each of them keeps.
+	pub fn buffer_fold_hidden_wrapped_rows(
+		&self,
+		buffer_id: BufferId,
+		row_index: usize,
+		width: usize,
+	) -> usize {
+		let Some(buffer) = self.buffers.get(buffer_id) else {
+			return 0;
+		};
+		let lines = rope_line_count(&buffer.text);
+		self
+			.buffer_closed_fold_spans(buffer_id)
+			.into_iter()
+			.filter(|(_, end)| *end < row_index)
+			.map(|(start, end)| {
+				(start..=end.min(lines.saturating_sub(1)))
+					.map(|row_idx| {
+						let has_newline =
+							row_idx + 1 < lines || (row_idx + 1 == lines && rope_ends_with_newline(&buffer.text));
+						let line = rope_line_without_newline(&buffer.text, row_idx).unwrap_or_default();
+						wrapped_line_rows(line.as_str(), has_newline, width) as usize
+					})
+					.sum::<usize>()
+					.saturating_sub(1)
+			})
+			.sum()
+	}
+
+	// Keeps folds and changelist positions on the same text after `diff` turned
+	// `before` into the current text. Folds whose every row was deleted go away;
+	// changelist positions in deleted rows move to where they were.
+	pub fn adjust_buffer_positions_for_edit(
+		&mut self,
+		buffer_id: BufferId,
+		before: &Rope,
+		diff: &RopeTextDiff,
+	) {
+		let Some(buffer) = self.buffers.get_mut(buffer_id) else {
+			return;
+		};
+		if buffer.folds.is_empty() && buffer.change_list.is_empty() {
+			return;
+		}
+		let first_row_index = before.char_to_line(diff.start_char.min(before.len_chars()));
+		let deleted_lines = diff.deleted_text.matches('\n').count();
+		let inserted_lines = diff.inserted_text.matches('\n').count();
+		let first_row = first_row_index.saturating_add(1);
+		let last_deleted_row = first_row.saturating_add(deleted_lines);
+		let starts_at_line = before.line_to_char(first_row_index) == diff.start_char;
+		let whole_lines_deleted =
+			starts_at_line && diff.deleted_text.ends_with('\n') && diff.inserted_text.is_empty();
+		// Lines put above a fold push it down instead of joining its first row.
+		let lines_inserted_above =
+			starts_at_line && diff.deleted_text.is_empty() && diff.inserted_text.ends_with('\n');
+		let map_row = |row: usize| {
+			if row < first_row || (row == first_row && !lines_inserted_above) {
+				row
+			} else if row > last_deleted_row || lines_inserted_above {
+				row.saturating_add(inserted_lines).saturating_sub(deleted_lines)
+			} else {
+				first_row
+			}
+		};
+		buffer.folds.retain_mut(|fold| {
+			let (start, end) = (fold.start_row as usize, fold.end_row as usize);
+			if whole_lines_deleted && first_row <= start && end < last_deleted_row {
+				return false;
+			}
+			fold.start_row = map_row(start) as u16;
+			fold.end_row = map_row(end) as u16;
+			true
+		});
+		let max_row = rope_line_count(&buffer.text).max(1) as u16;
+		for position in &mut buffer.change_list {
+			position.row = (map_row(position.row as usize) as u16).clamp(1, max_row);
+		}
+		self.clamp_buffer_folds(buffer_id);
+	}
+
+	pub fn clamp_buffer_folds(&mut self, buffer_id: BufferId) {
+		let Some(buffer) = self.buffers.get_mut(buffer_id) else {
+			return;
+		};
+		let max_row = rope_line_count(&buffer.text) as u16;
+		buffer.folds.retain_mut(|fold| {
+			fold.end_row = fold.end_row.min(max_row);
+			fold.start_row <= fold.end_row
+		});
+		buffer
+			.folds
+			.dedup_by(|next, previous| next.start_row == previous.start_row && next.end_row == previous.end_row);
+	}
+
+	// Zero-based, end-inclusive row spans of the outermost closed folds.
+	fn buffer_closed_fold_spans(&self, buffer_id: BufferId) -> Vec<(usize, usize)> {
+		let Some(buffer) = self.buffers.get(buffer_id) else {
+			return Vec::new();
+		};
+		let mut spans: Vec<(usize, usize)> = Vec::new();
+		for fold in buffer.folds.iter().filter(|fold| fold.closed) {
+			let span = (fold.start_row.saturating_sub(1) as usize, fold.end_row.saturating_sub(1) as usize);
+			if spans.last().is_some_and(|last| span.1 <= last.1) {
+				continue;
+			}
+			spans.push(span);
+		}
+		spans
+	}
+
+	fn active_buffer_folds_mut(&mut self) -> Option<&mut Vec<Fold>> {
+		let buffer_id = self.active_buffer_id()?;
+		self.buffers.get_mut(buffer_id).map(|buffer| &mut buffer.folds)
+	}
+}
@@ -1,4 +1,4 @@
-use crate::{display_geometry::{char_display_width, display_width_of_char_prefix_with_virtual, navigable_col_for_display_target as geom_navigable_col_for_display_target}, editor::EditorState, text::{rope_ends_with_newline, rope_is_empty, rope_line_count, rope_line_len_chars, rope_line_without_newline}};
+use crate::{display_geometry::{display_width_of_char_prefix_with_virtual, grapheme_start_cols, line_display_width, navigable_col_for_display_target as geom_navigable_col_for_display_target}, edit::pad_rope_line_to_char_len, editor::EditorState, model::{EditorMode, VirtualCursor, VirtualEdit}, motion::{Motion, char_index_to_cursor, cursor_to_char_index, motion_target}, text::{clamp_cursor_for_rope, rope_ends_with_newline, rope_is_empty, rope_line_count, rope_line_len_chars, rope_line_without_newline}};
 
 impl EditorState {
 	pub fn active_cursor(&self) -> crate::model::CursorState {
@@ -7,57 +7,99 @@ impl EditorState {
 
 	pub fn move_cursor_up(&mut self) {
 		let target_display_col = self.target_display_col_for_vertical_move();
-		if let Some(cursor) = self.active_buffer_cursor_mut()
-			&& cursor.row > 1
-		{
-			cursor.row = cursor.row.saturating_sub(1);
+		// A closed fold is stepped over as one line, landing on its first row.
+		let from = self.active_cursor().row;
+		let from = self.active_buffer_closed_fold_at(from).map_or(from, |fold| fold.start_row);
+		if from > 1 {
+			let target = from.saturating_sub(1);
+			let target = self.active_buffer_closed_fold_at(target).map_or(target, |fold| fold.start_row);
+			if let Some(cursor) = self.active_buffer_cursor_mut() {
+				cursor.row = target;
+			}
 		}
 		let row = self.active_cursor().row;
 		let target_col = if self.is_visual_block_mode() || self.is_block_insert_mode() {
-			self.visual_block_col_for_display_target(row, target_display_col)
+			self.clamp_visual_block_display_col(row, target_display_col)
+		} else if self.mode == crate::model::EditorMode::VisualChar {
+			self.visual_char_col_for_display_target(row, target_display_col)
 		} else {
 			self.navigable_col_for_display_target(row, target_display_col)
 		};
 		if let Some(cursor) = self.active_buffer_cursor_mut() {
 			cursor.col = target_col;
 		}
+		self.follow_virtual_col_after_vertical_move(target_display_col);
 	}
 
 	pub fn move_cursor_down(&mut self) {
 		let target_display_col = self.target_display_col_for_vertical_move();
 		let max_row = self.max_row();
-		if let Some(cursor) = self.active_buffer_cursor_mut()
-			&& cursor.row < max_row
-		{
-			cursor.row = cursor.row.saturating_add(1);
+		let from = self.active_cursor().row;
+		let from = self.active_buffer_closed_fold_at(from).map_or(from, |fold| fold.end_row);
+		if from < max_row {
+			let target = from.saturating_add(1);
+			let target = self.active_buffer_closed_fold_at(target).map_or(target, |fold| fold.start_row);
+			if let Some(cursor) = self.active_buffer_cursor_mut() {
+				cursor.row = target;
+			}
 		}
 		let row = self.active_cursor().row;
 		let target_col = if self.is_visual_block_mode() || self.is_block_insert_mode() {
-			self.visual_block_col_for_display_target(row, target_display_col)
+			self.clamp_visual_block_display_col(row, target_display_col)
+		} else if self.mode == crate::model::EditorMode::VisualChar {
+			self.visual_char_col_for_display_target(row, target_display_col)
 		} else {
 			self.navigable_col_for_display_target(row, target_display_col)
 		};
 		if let Some(cursor) = self.active_buffer_cursor_mut() {
 			cursor.col = target_col;
 		}
+		self.follow_virtual_col_after_vertical_move(target_display_col);
 	}
 
 	pub fn move_cursor_left(&mut self) {
-		if let Some(cursor) = self.active_buffer_cursor_mut()
-			&& cursor.col > 1
-		{
-			cursor.col = cursor.col.saturating_sub(1);
+		if let Some(display_col) = self.active_virtual_display_col() {
+			// Stepping back onto the line leaves the cursor on its last char.
+			let next_display_col = display_col.saturating_sub(1);
+			let row = self.active_cursor().row;
+			let on_line =
+				next_display_col < self.row_display_width(row) || next_display_col <= self.real_cursor_display_col();
+			self.set_virtual_display_col((!on_line).then_some(next_display_col));
+			self.preferred_col = None;
+			return;
+		}
+		let cursor = self.active_cursor();
+		let target_col = self
+			.active_row_grapheme_start_cols(cursor.row)
+			.into_iter()
+			.rfind(|col| *col < cursor.col)
+			.unwrap_or_else(|| cursor.col.saturating_sub(1).max(1));
+		if let Some(cursor) = self.active_buffer_cursor_mut() {
+			cursor.col = target_col;
 		}
 		self.preferred_col = None;
 	}
 
 	pub fn move_cursor_right(&mut self) {
-		let row = self.active_cursor().row;
-		let max_col = self.max_navigable_col_for_row(row);
-		if let Some(cursor) = self.active_buffer_cursor_mut()
-			&& cursor.col < max_col
+		let cursor = self.active_cursor();
+		let max_col = self.max_navigable_col_for_row(cursor.row);
+		// Step over a whole cluster so the cursor never lands inside a flag or
+		// ZWJ emoji sequence.
+		let target_col = self
+			.active_row_grapheme_start_cols(cursor.row)
+			.into_iter()
+			.find(|col| *col > cursor.col && *col <= max_col);
+		if let Some(target_col) = target_col
+			&& let Some(cursor) = self.active_buffer_cursor_mut()
 		{
-			cursor.col = cursor.col.saturating_add(1);
+			cursor.col = target_col;
+		} else if target_col.is_none() && self.virtual_edit == VirtualEdit::All && self.mode == EditorMode::Normal
+		{
+			let next_display_col = match self.active_virtual_display_col() {
+				Some(display_col) => display_col.saturating_add(1),
+				None => self.row_display_width(cursor.row).max(self.real_cursor_display_col().saturating_add(1)),
+			};
+			self.set_virtual_display_col(Some(next_display_col));
 		}
 		self.preferred_col = None;
 	}
@@ -77,10 +119,14 @@ impl EditorState {
 			}
 			return;
 		}
-		if let Some(cursor) = self.active_buffer_cursor_mut()
-			&& cursor.col > 1
-		{
-			cursor.col = cursor.col.saturating_sub(1);
+		let cursor = self.active_cursor();
+		let target_col = self
+			.active_row_grapheme_start_cols(cursor.row)
+			.into_iter()
+			.rfind(|col| *col < cursor.col)
+			.unwrap_or_else(|| cursor.col.saturating_sub(1).max(1));
+		if let Some(cursor) = self.active_buffer_cursor_mut() {
+			cursor.col = target_col;
 		}
 		self.preferred_col = None;
 	}
@@ -91,9 +137,12 @@ impl EditorState {
 				.visual_block_cursor_display_col
 				.unwrap_or_else(|| self.active_cursor_display_col())
 				.saturating_add(1);
+			let row = self.active_cursor().row;
+			if self.virtual_edit == VirtualEdit::None && next_display_col > self.last_char_display_col(row) {
+				return;
+			}
 			self.visual_block_cursor_display_col = Some(next_display_col);
 			self.preferred_col = Some(next_display_col);
-			let row = self.active_cursor().row;
 			let target_col = self.visual_block_col_for_display_target(row, next_display_col);
 			if let Some(cursor) = self.active_buffer_cursor_mut() {
 				cursor.col = target_col;
@@ -102,12 +151,22 @@ impl EditorState {
 		}
 		let row = self.active_cursor().row;
 		let max_col = self.max_visual_char_col_for_row(row);
+		let cursor_col = self.active_cursor().col;
+		// The newline slot after the last cluster is not a cluster start of its own.
+		let target_col = self
+			.active_row_grapheme_start_cols(row)
+			.into_iter()
+			.find(|col| *col > cursor_col)
+			.unwrap_or(self.max_col_for_row(row))
+			.min(max_col);
 		if let Some(cursor) = self.active_buffer_cursor_mut()
-			&& cursor.col < max_col
+			&& cursor.col < target_col
 		{
-			cursor.col = cursor.col.saturating_add(1);
+			cursor.col = target_col;
 		}
-		self.preferred_col = None;
+		let on_newline_slot =
+			self.row_has_newline_char(row) && self.active_cursor().col > self.max_navigable_col_for_row(row);
+		self.preferred_col = on_newline_slot.then_some(u16::MAX);
 	}
 
 	pub fn move_cursor_line_start(&mut self) {
@@ -115,15 +174,21 @@ impl EditorState {
 			cursor.col = 1;
 		}
 		self.preferred_col = None;
+		self.virtual_cursor = None;
 	}
 
 	pub fn move_cursor_line_end(&mut self) {
 		let row = self.active_cursor().row;
-		let max_col = self.max_navigable_col_for_row(row);
+		let max_col = self
+			.active_row_grapheme_start_cols(row)
+			.into_iter()
+			.rfind(|col| *col <= self.max_navigable_col_for_row(row))
+			.unwrap_or_else(|| self.max_navigable_col_for_row(row));
 		if let Some(cursor) = self.active_buffer_cursor_mut() {
 			cursor.col = max_col;
 		}
 		self.preferred_col = None;
+		self.virtual_cursor = None;
 	}
 
 	pub fn move_cursor_file_start(&mut self) {
@@ -149,6 +214,84 @@ impl EditorState {
 		}
 	}
 
+	// Returns whether the jump wrapped around the file, or None without changes.
+	pub fn move_cursor_to_change_hunk(&mut self, forward: bool) -> Option<bool> {
+		let buffer_id = self.active_buffer_id()?;
+		let hunk_starts = self.buffer_change_hunk_starts(buffer_id);
+		let current_row = self.active_cursor().row.saturating_sub(1) as usize;
+		let (target_row, wrapped) = if forward {
+			match hunk_starts.iter().find(|row| **row > current_row) {
+				Some(row) => (*row, false),
+				None => (*hunk_starts.first()?, true),
+			}
+		} else {
+			match hunk_starts.iter().rev().find(|row| **row < current_row) {
+				Some(row) => (*row, false),
+				None => (*hunk_starts.last()?, true),
+			}
+		};
+		if let Some(cursor) = self.active_buffer_cursor_mut() {
+			cursor.row = target_row.saturating_add(1) as u16;
+			cursor.col = 1;
+		}
+		self.preferred_col = None;
+		Some(wrapped)
+	}
+
+	// Moves `count` entries through the changelist, stopping at its ends. Returns
+	// whether the cursor moved, or None while the buffer has no changes.
+	pub fn move_cursor_in_change_list(&mut self, older: bool, count: usize) -> Option<bool> {
+		let buffer_id = self.active_buffer_id()?;
+		let buffer = self.buffers.get_mut(buffer_id)?;
+		let len = buffer.change_list.len();
+		if len == 0 {
+			return None;
+		}
+		let index = buffer.change_list_index.min(len);
+		let count = count.max(1);
+		let target_index = if older {
+			if index == 0 {
+				return Some(false);
+			}
+			index.saturating_sub(count)
+		} else {
+			if index.saturating_add(1) >= len {
+				return Some(false);
+			}
+			index.saturating_add(count).min(len - 1)
+		};
+		buffer.change_list_index = target_index;
+		let target = buffer.change_list[target_index];
+		self.move_cursor_to(target);
+		Some(true)
+	}
+
+	pub fn move_cursor_to(&mut self, target: crate::model::CursorState) {
+		let Some(target) = self
+			.active_buffer_id()
+			.and_then(|buffer_id| self.buffers.get(buffer_id))
+			.map(|buffer| clamp_cursor_for_rope(&buffer.text, target))
+		else {
+			return;
+		};
+		if let Some(cursor) = self.active_buffer_cursor_mut() {
+			*cursor = target;
+		}
+		self.preferred_col = None;
+		self.virtual_cursor = None;
+		self.clamp_cursor_to_navigable_col();
+	}
+
+	pub fn move_cursor_by_motion(&mut self, motion: Motion) {
+		let Some(target) = self.active_buffer_rope().map(|text| {
+			let from = cursor_to_char_index(text, self.active_cursor());
+			char_index_to_cursor(text, motion_target(text, from, motion))
+		}) else {
+			return;
+		};
+		self.move_cursor_to(target);
+	}
+
 	pub fn move_cursor_right_for_insert(&mut self) {
 		let row = self.active_cursor().row;
 		let max_col = self.max_col_for_row(row);
@@ -172,14 +315,22 @@ impl EditorState {
 	pub fn clamp_cursor_to_navigable_col(&mut self) {
 		let row = self.active_cursor().row;
 		let max_col = self.max_navigable_col_for_row(row);
+		let col = self.active_cursor().col.min(max_col).max(1);
+		let col =
+			self.active_row_grapheme_start_cols(row).into_iter().rfind(|start| *start <= col).unwrap_or(col);
 		if let Some(cursor) = self.active_buffer_cursor_mut() {
-			cursor.col = cursor.col.min(max_col).max(1);
+			cursor.col = col;
 		}
 	}
 
 	pub fn target_display_col_for_vertical_move(&mut self) -> u16 {
 		if self.is_visual_block_mode() {
-			let col = self.visual_block_cursor_display_col.unwrap_or_else(|| self.active_cursor_display_col());
+			// Without virtual space the shown column is clamped to short lines, so
+			// the wanted one is kept in `preferred_col` instead.
+			let col = match (self.virtual_edit, self.preferred_col) {
+				(VirtualEdit::None, Some(col)) => col,
+				_ => self.visual_block_cursor_display_col.unwrap_or_else(|| self.active_cursor_display_col()),
+			};
 			self.preferred_col = Some(col);
 			return col;
 		}
@@ -215,6 +366,75 @@ impl EditorState {
 		if let Some(block_insert) = self.pending_block_insert {
 			return block_insert.cursor_display_col;
 		}
+		if let Some(display_col) = self.active_virtual_display_col() {
+			return display_col;
+		}
+		self.real_cursor_display_col()
+	}
+
+	pub fn active_virtual_display_col(&self) -> Option<u16> {
+		let virtual_cursor = self.virtual_cursor?;
+		let buffer_id = self.active_buffer_id()?;
+		let current = self.mode == EditorMode::Normal
+			&& virtual_cursor.window_id == self.active_window_id()
+			&& virtual_cursor.buffer_id == buffer_id
+			&& virtual_cursor.cursor == self.active_cursor()
+			&& self.buffer_changedtick(buffer_id) == Some(virtual_cursor.changedtick);
+		current.then_some(virtual_cursor.display_col)
+	}
+
+	// Entering insert mode from virtual space pads the line with spaces up to
+	// the cursor, or past it for an append, and puts the cursor after them.
+	pub fn fill_virtual_space_for_insert(&mut self, append: bool) -> bool {
+		let Some(display_col) = self.active_virtual_display_col() else {
+			return false;
+		};
+		let row = self.active_cursor().row;
+		let row_idx = row.saturating_sub(1) as usize;
+		let pad =
+			usize::from(display_col.saturating_add(u16::from(append)).saturating_sub(self.row_display_width(row)));
+		let Some((buffer_id, line_len)) =
+			self.active_buffer_id().zip(self.active_buffer_rope().map(|text| rope_line_len_chars(text, row_idx)))
+		else {
+			return false;
+		};
+		if let Some(buffer) = self.buffers.get_mut(buffer_id) {
+			pad_rope_line_to_char_len(&mut buffer.text, row_idx, line_len.saturating_add(pad));
+		}
+		if let Some(cursor) = self.active_buffer_cursor_mut() {
+			cursor.col = line_len.saturating_add(pad).saturating_add(1) as u16;
+		}
+		self.virtual_cursor = None;
+		self.mark_active_buffer_dirty();
+		true
+	}
+
+	fn set_virtual_display_col(&mut self, display_col: Option<u16>) {
+		self.virtual_cursor = display_col.and_then(|display_col| {
+			let buffer_id = self.active_buffer_id()?;
+			Some(VirtualCursor {
+				window_id: self.active_window_id(),
+				buffer_id,
+				changedtick: self.buffer_changedtick(buffer_id)?,
+				cursor: self.active_cursor(),
+				display_col,
+			})
+		});
+	}
+
+	// With `virtualedit=all` a vertical move onto a line too short for the
+	// wanted column parks the cursor in the virtual space past its end.
+	fn follow_virtual_col_after_vertical_move(&mut self, target_display_col: u16) {
+		if self.virtual_edit != VirtualEdit::All || self.mode != EditorMode::Normal {
+			return;
+		}
+		let row = self.active_cursor().row;
+		let past_end = target_display_col >= self.row_display_width(row)
+			&& target_display_col > self.real_cursor_display_col();
+		self.set_virtual_display_col(past_end.then_some(target_display_col));
+	}
+
+	fn real_cursor_display_col(&self) -> u16 {
 		let cursor = self.active_cursor();
 		let row_index = cursor.row.saturating_sub(1) as usize;
 		let char_index = cursor.col.saturating_sub(1) as usize;
@@ -225,16 +445,43 @@ impl EditorState {
 			.unwrap_or(0)
 	}
 
+	fn row_display_width(&self, row: u16) -> u16 {
+		self
+			.active_buffer_rope()
+			.and_then(|text| rope_line_without_newline(text, row.saturating_sub(1) as usize))
+			.map(|line| line_display_width(line.as_str()) as u16)
+			.unwrap_or(0)
+	}
+
+	fn last_char_display_col(&self, row: u16) -> u16 {
+		let col = self.max_navigable_col_for_row(row);
+		self.active_buffer_rope().map_or(0, |text| crate::edit::cursor_slot_display_col(text, row, col))
+	}
+
+	// Without virtual space a block's cursor stops at the last char of a short
+	// line; the block keeps the column it shows.
+	fn clamp_visual_block_display_col(&mut self, row: u16, target_display_col: u16) -> u16 {
+		if self.virtual_edit == VirtualEdit::None && self.is_visual_block_mode() {
+			let display_col = target_display_col.min(self.last_char_display_col(row));
+			self.visual_block_cursor_display_col = Some(display_col);
+			return self.visual_block_col_for_display_target(row, display_col);
+		}
+		self.visual_block_col_for_display_target(row, target_display_col)
+	}
+
 	pub fn active_line_display_width(&self) -> u16 {
 		let cursor = self.active_cursor();
 		let row_index = cursor.row.saturating_sub(1) as usize;
 		let base_width = self
 			.active_buffer_rope()
 			.and_then(|text| rope_line_without_newline(text, row_index))
-			.map(|line| line.chars().map(|ch| char_display_width(ch) as u16).sum())
+			.map(|line| line_display_width(line.as_str()) as u16)
 			.unwrap_or(0);
 
-		if self.is_visual_block_mode() || self.is_block_insert_mode() {
+		if self.is_visual_block_mode()
+			|| self.is_block_insert_mode()
+			|| self.active_virtual_display_col().is_some()
+		{
 			base_width.max(self.active_cursor_display_col())
 		} else {
 			base_width
@@ -249,6 +496,16 @@ impl EditorState {
 		col.min(self.max_navigable_col_for_row(row)).max(1)
 	}
 
+	// Like Vim's curswant after `$`, a selection parked on the newline slot keeps
+	// tracking line ends; any other column clamps to the last char.
+	fn visual_char_col_for_display_target(&self, row: u16, target_display_col: u16) -> u16 {
+		if target_display_col == u16::MAX && self.row_has_newline_char(row) {
+			self.max_visual_char_col_for_row(row)
+		} else {
+			self.navigable_col_for_display_target(row, target_display_col)
+		}
+	}
+
 	pub fn visual_block_col_for_display_target(&self, row: u16, target_display_col: u16) -> u16 {
 		crate::edit::block_col_for_display_target(
 			self.active_buffer_rope().unwrap_or(&ropey::Rope::new()),
@@ -262,6 +519,14 @@ impl EditorState {
 		self.windows.get_mut(active_window_id).map(|window| &mut window.cursor)
 	}
 
+	fn active_row_grapheme_start_cols(&self, row: u16) -> Vec<u16> {
+		self
+			.active_buffer_rope()
+			.and_then(|text| rope_line_without_newline(text, row.saturating_sub(1) as usize))
+			.map(|line| grapheme_start_cols(line.as_str()))
+			.unwrap_or_default()
+	}
+
 	fn max_navigable_col_for_row(&self, row: u16) -> u16 { self.max_col_for_row(row).saturating_sub(1).max(1) }
 
 	fn max_visual_char_col_for_row(&self, row: u16) -> u16 {
@@ -0,0 +1,115 @@
+use crate::{editor::EditorState, model::RegisterContent};
+
+const NUMBERED_REGISTER_COUNT: usize = 9;
+
+impl EditorState {
+	pub fn is_readable_register(name: char) -> bool {
+		matches!(name, '"' | '-' | '0'..='9' | 'a'..='z' | 'A'..='Z')
+	}
+
+	pub fn select_register(&mut self, name: char) -> bool {
+		if !Self::is_readable_register(name) {
+			return false;
+		}
+		self.selected_register = Some(name);
+		true
+	}
+
+	pub fn register_content(&self, name: char) -> Option<RegisterContent> {
+		match name {
+			'"' => self.line_slot.clone().map(|text| RegisterContent {
+				text,
+				line_wise: self.line_slot_line_wise,
+				block_wise: self.line_slot_block_wise,
+				block_rows: self.line_slot_block_rows.clone(),
+			}),
+			'0' => self.yank_register.clone(),
+			'-' => self.small_delete_register.clone(),
+			'1'..='9' => {
+				let index = name.to_digit(10).map_or(0, |digit| digit as usize).saturating_sub(1);
+				self.numbered_registers.get(index).cloned()
+			}
+			'a'..='z' | 'A'..='Z' => self.named_registers.get(&name.to_ascii_lowercase()).cloned(),
+			_ => None,
+		}
+	}
+
+	// Every non-empty register in `:registers` order.
+	pub fn register_listing(&self) -> Vec<(char, RegisterContent)> {
+		['"', '0']
+			.into_iter()
+			.chain('1'..='9')
+			.chain('a'..='z')
+			.chain(['-'])
+			.filter_map(|name| self.register_content(name).map(|content| (name, content)))
+			.collect()
+	}
+
+	// Consumes the `"x` selection made for the next command; pastes without one
+	// read the unnamed register.
+	pub(super) fn take_paste_register(&mut self) -> Option<RegisterContent> {
+		let name = self.selected_register.take().unwrap_or('"');
+		self.register_content(name)
+	}
+
+	// Only yanks write "0, so it survives any number of later deletes.
+	pub(super) fn store_yank(&mut self, content: RegisterContent) {
+		if let Some(named) = self.store_selected_named_register(content.clone()) {
+			self.set_unnamed_register(named);
+			return;
+		}
+		self.yank_register = Some(content.clone());
+		self.set_unnamed_register(content);
+	}
+
+	// Like Vim, line-wise deletes and deletes spanning lines rotate through "1-"9,
+	// while deletes within one line land in the small delete register "-.
+	pub(super) fn store_delete(&mut self, content: RegisterContent) {
+		if let Some(named) = self.store_selected_named_register(content.clone()) {
+			self.set_unnamed_register(named);
+			return;
+		}
+		if content.line_wise || content.text.contains('\n') {
+			self.numbered_registers.push_front(content.clone());
+			self.numbered_registers.truncate(NUMBERED_REGISTER_COUNT);
+		} else {
+			self.small_delete_register = Some(content.clone());
+		}
+		self.set_unnamed_register(content);
+	}
+
+	// An uppercase name appends to its lowercase register instead of replacing it.
+	fn store_selected_named_register(&mut self, content: RegisterContent) -> Option<RegisterContent> {
+		let name = self.selected_register.filter(char::is_ascii_alphabetic)?;
+		self.selected_register = None;
+		let key = name.to_ascii_lowercase();
+		let stored = match self.named_registers.remove(&key) {
+			Some(mut existing) if name.is_ascii_uppercase() => {
+				// Blocks appended to a block stack below it as more rows.
+				let stacked_block = existing.block_wise && content.block_wise;
+				if existing.line_wise || content.line_wise || stacked_block {
+					existing.text.push('\n');
+				}
+				existing.text.push_str(content.text.as_str());
+				existing.line_wise |= content.line_wise;
+				existing.block_wise = stacked_block;
+				if stacked_block {
+					existing.block_rows.extend(content.block_rows);
+				} else {
+					existing.block_rows.clear();
+				}
+				existing
+			}
+			_ => content,
+		};
+		self.named_registers.insert(key, stored.clone());
+		Some(stored)
+	}
+
+	fn set_unnamed_register(&mut self, content: RegisterContent) {
+		self.line_slot = Some(content.text);
+		self.line_slot_line_wise = content.line_wise;
+		self.line_slot_block_wise = content.block_wise;
+		self.line_slot_block_rows = content.block_rows;
+	}
+}
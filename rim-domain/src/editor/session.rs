@@ -123,7 +123,55 @@ impl EditorState {
 		}
 	}
 
+	// Session files only carry file-backed buffers and their views; texts are left
+	// empty because the files are reopened through the regular load path.
+	pub fn layout_session_snapshot(&self) -> WorkspaceSessionSnapshot {
+		let mut snapshot = self.workspace_session_snapshot(false);
+		let mut index_map = Vec::with_capacity(snapshot.buffers.len());
+		let mut next_index = 0;
+		for buffer in &snapshot.buffers {
+			if buffer.path.is_some() {
+				index_map.push(Some(next_index));
+				next_index += 1;
+			} else {
+				index_map.push(None);
+			}
+		}
+		let remap = |index: usize| index_map.get(index).copied().flatten();
+		snapshot.buffers.retain(|buffer| buffer.path.is_some());
+		for buffer in &mut snapshot.buffers {
+			buffer.text.clear();
+			buffer.clean_text.clear();
+			buffer.history = None;
+		}
+		snapshot.buffer_order = snapshot.buffer_order.into_iter().filter_map(remap).collect();
+		for tab in &mut snapshot.tabs {
+			tab.buffer_order = std::mem::take(&mut tab.buffer_order).into_iter().filter_map(remap).collect();
+			for window in &mut tab.windows {
+				window.buffer_index = window.buffer_index.and_then(remap);
+				window.views = std::mem::take(&mut window.views)
+					.into_iter()
+					.filter_map(|mut view| {
+						view.buffer_index = remap(view.buffer_index)?;
+						Some(view)
+					})
+					.collect();
+			}
+		}
+		snapshot
+	}
+
 	pub fn restore_workspace_session(&mut self, snapshot: WorkspaceSessionSnapshot) -> bool {
+		self.restore_session_snapshot(snapshot, true)
+	}
+
+	// Cursors stay unclamped because the buffers are still empty; callers clamp
+	// them once each file has loaded.
+	pub fn restore_layout_session(&mut self, snapshot: WorkspaceSessionSnapshot) -> bool {
+		self.restore_session_snapshot(snapshot, false)
+	}
+
+	fn restore_session_snapshot(&mut self, snapshot: WorkspaceSessionSnapshot, clamp_cursors: bool) -> bool {
 		if snapshot.version != WORKSPACE_SESSION_VERSION || snapshot.tabs.is_empty() {
 			return false;
 		}
@@ -134,6 +182,7 @@ impl EditorState {
 		self.windows = SlotMap::with_key();
 		self.tabs.clear();
 		self.window_buffer_views.clear();
+		self.alternate_buffers.clear();
 
 		let mut restored_buffer_ids = Vec::with_capacity(snapshot.buffers.len());
 		for buffer_snapshot in snapshot.buffers {
@@ -157,8 +206,16 @@ impl EditorState {
 				clean_text: clean_rope,
 				dirty: rope != buffer_snapshot.clean_text.as_str(),
 				externally_modified: false,
-				undo_stack: history.undo_stack,
-				redo_stack: history.redo_stack,
+				read_only: false,
+				new_file: false,
+				deleted_on_disk: false,
+				undo_stack: history.undo_stack.into(),
+				redo_stack: history.redo_stack.into(),
+				changedtick: 0,
+				folds: Vec::new(),
+				change_list: Vec::new(),
+				change_list_index: 0,
+				filetype: None,
 			});
 			restored_buffer_ids.push(buffer_id);
 		}
@@ -208,6 +265,7 @@ impl EditorState {
 					let clamped_cursor = self
 						.buffers
 						.get(buffer_id)
+						.filter(|_| clamp_cursors)
 						.map(|buffer| clamp_cursor_for_rope(&buffer.text, view_snapshot.cursor))
 						.unwrap_or(view_snapshot.cursor);
 					self.window_buffer_views.insert((window_id, buffer_id), WindowBufferViewState {
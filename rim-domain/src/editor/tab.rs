@@ -35,6 +35,31 @@ impl EditorState {
 		}
 	}
 
+	// The active tab wins over other tabs, and within a tab its active window
+	// wins; the rest are searched in order.
+	pub fn focus_window_showing_buffer(&mut self, buffer_id: BufferId) -> bool {
+		let tab_ids = std::iter::once(self.active_tab)
+			.chain(self.tabs.keys().copied().filter(|tab_id| *tab_id != self.active_tab))
+			.collect::<Vec<_>>();
+		for tab_id in tab_ids {
+			let Some(tab) = self.tabs.get_mut(&tab_id) else {
+				continue;
+			};
+			let shows_buffer = |window_id: &WindowId| {
+				self.windows.get(*window_id).is_some_and(|window| window.buffer_id == Some(buffer_id))
+			};
+			let Some(window_id) =
+				std::iter::once(tab.active_window).chain(tab.windows.iter().copied()).find(shows_buffer)
+			else {
+				continue;
+			};
+			tab.active_window = window_id;
+			self.active_tab = tab_id;
+			return true;
+		}
+		false
+	}
+
 	pub fn active_tab_window_ids(&self) -> Vec<WindowId> {
 		self.tabs.get(&self.active_tab).map(|tab| tab.windows.clone()).unwrap_or_default()
 	}
@@ -1,4 +1,4 @@
-use crate::{display_geometry::char_display_width, edit::{block_col_for_display_target, clamp_cursor_col_for_line, cursor_slot_display_col, expand_tab_padding_at_display_target, pad_rope_line_to_char_len, previous_char_display_width, rope_block_char_range, rope_cursor_char, rope_editable_line_count, rope_editable_line_len_chars, rope_join_rows_without_newline, rope_linewise_char_range, rope_linewise_insertion_text, split_lines_owned}, editor::{EditorOperationError, EditorState}, model::{BufferState, CursorState, EditorMode, PendingBlockInsert, WindowState}, text::{rope_line_count, rope_line_len_chars, rope_line_without_newline}};
+use crate::{display_geometry::char_display_width, edit::{block_col_for_display_target, clamp_cursor_col_for_line, cursor_slot_display_col, expand_tab_padding_at_display_target, pad_rope_line_to_char_len, previous_char_display_width, rope_block_char_range, rope_cursor_char, rope_editable_line_count, rope_editable_line_len_chars, rope_join_rows_without_newline, rope_linewise_char_range, rope_linewise_delete_range, rope_linewise_insertion_text}, editor::{EditorOperationError, EditorState}, model::{BufferState, CursorState, EditorMode, PendingBlockInsert, RegisterContent, WindowState}, text::{rope_line_count, rope_line_len_chars, rope_line_without_newline}};
 
 fn active_buffer_and_window_mut(state: &mut EditorState) -> Option<(&mut BufferState, &mut WindowState)> {
 	let buffer_id = state.active_buffer_id()?;
@@ -166,9 +166,7 @@ impl EditorState {
 			let line = rope_line_without_newline(&buffer.text, start_row).unwrap_or_default();
 			window.cursor.col = clamp_cursor_col_for_line(line.as_str(), start.col);
 			self.mark_active_buffer_dirty();
-			self.line_slot = Some(deleted_parts.join("\n"));
-			self.line_slot_line_wise = false;
-			self.line_slot_block_wise = true;
+			self.store_delete(RegisterContent::block(deleted_parts));
 			return Ok(());
 		}
 
@@ -176,7 +174,7 @@ impl EditorState {
 			let Some(deleted) = rope_join_rows_without_newline(&buffer.text, start_row, end_row) else {
 				return Err(EditorOperationError::OutOfRange);
 			};
-			let Some(delete_range) = rope_linewise_char_range(&buffer.text, start_row, end_row) else {
+			let Some(delete_range) = rope_linewise_delete_range(&buffer.text, start_row, end_row) else {
 				return Err(EditorOperationError::OutOfRange);
 			};
 			buffer.text.remove(delete_range);
@@ -184,9 +182,12 @@ impl EditorState {
 			let new_row = start_row.min(visible_rows.saturating_sub(1)).saturating_add(1) as u16;
 			window.cursor.row = new_row;
 			window.cursor.col = 1;
-			self.line_slot = Some(deleted);
-			self.line_slot_line_wise = true;
-			self.line_slot_block_wise = false;
+			self.store_delete(RegisterContent {
+				text:       deleted,
+				line_wise:  true,
+				block_wise: false,
+				block_rows: Vec::new(),
+			});
 			self.mark_active_buffer_dirty();
 			return Ok(());
 		}
@@ -213,9 +214,12 @@ impl EditorState {
 		let line_len = rope_line_len_chars(&buffer.text, start_row) as u16;
 		window.cursor.col = start_col.min(line_len.saturating_add(1));
 		self.mark_active_buffer_dirty();
-		self.line_slot = Some(deleted_text);
-		self.line_slot_line_wise = false;
-		self.line_slot_block_wise = false;
+		self.store_delete(RegisterContent {
+			text:       deleted_text,
+			line_wise:  false,
+			block_wise: false,
+			block_rows: Vec::new(),
+		});
 		Ok(())
 	}
 
@@ -238,6 +242,16 @@ impl EditorState {
 	}
 
 	pub fn yank_visual_selection_to_slot(&mut self) -> Result<(), EditorOperationError> {
+		let content = self.visual_selection_content()?;
+		self.store_yank(content);
+		// Like Vim, the cursor ends at the top-left of what was yanked.
+		if let Some((start, _)) = self.normalized_visual_bounds() {
+			self.move_cursor_to(start);
+		}
+		Ok(())
+	}
+
+	pub fn visual_selection_content(&self) -> Result<RegisterContent, EditorOperationError> {
 		let line_wise = self.mode == EditorMode::VisualLine;
 		let block_wise = self.mode == EditorMode::VisualBlock;
 		let Some((start, end)) = self.normalized_visual_bounds() else {
@@ -266,16 +280,18 @@ impl EditorState {
 			if !yanked_any {
 				return Err(EditorOperationError::EmptySelection);
 			}
-			self.line_slot = Some(yanked_parts.join("\n"));
-			self.line_slot_line_wise = false;
-			self.line_slot_block_wise = true;
-			return Ok(());
+			return Ok(RegisterContent::block(yanked_parts));
 		}
 		if line_wise {
-			self.line_slot = rope_join_rows_without_newline(text, start_row, end_row);
-			self.line_slot_line_wise = true;
-			self.line_slot_block_wise = false;
-			return Ok(());
+			let Some(yanked) = rope_join_rows_without_newline(text, start_row, end_row) else {
+				return Err(EditorOperationError::OutOfRange);
+			};
+			return Ok(RegisterContent {
+				text:       yanked,
+				line_wise:  true,
+				block_wise: false,
+				block_rows: Vec::new(),
+			});
 		}
 
 		let start_line_len = rope_editable_line_len_chars(text, start_row).unwrap_or(0) as u16;
@@ -291,19 +307,15 @@ impl EditorState {
 		};
 		let yanked = text.slice(yank_start..yank_end).to_string();
 
-		self.line_slot = Some(yanked);
-		self.line_slot_line_wise = false;
-		self.line_slot_block_wise = false;
-		Ok(())
+		Ok(RegisterContent { text: yanked, line_wise: false, block_wise: false, block_rows: Vec::new() })
 	}
 
 	pub fn replace_visual_selection_with_slot(&mut self) -> Result<(), EditorOperationError> {
 		let line_wise = self.mode == EditorMode::VisualLine;
 		let block_wise = self.mode == EditorMode::VisualBlock;
-		let Some(slot_text) = self.line_slot.clone() else {
+		let Some(slot) = self.take_paste_register() else {
 			return Err(EditorOperationError::SlotEmpty);
 		};
-		let slot_block_wise = self.line_slot_block_wise;
 		let Some((start, end)) = self.normalized_visual_bounds() else {
 			return Err(EditorOperationError::NoAnchor);
 		};
@@ -320,14 +332,14 @@ impl EditorState {
 		}
 
 		if block_wise {
-			let block_lines = split_lines_owned(&slot_text);
+			// A charwise or linewise slot puts its first line on every row.
+			let first_line = slot.text.split('\n').next().unwrap_or_default();
 			let mut replacements = Vec::new();
 			for row_idx in start_row..=end_row {
-				let slot_offset = row_idx.saturating_sub(start_row);
-				let replacement = if slot_block_wise {
-					block_lines.get(slot_offset).cloned().unwrap_or_default()
+				let replacement = if slot.block_wise {
+					slot.block_rows.get(row_idx.saturating_sub(start_row)).cloned().unwrap_or_default()
 				} else {
-					block_lines.first().cloned().unwrap_or_default()
+					first_line.to_string()
 				};
 				let Some(range) = rope_block_char_range(&buffer.text, row_idx, start.col, end.col) else {
 					continue;
@@ -354,7 +366,7 @@ impl EditorState {
 				return Err(EditorOperationError::OutOfRange);
 			};
 			let has_following_rows = end_row.saturating_add(1) < rope_editable_line_count(&buffer.text);
-			let replacement = rope_linewise_insertion_text(slot_text.as_str(), has_following_rows);
+			let replacement = rope_linewise_insertion_text(slot.text.as_str(), has_following_rows);
 			buffer.text.remove(replace_range.clone());
 			if !replacement.is_empty() {
 				buffer.text.insert(replace_range.start, replacement.as_str());
@@ -378,8 +390,8 @@ impl EditorState {
 			return Err(EditorOperationError::OutOfRange);
 		};
 		buffer.text.remove(replace_start..replace_end);
-		if !slot_text.is_empty() {
-			buffer.text.insert(replace_start, slot_text.as_str());
+		if !slot.text.is_empty() {
+			buffer.text.insert(replace_start, slot.text.as_str());
 		}
 		window.cursor.row = start_row.saturating_add(1) as u16;
 		window.cursor.col = start_col;
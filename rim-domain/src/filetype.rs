@@ -0,0 +1,49 @@
+use std::path::Path;
+
+// Language ids follow the names language servers expect in `didOpen`.
+pub fn filetype_for_path(path: &Path) -> Option<&'static str> {
+	if path.file_name().and_then(|name| name.to_str()) == Some("Makefile") {
+		return Some("make");
+	}
+	let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+	let filetype = match extension.as_str() {
+		"rs" => "rust",
+		"c" | "h" => "c",
+		"cc" | "cpp" | "cxx" | "hpp" | "hh" => "cpp",
+		"go" => "go",
+		"py" => "python",
+		"js" | "mjs" | "cjs" => "javascript",
+		"jsx" => "javascriptreact",
+		"ts" | "mts" | "cts" => "typescript",
+		"tsx" => "typescriptreact",
+		"lua" => "lua",
+		"sh" | "bash" => "sh",
+		"toml" => "toml",
+		"json" => "json",
+		"yaml" | "yml" => "yaml",
+		"md" | "markdown" => "markdown",
+		"html" | "htm" => "html",
+		"css" => "css",
+		"zig" => "zig",
+		"nix" => "nix",
+		"mk" => "make",
+		_ => return None,
+	};
+	Some(filetype)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+
+	use super::filetype_for_path;
+
+	#[test]
+	fn filetype_for_path_should_map_extensions_case_insensitively() {
+		assert_eq!(filetype_for_path(Path::new("src/main.rs")), Some("rust"));
+		assert_eq!(filetype_for_path(Path::new("SCRIPT.PY")), Some("python"));
+		assert_eq!(filetype_for_path(Path::new("Makefile")), Some("make"));
+		assert_eq!(filetype_for_path(Path::new("notes")), None);
+		assert_eq!(filetype_for_path(Path::new("image.png")), None);
+	}
+}
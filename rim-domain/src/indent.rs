@@ -0,0 +1,191 @@
+// Rewrites only the leading run of tabs and spaces, keeping its display width.
+// Expanding turns every tab in it into spaces; tabifying packs full tab stops
+// into tabs and leaves the remainder as spaces. Returns the new indent and the
+// char length of the old one, or `None` when the line stays as it is.
+pub fn retab_indent(line: &str, tab_width: usize, to_tabs: bool) -> Option<(String, usize)> {
+	let tab_width = tab_width.max(1);
+	let indent = leading_indent(line);
+	let width = indent_display_width(indent, tab_width);
+	let retabbed = if to_tabs {
+		format!("{}{}", "\t".repeat(width / tab_width), " ".repeat(width % tab_width))
+	} else {
+		" ".repeat(width)
+	};
+	(retabbed != indent).then(|| (retabbed, indent.chars().count()))
+}
+
+// Shifts every line by the width difference between the first line's indent
+// and `target_indent`, keeping relative indentation. New indents use tabs only
+// when the target indent does; empty lines stay empty.
+pub fn reindent_lines(text: &str, target_indent: &str, tab_width: usize) -> String {
+	let tab_width = tab_width.max(1);
+	let use_tabs = target_indent.contains('\t');
+	let target_width = indent_display_width(target_indent, tab_width) as isize;
+	let first_width =
+		text.split('\n').next().map_or(0, |line| indent_display_width(leading_indent(line), tab_width)) as isize;
+	let delta = target_width - first_width;
+	text
+		.split('\n')
+		.map(|line| {
+			if line.is_empty() {
+				return String::new();
+			}
+			let indent = leading_indent(line);
+			let width = (indent_display_width(indent, tab_width) as isize + delta).max(0) as usize;
+			let indent = if use_tabs {
+				format!("{}{}", "\t".repeat(width / tab_width), " ".repeat(width % tab_width))
+			} else {
+				" ".repeat(width)
+			};
+			format!("{}{}", indent, line.trim_start_matches([' ', '\t']))
+		})
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectedIndent {
+	Tabs,
+	Spaces(usize),
+}
+
+// Looks at the first `sample` lines, whatever they hold. Tabs win when more of
+// them start with a tab than with spaces; otherwise the shift width is the step
+// seen most often between the space indents of neighbouring lines, the smaller
+// one on a tie. Blank lines are skipped, and one-space steps, mostly comment
+// continuations, are not counted.
+pub fn detect_indent<S: AsRef<str>>(
+	lines: impl IntoIterator<Item = S>,
+	sample: usize,
+) -> Option<DetectedIndent> {
+	let (mut tab_lines, mut space_lines) = (0, 0);
+	let mut steps = [0usize; 9];
+	let mut previous_width = 0;
+	for line in lines.into_iter().take(sample) {
+		let line = line.as_ref();
+		if line.trim().is_empty() {
+			continue;
+		}
+		let indent = leading_indent(line);
+		if indent.starts_with('\t') {
+			tab_lines += 1;
+			continue;
+		}
+		if indent.contains('\t') {
+			continue;
+		}
+		if !indent.is_empty() {
+			space_lines += 1;
+		}
+		let step = indent.len().abs_diff(previous_width);
+		if let Some(count) = steps.get_mut(step).filter(|_| step > 1) {
+			*count += 1;
+		}
+		previous_width = indent.len();
+	}
+	if tab_lines > space_lines {
+		return Some(DetectedIndent::Tabs);
+	}
+	let (step, count) =
+		steps.iter().enumerate().max_by_key(|(step, count)| (**count, std::cmp::Reverse(*step)))?;
+	(*count > 0).then_some(DetectedIndent::Spaces(step))
+}
+
+pub fn leading_indent(line: &str) -> &str {
+	let body = line.trim_start_matches([' ', '\t']);
+	&line[..line.len() - body.len()]
+}
+
+pub fn indent_display_width(indent: &str, tab_width: usize) -> usize {
+	indent.chars().fold(0, |width, ch| match ch {
+		'\t' => (width / tab_width + 1) * tab_width,
+		_ => width + 1,
+	})
+}
+
+// One-based, inclusive row ranges for `foldmethod=indent`: every run of two or
+// more lines at least `level` shift widths deep folds at that level, outermost
+// first. Blank lines take the lower level of the lines around them.
+pub fn indent_fold_ranges<S: AsRef<str>>(
+	lines: &[S],
+	shift_width: usize,
+	tab_width: usize,
+) -> Vec<(usize, usize)> {
+	let shift_width = shift_width.max(1);
+	let levels = lines
+		.iter()
+		.map(|line| {
+			let line = line.as_ref();
+			(!line.trim().is_empty()).then(|| indent_display_width(leading_indent(line), tab_width) / shift_width)
+		})
+		.collect::<Vec<_>>();
+	let levels = (0..levels.len())
+		.map(|idx| {
+			levels[idx].unwrap_or_else(|| {
+				let previous = levels[..idx].iter().rev().find_map(|level| *level).unwrap_or(0);
+				let next = levels[idx + 1..].iter().find_map(|level| *level).unwrap_or(0);
+				previous.min(next)
+			})
+		})
+		.collect::<Vec<_>>();
+	let max_level = levels.iter().copied().max().unwrap_or(0);
+	let mut ranges = Vec::new();
+	for level in 1..=max_level {
+		let mut run_start = None;
+		for (idx, line_level) in levels.iter().chain(std::iter::once(&0)).enumerate() {
+			match (run_start, *line_level >= level) {
+				(None, true) => run_start = Some(idx),
+				(Some(start), false) => {
+					if idx - start > 1 {
+						ranges.push((start + 1, idx));
+					}
+					run_start = None;
+				}
+				_ => {}
+			}
+		}
+	}
+	ranges.sort_by_key(|&(start, end)| (start, std::cmp::Reverse(end)));
+	ranges
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{DetectedIndent, detect_indent, indent_fold_ranges, reindent_lines, retab_indent};
+
+	#[test]
+	fn retab_indent_should_keep_display_width_and_skip_unchanged_lines() {
+		assert_eq!(retab_indent("\t  \tx\t\"a\tb\"", 4, false), Some(("        ".to_string(), 4)));
+		assert_eq!(retab_indent("      x", 4, true), Some(("\t  ".to_string(), 6)));
+		assert_eq!(retab_indent("  \tx", 4, true), Some(("\t".to_string(), 3)));
+		assert_eq!(retab_indent("    x", 4, false), None);
+		assert_eq!(retab_indent("\tx", 4, true), None);
+		assert_eq!(retab_indent("x\ty", 4, false), None);
+	}
+
+	#[test]
+	fn reindent_lines_should_shift_block_relative_to_first_line() {
+		assert_eq!(reindent_lines("  a\n\n    b", "        ", 4), "        a\n\n          b");
+		assert_eq!(reindent_lines("\t\ta\n\t\t\tb", "\t", 4), "\ta\n\t\tb");
+		assert_eq!(reindent_lines("    a\n  b", "", 4), "a\nb");
+	}
+
+	#[test]
+	fn detect_indent_should_find_tabs_or_the_most_common_space_step() {
+		let two_spaces = ["a:", "  b:", "    c: 1", "", "    d: 2", "  e:", "    f: 3", "g: 4"];
+		assert_eq!(detect_indent(two_spaces, 100), Some(DetectedIndent::Spaces(2)));
+		let tabs = ["fn a() {", "\tif x {", "\t\ty();", "\t}", " * doc", "}"];
+		assert_eq!(detect_indent(tabs, 100), Some(DetectedIndent::Tabs));
+		assert_eq!(detect_indent(["a", "b", " * c"], 100), None);
+		assert_eq!(detect_indent(["a", "\tb", "    c", "        d"], 2), Some(DetectedIndent::Tabs));
+		assert_eq!(detect_indent(["a", "b", "  c", "    d"], 2), None);
+	}
+
+	#[test]
+	fn indent_fold_ranges_should_nest_blocks_and_bridge_blank_lines() {
+		let lines =
+			["fn a() {", "    if x {", "\t    y();", "", "        z();", "    }", "    w();", "}", "    lone"];
+		assert_eq!(indent_fold_ranges(&lines, 4, 4), vec![(2, 7), (3, 5)]);
+		assert!(indent_fold_ranges(&["a", "b"], 4, 4).is_empty());
+	}
+}
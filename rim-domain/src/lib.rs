@@ -1,6 +1,13 @@
 pub mod display_geometry;
 pub mod edit;
 pub mod editor;
+pub mod filetype;
+pub mod indent;
 pub mod model;
+pub mod motion;
 pub mod preview;
+pub mod search;
+pub mod spell;
+pub mod tags;
 pub mod text;
+pub mod word_count;
@@ -1,9 +1,11 @@
-use std::path::PathBuf;
+use std::{collections::VecDeque, path::PathBuf, time::SystemTime};
 
 use ropey::Rope;
 use serde::{Deserialize, Serialize};
 use slotmap::new_key_type;
 
+use crate::filetype::filetype_for_path;
+
 new_key_type! { pub struct BufferId; }
 new_key_type! { pub struct WindowId; }
 
@@ -18,8 +20,39 @@ pub struct BufferState {
 	pub clean_text:          Rope,
 	pub dirty:               bool,
 	pub externally_modified: bool,
-	pub undo_stack:          Vec<BufferHistoryEntry>,
-	pub redo_stack:          Vec<BufferHistoryEntry>,
+	// Set when the file could not be read, so a plain `:w` does not overwrite it.
+	pub read_only:           bool,
+	// Opened at a path with no file behind it yet; cleared by the first save.
+	pub new_file:            bool,
+	// Reported by the file watcher; cleared when the file is loaded or saved again.
+	pub deleted_on_disk:     bool,
+	pub undo_stack:          VecDeque<BufferHistoryEntry>,
+	pub redo_stack:          VecDeque<BufferHistoryEntry>,
+	pub changedtick:         u64,
+	pub folds:               Vec<Fold>,
+	// Where committed edits left the cursor, oldest first; `g;` and `g,` walk it
+	// from `change_list_index`, which is the length after a new change.
+	pub change_list:         Vec<CursorState>,
+	pub change_list_index:   usize,
+	// Set by `:set filetype`; otherwise the filetype follows the path.
+	pub filetype:            Option<String>,
+}
+
+impl BufferState {
+	pub fn bump_changedtick(&mut self) { self.changedtick = self.changedtick.wrapping_add(1); }
+
+	pub fn filetype(&self) -> Option<&str> {
+		self.filetype.as_deref().or_else(|| self.path.as_deref().and_then(filetype_for_path))
+	}
+}
+
+// Rows are 1-based and inclusive, like cursor rows. Folds may nest but never
+// overlap partially.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fold {
+	pub start_row: u16,
+	pub end_row:   u16,
+	pub closed:    bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -27,6 +60,10 @@ pub struct BufferHistoryEntry {
 	pub edits:         Vec<BufferEditSnapshot>,
 	pub before_cursor: CursorState,
 	pub after_cursor:  CursorState,
+	// When the change entered the history; `None` for entries from undo files
+	// written before times were kept.
+	#[serde(default)]
+	pub changed_at:    Option<SystemTime>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -44,6 +81,13 @@ pub struct BufferEditSnapshot {
 	pub inserted_text: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChangeSign {
+	Added,
+	Changed,
+	Deleted,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RopeTextDiff {
 	pub start_char:    usize,
@@ -54,18 +98,24 @@ pub struct RopeTextDiff {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct WindowState {
-	pub buffer_id: Option<BufferId>,
-	pub cursor:    CursorState,
-	pub scroll_x:  u16,
-	pub scroll_y:  u16,
-	pub x:         u16,
-	pub y:         u16,
-	pub width:     u16,
-	pub height:    u16,
-	pub layout_x:  u32,
-	pub layout_y:  u32,
-	pub layout_w:  u32,
-	pub layout_h:  u32,
+	pub buffer_id:  Option<BufferId>,
+	pub cursor:     CursorState,
+	pub scroll_x:   u16,
+	pub scroll_y:   u16,
+	pub x:          u16,
+	pub y:          u16,
+	pub width:      u16,
+	pub height:     u16,
+	pub layout_x:   u32,
+	pub layout_y:   u32,
+	pub layout_w:   u32,
+	pub layout_h:   u32,
+	// `:diffthis`; diff windows in a tab compare their buffers.
+	pub diff:       bool,
+	// `:set scrollbind`; bound windows in a tab scroll together.
+	pub scrollbind: bool,
+	// `:setlocal wrap`; unset windows follow the global `wrap`.
+	pub wrap:       Option<bool>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -102,6 +152,27 @@ pub enum EditorMode {
 	VisualBlock,
 }
 
+// Like Vim's `virtualedit`: whether the cursor may sit past the end of a line,
+// in a visual block only or anywhere it moves in normal mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtualEdit {
+	None,
+	Block,
+	All,
+}
+
+// A normal-mode cursor in the virtual space past its line's end. It only holds
+// while the real cursor, which stays on the last char, and the buffer's
+// changedtick are still the ones it was taken at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VirtualCursor {
+	pub window_id:   WindowId,
+	pub buffer_id:   BufferId,
+	pub changedtick: u64,
+	pub cursor:      CursorState,
+	pub display_col: u16,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SplitAxis {
 	Horizontal,
@@ -115,6 +186,43 @@ pub struct PendingInsertUndoGroup {
 	pub edits:         Vec<BufferEditSnapshot>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InsertCompletionState {
+	pub buffer_id:  BufferId,
+	pub row:        u16,
+	pub start_col:  u16,
+	pub prefix:     String,
+	pub candidates: Vec<String>,
+	pub selected:   Option<usize>,
+}
+
+impl InsertCompletionState {
+	pub fn current_text(&self) -> &str {
+		match self.selected {
+			Some(index) => self.candidates[index].as_str(),
+			None => self.prefix.as_str(),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterContent {
+	pub text:       String,
+	pub line_wise:  bool,
+	pub block_wise: bool,
+	// A block keeps one slice per row of the rectangle, top to bottom, so a
+	// later paste can rebuild its shape; a row the block missed is an empty
+	// slice. `text` holds the same rows newline-joined for the clipboard and
+	// `:registers`. Empty for anything but a block.
+	pub block_rows: Vec<String>,
+}
+
+impl RegisterContent {
+	pub fn block(rows: Vec<String>) -> Self {
+		Self { text: rows.join("\n"), line_wise: false, block_wise: true, block_rows: rows }
+	}
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PendingBlockInsert {
 	pub start_row:          u16,
@@ -0,0 +1,146 @@
+use ropey::Rope;
+
+use crate::{model::CursorState, text::{is_completion_word_char, rope_line_count}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Motion {
+	WordForward,
+	WordEnd,
+}
+
+impl Motion {
+	// Inclusive motions take the char under the target into an operator's range;
+	// exclusive ones stop just before it.
+	pub fn is_inclusive(self) -> bool {
+		match self {
+			Self::WordForward => false,
+			Self::WordEnd => true,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+	Blank,
+	Word,
+	Punct,
+}
+
+fn char_class(ch: char) -> CharClass {
+	if ch.is_whitespace() {
+		CharClass::Blank
+	} else if is_completion_word_char(ch) {
+		CharClass::Word
+	} else {
+		CharClass::Punct
+	}
+}
+
+pub fn cursor_to_char_index(text: &Rope, cursor: CursorState) -> usize {
+	let row_index = (cursor.row.saturating_sub(1) as usize).min(text.len_lines().saturating_sub(1));
+	text.line_to_char(row_index).saturating_add(cursor.col.saturating_sub(1) as usize).min(text.len_chars())
+}
+
+pub fn char_index_to_cursor(text: &Rope, char_index: usize) -> CursorState {
+	let char_index = char_index.min(text.len_chars());
+	let row_index = text.char_to_line(char_index).min(rope_line_count(text).saturating_sub(1));
+	let col = char_index.saturating_sub(text.line_to_char(row_index));
+	CursorState { row: row_index.saturating_add(1) as u16, col: col.saturating_add(1) as u16 }
+}
+
+// Returns the char index the motion lands on, clamped to the last char.
+pub fn motion_target(text: &Rope, from: usize, motion: Motion) -> usize {
+	let len = text.len_chars();
+	if len == 0 {
+		return 0;
+	}
+	let class_at = |index: usize| char_class(text.char(index));
+	let last = len.saturating_sub(1);
+	match motion {
+		Motion::WordForward => {
+			let mut index = from.min(last);
+			let start_class = class_at(index);
+			if start_class != CharClass::Blank {
+				while index < len && class_at(index) == start_class {
+					index += 1;
+				}
+			}
+			while index < len && class_at(index) == CharClass::Blank {
+				index += 1;
+			}
+			index.min(len)
+		}
+		Motion::WordEnd => {
+			let mut index = from.saturating_add(1);
+			while index < len && class_at(index) == CharClass::Blank {
+				index += 1;
+			}
+			if index >= len {
+				return last;
+			}
+			let class = class_at(index);
+			while index + 1 < len && class_at(index + 1) == class {
+				index += 1;
+			}
+			index
+		}
+	}
+}
+
+// Char range an operator acts on, with the motion repeated `count` times. Like
+// Vim, an exclusive motion whose last step crosses into a later line stops at
+// the end of the line that step started on.
+pub fn motion_operator_range(
+	text: &Rope,
+	from: usize,
+	motion: Motion,
+	count: usize,
+) -> std::ops::Range<usize> {
+	let last_start = (1..count.max(1)).fold(from, |index, _| motion_target(text, index, motion));
+	let target = motion_target(text, last_start, motion);
+	let end = if motion.is_inclusive() {
+		target.saturating_add(1).min(text.len_chars())
+	} else {
+		let from_row = text.char_to_line(last_start.min(text.len_chars()));
+		if target < text.len_chars() && text.char_to_line(target) > from_row {
+			let line = text.line(from_row);
+			let newline_len = usize::from(line.len_chars() > 0 && line.char(line.len_chars() - 1) == '\n');
+			text.line_to_char(from_row).saturating_add(line.len_chars()).saturating_sub(newline_len)
+		} else {
+			target
+		}
+	};
+	from..end.max(from)
+}
+
+#[cfg(test)]
+mod tests {
+	use ropey::Rope;
+
+	use super::{Motion, motion_operator_range, motion_target};
+
+	#[test]
+	fn motion_target_should_move_between_words_and_punctuation() {
+		let text = Rope::from_str("foo.bar  baz\nqux");
+
+		assert_eq!(motion_target(&text, 0, Motion::WordForward), 3);
+		assert_eq!(motion_target(&text, 3, Motion::WordForward), 4);
+		assert_eq!(motion_target(&text, 4, Motion::WordForward), 9);
+		assert_eq!(motion_target(&text, 9, Motion::WordForward), 13);
+		assert_eq!(motion_target(&text, 0, Motion::WordEnd), 2);
+		assert_eq!(motion_target(&text, 2, Motion::WordEnd), 3);
+		assert_eq!(motion_target(&text, 9, Motion::WordEnd), 11);
+		assert_eq!(motion_target(&text, 11, Motion::WordEnd), 15);
+	}
+
+	#[test]
+	fn exclusive_motion_range_should_stop_at_end_of_starting_line() {
+		let text = Rope::from_str("one two\nthree");
+
+		assert_eq!(motion_operator_range(&text, 4, Motion::WordForward, 1), 4..7);
+		assert_eq!(motion_operator_range(&text, 4, Motion::WordEnd, 1), 4..7);
+		assert_eq!(motion_operator_range(&text, 0, Motion::WordForward, 1), 0..4);
+		assert_eq!(motion_operator_range(&text, 0, Motion::WordForward, 2), 0..7);
+		assert_eq!(motion_operator_range(&text, 0, Motion::WordEnd, 3), 0..13);
+	}
+}
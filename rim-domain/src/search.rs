@@ -0,0 +1,136 @@
+use std::ops::Range;
+
+use ropey::Rope;
+
+use crate::{model::CursorState, text::{rope_line_count, rope_line_without_newline}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+	pub cursor:  CursorState,
+	pub wrapped: bool,
+}
+
+// Literal forward search starting just after `from`, wrapping past the end of
+// the text; a match at `from` itself is only found after a full wrap.
+pub fn find_next_match(text: &Rope, from: CursorState, pattern: &str) -> Option<SearchMatch> {
+	if pattern.is_empty() {
+		return None;
+	}
+	let line_count = rope_line_count(text);
+	let start_row = (from.row.saturating_sub(1) as usize).min(line_count.saturating_sub(1));
+	let start_col = from.col.saturating_sub(1) as usize;
+	(0..=line_count).find_map(|offset| {
+		let row_index = (start_row + offset) % line_count;
+		let line = rope_line_without_newline(text, row_index)?;
+		let col = match offset {
+			0 => find_in_line_after(line.as_str(), pattern, Some(start_col)),
+			_ if offset == line_count => {
+				find_in_line_after(line.as_str(), pattern, None).filter(|col| *col <= start_col)
+			}
+			_ => find_in_line_after(line.as_str(), pattern, None),
+		}?;
+		Some(SearchMatch {
+			cursor:  CursorState { row: row_index.saturating_add(1) as u16, col: col.saturating_add(1) as u16 },
+			wrapped: offset > 0 && row_index <= start_row,
+		})
+	})
+}
+
+// Returns the char column of the first match strictly after `after`, or of the
+// first match on the line when `after` is None.
+fn find_in_line_after(line: &str, pattern: &str, after: Option<usize>) -> Option<usize> {
+	line.match_indices(pattern).map(|(byte_index, _)| line[..byte_index].chars().count()).find(
+		|col| match after {
+			Some(after) => *col > after,
+			None => true,
+		},
+	)
+}
+
+// Char ranges of the literal matches on one line, left to right and never
+// overlapping; only the first one unless `global`.
+pub fn find_line_matches(line: &str, pattern: &str, global: bool) -> Vec<Range<usize>> {
+	if pattern.is_empty() {
+		return Vec::new();
+	}
+	let pattern_chars = pattern.chars().count();
+	line
+		.match_indices(pattern)
+		.take(if global { usize::MAX } else { 1 })
+		.map(|(byte_index, _)| {
+			let start = line[..byte_index].chars().count();
+			start..start + pattern_chars
+		})
+		.collect()
+}
+
+// Replaces the matches `find_line_matches` gives, returning the new line with
+// the char ranges the replacements took in it.
+pub fn substitute_line(
+	line: &str,
+	pattern: &str,
+	replacement: &str,
+	global: bool,
+) -> (String, Vec<Range<usize>>) {
+	let replacement_chars = replacement.chars().count();
+	let mut out = String::with_capacity(line.len());
+	let mut ranges = Vec::new();
+	let mut consumed = 0;
+	let mut chars = line.chars();
+	for found in find_line_matches(line, pattern, global) {
+		out.extend(chars.by_ref().take(found.start - consumed));
+		chars.by_ref().take(found.len()).for_each(drop);
+		let start = out.chars().count();
+		out.push_str(replacement);
+		ranges.push(start..start + replacement_chars);
+		consumed = found.end;
+	}
+	out.extend(chars);
+	(out, ranges)
+}
+
+#[cfg(test)]
+mod tests {
+	use ropey::Rope;
+
+	use super::{SearchMatch, find_line_matches, find_next_match, substitute_line};
+	use crate::model::CursorState;
+
+	#[test]
+	fn find_next_match_should_search_forward_and_wrap_to_top() {
+		let text = Rope::from_str("foo bar\nbaz foo\nqux\n");
+
+		assert_eq!(
+			find_next_match(&text, CursorState { row: 1, col: 1 }, "foo"),
+			Some(SearchMatch { cursor: CursorState { row: 2, col: 5 }, wrapped: false })
+		);
+		assert_eq!(
+			find_next_match(&text, CursorState { row: 2, col: 5 }, "foo"),
+			Some(SearchMatch { cursor: CursorState { row: 1, col: 1 }, wrapped: true })
+		);
+		assert_eq!(
+			find_next_match(&text, CursorState { row: 3, col: 1 }, "qux"),
+			Some(SearchMatch { cursor: CursorState { row: 3, col: 1 }, wrapped: true })
+		);
+		assert_eq!(find_next_match(&text, CursorState { row: 1, col: 1 }, "nope"), None);
+	}
+
+	#[test]
+	fn find_line_matches_should_return_char_ranges_of_the_first_or_every_match() {
+		assert_eq!(find_line_matches("aé foo é foo", "foo", false).first(), Some(&(3..6)));
+		assert_eq!(find_line_matches("aé foo é foo", "foo", false).len(), 1);
+		assert_eq!(find_line_matches("aé foo é foo", "foo", true), vec![3..6, 9..12]);
+		assert_eq!(find_line_matches("aaaa", "aa", true), vec![0..2, 2..4]);
+		assert_eq!(find_line_matches("abc", "", true), Vec::<std::ops::Range<usize>>::new());
+	}
+
+	#[test]
+	fn substitute_line_should_report_where_the_replacements_landed() {
+		let (line, ranges) = substitute_line("foo é foo", "foo", "barbaz", false);
+		assert_eq!(line, "barbaz é foo");
+		assert_eq!(ranges.first(), Some(&(0..6)));
+		assert_eq!(ranges.len(), 1);
+		assert_eq!(substitute_line("foo é foo", "foo", "x", true), ("x é x".to_string(), vec![0..1, 4..5]));
+		assert_eq!(substitute_line("abc", "z", "y", true), ("abc".to_string(), Vec::new()));
+	}
+}
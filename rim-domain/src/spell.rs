@@ -0,0 +1,183 @@
+use std::{collections::HashSet, ops::Range, sync::OnceLock};
+
+const BUNDLED_WORDS: &str = include_str!("spell_words.txt");
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+fn bundled_words() -> &'static HashSet<&'static str> {
+	static WORDS: OnceLock<HashSet<&'static str>> = OnceLock::new();
+	WORDS.get_or_init(|| BUNDLED_WORDS.lines().map(str::trim).filter(|word| !word.is_empty()).collect())
+}
+
+// Char ranges of the words on `line` that are neither in the bundled list nor
+// in `good_words`. Identifiers with digits, underscores or inner capitals are
+// left alone so code comments stay quiet.
+pub fn misspelled_word_ranges(line: &str, good_words: &HashSet<String>) -> Vec<Range<usize>> {
+	let chars = line.chars().collect::<Vec<_>>();
+	let mut ranges = Vec::new();
+	let mut index = 0;
+	while index < chars.len() {
+		if !is_token_char(chars[index]) {
+			index += 1;
+			continue;
+		}
+		let start = index;
+		while index < chars.len()
+			&& (is_token_char(chars[index])
+				|| (chars[index] == '\'' && chars.get(index + 1).is_some_and(|next| next.is_alphabetic())))
+		{
+			index += 1;
+		}
+		let word = chars[start..index].iter().collect::<String>();
+		if is_checkable_word(word.as_str()) && !is_known_word(word.as_str(), good_words) {
+			ranges.push(start..index);
+		}
+	}
+	ranges
+}
+
+pub fn is_known_word(word: &str, good_words: &HashSet<String>) -> bool {
+	let lower = word.to_lowercase();
+	let known = |candidate: &str| bundled_words().contains(candidate) || good_words.contains(candidate);
+	if known(lower.as_str()) {
+		return true;
+	}
+	// A few regular inflections keep the bundled list small.
+	word_stems(lower.as_str()).iter().any(|stem| known(stem.as_str()))
+}
+
+// Closest known words by edit distance, nearest first; a capitalised word gets
+// capitalised suggestions.
+pub fn spell_suggestions(word: &str, good_words: &HashSet<String>, limit: usize) -> Vec<String> {
+	let lower = word.to_lowercase();
+	let target = lower.chars().collect::<Vec<_>>();
+	let mut scored = bundled_words()
+		.iter()
+		.copied()
+		.chain(good_words.iter().map(String::as_str))
+		.filter(|candidate| *candidate != lower)
+		.filter_map(|candidate| {
+			let candidate_chars = candidate.chars().collect::<Vec<_>>();
+			if candidate_chars.len().abs_diff(target.len()) > MAX_SUGGESTION_DISTANCE {
+				return None;
+			}
+			let distance = edit_distance(target.as_slice(), candidate_chars.as_slice());
+			(distance <= MAX_SUGGESTION_DISTANCE).then_some((distance, candidate))
+		})
+		.collect::<Vec<_>>();
+	scored.sort_unstable();
+	scored.dedup();
+	let capitalised = word.chars().next().is_some_and(char::is_uppercase);
+	scored
+		.into_iter()
+		.take(limit)
+		.map(|(_, candidate)| if capitalised { capitalise(candidate) } else { candidate.to_string() })
+		.collect()
+}
+
+fn is_token_char(ch: char) -> bool { ch.is_alphanumeric() || ch == '_' }
+
+fn is_checkable_word(word: &str) -> bool {
+	word.chars().count() > 1
+		&& word.chars().all(|ch| ch.is_alphabetic() || ch == '\'')
+		&& !word.chars().skip(1).any(char::is_uppercase)
+}
+
+fn word_stems(word: &str) -> Vec<String> {
+	let mut stems = Vec::new();
+	if let Some(stem) = word.strip_suffix("'s") {
+		stems.push(stem.to_string());
+	}
+	if let Some(stem) = word.strip_suffix("ies").or_else(|| word.strip_suffix("ied")) {
+		stems.push(format!("{}y", stem));
+	}
+	for suffix in ["s", "es", "ly"] {
+		if let Some(stem) = word.strip_suffix(suffix) {
+			stems.push(stem.to_string());
+		}
+	}
+	for suffix in ["ing", "ed", "er", "est"] {
+		let Some(stem) = word.strip_suffix(suffix) else {
+			continue;
+		};
+		stems.push(stem.to_string());
+		stems.push(format!("{}e", stem));
+		// running -> run, stopped -> stop
+		let mut chars = stem.chars().rev();
+		if let (Some(last), Some(before)) = (chars.next(), chars.next())
+			&& last == before
+		{
+			stems.push(stem[..stem.len() - last.len_utf8()].to_string());
+		}
+	}
+	stems.retain(|stem| !stem.is_empty());
+	stems
+}
+
+fn capitalise(word: &str) -> String {
+	let mut chars = word.chars();
+	chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+// Optimal string alignment distance: Levenshtein plus adjacent swaps, which are
+// the most common typing slip.
+fn edit_distance(left: &[char], right: &[char]) -> usize {
+	let width = right.len() + 1;
+	let mut table = vec![0usize; (left.len() + 1) * width];
+	for (row, cell) in table.iter_mut().step_by(width).enumerate() {
+		*cell = row;
+	}
+	for (col, cell) in table.iter_mut().take(width).enumerate() {
+		*cell = col;
+	}
+	for row in 1..=left.len() {
+		for col in 1..=right.len() {
+			let cost = usize::from(left[row - 1] != right[col - 1]);
+			let mut best = (table[(row - 1) * width + col] + 1)
+				.min(table[row * width + col - 1] + 1)
+				.min(table[(row - 1) * width + col - 1] + cost);
+			if row > 1 && col > 1 && left[row - 1] == right[col - 2] && left[row - 2] == right[col - 1] {
+				best = best.min(table[(row - 2) * width + col - 2] + 1);
+			}
+			table[row * width + col] = best;
+		}
+	}
+	table[left.len() * width + right.len()]
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashSet;
+
+	use super::{is_known_word, misspelled_word_ranges, spell_suggestions};
+
+	#[test]
+	fn misspelled_word_ranges_should_flag_unknown_words_and_skip_identifiers() {
+		let good_words = HashSet::new();
+		let line = "The quikc brown fox jumpd over snake_case, HTTP2 and rimRope; it's fine.";
+
+		let flagged = misspelled_word_ranges(line, &good_words)
+			.into_iter()
+			.map(|range| line.chars().skip(range.start).take(range.len()).collect::<String>())
+			.collect::<Vec<_>>();
+
+		assert_eq!(flagged, vec!["quikc".to_string(), "jumpd".to_string()]);
+	}
+
+	#[test]
+	fn misspelled_word_ranges_should_accept_good_words_and_inflections() {
+		let good_words = HashSet::from(["quikc".to_string()]);
+
+		assert!(misspelled_word_ranges("Quikc windows jumped, running fixes", &good_words).is_empty());
+		assert!(is_known_word("Buffers", &good_words));
+		assert!(!is_known_word("bufferz", &good_words));
+	}
+
+	#[test]
+	fn spell_suggestions_should_rank_nearest_words_first_and_keep_capitals() {
+		let good_words = HashSet::new();
+
+		assert!(spell_suggestions("teh", &good_words, 3).contains(&"the".to_string()));
+		assert_eq!(spell_suggestions("Wrod", &good_words, 1), vec!["Word".to_string()]);
+		assert!(spell_suggestions("qqqqqqqq", &good_words, 5).is_empty());
+	}
+}
@@ -0,0 +1,177 @@
+use ropey::Rope;
+
+use crate::{model::CursorState, text::{rope_line_count, rope_line_without_newline}};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagEntry {
+	pub name:    String,
+	pub file:    String,
+	pub address: TagAddress,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagAddress {
+	Line(usize),
+	Pattern(TagPattern),
+	// Exact spots such as language server locations; never parsed from a tags
+	// file.
+	Cursor(CursorState),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagPattern {
+	pub text:           String,
+	pub anchored_start: bool,
+	pub anchored_end:   bool,
+}
+
+pub fn parse_tags(text: &str) -> Vec<TagEntry> { text.lines().filter_map(parse_tag_line).collect() }
+
+pub fn find_tag<'a>(entries: &'a [TagEntry], name: &str) -> Option<&'a TagEntry> {
+	entries.iter().find(|entry| entry.name == name)
+}
+
+// Resolve against the target buffer text; pattern addresses land on the match
+// start, line addresses on the first non-blank column, cursor addresses are
+// clamped into the text.
+pub fn resolve_tag_cursor(text: &Rope, address: &TagAddress) -> Option<CursorState> {
+	let line_count = rope_line_count(text);
+	match address {
+		TagAddress::Line(line) => {
+			let row_index = line.saturating_sub(1).min(line_count.saturating_sub(1));
+			let line = rope_line_without_newline(text, row_index).unwrap_or_default();
+			let col = line.chars().take_while(|ch| ch.is_whitespace()).count();
+			Some(CursorState { row: row_index.saturating_add(1) as u16, col: col.saturating_add(1) as u16 })
+		}
+		TagAddress::Cursor(cursor) => {
+			let row_index = (cursor.row.saturating_sub(1) as usize).min(line_count.saturating_sub(1));
+			let line_chars = rope_line_without_newline(text, row_index).map_or(0, |line| line.chars().count());
+			let col = (cursor.col.saturating_sub(1) as usize).min(line_chars.saturating_sub(1));
+			Some(CursorState { row: row_index.saturating_add(1) as u16, col: col.saturating_add(1) as u16 })
+		}
+		TagAddress::Pattern(pattern) => (0..line_count).find_map(|row_index| {
+			let line = rope_line_without_newline(text, row_index)?;
+			let byte_start = match (pattern.anchored_start, pattern.anchored_end) {
+				(true, true) => (line == pattern.text).then_some(0),
+				(true, false) => line.starts_with(pattern.text.as_str()).then_some(0),
+				(false, true) => line.ends_with(pattern.text.as_str()).then(|| line.len() - pattern.text.len()),
+				(false, false) => line.find(pattern.text.as_str()),
+			}?;
+			let col = line[..byte_start].chars().count();
+			Some(CursorState { row: row_index.saturating_add(1) as u16, col: col.saturating_add(1) as u16 })
+		}),
+	}
+}
+
+fn parse_tag_line(line: &str) -> Option<TagEntry> {
+	if line.starts_with("!_TAG_") {
+		return None;
+	}
+	let (name, rest) = line.split_once('\t')?;
+	let (file, address) = rest.split_once('\t')?;
+	if name.is_empty() || file.is_empty() {
+		return None;
+	}
+	Some(TagEntry {
+		name:    name.to_string(),
+		file:    file.to_string(),
+		address: parse_tag_address(address)?,
+	})
+}
+
+fn parse_tag_address(address: &str) -> Option<TagAddress> {
+	let mut chars = address.chars();
+	let delimiter = chars.next()?;
+	if delimiter != '/' && delimiter != '?' {
+		let digits = address.chars().take_while(char::is_ascii_digit).collect::<String>();
+		return digits.parse().ok().map(TagAddress::Line);
+	}
+
+	let mut body = String::new();
+	let mut closed = false;
+	while let Some(ch) = chars.next() {
+		if ch == '\\' {
+			// ctags only escapes the delimiter and the backslash itself.
+			match chars.next() {
+				Some(next) if next == delimiter || next == '\\' => body.push(next),
+				Some(next) => {
+					body.push('\\');
+					body.push(next);
+				}
+				None => body.push('\\'),
+			}
+			continue;
+		}
+		if ch == delimiter {
+			closed = true;
+			break;
+		}
+		body.push(ch);
+	}
+	if !closed {
+		return None;
+	}
+
+	let anchored_start = body.starts_with('^');
+	if anchored_start {
+		body.remove(0);
+	}
+	let anchored_end = body.ends_with('$') && !body.ends_with("\\$");
+	if anchored_end {
+		body.pop();
+	}
+	Some(TagAddress::Pattern(TagPattern { text: body, anchored_start, anchored_end }))
+}
+
+#[cfg(test)]
+mod tests {
+	use ropey::Rope;
+
+	use super::{TagAddress, TagPattern, find_tag, parse_tags, resolve_tag_cursor};
+	use crate::model::CursorState;
+
+	#[test]
+	fn parse_tags_should_read_pattern_and_line_addresses() {
+		let entries = parse_tags(
+			"!_TAG_FILE_SORTED\t1\t/0=unsorted/\nmain\tsrc/main.rs\t/^fn main() \
+			 {$/;\"\tf\nPATH\tsrc/a\\/b.rs\t/a\\/b/\nlimit\tsrc/lib.rs\t42;\"\tc\nbroken\n",
+		);
+
+		assert_eq!(entries.len(), 3);
+		assert_eq!(entries[0].name, "main");
+		assert_eq!(entries[0].file, "src/main.rs");
+		assert_eq!(
+			entries[0].address,
+			TagAddress::Pattern(TagPattern {
+				text:           "fn main() {".to_string(),
+				anchored_start: true,
+				anchored_end:   true,
+			})
+		);
+		assert_eq!(
+			entries[1].address,
+			TagAddress::Pattern(TagPattern {
+				text:           "a/b".to_string(),
+				anchored_start: false,
+				anchored_end:   false,
+			})
+		);
+		assert_eq!(
+			find_tag(entries.as_slice(), "limit").map(|entry| &entry.address),
+			Some(&TagAddress::Line(42))
+		);
+		assert!(find_tag(entries.as_slice(), "broken").is_none());
+	}
+
+	#[test]
+	fn resolve_tag_cursor_should_find_pattern_row_and_clamp_line_numbers() {
+		let text = Rope::from_str("use x;\n\n  fn main() {\n}\n");
+		let entries = parse_tags(
+			"main\tsrc/main.rs\t/main() {$/;\"\tf\nfar\tsrc/main.rs\t99\nmissing\tsrc/main.rs\t/^nope$/\n",
+		);
+
+		assert_eq!(resolve_tag_cursor(&text, &entries[0].address), Some(CursorState { row: 3, col: 6 }));
+		assert_eq!(resolve_tag_cursor(&text, &entries[1].address), Some(CursorState { row: 4, col: 1 }));
+		assert_eq!(resolve_tag_cursor(&text, &entries[2].address), None);
+	}
+}
@@ -1,8 +1,8 @@
-use std::path::Path;
+use std::{ops::Range, path::Path};
 
-use ropey::Rope;
+use ropey::{Rope, RopeSlice};
 
-use crate::model::{BufferEditSnapshot, CursorState, RopeTextDiff};
+use crate::model::{BufferEditSnapshot, CursorState, LineChangeSign, RopeTextDiff};
 
 pub fn buffer_name_from_path(path: &Path) -> Option<String> {
 	path.file_name().map(|name| name.to_string_lossy().to_string())
@@ -114,3 +114,158 @@ pub fn compute_rope_text_diff(before: &Rope, after: &Rope) -> Option<RopeTextDif
 		inserted_text: after.slice(common_prefix_chars..after_mid_end).to_string(),
 	})
 }
+
+// Hunks larger than this fall back to marking every line in them as changed.
+const LINE_DIFF_MAX_CELLS: usize = 250_000;
+
+pub fn compute_line_change_signs(
+	baseline: &Rope,
+	current: &Rope,
+	rows: Range<usize>,
+) -> Vec<Option<LineChangeSign>> {
+	let mut visible = vec![None; rows.len()];
+	if rows.is_empty() || baseline == current {
+		return visible;
+	}
+
+	let old_len = if rope_is_empty(baseline) { 0 } else { rope_line_count(baseline) };
+	let new_len = if rope_is_empty(current) { 0 } else { rope_line_count(current) };
+	let mut prefix = 0usize;
+	while prefix < old_len
+		&& prefix < new_len
+		&& rope_line_content(baseline, prefix) == rope_line_content(current, prefix)
+	{
+		prefix += 1;
+	}
+	let mut suffix = 0usize;
+	while suffix < old_len - prefix
+		&& suffix < new_len - prefix
+		&& rope_line_content(baseline, old_len - 1 - suffix) == rope_line_content(current, new_len - 1 - suffix)
+	{
+		suffix += 1;
+	}
+
+	let old_mid = prefix..old_len - suffix;
+	let new_mid = prefix..new_len - suffix;
+	// Deleted-line markers can land one row outside the changed hunk.
+	let marked = new_mid.start.saturating_sub(1)..new_mid.end.saturating_add(1).min(new_len).max(new_mid.end);
+	if rows.end <= marked.start || rows.start >= marked.end {
+		return visible;
+	}
+
+	let mut signs = vec![None; marked.len()];
+	let old_count = old_mid.len();
+	let new_count = new_mid.len();
+	if old_count == 0 || new_count == 0 || old_count.saturating_mul(new_count) > LINE_DIFF_MAX_CELLS {
+		mark_line_change_run(&mut signs, marked.start, new_mid.start, new_count, old_count);
+	} else {
+		let width = new_count + 1;
+		let same_line = |old_idx: usize, new_idx: usize| {
+			rope_line_content(baseline, old_mid.start + old_idx)
+				== rope_line_content(current, new_mid.start + new_idx)
+		};
+		let mut lcs = vec![0u32; (old_count + 1) * width];
+		for old_idx in (0..old_count).rev() {
+			for new_idx in (0..new_count).rev() {
+				lcs[old_idx * width + new_idx] = if same_line(old_idx, new_idx) {
+					lcs[(old_idx + 1) * width + new_idx + 1] + 1
+				} else {
+					lcs[(old_idx + 1) * width + new_idx].max(lcs[old_idx * width + new_idx + 1])
+				};
+			}
+		}
+
+		let (mut old_idx, mut new_idx) = (0usize, 0usize);
+		let (mut run_start, mut inserted, mut deleted) = (0usize, 0usize, 0usize);
+		while old_idx < old_count || new_idx < new_count {
+			if old_idx < old_count && new_idx < new_count && same_line(old_idx, new_idx) {
+				mark_line_change_run(&mut signs, marked.start, new_mid.start + run_start, inserted, deleted);
+				old_idx += 1;
+				new_idx += 1;
+				run_start = new_idx;
+				inserted = 0;
+				deleted = 0;
+			} else if new_idx < new_count
+				&& (old_idx == old_count
+					|| lcs[old_idx * width + new_idx + 1] >= lcs[(old_idx + 1) * width + new_idx])
+			{
+				new_idx += 1;
+				inserted += 1;
+			} else {
+				old_idx += 1;
+				deleted += 1;
+			}
+		}
+		mark_line_change_run(&mut signs, marked.start, new_mid.start + run_start, inserted, deleted);
+	}
+
+	for (slot, row) in visible.iter_mut().zip(rows) {
+		if marked.contains(&row) {
+			*slot = signs[row - marked.start];
+		}
+	}
+	visible
+}
+
+pub fn line_change_hunk_starts(baseline: &Rope, current: &Rope) -> Vec<usize> {
+	let signs = compute_line_change_signs(baseline, current, 0..rope_line_count(current));
+	signs
+		.iter()
+		.enumerate()
+		.filter(|(row, sign)| sign.is_some() && (*row == 0 || signs[row - 1].is_none()))
+		.map(|(row, _)| row)
+		.collect()
+}
+
+fn mark_line_change_run(
+	signs: &mut [Option<LineChangeSign>],
+	signs_start: usize,
+	new_start: usize,
+	inserted: usize,
+	deleted: usize,
+) {
+	for offset in 0..inserted {
+		signs[new_start + offset - signs_start] =
+			Some(if offset < deleted { LineChangeSign::Changed } else { LineChangeSign::Added });
+	}
+	if inserted == 0 && deleted > 0 {
+		let target = new_start.saturating_sub(1);
+		if let Some(slot) = target.checked_sub(signs_start).and_then(|idx| signs.get_mut(idx))
+			&& slot.is_none()
+		{
+			*slot = Some(LineChangeSign::Deleted);
+		}
+	}
+}
+
+fn rope_line_content(text: &Rope, row_index: usize) -> RopeSlice<'_> {
+	let line = text.line(row_index);
+	let mut end = line.len_chars();
+	if end > 0 && line.char(end - 1) == '\n' {
+		end -= 1;
+		if end > 0 && line.char(end - 1) == '\r' {
+			end -= 1;
+		}
+	}
+	line.slice(..end)
+}
+
+pub fn is_completion_word_char(ch: char) -> bool { ch.is_alphanumeric() || ch == '_' }
+
+pub fn collect_completion_candidates(text: &Rope, prefix: &str, candidates: &mut Vec<String>) {
+	let mut word = String::new();
+	let mut push_word = |word: &mut String| {
+		if word.len() > prefix.len() && word.starts_with(prefix) && !candidates.contains(word) {
+			candidates.push(word.clone());
+		}
+		word.clear();
+	};
+	for ch in text.chars() {
+		if is_completion_word_char(ch) {
+			word.push(ch);
+		} else {
+			push_word(&mut word);
+		}
+	}
+	push_word(&mut word);
+}
@@ -0,0 +1,34 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+pub const READING_WORDS_PER_MINUTE: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextStats {
+	pub words: usize,
+	pub chars: usize,
+}
+
+impl TextStats {
+	pub fn reading_minutes(&self) -> usize { self.words.div_ceil(READING_WORDS_PER_MINUTE) }
+}
+
+// Words follow UAX #29 boundaries, so punctuation and whitespace runs are not
+// words while CJK ideographs and contractions are handled per the standard.
+pub fn text_stats(text: &str) -> TextStats {
+	TextStats { words: text.unicode_words().count(), chars: text.chars().count() }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{TextStats, text_stats};
+
+	#[test]
+	fn text_stats_should_skip_punctuation_and_round_reading_time_up() {
+		let stats = text_stats("Hello, world -- it's caf\u{e9} time.\n\n");
+
+		assert_eq!(stats, TextStats { words: 5, chars: 33 });
+		assert_eq!(stats.reading_minutes(), 1);
+		assert_eq!(text_stats("").reading_minutes(), 0);
+		assert_eq!(TextStats { words: 401, chars: 0 }.reading_minutes(), 3);
+	}
+}
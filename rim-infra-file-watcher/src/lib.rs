@@ -172,7 +172,11 @@ impl FileWatcherState {
 			}
 			if let Some(buffer_id) = file_to_buffer.get(&changed_path).copied()
 				&& event_tx
-					.send(AppAction::File(FileAction::ExternalChangeDetected { buffer_id, path: changed_path.clone() }))
+					.send(AppAction::File(FileAction::ExternalChangeDetected {
+						buffer_id,
+						on_disk: semantic_tracker.is_on_disk(&changed_path),
+						path: changed_path.clone(),
+					}))
 					.is_err()
 			{
 				return false;
@@ -516,6 +520,10 @@ impl SemanticTracker {
 		}
 		changed_paths
 	}
+
+	fn is_on_disk(&self, path: &Path) -> bool {
+		!matches!(self.snapshots.get(path), Some(FileSnapshot::Missing))
+	}
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -588,8 +596,11 @@ mod tests {
 
 		fs::write(&file_path, "beta").expect("write changed content");
 		assert_eq!(tracker.collect_changed_files(&candidates), vec![file_path.clone()]);
+		assert!(tracker.is_on_disk(&file_path));
 
-		let _ = fs::remove_file(file_path);
+		fs::remove_file(&file_path).expect("remove file");
+		assert_eq!(tracker.collect_changed_files(&candidates), vec![file_path.clone()]);
+		assert!(!tracker.is_on_disk(&file_path));
 	}
 
 	#[test]
@@ -1,12 +1,20 @@
-use std::{sync::{Arc, atomic::{AtomicBool, Ordering}}, thread, time::Duration};
+use std::{sync::{Arc, atomic::{AtomicBool, AtomicU64, Ordering}}, thread, time::Duration};
 
 use crossterm::{event, event::{Event, KeyCode as CrosstermKeyCode, KeyEvent as CrosstermKeyEvent, KeyEventKind as CrosstermKeyEventKind, KeyModifiers as CrosstermKeyModifiers}};
 use rim_application::action::{AppAction, EditorAction, KeyCode, KeyEvent, KeyModifiers, LayoutAction};
 use tracing::error;
 
+// Caps one drain so a burst of keys, such as a paste from a terminal without
+// bracketed paste, still yields to the event loop.
+const MAX_EVENT_BATCH: usize = 256;
+
 pub struct InputHandler;
 
 impl InputHandler {
+	pub fn actions(&self, events: &[Event]) -> Vec<AppAction> {
+		events.iter().filter_map(|event| self.action(event)).collect()
+	}
+
 	pub fn action(&self, event: &Event) -> Option<AppAction> {
 		match event {
 			Event::Resize(width, height) => {
@@ -16,6 +24,7 @@ impl InputHandler {
 				let key = Self::map_key(*key)?;
 				Some(AppAction::Editor(EditorAction::KeyPressed(key)))
 			}
+			Event::Paste(text) => Some(AppAction::Paste(text.clone())),
 			_ => None,
 		}
 	}
@@ -38,6 +47,14 @@ impl InputHandler {
 			CrosstermKeyCode::Tab => KeyCode::Tab,
 			CrosstermKeyCode::Esc => KeyCode::Esc,
 			CrosstermKeyCode::F(1) => KeyCode::F1,
+			// Legacy terminals encode Ctrl-] as 0x1d, which crossterm reports as Ctrl-5.
+			CrosstermKeyCode::Char('5') if event.modifiers.contains(CrosstermKeyModifiers::CONTROL) => {
+				KeyCode::Char(']')
+			}
+			// Likewise Ctrl-^ arrives as 0x1e, reported as Ctrl-6.
+			CrosstermKeyCode::Char('6') if event.modifiers.contains(CrosstermKeyModifiers::CONTROL) => {
+				KeyCode::Char('^')
+			}
 			CrosstermKeyCode::Char(ch) => KeyCode::Char(ch),
 			_ => return None,
 		};
@@ -59,12 +76,24 @@ impl InputHandler {
 pub struct InputPumpService {
 	join_handle: Option<std::thread::JoinHandle<()>>,
 	running:     Arc<AtomicBool>,
+	key_timeout: Arc<AtomicU64>,
 	event_tx:    flume::Sender<AppAction>,
 }
 
 impl InputPumpService {
 	pub fn new(event_tx: flume::Sender<AppAction>) -> Self {
-		Self { join_handle: None, running: Arc::new(AtomicBool::new(false)), event_tx }
+		Self {
+			join_handle: None,
+			running: Arc::new(AtomicBool::new(false)),
+			key_timeout: Arc::new(AtomicU64::new(50)),
+			event_tx,
+		}
+	}
+
+	// How long one poll waits for the first key; a running pump picks up changes on
+	// its next poll.
+	pub fn set_key_timeout(&self, timeout: Duration) {
+		self.key_timeout.store(timeout.as_millis().max(1) as u64, Ordering::SeqCst);
 	}
 
 	pub fn start(&mut self) {
@@ -74,30 +103,19 @@ impl InputPumpService {
 		let input_handler = InputHandler;
 		let running = self.running.clone();
 		running.store(true, Ordering::SeqCst);
+		let key_timeout = self.key_timeout.clone();
 		let event_tx = self.event_tx.clone();
 		let join_handle = thread::spawn(move || {
 			while running.load(Ordering::SeqCst) {
-				let poll_ready = match event::poll(Duration::from_millis(50)) {
-					Ok(ready) => ready,
+				let timeout = Duration::from_millis(key_timeout.load(Ordering::SeqCst));
+				let events = match read_event_batch(timeout) {
+					Ok(events) => events,
 					Err(err) => {
-						error!("input pump stopped: failed to poll terminal event: {}", err);
+						error!("input pump stopped: {}", err);
 						break;
 					}
 				};
-				if !poll_ready {
-					continue;
-				}
-				let evt = match event::read() {
-					Ok(evt) => evt,
-					Err(err) => {
-						error!("input pump stopped: failed to read terminal event: {}", err);
-						break;
-					}
-				};
-				let Some(action) = input_handler.action(&evt) else {
-					continue;
-				};
-				if event_tx.send(action).is_err() {
+				if input_handler.actions(events.as_slice()).into_iter().any(|action| event_tx.send(action).is_err()) {
 					break;
 				}
 			}
@@ -113,6 +131,26 @@ impl InputPumpService {
 	}
 }
 
+// Waits up to `timeout` for the first event, then drains whatever else the
+// terminal already has buffered without blocking.
+fn read_event_batch(timeout: Duration) -> Result<Vec<Event>, String> {
+	let mut events = Vec::new();
+	let mut wait = timeout;
+	while events.len() < MAX_EVENT_BATCH {
+		match event::poll(wait) {
+			Ok(true) => {}
+			Ok(false) => break,
+			Err(err) => return Err(format!("failed to poll terminal event: {}", err)),
+		}
+		match event::read() {
+			Ok(evt) => events.push(evt),
+			Err(err) => return Err(format!("failed to read terminal event: {}", err)),
+		}
+		wait = Duration::ZERO;
+	}
+	Ok(events)
+}
+
 impl Drop for InputPumpService {
 	fn drop(&mut self) { self.stop(); }
 }
@@ -157,6 +195,21 @@ mod tests {
 		assert!(action.is_none());
 	}
 
+	#[test]
+	fn should_map_event_batch_in_order_without_releases() {
+		let input_handler = InputHandler;
+
+		let actions = input_handler.actions(&[
+			Event::Key(make_key_event(CrosstermKeyEventKind::Press)),
+			Event::Key(make_key_event(CrosstermKeyEventKind::Release)),
+			Event::Resize(80, 24),
+		]);
+
+		assert_eq!(actions.len(), 2);
+		assert!(matches!(actions[0], AppAction::Editor(EditorAction::KeyPressed(_))));
+		assert!(matches!(actions[1], AppAction::Layout(LayoutAction::ViewportResized { width: 80, height: 24 })));
+	}
+
 	#[test]
 	fn should_map_resize_event() {
 		let input_handler = InputHandler;
@@ -172,6 +225,18 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn should_map_bracketed_paste_to_one_action() {
+		let input_handler = InputHandler;
+
+		let action = input_handler.action(&Event::Paste("one\rtwo".to_string()));
+
+		match action {
+			Some(AppAction::Paste(text)) => assert_eq!(text, "one\rtwo"),
+			_ => panic!("expected paste action"),
+		}
+	}
+
 	#[test]
 	fn should_map_f1_key_event() {
 		let input_handler = InputHandler;
@@ -190,4 +255,42 @@ mod tests {
 			_ => panic!("expected mapped F1 action"),
 		}
 	}
+
+	#[test]
+	fn should_map_legacy_ctrl_five_to_ctrl_right_bracket() {
+		let input_handler = InputHandler;
+		let action = input_handler.action(&Event::Key(CrosstermKeyEvent {
+			code:      CrosstermKeyCode::Char('5'),
+			modifiers: CrosstermKeyModifiers::CONTROL,
+			kind:      CrosstermKeyEventKind::Press,
+			state:     CrosstermKeyEventState::NONE,
+		}));
+
+		match action {
+			Some(AppAction::Editor(EditorAction::KeyPressed(key))) => {
+				assert_eq!(key.code, KeyCode::Char(']'));
+				assert_eq!(key.modifiers, KeyModifiers::CONTROL);
+			}
+			_ => panic!("expected mapped Ctrl-] action"),
+		}
+	}
+
+	#[test]
+	fn should_map_legacy_ctrl_six_to_ctrl_caret() {
+		let input_handler = InputHandler;
+		let action = input_handler.action(&Event::Key(CrosstermKeyEvent {
+			code:      CrosstermKeyCode::Char('6'),
+			modifiers: CrosstermKeyModifiers::CONTROL,
+			kind:      CrosstermKeyEventKind::Press,
+			state:     CrosstermKeyEventState::NONE,
+		}));
+
+		match action {
+			Some(AppAction::Editor(EditorAction::KeyPressed(key))) => {
+				assert_eq!(key.code, KeyCode::Char('^'));
+				assert_eq!(key.modifiers, KeyModifiers::CONTROL);
+			}
+			_ => panic!("expected mapped Ctrl-^ action"),
+		}
+	}
 }
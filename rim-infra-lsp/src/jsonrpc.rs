@@ -0,0 +1,111 @@
+use std::io::{self, BufRead};
+
+use serde_json::Value;
+use thiserror::Error;
+
+// Far above any real message; a larger header is a broken or hostile stream,
+// not something to allocate for.
+const MAX_CONTENT_LENGTH: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub(crate) enum JsonRpcError {
+	#[error("language server stream failed: {0}")]
+	Io(#[from] io::Error),
+	#[error("message header is missing Content-Length")]
+	MissingContentLength,
+	#[error("malformed message header: {0}")]
+	InvalidHeader(String),
+	#[error("message of {0} bytes exceeds the {MAX_CONTENT_LENGTH} byte limit")]
+	ContentTooLong(usize),
+	#[error("message body is not valid JSON: {0}")]
+	InvalidBody(#[from] serde_json::Error),
+}
+
+pub(crate) fn encode_message(message: &Value) -> Vec<u8> {
+	let body = message.to_string();
+	let mut bytes = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+	bytes.extend_from_slice(body.as_bytes());
+	bytes
+}
+
+// Reads one framed message. A clean end of stream before any header byte is
+// `None`; an end in the middle of a message is an error.
+pub(crate) fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>, JsonRpcError> {
+	let mut content_length = None;
+	let mut read_any_header = false;
+	loop {
+		let mut line = String::new();
+		if reader.read_line(&mut line)? == 0 {
+			if read_any_header {
+				return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+			}
+			return Ok(None);
+		}
+		read_any_header = true;
+		let line = line.trim_end_matches(['\r', '\n']);
+		if line.is_empty() {
+			break;
+		}
+		let Some((name, value)) = line.split_once(':') else {
+			return Err(JsonRpcError::InvalidHeader(line.to_string()));
+		};
+		// Content-Type is the only other header and is always utf-8 JSON.
+		if name.trim().eq_ignore_ascii_case("Content-Length") {
+			let length =
+				value.trim().parse::<usize>().map_err(|_| JsonRpcError::InvalidHeader(line.to_string()))?;
+			content_length = Some(length);
+		}
+	}
+	let content_length = content_length.ok_or(JsonRpcError::MissingContentLength)?;
+	if content_length > MAX_CONTENT_LENGTH {
+		return Err(JsonRpcError::ContentTooLong(content_length));
+	}
+	let mut body = vec![0; content_length];
+	reader.read_exact(&mut body)?;
+	Ok(Some(serde_json::from_slice(&body)?))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use serde_json::json;
+
+	use super::{JsonRpcError, encode_message, read_message};
+
+	#[test]
+	fn encode_message_should_prefix_body_with_byte_length() {
+		let bytes = encode_message(&json!({"method": "é"}));
+
+		assert_eq!(String::from_utf8(bytes).unwrap(), "Content-Length: 15\r\n\r\n{\"method\":\"é\"}");
+	}
+
+	#[test]
+	fn read_message_should_split_back_to_back_messages_and_stop_at_end_of_stream() {
+		let mut stream = encode_message(&json!({"id": 1, "result": null}));
+		stream.extend_from_slice(
+			b"content-length: 17\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n",
+		);
+		stream.extend_from_slice(br#"{"method":"ping"}"#);
+		let mut reader = Cursor::new(stream);
+
+		assert_eq!(read_message(&mut reader).unwrap(), Some(json!({"id": 1, "result": null})));
+		assert_eq!(read_message(&mut reader).unwrap(), Some(json!({"method": "ping"})));
+		assert_eq!(read_message(&mut reader).unwrap(), None);
+	}
+
+	#[test]
+	fn read_message_should_reject_missing_length_and_truncated_bodies() {
+		let mut missing = Cursor::new(b"Content-Type: application/json\r\n\r\n{}".to_vec());
+		assert!(matches!(read_message(&mut missing), Err(JsonRpcError::MissingContentLength)));
+
+		let mut truncated = Cursor::new(b"Content-Length: 10\r\n\r\n{}".to_vec());
+		assert!(matches!(read_message(&mut truncated), Err(JsonRpcError::Io(_))));
+
+		let mut garbage = Cursor::new(b"hello\r\n\r\n".to_vec());
+		assert!(matches!(read_message(&mut garbage), Err(JsonRpcError::InvalidHeader(_))));
+
+		let mut oversized = Cursor::new(b"Content-Length: 18446744073709551615\r\n\r\n{}".to_vec());
+		assert!(matches!(read_message(&mut oversized), Err(JsonRpcError::ContentTooLong(_))));
+	}
+}
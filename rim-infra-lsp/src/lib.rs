@@ -0,0 +1,502 @@
+use std::{collections::{HashMap, HashSet}, fs, io::{BufReader, Write}, path::PathBuf, process::{Child, ChildStdin, Command, Stdio}, sync::Mutex, thread, time::{Duration, Instant}};
+
+use rim_application::{action::{AppAction, LspAction}, state::DefinitionLocation};
+use rim_domain::model::BufferId;
+use rim_ports::{LanguageClient, LanguageClientError, LanguageDocument, LanguagePosition};
+use serde_json::Value;
+use tracing::{error, warn};
+
+mod jsonrpc;
+mod protocol;
+
+use jsonrpc::{encode_message, read_message};
+use protocol::{INITIALIZE_REQUEST_ID, buffer_diagnostics, definition_request, definition_targets, did_change_notification, did_close_notification, did_open_notification, exit_notification, file_uri, hover_request, hover_text, initialize_request, initialized_notification, is_initialize_response, path_from_file_uri, published_diagnostics, reply_to_server_request, shutdown_request, text_position};
+
+#[derive(dep_inj::DepInj)]
+#[target(LanguageClientImpl)]
+pub struct LanguageClientState {
+	worker_tx:   flume::Sender<LspWorkerEvent>,
+	worker_rx:   flume::Receiver<LspWorkerEvent>,
+	event_tx:    flume::Sender<AppAction>,
+	worker_join: Mutex<Option<thread::JoinHandle<()>>>,
+}
+
+impl AsRef<LanguageClientState> for LanguageClientState {
+	fn as_ref(&self) -> &LanguageClientState { self }
+}
+
+impl<Deps> LanguageClient for LanguageClientImpl<Deps>
+where Deps: AsRef<LanguageClientState>
+{
+	type BufferId = BufferId;
+
+	fn enqueue_lsp_open(
+		&self,
+		buffer_id: BufferId,
+		document: LanguageDocument,
+	) -> Result<(), LanguageClientError> {
+		self.enqueue(LspRequest::Open { buffer_id, document }, "open")
+	}
+
+	fn enqueue_lsp_change(&self, buffer_id: BufferId, text: String) -> Result<(), LanguageClientError> {
+		self.enqueue(LspRequest::Change { buffer_id, text }, "change")
+	}
+
+	fn enqueue_lsp_close(&self, buffer_id: BufferId) -> Result<(), LanguageClientError> {
+		self.enqueue(LspRequest::Close { buffer_id }, "close")
+	}
+
+	fn enqueue_lsp_definition(
+		&self,
+		buffer_id: BufferId,
+		position: LanguagePosition,
+	) -> Result<(), LanguageClientError> {
+		self.enqueue(LspRequest::Definition { buffer_id, position }, "definition")
+	}
+
+	fn enqueue_lsp_hover(
+		&self,
+		buffer_id: BufferId,
+		position: LanguagePosition,
+	) -> Result<(), LanguageClientError> {
+		self.enqueue(LspRequest::Hover { buffer_id, position }, "hover")
+	}
+}
+
+impl<Deps> LanguageClientImpl<Deps>
+where Deps: AsRef<LanguageClientState>
+{
+	fn enqueue(&self, request: LspRequest, operation: &'static str) -> Result<(), LanguageClientError> {
+		self.worker_tx.send(LspWorkerEvent::Request(request)).map_err(|err| {
+			error!("enqueue_lsp_{} failed: language server request channel is disconnected: {}", operation, err);
+			LanguageClientError::RequestChannelDisconnected { operation }
+		})
+	}
+}
+
+impl LanguageClientState {
+	pub fn new(event_tx: flume::Sender<AppAction>) -> Self {
+		let (worker_tx, worker_rx) = flume::unbounded();
+		Self { worker_tx, worker_rx, event_tx, worker_join: Mutex::new(None) }
+	}
+
+	pub fn start(&self) {
+		let mut worker_guard = self.worker_join.lock().expect("language client worker mutex poisoned");
+		if worker_guard.is_some() {
+			return;
+		}
+		let worker_rx = self.worker_rx.clone();
+		let worker_tx = self.worker_tx.clone();
+		let event_tx = self.event_tx.clone();
+		*worker_guard = Some(thread::spawn(move || LspWorker::new(worker_tx, event_tx).run(worker_rx)));
+	}
+
+	// The reader threads hold senders too, so the worker only stops when told to.
+	// A server that ignores `exit` must not hold up quitting the editor, so the
+	// worker is left behind once the timeout passes.
+	pub fn shutdown(&self) {
+		let Some(join) = self.worker_join.lock().ok().and_then(|mut guard| guard.take()) else {
+			return;
+		};
+		let _ = self.worker_tx.send(LspWorkerEvent::Shutdown);
+		let deadline = Instant::now() + WORKER_SHUTDOWN_TIMEOUT;
+		while !join.is_finished() && Instant::now() < deadline {
+			thread::sleep(Duration::from_millis(10));
+		}
+		if join.is_finished() {
+			let _ = join.join();
+		} else {
+			warn!("language client worker did not stop within {:?}", WORKER_SHUTDOWN_TIMEOUT);
+		}
+	}
+}
+
+impl Drop for LanguageClientState {
+	fn drop(&mut self) { self.shutdown(); }
+}
+
+// How long quitting waits for every server to be asked to exit and reaped.
+const WORKER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+enum LspWorkerEvent {
+	Request(LspRequest),
+	ServerMessage { server: usize, message: Value },
+	ServerExited { server: usize, reason: String },
+	Shutdown,
+}
+
+enum LspRequest {
+	Open { buffer_id: BufferId, document: LanguageDocument },
+	Change { buffer_id: BufferId, text: String },
+	Close { buffer_id: BufferId },
+	Definition { buffer_id: BufferId, position: LanguagePosition },
+	Hover { buffer_id: BufferId, position: LanguagePosition },
+}
+
+#[derive(Clone, Copy)]
+enum PendingRequest {
+	Definition,
+	Hover,
+}
+
+impl PendingRequest {
+	fn name(self) -> &'static str {
+		match self {
+			Self::Definition => "definition",
+			Self::Hover => "hover",
+		}
+	}
+}
+
+struct LanguageServer {
+	command:     Vec<String>,
+	child:       Child,
+	stdin:       ChildStdin,
+	initialized: bool,
+	// Notifications written before the initialize response arrives.
+	pending:     Vec<Value>,
+}
+
+// How long a server gets to exit on its own after `shutdown` and `exit`.
+const SERVER_EXIT_GRACE: Duration = Duration::from_millis(200);
+
+// Asks the server to exit cleanly, then kills whatever is left and reaps it so
+// no zombie outlives the editor.
+impl Drop for LanguageServer {
+	fn drop(&mut self) {
+		if self.initialized {
+			for message in [shutdown_request(), exit_notification()] {
+				let _ = self.stdin.write_all(&encode_message(&message)).and_then(|()| self.stdin.flush());
+			}
+			let deadline = Instant::now() + SERVER_EXIT_GRACE;
+			while Instant::now() < deadline {
+				match self.child.try_wait() {
+					Ok(None) => thread::sleep(Duration::from_millis(10)),
+					Ok(Some(_)) | Err(_) => break,
+				}
+			}
+		}
+		let _ = self.child.kill();
+		let _ = self.child.wait();
+	}
+}
+
+// Diagnostics are mapped against the text the server last saw.
+struct TrackedDocument {
+	server:  usize,
+	path:    PathBuf,
+	uri:     String,
+	version: i64,
+	text:    String,
+}
+
+struct LspWorker {
+	worker_tx:       flume::Sender<LspWorkerEvent>,
+	event_tx:        flume::Sender<AppAction>,
+	servers:         HashMap<usize, LanguageServer>,
+	next_server:     usize,
+	failed_commands: HashSet<Vec<String>>,
+	documents:       HashMap<BufferId, TrackedDocument>,
+	next_request_id: u64,
+	// Keyed by server and request id; servers number their replies with ours.
+	requests:        HashMap<(usize, u64), PendingRequest>,
+}
+
+impl LspWorker {
+	fn new(worker_tx: flume::Sender<LspWorkerEvent>, event_tx: flume::Sender<AppAction>) -> Self {
+		Self {
+			worker_tx,
+			event_tx,
+			servers: HashMap::new(),
+			next_server: 0,
+			failed_commands: HashSet::new(),
+			documents: HashMap::new(),
+			next_request_id: INITIALIZE_REQUEST_ID + 1,
+			requests: HashMap::new(),
+		}
+	}
+
+	fn run(mut self, worker_rx: flume::Receiver<LspWorkerEvent>) {
+		while let Ok(event) = worker_rx.recv() {
+			let keep_running = match event {
+				LspWorkerEvent::Request(request) => {
+					self.handle_request(request);
+					true
+				}
+				LspWorkerEvent::ServerMessage { server, message } => self.handle_server_message(server, message),
+				LspWorkerEvent::ServerExited { server, reason } => self.handle_server_exit(server, reason),
+				LspWorkerEvent::Shutdown => {
+					// Dropping a server sends it `shutdown` and `exit`.
+					self.servers.clear();
+					false
+				}
+			};
+			if !keep_running {
+				return;
+			}
+		}
+	}
+
+	fn handle_request(&mut self, request: LspRequest) {
+		match request {
+			LspRequest::Open { buffer_id, document } => {
+				let Some(server) = self.server_for(&document) else {
+					return;
+				};
+				let path =
+					if document.path.is_absolute() { document.path } else { document.root.join(document.path) };
+				let uri = file_uri(path.as_path());
+				self.send(
+					server,
+					did_open_notification(uri.as_str(), document.language_id.as_str(), 1, &document.text),
+				);
+				self.documents.insert(buffer_id, TrackedDocument {
+					server,
+					path,
+					uri,
+					version: 1,
+					text: document.text,
+				});
+			}
+			LspRequest::Change { buffer_id, text } => {
+				let Some(document) = self.documents.get_mut(&buffer_id) else {
+					return;
+				};
+				document.version += 1;
+				document.text = text;
+				let message = did_change_notification(document.uri.as_str(), document.version, &document.text);
+				let server = document.server;
+				self.send(server, message);
+			}
+			LspRequest::Close { buffer_id } => {
+				if let Some(document) = self.documents.remove(&buffer_id) {
+					self.send(document.server, did_close_notification(document.uri.as_str()));
+				}
+			}
+			LspRequest::Definition { buffer_id, position } => {
+				self.send_request(buffer_id, position, PendingRequest::Definition);
+			}
+			LspRequest::Hover { buffer_id, position } => {
+				self.send_request(buffer_id, position, PendingRequest::Hover);
+			}
+		}
+	}
+
+	fn send_request(&mut self, buffer_id: BufferId, position: LanguagePosition, kind: PendingRequest) {
+		let Some(document) = self.documents.get(&buffer_id) else {
+			let _ = self.event_tx.send(AppAction::Lsp(LspAction::RequestFailed {
+				request: kind.name(),
+				reason:  "no language server for this buffer".to_string(),
+			}));
+			return;
+		};
+		let id = self.next_request_id;
+		self.next_request_id += 1;
+		let message = match kind {
+			PendingRequest::Definition => {
+				definition_request(id, document.uri.as_str(), document.text.as_str(), position)
+			}
+			PendingRequest::Hover => hover_request(id, document.uri.as_str(), document.text.as_str(), position),
+		};
+		let server = document.server;
+		self.requests.insert((server, id), kind);
+		self.send(server, message);
+	}
+
+	// `false` only when the application side of the bus is gone.
+	fn handle_server_message(&mut self, server: usize, message: Value) -> bool {
+		if is_initialize_response(&message) {
+			if let Some(error) = message.get("error") {
+				return self.handle_server_exit(server, format!("initialize failed: {}", error));
+			}
+			if let Some(language_server) = self.servers.get_mut(&server) {
+				language_server.initialized = true;
+				let pending = std::mem::take(&mut language_server.pending);
+				self.send(server, initialized_notification());
+				for message in pending {
+					self.send(server, message);
+				}
+			}
+			return true;
+		}
+		if message.get("method").is_some() && message.get("id").is_some() {
+			if let Some(reply) = reply_to_server_request(&message) {
+				self.write(server, &reply);
+			}
+			return true;
+		}
+		if message.get("method").is_none()
+			&& let Some(id) = message.get("id").and_then(Value::as_u64)
+			&& let Some(kind) = self.requests.remove(&(server, id))
+		{
+			return self.handle_response(kind, &message);
+		}
+		let Some((uri, diagnostics)) = published_diagnostics(&message) else {
+			return true;
+		};
+		let uri_path = path_from_file_uri(uri);
+		let Some((buffer_id, document)) = self.documents.iter().find(|(_, document)| {
+			document.server == server && (document.uri == uri || uri_path.as_ref() == Some(&document.path))
+		}) else {
+			return true;
+		};
+		let diagnostics = buffer_diagnostics(diagnostics, document.text.as_str());
+		self
+			.event_tx
+			.send(AppAction::Lsp(LspAction::DiagnosticsPublished { buffer_id: *buffer_id, diagnostics }))
+			.is_ok()
+	}
+
+	fn handle_response(&self, kind: PendingRequest, message: &Value) -> bool {
+		let action = if let Some(error) = message.get("error") {
+			let reason = error.get("message").and_then(Value::as_str).unwrap_or("server error").to_string();
+			LspAction::RequestFailed { request: kind.name(), reason }
+		} else {
+			let result = message.get("result").unwrap_or(&Value::Null);
+			match kind {
+				PendingRequest::Definition => {
+					LspAction::DefinitionFound { locations: self.definition_locations(result) }
+				}
+				PendingRequest::Hover => LspAction::HoverReceived { contents: hover_text(result) },
+			}
+		};
+		self.event_tx.send(AppAction::Lsp(action)).is_ok()
+	}
+
+	// Columns are mapped against the tracked text when the target is open, and
+	// against the file on disk otherwise.
+	fn definition_locations(&self, result: &Value) -> Vec<DefinitionLocation> {
+		definition_targets(result)
+			.into_iter()
+			.filter_map(|(uri, start)| {
+				let path = path_from_file_uri(uri)?;
+				let tracked = self.documents.values().find(|document| document.path == path);
+				let text = match tracked {
+					Some(document) => document.text.clone(),
+					None => fs::read_to_string(path.as_path()).unwrap_or_default(),
+				};
+				let (row, col) = text_position(text.as_str(), start)?;
+				Some(DefinitionLocation { path, row, col })
+			})
+			.collect()
+	}
+
+	fn handle_server_exit(&mut self, server: usize, reason: String) -> bool {
+		let Some(language_server) = self.servers.remove(&server) else {
+			return true;
+		};
+		self.documents.retain(|_, document| document.server != server);
+		self.requests.retain(|(request_server, _), _| *request_server != server);
+		let command = language_server.command.join(" ");
+		// A crashed server stays down for the rest of the session.
+		self.failed_commands.insert(language_server.command.clone());
+		self.report_failure(command, reason)
+	}
+
+	fn report_failure(&self, command: String, reason: String) -> bool {
+		self.event_tx.send(AppAction::Lsp(LspAction::ServerFailed { command, reason })).is_ok()
+	}
+
+	fn server_for(&mut self, document: &LanguageDocument) -> Option<usize> {
+		if let Some((id, _)) = self.servers.iter().find(|(_, server)| server.command == document.server_command) {
+			return Some(*id);
+		}
+		if self.failed_commands.contains(&document.server_command) {
+			return None;
+		}
+		match self.spawn_server(document) {
+			Ok(id) => Some(id),
+			Err(reason) => {
+				self.failed_commands.insert(document.server_command.clone());
+				self.report_failure(document.server_command.join(" "), reason);
+				None
+			}
+		}
+	}
+
+	fn spawn_server(&mut self, document: &LanguageDocument) -> Result<usize, String> {
+		let (program, args) = document.server_command.split_first().ok_or_else(|| "empty command".to_string())?;
+		let mut child = Command::new(program)
+			.args(args)
+			.current_dir(document.root.as_path())
+			.stdin(Stdio::piped())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::null())
+			.spawn()
+			.map_err(|err| format!("start failed: {}", err))?;
+		let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+			let _ = child.kill();
+			return Err("server pipes unavailable".to_string());
+		};
+		let id = self.next_server;
+		self.next_server += 1;
+		let worker_tx = self.worker_tx.clone();
+		thread::spawn(move || {
+			let mut reader = BufReader::new(stdout);
+			let reason = loop {
+				match read_message(&mut reader) {
+					Ok(Some(message)) => {
+						if worker_tx.send(LspWorkerEvent::ServerMessage { server: id, message }).is_err() {
+							return;
+						}
+					}
+					Ok(None) => break "server exited".to_string(),
+					Err(err) => break err.to_string(),
+				}
+			};
+			let _ = worker_tx.send(LspWorkerEvent::ServerExited { server: id, reason });
+		});
+		let server = LanguageServer {
+			command: document.server_command.clone(),
+			child,
+			stdin,
+			initialized: false,
+			pending: Vec::new(),
+		};
+		self.servers.insert(id, server);
+		self.write(id, &initialize_request(document.root.as_path()));
+		Ok(id)
+	}
+
+	fn send(&mut self, server: usize, message: Value) {
+		let Some(language_server) = self.servers.get_mut(&server) else {
+			return;
+		};
+		if !language_server.initialized {
+			language_server.pending.push(message);
+			return;
+		}
+		self.write(server, &message);
+	}
+
+	fn write(&mut self, server: usize, message: &Value) {
+		let Some(language_server) = self.servers.get_mut(&server) else {
+			return;
+		};
+		let result =
+			language_server.stdin.write_all(&encode_message(message)).and_then(|()| language_server.stdin.flush());
+		if let Err(err) = result {
+			// The reader thread reports the exit once stdout closes.
+			warn!("language server write failed: command={} error={}", language_server.command.join(" "), err);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Instant;
+
+	use super::{LanguageClientState, WORKER_SHUTDOWN_TIMEOUT};
+
+	#[test]
+	fn shutdown_should_stop_the_worker_before_the_timeout() {
+		let (event_tx, _event_rx) = flume::unbounded();
+		let client = LanguageClientState::new(event_tx);
+		client.start();
+
+		let started = Instant::now();
+		client.shutdown();
+		assert!(started.elapsed() < WORKER_SHUTDOWN_TIMEOUT);
+		assert!(client.worker_join.lock().expect("worker mutex").is_none());
+	}
+}
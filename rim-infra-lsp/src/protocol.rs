@@ -0,0 +1,405 @@
+use std::path::{Path, PathBuf};
+
+use rim_application::state::{BufferDiagnostic, DiagnosticSeverity};
+use rim_ports::LanguagePosition;
+use serde_json::{Value, json};
+
+pub(crate) const INITIALIZE_REQUEST_ID: u64 = 1;
+// Below every id the worker hands out, so the reply is never mistaken for one.
+pub(crate) const SHUTDOWN_REQUEST_ID: u64 = 0;
+
+pub(crate) fn initialize_request(root: &Path) -> Value {
+	json!({
+		"jsonrpc": "2.0",
+		"id": INITIALIZE_REQUEST_ID,
+		"method": "initialize",
+		"params": {
+			"processId": std::process::id(),
+			"clientInfo": { "name": "rim" },
+			"rootUri": file_uri(root),
+			"workspaceFolders": [{ "uri": file_uri(root), "name": root.display().to_string() }],
+			"capabilities": {
+				"textDocument": {
+					"synchronization": { "dynamicRegistration": false },
+					"publishDiagnostics": { "relatedInformation": false },
+				},
+				"general": { "positionEncodings": ["utf-16"] },
+			},
+		},
+	})
+}
+
+pub(crate) fn notification(method: &str, params: Value) -> Value {
+	json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+pub(crate) fn initialized_notification() -> Value { notification("initialized", json!({})) }
+
+pub(crate) fn shutdown_request() -> Value { request(SHUTDOWN_REQUEST_ID, "shutdown", Value::Null) }
+
+pub(crate) fn exit_notification() -> Value { notification("exit", Value::Null) }
+
+pub(crate) fn did_open_notification(uri: &str, language_id: &str, version: i64, text: &str) -> Value {
+	notification(
+		"textDocument/didOpen",
+		json!({ "textDocument": { "uri": uri, "languageId": language_id, "version": version, "text": text } }),
+	)
+}
+
+// Always a full-text change; every server accepts one whatever sync kind it
+// asked for.
+pub(crate) fn did_change_notification(uri: &str, version: i64, text: &str) -> Value {
+	notification(
+		"textDocument/didChange",
+		json!({ "textDocument": { "uri": uri, "version": version }, "contentChanges": [{ "text": text }] }),
+	)
+}
+
+pub(crate) fn did_close_notification(uri: &str) -> Value {
+	notification("textDocument/didClose", json!({ "textDocument": { "uri": uri } }))
+}
+
+pub(crate) fn request(id: u64, method: &str, params: Value) -> Value {
+	json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+// `position` counts chars; the request counts UTF-16 units of that line.
+pub(crate) fn position_request(
+	id: u64,
+	method: &str,
+	uri: &str,
+	text: &str,
+	position: LanguagePosition,
+) -> Value {
+	let character = text
+		.split('\n')
+		.nth(position.row)
+		.map_or(0, |line| line.chars().take(position.col).map(char::len_utf16).sum::<usize>());
+	request(
+		id,
+		method,
+		json!({ "textDocument": { "uri": uri }, "position": { "line": position.row, "character": character } }),
+	)
+}
+
+pub(crate) fn definition_request(id: u64, uri: &str, text: &str, position: LanguagePosition) -> Value {
+	position_request(id, "textDocument/definition", uri, text, position)
+}
+
+pub(crate) fn hover_request(id: u64, uri: &str, text: &str, position: LanguagePosition) -> Value {
+	position_request(id, "textDocument/hover", uri, text, position)
+}
+
+// Server-to-client requests still need an answer or some servers stall. We
+// have no settings to offer, so `workspace/configuration` gets one null per
+// item and everything else a null result.
+pub(crate) fn reply_to_server_request(request: &Value) -> Option<Value> {
+	let id = request.get("id")?;
+	let method = request.get("method")?.as_str()?;
+	let result = match method {
+		"workspace/configuration" => {
+			let items = request.pointer("/params/items").and_then(Value::as_array).map_or(0, Vec::len);
+			Value::Array(vec![Value::Null; items])
+		}
+		_ => Value::Null,
+	};
+	Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+pub(crate) fn is_initialize_response(message: &Value) -> bool {
+	message.get("method").is_none() && message.get("id").and_then(Value::as_u64) == Some(INITIALIZE_REQUEST_ID)
+}
+
+// The target uri and raw start position of every entry in a definition
+// result, which may be one `Location`, a list of them, or `LocationLink`s.
+pub(crate) fn definition_targets(result: &Value) -> Vec<(&str, &Value)> {
+	let entries = match result {
+		Value::Array(entries) => entries.iter().collect::<Vec<_>>(),
+		Value::Null => Vec::new(),
+		entry => vec![entry],
+	};
+	entries
+		.into_iter()
+		.filter_map(|entry| {
+			let (uri, range) = match entry.get("targetUri") {
+				Some(uri) => (uri, entry.get("targetSelectionRange").or_else(|| entry.get("targetRange"))?),
+				None => (entry.get("uri")?, entry.get("range")?),
+			};
+			Some((uri.as_str()?, range.get("start")?))
+		})
+		.collect()
+}
+
+// Maps one raw position onto the char columns of `text`, for definition
+// targets that are not in a tracked document.
+pub(crate) fn text_position(text: &str, position: &Value) -> Option<(usize, usize)> {
+	let lines = text.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line)).collect::<Vec<_>>();
+	buffer_position(&lines, position)
+}
+
+// Hover contents come as `MarkupContent`, a `MarkedString`, or a list of
+// marked strings, which end up separated by blank lines.
+pub(crate) fn hover_text(result: &Value) -> String {
+	fn marked_string(value: &Value) -> Option<&str> {
+		match value {
+			Value::String(text) => Some(text.as_str()),
+			value => value.get("value")?.as_str(),
+		}
+	}
+	let Some(contents) = result.get("contents") else {
+		return String::new();
+	};
+	match contents {
+		Value::Array(parts) => parts
+			.iter()
+			.filter_map(marked_string)
+			.map(str::trim)
+			.filter(|part| !part.is_empty())
+			.collect::<Vec<_>>()
+			.join("\n\n"),
+		contents => marked_string(contents).unwrap_or_default().trim().to_string(),
+	}
+}
+
+// The uri and raw diagnostics of a `textDocument/publishDiagnostics`.
+pub(crate) fn published_diagnostics(message: &Value) -> Option<(&str, &[Value])> {
+	if message.get("method")?.as_str()? != "textDocument/publishDiagnostics" {
+		return None;
+	}
+	let params = message.get("params")?;
+	let uri = params.get("uri")?.as_str()?;
+	let diagnostics = params.get("diagnostics")?.as_array()?;
+	Some((uri, diagnostics.as_slice()))
+}
+
+// Maps LSP ranges, which count UTF-16 code units, onto the char columns of
+// `text`. Entries without a usable range are dropped.
+pub(crate) fn buffer_diagnostics(diagnostics: &[Value], text: &str) -> Vec<BufferDiagnostic> {
+	let lines = text.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line)).collect::<Vec<_>>();
+	diagnostics
+		.iter()
+		.filter_map(|diagnostic| {
+			let range = diagnostic.get("range")?;
+			let (start_row, start_col) = buffer_position(&lines, range.get("start")?)?;
+			let (end_row, end_col) = buffer_position(&lines, range.get("end")?)?;
+			let severity = match diagnostic.get("severity").and_then(Value::as_u64) {
+				Some(2) => DiagnosticSeverity::Warning,
+				Some(3) => DiagnosticSeverity::Information,
+				Some(4) => DiagnosticSeverity::Hint,
+				_ => DiagnosticSeverity::Error,
+			};
+			let message = diagnostic.get("message").and_then(Value::as_str).unwrap_or_default().to_string();
+			Some(BufferDiagnostic { start_row, start_col, end_row, end_col, severity, message })
+		})
+		.collect()
+}
+
+// Positions past the end of a line or of the text clamp to the last char, the
+// way servers expect clients to treat them.
+fn buffer_position(lines: &[&str], position: &Value) -> Option<(usize, usize)> {
+	let row = position.get("line")?.as_u64()? as usize;
+	let utf16_col = position.get("character")?.as_u64()? as usize;
+	let last_row = lines.len().saturating_sub(1);
+	let Some(line) = lines.get(row) else {
+		return Some((last_row, lines.last().map_or(0, |line| line.chars().count())));
+	};
+	let mut units = 0;
+	let mut col = 0;
+	for ch in line.chars() {
+		if units >= utf16_col {
+			break;
+		}
+		units += ch.len_utf16();
+		col += 1;
+	}
+	Some((row, col))
+}
+
+pub(crate) fn file_uri(path: &Path) -> String {
+	let mut uri = String::from("file://");
+	let path = path.to_string_lossy();
+	if !path.starts_with('/') {
+		uri.push('/');
+	}
+	for byte in path.replace('\\', "/").bytes() {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => uri.push(byte as char),
+			_ => uri.push_str(format!("%{:02X}", byte).as_str()),
+		}
+	}
+	uri
+}
+
+pub(crate) fn path_from_file_uri(uri: &str) -> Option<PathBuf> {
+	let encoded = uri.strip_prefix("file://")?;
+	let bytes = encoded.as_bytes();
+	let mut decoded = Vec::with_capacity(bytes.len());
+	let mut index = 0;
+	while index < bytes.len() {
+		if bytes[index] == b'%' {
+			let hex = std::str::from_utf8(bytes.get(index + 1..index + 3)?).ok()?;
+			decoded.push(u8::from_str_radix(hex, 16).ok()?);
+			index += 3;
+		} else {
+			decoded.push(bytes[index]);
+			index += 1;
+		}
+	}
+	Some(PathBuf::from(String::from_utf8(decoded).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::{Path, PathBuf};
+
+	use rim_application::state::{BufferDiagnostic, DiagnosticSeverity};
+	use rim_ports::LanguagePosition;
+	use serde_json::json;
+
+	use super::{buffer_diagnostics, definition_request, definition_targets, did_change_notification, file_uri, hover_request, hover_text, path_from_file_uri, published_diagnostics, reply_to_server_request, text_position};
+
+	#[test]
+	fn buffer_diagnostics_should_map_utf16_ranges_to_char_columns() {
+		// "🦀" is two UTF-16 units but one char; "é" is one of each.
+		let text = "let 🦀 = é;\r\nfoo()\n";
+		let message = json!({
+			"method": "textDocument/publishDiagnostics",
+			"params": {
+				"uri": "file:///tmp/main.rs",
+				"diagnostics": [
+					{
+						"range": { "start": { "line": 0, "character": 4 }, "end": { "line": 0, "character": 6 } },
+						"severity": 2,
+						"message": "unused variable",
+					},
+					{
+						"range": { "start": { "line": 0, "character": 9 }, "end": { "line": 1, "character": 99 } },
+						"message": "mismatched types",
+					},
+					{
+						"range": { "start": { "line": 7, "character": 0 }, "end": { "line": 9, "character": 0 } },
+						"severity": 4,
+						"message": "past the end",
+					},
+					{ "message": "no range" },
+				],
+			},
+		});
+
+		let (uri, diagnostics) = published_diagnostics(&message).expect("publishDiagnostics should parse");
+		assert_eq!(uri, "file:///tmp/main.rs");
+		assert_eq!(buffer_diagnostics(diagnostics, text), vec![
+			BufferDiagnostic {
+				start_row: 0,
+				start_col: 4,
+				end_row:   0,
+				end_col:   5,
+				severity:  DiagnosticSeverity::Warning,
+				message:   "unused variable".to_string(),
+			},
+			BufferDiagnostic {
+				start_row: 0,
+				start_col: 8,
+				end_row:   1,
+				end_col:   5,
+				severity:  DiagnosticSeverity::Error,
+				message:   "mismatched types".to_string(),
+			},
+			BufferDiagnostic {
+				start_row: 2,
+				start_col: 0,
+				end_row:   2,
+				end_col:   0,
+				severity:  DiagnosticSeverity::Hint,
+				message:   "past the end".to_string(),
+			},
+		]);
+	}
+
+	#[test]
+	fn file_uri_should_percent_encode_and_round_trip() {
+		let path = Path::new("/tmp/my project/ü.rs");
+
+		assert_eq!(file_uri(path), "file:///tmp/my%20project/%C3%BC.rs");
+		assert_eq!(path_from_file_uri(file_uri(path).as_str()), Some(PathBuf::from("/tmp/my project/ü.rs")));
+		assert_eq!(path_from_file_uri("https://example.com"), None);
+	}
+
+	#[test]
+	fn server_requests_should_get_a_reply_shaped_for_the_method() {
+		let configuration = json!({
+			"jsonrpc": "2.0",
+			"id": 7,
+			"method": "workspace/configuration",
+			"params": { "items": [{ "section": "a" }, { "section": "b" }] },
+		});
+		let progress = json!({ "jsonrpc": "2.0", "id": "p1", "method": "window/workDoneProgress/create" });
+
+		assert_eq!(
+			reply_to_server_request(&configuration),
+			Some(json!({ "jsonrpc": "2.0", "id": 7, "result": [null, null] }))
+		);
+		assert_eq!(
+			reply_to_server_request(&progress),
+			Some(json!({ "jsonrpc": "2.0", "id": "p1", "result": null }))
+		);
+		assert_eq!(reply_to_server_request(&did_change_notification("file:///a", 2, "x")), None);
+	}
+
+	#[test]
+	fn position_requests_should_count_utf16_units_up_to_the_cursor() {
+		let text = "fn main() {\n    let 🦀 = widget();\n}\n";
+		let position = LanguagePosition { row: 1, col: 12 };
+
+		assert_eq!(
+			definition_request(4, "file:///tmp/main.rs", text, position),
+			json!({
+				"jsonrpc": "2.0",
+				"id": 4,
+				"method": "textDocument/definition",
+				"params": {
+					"textDocument": { "uri": "file:///tmp/main.rs" },
+					"position": { "line": 1, "character": 13 },
+				},
+			})
+		);
+		assert_eq!(
+			hover_request(5, "file:///tmp/main.rs", text, LanguagePosition { row: 0, col: 3 })["params"],
+			json!({ "textDocument": { "uri": "file:///tmp/main.rs" }, "position": { "line": 0, "character": 3 } })
+		);
+	}
+
+	#[test]
+	fn definition_targets_should_accept_locations_and_location_links() {
+		let location = json!({
+			"uri": "file:///tmp/lib.rs",
+			"range": { "start": { "line": 2, "character": 7 }, "end": { "line": 2, "character": 13 } },
+		});
+		let link = json!([{
+			"targetUri": "file:///tmp/widget.rs",
+			"targetRange": { "start": { "line": 0, "character": 0 }, "end": { "line": 9, "character": 0 } },
+			"targetSelectionRange": { "start": { "line": 4, "character": 2 }, "end": { "line": 4, "character": 8 } },
+		}]);
+
+		let single = definition_targets(&location);
+		assert_eq!(single.len(), 1);
+		assert_eq!(single[0].0, "file:///tmp/lib.rs");
+		assert_eq!(text_position("\n\npub fn widget() {}\n", single[0].1), Some((2, 7)));
+		let linked = definition_targets(&link);
+		assert_eq!(linked[0].0, "file:///tmp/widget.rs");
+		assert_eq!(linked[0].1, &json!({ "line": 4, "character": 2 }));
+		assert_eq!(definition_targets(&json!([location, location])).len(), 2);
+		assert!(definition_targets(&json!(null)).is_empty());
+	}
+
+	#[test]
+	fn hover_text_should_flatten_markup_and_marked_strings() {
+		let markup = json!({ "contents": { "kind": "markdown", "value": "```rust\nfn widget()\n```\n" } });
+		let marked =
+			json!({ "contents": [{ "language": "rust", "value": "fn widget()" }, "Draws a widget.", ""] });
+
+		assert_eq!(hover_text(&markup), "```rust\nfn widget()\n```");
+		assert_eq!(hover_text(&marked), "fn widget()\n\nDraws a widget.");
+		assert_eq!(hover_text(&json!(null)), "");
+	}
+}
@@ -0,0 +1,132 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+// Expands `*`, `?`, `[...]` and `**` component by component; relative patterns
+// resolve against `root`. Only regular files are returned, sorted by path.
+pub(crate) fn expand_glob_blocking(root: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+	let pattern_path = Path::new(pattern);
+	let mut base = if pattern_path.is_absolute() { PathBuf::new() } else { root.to_path_buf() };
+	let mut segments = Vec::new();
+	for component in pattern_path.components() {
+		match component {
+			Component::Normal(segment) => segments.push(segment.to_string_lossy().into_owned()),
+			Component::ParentDir if segments.is_empty() => base.push(".."),
+			Component::ParentDir => segments.push("..".to_string()),
+			Component::CurDir => {}
+			Component::RootDir | Component::Prefix(_) => base.push(component.as_os_str()),
+		}
+	}
+
+	let mut matches = Vec::new();
+	expand_segments(base.as_path(), segments.as_slice(), &mut matches)?;
+	matches.sort();
+	matches.dedup();
+	Ok(matches)
+}
+
+fn expand_segments(dir: &Path, segments: &[String], matches: &mut Vec<PathBuf>) -> Result<()> {
+	let Some((segment, rest)) = segments.split_first() else {
+		if dir.is_file() {
+			matches.push(dir.to_path_buf());
+		}
+		return Ok(());
+	};
+	if segment == "**" {
+		expand_segments(dir, rest, matches)?;
+		for child in read_child_dirs(dir)? {
+			expand_segments(child.as_path(), segments, matches)?;
+		}
+		return Ok(());
+	}
+	if !has_wildcard(segment) {
+		let next = dir.join(segment);
+		if next.exists() {
+			expand_segments(next.as_path(), rest, matches)?;
+		}
+		return Ok(());
+	}
+	if !dir.is_dir() {
+		return Ok(());
+	}
+	let entries = std::fs::read_dir(dir).with_context(|| format!("read dir failed: {}", dir.display()))?;
+	for entry_result in entries {
+		let entry = entry_result.with_context(|| format!("read dir entry failed: {}", dir.display()))?;
+		let name = entry.file_name().to_string_lossy().into_owned();
+		// Like a shell, wildcards only match dotfiles when the pattern asks for them.
+		if name.starts_with('.') && !segment.starts_with('.') {
+			continue;
+		}
+		if matches_segment(segment, name.as_str()) {
+			expand_segments(entry.path().as_path(), rest, matches)?;
+		}
+	}
+	Ok(())
+}
+
+fn has_wildcard(segment: &str) -> bool { segment.contains(['*', '?', '[']) }
+
+fn read_child_dirs(dir: &Path) -> Result<Vec<PathBuf>> {
+	if !dir.is_dir() {
+		return Ok(Vec::new());
+	}
+	let mut children = Vec::new();
+	for entry_result in std::fs::read_dir(dir).with_context(|| format!("read dir failed: {}", dir.display()))? {
+		let entry = entry_result.with_context(|| format!("read dir entry failed: {}", dir.display()))?;
+		let file_type =
+			entry.file_type().with_context(|| format!("read file type failed: {}", entry.path().display()))?;
+		if file_type.is_dir() && !entry.file_name().to_string_lossy().starts_with('.') {
+			children.push(entry.path());
+		}
+	}
+	Ok(children)
+}
+
+fn matches_segment(pattern: &str, name: &str) -> bool {
+	let pattern = pattern.chars().collect::<Vec<_>>();
+	let name = name.chars().collect::<Vec<_>>();
+	matches_from(pattern.as_slice(), name.as_slice())
+}
+
+fn matches_from(pattern: &[char], name: &[char]) -> bool {
+	match pattern.split_first() {
+		None => name.is_empty(),
+		Some(('*', rest)) => (0..=name.len()).any(|skip| matches_from(rest, &name[skip..])),
+		Some(('?', rest)) => !name.is_empty() && matches_from(rest, &name[1..]),
+		Some(('[', rest)) => {
+			let Some((&first, name_rest)) = name.split_first() else {
+				return false;
+			};
+			match match_char_class(rest, first) {
+				Some((matched, class_len)) => matched && matches_from(&rest[class_len..], name_rest),
+				// An unterminated class is a literal `[`.
+				None => first == '[' && matches_from(rest, name_rest),
+			}
+		}
+		Some((&ch, rest)) => name.first() == Some(&ch) && matches_from(rest, &name[1..]),
+	}
+}
+
+// Returns whether `ch` is in the class and how many pattern chars the class
+// used, including the closing `]`.
+fn match_char_class(class: &[char], ch: char) -> Option<(bool, usize)> {
+	let negated = matches!(class.first(), Some('!' | '^'));
+	let mut index = usize::from(negated);
+	let mut matched = false;
+	let mut first = true;
+	while index < class.len() {
+		let current = class[index];
+		if current == ']' && !first {
+			return Some((matched != negated, index + 1));
+		}
+		if class.get(index + 1) == Some(&'-') && class.get(index + 2).is_some_and(|end| *end != ']') {
+			matched |= (current..=class[index + 2]).contains(&ch);
+			index += 3;
+		} else {
+			matched |= current == ch;
+			index += 1;
+		}
+		first = false;
+	}
+	None
+}
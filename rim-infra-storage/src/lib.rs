@@ -3,10 +3,12 @@ use std::{path::PathBuf, sync::Mutex, thread, time::Duration};
 use anyhow::Result;
 use rim_application::{action::{AppAction, FileLoadSource}, ports::SwapEditOp};
 use rim_domain::model::{BufferId, PersistedBufferHistory, WorkspaceSessionSnapshot};
-use rim_ports::{StorageIo, StorageIoError};
+use rim_ports::{FileSaveOptions, StorageIo, StorageIoError};
 use tracing::error;
 
+mod glob;
 mod path_codec;
+mod save;
 mod session;
 mod swap_session;
 mod undo_history;
@@ -62,6 +64,28 @@ where Deps: AsRef<StorageIoState>
 		)
 	}
 
+	fn enqueue_save_session_file(
+		&self,
+		path: PathBuf,
+		snapshot: WorkspaceSessionSnapshot,
+	) -> Result<(), StorageIoError> {
+		send_request(
+			&self.request_tx,
+			StorageIoRequest::SaveSessionFile { path, snapshot },
+			"enqueue_save_session_file",
+			"save_session_file",
+		)
+	}
+
+	fn enqueue_load_session_file(&self, path: PathBuf) -> Result<(), StorageIoError> {
+		send_request(
+			&self.request_tx,
+			StorageIoRequest::LoadSessionFile { path },
+			"enqueue_load_session_file",
+			"load_session_file",
+		)
+	}
+
 	fn enqueue_load(&self, buffer_id: BufferId, path: PathBuf) -> Result<(), StorageIoError> {
 		send_request(
 			&self.request_tx,
@@ -89,10 +113,43 @@ where Deps: AsRef<StorageIoState>
 		)
 	}
 
-	fn enqueue_save(&self, buffer_id: BufferId, path: PathBuf, text: String) -> Result<(), StorageIoError> {
+	fn enqueue_expand_glob(&self, workspace_root: PathBuf, pattern: String) -> Result<(), StorageIoError> {
+		send_request(
+			&self.request_tx,
+			StorageIoRequest::ExpandGlob { workspace_root, pattern },
+			"enqueue_expand_glob",
+			"expand_glob",
+		)
+	}
+
+	fn enqueue_load_tags(&self, tags_path: PathBuf, tag: String) -> Result<(), StorageIoError> {
+		send_request(
+			&self.request_tx,
+			StorageIoRequest::LoadTags { tags_path, tag },
+			"enqueue_load_tags",
+			"load_tags",
+		)
+	}
+
+	fn enqueue_check_mtimes(&self, files: Vec<(BufferId, PathBuf)>) -> Result<(), StorageIoError> {
+		send_request(
+			&self.request_tx,
+			StorageIoRequest::CheckMtimes { files },
+			"enqueue_check_mtimes",
+			"check_mtimes",
+		)
+	}
+
+	fn enqueue_save(
+		&self,
+		buffer_id: BufferId,
+		path: PathBuf,
+		text: String,
+		options: FileSaveOptions,
+	) -> Result<(), StorageIoError> {
 		send_request(
 			&self.request_tx,
-			StorageIoRequest::SaveFile { buffer_id, path, text },
+			StorageIoRequest::SaveFile { buffer_id, path, text, options },
 			"enqueue_save",
 			"save",
 		)
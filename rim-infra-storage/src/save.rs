@@ -0,0 +1,96 @@
+use std::{path::{Path, PathBuf}, time::SystemTime};
+
+use anyhow::{Context, Result};
+use rim_application::action::SaveVerification;
+use rim_ports::FileSaveOptions;
+
+pub(crate) async fn save_file(
+	path: PathBuf,
+	text: String,
+	options: FileSaveOptions,
+) -> Result<SaveVerification> {
+	let written = text.len() as u64;
+	if options.backup {
+		write_backup(path.as_path()).await?;
+	}
+	// Renaming over a symlink would replace the link itself, so those are always
+	// written through in place.
+	let is_symlink = compio::fs::symlink_metadata(&path).await.is_ok_and(|metadata| metadata.is_symlink());
+	if options.atomic && !is_symlink {
+		write_file_atomically(path.as_path(), text).await?;
+	} else {
+		let write_result = compio::fs::write(&path, text.into_bytes()).await.0;
+		write_result.with_context(|| format!("write file failed: {}", path.display()))?;
+	}
+	// The file's own clock stamps our write; the local clock may disagree with it
+	// on network filesystems.
+	let written_mtime = compio::fs::metadata(&path).await.ok().and_then(|metadata| metadata.modified().ok());
+	let metadata = compio::fs::metadata(&path)
+		.await
+		.with_context(|| format!("read saved file metadata failed: {}", path.display()))?;
+	Ok(verify_saved_file(written, written_mtime, metadata.len(), metadata.modified().ok()))
+}
+
+// Another writer racing this save shows up as a size we never wrote, or as
+// an mtime later than the one our own write left behind.
+pub(crate) fn verify_saved_file(
+	written: u64,
+	written_mtime: Option<SystemTime>,
+	on_disk: u64,
+	mtime: Option<SystemTime>,
+) -> SaveVerification {
+	if on_disk != written {
+		return SaveVerification::SizeMismatch { written, on_disk };
+	}
+	if written_mtime.zip(mtime).is_some_and(|(written_mtime, mtime)| mtime > written_mtime) {
+		return SaveVerification::MtimeMismatch;
+	}
+	SaveVerification::Matched { mtime }
+}
+
+pub(crate) fn backup_path(path: &Path) -> PathBuf {
+	let mut file_name = path.file_name().map(|name| name.to_os_string()).unwrap_or_default();
+	file_name.push("~");
+	path.with_file_name(file_name)
+}
+
+pub(crate) fn atomic_save_temp_path(path: &Path) -> PathBuf {
+	let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+	path.with_file_name(format!(".{}.rim-save-{}", file_name, std::process::id()))
+}
+
+// A file that does not exist yet has no previous version worth keeping.
+async fn write_backup(path: &Path) -> Result<()> {
+	let previous = match compio::fs::read(path).await {
+		Ok(previous) => previous,
+		Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+		Err(err) => return Err(err).with_context(|| format!("read file for backup failed: {}", path.display())),
+	};
+	let backup_path = backup_path(path);
+	let write_result = compio::fs::write(&backup_path, previous).await.0;
+	write_result.with_context(|| format!("write backup file failed: {}", backup_path.display()))
+}
+
+// The temp file lives next to the target so the rename never crosses
+// filesystems; it takes over the target's permissions before replacing it.
+async fn write_file_atomically(path: &Path, text: String) -> Result<()> {
+	let temp_path = atomic_save_temp_path(path);
+	let permissions = compio::fs::metadata(path).await.ok().map(|metadata| metadata.permissions());
+	let result = async {
+		let write_result = compio::fs::write(&temp_path, text.into_bytes()).await.0;
+		write_result.with_context(|| format!("write temp file failed: {}", temp_path.display()))?;
+		if let Some(permissions) = permissions {
+			compio::fs::set_permissions(&temp_path, permissions)
+				.await
+				.with_context(|| format!("copy file permissions failed: {}", temp_path.display()))?;
+		}
+		compio::fs::rename(&temp_path, path)
+			.await
+			.with_context(|| format!("replace file failed: {}", path.display()))
+	}
+	.await;
+	if result.is_err() {
+		let _ = compio::fs::remove_file(&temp_path).await;
+	}
+	result
+}
@@ -6,8 +6,11 @@ use rim_domain::model::WorkspaceSessionSnapshot;
 const WORKSPACE_SESSION_FILE_NAME: &str = "last-session.json";
 
 pub(crate) async fn load_workspace_session(session_dir: &Path) -> Result<Option<WorkspaceSessionSnapshot>> {
-	let session_path = workspace_session_path(session_dir);
-	let session_bytes = match compio::fs::read(session_path.as_path()).await {
+	load_session_file(workspace_session_path(session_dir).as_path()).await
+}
+
+pub(crate) async fn load_session_file(session_path: &Path) -> Result<Option<WorkspaceSessionSnapshot>> {
+	let session_bytes = match compio::fs::read(session_path).await {
 		Ok(bytes) => bytes,
 		Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
 		Err(err) => {
@@ -23,10 +26,16 @@ pub(crate) async fn save_workspace_session(
 	session_dir: &Path,
 	snapshot: &WorkspaceSessionSnapshot,
 ) -> Result<()> {
-	let session_path = workspace_session_path(session_dir);
+	save_session_file(workspace_session_path(session_dir).as_path(), snapshot).await
+}
+
+pub(crate) async fn save_session_file(
+	session_path: &Path,
+	snapshot: &WorkspaceSessionSnapshot,
+) -> Result<()> {
 	let encoded = serde_json::to_vec_pretty(snapshot)
 		.with_context(|| format!("encode workspace session failed: {}", session_path.display()))?;
-	compio::fs::write(session_path.as_path(), encoded)
+	compio::fs::write(session_path, encoded)
 		.await
 		.0
 		.with_context(|| format!("write workspace session failed: {}", session_path.display()))?;
@@ -0,0 +1,40 @@
+use super::*;
+
+#[test]
+fn expand_glob_should_match_wildcards_within_one_directory() {
+	let root = make_tmp_dir("glob-flat");
+	create_dir_all(&root.join("src"));
+	for name in ["src/a.rs", "src/b.rs", "src/c.txt", "src/.hidden.rs"] {
+		write_string(&root.join(name), String::new());
+	}
+
+	let matches = expand_glob_blocking(root.as_path(), "src/*.rs").expect("glob should expand");
+	assert_eq!(matches, vec![root.join("src/a.rs"), root.join("src/b.rs")]);
+
+	let matches = expand_glob_blocking(root.as_path(), "src/[!a].*").expect("glob should expand");
+	assert_eq!(matches, vec![root.join("src/b.rs"), root.join("src/c.txt")]);
+
+	let matches = expand_glob_blocking(root.as_path(), "src/?.t?t").expect("glob should expand");
+	assert_eq!(matches, vec![root.join("src/c.txt")]);
+}
+
+#[test]
+fn expand_glob_should_recurse_for_double_star_and_skip_directories() {
+	let root = make_tmp_dir("glob-recursive");
+	create_dir_all(&root.join("src/nested/deeper"));
+	create_dir_all(&root.join("src/dir.rs"));
+	for name in ["top.rs", "src/lib.rs", "src/nested/mod.rs", "src/nested/deeper/leaf.rs"] {
+		write_string(&root.join(name), String::new());
+	}
+
+	let matches = expand_glob_blocking(root.as_path(), "src/**/*.rs").expect("glob should expand");
+	assert_eq!(matches, vec![
+		root.join("src/lib.rs"),
+		root.join("src/nested/deeper/leaf.rs"),
+		root.join("src/nested/mod.rs"),
+	]);
+
+	let absolute = format!("{}/*.rs", root.display());
+	let matches = expand_glob_blocking(Path::new("/unused"), absolute.as_str()).expect("glob should expand");
+	assert_eq!(matches, vec![root.join("top.rs")]);
+}
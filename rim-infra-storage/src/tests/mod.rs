@@ -8,9 +8,11 @@ use ropey::Rope;
 use super::*;
 #[cfg(target_os = "windows")]
 use crate::path_codec::source_path_from_swap_storage_path;
-use crate::{path_codec::{encode_source_path_for_file_name, normalize_source_path_text, swap_path_for_source, undo_log_path_for_source, undo_meta_path_for_source}, swap_session::{BufferedSwapOp, SwapSession, append_swap_ops, parse_swap_file, write_swap_snapshot}, undo_history::{load_undo_history, save_undo_history}};
+use crate::{glob::expand_glob_blocking, path_codec::{encode_source_path_for_file_name, normalize_source_path_text, swap_path_for_source, undo_log_path_for_source, undo_meta_path_for_source}, save::{atomic_save_temp_path, backup_path, save_file, verify_saved_file}, swap_session::{BufferedSwapOp, SwapSession, append_swap_ops, parse_swap_file, write_swap_snapshot}, undo_history::{load_undo_history, save_undo_history}};
 
+mod glob;
 mod path_codec;
+mod save;
 mod session;
 mod swap_session;
 mod undo_history;
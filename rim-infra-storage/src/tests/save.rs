@@ -0,0 +1,105 @@
+use rim_application::action::SaveVerification;
+use rim_ports::FileSaveOptions;
+
+use super::*;
+
+#[test]
+fn atomic_save_should_replace_content_and_remove_temp_file() {
+	let dir = make_tmp_dir("atomic-save");
+	let path = dir.join("notes.txt");
+	write_string(&path, "old content that is longer".to_string());
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).expect("chmod test file failed");
+	}
+
+	let verification =
+		run_async(save_file(path.clone(), "new".to_string(), FileSaveOptions { atomic: true, backup: false }))
+			.expect("atomic save should succeed");
+
+	assert_eq!(verification, SaveVerification::Matched {
+		mtime: std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok(),
+	});
+	assert_eq!(read_to_string(&path), "new");
+	assert!(!path_exists(&atomic_save_temp_path(&path)));
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		let mode = std::fs::metadata(&path).expect("stat saved file failed").permissions().mode();
+		assert_eq!(mode & 0o777, 0o640);
+	}
+}
+
+#[test]
+fn direct_save_should_write_in_place_when_atomic_is_off() {
+	let dir = make_tmp_dir("direct-save");
+	let path = dir.join("notes.txt");
+
+	let verification =
+		run_async(save_file(path.clone(), "fresh".to_string(), FileSaveOptions { atomic: false, backup: false }))
+			.expect("direct save should succeed");
+
+	assert_eq!(verification, SaveVerification::Matched {
+		mtime: std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok(),
+	});
+	assert_eq!(read_to_string(&path), "fresh");
+	assert!(!path_exists(&atomic_save_temp_path(&path)));
+}
+
+#[test]
+fn backup_save_should_keep_previous_content_and_skip_new_files() {
+	let dir = make_tmp_dir("backup-save");
+	let path = dir.join("notes.txt");
+	let options = FileSaveOptions { atomic: true, backup: true };
+
+	run_async(save_file(path.clone(), "first".to_string(), options)).expect("first save should succeed");
+	assert!(!path_exists(&backup_path(&path)));
+
+	run_async(save_file(path.clone(), "second".to_string(), options)).expect("second save should succeed");
+	assert_eq!(read_to_string(&path), "second");
+	assert_eq!(backup_path(&path), dir.join("notes.txt~"));
+	assert_eq!(read_to_string(&backup_path(&path)), "first");
+}
+
+// Writing through a link to `/dev/null` leaves an empty file behind, the same
+// picture as another writer truncating it while the save ran.
+#[cfg(unix)]
+#[test]
+fn save_should_report_size_mismatch_when_the_disk_holds_other_bytes() {
+	let dir = make_tmp_dir("size-mismatch-save");
+	let path = dir.join("notes.txt");
+	std::os::unix::fs::symlink("/dev/null", &path).expect("symlink test file failed");
+
+	let verification =
+		run_async(save_file(path.clone(), "lost".to_string(), FileSaveOptions { atomic: true, backup: false }))
+			.expect("save through the link should succeed");
+
+	assert_eq!(verification, SaveVerification::SizeMismatch { written: 4, on_disk: 0 });
+}
+
+#[test]
+fn save_verification_should_flag_an_mtime_after_the_write_finished() {
+	let written_mtime = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(100);
+	let after = written_mtime + std::time::Duration::from_millis(1);
+
+	assert_eq!(verify_saved_file(4, Some(written_mtime), 4, Some(written_mtime)), SaveVerification::Matched {
+		mtime: Some(written_mtime),
+	});
+	assert_eq!(verify_saved_file(4, Some(written_mtime), 4, None), SaveVerification::Matched { mtime: None });
+	assert_eq!(verify_saved_file(4, None, 4, Some(after)), SaveVerification::Matched { mtime: Some(after) });
+	assert_eq!(verify_saved_file(4, Some(written_mtime), 4, Some(after)), SaveVerification::MtimeMismatch);
+	assert_eq!(
+		verify_saved_file(4, Some(written_mtime), 9, Some(written_mtime)),
+		SaveVerification::SizeMismatch { written: 4, on_disk: 9 }
+	);
+}
+
+#[test]
+fn save_verification_should_accept_a_file_clock_running_ahead_of_the_local_clock() {
+	let ahead = std::time::SystemTime::now() + std::time::Duration::from_secs(3600);
+
+	assert_eq!(verify_saved_file(4, Some(ahead), 4, Some(ahead)), SaveVerification::Matched {
+		mtime: Some(ahead),
+	});
+}
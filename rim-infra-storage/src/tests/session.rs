@@ -31,6 +31,7 @@ fn workspace_session_should_roundtrip_on_disk() {
 						}],
 						before_cursor: CursorState { row: 1, col: 1 },
 						after_cursor:  CursorState { row: 1, col: 8 },
+						changed_at:    None,
 					}],
 					redo_stack: Vec::new(),
 				}),
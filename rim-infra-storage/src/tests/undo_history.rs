@@ -16,6 +16,7 @@ fn save_and_load_undo_history_should_roundtrip() {
 			}],
 			before_cursor: CursorState { row: 1, col: 2 },
 			after_cursor:  CursorState { row: 1, col: 3 },
+			changed_at:    Some(UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000)),
 		}],
 		redo_stack:   vec![BufferHistoryEntry {
 			edits:         vec![BufferEditSnapshot {
@@ -25,6 +26,7 @@ fn save_and_load_undo_history_should_roundtrip() {
 			}],
 			before_cursor: CursorState { row: 1, col: 3 },
 			after_cursor:  CursorState { row: 1, col: 2 },
+			changed_at:    None,
 		}],
 	};
 
@@ -58,6 +60,7 @@ fn save_undo_history_should_truncate_redo_tail_before_appending_new_branch() {
 			}],
 			before_cursor: CursorState { row: 1, col: 2 },
 			after_cursor:  CursorState { row: 1, col: 3 },
+			changed_at:    None,
 		}],
 		redo_stack:   vec![BufferHistoryEntry {
 			edits:         vec![BufferEditSnapshot {
@@ -67,6 +70,7 @@ fn save_undo_history_should_truncate_redo_tail_before_appending_new_branch() {
 			}],
 			before_cursor: CursorState { row: 1, col: 3 },
 			after_cursor:  CursorState { row: 1, col: 2 },
+			changed_at:    None,
 		}],
 	};
 	run_async(save_undo_history(undo_dir.as_path(), source_path.as_path(), &original, &mut undo_sessions))
@@ -84,6 +88,7 @@ fn save_undo_history_should_truncate_redo_tail_before_appending_new_branch() {
 			}],
 			before_cursor: CursorState { row: 1, col: 2 },
 			after_cursor:  CursorState { row: 1, col: 3 },
+			changed_at:    None,
 		}],
 		redo_stack:   Vec::new(),
 	};
@@ -121,6 +126,7 @@ fn save_undo_history_should_remove_file_when_history_is_empty() {
 			}],
 			before_cursor: CursorState { row: 1, col: 1 },
 			after_cursor:  CursorState { row: 1, col: 2 },
+			changed_at:    None,
 		}],
 		redo_stack:   Vec::new(),
 	};
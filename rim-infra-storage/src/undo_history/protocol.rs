@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::{path::Path, time::{Duration, SystemTime}};
 
 use anyhow::{Context, Result, anyhow, bail};
 use compio::{fs::OpenOptions, io::AsyncWriteAtExt};
@@ -36,6 +36,9 @@ pub(super) struct UndoHistoryEntry {
 	pub(super) edits:         Vec<UndoEditSnapshot>,
 	pub(super) before_cursor: UndoCursor,
 	pub(super) after_cursor:  UndoCursor,
+	// Unix seconds; missing from files written before times were kept.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub(super) changed_at:    Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -89,6 +92,10 @@ impl From<BufferHistoryEntry> for UndoHistoryEntry {
 			edits:         entry.edits.into_iter().map(Into::into).collect(),
 			before_cursor: entry.before_cursor.into(),
 			after_cursor:  entry.after_cursor.into(),
+			changed_at:    entry
+				.changed_at
+				.and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+				.map(|since_epoch| since_epoch.as_secs()),
 		}
 	}
 }
@@ -99,6 +106,7 @@ impl From<UndoHistoryEntry> for BufferHistoryEntry {
 			edits:         entry.edits.into_iter().map(Into::into).collect(),
 			before_cursor: entry.before_cursor.into(),
 			after_cursor:  entry.after_cursor.into(),
+			changed_at:    entry.changed_at.map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs)),
 		}
 	}
 }
@@ -1,6 +1,7 @@
 use rim_application::action::{AppAction, FileAction};
 
-use super::{StorageIoRequest, list_workspace_files, load_file, load_workspace_file_preview, save_file, send_file_action_async};
+use super::{StorageIoRequest, expand_glob, file_mtime, list_workspace_files, load_file, load_tags_file, load_workspace_file_preview, send_file_action_async};
+use crate::save::save_file;
 
 pub(super) fn handle_file_transfer_request(
 	request: StorageIoRequest,
@@ -10,7 +11,8 @@ pub(super) fn handle_file_transfer_request(
 	match request {
 		StorageIoRequest::LoadFile { buffer_id, path, source } => {
 			spawn_file_action(in_flight, event_tx, "LoadCompleted", async move {
-				FileAction::LoadCompleted { buffer_id, source, result: load_file(path).await }
+				let mtime = file_mtime(&path).await;
+				FileAction::LoadCompleted { buffer_id, source, result: load_file(path).await, mtime }
 			});
 		}
 		StorageIoRequest::ListWorkspaceFiles { workspace_root } => {
@@ -29,9 +31,32 @@ pub(super) fn handle_file_transfer_request(
 				}
 			});
 		}
-		StorageIoRequest::SaveFile { buffer_id, path, text } => {
+		StorageIoRequest::ExpandGlob { workspace_root, pattern } => {
+			spawn_file_action(in_flight, event_tx, "GlobExpanded", async move {
+				FileAction::GlobExpanded {
+					pattern: pattern.clone(),
+					result:  expand_glob(workspace_root, pattern).await,
+				}
+			});
+		}
+		StorageIoRequest::LoadTags { tags_path, tag } => {
+			spawn_file_action(in_flight, event_tx, "TagsLoaded", async move {
+				FileAction::TagsLoaded { tags_path: tags_path.clone(), tag, result: load_tags_file(tags_path).await }
+			});
+		}
+		StorageIoRequest::CheckMtimes { files } => {
+			spawn_file_action(in_flight, event_tx, "MtimesChecked", async move {
+				let mut mtimes = Vec::with_capacity(files.len());
+				for (buffer_id, path) in files {
+					let mtime = file_mtime(&path).await;
+					mtimes.push((buffer_id, path, mtime));
+				}
+				FileAction::MtimesChecked { mtimes }
+			});
+		}
+		StorageIoRequest::SaveFile { buffer_id, path, text, options } => {
 			spawn_file_action(in_flight, event_tx, "SaveCompleted", async move {
-				FileAction::SaveCompleted { buffer_id, result: save_file(path, text).await }
+				FileAction::SaveCompleted { buffer_id, result: save_file(path, text, options).await }
 			});
 		}
 		_ => unreachable!("non file transfer request routed to handle_file_transfer_request"),
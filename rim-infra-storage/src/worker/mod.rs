@@ -1,8 +1,9 @@
-use std::{collections::HashMap, path::{Path, PathBuf}, time::Instant};
+use std::{collections::HashMap, path::{Path, PathBuf}, time::{Instant, SystemTime}};
 
 use anyhow::{Context, Result, anyhow};
 use rim_application::{action::{AppAction, FileAction, FileLoadSource}, ports::SwapEditOp};
 use rim_domain::model::{BufferId, PersistedBufferHistory, WorkspaceSessionSnapshot};
+use rim_ports::FileSaveOptions;
 use tracing::error;
 
 mod file_transfer;
@@ -13,7 +14,7 @@ use file_transfer::handle_file_transfer_request;
 use history_flow::handle_history_request;
 use swap_flow::handle_swap_request;
 
-use crate::{session::{load_workspace_session, save_workspace_session}, swap_session::SwapSession, undo_history::UndoHistorySession};
+use crate::{glob::expand_glob_blocking, session::{load_session_file, load_workspace_session, save_session_file, save_workspace_session}, swap_session::SwapSession, undo_history::UndoHistorySession};
 
 pub(super) fn run_worker(
 	request_rx: flume::Receiver<StorageIoRequest>,
@@ -116,10 +117,22 @@ pub(super) enum StorageIoRequest {
 	LoadWorkspaceFilePreview {
 		path: PathBuf,
 	},
+	ExpandGlob {
+		workspace_root: PathBuf,
+		pattern:        String,
+	},
+	LoadTags {
+		tags_path: PathBuf,
+		tag:       String,
+	},
+	CheckMtimes {
+		files: Vec<(BufferId, PathBuf)>,
+	},
 	SaveFile {
 		buffer_id: BufferId,
 		path:      PathBuf,
 		text:      String,
+		options:   FileSaveOptions,
 	},
 	Open {
 		buffer_id:   BufferId,
@@ -163,6 +176,13 @@ pub(super) enum StorageIoRequest {
 	SaveWorkspaceSession {
 		snapshot: WorkspaceSessionSnapshot,
 	},
+	LoadSessionFile {
+		path: PathBuf,
+	},
+	SaveSessionFile {
+		path:     PathBuf,
+		snapshot: WorkspaceSessionSnapshot,
+	},
 	Close {
 		buffer_id: BufferId,
 	},
@@ -214,6 +234,9 @@ async fn handle_request(request: StorageIoRequest, context: StorageIoContext<'_>
 		StorageIoRequest::LoadFile { .. }
 		| StorageIoRequest::ListWorkspaceFiles { .. }
 		| StorageIoRequest::LoadWorkspaceFilePreview { .. }
+		| StorageIoRequest::ExpandGlob { .. }
+		| StorageIoRequest::LoadTags { .. }
+		| StorageIoRequest::CheckMtimes { .. }
 		| StorageIoRequest::SaveFile { .. } => {
 			handle_file_transfer_request(request, event_tx, in_flight);
 		}
@@ -242,6 +265,18 @@ async fn handle_request(request: StorageIoRequest, context: StorageIoContext<'_>
 				error!("save workspace session failed: {:#}", err);
 			}
 		}
+		StorageIoRequest::LoadSessionFile { path } => {
+			let result = load_session_file(path.as_path()).await;
+			return send_file_action(
+				event_tx,
+				FileAction::SessionFileLoaded { path, result },
+				"session_file_loaded",
+			);
+		}
+		StorageIoRequest::SaveSessionFile { path, snapshot } => {
+			let result = save_session_file(path.as_path(), &snapshot).await;
+			return send_file_action(event_tx, FileAction::SessionFileSaved { path, result }, "session_file_saved");
+		}
 	}
 
 	true
@@ -318,6 +353,10 @@ async fn create_swap_session(
 	SwapSession::new(buffer_id, source_path, swap_dir, pid, username.to_string())
 }
 
+async fn file_mtime(path: &Path) -> Option<SystemTime> {
+	compio::fs::metadata(path).await.ok().and_then(|metadata| metadata.modified().ok())
+}
+
 async fn load_file(path: PathBuf) -> Result<String> {
 	let file_bytes =
 		compio::fs::read(&path).await.with_context(|| format!("read file failed: {}", path.display()))?;
@@ -372,9 +411,18 @@ async fn load_workspace_file_preview(path: PathBuf) -> Result<String> {
 	Ok(String::from_utf8_lossy(file_bytes.as_slice()).into_owned())
 }
 
-async fn save_file(path: PathBuf, text: String) -> Result<()> {
-	let write_result = compio::fs::write(&path, text.into_bytes()).await.0;
-	write_result.with_context(|| format!("write file failed: {}", path.display())).map(|_| ())
+async fn expand_glob(workspace_root: PathBuf, pattern: String) -> Result<Vec<PathBuf>> {
+	compio::runtime::spawn_blocking(move || expand_glob_blocking(workspace_root.as_path(), pattern.as_str()))
+		.await
+		.map_err(|panic_payload| anyhow!("glob expansion task panicked: {:?}", panic_payload))?
+}
+
+async fn load_tags_file(path: PathBuf) -> Result<Option<String>> {
+	match compio::fs::read(&path).await {
+		Ok(file_bytes) => Ok(Some(String::from_utf8_lossy(file_bytes.as_slice()).into_owned())),
+		Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+		Err(err) => Err(err).with_context(|| format!("read tags file failed: {}", path.display())),
+	}
 }
 
 fn collect_workspace_files_recursive_blocking(root: &Path) -> Result<Vec<PathBuf>> {
@@ -255,6 +255,25 @@ fn clamp_rect_to_content_area(rect: Rect, content_area: Rect) -> Rect {
 	Rect { x, y, width: rect.width.min(max_width), height: rect.height.min(max_height) }
 }
 
+// The keys column is only drawn when the description keeps room beside it.
+#[derive(Debug, Clone, Copy)]
+struct CommandColumnWidths {
+	name:    usize,
+	command: usize,
+	keys:    usize,
+	desc:    usize,
+}
+
+impl CommandColumnWidths {
+	fn new(body_width: usize) -> Self {
+		let name = compute_name_column_width(body_width);
+		let command = compute_command_column_width(body_width, name);
+		let desc = body_width.saturating_sub(name + command + 2);
+		let keys = if desc >= 28 { 10 } else { 0 };
+		Self { name, command, keys, desc: if keys > 0 { desc - keys - 1 } else { desc } }
+	}
+}
+
 fn compute_name_column_width(body_width: usize) -> usize {
 	let preferred = body_width / 6;
 	preferred.clamp(8, 18)
@@ -273,10 +292,7 @@ fn render_command_palette_item(
 ) -> Line<'static> {
 	match item {
 		CommandPaletteItem::Command(item) => {
-			let name_width = compute_name_column_width(body_width);
-			let command_width = compute_command_column_width(body_width, name_width);
-			let desc_width = body_width - name_width - command_width - 2;
-			render_command_palette_command_item(item, selected, name_width, command_width, desc_width)
+			render_command_palette_command_item(item, selected, CommandColumnWidths::new(body_width))
 		}
 		CommandPaletteItem::File(item) => render_command_palette_file_item(item, selected, body_width),
 	}
@@ -286,14 +302,16 @@ fn render_command_palette_header(item: &CommandPaletteItem, body_width: usize) -
 	let header_style = Style::default().fg(Color::DarkGray).add_modifier(Modifier::BOLD);
 	match item {
 		CommandPaletteItem::Command(_) => {
-			let name_width = compute_name_column_width(body_width);
-			let command_width = compute_command_column_width(body_width, name_width);
-			let desc_width = body_width - name_width - command_width - 2;
-			let mut spans = padded_text("NAME", name_width, header_style);
+			let widths = CommandColumnWidths::new(body_width);
+			let mut spans = padded_text("NAME", widths.name, header_style);
 			spans.push(Span::styled(" ", header_style));
-			spans.extend(padded_text("COMMAND", command_width, header_style));
+			spans.extend(padded_text("COMMAND", widths.command, header_style));
 			spans.push(Span::styled(" ", header_style));
-			spans.extend(highlighted_text("DESCRIPTION", desc_width, &[], header_style, header_style, false));
+			if widths.keys > 0 {
+				spans.extend(padded_text("KEYS", widths.keys, header_style));
+				spans.push(Span::styled(" ", header_style));
+			}
+			spans.extend(highlighted_text("DESCRIPTION", widths.desc, &[], header_style, header_style, false));
 			Line::from(spans)
 		}
 		CommandPaletteItem::File(_) => Line::styled("FILE", header_style),
@@ -303,9 +321,7 @@ fn render_command_palette_header(item: &CommandPaletteItem, body_width: usize) -
 fn render_command_palette_command_item(
 	item: &CommandPaletteMatch,
 	selected: bool,
-	name_width: usize,
-	command_width: usize,
-	desc_width: usize,
+	widths: CommandColumnWidths,
 ) -> Line<'static> {
 	let row_style = if selected { Style::default().bg(Color::Rgb(18, 36, 52)) } else { Style::default() };
 	let name_style = if item.is_error {
@@ -329,7 +345,7 @@ fn render_command_palette_command_item(
 		if item.is_error { row_style.fg(Color::Rgb(255, 170, 170)) } else { row_style.fg(Color::Gray) };
 	let mut spans = highlighted_text(
 		item.name.as_str(),
-		name_width,
+		widths.name,
 		&item.name_match_indices,
 		name_style,
 		if item.is_error { row_style.fg(Color::Rgb(255, 170, 170)) } else { row_style.fg(Color::White) },
@@ -338,16 +354,27 @@ fn render_command_palette_command_item(
 	spans.push(Span::styled(" ", row_style));
 	spans.extend(highlighted_text(
 		item.command_id_label.as_str(),
-		command_width,
+		widths.command,
 		&item.command_id_match_indices,
 		command_style,
 		command_base_style,
 		true,
 	));
 	spans.push(Span::styled(" ", row_style));
+	if widths.keys > 0 {
+		spans.extend(highlighted_text(
+			item.key_binding.as_str(),
+			widths.keys,
+			&item.key_binding_match_indices,
+			desc_highlight_style,
+			row_style.fg(Color::Rgb(176, 190, 214)),
+			true,
+		));
+		spans.push(Span::styled(" ", row_style));
+	}
 	spans.extend(highlighted_text(
 		item.description.as_str(),
-		desc_width,
+		widths.desc,
 		&item.description_match_indices,
 		desc_highlight_style,
 		desc_base_style,
@@ -12,25 +12,37 @@ use command_palette::CommandPaletteWidgets;
 use floating_window::FloatingWindowWidget;
 use notification_center::NotificationCenterWidget;
 use notification_preview::NotificationPreviewWidget;
-use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::{Terminal, backend::TestBackend, layout::{Constraint, Layout, Rect}};
 use rim_application::state::RimState;
 use status_bar::StatusBarWidget;
-pub use terminal_session::{TerminalSession, TerminalSessionError};
+pub use terminal_session::{TerminalSession, TerminalSessionError, restore_terminal};
 use top_bar::TopBarWidget;
+use unicode_width::UnicodeWidthStr;
 use window_area::WindowAreaWidget;
 use workspace_file_picker::WorkspaceFilePickerWidget;
 
 pub struct Renderer {
 	last_content_area: Option<Rect>,
+	last_generation:   Option<u64>,
 }
 
 impl Renderer {
-	pub fn new() -> Self { Self { last_content_area: None } }
+	pub fn new() -> Self { Self { last_content_area: None, last_generation: None } }
+
+	// False while no action has touched the state since the last drawn frame.
+	pub fn needs_redraw(&self, state: &RimState) -> bool {
+		self.last_generation != Some(state.render_generation())
+	}
 
 	pub fn render(&mut self, frame: &mut ratatui::Frame<'_>, state: &mut RimState) {
 		let area = frame.area();
-		let chunks =
-			Layout::vertical([Constraint::Length(1), Constraint::Min(1), Constraint::Length(1)]).split(area);
+		let status_bar_visible = state.status_bar_visible();
+		let chunks = Layout::vertical([
+			Constraint::Length(1),
+			Constraint::Min(1),
+			Constraint::Length(u16::from(status_bar_visible)),
+		])
+		.split(area);
 
 		if self
 			.last_content_area
@@ -40,10 +52,11 @@ impl Renderer {
 			state.update_active_tab_layout(chunks[1].width, chunks[1].height);
 			self.last_content_area = Some(chunks[1]);
 		}
+		self.last_generation = Some(state.render_generation());
 
 		let top_bar = TopBarWidget::from_state(state);
 		let (window_area, cursor_position) = WindowAreaWidget::from_state(state, chunks[1]);
-		let status_bar = StatusBarWidget::from_state(state);
+		let status_bar = status_bar_visible.then(|| StatusBarWidget::from_state(state));
 		let command_palette = CommandPaletteWidgets::from_state(state, chunks[1]);
 		let workspace_file_picker = WorkspaceFilePickerWidget::from_state(state, chunks[1]);
 		let floating_window = FloatingWindowWidget::from_state(state, chunks[1]);
@@ -61,7 +74,9 @@ impl Renderer {
 			if let Some(notification_preview) = notification_preview.clone() {
 				frame.render_widget(notification_preview, chunks[1]);
 			}
-			frame.render_widget(status_bar, chunks[2]);
+			if let Some(status_bar) = status_bar {
+				frame.render_widget(status_bar, chunks[2]);
+			}
 			frame.set_cursor_position(cursor_to_draw);
 			return;
 		}
@@ -74,7 +89,9 @@ impl Renderer {
 			if let Some(notification_preview) = notification_preview.clone() {
 				frame.render_widget(notification_preview, chunks[1]);
 			}
-			frame.render_widget(status_bar, chunks[2]);
+			if let Some(status_bar) = status_bar {
+				frame.render_widget(status_bar, chunks[2]);
+			}
 			frame.set_cursor_position(cursor_to_draw);
 			return;
 		}
@@ -87,15 +104,191 @@ impl Renderer {
 		if let Some(notification_preview) = notification_preview {
 			frame.render_widget(notification_preview, chunks[1]);
 		}
-		frame.render_widget(status_bar, chunks[2]);
+		if let Some(status_bar) = status_bar {
+			frame.render_widget(status_bar, chunks[2]);
+		}
 		if let Some(cursor_to_draw) = cursor_position {
 			frame.set_cursor_position(cursor_to_draw);
 		}
 	}
 
 	pub fn mark_layout_dirty(&mut self) { self.last_content_area = None; }
+
+	// Forces the next frame to draw, for when the terminal itself was cleared or
+	// resized.
+	pub fn invalidate(&mut self) { self.last_generation = None; }
 }
 
 impl Default for Renderer {
 	fn default() -> Self { Self::new() }
 }
+
+// Renders a single frame off-screen and returns one line per terminal row.
+pub fn render_to_string(state: &mut RimState, width: u16, height: u16) -> String {
+	let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("test backend should not fail");
+	let mut renderer = Renderer::new();
+	terminal.draw(|frame| renderer.render(frame, state)).expect("test backend should not fail");
+
+	let buffer = terminal.backend().buffer();
+	let mut rows = Vec::with_capacity(height as usize);
+	for y in 0..buffer.area.height {
+		let mut row = String::new();
+		let mut skip = 0usize;
+		for x in 0..buffer.area.width {
+			if skip > 0 {
+				skip -= 1;
+				continue;
+			}
+			let symbol = buffer[(x, y)].symbol();
+			row.push_str(symbol);
+			skip = symbol.width().saturating_sub(1);
+		}
+		rows.push(row);
+	}
+	rows.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use ratatui::{Terminal, backend::TestBackend};
+	use rim_application::state::RimState;
+
+	use super::{Renderer, render_to_string};
+
+	#[test]
+	fn render_to_string_should_include_top_bar_window_area_and_status_bar() {
+		let mut state = RimState::new();
+		let buffer_id = state.create_buffer(Some(std::path::PathBuf::from("demo.txt")), "alpha\nbeta");
+		state.bind_buffer_to_active_window(buffer_id);
+
+		let rendered = render_to_string(&mut state, 30, 6);
+
+		let expected = [" demo.txt", "1 alpha", "2 beta", "~", "~", " NORMAL  new file | 1:1 Top"]
+			.map(|line| format!("{:<30}", line))
+			.join("\n");
+		assert_eq!(rendered, expected);
+	}
+
+	#[test]
+	fn render_to_string_should_show_change_sign_in_gutter_for_edited_line() {
+		let mut state = RimState::new();
+		let buffer_id = state.create_buffer(Some(std::path::PathBuf::from("demo.txt")), "alpha\nbeta");
+		state.bind_buffer_to_active_window(buffer_id);
+		state.move_cursor_down();
+		state.insert_char_at_cursor('x');
+
+		let rendered = render_to_string(&mut state, 30, 6);
+
+		let rows = rendered.lines().collect::<Vec<_>>();
+		assert!(rows[1].starts_with("1 alpha"));
+		assert!(rows[2].starts_with("2~xbeta"));
+	}
+
+	#[test]
+	fn render_to_string_should_fill_rows_past_end_with_tilde_when_wrapping() {
+		let mut state = RimState::new();
+		let buffer_id =
+			state.create_buffer(Some(std::path::PathBuf::from("demo.txt")), "abcdefghijklmnopqrstuvwxyz");
+		state.bind_buffer_to_active_window(buffer_id);
+		state.workbench.word_wrap = true;
+
+		let rendered = render_to_string(&mut state, 12, 7);
+
+		let rows = rendered.lines().collect::<Vec<_>>();
+		assert_eq!(rows[1], "1 abcdefghij");
+		assert_eq!(rows[2], "  klmnopqrst");
+		assert_eq!(rows[3], "  uvwxyz    ");
+		assert_eq!(rows[4], "~           ");
+		assert_eq!(rows[5], "~           ");
+	}
+
+	#[test]
+	fn render_to_string_should_show_list_glyphs_when_list_mode_is_on() {
+		let mut state = RimState::new();
+		let buffer_id = state.create_buffer(Some(std::path::PathBuf::from("demo.txt")), "\tab  \nx");
+		state.bind_buffer_to_active_window(buffer_id);
+		assert_eq!(state.apply_set_option("list"), Ok("list".to_string()));
+
+		let rendered = render_to_string(&mut state, 16, 5);
+		let rows = rendered.lines().collect::<Vec<_>>();
+		assert_eq!(rows[1], "1 »   ab··      ");
+
+		assert_eq!(state.apply_set_option("nolist"), Ok("nolist".to_string()));
+		let rendered = render_to_string(&mut state, 16, 5);
+		let rows = rendered.lines().collect::<Vec<_>>();
+		assert_eq!(rows[1], "1     ab        ");
+	}
+
+	#[test]
+	fn render_to_string_should_give_status_bar_row_to_text_when_laststatus_is_zero() {
+		let mut state = RimState::new();
+		let text = (1..=6).map(|line| format!("line{}", line)).collect::<Vec<_>>().join("\n");
+		let buffer_id = state.create_buffer(Some(std::path::PathBuf::from("demo.txt")), text.as_str());
+		state.bind_buffer_to_active_window(buffer_id);
+		assert_eq!(state.apply_set_option("laststatus=0"), Ok("laststatus=0".to_string()));
+
+		let rendered = render_to_string(&mut state, 30, 6);
+
+		let expected = [" demo.txt", "1 line1", "2 line2", "3 line3", "4 line4", "5 line5"]
+			.map(|line| format!("{:<30}", line))
+			.join("\n");
+		assert_eq!(rendered, expected);
+		assert!(!rendered.contains("NORMAL"));
+
+		assert_eq!(state.apply_set_option("laststatus=1"), Ok("laststatus=1".to_string()));
+		let rendered = render_to_string(&mut state, 30, 6);
+		assert!(!rendered.contains("NORMAL"));
+		assert_eq!(state.apply_set_option("laststatus=3"), Err("invalid laststatus: 3".to_string()));
+	}
+
+	#[test]
+	fn render_to_string_should_bring_up_hidden_status_bar_for_command_line_and_unread_message() {
+		let mut state = RimState::new();
+		let text = (1..=6).map(|line| format!("line{}", line)).collect::<Vec<_>>().join("\n");
+		let buffer_id = state.create_buffer(Some(std::path::PathBuf::from("demo.txt")), text.as_str());
+		state.bind_buffer_to_active_window(buffer_id);
+		assert_eq!(state.apply_set_option("laststatus=0"), Ok("laststatus=0".to_string()));
+
+		state.enter_command_mode();
+		for ch in "set".chars() {
+			state.push_command_char(ch);
+		}
+		let rendered = render_to_string(&mut state, 30, 6);
+		let rows = rendered.lines().collect::<Vec<_>>();
+		assert_eq!(rows.len(), 6);
+		assert!(rows[5].contains(":set"), "{:?}", rows[5]);
+
+		state.exit_command_mode();
+		state.workbench.status_bar.message = "written".to_string();
+		let rendered = render_to_string(&mut state, 30, 6);
+		assert!(rendered.lines().last().is_some_and(|row| row.contains("written")));
+
+		state.mark_status_message_seen();
+		let rendered = render_to_string(&mut state, 30, 6);
+		assert!(rendered.lines().last().is_some_and(|row| row.starts_with("5 line5")));
+	}
+
+	#[test]
+	fn renderer_should_skip_redraw_until_visible_state_changes() {
+		let mut state = RimState::new();
+		let buffer_id = state.create_buffer(Some(std::path::PathBuf::from("demo.txt")), "alpha\nbeta");
+		state.bind_buffer_to_active_window(buffer_id);
+		let mut terminal = Terminal::new(TestBackend::new(30, 6)).expect("test backend should not fail");
+		let mut renderer = Renderer::new();
+
+		assert!(renderer.needs_redraw(&state));
+		terminal.draw(|frame| renderer.render(frame, &mut state)).expect("test backend should not fail");
+		assert!(!renderer.needs_redraw(&state));
+		terminal.draw(|frame| renderer.render(frame, &mut state)).expect("test backend should not fail");
+		assert!(!renderer.needs_redraw(&state));
+
+		state.move_cursor_down();
+		state.mark_render_dirty();
+		assert!(renderer.needs_redraw(&state));
+		terminal.draw(|frame| renderer.render(frame, &mut state)).expect("test backend should not fail");
+		assert!(!renderer.needs_redraw(&state));
+
+		renderer.invalidate();
+		assert!(renderer.needs_redraw(&state));
+	}
+}
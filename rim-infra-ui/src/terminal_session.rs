@@ -1,6 +1,6 @@
-use std::io;
+use std::{io, sync::{Once, atomic::{AtomicBool, Ordering}}};
 
-use crossterm::{cursor::SetCursorStyle, execute, terminal::{EnterAlternateScreen, LeaveAlternateScreen, SetTitle, disable_raw_mode, enable_raw_mode}};
+use crossterm::{cursor::{SetCursorStyle, Show}, event::{DisableBracketedPaste, EnableBracketedPaste}, execute, terminal::{EnterAlternateScreen, LeaveAlternateScreen, SetTitle, disable_raw_mode, enable_raw_mode}};
 use ratatui::{Terminal, backend::CrosstermBackend};
 use rim_application::state::EditorMode;
 use thiserror::Error;
@@ -49,33 +49,75 @@ pub enum TerminalSessionError {
 	},
 }
 
+// Set while raw mode and the alternate screen are active, so the panic hook and
+// the session guard never restore the terminal twice.
+static TERMINAL_ACTIVE: AtomicBool = AtomicBool::new(false);
+static PANIC_HOOK: Once = Once::new();
+
+// Leaves raw mode and the alternate screen and shows the cursor. Returns
+// whether anything was restored; later calls are no-ops until the next session
+// starts.
+pub fn restore_terminal() -> bool {
+	if !TERMINAL_ACTIVE.swap(false, Ordering::SeqCst) {
+		return false;
+	}
+	let _ = disable_raw_mode();
+	let mut stdout = io::stdout();
+	let _ =
+		execute!(stdout, SetCursorStyle::DefaultUserShape, DisableBracketedPaste, LeaveAlternateScreen, Show);
+	true
+}
+
+// Restores the terminal before the previous hook prints, so the panic message
+// lands on the user's shell instead of the alternate screen.
+fn install_panic_hook() {
+	PANIC_HOOK.call_once(|| {
+		let previous_hook = std::panic::take_hook();
+		std::panic::set_hook(Box::new(move |info| {
+			restore_terminal();
+			previous_hook(info);
+		}));
+	});
+}
+
+// Leaving the session goes back to `DefaultUserShape`, which also drops the
+// blink setting.
+fn cursor_style(mode: EditorMode, blink: bool) -> SetCursorStyle {
+	match (mode, blink) {
+		(EditorMode::Insert, false) => SetCursorStyle::SteadyBar,
+		(EditorMode::Insert, true) => SetCursorStyle::BlinkingBar,
+		(_, false) => SetCursorStyle::SteadyBlock,
+		(_, true) => SetCursorStyle::BlinkingBlock,
+	}
+}
+
 struct TerminalModeGuard;
 
 impl Drop for TerminalModeGuard {
-	fn drop(&mut self) {
-		let _ = disable_raw_mode();
-		let mut stdout = io::stdout();
-		let _ = execute!(stdout, SetCursorStyle::DefaultUserShape, LeaveAlternateScreen);
-	}
+	fn drop(&mut self) { restore_terminal(); }
 }
 
 pub struct TerminalSession {
-	terminal:    Terminal<CrosstermBackend<io::Stdout>>,
-	title:       String,
-	_mode_guard: TerminalModeGuard,
+	terminal:     Terminal<CrosstermBackend<io::Stdout>>,
+	title:        String,
+	// Set when resuming cleared the screen, so the next frame must be drawn.
+	screen_reset: bool,
+	_mode_guard:  TerminalModeGuard,
 }
 
 impl TerminalSession {
 	pub fn enter(title: &str) -> Result<Self, TerminalSessionError> {
+		install_panic_hook();
 		enable_raw_mode().map_err(|source| TerminalSessionError::EnableRawMode { source })?;
+		TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
 		let mode_guard = TerminalModeGuard;
 		let mut stdout = io::stdout();
-		execute!(stdout, EnterAlternateScreen, SetTitle(title))
+		execute!(stdout, EnterAlternateScreen, EnableBracketedPaste, SetTitle(title))
 			.map_err(|source| TerminalSessionError::EnterAlternateScreen { source })?;
 		let backend = CrosstermBackend::new(stdout);
 		let terminal =
 			Terminal::new(backend).map_err(|source| TerminalSessionError::CreateTerminal { source })?;
-		Ok(Self { terminal, title: title.to_string(), _mode_guard: mode_guard })
+		Ok(Self { terminal, title: title.to_string(), screen_reset: false, _mode_guard: mode_guard })
 	}
 
 	pub fn draw(&mut self, render: impl FnOnce(&mut ratatui::Frame<'_>)) -> Result<(), TerminalSessionError> {
@@ -83,32 +125,77 @@ impl TerminalSession {
 		Ok(())
 	}
 
-	pub fn sync_cursor_style(&mut self, mode: EditorMode) -> Result<(), TerminalSessionError> {
-		let style = match mode {
-			EditorMode::Insert => SetCursorStyle::SteadyBar,
-			EditorMode::Normal
-			| EditorMode::Command
-			| EditorMode::VisualChar
-			| EditorMode::VisualLine
-			| EditorMode::VisualBlock => SetCursorStyle::SteadyBlock,
-		};
-		execute!(self.terminal.backend_mut(), style)
+	pub fn sync_cursor_style(&mut self, mode: EditorMode, blink: bool) -> Result<(), TerminalSessionError> {
+		execute!(self.terminal.backend_mut(), cursor_style(mode, blink))
 			.map_err(|source| TerminalSessionError::SetCursorStyle { source })?;
 		Ok(())
 	}
 
 	pub fn suspend(&mut self) -> Result<(), TerminalSessionError> {
 		disable_raw_mode().map_err(|source| TerminalSessionError::DisableRawMode { source })?;
-		execute!(self.terminal.backend_mut(), SetCursorStyle::DefaultUserShape, LeaveAlternateScreen)
-			.map_err(|source| TerminalSessionError::LeaveAlternateScreen { source })?;
+		TERMINAL_ACTIVE.store(false, Ordering::SeqCst);
+		execute!(
+			self.terminal.backend_mut(),
+			SetCursorStyle::DefaultUserShape,
+			DisableBracketedPaste,
+			LeaveAlternateScreen
+		)
+		.map_err(|source| TerminalSessionError::LeaveAlternateScreen { source })?;
 		Ok(())
 	}
 
 	pub fn resume(&mut self) -> Result<(), TerminalSessionError> {
 		enable_raw_mode().map_err(|source| TerminalSessionError::EnableRawMode { source })?;
-		execute!(self.terminal.backend_mut(), EnterAlternateScreen, SetTitle(self.title.as_str()))
-			.map_err(|source| TerminalSessionError::EnterAlternateScreen { source })?;
+		TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+		execute!(
+			self.terminal.backend_mut(),
+			EnterAlternateScreen,
+			EnableBracketedPaste,
+			SetTitle(self.title.as_str())
+		)
+		.map_err(|source| TerminalSessionError::EnterAlternateScreen { source })?;
 		self.terminal.clear().map_err(|source| TerminalSessionError::ClearTerminal { source })?;
+		self.screen_reset = true;
 		Ok(())
 	}
+
+	pub fn take_screen_reset(&mut self) -> bool { std::mem::take(&mut self.screen_reset) }
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::atomic::Ordering;
+
+	use crossterm::Command;
+	use rim_application::state::EditorMode;
+
+	use super::{TERMINAL_ACTIVE, cursor_style, install_panic_hook, restore_terminal};
+
+	fn cursor_style_ansi(mode: EditorMode, blink: bool) -> String {
+		let mut ansi = String::new();
+		cursor_style(mode, blink).write_ansi(&mut ansi).expect("writing to a string should not fail");
+		ansi
+	}
+
+	#[test]
+	fn cursor_style_should_only_blink_when_the_option_is_set() {
+		assert_eq!(cursor_style_ansi(EditorMode::Normal, false), "\x1b[2 q");
+		assert_eq!(cursor_style_ansi(EditorMode::Insert, false), "\x1b[6 q");
+		assert_eq!(cursor_style_ansi(EditorMode::Normal, true), "\x1b[1 q");
+		assert_eq!(cursor_style_ansi(EditorMode::Insert, true), "\x1b[5 q");
+	}
+
+	#[test]
+	fn restore_terminal_should_be_idempotent_and_run_from_panic_hook() {
+		TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+		assert!(restore_terminal());
+		assert!(!restore_terminal());
+
+		install_panic_hook();
+		TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+		let result = std::panic::catch_unwind(|| panic!("render exploded"));
+		assert!(result.is_err());
+		assert!(!TERMINAL_ACTIVE.load(Ordering::SeqCst));
+		assert!(!restore_terminal());
+	}
 }
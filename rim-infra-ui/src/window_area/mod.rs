@@ -1,13 +1,22 @@
-use ratatui::{buffer::{Buffer, Cell}, layout::Rect, style::{Color, Style}, widgets::{Paragraph, Widget, Wrap}};
-use rim_application::state::{CursorState, RimState};
-use rim_domain::display_geometry::{char_display_width as geom_char_display_width, display_width_of_char_prefix as geom_display_width_of_char_prefix, display_width_of_char_prefix_with_virtual as geom_display_width_of_char_prefix_with_virtual, wrap_line_with_display_span as geom_wrap_line_with_display_span, wrapped_row_index_for_row_display_col as geom_wrapped_row_index_for_row_display_col};
+use std::{cmp::Reverse, ops::Range};
+
+use ratatui::{buffer::{Buffer, Cell}, layout::Rect, style::{Color, Modifier, Style}, widgets::{Paragraph, Widget, Wrap}};
+use rim_application::state::{BufferId, CursorState, DiagnosticSeverity, Fold, LineChangeSign, ListChars, RimState, WindowId};
+use rim_domain::{display_geometry::{TAB_DISPLAY_WIDTH, char_display_widths as geom_char_display_widths, display_width_of_char_prefix as geom_display_width_of_char_prefix, display_width_of_char_prefix_with_virtual as geom_display_width_of_char_prefix_with_virtual, wrap_line_with_display_span as geom_wrap_line_with_display_span, wrapped_row_index_for_row_display_col as geom_wrapped_row_index_for_row_display_col}, indent::leading_indent};
 use ropey::Rope;
 
 pub(super) struct WindowAreaWidget {
-	windows:            Vec<WindowView>,
-	selection_segments: Vec<SelectionSegment>,
-	vertical_lines:     Vec<VerticalLine>,
-	horizontal_lines:   Vec<HorizontalLine>,
+	windows:             Vec<WindowView>,
+	selection_segments:  Vec<SelectionSegment>,
+	yank_segments:       Vec<SelectionSegment>,
+	substitute_segments: Vec<SelectionSegment>,
+	spell_segments:      Vec<SelectionSegment>,
+	diagnostic_segments: Vec<(SelectionSegment, DiagnosticSeverity)>,
+	diff_segments:       Vec<(SelectionSegment, LineChangeSign)>,
+	fold_segments:       Vec<SelectionSegment>,
+	indent_guides:       Vec<GuideCell>,
+	vertical_lines:      Vec<VerticalLine>,
+	horizontal_lines:    Vec<HorizontalLine>,
 }
 
 #[derive(Debug)]
@@ -15,6 +24,9 @@ struct WindowView {
 	local_rect:        Rect,
 	number_col_width:  u16,
 	line_numbers_text: String,
+	line_signs:        Vec<Option<LineChangeSign>>,
+	diagnostic_signs:  Vec<Option<DiagnosticSeverity>>,
+	content_rows:      u16,
 	text_text:         String,
 	word_wrap:         bool,
 }
@@ -27,6 +39,12 @@ struct WrappedViewportRow {
 	text:          String,
 }
 
+#[derive(Debug, Clone, Copy)]
+struct UnwrappedViewportRow {
+	row_idx: usize,
+	fold:    Option<Fold>,
+}
+
 #[derive(Debug)]
 struct VerticalLine {
 	x:       u16,
@@ -50,6 +68,12 @@ struct SelectionSegment {
 	y:       u16,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+struct GuideCell {
+	x: u16,
+	y: u16,
+}
+
 #[derive(Clone, Copy)]
 struct VisualSelectionSpec {
 	text_rect:  Rect,
@@ -65,6 +89,13 @@ impl WindowAreaWidget {
 	pub(super) fn from_state(state: &RimState, content_area: Rect) -> (Self, Option<(u16, u16)>) {
 		let mut windows = Vec::new();
 		let mut selection_segments = Vec::new();
+		let mut yank_segments = Vec::new();
+		let mut substitute_segments = Vec::new();
+		let mut spell_segments = Vec::new();
+		let mut diagnostic_segments = Vec::new();
+		let mut diff_segments = Vec::new();
+		let mut fold_segments = Vec::new();
+		let mut indent_guides = Vec::new();
 		let mut cursor_position = None;
 
 		for window_id in state.active_tab_window_ids() {
@@ -92,6 +123,10 @@ impl WindowAreaWidget {
 
 			let buffer_text =
 				window.buffer_id.and_then(|buffer_id| state.buffers.get(buffer_id)).map(|buf| &buf.text);
+			// A `:s` being typed shows its result in place of the text.
+			let substitute_preview =
+				window.buffer_id.and_then(|buffer_id| state.substitute_preview_for_buffer(buffer_id));
+			let buffer_text = substitute_preview.map(|preview| &preview.text).or(buffer_text);
 			let total_lines = buffer_text.map(rope_display_line_count).unwrap_or(1);
 			let desired_number_col_width = total_lines.to_string().len() as u16 + 1;
 			let number_col_width =
@@ -111,10 +146,44 @@ impl WindowAreaWidget {
 			let scroll_y = window.scroll_y as usize;
 			let scroll_x = window.scroll_x as usize;
 			let visible_rows = local_rect.height as usize;
-			let word_wrap = state.word_wrap_enabled();
+			let word_wrap = state.window_word_wrap(window_id);
+			let list_chars = state.workbench.list_mode.then_some(state.workbench.list_chars);
+			let closed_fold_at = |row_idx: usize| {
+				window.buffer_id.and_then(|buffer_id| state.buffer_closed_fold_at(buffer_id, row_idx as u16 + 1))
+			};
+			let unwrapped_rows = if word_wrap {
+				Vec::new()
+			} else {
+				collect_unwrapped_viewport_rows(scroll_y, visible_rows, closed_fold_at)
+			};
+			// Without wrap, rows are collected over every buffer row the viewport
+			// covers, closed folds included, then moved to the screen row showing them.
+			let first_row = unwrapped_rows.first().map_or(scroll_y, |row| row.row_idx);
+			let covered_rows = unwrapped_rows.last().map_or(visible_rows, |row| {
+				row.fold.map_or(row.row_idx + 1, |fold| fold.end_row as usize).saturating_sub(first_row)
+			});
+			let mut screen_rows = vec![None; covered_rows];
+			for (screen_row, row) in unwrapped_rows.iter().enumerate() {
+				if row.fold.is_none()
+					&& let Some(slot) = screen_rows.get_mut(row.row_idx.saturating_sub(first_row))
+				{
+					*slot = Some(screen_row as u16);
+				}
+			}
+			let covered_rect = Rect { height: covered_rows as u16, ..text_rect };
+			let to_screen_y = |y: u16| {
+				let offset = y.checked_sub(text_rect.y)? as usize;
+				screen_rows.get(offset).copied().flatten().map(|screen_row| text_rect.y.saturating_add(screen_row))
+			};
 			let (wrapped_rows, line_numbers_text, text_text) = if word_wrap {
-				let wrapped_rows =
-					collect_wrapped_viewport_rows(buffer_text, scroll_y, visible_rows, text_width as usize);
+				let wrapped_rows = collect_wrapped_viewport_rows(
+					buffer_text,
+					scroll_y,
+					visible_rows,
+					text_width as usize,
+					list_chars,
+					closed_fold_at,
+				);
 				let line_numbers_text = if number_col_width == 0 {
 					String::new()
 				} else {
@@ -127,19 +196,26 @@ impl WindowAreaWidget {
 				let line_numbers_text = if number_col_width == 0 {
 					String::new()
 				} else {
-					(scroll_y..scroll_y.saturating_add(visible_rows))
-						.map(|row_idx| {
-							format!("{:>width$} ", row_idx + 1, width = number_col_width.saturating_sub(1) as usize)
+					unwrapped_rows
+						.iter()
+						.take_while(|row| row.row_idx < total_lines)
+						.map(|row| {
+							format!("{:>width$} ", row.row_idx + 1, width = number_col_width.saturating_sub(1) as usize)
 						})
 						.collect::<Vec<_>>()
 						.join("\n")
 				};
-				let text_text = (scroll_y..scroll_y.saturating_add(visible_rows))
-					.map(|row_idx| {
+				let text_text = unwrapped_rows
+					.iter()
+					.map(|&UnwrappedViewportRow { row_idx, fold }| {
+						if let Some(fold) = fold {
+							return fold_summary_line(buffer_text, fold, text_width as usize);
+						}
+						let visible_cols = scroll_x.saturating_add(text_width as usize);
 						let line = buffer_text
-							.and_then(|text| rope_logical_line(text, row_idx))
+							.and_then(|text| rope_logical_line_covering_cols(text, row_idx, visible_cols))
 							.unwrap_or_else(empty_owned_logical_line);
-						let rendered = render_line_for_display(line.text.as_str(), line.has_newline);
+						let rendered = render_logical_line_for_display(&line, list_chars);
 						visible_slice_by_display_width(&rendered, scroll_x, text_width as usize)
 					})
 					.collect::<Vec<_>>()
@@ -147,27 +223,254 @@ impl WindowAreaWidget {
 				(Vec::new(), line_numbers_text, text_text)
 			};
 
+			let content_rows = if word_wrap {
+				wrapped_rows.len().min(visible_rows)
+			} else {
+				unwrapped_rows.iter().take_while(|row| row.row_idx < total_lines).count()
+			} as u16;
+			let fold_rows = if word_wrap {
+				wrapped_rows
+					.iter()
+					.take(visible_rows)
+					.enumerate()
+					.filter(|(_, row)| closed_fold_at(row.logical_row).is_some())
+					.map(|(screen_row, _)| screen_row)
+					.collect::<Vec<_>>()
+			} else {
+				unwrapped_rows
+					.iter()
+					.enumerate()
+					.filter(|(_, row)| row.fold.is_some())
+					.map(|(screen_row, _)| screen_row)
+					.collect()
+			};
+			fold_segments.extend(fold_rows.into_iter().map(|screen_row| SelectionSegment {
+				x_start: text_rect.x,
+				x_end:   text_rect.x.saturating_add(text_rect.width),
+				y:       text_rect.y.saturating_add(screen_row as u16),
+			}));
+			let line_signs = match window.buffer_id {
+				Some(_) if number_col_width > 0 => {
+					if word_wrap {
+						line_signs_for_wrapped_rows(state, window_id, wrapped_rows.as_slice(), visible_rows)
+					} else {
+						let signs = state.window_line_change_signs(window_id, first_row..first_row + covered_rows);
+						signs_for_unwrapped_rows(unwrapped_rows.as_slice(), first_row, &signs)
+					}
+				}
+				_ => Vec::new(),
+			};
+			// Diff mode also marks added and changed rows across the whole text width.
+			let diff_rows = if word_wrap {
+				let rows = &wrapped_rows[..wrapped_rows.len().min(visible_rows)];
+				match (rows.first(), rows.last()) {
+					(Some(first), Some(last)) => state
+						.window_diff_signs(window_id, first.logical_row..last.logical_row + 1)
+						.map(|signs| rows.iter().map(|row| signs[row.logical_row - first.logical_row]).collect()),
+					_ => None,
+				}
+			} else {
+				state
+					.window_diff_signs(window_id, first_row..first_row + covered_rows)
+					.map(|signs| signs_for_unwrapped_rows(unwrapped_rows.as_slice(), first_row, &signs))
+			};
+			diff_segments.extend(diff_rows.unwrap_or_default().into_iter().enumerate().filter_map(
+				|(screen_row, sign)| match sign? {
+					LineChangeSign::Deleted => None,
+					sign => Some((
+						SelectionSegment {
+							x_start: text_rect.x,
+							x_end:   text_rect.x.saturating_add(text_rect.width),
+							y:       text_rect.y.saturating_add(screen_row as u16),
+						},
+						sign,
+					)),
+				},
+			));
+			let diagnostic_signs = match window.buffer_id {
+				Some(buffer_id) if number_col_width > 0 => {
+					if word_wrap {
+						diagnostic_signs_for_wrapped_rows(state, buffer_id, wrapped_rows.as_slice(), visible_rows)
+					} else {
+						let signs = state.buffer_diagnostic_signs(buffer_id, first_row..first_row + covered_rows);
+						signs_for_unwrapped_rows(unwrapped_rows.as_slice(), first_row, &signs)
+					}
+				}
+				_ => Vec::new(),
+			};
+			if state.workbench.indent_guides
+				&& let Some(text) = buffer_text
+			{
+				let shift_width = window.buffer_id.map_or(state.workbench.shift_width, |buffer_id| {
+					state.buffer_indent_settings(buffer_id).shift_width
+				});
+				let guides = collect_indent_guides(
+					text,
+					if word_wrap { text_rect } else { covered_rect },
+					(scroll_x, first_row),
+					shift_width,
+					word_wrap.then_some(wrapped_rows.as_slice()),
+				);
+				if word_wrap {
+					indent_guides.extend(guides);
+				} else {
+					indent_guides.extend(
+						guides.into_iter().filter_map(|guide| Some(GuideCell { y: to_screen_y(guide.y)?, ..guide })),
+					);
+				}
+			}
+			if state.workbench.spell
+				&& let Some(text) = buffer_text
+			{
+				let segments = collect_spell_segments(
+					state,
+					text,
+					if word_wrap { text_rect } else { covered_rect },
+					(scroll_x, first_row),
+					word_wrap.then_some(wrapped_rows.as_slice()),
+				);
+				spell_segments.extend(segments.into_iter().filter_map(|segment| {
+					if word_wrap {
+						return Some(segment);
+					}
+					Some(SelectionSegment { y: to_screen_y(segment.y)?, ..segment })
+				}));
+			}
+			if let Some(buffer_id) = window.buffer_id
+				&& let Some(text) = buffer_text
+			{
+				let segments = collect_diagnostic_segments(
+					state,
+					buffer_id,
+					text,
+					if word_wrap { text_rect } else { covered_rect },
+					(scroll_x, first_row),
+					word_wrap.then_some(wrapped_rows.as_slice()),
+				);
+				diagnostic_segments.extend(segments.into_iter().filter_map(|(segment, severity)| {
+					if word_wrap {
+						return Some((segment, severity));
+					}
+					Some((SelectionSegment { y: to_screen_y(segment.y)?, ..segment }, severity))
+				}));
+			}
+
+			if let Some(preview) = substitute_preview {
+				let preview_ranges = |row: usize, _: &OwnedLogicalLine| {
+					let first = preview.ranges.partition_point(|(range_row, _)| *range_row < row);
+					preview.ranges[first..]
+						.iter()
+						.take_while(|(range_row, _)| *range_row == row)
+						.map(|(_, range)| (range.clone(), ()))
+						.collect::<Vec<_>>()
+				};
+				let segments = collect_char_range_segments(
+					&preview.text,
+					if word_wrap { text_rect } else { covered_rect },
+					(scroll_x, first_row),
+					word_wrap.then_some(wrapped_rows.as_slice()),
+					preview_ranges,
+				);
+				substitute_segments.extend(segments.into_iter().filter_map(|(segment, ())| {
+					if word_wrap {
+						return Some(segment);
+					}
+					Some(SelectionSegment { y: to_screen_y(segment.y)?, ..segment })
+				}));
+			}
+
+			// Every window showing the buffer flashes what was just yanked.
+			if let Some(highlight) =
+				window.buffer_id.and_then(|buffer_id| state.yank_highlight_for_buffer(buffer_id))
+				&& let Some(text) = buffer_text
+			{
+				let segments = match highlight.block_display_cols {
+					Some((anchor_display, cursor_display)) if word_wrap => {
+						collect_visual_block_selection_segments_rope_wrapped(
+							text_rect,
+							highlight.start.row,
+							highlight.end.row,
+							anchor_display,
+							cursor_display,
+							wrapped_rows.as_slice(),
+						)
+					}
+					Some((anchor_display, cursor_display)) => collect_visual_block_selection_segments_rope(
+						covered_rect,
+						window.scroll_x,
+						first_row as u16,
+						highlight.start.row,
+						highlight.end.row,
+						anchor_display,
+						cursor_display,
+					),
+					None if word_wrap => collect_visual_selection_segments_rope_wrapped(
+						text,
+						VisualSelectionSpec {
+							text_rect,
+							scroll_x: 0,
+							scroll_y: window.scroll_y,
+							anchor: highlight.start,
+							cursor: highlight.end,
+							line_wise: highlight.line_wise,
+							block_wise: false,
+						},
+						wrapped_rows.as_slice(),
+					),
+					None => collect_visual_selection_segments_rope(text, VisualSelectionSpec {
+						text_rect:  covered_rect,
+						scroll_x:   window.scroll_x,
+						scroll_y:   first_row as u16,
+						anchor:     highlight.start,
+						cursor:     highlight.end,
+						line_wise:  highlight.line_wise,
+						block_wise: false,
+					}),
+				};
+				yank_segments.extend(segments.into_iter().filter_map(|segment| {
+					if word_wrap {
+						return Some(segment);
+					}
+					Some(SelectionSegment { y: to_screen_y(segment.y)?, ..segment })
+				}));
+			}
+
 			if state.active_window_id() == window_id {
 				let cursor = state.active_cursor();
 				let line_idx = cursor.row.saturating_sub(1) as usize;
+				let cursor_col_chars = cursor.col.saturating_sub(1) as usize;
+				// A little past the cursor keeps the grapheme under it whole.
 				let active_line = buffer_text
-					.and_then(|text| rope_logical_line(text, line_idx))
+					.and_then(|text| rope_logical_line_prefix(text, line_idx, cursor_col_chars.saturating_add(32)))
 					.map(|line| line.text)
 					.unwrap_or_default();
-				let cursor_col_chars = cursor.col.saturating_sub(1) as usize;
 				let cursor_display_col = if let Some(block_insert) = state.pending_block_insert {
 					block_insert.cursor_display_col as usize
 				} else if state.is_visual_block_mode() {
 					state.visual_block_cursor_display_col.unwrap_or_else(|| {
 						display_width_of_char_prefix_with_virtual(active_line.as_str(), cursor_col_chars) as u16
 					}) as usize
+				} else if let Some(display_col) = state.active_virtual_display_col() {
+					display_col as usize
 				} else {
 					display_width_of_char_prefix(active_line.as_str(), cursor_col_chars)
 				};
-				let cursor_line = cursor.row.saturating_sub(1);
+				let cursor_fold = closed_fold_at(cursor.row.saturating_sub(1) as usize);
+				// The cursor sits at the start of the summary row of a closed fold.
+				let cursor_display_col = if cursor_fold.is_some() { 0 } else { cursor_display_col };
+				let cursor_line = cursor.row.saturating_sub(1) as usize;
 				if word_wrap {
+					let cursor_row = cursor_fold.map_or(cursor.row, |fold| fold.start_row);
+					let hidden_rows = window.buffer_id.map_or(0, |buffer_id| {
+						state.buffer_fold_hidden_wrapped_rows(
+							buffer_id,
+							cursor_row.saturating_sub(1) as usize,
+							text_width as usize,
+						)
+					});
 					let cursor_wrapped_row =
-						wrapped_row_index_for_cursor(buffer_text, cursor.row, cursor_display_col, text_width as usize);
+						wrapped_row_index_for_cursor(buffer_text, cursor_row, cursor_display_col, text_width as usize)
+							.saturating_sub(hidden_rows);
 					let top = window.scroll_y as usize;
 					let bottom_exclusive = top.saturating_add(text_rect.height as usize);
 					if cursor_wrapped_row >= top && cursor_wrapped_row < bottom_exclusive {
@@ -182,15 +485,20 @@ impl WindowAreaWidget {
 						));
 					}
 				} else {
-					let row_in_view =
-						cursor_line >= window.scroll_y && cursor_line < window.scroll_y.saturating_add(text_rect.height);
-					let col_in_view_left = cursor_display_col >= scroll_x;
-					if row_in_view && col_in_view_left {
+					let cursor_screen_row = unwrapped_rows.iter().position(|row| match row.fold {
+						Some(fold) => fold.start_row as usize <= cursor_line + 1 && cursor_line < fold.end_row as usize,
+						None => row.row_idx == cursor_line,
+					});
+					let cursor_scroll_x = if cursor_fold.is_some() { 0 } else { scroll_x };
+					let col_in_view_left = cursor_display_col >= cursor_scroll_x;
+					if let Some(cursor_screen_row) = cursor_screen_row
+						&& col_in_view_left
+					{
 						let cursor_x_offset = cursor_display_col
-							.saturating_sub(scroll_x)
+							.saturating_sub(cursor_scroll_x)
 							.min(text_rect.width.saturating_sub(1) as usize) as u16;
 						let cursor_x_local = text_rect.x.saturating_add(cursor_x_offset);
-						let cursor_y_local = text_rect.y.saturating_add(cursor_line.saturating_sub(window.scroll_y));
+						let cursor_y_local = text_rect.y.saturating_add(cursor_screen_row as u16);
 						cursor_position = Some((
 							content_area.x.saturating_add(cursor_x_local),
 							content_area.y.saturating_add(cursor_y_local),
@@ -200,6 +508,7 @@ impl WindowAreaWidget {
 
 				if state.is_visual_mode()
 					&& let Some(anchor) = state.visual_anchor
+					&& let Some((start, end)) = state.normalized_visual_bounds()
 					&& let Some(text) = buffer_text
 				{
 					if state.is_visual_block_mode()
@@ -216,49 +525,84 @@ impl WindowAreaWidget {
 								wrapped_rows.as_slice(),
 							));
 						} else {
-							selection_segments.extend(collect_visual_block_selection_segments_rope(
-								text_rect,
+							let segments = collect_visual_block_selection_segments_rope(
+								covered_rect,
 								window.scroll_x,
-								window.scroll_y,
+								first_row as u16,
 								anchor.row,
 								cursor.row,
 								anchor_display,
 								cursor_display,
-							));
+							);
+							selection_segments.extend(
+								segments
+									.into_iter()
+									.filter_map(|segment| Some(SelectionSegment { y: to_screen_y(segment.y)?, ..segment })),
+							);
 						}
 					} else if word_wrap {
+						// Char and line selections draw the same span the editor operates on.
 						selection_segments.extend(collect_visual_selection_segments_rope_wrapped(
 							text,
 							VisualSelectionSpec {
 								text_rect,
 								scroll_x: 0,
 								scroll_y: window.scroll_y,
-								anchor,
-								cursor,
+								anchor: start,
+								cursor: end,
 								line_wise: state.is_visual_line_mode(),
 								block_wise: state.is_visual_block_mode(),
 							},
 							wrapped_rows.as_slice(),
 						));
 					} else {
-						selection_segments.extend(collect_visual_selection_segments_rope(text, VisualSelectionSpec {
-							text_rect,
-							scroll_x: window.scroll_x,
-							scroll_y: window.scroll_y,
-							anchor,
-							cursor,
-							line_wise: state.is_visual_line_mode(),
+						let segments = collect_visual_selection_segments_rope(text, VisualSelectionSpec {
+							text_rect:  covered_rect,
+							scroll_x:   window.scroll_x,
+							scroll_y:   first_row as u16,
+							anchor:     start,
+							cursor:     end,
+							line_wise:  state.is_visual_line_mode(),
 							block_wise: state.is_visual_block_mode(),
-						}));
+						});
+						selection_segments.extend(
+							segments
+								.into_iter()
+								.filter_map(|segment| Some(SelectionSegment { y: to_screen_y(segment.y)?, ..segment })),
+						);
 					}
 				}
 			}
 
-			windows.push(WindowView { local_rect, number_col_width, line_numbers_text, text_text, word_wrap });
+			windows.push(WindowView {
+				local_rect,
+				number_col_width,
+				line_numbers_text,
+				line_signs,
+				diagnostic_signs,
+				content_rows,
+				text_text,
+				word_wrap,
+			});
 		}
 
 		let (vertical_lines, horizontal_lines) = collect_split_lines(state, content_area);
-		(Self { windows, selection_segments, vertical_lines, horizontal_lines }, cursor_position)
+		(
+			Self {
+				windows,
+				selection_segments,
+				yank_segments,
+				substitute_segments,
+				spell_segments,
+				diagnostic_segments,
+				diff_segments,
+				fold_segments,
+				indent_guides,
+				vertical_lines,
+				horizontal_lines,
+			},
+			cursor_position,
+		)
 	}
 }
 
@@ -266,10 +610,12 @@ impl WindowAreaWidget {
 struct OwnedLogicalLine {
 	text:        String,
 	has_newline: bool,
+	// Only a prefix of the line was taken; the rest lies past the viewport.
+	truncated:   bool,
 }
 
 fn empty_owned_logical_line() -> OwnedLogicalLine {
-	OwnedLogicalLine { text: String::new(), has_newline: false }
+	OwnedLogicalLine { text: String::new(), has_newline: false, truncated: false }
 }
 
 #[cfg(test)]
@@ -324,11 +670,57 @@ fn rope_logical_line(text: &Rope, row_idx: usize) -> Option<OwnedLogicalLine> {
 			line.pop();
 		}
 	}
-	Some(OwnedLogicalLine { text: line, has_newline })
+	Some(OwnedLogicalLine { text: line, has_newline, truncated: false })
+}
+
+fn rope_logical_line_prefix(text: &Rope, row_idx: usize, max_chars: usize) -> Option<OwnedLogicalLine> {
+	if row_idx >= rope_display_line_count(text) {
+		return None;
+	}
+	let line = text.line(row_idx);
+	if line.len_chars() <= max_chars {
+		return rope_logical_line(text, row_idx);
+	}
+	Some(OwnedLogicalLine {
+		text:        line.slice(..max_chars).to_string(),
+		has_newline: false,
+		truncated:   true,
+	})
+}
+
+// Takes just enough of the line to fill `cols` display columns, so very long
+// lines cost the width of the viewport instead of the whole line. The prefix
+// grows until it covers the columns, which only repeats for lines full of
+// zero-width chars.
+fn rope_logical_line_covering_cols(text: &Rope, row_idx: usize, cols: usize) -> Option<OwnedLogicalLine> {
+	let mut max_chars = cols.saturating_add(TAB_DISPLAY_WIDTH);
+	loop {
+		let line = rope_logical_line_prefix(text, row_idx, max_chars)?;
+		if !line.truncated
+			|| char_display_widths(line.text.as_str()).into_iter().sum::<usize>()
+				>= cols.saturating_add(TAB_DISPLAY_WIDTH)
+		{
+			return Some(line);
+		}
+		max_chars = max_chars.saturating_mul(2);
+	}
+}
+
+fn render_logical_line_for_display(line: &OwnedLogicalLine, list_chars: Option<ListChars>) -> String {
+	match list_chars {
+		// Trailing spaces of a truncated line are not the end of the real line.
+		Some(list_chars) if line.truncated => {
+			expand_whitespace_for_list_display(line.text.as_str(), list_chars, false)
+		}
+		_ => render_line_for_display(line.text.as_str(), line.has_newline, list_chars),
+	}
 }
 
-fn render_line_for_display(line: &str, has_newline: bool) -> String {
-	let expanded_line = expand_tabs_for_display(line);
+fn render_line_for_display(line: &str, has_newline: bool, list_chars: Option<ListChars>) -> String {
+	let expanded_line = match list_chars {
+		Some(list_chars) => expand_whitespace_for_list_display(line, list_chars, true),
+		None => expand_tabs_for_display(line),
+	};
 	if has_newline {
 		let mut rendered = String::with_capacity(expanded_line.len().saturating_add(3));
 		rendered.push_str(expanded_line.as_str());
@@ -344,15 +736,25 @@ fn collect_wrapped_viewport_rows(
 	scroll_y: usize,
 	visible_rows: usize,
 	width: usize,
+	list_chars: Option<ListChars>,
+	closed_fold_at: impl Fn(usize) -> Option<Fold>,
 ) -> Vec<WrappedViewportRow> {
 	let mut rows = Vec::new();
 	let mut row_idx = 0usize;
 	let mut skipped_wrapped_rows = 0usize;
 	while rows.len() < visible_rows {
-		let line =
-			buffer_text.and_then(|text| rope_logical_line(text, row_idx)).unwrap_or_else(empty_owned_logical_line);
-		let rendered = render_line_for_display(line.text.as_str(), line.has_newline);
-		let wrapped = wrap_line_with_display_span(rendered.as_str(), width);
+		// A closed fold is one summary row covering no display columns.
+		let fold = closed_fold_at(row_idx);
+		let wrapped = match fold {
+			Some(fold) => vec![(0, 0, fold_summary_line(buffer_text, fold, width))],
+			None => {
+				let line = buffer_text
+					.and_then(|text| rope_logical_line(text, row_idx))
+					.unwrap_or_else(empty_owned_logical_line);
+				let rendered = render_line_for_display(line.text.as_str(), line.has_newline, list_chars);
+				wrap_line_with_display_span(rendered.as_str(), width)
+			}
+		};
 		let wrapped_len = wrapped.len();
 		if skipped_wrapped_rows.saturating_add(wrapped_len) <= scroll_y {
 			skipped_wrapped_rows = skipped_wrapped_rows.saturating_add(wrapped_len);
@@ -369,6 +771,9 @@ fn collect_wrapped_viewport_rows(
 		if rows.len() >= visible_rows {
 			break;
 		}
+		if let Some(fold) = fold {
+			row_idx = (fold.end_row as usize).saturating_sub(1).max(row_idx);
+		}
 		if buffer_text.and_then(|text| rope_logical_line(text, row_idx + 1)).is_none() {
 			break;
 		}
@@ -377,6 +782,59 @@ fn collect_wrapped_viewport_rows(
 	rows
 }
 
+// Without wrap each screen row shows one buffer row, or one row for the whole
+// of a closed fold. A viewport that starts inside a fold shows all of it.
+fn collect_unwrapped_viewport_rows(
+	scroll_y: usize,
+	visible_rows: usize,
+	closed_fold_at: impl Fn(usize) -> Option<Fold>,
+) -> Vec<UnwrappedViewportRow> {
+	let mut rows = Vec::with_capacity(visible_rows);
+	let mut row_idx = scroll_y;
+	while rows.len() < visible_rows {
+		match closed_fold_at(row_idx) {
+			Some(fold) => {
+				let start = (fold.start_row as usize).saturating_sub(1);
+				rows.push(UnwrappedViewportRow { row_idx: start, fold: Some(fold) });
+				row_idx = (fold.end_row as usize).max(row_idx + 1);
+			}
+			None => {
+				rows.push(UnwrappedViewportRow { row_idx, fold: None });
+				row_idx += 1;
+			}
+		}
+	}
+	rows
+}
+
+fn fold_summary_line(buffer_text: Option<&Rope>, fold: Fold, width: usize) -> String {
+	let first_line = buffer_text
+		.and_then(|text| rope_logical_line(text, (fold.start_row as usize).saturating_sub(1)))
+		.map(|line| expand_tabs_for_display(line.text.trim()))
+		.unwrap_or_default();
+	let line_count = fold.end_row.saturating_sub(fold.start_row).saturating_add(1);
+	let summary = format!("+--{line_count:>3} lines: {first_line} ");
+	let summary = visible_slice_by_display_width(&summary, 0, width);
+	let fill = width.saturating_sub(display_width_of_char_prefix(&summary, summary.chars().count()));
+	format!("{summary}{}", "-".repeat(fill))
+}
+
+// A closed fold shows the first sign found on any of its rows.
+fn signs_for_unwrapped_rows<T: Copy>(
+	rows: &[UnwrappedViewportRow],
+	first_row: usize,
+	signs: &[Option<T>],
+) -> Vec<Option<T>> {
+	rows
+		.iter()
+		.map(|row| {
+			let start = row.row_idx.saturating_sub(first_row);
+			let end = row.fold.map_or(row.row_idx + 1, |fold| fold.end_row as usize).saturating_sub(first_row);
+			signs.get(start..end.min(signs.len())).and_then(|signs| signs.iter().find_map(|sign| *sign))
+		})
+		.collect()
+}
+
 fn line_numbers_for_wrapped_rows(rows: &[WrappedViewportRow], number_col_width: u16) -> String {
 	let mut previous_row = None;
 	rows
@@ -394,6 +852,74 @@ fn line_numbers_for_wrapped_rows(rows: &[WrappedViewportRow], number_col_width:
 		.join("\n")
 }
 
+fn line_signs_for_wrapped_rows(
+	state: &RimState,
+	window_id: WindowId,
+	rows: &[WrappedViewportRow],
+	visible_rows: usize,
+) -> Vec<Option<LineChangeSign>> {
+	signs_for_wrapped_rows(rows, visible_rows, |logical_rows| {
+		state.window_line_change_signs(window_id, logical_rows)
+	})
+}
+
+fn diagnostic_signs_for_wrapped_rows(
+	state: &RimState,
+	buffer_id: BufferId,
+	rows: &[WrappedViewportRow],
+	visible_rows: usize,
+) -> Vec<Option<DiagnosticSeverity>> {
+	signs_for_wrapped_rows(rows, visible_rows, |logical_rows| {
+		state.buffer_diagnostic_signs(buffer_id, logical_rows)
+	})
+}
+
+// A sign belongs to the first screen row of its logical line.
+fn signs_for_wrapped_rows<T: Copy>(
+	rows: &[WrappedViewportRow],
+	visible_rows: usize,
+	logical_signs: impl FnOnce(Range<usize>) -> Vec<Option<T>>,
+) -> Vec<Option<T>> {
+	let rows = &rows[..rows.len().min(visible_rows)];
+	let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+		return Vec::new();
+	};
+	let logical_signs = logical_signs(first.logical_row..last.logical_row + 1);
+	let mut previous_row = None;
+	rows
+		.iter()
+		.map(|row| {
+			let first_segment = previous_row != Some(row.logical_row);
+			previous_row = Some(row.logical_row);
+			if first_segment { logical_signs[row.logical_row - first.logical_row] } else { None }
+		})
+		.collect()
+}
+
+fn line_change_sign_cell(sign: LineChangeSign) -> (&'static str, Color) {
+	match sign {
+		LineChangeSign::Added => ("+", Color::Green),
+		LineChangeSign::Changed => ("~", Color::Yellow),
+		LineChangeSign::Deleted => ("-", Color::Red),
+	}
+}
+
+fn diff_row_bg(sign: LineChangeSign) -> Color {
+	match sign {
+		LineChangeSign::Added => Color::Rgb(24, 56, 32),
+		LineChangeSign::Changed | LineChangeSign::Deleted => Color::Rgb(36, 40, 72),
+	}
+}
+
+fn diagnostic_sign_cell(severity: DiagnosticSeverity) -> (&'static str, Color) {
+	match severity {
+		DiagnosticSeverity::Error => ("E", Color::Red),
+		DiagnosticSeverity::Warning => ("W", Color::Yellow),
+		DiagnosticSeverity::Information => ("I", Color::Blue),
+		DiagnosticSeverity::Hint => ("H", Color::Cyan),
+	}
+}
+
 fn wrapped_row_index_for_cursor(
 	buffer_text: Option<&Rope>,
 	cursor_row: u16,
@@ -441,11 +967,59 @@ impl Widget for WindowAreaWidget {
 			Paragraph::new(window.line_numbers_text.as_str())
 				.style(Style::default().fg(Color::DarkGray))
 				.render(number_rect, buf);
+			// Change signs reuse the separator column between line numbers and text.
+			let sign_x = number_rect.x.saturating_add(number_rect.width.saturating_sub(1));
+			for (row_offset, sign) in window.line_signs.iter().enumerate().take(number_rect.height as usize) {
+				let Some(sign) = sign else {
+					continue;
+				};
+				let (symbol, color) = line_change_sign_cell(*sign);
+				if let Some(cell) = buf.cell_mut((sign_x, number_rect.y.saturating_add(row_offset as u16))) {
+					cell.set_symbol(symbol).set_fg(color);
+				}
+			}
+			// Diagnostics win the shared column over change signs.
+			for (row_offset, severity) in
+				window.diagnostic_signs.iter().enumerate().take(number_rect.height as usize)
+			{
+				let Some(severity) = severity else {
+					continue;
+				};
+				let (symbol, color) = diagnostic_sign_cell(*severity);
+				if let Some(cell) = buf.cell_mut((sign_x, number_rect.y.saturating_add(row_offset as u16))) {
+					cell.set_symbol(symbol).set_fg(color);
+				}
+			}
 			if window.word_wrap {
 				Paragraph::new(window.text_text.as_str()).wrap(Wrap { trim: false }).render(text_rect, buf);
 			} else {
 				Paragraph::new(window.text_text.as_str()).render(text_rect, buf);
 			}
+			// Rows past the end of the buffer are marked with a dim "~" in the first
+			// column.
+			for row_offset in window.content_rows..abs_rect.height {
+				if let Some(cell) = buf.cell_mut((abs_rect.x, abs_rect.y.saturating_add(row_offset))) {
+					cell.set_symbol("~").set_fg(Color::DarkGray);
+				}
+			}
+		}
+
+		for (segment, sign) in self.diff_segments {
+			let abs_y = area.y.saturating_add(segment.y);
+			for x in segment.x_start..segment.x_end {
+				if let Some(cell) = buf.cell_mut((area.x.saturating_add(x), abs_y)) {
+					cell.set_bg(diff_row_bg(sign));
+				}
+			}
+		}
+
+		// Guides only take blank cells, so list-mode tab glyphs stay visible.
+		for guide in self.indent_guides {
+			if let Some(cell) = buf.cell_mut((area.x.saturating_add(guide.x), area.y.saturating_add(guide.y)))
+				&& cell.symbol() == " "
+			{
+				cell.set_symbol("│").set_fg(Color::DarkGray);
+			}
 		}
 
 		for segment in self.selection_segments {
@@ -458,6 +1032,57 @@ impl Widget for WindowAreaWidget {
 			}
 		}
 
+		for segment in self.yank_segments {
+			let abs_y = area.y.saturating_add(segment.y);
+			for x in segment.x_start..segment.x_end {
+				if let Some(cell) = buf.cell_mut((area.x.saturating_add(x), abs_y)) {
+					cell.set_bg(Color::Rgb(96, 72, 24));
+				}
+			}
+		}
+
+		for segment in self.substitute_segments {
+			let abs_y = area.y.saturating_add(segment.y);
+			for x in segment.x_start..segment.x_end {
+				if let Some(cell) = buf.cell_mut((area.x.saturating_add(x), abs_y)) {
+					cell.set_bg(Color::Yellow).set_fg(Color::Black);
+				}
+			}
+		}
+
+		for segment in self.spell_segments {
+			let abs_y = area.y.saturating_add(segment.y);
+			for x in segment.x_start..segment.x_end {
+				let abs_x = area.x.saturating_add(x);
+				if let Some(cell) = buf.cell_mut((abs_x, abs_y)) {
+					cell.set_style(Style::default().fg(Color::Red).add_modifier(Modifier::UNDERLINED));
+				}
+			}
+		}
+
+		for segment in self.fold_segments {
+			let abs_y = area.y.saturating_add(segment.y);
+			for x in segment.x_start..segment.x_end {
+				if let Some(cell) = buf.cell_mut((area.x.saturating_add(x), abs_y)) {
+					cell.set_fg(Color::Cyan);
+				}
+			}
+		}
+
+		let mut diagnostic_segments = self.diagnostic_segments;
+		// Worse severities are drawn last so they stay visible where ranges overlap.
+		diagnostic_segments.sort_by_key(|(_, severity)| Reverse(*severity));
+		for (segment, severity) in diagnostic_segments {
+			let (_, color) = diagnostic_sign_cell(severity);
+			let abs_y = area.y.saturating_add(segment.y);
+			for x in segment.x_start..segment.x_end {
+				let abs_x = area.x.saturating_add(x);
+				if let Some(cell) = buf.cell_mut((abs_x, abs_y)) {
+					cell.set_style(Style::default().fg(color).add_modifier(Modifier::UNDERLINED));
+				}
+			}
+		}
+
 		for line in self.horizontal_lines {
 			let abs_y = area.y.saturating_add(line.y);
 			if let Some(x) = line.left_join_x {
@@ -505,6 +1130,175 @@ impl Widget for WindowAreaWidget {
 	}
 }
 
+// One guide per `shift_width` columns of leading whitespace, at the display
+// columns the indent occupies after tab expansion. Wrapped continuation rows
+// carry no indent, so only the first row of each line gets guides.
+fn collect_indent_guides(
+	text: &Rope,
+	text_rect: Rect,
+	(scroll_x, scroll_y): (usize, usize),
+	shift_width: usize,
+	wrapped_rows: Option<&[WrappedViewportRow]>,
+) -> Vec<GuideCell> {
+	let shift_width = shift_width.max(1);
+	let visible_rows = text_rect.height as usize;
+	let view_cols = text_rect.width as usize;
+	let indent_width = |line: &OwnedLogicalLine| {
+		display_width_of_char_prefix(line.text.as_str(), leading_indent(line.text.as_str()).chars().count())
+	};
+	let rows = match wrapped_rows {
+		Some(wrapped_rows) => wrapped_rows
+			.iter()
+			.take(visible_rows)
+			.enumerate()
+			.filter(|(_, row)| row.start_display == 0)
+			.filter_map(|(y, row)| {
+				let line = rope_logical_line(text, row.logical_row)?;
+				Some((y, indent_width(&line).min(row.end_display), 0))
+			})
+			.collect::<Vec<_>>(),
+		None => (scroll_y..scroll_y.saturating_add(visible_rows))
+			.enumerate()
+			.map_while(|(y, row_idx)| {
+				let line = rope_logical_line_covering_cols(text, row_idx, scroll_x.saturating_add(view_cols))?;
+				Some((y, indent_width(&line), scroll_x))
+			})
+			.collect(),
+	};
+	let mut guides = Vec::new();
+	for (y, width, view_start) in rows {
+		for col in (0..width).step_by(shift_width) {
+			if col >= view_start && col < view_start.saturating_add(view_cols) {
+				guides.push(GuideCell {
+					x: text_rect.x.saturating_add(col.saturating_sub(view_start) as u16),
+					y: text_rect.y.saturating_add(y as u16),
+				});
+			}
+		}
+	}
+	guides
+}
+
+// Misspelled words on the visible rows.
+fn collect_spell_segments(
+	state: &RimState,
+	text: &Rope,
+	text_rect: Rect,
+	scroll: (usize, usize),
+	wrapped_rows: Option<&[WrappedViewportRow]>,
+) -> Vec<SelectionSegment> {
+	let misspelled_ranges = |_: usize, line: &OwnedLogicalLine| {
+		let line_chars = line.text.chars().count();
+		state
+			.misspelled_char_ranges(line.text.as_str())
+			.into_iter()
+			// The word may go on past a truncated prefix.
+			.filter(|range| !line.truncated || range.end < line_chars)
+			.map(|range| (range, ()))
+			.collect::<Vec<_>>()
+	};
+	collect_char_range_segments(text, text_rect, scroll, wrapped_rows, misspelled_ranges)
+		.into_iter()
+		.map(|(segment, ())| segment)
+		.collect()
+}
+
+// A diagnostic spanning rows underlines the rest of its first line, every line
+// in between and the head of its last line. Empty ranges still get one cell.
+fn collect_diagnostic_segments(
+	state: &RimState,
+	buffer_id: BufferId,
+	text: &Rope,
+	text_rect: Rect,
+	scroll: (usize, usize),
+	wrapped_rows: Option<&[WrappedViewportRow]>,
+) -> Vec<(SelectionSegment, DiagnosticSeverity)> {
+	let diagnostics = state.buffer_diagnostics(buffer_id);
+	if diagnostics.is_empty() {
+		return Vec::new();
+	}
+	let diagnostic_ranges = |row: usize, line: &OwnedLogicalLine| {
+		let line_chars = line.text.chars().count();
+		diagnostics
+			.iter()
+			.filter(|diagnostic| diagnostic.start_row <= row && row <= diagnostic.end_row)
+			.map(|diagnostic| {
+				let start = if diagnostic.start_row == row { diagnostic.start_col } else { 0 };
+				let end = if diagnostic.end_row == row { diagnostic.end_col } else { line_chars };
+				(start..end, diagnostic.severity)
+			})
+			.collect::<Vec<_>>()
+	};
+	collect_char_range_segments(text, text_rect, scroll, wrapped_rows, diagnostic_ranges)
+}
+
+// Start and end display columns of a char range, with what it marks.
+type DisplaySpan<T> = ((usize, usize), T);
+
+// Char ranges on the visible rows, in the same local coordinates as the
+// selection. Without wrap rows are clipped to the horizontal scroll; with wrap
+// a range split across rows gets a segment on each.
+fn collect_char_range_segments<T: Copy>(
+	text: &Rope,
+	text_rect: Rect,
+	(scroll_x, scroll_y): (usize, usize),
+	wrapped_rows: Option<&[WrappedViewportRow]>,
+	line_ranges: impl Fn(usize, &OwnedLogicalLine) -> Vec<(Range<usize>, T)>,
+) -> Vec<(SelectionSegment, T)> {
+	let visible_rows = text_rect.height as usize;
+	let mut segments = Vec::new();
+	let mut push_span = |y: usize, (span, tag): DisplaySpan<T>, view_start: usize, view_end: usize| {
+		let (start, end) = (span.0.max(view_start), span.1.min(view_end));
+		if start < end {
+			let segment = SelectionSegment {
+				x_start: text_rect.x.saturating_add(start.saturating_sub(view_start) as u16),
+				x_end:   text_rect.x.saturating_add(end.saturating_sub(view_start) as u16),
+				y:       text_rect.y.saturating_add(y as u16),
+			};
+			segments.push((segment, tag));
+		}
+	};
+	let display_spans = |row: usize, line: &OwnedLogicalLine| {
+		line_ranges(row, line)
+			.into_iter()
+			.map(|(range, tag)| {
+				let start = display_width_of_char_prefix(line.text.as_str(), range.start);
+				let end = display_width_of_char_prefix(line.text.as_str(), range.end).max(start.saturating_add(1));
+				((start, end), tag)
+			})
+			.collect::<Vec<_>>()
+	};
+
+	let Some(wrapped_rows) = wrapped_rows else {
+		let visible_cols = scroll_x.saturating_add(text_rect.width as usize);
+		for (y, row_idx) in (scroll_y..scroll_y.saturating_add(visible_rows)).enumerate() {
+			let Some(line) = rope_logical_line_covering_cols(text, row_idx, visible_cols) else {
+				break;
+			};
+			for span in display_spans(row_idx, &line) {
+				push_span(y, span, scroll_x, visible_cols);
+			}
+		}
+		return segments;
+	};
+	let mut cached: Option<(usize, Vec<DisplaySpan<T>>)> = None;
+	for (y, row) in wrapped_rows.iter().take(visible_rows).enumerate() {
+		if cached.as_ref().is_none_or(|(logical_row, _)| *logical_row != row.logical_row) {
+			let spans = rope_logical_line(text, row.logical_row)
+				.map(|line| display_spans(row.logical_row, &line))
+				.unwrap_or_default();
+			cached = Some((row.logical_row, spans));
+		}
+		let Some((_, spans)) = cached.as_ref() else {
+			continue;
+		};
+		for span in spans {
+			push_span(y, *span, row.start_display, row.end_display);
+		}
+	}
+	segments
+}
+
 fn collect_split_lines(state: &RimState, content_area: Rect) -> (Vec<VerticalLine>, Vec<HorizontalLine>) {
 	let mut vertical_lines = Vec::new();
 	let mut horizontal_lines = Vec::new();
@@ -966,22 +1760,24 @@ fn visible_slice_by_display_width(line: &str, skip_cols: usize, max_cols: usize)
 		return String::new();
 	}
 
+	let widths = char_display_widths(line);
 	let mut consumed = 0usize;
 	let mut start = line.len();
-	for (idx, ch) in line.char_indices() {
-		let width = char_display_width(ch);
+	let mut start_char = widths.len();
+	for (char_idx, (idx, ch)) in line.char_indices().enumerate() {
+		let width = widths[char_idx];
 		if consumed + width <= skip_cols {
 			consumed += width;
 			continue;
 		}
-		start = if consumed < skip_cols { idx + ch.len_utf8() } else { idx };
+		(start, start_char) =
+			if consumed < skip_cols { (idx + ch.len_utf8(), char_idx + 1) } else { (idx, char_idx) };
 		break;
 	}
 
 	let mut out = String::new();
 	let mut used = 0usize;
-	for ch in line[start..].chars() {
-		let width = char_display_width(ch);
+	for (ch, width) in line[start..].chars().zip(widths[start_char.min(widths.len())..].iter().copied()) {
 		if width == 0 {
 			if !out.is_empty() {
 				out.push(ch);
@@ -997,7 +1793,7 @@ fn visible_slice_by_display_width(line: &str, skip_cols: usize, max_cols: usize)
 	out
 }
 
-fn char_display_width(ch: char) -> usize { geom_char_display_width(ch) }
+fn char_display_widths(line: &str) -> Vec<usize> { geom_char_display_widths(line) }
 
 fn expand_tabs_for_display(line: &str) -> String {
 	let mut rendered = String::with_capacity(line.len());
@@ -1011,6 +1807,25 @@ fn expand_tabs_for_display(line: &str) -> String {
 	rendered
 }
 
+// List glyphs are single-width, so cursor and selection math stays unchanged.
+fn expand_whitespace_for_list_display(line: &str, list_chars: ListChars, mark_trailing: bool) -> String {
+	let trailing_start = if mark_trailing { line.trim_end_matches(' ').chars().count() } else { usize::MAX };
+	let mut rendered = String::with_capacity(line.len());
+	for (idx, ch) in line.chars().enumerate() {
+		if ch == '\t' {
+			rendered.push(list_chars.tab_head);
+			for _ in 1..TAB_DISPLAY_WIDTH {
+				rendered.push(list_chars.tab_fill);
+			}
+		} else if ch == ' ' && idx >= trailing_start {
+			rendered.push(list_chars.trail);
+		} else {
+			rendered.push(ch);
+		}
+	}
+	rendered
+}
+
 fn set_separator_cell(cell: &mut Cell) { merge_cell(cell, DIR_LEFT | DIR_RIGHT); }
 
 fn set_right_tee_cell(cell: &mut Cell) { merge_cell(cell, DIR_UP | DIR_RIGHT); }
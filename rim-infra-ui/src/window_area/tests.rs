@@ -1,9 +1,11 @@
 use std::path::PathBuf;
 
-use ratatui::layout::Rect;
-use rim_application::state::{CursorState, RimState};
+use ratatui::{buffer::Buffer, layout::Rect, style::{Color, Modifier}, widgets::Widget};
+use rim_application::state::{BufferDiagnostic, CursorState, DiagnosticSeverity, IndentSettings, ListChars, RimState, SplitAxis};
+use rim_domain::display_geometry::TAB_DISPLAY_WIDTH;
+use ropey::Rope;
 
-use super::{DIR_DOWN, DIR_LEFT, DIR_RIGHT, DIR_UP, SelectionSegment, VisualSelectionSpec, WindowAreaWidget, collect_visual_selection_segments, dirs_from_symbol, display_width_of_char_prefix, render_line_for_display, symbol_from_dirs, visible_slice_by_display_width};
+use super::{DIR_DOWN, DIR_LEFT, DIR_RIGHT, DIR_UP, SelectionSegment, VisualSelectionSpec, WindowAreaWidget, collect_visual_selection_segments, dirs_from_symbol, display_width_of_char_prefix, render_line_for_display, rope_logical_line_covering_cols, symbol_from_dirs, visible_slice_by_display_width};
 
 fn merged_symbol(existing: &str, add_dirs: u8) -> &'static str {
 	symbol_from_dirs(dirs_from_symbol(existing) | add_dirs)
@@ -82,8 +84,18 @@ fn visible_slice_uses_display_columns() {
 
 #[test]
 fn render_line_for_display_should_expand_tab_to_spaces() {
-	assert_eq!(render_line_for_display("\t", false), "    ");
-	assert_eq!(render_line_for_display("a\tb", false), "a    b");
+	assert_eq!(render_line_for_display("\t", false, None), "    ");
+	assert_eq!(render_line_for_display("a\tb", false, None), "a    b");
+}
+
+#[test]
+fn render_line_for_display_should_show_list_glyphs_for_tabs_and_trailing_spaces() {
+	let list_chars = ListChars::default();
+	assert_eq!(render_line_for_display("\ta b  ", false, Some(list_chars)), "»   a b··");
+	assert_eq!(
+		render_line_for_display("a\t ", true, Some(ListChars { tab_head: '>', tab_fill: '-', trail: '.' })),
+		"a>---. "
+	);
 }
 
 #[test]
@@ -479,6 +491,64 @@ fn visual_selection_should_highlight_in_word_wrap_mode() {
 	assert!(!widget.selection_segments.is_empty());
 }
 
+fn render_selected_cells(state: &RimState, area: Rect) -> Vec<(u16, String)> {
+	let (widget, _) = WindowAreaWidget::from_state(state, area);
+	let mut buf = Buffer::empty(area);
+	widget.render(area, &mut buf);
+	let mut selected = Vec::new();
+	for y in area.top()..area.bottom() {
+		let row = (area.left()..area.right())
+			.filter_map(|x| buf.cell((x, y)))
+			.filter(|cell| cell.bg == Color::DarkGray)
+			.map(|cell| cell.symbol())
+			.collect::<String>();
+		if !row.is_empty() {
+			selected.push((y, row));
+		}
+	}
+	selected
+}
+
+#[test]
+fn char_wise_selection_should_style_exactly_the_selected_cells() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(Some(PathBuf::from("select.rs")), "abcdef\na\tbcd");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(20, 4);
+	state.move_cursor_right();
+	state.enter_visual_mode();
+	state.move_cursor_right_for_visual_char();
+	state.move_cursor_right_for_visual_char();
+
+	let area = Rect { x: 0, y: 0, width: 20, height: 4 };
+	assert_eq!(render_selected_cells(&state, area), vec![(0, "bcd".to_string())]);
+
+	state.exit_visual_mode();
+	state.move_cursor_down();
+	state.enter_visual_mode();
+	state.move_cursor_right_for_visual_char();
+	assert_eq!(render_selected_cells(&state, area), vec![(1, "    b".to_string())]);
+}
+
+#[test]
+fn line_wise_selection_should_style_whole_lines() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(Some(PathBuf::from("select.rs")), "ab\ncd\nef");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(20, 4);
+	state.move_cursor_down();
+	state.move_cursor_right();
+	state.enter_visual_line_mode();
+	state.move_cursor_down();
+
+	let area = Rect { x: 0, y: 0, width: 20, height: 4 };
+	assert_eq!(render_selected_cells(&state, area), vec![(1, "cd ".to_string()), (2, "ef".to_string())]);
+
+	let active_window = state.active_window_id();
+	state.windows.get_mut(active_window).expect("window exists").scroll_y = 1;
+	assert_eq!(render_selected_cells(&state, area), vec![(0, "cd ".to_string()), (1, "ef".to_string())]);
+}
+
 #[test]
 fn word_wrap_cursor_should_stay_inside_window_area_after_scroll() {
 	let mut state = RimState::new();
@@ -495,3 +565,264 @@ fn word_wrap_cursor_should_stay_inside_window_area_after_scroll() {
 	assert!(x >= content_area.x && x < content_area.x + content_area.width);
 	assert!(y >= content_area.y && y < content_area.y + content_area.height);
 }
+
+#[test]
+fn very_long_line_should_render_only_the_visible_slice() {
+	let mut line = "0123456789".repeat(300_000);
+	line.push_str("\tEND");
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(Some(PathBuf::from("min.js")), line.as_str());
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(40, 4);
+	let area = Rect { x: 0, y: 0, width: 40, height: 4 };
+	let text_row = |state: &RimState| {
+		let (widget, _) = WindowAreaWidget::from_state(state, area);
+		let mut buf = Buffer::empty(area);
+		widget.render(area, &mut buf);
+		(0..area.width).map(|x| buf[(x, 0)].symbol().to_string()).collect::<String>()
+	};
+
+	assert_eq!(text_row(&state), format!("1 {}", &line[..38]));
+	// Only the chars that cover the viewport are copied out of the rope.
+	let covered = rope_logical_line_covering_cols(&Rope::from_str(line.as_str()), 0, 38)
+		.expect("the long line should exist");
+	assert!(covered.truncated);
+	assert_eq!(covered.text, line[..38 + TAB_DISPLAY_WIDTH]);
+
+	let active_window = state.active_window_id();
+	state.windows.get_mut(active_window).expect("window exists").scroll_x = 25;
+	assert_eq!(text_row(&state), format!("1 {}", &line[25..63]));
+}
+
+#[test]
+fn indent_guides_should_mark_each_shiftwidth_level_at_tab_expanded_columns() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "fn a() {\n    if x {\n\tlet y;\n\t    z\n    }\n}");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(20, 6);
+	state.apply_set_option("indentguides").expect("indentguides should be a known option");
+	let area = Rect { x: 0, y: 0, width: 20, height: 6 };
+	let guide_columns = |state: &RimState| {
+		let (widget, _) = WindowAreaWidget::from_state(state, area);
+		let mut buf = Buffer::empty(area);
+		widget.render(area, &mut buf);
+		(0..area.height)
+			.map(|y| (0..area.width).filter(|x| buf[(*x, y)].symbol() == "│").collect::<Vec<_>>())
+			.collect::<Vec<_>>()
+	};
+
+	// Text starts after the two-column line number gutter.
+	assert_eq!(guide_columns(&state), vec![vec![], vec![2], vec![2], vec![2, 6], vec![2], vec![]]);
+
+	state.apply_set_option("shiftwidth=2").expect("shiftwidth should accept a width");
+	assert_eq!(guide_columns(&state), vec![
+		vec![],
+		vec![2, 4],
+		vec![2, 4],
+		vec![2, 4, 6, 8],
+		vec![2, 4],
+		vec![]
+	]);
+}
+
+#[test]
+fn indent_guides_should_follow_the_shiftwidth_of_the_window_buffer() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(Some(PathBuf::from("main.rs")), "fn a() {\n    x\n}");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(20, 3);
+	state.apply_set_option("indentguides").expect("indentguides should be a known option");
+	state.workbench.filetype_indent_settings.insert("rust".to_string(), IndentSettings {
+		tab_stop:    8,
+		shift_width: 2,
+		expand_tab:  true,
+	});
+	let area = Rect { x: 0, y: 0, width: 20, height: 3 };
+	let (widget, _) = WindowAreaWidget::from_state(&state, area);
+	let mut buf = Buffer::empty(area);
+	widget.render(area, &mut buf);
+
+	let guides = (0..area.width).filter(|x| buf[(*x, 1)].symbol() == "│").collect::<Vec<_>>();
+	assert_eq!(guides, vec![2, 4]);
+}
+
+#[test]
+fn diagnostics_should_draw_gutter_sign_and_underline_the_range() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "let x = 1;\nfoo(a\tb);");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(20, 3);
+	state.set_buffer_diagnostics(buffer_id, vec![
+		BufferDiagnostic {
+			start_row: 1,
+			start_col: 4,
+			end_row:   1,
+			end_col:   7,
+			severity:  DiagnosticSeverity::Error,
+			message:   "mismatched types".to_string(),
+		},
+		BufferDiagnostic {
+			start_row: 0,
+			start_col: 4,
+			end_row:   0,
+			end_col:   5,
+			severity:  DiagnosticSeverity::Warning,
+			message:   "unused variable".to_string(),
+		},
+	]);
+	let area = Rect { x: 0, y: 0, width: 20, height: 3 };
+	let (widget, _) = WindowAreaWidget::from_state(&state, area);
+	let mut buf = Buffer::empty(area);
+	widget.render(area, &mut buf);
+	let underlined = |y: u16, color: Color| {
+		(0..area.width)
+			.filter(|x| buf[(*x, y)].modifier.contains(Modifier::UNDERLINED) && buf[(*x, y)].fg == color)
+			.map(|x| buf[(x, y)].symbol())
+			.collect::<String>()
+	};
+
+	assert_eq!((buf[(1, 0)].symbol(), buf[(1, 0)].fg), ("W", Color::Yellow));
+	assert_eq!((buf[(1, 1)].symbol(), buf[(1, 1)].fg), ("E", Color::Red));
+	assert_eq!(buf[(1, 2)].symbol(), " ");
+	assert_eq!(underlined(0, Color::Yellow), "x");
+	// The tab expands to four cells and is underlined as a whole.
+	assert_eq!(underlined(1, Color::Red), "a    b");
+}
+
+#[test]
+fn closed_fold_should_collapse_to_one_summary_row_with_or_without_wrap() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "one\n\ttwo\nthree\nfour\nfive");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(24, 5);
+	state.create_fold(2, 3).expect("fold should be created");
+	let area = Rect { x: 0, y: 0, width: 24, height: 5 };
+	let rendered_rows = |state: &RimState| {
+		let (widget, cursor_position) = WindowAreaWidget::from_state(state, area);
+		let mut buf = Buffer::empty(area);
+		widget.render(area, &mut buf);
+		let rows = (0..area.height)
+			.map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect::<String>().trim_end().to_string())
+			.collect::<Vec<_>>();
+		(rows, buf[(2, 1)].fg, cursor_position)
+	};
+	let expected = vec![
+		"1 one".to_string(),
+		"2 +--  2 lines: two -----".chars().take(24).collect::<String>(),
+		"4 four".to_string(),
+		"5 five".to_string(),
+		"~".to_string(),
+	];
+
+	assert_eq!(rendered_rows(&state), (expected.clone(), Color::Cyan, Some((2, 1))));
+
+	state.toggle_word_wrap();
+	assert_eq!(rendered_rows(&state), (expected, Color::Cyan, Some((2, 1))));
+}
+
+#[test]
+fn diff_windows_should_highlight_added_and_changed_rows() {
+	let mut state = RimState::new();
+	let top = state.create_buffer(None, "a\nb\nc");
+	state.bind_buffer_to_active_window(top);
+	state.diff_this();
+	state.split_active_window(SplitAxis::Vertical);
+	let bottom = state.create_buffer(None, "a\nB\nc\nd");
+	state.bind_buffer_to_active_window(bottom);
+	state.update_active_tab_layout(20, 9);
+	let area = Rect { x: 0, y: 0, width: 20, height: 9 };
+	let (widget, _) = WindowAreaWidget::from_state(&state, area);
+	let mut buf = Buffer::empty(area);
+	widget.render(area, &mut buf);
+	let signs = (0..area.height).map(|y| buf[(1, y)].symbol().to_string()).collect::<String>();
+	let row_bgs = (0..area.height).map(|y| buf[(area.width - 1, y)].bg).collect::<Vec<_>>();
+	let (changed, added) = (Color::Rgb(36, 40, 72), Color::Rgb(24, 56, 32));
+
+	// The row only the bottom window has shows as deleted below `c` in the top one.
+	assert_eq!(signs, " ~- ─ ~ +");
+	assert_eq!(row_bgs, vec![
+		Color::Reset,
+		changed,
+		Color::Reset,
+		Color::Reset,
+		Color::Reset,
+		Color::Reset,
+		changed,
+		Color::Reset,
+		added
+	]);
+}
+
+#[test]
+fn yank_highlight_should_color_the_yanked_cells_until_it_expires() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "abcd\nefgh");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(10, 3);
+	state.move_cursor_right();
+	state.enter_visual_mode();
+	state.move_cursor_right();
+	state.yank_visual_selection_to_slot();
+	let area = Rect { x: 0, y: 0, width: 10, height: 3 };
+	let row_bgs = |state: &RimState| {
+		let (widget, _) = WindowAreaWidget::from_state(state, area);
+		let mut buf = Buffer::empty(area);
+		widget.render(area, &mut buf);
+		(2..7).map(|x| buf[(x, 0)].bg).collect::<Vec<_>>()
+	};
+	let yanked = Color::Rgb(96, 72, 24);
+
+	assert_eq!(row_bgs(&state), vec![Color::Reset, yanked, yanked, Color::Reset, Color::Reset]);
+
+	let expires_at = state.workbench.yank_highlight.expect("yank should set a highlight").expires_at;
+	state.clear_expired_yank_highlight(expires_at);
+	assert_eq!(row_bgs(&state), vec![Color::Reset; 5]);
+}
+
+#[test]
+fn substitute_preview_should_draw_the_replaced_text_highlighted() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(None, "a foo\nfoo");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.update_active_tab_layout(10, 3);
+	state.enter_command_mode();
+	"%s/foo/xy".chars().for_each(|ch| state.push_command_char(ch));
+	let area = Rect { x: 0, y: 0, width: 10, height: 3 };
+	let (widget, _) = WindowAreaWidget::from_state(&state, area);
+	let mut buf = Buffer::empty(area);
+	widget.render(area, &mut buf);
+
+	let row = |y: u16| (2..7).map(|x| buf[(x, y)].symbol().to_string()).collect::<String>();
+	assert_eq!(row(0), "a xy ");
+	assert_eq!(row(1), "xy   ");
+	let bgs = (2..7).map(|x| buf[(x, 0)].bg).collect::<Vec<_>>();
+	assert_eq!(bgs, vec![Color::Reset, Color::Reset, Color::Yellow, Color::Yellow, Color::Reset]);
+	assert_eq!(state.buffers.get(buffer_id).expect("buffer exists").text.to_string(), "a foo\nfoo");
+}
+
+#[test]
+fn setlocal_wrap_should_only_wrap_the_active_window() {
+	let mut state = RimState::new();
+	let buffer_id = state.create_buffer(Some(PathBuf::from("wrap.rs")), "abcdefghijklmnopqrstuvwxyz");
+	state.bind_buffer_to_active_window(buffer_id);
+	state.split_active_window(SplitAxis::Vertical);
+	state.update_active_tab_layout(20, 9);
+	assert_eq!(state.apply_set_local_option("wrap"), Ok("wrap".to_string()));
+
+	let area = Rect { x: 0, y: 0, width: 20, height: 9 };
+	let (widget, _) = WindowAreaWidget::from_state(&state, area);
+	let views = state
+		.active_tab_window_ids()
+		.into_iter()
+		.zip(widget.windows.iter())
+		.map(|(window_id, view)| (window_id == state.active_window_id(), view))
+		.collect::<Vec<_>>();
+	let (_, wrapped) = views.iter().find(|(active, _)| *active).expect("active window should render");
+	let (_, sibling) = views.iter().find(|(active, _)| !*active).expect("sibling window should render");
+	assert!(wrapped.word_wrap);
+	assert!(!sibling.word_wrap);
+	assert_eq!(wrapped.text_text.lines().collect::<Vec<_>>(), vec!["abcdefghijklmnopqr", "stuvwxyz"]);
+	assert_eq!(sibling.text_text.lines().filter(|row| !row.is_empty()).collect::<Vec<_>>(), vec![
+		"abcdefghijklmnopqr"
+	]);
+}
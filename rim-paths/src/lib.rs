@@ -75,6 +75,15 @@ pub fn user_log_dir() -> PathBuf {
 	}
 }
 
+// Only an absolute home counts, so a stray relative `HOME` never abbreviates.
+pub fn user_home_dir() -> Option<PathBuf> {
+	#[cfg(target_os = "windows")]
+	let home = std::env::var_os("USERPROFILE");
+	#[cfg(not(target_os = "windows"))]
+	let home = std::env::var_os("HOME");
+	home.map(PathBuf::from).filter(|path| path.is_absolute())
+}
+
 #[cfg(any(test, target_os = "windows"))]
 fn windows_state_root_from_env(
 	local_app_data: Option<PathBuf>,
@@ -20,6 +20,13 @@ pub enum FileWatcherError {
 	RequestChannelDisconnected { operation: &'static str },
 }
 
+/// Error contract for the language server port.
+#[derive(Debug, Error)]
+pub enum LanguageClientError {
+	#[error("language server request channel disconnected while enqueueing {operation}")]
+	RequestChannelDisconnected { operation: &'static str },
+}
+
 /// Error contract for host-provided file picker integrations.
 #[derive(Debug, Error)]
 pub enum FilePickerError {
@@ -29,6 +36,16 @@ pub enum FilePickerError {
 	Failed { message: String },
 }
 
+/// How the storage side should put buffer text on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FileSaveOptions {
+	/// Write a sibling temp file and rename it over the target, so a crash never
+	/// leaves a half-written file behind.
+	pub atomic: bool,
+	/// Keep the previous on-disk content as `<file>~` before overwriting it.
+	pub backup: bool,
+}
+
 /// Outbound port for subscribing/unsubscribing file change notifications.
 pub trait FileWatcher {
 	type BufferId: Copy;
@@ -38,6 +55,58 @@ pub trait FileWatcher {
 	fn enqueue_watch_workspace_root(&self, _path: PathBuf) -> Result<(), FileWatcherError> { Ok(()) }
 }
 
+/// A buffer handed to a language server when it starts tracking the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageDocument {
+	pub path:           PathBuf,
+	pub language_id:    String,
+	/// Program and arguments of the server; one server runs per distinct command.
+	pub server_command: Vec<String>,
+	pub root:           PathBuf,
+	pub text:           String,
+}
+
+/// Zero-based row and char column inside a tracked buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LanguagePosition {
+	pub row: usize,
+	pub col: usize,
+}
+
+/// Outbound port that keeps language servers in sync with open buffers.
+/// Diagnostics and request results come back as application actions. Every
+/// method defaults to a no-op so hosts built without language support can skip
+/// it.
+pub trait LanguageClient {
+	type BufferId: Copy;
+
+	fn enqueue_lsp_open(
+		&self,
+		_buffer_id: Self::BufferId,
+		_document: LanguageDocument,
+	) -> Result<(), LanguageClientError> {
+		Ok(())
+	}
+	fn enqueue_lsp_change(&self, _buffer_id: Self::BufferId, _text: String) -> Result<(), LanguageClientError> {
+		Ok(())
+	}
+	fn enqueue_lsp_close(&self, _buffer_id: Self::BufferId) -> Result<(), LanguageClientError> { Ok(()) }
+	fn enqueue_lsp_definition(
+		&self,
+		_buffer_id: Self::BufferId,
+		_position: LanguagePosition,
+	) -> Result<(), LanguageClientError> {
+		Ok(())
+	}
+	fn enqueue_lsp_hover(
+		&self,
+		_buffer_id: Self::BufferId,
+		_position: LanguagePosition,
+	) -> Result<(), LanguageClientError> {
+		Ok(())
+	}
+}
+
 /// Host capability for showing a native file picker and returning one selected
 /// path.
 pub trait FilePicker {
@@ -62,14 +131,31 @@ pub trait StorageIo {
 	) -> Result<(), StorageIoError> {
 		Ok(())
 	}
+	fn enqueue_save_session_file(
+		&self,
+		_path: PathBuf,
+		_snapshot: Self::WorkspaceSessionSnapshot,
+	) -> Result<(), StorageIoError> {
+		Ok(())
+	}
+	fn enqueue_load_session_file(&self, _path: PathBuf) -> Result<(), StorageIoError> { Ok(()) }
 	fn enqueue_load(&self, buffer_id: Self::BufferId, path: PathBuf) -> Result<(), StorageIoError>;
 	fn enqueue_list_workspace_files(&self, _workspace_root: PathBuf) -> Result<(), StorageIoError> { Ok(()) }
 	fn enqueue_load_workspace_file_preview(&self, _path: PathBuf) -> Result<(), StorageIoError> { Ok(()) }
+	fn enqueue_expand_glob(&self, _workspace_root: PathBuf, _pattern: String) -> Result<(), StorageIoError> {
+		Ok(())
+	}
+	fn enqueue_load_tags(&self, _tags_path: PathBuf, _tag: String) -> Result<(), StorageIoError> { Ok(()) }
+	/// Reads the modification time of each file, for `:checktime`.
+	fn enqueue_check_mtimes(&self, _files: Vec<(Self::BufferId, PathBuf)>) -> Result<(), StorageIoError> {
+		Ok(())
+	}
 	fn enqueue_save(
 		&self,
 		buffer_id: Self::BufferId,
 		path: PathBuf,
 		text: String,
+		options: FileSaveOptions,
 	) -> Result<(), StorageIoError>;
 	fn enqueue_external_load(&self, buffer_id: Self::BufferId, path: PathBuf) -> Result<(), StorageIoError>;
 	fn enqueue_open(&self, buffer_id: Self::BufferId, source_path: PathBuf) -> Result<(), StorageIoError>;